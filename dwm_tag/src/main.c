@@ -38,6 +38,69 @@ LOG_MODULE_REGISTER(main);
 #define CMD_M1_BRAKE   0x32
 #define CMD_M2_BRAKE   0x42
 
+/* Unsolicited frames the STM32 appends after its telemetry frame. */
+#define SPI_EXTRA_OFFSET  55
+#define MSG_EVENT         0x61
+#define MSG_EVENT_LEN     7
+#define MSG_HEARTBEAT     0x62
+#define MSG_HEARTBEAT_LEN 7
+#define MSG_ACK           0x63
+#define MSG_ACK_LEN       3
+#define MSG_CAPABILITIES  0x64
+#define MSG_CAPABILITIES_LEN 5
+#define MSG_AXIS_CAPS     0x65
+#define MSG_AXIS_CAPS_LEN 8
+#define MSG_TELEMETRY_DESCRIPTOR     0x67
+#define MSG_TELEMETRY_DESCRIPTOR_LEN 22
+#define MSG_STATS                    0x69
+#define MSG_STATS_LEN                21
+#define MSG_FAULT_SNAPSHOT           0x6A
+#define MSG_FAULT_SNAPSHOT_LEN       18
+#define MSG_FAULT_HISTORY            0x6B
+#define MSG_FAULT_HISTORY_LEN        18
+#define MSG_LINK_STATS               0x6C
+#define MSG_LINK_STATS_LEN           20
+#define MSG_PARAM                    0x6D
+#define MSG_PARAM_LEN                6
+#define MSG_CONFIG_STATUS            0x6E
+#define MSG_CONFIG_STATUS_LEN        2
+#define MSG_REGISTER                 0x6F
+#define MSG_REGISTER_LEN             12
+#define MSG_ECHO                     0x5D
+#define MSG_ECHO_LEN                 8
+/* Sent alone in a transfer while a long operation holds the STM32 main loop. */
+#define MSG_PROGRESS                 0xA0
+#define MSG_PROGRESS_LEN             5
+#define MSG_TOKEN_ACK                0xA1
+#define MSG_TOKEN_ACK_LEN            4
+#define MSG_TOKEN_EVENT              0xA2
+#define MSG_TOKEN_EVENT_LEN          9
+#define MSG_WIRE_DESCRIPTOR          0xA3
+#define MSG_WIRE_DESCRIPTOR_LEN      42
+#define MSG_IDENT_SAMPLE             0xA4
+#define MSG_IDENT_SAMPLE_LEN         9
+
+/* Telemetry field selection. Mirrors omnitiles/proto/src/telemetry.rs. */
+#define MSG_SEQ                  0x90
+#define MSG_TOKEN                0x91
+#define MSG_SET_TELEMETRY_FIELDS 0x52
+#define MSG_TELEMETRY_SELECTED   0x66
+#define TELEM_FIELD_UWB          (1u << 0)
+#define TELEM_FIELD_TOF          (1u << 1)
+#define TELEM_FIELD_IMU          (1u << 2)
+#define TELEM_FIELD_MOTOR_ADCS   (1u << 3)
+#define TELEM_FIELD_TIMESTAMP    (1u << 4)
+#define TELEM_FIELD_MOTOR_TEMPS  (1u << 5)
+#define TELEM_FIELD_STACK        (1u << 6)
+#define TELEM_FIELD_ALL          0x7F
+#define TELEM_SELECTED_MAX_LEN   64
+
+/* Delta telemetry. Mirrors omnitiles/proto/src/telemetry.rs. */
+#define MSG_SET_TELEMETRY_DELTA 0x54
+#define MSG_TELEMETRY_DELTA     0x68
+#define TELEM_FULL_LEN          63
+#define TELEM_SLOT_COUNT        23
+
 // ---------------------------------------------------------------------------
 // Per-tile calibration. Set with: west build -- -DCONFIG_TILE_ID=<n>
 // Each tile gets its own BLE name and antenna delay, calibrated against the
@@ -107,6 +170,27 @@ static uint8_t last_imu_bytes[24];
 /* Raw motor ADC telemetry bytes from STM32, forwarded as-is.
  * Layout: 6 × u16 little-endian = m1_adc1..4, m2_adc1..2. */
 static uint8_t last_motor_adc_bytes[12];
+/* STM32 sample time of the cached telemetry, µs since its boot (u32 little-endian). */
+static uint8_t last_sample_us_bytes[4];
+/* Motor temperatures from STM32, forwarded as-is.
+ * Layout: 2 × i16 little-endian = m1, m2 in 0.1 °C, 0x7FFF = no sensor. */
+static uint8_t last_motor_temp_bytes[4] = {0xFF, 0x7F, 0xFF, 0x7F};
+/* STM32 peak stack use in bytes since its boot (u16 little-endian), forwarded as-is. */
+static uint8_t last_stack_bytes[2];
+/* Optional telemetry fields selected by the host (TELEM_FIELD_*). */
+static volatile uint8_t telemetry_fields = TELEM_FIELD_ALL;
+/* Keyframe interval for delta telemetry; 0 or 1 sends every frame in full. */
+static volatile uint8_t telemetry_delta_interval = 0;
+/* Last full telemetry frame delivered to the host, the base for the next delta. */
+static uint8_t last_full_telem[TELEM_FULL_LEN];
+static volatile bool delta_base_valid = false;
+static uint8_t frames_since_keyframe;
+
+/* Slot widths of the full telemetry body in wire order: m1, m2, d0-d3, tof (u16),
+ * IMU (6 x f32), motor ADCs (6 x u16), sample time (u32), motor temps (2 x i16),
+ * stack peak (u16). */
+static const uint8_t telem_slot_size[TELEM_SLOT_COUNT] = {
+    2, 2, 2, 2, 2, 2, 2, 4, 4, 4, 4, 4, 4, 2, 2, 2, 2, 2, 2, 4, 2, 2, 2};
 
 static uint16_t median_u16(uint16_t* buf, int n) {
   for (int i = 1; i < n; i++) {
@@ -265,6 +349,228 @@ static void spi_bridge_thread_fn(void* p1, void* p2, void* p3) {
   }
 }
 
+// Forward event/heartbeat/ACK/capability frames from `start` in an SPI response (just past
+// the telemetry frame, or 0 in a transfer without one) to the BLE host unchanged. Stops at the
+// first unknown byte.
+static void forward_extra_frames(const uint8_t* buf, size_t start) {
+  if (current_conn == NULL) {
+    return;
+  }
+
+  size_t i = start;
+  while (i + 1 < SPI_BUF_SIZE && buf[i] == CMD_START_BYTE) {
+    size_t payload_len;
+    switch (buf[i + 1]) {
+      case MSG_EVENT:
+        payload_len = MSG_EVENT_LEN;
+        break;
+      case MSG_HEARTBEAT:
+        payload_len = MSG_HEARTBEAT_LEN;
+        break;
+      case MSG_ACK:
+        payload_len = MSG_ACK_LEN;
+        break;
+      case MSG_CAPABILITIES:
+        payload_len = MSG_CAPABILITIES_LEN;
+        break;
+      case MSG_AXIS_CAPS:
+        payload_len = MSG_AXIS_CAPS_LEN;
+        break;
+      case MSG_TELEMETRY_DESCRIPTOR:
+        payload_len = MSG_TELEMETRY_DESCRIPTOR_LEN;
+        break;
+      case MSG_STATS:
+        payload_len = MSG_STATS_LEN;
+        break;
+      case MSG_FAULT_SNAPSHOT:
+        payload_len = MSG_FAULT_SNAPSHOT_LEN;
+        break;
+      case MSG_FAULT_HISTORY:
+        payload_len = MSG_FAULT_HISTORY_LEN;
+        break;
+      case MSG_LINK_STATS:
+        payload_len = MSG_LINK_STATS_LEN;
+        break;
+      case MSG_PARAM:
+        payload_len = MSG_PARAM_LEN;
+        break;
+      case MSG_CONFIG_STATUS:
+        payload_len = MSG_CONFIG_STATUS_LEN;
+        break;
+      case MSG_REGISTER:
+        payload_len = MSG_REGISTER_LEN;
+        break;
+      case MSG_ECHO:
+        payload_len = MSG_ECHO_LEN;
+        break;
+      case MSG_PROGRESS:
+        payload_len = MSG_PROGRESS_LEN;
+        break;
+      case MSG_TOKEN_ACK:
+        payload_len = MSG_TOKEN_ACK_LEN;
+        break;
+      case MSG_TOKEN_EVENT:
+        payload_len = MSG_TOKEN_EVENT_LEN;
+        break;
+      case MSG_WIRE_DESCRIPTOR:
+        payload_len = MSG_WIRE_DESCRIPTOR_LEN;
+        break;
+      case MSG_IDENT_SAMPLE:
+        payload_len = MSG_IDENT_SAMPLE_LEN;
+        break;
+      default:
+        return;
+    }
+
+    size_t frame_len = payload_len + 3;
+    if (i + frame_len > SPI_BUF_SIZE) {
+      return;
+    }
+    int err = bt_nus_send(current_conn, &buf[i], frame_len);
+    if (err) {
+      LOG_WRN("bt_nus_send (extra frame 0x%02x) failed: %d", buf[i + 1], err);
+      return;
+    }
+    i += frame_len;
+  }
+}
+
+// Apply a bridge-side telemetry setting (SET_TELEMETRY_FIELDS or SET_TELEMETRY_DELTA,
+// bare or in a SEQ or TOKEN envelope) at the start of a BLE write. The command is still forwarded
+// to the STM32 so sequenced writes get ACKed.
+static void snoop_telemetry_settings(const uint8_t* data, uint16_t len) {
+  size_t id_idx = 1;
+  if (len >= 3 && data[0] == CMD_START_BYTE && data[1] == MSG_SEQ) {
+    id_idx = 3;
+  } else if (len >= 3 && data[0] == CMD_START_BYTE && data[1] == MSG_TOKEN) {
+    id_idx = 4;
+  }
+  if (len < id_idx + 3 || data[0] != CMD_START_BYTE) {
+    return;
+  }
+  uint8_t id = data[id_idx];
+  if (id != MSG_SET_TELEMETRY_FIELDS && id != MSG_SET_TELEMETRY_DELTA) {
+    return;
+  }
+
+  uint8_t csum = 0;
+  for (size_t i = 1; i <= id_idx + 1; i++) {
+    csum += data[i];
+  }
+  if (csum != data[id_idx + 2]) {
+    return;
+  }
+  if (id == MSG_SET_TELEMETRY_FIELDS) {
+    telemetry_fields = data[id_idx + 1] & TELEM_FIELD_ALL;
+    LOG_INF("Telemetry fields set to 0x%02x", telemetry_fields);
+  } else {
+    telemetry_delta_interval = data[id_idx + 1];
+    delta_base_valid = false;
+    LOG_INF("Telemetry keyframe interval set to %d", telemetry_delta_interval);
+  }
+}
+
+// Build a TELEMETRY_DELTA frame for the full frame `cur` against `base`. Changed u16
+// slots carry an i8 difference and changed 4-byte slots their raw bytes; unchanged slots
+// are omitted. Returns 0 if a u16 slot moved too far for an i8, in which case a keyframe
+// must be sent.
+static size_t build_delta_telemetry(uint8_t* out,
+    const uint8_t* cur,
+    const uint8_t* base) {
+  uint32_t changed = 0;
+  size_t n = 5;
+  size_t off = 2;
+
+  for (int s = 0; s < TELEM_SLOT_COUNT; s++) {
+    uint8_t size = telem_slot_size[s];
+    if (memcmp(&cur[off], &base[off], size) != 0) {
+      changed |= 1u << s;
+      if (size == 2) {
+        int32_t now = (int32_t)((uint16_t)cur[off] | ((uint16_t)cur[off + 1] << 8));
+        int32_t was = (int32_t)((uint16_t)base[off] | ((uint16_t)base[off + 1] << 8));
+        int32_t diff = now - was;
+        if (diff < INT8_MIN || diff > INT8_MAX) {
+          return 0;
+        }
+        out[n++] = (uint8_t)(int8_t)diff;
+      } else {
+        memcpy(&out[n], &cur[off], size);
+        n += size;
+      }
+    }
+    off += size;
+  }
+
+  out[0] = CMD_START_BYTE;
+  out[1] = MSG_TELEMETRY_DELTA;
+  out[2] = (uint8_t)(changed);
+  out[3] = (uint8_t)(changed >> 8);
+  out[4] = (uint8_t)(changed >> 16);
+
+  uint8_t csum = 0;
+  for (size_t i = 1; i < n; i++) {
+    csum += out[i];
+  }
+  out[n++] = csum;
+  return n;
+}
+
+// Build a TELEMETRY_SELECTED frame carrying the positions plus the fields in `fields`.
+// `out` must hold at least TELEM_SELECTED_MAX_LEN bytes. Returns the frame length.
+static size_t build_selected_telemetry(uint8_t* out,
+    uint8_t fields,
+    uint16_t m1_adc,
+    uint16_t m2_adc,
+    uint16_t tof_mm) {
+  size_t n = 0;
+  out[n++] = CMD_START_BYTE;
+  out[n++] = MSG_TELEMETRY_SELECTED;
+  out[n++] = fields;
+  out[n++] = (uint8_t)(m1_adc);
+  out[n++] = (uint8_t)(m1_adc >> 8);
+  out[n++] = (uint8_t)(m2_adc);
+  out[n++] = (uint8_t)(m2_adc >> 8);
+
+  if (fields & TELEM_FIELD_UWB) {
+    for (int a = 0; a < NUM_ANCHORS; a++) {
+      uint16_t d = uwb_dist_mm[a];
+      out[n++] = (uint8_t)(d);
+      out[n++] = (uint8_t)(d >> 8);
+    }
+  }
+  if (fields & TELEM_FIELD_TOF) {
+    out[n++] = (uint8_t)(tof_mm);
+    out[n++] = (uint8_t)(tof_mm >> 8);
+  }
+  if (fields & TELEM_FIELD_IMU) {
+    memcpy(&out[n], last_imu_bytes, sizeof(last_imu_bytes));
+    n += sizeof(last_imu_bytes);
+  }
+  if (fields & TELEM_FIELD_MOTOR_ADCS) {
+    memcpy(&out[n], last_motor_adc_bytes, sizeof(last_motor_adc_bytes));
+    n += sizeof(last_motor_adc_bytes);
+  }
+  if (fields & TELEM_FIELD_TIMESTAMP) {
+    memcpy(&out[n], last_sample_us_bytes, sizeof(last_sample_us_bytes));
+    n += sizeof(last_sample_us_bytes);
+  }
+  if (fields & TELEM_FIELD_MOTOR_TEMPS) {
+    memcpy(&out[n], last_motor_temp_bytes, sizeof(last_motor_temp_bytes));
+    n += sizeof(last_motor_temp_bytes);
+  }
+  if (fields & TELEM_FIELD_STACK) {
+    memcpy(&out[n], last_stack_bytes, sizeof(last_stack_bytes));
+    n += sizeof(last_stack_bytes);
+  }
+
+  uint8_t csum = 0;
+  for (size_t i = 1; i < n; i++) {
+    csum += out[i];
+  }
+  out[n++] = csum;
+  return n;
+}
+
 K_THREAD_STACK_DEFINE(spi_bridge_stack, 1024);
 static struct k_thread spi_bridge_thread;
 
@@ -272,6 +578,7 @@ static struct k_thread spi_bridge_thread;
 
 static void bt_receive_cb(struct bt_conn* conn, const uint8_t* const data, uint16_t len) {
   last_ble_rx_ms = k_uptime_get_32();
+  snoop_telemetry_settings(data, len);
   uint8_t temp_buf[SPI_BUF_SIZE];
 
   uint16_t copy_len = (len > SPI_BUF_SIZE) ? SPI_BUF_SIZE : len;
@@ -302,6 +609,7 @@ static void connected(struct bt_conn* conn, uint8_t err) {
   /* Track the current connection for NUS TX */
   last_ble_rx_ms = k_uptime_get_32();
   ble_watchdog_braked = false;
+  delta_base_valid = false;
   current_conn = bt_conn_ref(conn);
 }
 
@@ -596,6 +904,10 @@ int main(void) {
           last_tof_mm = (uint16_t)rx_buffer[6] | ((uint16_t)rx_buffer[7] << 8);
           memcpy(last_imu_bytes, &rx_buffer[8], 24);
           memcpy(last_motor_adc_bytes, &rx_buffer[32], 12);
+          memcpy(last_sample_us_bytes, &rx_buffer[44], 4);
+          memcpy(last_motor_temp_bytes, &rx_buffer[48], 4);
+          memcpy(last_stack_bytes, &rx_buffer[52], 2);
+          forward_extra_frames(rx_buffer, SPI_EXTRA_OFFSET);
 
           if (spi_was_timing_out) {
             LOG_INF("SPI: STM32 reconnected, clearing stale brake flags");
@@ -605,6 +917,9 @@ int main(void) {
             ble_watchdog_braked = false;
             last_ble_rx_ms = k_uptime_get_32();
           }
+        } else if (spi_result >= 0 && rx_buffer[0] == 0xA5) {
+          // Progress of a long operation, no telemetry.
+          forward_extra_frames(rx_buffer, 0);
         } else if (spi_result < 0) {
           LOG_WRN("spi_transceive failed: %d", spi_result);
         }
@@ -671,6 +986,10 @@ int main(void) {
           last_tof_mm = (uint16_t)rx_buffer[6] | ((uint16_t)rx_buffer[7] << 8);
           memcpy(last_imu_bytes, &rx_buffer[8], 24);
           memcpy(last_motor_adc_bytes, &rx_buffer[32], 12);
+          memcpy(last_sample_us_bytes, &rx_buffer[44], 4);
+          memcpy(last_motor_temp_bytes, &rx_buffer[48], 4);
+          memcpy(last_stack_bytes, &rx_buffer[52], 2);
+          forward_extra_frames(rx_buffer, SPI_EXTRA_OFFSET);
 
           if (spi_was_timing_out) {
             LOG_INF("SPI: STM32 reconnected, clearing stale brake flags");
@@ -680,6 +999,9 @@ int main(void) {
             ble_watchdog_braked = false;
             last_ble_rx_ms = k_uptime_get_32();
           }
+        } else if (spi_result >= 0 && rx_buffer[0] == 0xA5) {
+          // Progress of a long operation, no telemetry.
+          forward_extra_frames(rx_buffer, 0);
         } else if (spi_result < 0) {
           LOG_WRN("spi_transceive failed: %d", spi_result);
         }
@@ -704,8 +1026,25 @@ int main(void) {
       bool in_backoff = (now < nus_send_backoff_until_ms);
       bool rate_ok = (now - last_nus_send_ms >= NUS_SEND_INTERVAL_MS);
 
-      if (!in_backoff && rate_ok) {
-        uint8_t telem[53];
+      uint8_t fields = telemetry_fields;
+      if (!in_backoff && rate_ok && fields != TELEM_FIELD_ALL) {
+        uint8_t telem[TELEM_SELECTED_MAX_LEN];
+        size_t telem_len =
+            build_selected_telemetry(telem, fields, m1_adc, m2_adc, tof_mm);
+        delta_base_valid = false;
+
+        int err = bt_nus_send(current_conn, telem, telem_len);
+        if (err == 0) {
+          last_nus_send_ms = now;
+          nus_send_backoff_until_ms = 0;
+        } else {
+          nus_send_backoff_until_ms = now + NUS_SEND_BACKOFF_MS;
+          LOG_WRN("bt_nus_send failed: %d (backing off %d ms)",
+              err,
+              (int)NUS_SEND_BACKOFF_MS);
+        }
+      } else if (!in_backoff && rate_ok) {
+        uint8_t telem[TELEM_FULL_LEN];
         telem[0] = 0xA5;
         telem[1] = 0x60;
         telem[2] = (uint8_t)(m1_adc);
@@ -731,18 +1070,39 @@ int main(void) {
 
         memcpy(&telem[16], last_imu_bytes, 24);
         memcpy(&telem[40], last_motor_adc_bytes, 12);
+        memcpy(&telem[52], last_sample_us_bytes, 4);
+        memcpy(&telem[56], last_motor_temp_bytes, 4);
+        memcpy(&telem[60], last_stack_bytes, 2);
 
         uint8_t csum = 0;
-        for (int i = 1; i < 52; i++) {
+        for (int i = 1; i < 62; i++) {
           csum += telem[i];
         }
-        telem[52] = csum;
+        telem[62] = csum;
+
+        // Between keyframes, send only what changed since the last delivered frame.
+        uint8_t delta[TELEM_FULL_LEN];
+        const uint8_t* tx = telem;
+        size_t tx_len = sizeof(telem);
+        uint8_t interval = telemetry_delta_interval;
+        if (delta_base_valid && frames_since_keyframe + 1 < interval) {
+          size_t n = build_delta_telemetry(delta, telem, last_full_telem);
+          if (n > 0) {
+            tx = delta;
+            tx_len = n;
+          }
+        }
 
-        int err = bt_nus_send(current_conn, telem, sizeof(telem));
+        int err = bt_nus_send(current_conn, tx, tx_len);
         if (err == 0) {
           last_nus_send_ms = now;
           nus_send_backoff_until_ms = 0;
+          memcpy(last_full_telem, telem, sizeof(telem));
+          delta_base_valid = true;
+          frames_since_keyframe = (tx == telem) ? 0 : frames_since_keyframe + 1;
         } else {
+          // The host may not have the base any more; resync with a keyframe.
+          delta_base_valid = false;
           nus_send_backoff_until_ms = now + NUS_SEND_BACKOFF_MS;
           LOG_WRN("bt_nus_send failed: %d (backing off %d ms)",
               err,
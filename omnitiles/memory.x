@@ -1,8 +1,11 @@
 MEMORY
 {
   /* NOTE K = KiBi = 1024 bytes */
-  FLASH (rx) : ORIGIN = 0x08000000, LENGTH = 2048K
-  RAM (rwx) : ORIGIN = 0x20020000, LENGTH = 384K
+  /* Last three 256K sectors are reserved: 9 and 11 for the tile config banks, 10 for motor stats. See hw/flash.rs */
+  FLASH (rx) : ORIGIN = 0x08000000, LENGTH = 1280K
+  /* SRAM1 only. SRAM2 is the non-cacheable DMA region, see hw/memory.rs */
+  RAM (rwx) : ORIGIN = 0x20020000, LENGTH = 368K
+  SRAM2 (rwx) : ORIGIN = 0x2007C000, LENGTH = 16K
   ITCM (rwx) : ORIGIN = 0x00000000, LENGTH = 16K /* Instruction Tighly Coupled Memory */
   DTCM (rwx) : ORIGIN = 0x20000000, LENGTH = 128K /* Data Tighly Coupled Memory */
 }
@@ -22,6 +25,12 @@ SECTIONS
       . = ALIGN(4);
   } > DTCM
 
+  .dma (NOLOAD) : ALIGN(32)
+  {
+      *(.dma .dma.*);
+      . = ALIGN(32);
+  } > SRAM2
+
 }
 
 /* You can then use something like this to place a variable into a specific section of memory:
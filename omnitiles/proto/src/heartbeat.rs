@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Periodic heartbeat payload.
+//!
+//! Sent every [`HEARTBEAT_INTERVAL_MS`] as an unsolicited `MSG_HEARTBEAT` frame so the host can
+//! tell a dead or rebooted tile apart from a quiet one. Payload (little-endian):
+//!
+//! | Offset | Field |
+//! | ------ | ----- |
+//! | 0 | `node_id: u8` |
+//! | 1 | `uptime_ms: u32` |
+//! | 5 | `mode: u8` (see [`mode`]) |
+//! | 6 | `faults: u8` (see [`fault`]) |
+
+/// Heartbeat period. Four beats per second lets the host declare a tile dead within a second.
+pub const HEARTBEAT_INTERVAL_MS: u64 = 250;
+
+/// Serialized payload length.
+pub const HEARTBEAT_LEN: usize = 7;
+
+/// Mode bits.
+pub mod mode {
+    /// M1 is under closed-loop position control.
+    pub const M1_POSITION: u8 = 1 << 0;
+    /// M2 is under closed-loop position control.
+    pub const M2_POSITION: u8 = 1 << 1;
+    /// The SPI watchdog has braked the motors.
+    pub const WATCHDOG_BRAKED: u8 = 1 << 2;
+    /// The tile has been provisioned.
+    pub const PROVISIONED: u8 = 1 << 3;
+    /// The tile booted into safe mode with a corrupt configuration.
+    pub const SAFE_MODE: u8 = 1 << 4;
+}
+
+/// Fault summary bits.
+pub mod fault {
+    /// M1 lost position feedback.
+    pub const M1_FEEDBACK: u8 = 1 << 0;
+    /// M2 lost position feedback.
+    pub const M2_FEEDBACK: u8 = 1 << 1;
+    /// An actuator is braked at a soft limit.
+    pub const LIMIT_BRAKING: u8 = 1 << 2;
+    /// The IMU did not initialize.
+    pub const IMU_MISSING: u8 = 1 << 3;
+    /// The ToF sensor did not initialize.
+    pub const TOF_MISSING: u8 = 1 << 4;
+    /// Checked transfers on the shared SPI bus keep failing (see `hw::spi::BusHealth`).
+    pub const SPI_BUS: u8 = 1 << 5;
+    /// The lift feedback disagrees with the ToF height (see `sensors::HeightCrossCheck`).
+    pub const HEIGHT_MISMATCH: u8 = 1 << 6;
+    /// Another node on the CAN backbone uses this tile's IDs (see `net::id_guard`).
+    pub const NODE_ID_CONFLICT: u8 = 1 << 7;
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Heartbeat {
+    pub node_id: u8,
+    pub uptime_ms: u32,
+    pub mode: u8,
+    pub faults: u8,
+}
+
+impl Heartbeat {
+    pub fn to_bytes(&self) -> [u8; HEARTBEAT_LEN] {
+        let up = self.uptime_ms.to_le_bytes();
+        [
+            self.node_id,
+            up[0],
+            up[1],
+            up[2],
+            up[3],
+            self.mode,
+            self.faults,
+        ]
+    }
+}
@@ -0,0 +1,38 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! # OmniTiles protocol
+//!
+//! Wire definitions shared by the firmware and host-side Rust tools: message IDs, the frame
+//! parser, sequence and token acknowledgements, and the layouts of the frames the tile sends
+//! back (heartbeat, progress, telemetry). The firmware compiles exactly these definitions, so a
+//! host tool built against the same revision can't drift from it.
+//!
+//! The crate is `no_std` by default. The `std` feature adds conveniences for host tools, such as
+//! [`messages::frame`].
+//!
+//! | Module | Purpose |
+//! | ------ | -------- |
+//! | [`messages`]  | Message IDs, [`Command`] and frame encoding |
+//! | [`parser`]    | Byte-wise frame parser |
+//! | [`seq`]       | Sequence numbers, tokens, ACK status and reject codes |
+//! | [`heartbeat`] | `MSG_HEARTBEAT` payload |
+//! | [`progress`]  | `MSG_PROGRESS` payload and rate-limited reporting |
+//! | [`telemetry`] | Telemetry field selection and descriptor |
+//! | [`wire`]      | Machine-readable descriptor of messages and parameters |
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod heartbeat;
+pub mod messages;
+pub mod parser;
+pub mod progress;
+pub mod seq;
+pub mod telemetry;
+pub mod wire;
+
+pub use heartbeat::Heartbeat;
+pub use messages::Command;
+pub use parser::{Packet, Parser, ParserStats};
+pub use progress::{Progress, Reporter};
+pub use seq::{AckStatus, Reject, SeqTracker};
@@ -0,0 +1,376 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Command message protocol used to communicate with OmniTiles.
+
+/// Sync byte for the protocol.
+pub const START_BYTE: u8 = 0xA5;
+
+// Message IDs
+pub const MSG_M1_EXTEND: u8 = 0x30;
+pub const MSG_M1_RETRACT: u8 = 0x31;
+pub const MSG_M1_BRAKE: u8 = 0x32;
+pub const MSG_M1_SET_POSITION: u8 = 0x33;
+/// Relative M1 move. Payload: `i16` LE delta in 0.01 mm.
+pub const MSG_M1_MOVE_RELATIVE: u8 = 0x34;
+
+pub const MSG_M2_EXTEND: u8 = 0x40;
+pub const MSG_M2_RETRACT: u8 = 0x41;
+pub const MSG_M2_BRAKE: u8 = 0x42;
+pub const MSG_M2_SET_POSITION: u8 = 0x43;
+/// Relative M2 move. Payload: `i16` LE delta in 0.01 mm.
+pub const MSG_M2_MOVE_RELATIVE: u8 = 0x44;
+
+pub const MSG_PING: u8 = 0x50;
+/// Request the capabilities handshake. See `protocol::caps`.
+pub const MSG_GET_CAPABILITIES: u8 = 0x51;
+/// Select the telemetry fields streamed over BLE. Payload: `u8` mask, see `protocol::telemetry`.
+pub const MSG_SET_TELEMETRY_FIELDS: u8 = 0x52;
+/// Request the telemetry field descriptor.
+pub const MSG_GET_TELEMETRY_DESCRIPTOR: u8 = 0x53;
+/// Enable delta telemetry over BLE. Payload: `u8` keyframe interval, 0 = off.
+pub const MSG_SET_TELEMETRY_DELTA: u8 = 0x54;
+/// Request usage counters for one axis. Payload: `u8` axis.
+pub const MSG_GET_STATS: u8 = 0x55;
+/// Request the fault snapshot of one axis. Payload: `u8` axis.
+pub const MSG_GET_FAULT_SNAPSHOT: u8 = 0x56;
+/// Set the wall clock. Payload: `u32` LE Unix seconds.
+pub const MSG_SET_TIME: u8 = 0x57;
+/// Configure one unattended schedule slot. Payload: `[slot, kind, sequence, arg: u16]`, see
+/// `control::schedule`.
+pub const MSG_SET_SCHEDULE: u8 = 0x58;
+/// Request the command link's frame counters.
+pub const MSG_GET_LINK_STATS: u8 = 0x59;
+/// Read a tunable parameter. Payload: `u8` parameter ID, see `config::params`.
+pub const MSG_GET_PARAM: u8 = 0x5A;
+/// Route an internal signal to a DAC pin. Payload: `[channel, signal]`, see `hw::dac`.
+pub const MSG_SET_DAC_PROBE: u8 = 0x5B;
+/// Read one motor driver register raw. Payload: `[device, addr]`, see `protocol::register`.
+pub const MSG_READ_REGISTER: u8 = 0x5C;
+/// Latency probe. Payload: `[token: u32]`. Answered with the same ID and
+/// `[token: u32, held_us: u32]`, the time from receiving the request to sending the reply.
+pub const MSG_ECHO: u8 = 0x5D;
+/// Request one record of the wire format descriptor. Payload: `u8` index, see `wire`.
+pub const MSG_GET_WIRE_DESCRIPTOR: u8 = 0x5E;
+
+pub const MSG_TELEMETRY: u8 = 0x60;
+/// Unsolicited controller event. Payload: `[kind, axis, code, timestamp_us: u32]`.
+pub const MSG_EVENT: u8 = 0x61;
+/// Unsolicited periodic heartbeat. Payload: see `protocol::heartbeat`.
+pub const MSG_HEARTBEAT: u8 = 0x62;
+/// Acknowledgement of a sequenced command. Payload: `[seq, status, reason]`.
+pub const MSG_ACK: u8 = 0x63;
+/// Capabilities summary. Payload: `[protocol_version, node_id, axis_count, reset_reason]`.
+pub const MSG_CAPABILITIES: u8 = 0x64;
+/// Per-axis range and units. Payload: see `protocol::caps`.
+pub const MSG_AXIS_CAPS: u8 = 0x65;
+/// Telemetry with a host-selected field set. Payload: see `protocol::telemetry`.
+pub const MSG_TELEMETRY_SELECTED: u8 = 0x66;
+/// Telemetry field map. Payload: `[count, (bit, kind, count)...]`.
+pub const MSG_TELEMETRY_DESCRIPTOR: u8 = 0x67;
+/// Changes since the previous full telemetry frame. Payload: see `protocol::telemetry`.
+pub const MSG_TELEMETRY_DELTA: u8 = 0x68;
+/// Usage counters. Payload: `[axis, travel_centi: u64, reversals: u32, load_s: u32, ocp: u32]`.
+pub const MSG_STATS: u8 = 0x69;
+/// Axis state at the last fault. Payload: see `control::fault_snapshot`.
+pub const MSG_FAULT_SNAPSHOT: u8 = 0x6A;
+/// Command history at the last fault. Payload: `[axis, index, 16 command IDs]`.
+pub const MSG_FAULT_HISTORY: u8 = 0x6B;
+/// Command link frame counters. Payload: see `protocol::parser::ParserStats::to_bytes`.
+pub const MSG_LINK_STATS: u8 = 0x6C;
+/// Parameter value. Payload: `[id, status, value: f32 LE]`, status 0 = ok, else a
+/// `config::params::ParamError`.
+pub const MSG_PARAM: u8 = 0x6D;
+/// Outcome of a config commit or revert. Payload: `[status, pending]`, status 0 = ok, 1 = no
+/// change pending, 2 = flash error; `pending` is 1 while a stored change can still be reverted.
+pub const MSG_CONFIG_STATUS: u8 = 0x6E;
+/// Raw register value. Payload: see `protocol::register`.
+pub const MSG_REGISTER: u8 = 0x6F;
+
+pub const MSG_BASE_VELOCITY: u8 = 0x70;
+pub const MSG_BASE_BRAKE: u8 = 0x71;
+
+/// Relative tilt move. Payload: `i16` LE delta in 0.01°.
+pub const MSG_TILT_MOVE_RELATIVE: u8 = 0x72;
+/// Start or stop an open-loop identification run. Payload:
+/// `[axis, signal, amplitude, param, duration_s]`, see [`Command::Identify`].
+pub const MSG_IDENTIFY: u8 = 0x73;
+/// Start or stop a closed-loop frequency response sweep. Payload: `[axis, amplitude]`, see
+/// [`Command::FrequencyResponse`].
+pub const MSG_FREQUENCY_RESPONSE: u8 = 0x74;
+
+pub const MSG_PROVISION: u8 = 0x80;
+pub const MSG_SET_POLARITY: u8 = 0x81;
+pub const MSG_SET_MOTION_LIMITS: u8 = 0x82;
+/// Set and persist an axis's DRV8873 current limit. Payload: `[axis, level]`, level 0–3 selects
+/// `ITRIP_LVL` (4.0, 5.4, 6.5, 7.0 A), 0xFF turns regulation off.
+pub const MSG_SET_CURRENT_LIMIT: u8 = 0x83;
+/// Write a tunable parameter. Payload: `[id, value: f32 LE]`, see `config::params`.
+pub const MSG_SET_PARAM: u8 = 0x84;
+/// Keep the pending configuration change and drop its rollback point. See `config::bank`.
+pub const MSG_COMMIT_CONFIG: u8 = 0x85;
+/// Discard the pending configuration change and return to the last committed one.
+pub const MSG_REVERT_CONFIG: u8 = 0x86;
+/// Set and persist one interlock slot. Payload: `[slot, axis, source, min: i16 LE, max: i16 LE]`,
+/// see `control::interlock`.
+pub const MSG_SET_INTERLOCK: u8 = 0x87;
+/// Start or stop demo mode. Payload: `[mode]`, see [`Command::SetDemo`].
+pub const MSG_SET_DEMO: u8 = 0x88;
+/// Move a GIM6010 on the motor bus to the tilt axis address and bitrate. Payload:
+/// `[addr, bitrate]`, its current settings; see [`Command::ProvisionTiltMotor`].
+pub const MSG_PROVISION_TILT_MOTOR: u8 = 0x89;
+
+/// Sequenced envelope: `[seq, msg_id, payload...]`. See `protocol::seq`.
+pub const MSG_SEQ: u8 = 0x90;
+/// Token envelope: `[token: u16 LE, msg_id, payload...]`. See `protocol::seq`.
+pub const MSG_TOKEN: u8 = 0x91;
+
+/// Unsolicited progress of a long operation (homing, calibration, firmware update). Payload:
+/// `[operation, axis, phase, percent, status]`, see `protocol::progress`.
+pub const MSG_PROGRESS: u8 = 0xA0;
+/// Acknowledgement of a command sent with a token. Payload: `[token: u16 LE, status, reason]`.
+pub const MSG_TOKEN_ACK: u8 = 0xA1;
+/// Controller event caused by a command sent with a token. Payload: the `MSG_EVENT` payload
+/// followed by `token: u16 LE`.
+pub const MSG_TOKEN_EVENT: u8 = 0xA2;
+/// One wire format descriptor record. Payload: `[index, total, kind, record...]`, see `wire`.
+pub const MSG_WIRE_DESCRIPTOR: u8 = 0xA3;
+/// One input/output sample of an identification run. Payload:
+/// `[axis, index: u16, t_ms: u16, duty: i16, position: i16]`, see `control::ident`.
+pub const MSG_IDENT_SAMPLE: u8 = 0xA4;
+
+/// Direct motor commands.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Ping,
+    /// Reply with the capabilities summary and one record per axis.
+    GetCapabilities,
+    /// Select the optional telemetry fields streamed over BLE (see `protocol::telemetry::field`).
+    SetTelemetryFields(u8),
+    /// Reply with the telemetry field descriptor.
+    GetTelemetryDescriptor,
+    /// Send a full telemetry frame every N frames and deltas in between (0 = always full).
+    SetTelemetryDelta(u8),
+    /// Reply with the usage counters of one axis (1 = M1, 2 = M2).
+    GetStats(u8),
+    /// Reply with the state captured at the last fault of one axis.
+    GetFaultSnapshot(u8),
+    /// Reply with the command parser's frame counters.
+    GetLinkStats,
+    /// Reply with the value of one parameter (see `config::params`).
+    GetParam(u8),
+    /// Route a signal to DAC channel 1 or 2 (see `hw::dac::Signal`), 0 = off.
+    SetDacProbe {
+        channel: u8,
+        signal: u8,
+    },
+    /// Reply with `token` and the time the tile held the request.
+    Echo(u32),
+    /// Reply with record `index` of the wire format descriptor (see `wire`).
+    GetWireDescriptor(u8),
+    /// Read register or read command `addr` of `device` (see `protocol::register::device`).
+    ReadRegister {
+        device: u8,
+        addr: u8,
+    },
+    /// Set the RTC to the given Unix time in seconds.
+    SetTime(u32),
+    /// Set and persist one schedule slot. `kind` and `arg` encode a `control::schedule::Trigger`.
+    SetSchedule {
+        slot: u8,
+        kind: u8,
+        sequence: u8,
+        arg: u16,
+    },
+    M1Extend(u8),
+    M1Retract(u8),
+    M1Brake,
+    M1SetPosition(u8),
+    /// Move M1 by a signed delta in 0.01 mm from its current target, clamped to the stroke.
+    M1MoveRelative(i16),
+    M2Extend(u8),
+    M2Retract(u8),
+    M2Brake,
+    M2SetPosition(u8),
+    /// Move M2 by a signed delta in 0.01 mm from its current target, clamped to the stroke.
+    M2MoveRelative(i16),
+    BaseVelocity { vx: i8, vy: i8, omega: i8 },
+    BaseBrake,
+    /// Move the tilt axis by a signed delta in 0.01° from its current target, clamped to the
+    /// angle limits.
+    TiltMoveRelative(i16),
+    /// Drive axis 1 or 2 open loop with a test signal and stream the samples (see
+    /// `control::ident`). `signal` 0 stops a run, 1 = PRBS, 2 = chirp. `amplitude` is in 1/255
+    /// duty; `param` is the PRBS bit length in control steps or the chirp end frequency in
+    /// 0.1 Hz.
+    Identify {
+        axis: u8,
+        signal: u8,
+        amplitude: u8,
+        param: u8,
+        duration_s: u8,
+    },
+    /// Sweep axis 1 or 2 in position control with a sine of `amplitude` (0.1 mm) about its
+    /// position and log the gain and phase at each frequency (see `control::bode`). 0 stops a
+    /// sweep.
+    FrequencyResponse {
+        axis: u8,
+        amplitude: u8,
+    },
+    /// Run the factory provisioning flow and assign the given node ID.
+    Provision { node_id: u8 },
+    /// Set and persist the wiring polarity flags (see `config::Polarity` for the bit layout).
+    SetPolarity(u8),
+    /// Set and persist motion limits for one axis (1 = M1, 2 = M2). Velocity is in 0.1 mm/s,
+    /// acceleration in mm/s², duty in 1/255 of full scale.
+    SetMotionLimits {
+        axis: u8,
+        max_velocity: u16,
+        max_accel: u16,
+        max_duty: u8,
+    },
+    /// Set and persist the current regulation level of one axis (1 = M1, 2 = M2). See
+    /// `config::itrip_from_byte` for the level encoding.
+    SetCurrentLimit {
+        axis: u8,
+        level: u8,
+    },
+    /// Set one parameter (see `config::params`), persisting it if it lives in the config.
+    SetParam {
+        id: u8,
+        value: f32,
+    },
+    /// Make the pending configuration change permanent.
+    CommitConfig,
+    /// Roll the stored configuration back to the last committed record and apply it.
+    RevertConfig,
+    /// Demo mode: 0 = stop and don't start at boot, 1 = run until stopped or reset, 2 = run now
+    /// and after every boot.
+    SetDemo(u8),
+    /// Set and persist one interlock slot. `axis` 0 clears it.
+    SetInterlock {
+        slot: u8,
+        axis: u8,
+        source: u8,
+        min: i16,
+        max: i16,
+    },
+    /// Move the GIM6010 answering at `addr` and `bitrate` (0 = 1 Mbit/s, 1 = 500, 2 = 250,
+    /// 3 = 125 kbit/s) to the tilt axis settings, then home the axis. It should be the only
+    /// motor powered on the bus.
+    ProvisionTiltMotor {
+        addr: u8,
+        bitrate: u8,
+    },
+}
+
+impl Command {
+    /// True for commands that drive or stop an actuator or the base.
+    pub fn is_motion(&self) -> bool {
+        matches!(
+            self,
+            Command::M1Extend(_)
+                | Command::M1Retract(_)
+                | Command::M1Brake
+                | Command::M1SetPosition(_)
+                | Command::M1MoveRelative(_)
+                | Command::M2Extend(_)
+                | Command::M2Retract(_)
+                | Command::M2Brake
+                | Command::M2SetPosition(_)
+                | Command::M2MoveRelative(_)
+                | Command::BaseVelocity { .. }
+                | Command::BaseBrake
+                | Command::TiltMoveRelative(_)
+                | Command::Identify { .. }
+                | Command::FrequencyResponse { .. }
+        )
+    }
+
+    /// Axis a command sets moving (1 = M1, 2 = M2, 3 = tilt), for interlock checks. Brakes and
+    /// base commands have none.
+    pub fn motion_axis(&self) -> Option<u8> {
+        match self {
+            Command::M1Extend(_)
+            | Command::M1Retract(_)
+            | Command::M1SetPosition(_)
+            | Command::M1MoveRelative(_) => Some(1),
+            Command::M2Extend(_)
+            | Command::M2Retract(_)
+            | Command::M2SetPosition(_)
+            | Command::M2MoveRelative(_) => Some(2),
+            Command::TiltMoveRelative(_) => Some(3),
+            Command::Identify { axis, .. } | Command::FrequencyResponse { axis, .. } => Some(*axis),
+            _ => None,
+        }
+    }
+
+    /// Message ID this command was sent with.
+    pub fn msg_id(&self) -> u8 {
+        match self {
+            Command::Ping => MSG_PING,
+            Command::GetCapabilities => MSG_GET_CAPABILITIES,
+            Command::SetTelemetryFields(_) => MSG_SET_TELEMETRY_FIELDS,
+            Command::GetTelemetryDescriptor => MSG_GET_TELEMETRY_DESCRIPTOR,
+            Command::SetTelemetryDelta(_) => MSG_SET_TELEMETRY_DELTA,
+            Command::GetStats(_) => MSG_GET_STATS,
+            Command::GetFaultSnapshot(_) => MSG_GET_FAULT_SNAPSHOT,
+            Command::GetLinkStats => MSG_GET_LINK_STATS,
+            Command::GetParam(_) => MSG_GET_PARAM,
+            Command::SetDacProbe { .. } => MSG_SET_DAC_PROBE,
+            Command::ReadRegister { .. } => MSG_READ_REGISTER,
+            Command::GetWireDescriptor(_) => MSG_GET_WIRE_DESCRIPTOR,
+            Command::Echo(_) => MSG_ECHO,
+            Command::SetTime(_) => MSG_SET_TIME,
+            Command::SetSchedule { .. } => MSG_SET_SCHEDULE,
+            Command::M1Extend(_) => MSG_M1_EXTEND,
+            Command::M1Retract(_) => MSG_M1_RETRACT,
+            Command::M1Brake => MSG_M1_BRAKE,
+            Command::M1SetPosition(_) => MSG_M1_SET_POSITION,
+            Command::M1MoveRelative(_) => MSG_M1_MOVE_RELATIVE,
+            Command::M2Extend(_) => MSG_M2_EXTEND,
+            Command::M2Retract(_) => MSG_M2_RETRACT,
+            Command::M2Brake => MSG_M2_BRAKE,
+            Command::M2SetPosition(_) => MSG_M2_SET_POSITION,
+            Command::M2MoveRelative(_) => MSG_M2_MOVE_RELATIVE,
+            Command::BaseVelocity { .. } => MSG_BASE_VELOCITY,
+            Command::BaseBrake => MSG_BASE_BRAKE,
+            Command::TiltMoveRelative(_) => MSG_TILT_MOVE_RELATIVE,
+            Command::Identify { .. } => MSG_IDENTIFY,
+            Command::FrequencyResponse { .. } => MSG_FREQUENCY_RESPONSE,
+            Command::Provision { .. } => MSG_PROVISION,
+            Command::SetPolarity(_) => MSG_SET_POLARITY,
+            Command::SetMotionLimits { .. } => MSG_SET_MOTION_LIMITS,
+            Command::SetCurrentLimit { .. } => MSG_SET_CURRENT_LIMIT,
+            Command::SetParam { .. } => MSG_SET_PARAM,
+            Command::CommitConfig => MSG_COMMIT_CONFIG,
+            Command::RevertConfig => MSG_REVERT_CONFIG,
+            Command::SetInterlock { .. } => MSG_SET_INTERLOCK,
+            Command::SetDemo(_) => MSG_SET_DEMO,
+            Command::ProvisionTiltMotor { .. } => MSG_PROVISION_TILT_MOTOR,
+        }
+    }
+}
+
+/// Encode a frame (`[START_BYTE, id, payload..., checksum]`) into `out`.
+///
+/// Returns the number of bytes written, or `None` if `out` is too small.
+pub fn encode_frame(id: u8, payload: &[u8], out: &mut [u8]) -> Option<usize> {
+    let len = payload.len() + 3;
+    if out.len() < len {
+        return None;
+    }
+    out[0] = START_BYTE;
+    out[1] = id;
+    out[2..len - 1].copy_from_slice(payload);
+    out[len - 1] = payload.iter().fold(id, |acc, b| acc.wrapping_add(*b));
+    Some(len)
+}
+
+/// Encode a frame into a new buffer.
+#[cfg(feature = "std")]
+pub fn frame(id: u8, payload: &[u8]) -> std::vec::Vec<u8> {
+    let mut out = std::vec![0; payload.len() + 3];
+    encode_frame(id, payload, &mut out);
+    out
+}
@@ -0,0 +1,442 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Message parser for the OmniTiles command protocol.
+//!
+//! This module provides functionality to parse incoming command messages
+//! and convert them into actionable commands for the OmniTiles system.
+//!
+//! Commands may arrive bare or wrapped in a `MSG_SEQ` or `MSG_TOKEN` envelope; see
+//! [`crate::seq`].
+//!
+//! Every command shares the 0xA5 start byte, so a frame that loses a byte in transit would
+//! otherwise swallow the start of the next one. [`Parser::push_packet_at`] takes the receive time
+//! and abandons a frame whose bytes stop arriving ([`Parser::inter_byte_timeout_ms`]) or that has
+//! been in progress for too long ([`Parser::max_frame_age_ms`]), so the next start byte begins a
+//! fresh frame.
+//!
+//! The parser keeps [`ParserStats`] on what it saw, so a flaky cable or bridge shows up as rising
+//! checksum and resync counts rather than as commands that silently never happened.
+
+use crate::messages::*;
+
+/// Maximum payload size for any message.
+const MAX_PAYLOAD: usize = 7;
+/// Default gap after which a partly received frame is dropped. Must exceed the time between
+/// transfers on the link, since a frame may be split across two of them.
+pub const DEFAULT_INTER_BYTE_TIMEOUT_MS: u32 = 100;
+/// Default time from start byte to checksum after which a frame is dropped.
+pub const DEFAULT_MAX_FRAME_AGE_MS: u32 = 250;
+
+/// A parsed command and, if it arrived in a `MSG_SEQ` or `MSG_TOKEN` envelope, its sequence
+/// number or token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Packet {
+    pub seq: Option<u8>,
+    pub token: Option<u16>,
+    pub command: Command,
+}
+
+/// Frame-level counters since boot (or the last [`Parser::reset_stats`]). All wrap.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParserStats {
+    /// Frames with a valid checksum, whether or not they decoded to a command.
+    pub frames: u32,
+    /// Frames dropped because the checksum didn't match.
+    pub checksum_errors: u32,
+    /// Frames dropped because the message ID isn't one the tile accepts.
+    pub unknown_ids: u32,
+    /// Runs of stray bytes skipped while hunting for a start byte. Idle-line padding (0x00 and
+    /// 0xFF) between frames doesn't count.
+    pub resyncs: u32,
+    /// Partial frames abandoned because the rest didn't arrive in time.
+    pub timeouts: u32,
+}
+
+/// Serialized [`ParserStats`] length.
+pub const PARSER_STATS_LEN: usize = 20;
+
+impl ParserStats {
+    /// `[frames, checksum_errors, unknown_ids, resyncs, timeouts]`, each `u32` LE.
+    pub fn to_bytes(&self) -> [u8; PARSER_STATS_LEN] {
+        let mut out = [0u8; PARSER_STATS_LEN];
+        let fields = [
+            self.frames,
+            self.checksum_errors,
+            self.unknown_ids,
+            self.resyncs,
+            self.timeouts,
+        ];
+        for (chunk, v) in out.chunks_exact_mut(4).zip(fields) {
+            chunk.copy_from_slice(&v.to_le_bytes());
+        }
+        out
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+enum State {
+    WaitStart,
+    WaitId,
+    WaitSeq,
+    WaitTokenLo,
+    WaitTokenHi {
+        lo: u8,
+    },
+    WaitInnerId,
+    WaitPayload {
+        id: u8,
+        buf: [u8; MAX_PAYLOAD],
+        received: u8,
+        expected: u8,
+    },
+    WaitChecksum {
+        id: u8,
+        buf: [u8; MAX_PAYLOAD],
+        len: u8,
+    },
+}
+
+pub struct Parser {
+    state: State,
+    checksum: u8,
+    seq: Option<u8>,
+    token: Option<u16>,
+    stats: ParserStats,
+    /// Inside a run of stray bytes already counted as a resync.
+    skipping: bool,
+    /// Gap between bytes after which a partial frame is dropped, 0 = never.
+    pub inter_byte_timeout_ms: u32,
+    /// Frame duration after which a partial frame is dropped, 0 = never.
+    pub max_frame_age_ms: u32,
+    frame_start_ms: u64,
+    last_byte_ms: u64,
+}
+
+pub(crate) const fn payload_len(id: u8) -> Option<u8> {
+    match id {
+        MSG_M1_EXTEND
+        | MSG_M1_RETRACT
+        | MSG_M1_SET_POSITION
+        | MSG_M2_EXTEND
+        | MSG_M2_RETRACT
+        | MSG_M2_SET_POSITION
+        | MSG_PROVISION
+        | MSG_SET_POLARITY
+        | MSG_SET_TELEMETRY_FIELDS
+        | MSG_SET_TELEMETRY_DELTA
+        | MSG_GET_STATS
+        | MSG_GET_FAULT_SNAPSHOT
+        | MSG_GET_PARAM
+        | MSG_GET_WIRE_DESCRIPTOR
+        | MSG_SET_DEMO => Some(1),
+        MSG_M1_BRAKE
+        | MSG_M2_BRAKE
+        | MSG_PING
+        | MSG_GET_CAPABILITIES
+        | MSG_GET_TELEMETRY_DESCRIPTOR
+        | MSG_GET_LINK_STATS
+        | MSG_BASE_BRAKE
+        | MSG_COMMIT_CONFIG
+        | MSG_REVERT_CONFIG => Some(0),
+        MSG_M1_MOVE_RELATIVE
+        | MSG_M2_MOVE_RELATIVE
+        | MSG_TILT_MOVE_RELATIVE
+        | MSG_SET_CURRENT_LIMIT
+        | MSG_PROVISION_TILT_MOTOR
+        | MSG_SET_DAC_PROBE
+        | MSG_READ_REGISTER
+        | MSG_FREQUENCY_RESPONSE => Some(2),
+        MSG_BASE_VELOCITY => Some(3),
+        MSG_SET_TIME | MSG_ECHO => Some(4),
+        MSG_SET_SCHEDULE | MSG_SET_PARAM | MSG_IDENTIFY => Some(5),
+        MSG_SET_MOTION_LIMITS => Some(6),
+        MSG_SET_INTERLOCK => Some(7),
+        _ => None,
+    }
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self {
+            state: State::WaitStart,
+            checksum: 0,
+            seq: None,
+            token: None,
+            stats: ParserStats::default(),
+            skipping: false,
+            inter_byte_timeout_ms: DEFAULT_INTER_BYTE_TIMEOUT_MS,
+            max_frame_age_ms: DEFAULT_MAX_FRAME_AGE_MS,
+            frame_start_ms: 0,
+            last_byte_ms: 0,
+        }
+    }
+
+    /// Override the stall timeouts, see [`push_packet_at`](Self::push_packet_at). 0 disables a
+    /// check.
+    pub fn with_timeouts(mut self, inter_byte_ms: u32, max_frame_age_ms: u32) -> Self {
+        self.inter_byte_timeout_ms = inter_byte_ms;
+        self.max_frame_age_ms = max_frame_age_ms;
+        self
+    }
+
+    /// Frame counters, see [`ParserStats`].
+    pub fn stats(&self) -> ParserStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = ParserStats::default();
+    }
+
+    /// Process a single incoming byte. Returns `Some(Command)` if a complete packet is received.
+    ///
+    /// The sequence number or token of enveloped commands is discarded; use
+    /// [`push_packet`](Self::push_packet) to get it.
+    pub fn push(&mut self, byte: u8) -> Option<Command> {
+        self.push_packet(byte).map(|p| p.command)
+    }
+
+    /// Like [`push_packet`](Self::push_packet), for a byte received at `now_ms`. A partial frame
+    /// that stalled past [`inter_byte_timeout_ms`](Self::inter_byte_timeout_ms) or is older than
+    /// [`max_frame_age_ms`](Self::max_frame_age_ms) is dropped first, and the byte is parsed as
+    /// if nothing were in progress.
+    pub fn push_packet_at(&mut self, byte: u8, now_ms: u64) -> Option<Packet> {
+        if !matches!(self.state, State::WaitStart) {
+            let expired =
+                |since: u64, limit: u32| limit != 0 && now_ms.saturating_sub(since) > limit as u64;
+            if expired(self.last_byte_ms, self.inter_byte_timeout_ms)
+                || expired(self.frame_start_ms, self.max_frame_age_ms)
+            {
+                self.state = State::WaitStart;
+                self.stats.timeouts = self.stats.timeouts.wrapping_add(1);
+            }
+        }
+        if matches!(self.state, State::WaitStart) {
+            self.frame_start_ms = now_ms;
+        }
+        self.last_byte_ms = now_ms;
+        self.push_packet(byte)
+    }
+
+    /// Process a single incoming byte. Returns `Some(Packet)` if a complete packet is received.
+    ///
+    /// Without a receive time, a stalled frame is only abandoned by a checksum mismatch; prefer
+    /// [`push_packet_at`](Self::push_packet_at) where a clock is available.
+    pub fn push_packet(&mut self, byte: u8) -> Option<Packet> {
+        let (seq, token) = (self.seq, self.token);
+        self.push_inner(byte).map(|command| Packet {
+            seq,
+            token,
+            command,
+        })
+    }
+
+    fn push_inner(&mut self, byte: u8) -> Option<Command> {
+        match self.state {
+            State::WaitStart => {
+                if byte == START_BYTE {
+                    self.state = State::WaitId;
+                    self.checksum = 0;
+                    self.seq = None;
+                    self.token = None;
+                    self.skipping = false;
+                } else if byte != 0x00 && byte != 0xFF && !self.skipping {
+                    self.stats.resyncs = self.stats.resyncs.wrapping_add(1);
+                    self.skipping = true;
+                }
+            }
+            State::WaitId if byte == MSG_SEQ => {
+                self.checksum = self.checksum.wrapping_add(byte);
+                self.state = State::WaitSeq;
+            }
+            State::WaitSeq => {
+                self.checksum = self.checksum.wrapping_add(byte);
+                self.seq = Some(byte);
+                self.state = State::WaitInnerId;
+            }
+            State::WaitId if byte == MSG_TOKEN => {
+                self.checksum = self.checksum.wrapping_add(byte);
+                self.state = State::WaitTokenLo;
+            }
+            State::WaitTokenLo => {
+                self.checksum = self.checksum.wrapping_add(byte);
+                self.state = State::WaitTokenHi { lo: byte };
+            }
+            State::WaitTokenHi { lo } => {
+                self.checksum = self.checksum.wrapping_add(byte);
+                self.token = Some(u16::from_le_bytes([lo, byte]));
+                self.state = State::WaitInnerId;
+            }
+            State::WaitId | State::WaitInnerId => {
+                self.checksum = self.checksum.wrapping_add(byte);
+
+                match payload_len(byte) {
+                    Some(0) => {
+                        self.state = State::WaitChecksum {
+                            id: byte,
+                            buf: [0; MAX_PAYLOAD],
+                            len: 0,
+                        };
+                    }
+                    Some(n) => {
+                        self.state = State::WaitPayload {
+                            id: byte,
+                            buf: [0; MAX_PAYLOAD],
+                            received: 0,
+                            expected: n,
+                        };
+                    }
+                    None => {
+                        self.stats.unknown_ids = self.stats.unknown_ids.wrapping_add(1);
+                        self.state = State::WaitStart;
+                    }
+                }
+            }
+            State::WaitPayload {
+                id,
+                mut buf,
+                received,
+                expected,
+            } => {
+                self.checksum = self.checksum.wrapping_add(byte);
+                buf[received as usize] = byte;
+                let received = received + 1;
+
+                if received >= expected {
+                    self.state = State::WaitChecksum {
+                        id,
+                        buf,
+                        len: received,
+                    };
+                } else {
+                    self.state = State::WaitPayload {
+                        id,
+                        buf,
+                        received,
+                        expected,
+                    };
+                }
+            }
+            State::WaitChecksum { id, buf, len } => {
+                let valid = byte == self.checksum;
+                self.state = State::WaitStart;
+
+                if valid {
+                    self.stats.frames = self.stats.frames.wrapping_add(1);
+                    return match id {
+                        MSG_M1_EXTEND => Some(Command::M1Extend(buf[0])),
+                        MSG_M1_RETRACT => Some(Command::M1Retract(buf[0])),
+                        MSG_M1_BRAKE => Some(Command::M1Brake),
+                        MSG_M1_SET_POSITION => Some(Command::M1SetPosition(buf[0])),
+                        MSG_M1_MOVE_RELATIVE if len >= 2 => {
+                            Some(Command::M1MoveRelative(i16::from_le_bytes([
+                                buf[0], buf[1],
+                            ])))
+                        }
+                        MSG_M2_EXTEND => Some(Command::M2Extend(buf[0])),
+                        MSG_M2_RETRACT => Some(Command::M2Retract(buf[0])),
+                        MSG_M2_BRAKE => Some(Command::M2Brake),
+                        MSG_M2_SET_POSITION => Some(Command::M2SetPosition(buf[0])),
+                        MSG_M2_MOVE_RELATIVE if len >= 2 => {
+                            Some(Command::M2MoveRelative(i16::from_le_bytes([
+                                buf[0], buf[1],
+                            ])))
+                        }
+                        MSG_PING => Some(Command::Ping),
+                        MSG_GET_CAPABILITIES => Some(Command::GetCapabilities),
+                        MSG_SET_TELEMETRY_FIELDS => Some(Command::SetTelemetryFields(buf[0])),
+                        MSG_GET_TELEMETRY_DESCRIPTOR => Some(Command::GetTelemetryDescriptor),
+                        MSG_GET_LINK_STATS => Some(Command::GetLinkStats),
+                        MSG_GET_PARAM => Some(Command::GetParam(buf[0])),
+                        MSG_GET_WIRE_DESCRIPTOR => Some(Command::GetWireDescriptor(buf[0])),
+                        MSG_SET_DAC_PROBE if len >= 2 => Some(Command::SetDacProbe {
+                            channel: buf[0],
+                            signal: buf[1],
+                        }),
+                        MSG_READ_REGISTER if len >= 2 => Some(Command::ReadRegister {
+                            device: buf[0],
+                            addr: buf[1],
+                        }),
+                        MSG_SET_TELEMETRY_DELTA => Some(Command::SetTelemetryDelta(buf[0])),
+                        MSG_GET_STATS => Some(Command::GetStats(buf[0])),
+                        MSG_GET_FAULT_SNAPSHOT => Some(Command::GetFaultSnapshot(buf[0])),
+                        MSG_SET_SCHEDULE if len >= 5 => Some(Command::SetSchedule {
+                            slot: buf[0],
+                            kind: buf[1],
+                            sequence: buf[2],
+                            arg: u16::from_le_bytes([buf[3], buf[4]]),
+                        }),
+                        MSG_SET_TIME if len >= 4 => Some(Command::SetTime(u32::from_le_bytes([
+                            buf[0], buf[1], buf[2], buf[3],
+                        ]))),
+                        MSG_ECHO if len >= 4 => Some(Command::Echo(u32::from_le_bytes([
+                            buf[0], buf[1], buf[2], buf[3],
+                        ]))),
+                        MSG_BASE_VELOCITY if len >= 3 => Some(Command::BaseVelocity {
+                            vx: buf[0] as i8,
+                            vy: buf[1] as i8,
+                            omega: buf[2] as i8,
+                        }),
+                        MSG_BASE_BRAKE => Some(Command::BaseBrake),
+                        MSG_TILT_MOVE_RELATIVE if len >= 2 => {
+                            Some(Command::TiltMoveRelative(i16::from_le_bytes([
+                                buf[0], buf[1],
+                            ])))
+                        }
+                        MSG_FREQUENCY_RESPONSE if len >= 2 => Some(Command::FrequencyResponse {
+                            axis: buf[0],
+                            amplitude: buf[1],
+                        }),
+                        MSG_IDENTIFY if len >= 5 => Some(Command::Identify {
+                            axis: buf[0],
+                            signal: buf[1],
+                            amplitude: buf[2],
+                            param: buf[3],
+                            duration_s: buf[4],
+                        }),
+                        MSG_PROVISION => Some(Command::Provision { node_id: buf[0] }),
+                        MSG_SET_POLARITY => Some(Command::SetPolarity(buf[0])),
+                        MSG_SET_CURRENT_LIMIT if len >= 2 => Some(Command::SetCurrentLimit {
+                            axis: buf[0],
+                            level: buf[1],
+                        }),
+                        MSG_SET_MOTION_LIMITS if len >= 6 => Some(Command::SetMotionLimits {
+                            axis: buf[0],
+                            max_velocity: u16::from_le_bytes([buf[1], buf[2]]),
+                            max_accel: u16::from_le_bytes([buf[3], buf[4]]),
+                            max_duty: buf[5],
+                        }),
+                        MSG_SET_PARAM if len >= 5 => Some(Command::SetParam {
+                            id: buf[0],
+                            value: f32::from_le_bytes([buf[1], buf[2], buf[3], buf[4]]),
+                        }),
+                        MSG_COMMIT_CONFIG => Some(Command::CommitConfig),
+                        MSG_REVERT_CONFIG => Some(Command::RevertConfig),
+                        MSG_SET_DEMO => Some(Command::SetDemo(buf[0])),
+                        MSG_SET_INTERLOCK if len >= 7 => Some(Command::SetInterlock {
+                            slot: buf[0],
+                            axis: buf[1],
+                            source: buf[2],
+                            min: i16::from_le_bytes([buf[3], buf[4]]),
+                            max: i16::from_le_bytes([buf[5], buf[6]]),
+                        }),
+                        MSG_PROVISION_TILT_MOTOR if len >= 2 => Some(Command::ProvisionTiltMotor {
+                            addr: buf[0],
+                            bitrate: buf[1],
+                        }),
+                        _ => None,
+                    };
+                }
+                self.stats.checksum_errors = self.stats.checksum_errors.wrapping_add(1);
+            }
+        }
+        None
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
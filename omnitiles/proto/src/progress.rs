@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Progress of long blocking operations.
+//!
+//! Homing, endpoint calibration and firmware updates hold the main loop for several seconds. To
+//! keep the host from timing out on a silent tile, they report through a [`Reporter`], which the
+//! dispatcher turns into unsolicited `MSG_PROGRESS` frames. Payload:
+//!
+//! | Offset | Field |
+//! | ------ | ----- |
+//! | 0 | `operation: u8` (see [`Operation`]) |
+//! | 1 | `axis: u8` (1 = M1, 2 = M2, 3 = tilt, 0 = whole tile) |
+//! | 2 | `phase: u8` (see [`phase`]) |
+//! | 3 | `percent: u8` (0–100, never decreases within one operation) |
+//! | 4 | `status: u8` (see [`Status`]) |
+//!
+//! A report goes out whenever the phase changes, and otherwise at most every
+//! [`PROGRESS_INTERVAL_MS`]. Every operation ends with one report of status
+//! [`Status::Done`] or [`Status::Failed`].
+
+/// Period of reports within one phase.
+pub const PROGRESS_INTERVAL_MS: u32 = 250;
+
+/// Serialized payload length.
+pub const PROGRESS_LEN: usize = 5;
+
+/// Long-running operation being reported. The discriminant is the wire value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Operation {
+    /// Finding an axis's hard stop (see `control::TiltController::home`).
+    Homing = 1,
+    /// Factory provisioning with endpoint calibration (see `config::provision`).
+    Calibration = 2,
+    /// Writing a new firmware image. Reserved for the bootloader; the application never sends
+    /// it.
+    FirmwareUpdate = 3,
+}
+
+/// State of the operation. The discriminant is the wire value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Status {
+    Running = 0,
+    Done = 1,
+    /// Stopped in the reported phase.
+    Failed = 2,
+}
+
+/// Phase codes, per operation.
+pub mod phase {
+    /// Homing: driving towards the hard stop.
+    pub const HOMING_SEEK: u8 = 0;
+    /// Homing: backing off the stop to the home position.
+    pub const HOMING_BACKOFF: u8 = 1;
+
+    // Calibration phases are the `config::provision::Step` discriminants.
+
+    /// Firmware update: erasing the target bank.
+    pub const UPDATE_ERASE: u8 = 0;
+    /// Firmware update: writing the image.
+    pub const UPDATE_WRITE: u8 = 1;
+    /// Firmware update: checking the written image.
+    pub const UPDATE_VERIFY: u8 = 2;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Progress {
+    pub operation: Operation,
+    pub axis: u8,
+    pub phase: u8,
+    pub percent: u8,
+    pub status: Status,
+}
+
+impl Progress {
+    pub fn to_bytes(&self) -> [u8; PROGRESS_LEN] {
+        [
+            self.operation as u8,
+            self.axis,
+            self.phase,
+            self.percent,
+            self.status as u8,
+        ]
+    }
+}
+
+/// `lo` plus the fraction `done / total` of the span up to `hi`, for a phase that covers
+/// `lo..hi` percent of the operation.
+pub fn scale(lo: u8, hi: u8, done: u32, total: u32) -> u8 {
+    if total == 0 || done >= total {
+        return hi;
+    }
+    lo + ((hi.saturating_sub(lo)) as u32 * done / total) as u8
+}
+
+/// Paces progress reports of one operation into `sink`.
+pub struct Reporter<F> {
+    sink: F,
+    operation: Operation,
+    axis: u8,
+    phase: Option<u8>,
+    percent: u8,
+    /// Time since the last report.
+    quiet_ms: u32,
+}
+
+impl<F: FnMut(Progress)> Reporter<F> {
+    pub fn new(operation: Operation, axis: u8, sink: F) -> Self {
+        Self {
+            sink,
+            operation,
+            axis,
+            phase: None,
+            percent: 0,
+            quiet_ms: 0,
+        }
+    }
+
+    /// Record `percent` done in `phase`, `dt_ms` after the previous update. Reports if the phase
+    /// changed or the last report is [`PROGRESS_INTERVAL_MS`] old.
+    pub fn update(&mut self, phase: u8, percent: u8, dt_ms: u32) {
+        self.percent = self.percent.max(percent.min(100));
+        self.quiet_ms = self.quiet_ms.saturating_add(dt_ms);
+        if self.phase != Some(phase) || self.quiet_ms >= PROGRESS_INTERVAL_MS {
+            self.phase = Some(phase);
+            self.send(Status::Running);
+        }
+    }
+
+    /// Report the end of the operation: 100 % on success, the last phase and percentage on
+    /// failure.
+    pub fn finish(&mut self, ok: bool) {
+        if ok {
+            self.percent = 100;
+            self.send(Status::Done);
+        } else {
+            self.send(Status::Failed);
+        }
+    }
+
+    fn send(&mut self, status: Status) {
+        self.quiet_ms = 0;
+        (self.sink)(Progress {
+            operation: self.operation,
+            axis: self.axis,
+            phase: self.phase.unwrap_or(0),
+            percent: self.percent,
+            status,
+        });
+    }
+}
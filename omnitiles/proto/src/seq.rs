@@ -0,0 +1,109 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Sequence numbers, duplicate rejection and acknowledgements.
+//!
+//! A host on a lossy link wraps commands in a `MSG_SEQ` envelope carrying an 8-bit sequence
+//! number:
+//!
+//! ```text
+//! [START_BYTE] [MSG_SEQ] [seq] [msg_id] [payload...] [checksum]
+//! ```
+//!
+//! The tile answers every sequenced command with `MSG_ACK` (`[seq, status, reason]`, the reason
+//! being a [`Reject`] code or 0). If the ACK is lost the host resends the same frame with the same
+//! `seq`; the tile recognises the duplicate, does not execute it again and re-sends the ACK with
+//! [`AckStatus::Duplicate`]. Unsequenced commands are still accepted as before.
+//!
+//! Eight bits are enough to spot a retransmission, but not to tell which command a response
+//! belongs to once ACKs, events and telemetry share one stream and a move can take seconds to
+//! finish. For that the host sends a `MSG_TOKEN` envelope with a 16-bit token instead:
+//!
+//! ```text
+//! [START_BYTE] [MSG_TOKEN] [token: u16 LE] [msg_id] [payload...] [checksum]
+//! ```
+//!
+//! Tokens are deduplicated the same way, in a window of their own, and acknowledged with
+//! `MSG_TOKEN_ACK` (`[token: u16, status, reason]`). For a position move, the controller event
+//! that ends it (target reached, a fault, an interlock stop) is sent as `MSG_TOKEN_EVENT`, the
+//! `MSG_EVENT` payload followed by the token.
+
+/// Number of recent sequence numbers (or tokens) remembered for duplicate detection. The host
+/// must not reuse one within this many commands.
+pub const SEQ_WINDOW: usize = 16;
+
+/// Why a command was not executed. The discriminant is the reason byte of `MSG_ACK` and
+/// `MSG_TOKEN_ACK`. Codes are never renumbered or reused.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Reject {
+    /// The command's class exceeded its rate.
+    RateLimited = 0x01,
+    /// The payload is out of range.
+    OutOfRange = 0x02,
+    /// The tile is in safe mode and the command isn't one of the few it accepts there.
+    SafeMode = 0x03,
+    /// A motion interlock blocks the axis.
+    Interlock = 0x04,
+    /// A later motion command for the same axis in the same transfer replaced it.
+    Superseded = 0x05,
+    /// A brake for the same axis later in the same transfer cancelled it.
+    Preempted = 0x06,
+    /// A configuration change arrived while an axis was moving.
+    Busy = 0x07,
+    /// The axis is faulted (e.g. lost position feedback) and can't take a position target.
+    /// Brake or drive it manually to clear the fault.
+    Faulted = 0x08,
+    /// The axis needs homing before it can take a position target.
+    NotHomed = 0x09,
+    /// This build has no such axis or feature.
+    Unsupported = 0x0A,
+}
+
+/// Status byte of a `MSG_ACK` frame.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AckStatus {
+    /// The command is new and has been accepted for execution.
+    Accepted = 0x00,
+    /// The command was a retransmission and was not executed again.
+    Duplicate = 0x01,
+    /// The command was not executed; the ACK's reason byte says why. Resend with a new `seq`.
+    Rejected = 0x02,
+}
+
+/// Remembers the last [`SEQ_WINDOW`] sequence numbers, or tokens with `SeqTracker<u16>`.
+pub struct SeqTracker<T = u8> {
+    recent: [Option<T>; SEQ_WINDOW],
+    next: usize,
+}
+
+impl<T: Copy + PartialEq> SeqTracker<T> {
+    pub const fn new() -> Self {
+        Self {
+            recent: [None; SEQ_WINDOW],
+            next: 0,
+        }
+    }
+
+    /// Record `seq` and report whether it should be executed.
+    pub fn check(&mut self, seq: T) -> AckStatus {
+        if self.recent.contains(&Some(seq)) {
+            return AckStatus::Duplicate;
+        }
+        self.recent[self.next] = Some(seq);
+        self.next = (self.next + 1) % SEQ_WINDOW;
+        AckStatus::Accepted
+    }
+
+    /// Forget all history (e.g. when the host reconnects and restarts its counter).
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<T: Copy + PartialEq> Default for SeqTracker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,181 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Telemetry field map for the BLE link.
+//!
+//! The full telemetry frame carries every field. On a bandwidth-constrained link the host can
+//! send `MSG_SET_TELEMETRY_FIELDS` with a bitmask of [`field`]s, after which the bridge streams
+//! `MSG_TELEMETRY_SELECTED` frames instead:
+//!
+//! ```text
+//! [mask: u8] [m1_pos: u16] [m2_pos: u16] [selected fields in bit order...]
+//! ```
+//!
+//! The two positions are always present. Selecting [`field::ALL`] restores the full frame.
+//!
+//! [`FIELDS`] is the single source of truth for the layout; `MSG_GET_TELEMETRY_DESCRIPTOR`
+//! replies with it serialized by [`write_descriptor`] so hosts need not hardcode it.
+//!
+//! # Delta encoding
+//!
+//! After `MSG_SET_TELEMETRY_DELTA` with interval N, the bridge sends a full frame (keyframe)
+//! every N frames and `MSG_TELEMETRY_DELTA` frames in between. The full frame body is treated as
+//! [`DELTA_SLOTS`] slots in wire order; a delta frame carries only the slots that changed since
+//! the previous frame:
+//!
+//! ```text
+//! [changed: u24 bitmap] [per changed slot: i8 difference (u16 slots) or raw 4 bytes]
+//! ```
+//!
+//! A u16 slot that moved by more than an i8 forces a keyframe, as does any failed send, so the
+//! host can always rebuild the full frame from the last one it received. i16 slots are diffed as
+//! their u16 bit pattern, so a reading crossing zero also forces one.
+
+/// Field selection bits.
+pub mod field {
+    /// Four UWB anchor ranges.
+    pub const UWB: u8 = 1 << 0;
+    /// ToF range.
+    pub const TOF: u8 = 1 << 1;
+    /// Six-axis IMU sample.
+    pub const IMU: u8 = 1 << 2;
+    /// Raw pot ADCs (4 × M1, 2 × M2).
+    pub const MOTOR_ADCS: u8 = 1 << 3;
+    /// Sample time in microseconds since boot.
+    pub const TIMESTAMP: u8 = 1 << 4;
+    /// M1 and M2 motor temperatures, see [`encode_temperature`](super::encode_temperature).
+    pub const MOTOR_TEMPS: u8 = 1 << 5;
+    /// Deepest stack use since boot in KB (1024 bytes), rounded up.
+    pub const STACK: u8 = 1 << 6;
+
+    /// Every optional field; equivalent to the full telemetry frame.
+    pub const ALL: u8 = UWB | TOF | IMU | MOTOR_ADCS | TIMESTAMP | MOTOR_TEMPS | STACK;
+}
+
+/// Element type of a field.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    U16 = 1,
+    F32 = 2,
+    U32 = 3,
+    I16 = 4,
+}
+
+impl FieldKind {
+    pub const fn size(self) -> usize {
+        match self {
+            FieldKind::U16 | FieldKind::I16 => 2,
+            FieldKind::F32 | FieldKind::U32 => 4,
+        }
+    }
+}
+
+/// Layout of one selectable field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FieldDesc {
+    pub bit: u8,
+    pub kind: FieldKind,
+    pub count: u8,
+}
+
+impl FieldDesc {
+    /// Encoded length in bytes.
+    #[allow(clippy::len_without_is_empty)]
+    pub const fn len(&self) -> usize {
+        self.kind.size() * self.count as usize
+    }
+}
+
+/// Selectable fields, in wire order.
+pub const FIELDS: [FieldDesc; 7] = [
+    FieldDesc {
+        bit: field::UWB,
+        kind: FieldKind::U16,
+        count: 4,
+    },
+    FieldDesc {
+        bit: field::TOF,
+        kind: FieldKind::U16,
+        count: 1,
+    },
+    FieldDesc {
+        bit: field::IMU,
+        kind: FieldKind::F32,
+        count: 6,
+    },
+    FieldDesc {
+        bit: field::MOTOR_ADCS,
+        kind: FieldKind::U16,
+        count: 6,
+    },
+    FieldDesc {
+        bit: field::TIMESTAMP,
+        kind: FieldKind::U32,
+        count: 1,
+    },
+    FieldDesc {
+        bit: field::MOTOR_TEMPS,
+        kind: FieldKind::I16,
+        count: 2,
+    },
+    FieldDesc {
+        bit: field::STACK,
+        kind: FieldKind::U16,
+        count: 1,
+    },
+];
+
+/// Element types of the full telemetry body, in wire order: M1 and M2 position, four UWB ranges,
+/// ToF, six IMU floats, six pot ADCs, sample time, two motor temperatures, stack peak.
+pub const DELTA_SLOTS: [FieldKind; 23] = {
+    use FieldKind::{F32, I16, U16, U32};
+    [
+        U16, U16, U16, U16, U16, U16, U16, F32, F32, F32, F32, F32, F32, U16, U16, U16, U16, U16,
+        U16, U32, I16, I16, U16,
+    ]
+};
+
+/// Motor temperature sent without a thermistor or a plausible reading.
+pub const NO_TEMPERATURE: i16 = i16::MAX;
+
+/// Encode a motor temperature for [`field::MOTOR_TEMPS`]: i16 in 0.1 °C, [`NO_TEMPERATURE`] for
+/// `None`.
+pub fn encode_temperature(celsius: Option<f32>) -> [u8; 2] {
+    let value = match celsius {
+        Some(c) => ((c * 10.0) as i16).min(NO_TEMPERATURE - 1),
+        None => NO_TEMPERATURE,
+    };
+    value.to_le_bytes()
+}
+
+/// Bytes always present in a selected frame: mask and both positions.
+pub const SELECTED_HEADER_LEN: usize = 5;
+
+/// Payload length of a `MSG_TELEMETRY_SELECTED` frame for `mask`.
+pub fn selected_len(mask: u8) -> usize {
+    FIELDS
+        .iter()
+        .filter(|f| mask & f.bit != 0)
+        .fold(SELECTED_HEADER_LEN, |n, f| n + f.len())
+}
+
+/// Descriptor payload length.
+pub const DESCRIPTOR_LEN: usize = 1 + FIELDS.len() * 3;
+
+/// Serialize [`FIELDS`] as a descriptor payload: `[count, (bit, kind, count)...]`.
+///
+/// Returns the number of bytes written, or `None` if `out` is too small.
+pub fn write_descriptor(out: &mut [u8]) -> Option<usize> {
+    let len = DESCRIPTOR_LEN;
+    if out.len() < len {
+        return None;
+    }
+    out[0] = FIELDS.len() as u8;
+    for (i, f) in FIELDS.iter().enumerate() {
+        out[1 + i * 3] = f.bit;
+        out[2 + i * 3] = f.kind as u8;
+        out[3 + i * 3] = f.count;
+    }
+    Some(len)
+}
@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Machine-readable description of the wire format.
+//!
+//! `MSG_GET_WIRE_DESCRIPTOR` asks for one record by index and is answered with one
+//! `MSG_WIRE_DESCRIPTOR` frame of fixed length [`WIRE_DESCRIPTOR_LEN`]:
+//!
+//! ```text
+//! [index] [total] [kind] [record...]
+//! ```
+//!
+//! Records `0..MESSAGES.len()` describe the messages in [`MESSAGES`]; the rest describe the
+//! tile's tunable parameters, from the firmware's parameter registry. A host asks for index 0,
+//! learns `total` and walks the rest, so a non-Rust host can generate bindings from the tile
+//! itself. An index past the end is answered with [`RECORD_NONE`].
+//!
+//! ```text
+//! message:   [id, direction, layout: 12 bytes, name: 24 bytes]
+//! parameter: [id, kind, stored, min: f32, max: f32, default: f32, name: 24 bytes]
+//! ```
+//!
+//! Strings are ASCII and NUL-padded. A message `layout` is a Python `struct` format for the
+//! payload without the byte-order prefix (always little-endian, standard sizes), so
+//! `struct.unpack("<" + layout, payload)` decodes it. `*` marks a payload whose length varies;
+//! see the message's documentation. A parameter `kind` is a
+//! [`FieldKind`](crate::telemetry::FieldKind) code.
+
+use crate::messages::*;
+use crate::telemetry::FieldKind;
+
+/// Payload length of a `MSG_WIRE_DESCRIPTOR` frame.
+pub const WIRE_DESCRIPTOR_LEN: usize = 3 + RECORD_LEN;
+/// Length of the record after the header, padded to the longest kind.
+pub const RECORD_LEN: usize = 39;
+/// Length of a message layout string.
+pub const LAYOUT_LEN: usize = 12;
+/// Length of a name string.
+pub const NAME_LEN: usize = 24;
+
+/// `kind` of a request past the last record.
+pub const RECORD_NONE: u8 = 0;
+/// `kind` of a message record.
+pub const RECORD_MESSAGE: u8 = 1;
+/// `kind` of a parameter record.
+pub const RECORD_PARAM: u8 = 2;
+
+/// Who sends a message.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Host to tile.
+    Command = 0,
+    /// Tile to host.
+    Reply = 1,
+}
+
+/// One row of [`MESSAGES`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MessageDesc {
+    pub id: u8,
+    pub direction: Direction,
+    /// Payload as a `struct` format, `*` if variable.
+    pub layout: &'static str,
+    /// Constant name without the `MSG_` prefix.
+    pub name: &'static str,
+}
+
+/// A tunable parameter as described on the wire.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParamRecord {
+    pub id: u8,
+    pub kind: FieldKind,
+    /// Persisted to flash on set.
+    pub stored: bool,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    pub name: &'static str,
+}
+
+/// A record of the descriptor.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Record {
+    None,
+    Message(&'static MessageDesc),
+    Param(ParamRecord),
+}
+
+const fn cmd(id: u8, layout: &'static str, name: &'static str) -> MessageDesc {
+    MessageDesc {
+        id,
+        direction: Direction::Command,
+        layout,
+        name,
+    }
+}
+
+const fn reply(id: u8, layout: &'static str, name: &'static str) -> MessageDesc {
+    MessageDesc {
+        id,
+        direction: Direction::Reply,
+        layout,
+        name,
+    }
+}
+
+/// Every message. `MSG_ECHO` has a row for each direction since its reply payload differs.
+#[rustfmt::skip]
+pub const MESSAGES: &[MessageDesc] = &[
+    cmd(MSG_M1_EXTEND, "B", "M1_EXTEND"),
+    cmd(MSG_M1_RETRACT, "B", "M1_RETRACT"),
+    cmd(MSG_M1_BRAKE, "", "M1_BRAKE"),
+    cmd(MSG_M1_SET_POSITION, "B", "M1_SET_POSITION"),
+    cmd(MSG_M1_MOVE_RELATIVE, "h", "M1_MOVE_RELATIVE"),
+    cmd(MSG_M2_EXTEND, "B", "M2_EXTEND"),
+    cmd(MSG_M2_RETRACT, "B", "M2_RETRACT"),
+    cmd(MSG_M2_BRAKE, "", "M2_BRAKE"),
+    cmd(MSG_M2_SET_POSITION, "B", "M2_SET_POSITION"),
+    cmd(MSG_M2_MOVE_RELATIVE, "h", "M2_MOVE_RELATIVE"),
+    cmd(MSG_PING, "", "PING"),
+    cmd(MSG_GET_CAPABILITIES, "", "GET_CAPABILITIES"),
+    cmd(MSG_SET_TELEMETRY_FIELDS, "B", "SET_TELEMETRY_FIELDS"),
+    cmd(MSG_GET_TELEMETRY_DESCRIPTOR, "", "GET_TELEMETRY_DESCRIPTOR"),
+    cmd(MSG_SET_TELEMETRY_DELTA, "B", "SET_TELEMETRY_DELTA"),
+    cmd(MSG_GET_STATS, "B", "GET_STATS"),
+    cmd(MSG_GET_FAULT_SNAPSHOT, "B", "GET_FAULT_SNAPSHOT"),
+    cmd(MSG_SET_TIME, "I", "SET_TIME"),
+    cmd(MSG_SET_SCHEDULE, "BBBH", "SET_SCHEDULE"),
+    cmd(MSG_GET_LINK_STATS, "", "GET_LINK_STATS"),
+    cmd(MSG_GET_PARAM, "B", "GET_PARAM"),
+    cmd(MSG_SET_DAC_PROBE, "BB", "SET_DAC_PROBE"),
+    cmd(MSG_READ_REGISTER, "BB", "READ_REGISTER"),
+    cmd(MSG_ECHO, "I", "ECHO"),
+    cmd(MSG_GET_WIRE_DESCRIPTOR, "B", "GET_WIRE_DESCRIPTOR"),
+    reply(MSG_ECHO, "II", "ECHO"),
+    reply(MSG_TELEMETRY, "7H6f6HIhhH", "TELEMETRY"),
+    reply(MSG_EVENT, "BBBI", "EVENT"),
+    reply(MSG_HEARTBEAT, "BIBB", "HEARTBEAT"),
+    reply(MSG_ACK, "BBB", "ACK"),
+    reply(MSG_CAPABILITIES, "BBBBB", "CAPABILITIES"),
+    reply(MSG_AXIS_CAPS, "BBhhH", "AXIS_CAPS"),
+    reply(MSG_TELEMETRY_SELECTED, "*", "TELEMETRY_SELECTED"),
+    reply(MSG_TELEMETRY_DESCRIPTOR, "*", "TELEMETRY_DESCRIPTOR"),
+    reply(MSG_TELEMETRY_DELTA, "*", "TELEMETRY_DELTA"),
+    reply(MSG_STATS, "BQIII", "STATS"),
+    reply(MSG_FAULT_SNAPSHOT, "BBIhhhHBBBx", "FAULT_SNAPSHOT"),
+    reply(MSG_FAULT_HISTORY, "BB16B", "FAULT_HISTORY"),
+    reply(MSG_LINK_STATS, "5I", "LINK_STATS"),
+    reply(MSG_PARAM, "BBf", "PARAM"),
+    reply(MSG_CONFIG_STATUS, "BB", "CONFIG_STATUS"),
+    reply(MSG_REGISTER, "BBBB8B", "REGISTER"),
+    cmd(MSG_BASE_VELOCITY, "bbb", "BASE_VELOCITY"),
+    cmd(MSG_BASE_BRAKE, "", "BASE_BRAKE"),
+    cmd(MSG_TILT_MOVE_RELATIVE, "h", "TILT_MOVE_RELATIVE"),
+    cmd(MSG_IDENTIFY, "BBBBB", "IDENTIFY"),
+    cmd(MSG_FREQUENCY_RESPONSE, "BB", "FREQUENCY_RESPONSE"),
+    cmd(MSG_PROVISION, "B", "PROVISION"),
+    cmd(MSG_SET_POLARITY, "B", "SET_POLARITY"),
+    cmd(MSG_SET_MOTION_LIMITS, "BHHB", "SET_MOTION_LIMITS"),
+    cmd(MSG_SET_CURRENT_LIMIT, "BB", "SET_CURRENT_LIMIT"),
+    cmd(MSG_SET_PARAM, "Bf", "SET_PARAM"),
+    cmd(MSG_COMMIT_CONFIG, "", "COMMIT_CONFIG"),
+    cmd(MSG_REVERT_CONFIG, "", "REVERT_CONFIG"),
+    cmd(MSG_SET_INTERLOCK, "BBBhh", "SET_INTERLOCK"),
+    cmd(MSG_SET_DEMO, "B", "SET_DEMO"),
+    cmd(MSG_PROVISION_TILT_MOTOR, "BB", "PROVISION_TILT_MOTOR"),
+    cmd(MSG_SEQ, "*", "SEQ"),
+    cmd(MSG_TOKEN, "*", "TOKEN"),
+    reply(MSG_PROGRESS, "BBBBB", "PROGRESS"),
+    reply(MSG_TOKEN_ACK, "HBB", "TOKEN_ACK"),
+    reply(MSG_TOKEN_EVENT, "BBBIH", "TOKEN_EVENT"),
+    reply(MSG_WIRE_DESCRIPTOR, "*", "WIRE_DESCRIPTOR"),
+    reply(MSG_IDENT_SAMPLE, "BHHhh", "IDENT_SAMPLE"),
+];
+
+/// Bytes a `struct` format describes, `None` for `*`.
+pub const fn layout_size(layout: &str) -> Option<usize> {
+    let bytes = layout.as_bytes();
+    let mut size = 0;
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        i += 1;
+        if c.is_ascii_digit() {
+            count = count * 10 + (c - b'0') as usize;
+            continue;
+        }
+        let width = match c {
+            b'x' | b'B' | b'b' | b's' => 1,
+            b'H' | b'h' => 2,
+            b'I' | b'i' | b'f' => 4,
+            b'Q' | b'q' => 8,
+            _ => return None,
+        };
+        size += width * if count == 0 { 1 } else { count };
+        count = 0;
+    }
+    Some(size)
+}
+
+/// True if every command row's layout matches the payload length the parser expects.
+const fn layouts_match_parser() -> bool {
+    let mut i = 0;
+    while i < MESSAGES.len() {
+        let m = &MESSAGES[i];
+        i += 1;
+        if !matches!(m.direction, Direction::Command) {
+            continue;
+        }
+        let ok = match (layout_size(m.layout), crate::parser::payload_len(m.id)) {
+            (Some(size), Some(len)) => size == len as usize,
+            // Envelopes are decoded by their own parser states.
+            (None, None) => true,
+            _ => false,
+        };
+        if !ok || m.layout.len() > LAYOUT_LEN || m.name.len() > NAME_LEN {
+            return false;
+        }
+    }
+    true
+}
+
+const _: () = assert!(
+    layouts_match_parser(),
+    "wire: MESSAGES must match the parser's payload lengths"
+);
+
+/// Number of records when the tile has `param_count` parameters.
+pub const fn record_count(param_count: usize) -> usize {
+    MESSAGES.len() + param_count
+}
+
+fn put_str(out: &mut [u8], s: &str) {
+    let n = s.len().min(out.len());
+    out[..n].copy_from_slice(&s.as_bytes()[..n]);
+}
+
+/// Build the `MSG_WIRE_DESCRIPTOR` payload for record `index` of `total`.
+pub fn write_descriptor(index: u8, total: u8, record: Record) -> [u8; WIRE_DESCRIPTOR_LEN] {
+    let mut out = [0u8; WIRE_DESCRIPTOR_LEN];
+    out[0] = index;
+    out[1] = total;
+    let body = &mut out[3..];
+    match record {
+        Record::None => out[2] = RECORD_NONE,
+        Record::Message(m) => {
+            body[0] = m.id;
+            body[1] = m.direction as u8;
+            put_str(&mut body[2..2 + LAYOUT_LEN], m.layout);
+            put_str(&mut body[2 + LAYOUT_LEN..], m.name);
+            out[2] = RECORD_MESSAGE;
+        }
+        Record::Param(p) => {
+            body[0] = p.id;
+            body[1] = p.kind as u8;
+            body[2] = p.stored as u8;
+            body[3..7].copy_from_slice(&p.min.to_le_bytes());
+            body[7..11].copy_from_slice(&p.max.to_le_bytes());
+            body[11..15].copy_from_slice(&p.default.to_le_bytes());
+            put_str(&mut body[15..15 + NAME_LEN], p.name);
+            out[2] = RECORD_PARAM;
+        }
+    }
+    out
+}
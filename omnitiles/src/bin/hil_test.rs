@@ -0,0 +1,418 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Hardware-in-the-loop test binary for end-of-line testing of assembled tiles.
+//!
+//! Flash with `cargo run --release --bin hil_test` and attach to the USART at 115200. Each check
+//! prints one line, followed by a summary line:
+//!
+//! ```text
+//! HIL,<check>,<PASS|FAIL|SKIP>,<detail>
+//! HIL,SUMMARY,<PASS|FAIL>,pass=<n> fail=<n> skip=<n>
+//! ```
+//!
+//! The CAN loopback and encoder checks run on builds with the `can` and `encoder` features and are
+//! reported as SKIP otherwise. The red LED is lit on failure and the green LED on success once all
+//! checks have run.
+
+#![no_main]
+#![no_std]
+#![allow(unused)]
+
+use cortex_m::delay::Delay;
+use cortex_m_rt::entry;
+use panic_halt as _;
+
+use core::cell::RefCell;
+use core::fmt::Write;
+
+use hal::{
+    i2c::{BlockingI2c, Mode as I2cMode},
+    pac,
+    prelude::*,
+    serial::{Config, Serial},
+    spi::{Mode, Phase, Polarity, Spi},
+};
+use stm32f7xx_hal as hal;
+
+#[cfg(feature = "encoder")]
+use omnitiles::hw::Encoder;
+use omnitiles::{
+    control::{LinearController, Pid},
+    drivers::{lsm6dsv16x, ActuonixLinear, Drv8873, Lsm6dsv16x, Vl53l0x},
+    hw::{self, Adc, BoardPins, ChipSelect, I2cBus, Led, NoChipSelect, SpiBus, Usart},
+    units::Mm,
+};
+#[cfg(feature = "can")]
+use omnitiles::{
+    hw::{CanBus, CanFrame, CanInterface},
+    net::slcan::btr_for_bitrate,
+};
+
+/// Raw ADC readings closer than this to either rail indicate an open or shorted pot.
+const ADC_RAIL_MARGIN: u16 = 40;
+
+/// Drive duty and duration for the motor pulse checks.
+const PULSE_SPEED: f32 = 0.6;
+const PULSE_MS: u32 = 150;
+
+/// Minimum fused pot movement (raw counts) for a motor pulse to count as a pass.
+const PULSE_MIN_DELTA: i32 = 20;
+
+/// Timeout for the closed-loop move checks.
+const MOVE_TIMEOUT_MS: u32 = 5000;
+
+/// Frame sent on each bus in the CAN loopback check, and how long to wait for it to come back.
+#[cfg(feature = "can")]
+const LOOPBACK_ID: u16 = 0x7A5;
+#[cfg(feature = "can")]
+const LOOPBACK_DATA: [u8; 8] = [0x55, 0xAA, 0x01, 0x02, 0x04, 0x08, 0x10, 0x20];
+#[cfg(feature = "can")]
+const LOOPBACK_TIMEOUT_MS: u32 = 10;
+
+/// Minimum M1 encoder count for a motor pulse to pass the encoder check.
+#[cfg(feature = "encoder")]
+const ENCODER_MIN_COUNTS: i32 = 8;
+
+/// Pass/fail tally and report formatter.
+struct Report {
+    pass: u32,
+    fail: u32,
+    skip: u32,
+}
+
+impl Report {
+    fn new() -> Self {
+        Self {
+            pass: 0,
+            fail: 0,
+            skip: 0,
+        }
+    }
+
+    fn result<W: Write>(
+        &mut self,
+        out: &mut W,
+        check: &str,
+        ok: bool,
+        detail: core::fmt::Arguments,
+    ) {
+        if ok {
+            self.pass += 1;
+        } else {
+            self.fail += 1;
+        }
+        let verdict = if ok { "PASS" } else { "FAIL" };
+        writeln!(out, "HIL,{},{},{}\r", check, verdict, detail).ok();
+    }
+
+    fn skip<W: Write>(&mut self, out: &mut W, check: &str, reason: &str) {
+        self.skip += 1;
+        writeln!(out, "HIL,{},SKIP,{}\r", check, reason).ok();
+    }
+
+    fn passed(&self) -> bool {
+        self.fail == 0
+    }
+}
+
+#[entry]
+fn main() -> ! {
+    let dp = pac::Peripherals::take().unwrap();
+    let mut cp = cortex_m::Peripherals::take().unwrap();
+    hw::init(&mut cp.SCB, &mut cp.CPUID, &mut cp.FPU, &mut cp.MPU);
+
+    let rcc = dp.RCC.constrain();
+    let clocks = rcc.cfgr.freeze();
+    let mut apb1 = rcc.apb1;
+    let mut apb2 = rcc.apb2;
+
+    let mut delay = Delay::new(cp.SYST, clocks.sysclk().raw());
+
+    let pins = BoardPins::new(dp.GPIOA, dp.GPIOB, dp.GPIOC, dp.GPIOD, dp.GPIOE);
+
+    let mut led_red = Led::active_low(pins.leds.red);
+    let mut led_yellow = Led::active_low(pins.leds.yellow);
+    let mut led_green = Led::active_low(pins.leds.green);
+    led_yellow.on();
+
+    let serial = Serial::new(
+        dp.USART1,
+        (pins.usart1.tx, pins.usart1.rx),
+        &clocks,
+        Config {
+            baud_rate: 115_200.bps(),
+            ..Default::default()
+        },
+    );
+    let mut usart = Usart::new(serial);
+    usart.println("HIL,BEGIN,OmniTiles");
+
+    let mut report = Report::new();
+
+    // ----- SPI: IMU register echo -----
+    let mut spi_bus = {
+        let spi_mode = Mode {
+            polarity: Polarity::IdleLow,
+            phase: Phase::CaptureOnFirstTransition,
+        };
+        let spi4_raw = Spi::new(dp.SPI4, (pins.spi4.sck, pins.spi4.miso, pins.spi4.mosi));
+        let spi4_enabled = spi4_raw.enable::<u8>(spi_mode, 100.kHz(), &clocks, &mut apb2);
+        SpiBus::new(spi4_enabled)
+    };
+    let mut cs1 = ChipSelect::active_low(pins.spi4.cs1);
+    cs1.deselect();
+    let mut cs2 = ChipSelect::active_low(pins.spi4.cs2);
+    cs2.deselect();
+
+    match Lsm6dsv16x::new(&mut spi_bus, &mut cs2) {
+        Ok(mut imu) => {
+            // Write a test pattern into CTRL1, read it back, then restore the 120 Hz ODR.
+            const PATTERN: u8 = 0x05;
+            let echoed = imu
+                .write_reg(&mut spi_bus, &mut cs2, lsm6dsv16x::reg::CTRL1, PATTERN)
+                .and_then(|_| imu.read_reg(&mut spi_bus, &mut cs2, lsm6dsv16x::reg::CTRL1));
+            imu.write_reg(&mut spi_bus, &mut cs2, lsm6dsv16x::reg::CTRL1, 0x06)
+                .ok();
+            match echoed {
+                Ok(v) => report.result(
+                    &mut usart,
+                    "spi_echo",
+                    v == PATTERN,
+                    format_args!("wrote=0x{:02X} read=0x{:02X}", PATTERN, v),
+                ),
+                Err(e) => report.result(&mut usart, "spi_echo", false, format_args!("{:?}", e)),
+            }
+        }
+        Err(e) => report.result(&mut usart, "spi_echo", false, format_args!("{:?}", e)),
+    }
+
+    // ----- I2C: ToF presence -----
+    let i2c_raw = BlockingI2c::i2c1(
+        dp.I2C1,
+        (pins.i2c1.scl, pins.i2c1.sda),
+        I2cMode::standard(100_000_u32.Hz()),
+        &clocks,
+        &mut apb1,
+        10_000, // data_timeout_us
+    );
+    match Vl53l0x::new(I2cBus::new(i2c_raw)) {
+        Ok(_) => report.result(&mut usart, "i2c_tof", true, format_args!("VL53L0X")),
+        Err(e) => report.result(&mut usart, "i2c_tof", false, format_args!("{:?}", e)),
+    }
+
+    // ----- CAN: silent loopback on both buses -----
+    // Silent loopback keeps the frames off the transceivers, so no other node is needed.
+    #[cfg(feature = "can")]
+    {
+        const LOOPBACK_BITRATE: u32 = 500_000;
+        let btr = btr_for_bitrate(clocks.pclk1().raw(), LOOPBACK_BITRATE).unwrap();
+        let can1 = hal::can::Can::new(dp.CAN1, &mut apb1, (pins.can.can1_tx, pins.can.can1_rx));
+        let mut can2 = hal::can::Can::new(dp.CAN2, &mut apb1, (pins.can.can2_tx, pins.can.can2_rx));
+        // CAN1 owns the filters, so it has to be up before CAN2.
+        let mut backbone = CanBus::new(can1, btr, true, true);
+        backbone.configure_accept_all_filters_for_dual_can(&mut can2);
+        let mut motor = CanBus::new(can2, btr, true, true);
+
+        let sent = CanFrame::standard(LOOPBACK_ID, &LOOPBACK_DATA).unwrap();
+        macro_rules! loopback_check {
+            ($bus:expr, $can:expr) => {{
+                let mut received = None;
+                if $can.send(&sent).is_ok() {
+                    for _ in 0..LOOPBACK_TIMEOUT_MS {
+                        received = $can.try_recv();
+                        if received.is_some() {
+                            break;
+                        }
+                        delay.delay_ms(1_u32);
+                    }
+                }
+                match received {
+                    Some(Ok(frame)) => report.result(
+                        &mut usart,
+                        "can_loopback",
+                        frame == sent,
+                        format_args!(
+                            "bus={} id={:?} data={:02X?}",
+                            $bus,
+                            frame.id(),
+                            frame.data()
+                        ),
+                    ),
+                    Some(Err(e)) => report.result(
+                        &mut usart,
+                        "can_loopback",
+                        false,
+                        format_args!("bus={} {:?}", $bus, e),
+                    ),
+                    None => report.result(
+                        &mut usart,
+                        "can_loopback",
+                        false,
+                        format_args!("bus={} no frame", $bus),
+                    ),
+                }
+            }};
+        }
+        loopback_check!("backbone", backbone);
+        loopback_check!("motor", motor);
+    }
+    #[cfg(not(feature = "can"))]
+    report.skip(&mut usart, "can_loopback", "built without `can`");
+
+    // ----- ADC: pot plausibility -----
+    let adc1 = RefCell::new(Adc::adc1(dp.ADC1));
+    let channels: [u8; 6] = [14, 9, 10, 11, 15, 13];
+    for ch in channels {
+        let v = adc1.borrow().read(ch);
+        let ok = v > ADC_RAIL_MARGIN && v < 4095 - ADC_RAIL_MARGIN;
+        report.result(
+            &mut usart,
+            "adc_plausible",
+            ok,
+            format_args!("ch={} raw={}", ch, v),
+        );
+    }
+
+    // ----- Motors: short pulses with pot feedback -----
+    let pwm_tim1 = dp
+        .TIM1
+        .pwm::<_, _, 1_000_000>(pins.m2.in2, 50.micros(), &clocks);
+    let pwm_tim3 = dp.TIM3.pwm::<_, _, 1_000_000>(
+        (pins.m1.in1, pins.m1.in2, pins.m2.in1),
+        50.micros(), // 20 kHz
+        &clocks,
+    );
+    let (m1_in1, m1_in2, m2_in1) = pwm_tim3.split();
+    let m2_in2 = pwm_tim1.split();
+
+    let mut m1 = ActuonixLinear::new(
+        Drv8873::new(NoChipSelect),
+        m1_in1,
+        m1_in2,
+        pins.m1.nsleep,
+        pins.m1.disable,
+        Adc::make_multi_reader(&adc1, [14, 9, 10, 11]),
+        [false, false, true, true],
+        150.0,
+        123.0,
+        20.0,
+        35.0,
+    );
+    let mut m2 = ActuonixLinear::new(
+        Drv8873::new(NoChipSelect),
+        m2_in1,
+        m2_in2,
+        pins.m2.nsleep,
+        pins.m2.disable,
+        Adc::make_multi_reader(&adc1, [15, 13]),
+        [false, false],
+        100.0,
+        100.0,
+        25.0,
+        15.0,
+    );
+    m1.enable_outputs();
+    m2.enable_outputs();
+    m1.brake();
+    m2.brake();
+
+    // Pulse toward mid-stroke so neither soft limit blocks the move, then pulse back.
+    macro_rules! pulse_check {
+        ($name:expr, $act:expr) => {{
+            let before = $act.position_raw().unwrap_or(0) as i32;
+            let dir = if before < 2048 { 1.0 } else { -1.0 };
+            $act.set_speed(dir * PULSE_SPEED);
+            delay.delay_ms(PULSE_MS);
+            $act.brake();
+            delay.delay_ms(50_u32);
+            let after = $act.position_raw().unwrap_or(0) as i32;
+            $act.set_speed(-dir * PULSE_SPEED);
+            delay.delay_ms(PULSE_MS);
+            $act.brake();
+            let delta = (after - before) * dir as i32;
+            report.result(
+                &mut usart,
+                $name,
+                delta >= PULSE_MIN_DELTA,
+                format_args!("before={} after={} delta={}", before, after, delta),
+            );
+        }};
+    }
+    pulse_check!("motor_pulse_m1", m1);
+    pulse_check!("motor_pulse_m2", m2);
+
+    // ----- Encoder: count a pulse of M1 -----
+    #[cfg(feature = "encoder")]
+    {
+        let encoder = Encoder::tim2(dp.TIM2);
+        let dir = if m1.position_raw().unwrap_or(0) < 2048 {
+            1.0
+        } else {
+            -1.0
+        };
+        m1.set_speed(dir * PULSE_SPEED);
+        delay.delay_ms(PULSE_MS);
+        m1.brake();
+        delay.delay_ms(50_u32);
+        let counts = encoder.position();
+        m1.set_speed(-dir * PULSE_SPEED);
+        delay.delay_ms(PULSE_MS);
+        m1.brake();
+        report.result(
+            &mut usart,
+            "encoder_count",
+            counts.abs() >= ENCODER_MIN_COUNTS,
+            format_args!("counts={}", counts),
+        );
+    }
+    #[cfg(not(feature = "encoder"))]
+    report.skip(&mut usart, "encoder_count", "built without `encoder`");
+
+    // ----- Closed loop: move to mid-stroke and wait for on-target -----
+    let mut m1_ctl = LinearController::new(Pid::new(0.0, 5.0, 0.0), 20.0, 115.0, 2.0);
+    let mut m2_ctl = LinearController::new(Pid::new(0.0, 5.0, 0.0), 25.0, 85.0, 0.45);
+    macro_rules! move_check {
+        ($name:expr, $ctl:expr, $act:expr) => {{
+            let target = Mm(($ctl.min_position_mm + $ctl.max_position_mm) / 2.0);
+            let result = $ctl.move_to_blocking(&mut $act, target, MOVE_TIMEOUT_MS, &mut delay);
+            $act.brake();
+            report.result(
+                &mut usart,
+                $name,
+                result.is_ok(),
+                format_args!(
+                    "target={:?} pos={:?} {:?}",
+                    target,
+                    $act.position_mm(),
+                    result
+                ),
+            );
+        }};
+    }
+    move_check!("move_m1", m1_ctl, m1);
+    move_check!("move_m2", m2_ctl, m2);
+
+    m1.disable_outputs();
+    m2.disable_outputs();
+
+    // ----- Summary -----
+    let verdict = if report.passed() { "PASS" } else { "FAIL" };
+    writeln!(
+        usart,
+        "HIL,SUMMARY,{},pass={} fail={} skip={}\r",
+        verdict, report.pass, report.fail, report.skip
+    )
+    .ok();
+
+    led_yellow.off();
+    if report.passed() {
+        led_green.on();
+    } else {
+        led_red.on();
+    }
+
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
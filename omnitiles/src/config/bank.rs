@@ -0,0 +1,177 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! A/B storage for the configuration record.
+//!
+//! The record is kept in two flash sectors, [`Bank::A`] (the original config sector) and
+//! [`Bank::B`]. Each copy carries a generation number in the four bytes before its CRC, and
+//! [`load`] returns the valid copy with the newest generation. A write never erases the copy the
+//! loader would otherwise fall back to, so losing power mid-write costs at most the change being
+//! written, never the calibration.
+//!
+//! Writes stay revertible until committed:
+//!
+//! - With one valid copy, [`store`] writes the other bank with the next generation and keeps the
+//!   old copy as the rollback point.
+//! - While both copies are valid a change is pending. Further stores rewrite the newer copy, so
+//!   the rollback point stays the last committed record.
+//! - [`commit`] erases the older copy, leaving the newer one as the only record.
+//! - [`revert`] erases the newer copy and hands back the older one.
+//!
+//! A pending change is still what [`load`] returns after a reset. Records written before the
+//! second bank existed have zeros in the generation bytes and load as generation 0.
+
+use super::{crc32, Config, ConfigError, CRC_OFFSET, RECORD_LEN};
+use crate::hw::flash::{self, Flash};
+
+/// Offset of the `u32` LE generation. [`Config::to_bytes`] leaves these bytes zero.
+const GENERATION_OFFSET: usize = CRC_OFFSET - 4;
+
+/// One of the two config sectors.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Bank {
+    A,
+    B,
+}
+
+impl Bank {
+    pub fn sector(self) -> u8 {
+        match self {
+            Bank::A => flash::CONFIG_SECTOR,
+            Bank::B => flash::CONFIG_B_SECTOR,
+        }
+    }
+
+    pub fn addr(self) -> u32 {
+        match self {
+            Bank::A => flash::CONFIG_ADDR,
+            Bank::B => flash::CONFIG_B_ADDR,
+        }
+    }
+
+    pub fn other(self) -> Bank {
+        match self {
+            Bank::A => Bank::B,
+            Bank::B => Bank::A,
+        }
+    }
+}
+
+/// A valid record and where it was read from.
+#[derive(Copy, Clone, Debug)]
+pub struct Record {
+    pub bank: Bank,
+    pub generation: u32,
+    pub config: Config,
+}
+
+/// Contents of both banks.
+#[derive(Copy, Clone, Debug)]
+pub struct Banks {
+    /// Newest valid record, or why neither bank holds one.
+    pub current: Result<Record, ConfigError>,
+    /// The other bank's record if it is valid too: the rollback point of a pending change.
+    pub previous: Option<Record>,
+}
+
+impl Banks {
+    /// True if the newest record can still be committed or reverted.
+    pub fn is_pending(&self) -> bool {
+        self.previous.is_some()
+    }
+}
+
+fn read(bank: Bank) -> Result<Record, ConfigError> {
+    let mut buf = [0u8; RECORD_LEN];
+    Flash::read(bank.addr(), &mut buf);
+    let config = Config::from_bytes(&buf)?;
+    let generation = u32::from_le_bytes(buf[GENERATION_OFFSET..CRC_OFFSET].try_into().unwrap());
+    Ok(Record {
+        bank,
+        generation,
+        config,
+    })
+}
+
+/// True if generation `a` was written after `b`, allowing for wrap-around.
+fn is_newer(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) > 0
+}
+
+/// Read and validate both banks.
+pub fn scan() -> Banks {
+    match (read(Bank::A), read(Bank::B)) {
+        (Ok(a), Ok(b)) => {
+            let (current, previous) = if is_newer(b.generation, a.generation) {
+                (b, a)
+            } else {
+                (a, b)
+            };
+            Banks {
+                current: Ok(current),
+                previous: Some(previous),
+            }
+        }
+        (Ok(r), Err(_)) | (Err(_), Ok(r)) => Banks {
+            current: Ok(r),
+            previous: None,
+        },
+        (Err(a), Err(b)) => {
+            // A corrupt bank outranks a blank one, so a damaged board still boots into safe mode.
+            let e = if b.is_corrupt() && !a.is_corrupt() {
+                b
+            } else {
+                a
+            };
+            Banks {
+                current: Err(e),
+                previous: None,
+            }
+        }
+    }
+}
+
+/// Newest valid configuration.
+pub fn load() -> Result<Config, ConfigError> {
+    scan().current.map(|r| r.config)
+}
+
+/// Write `config` without touching the rollback point.
+pub fn store(flash: &mut Flash, config: &Config) -> Result<(), ConfigError> {
+    let banks = scan();
+    let (bank, generation) = match (banks.current, banks.previous) {
+        (Ok(current), Some(_)) => (current.bank, current.generation),
+        (Ok(current), None) => (current.bank.other(), current.generation.wrapping_add(1)),
+        (Err(_), _) => (Bank::A, 0),
+    };
+
+    let mut buf = config.to_bytes();
+    buf[GENERATION_OFFSET..CRC_OFFSET].copy_from_slice(&generation.to_le_bytes());
+    let crc = crc32(&buf[..CRC_OFFSET]);
+    buf[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+
+    flash.erase_sector(bank.sector())?;
+    flash.program(bank.addr(), &buf)?;
+    Ok(())
+}
+
+/// Make the newest record the only one by erasing the rollback point. `Ok(false)` if no change
+/// was pending.
+pub fn commit(flash: &mut Flash) -> Result<bool, ConfigError> {
+    let Some(previous) = scan().previous else {
+        return Ok(false);
+    };
+    flash.erase_sector(previous.bank.sector())?;
+    Ok(true)
+}
+
+/// Drop the newest record and return the rollback point, which becomes the stored configuration
+/// again. `Ok(None)` if no change was pending.
+pub fn revert(flash: &mut Flash) -> Result<Option<Config>, ConfigError> {
+    let banks = scan();
+    let (Ok(current), Some(previous)) = (banks.current, banks.previous) else {
+        return Ok(None);
+    };
+    flash.erase_sector(current.bank.sector())?;
+    Ok(Some(previous.config))
+}
@@ -0,0 +1,458 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! # Persistent Tile Configuration
+//!
+//! Per-tile settings and calibration results that must survive a power cycle. The configuration
+//! is serialized into a fixed-size little-endian record guarded by a magic word, a layout version
+//! and a CRC-32, and stored in the two flash sectors reserved by [`crate::hw::flash`].
+//!
+//! ## Modules
+//!
+//! - [`bank`] - A/B record storage with commit and revert.
+//! - [`params`] - Tunable parameters (gains, limits, calibration) addressed by numeric ID.
+//! - [`provision`] - Factory provisioning flow (node ID, endpoint calibration, sanity checks).
+//! - [`stats`] - Wear-levelled log of per-motor usage counters.
+//! - [`warm`] - Lift height and fault log kept in backup SRAM across resets.
+
+pub mod bank;
+pub mod params;
+pub mod provision;
+pub mod stats;
+pub mod warm;
+
+use crate::control::cooling::FanMode;
+use crate::control::interlock::{self, INTERLOCK_RULE_LEN, INTERLOCK_SLOTS};
+use crate::control::schedule::{ScheduleEntry, SCHEDULE_ENTRY_LEN, SCHEDULE_SLOTS};
+use crate::control::MotionLimits;
+use crate::drivers::drv8873::ItripLevel;
+use crate::drivers::OutputRange;
+use crate::hw::flash::{self, Flash};
+use crate::sensors::motor_temp::ThermalLimits;
+
+/// Record magic ("OTCF").
+pub const MAGIC: u32 = 0x4F54_4346;
+/// Current record layout version. Bump whenever fields are added. Fields are only ever appended,
+/// so older records still load with defaults for the newer fields.
+pub const VERSION: u16 = 8;
+/// Serialized record size in bytes. Unused trailing bytes are zero, except for the bank
+/// generation just before the CRC (see [`bank`]).
+pub const RECORD_LEN: usize = 128;
+
+const _: () = assert!(
+    RECORD_LEN <= flash::CONFIG_SECTOR_LEN,
+    "config: record doesn't fit its flash sector"
+);
+
+/// Nominal FIT0185 encoder resolution at the output shaft.
+pub const NOMINAL_ENCODER_CPR: u32 = 2803;
+
+/// Horizontal distance from the tilt pivot to the edge of the top, for
+/// [`Envelope`](crate::control::envelope::Envelope).
+pub const TOP_HALF_SPAN_MM: f32 = 150.0;
+/// Height of the top's edge above the frame with the lift retracted and the top level.
+pub const FRAME_GAP_MM: f32 = 4.0;
+/// Clearance kept between the top's edge and the frame at any tilt.
+pub const FRAME_MARGIN_MM: f32 = 3.0;
+
+/// Bus voltage the drive duties are tuned at, for
+/// [`SupplyCompensation`](crate::sensors::supply::SupplyCompensation).
+pub const NOMINAL_SUPPLY_V: f32 = 24.5;
+
+/// Temperature the pot calibration is taken at, for
+/// [`PotTempCompensation`](crate::sensors::temperature::PotTempCompensation).
+pub const POT_REFERENCE_TEMP_C: f32 = 25.0;
+/// Pot drift per °C above [`POT_REFERENCE_TEMP_C`], the same for the P16 and T16: a fixed offset
+/// and a fraction of the reading. Together about 1 mm mid-stroke over the 25 °C a closed tile
+/// warms by.
+pub const POT_DRIFT_MM_PER_C: f32 = 0.03;
+pub const POT_DRIFT_GAIN_PER_C: f32 = 0.0001;
+
+/// Motor can temperatures for [`MotorTemperature`](crate::sensors::motor_temp::MotorTemperature).
+/// The Actuonix motors are rated to 85 °C ambient; their cans run some 15 °C hotter than that
+/// under continuous load, so derating starts well before and bottoms out at the rating.
+pub const MOTOR_THERMAL_LIMITS: ThermalLimits = ThermalLimits {
+    warn_c: 70.0,
+    limit_c: 100.0,
+};
+
+/// Fan control for [`Cooling`](crate::control::Cooling), on the hotter of the motor cans and the
+/// MCU die. The die runs some 10 °C above the enclosure air at idle.
+pub const FAN_MODE: FanMode = FanMode::Hysteresis {
+    on_c: 55.0,
+    off_c: 45.0,
+};
+
+/// Motion limits used in safe mode, when the stored configuration can't be trusted: slow, gentle
+/// and at under a third of full duty, so a miscalibrated axis can't do much harm before it is
+/// provisioned again.
+pub const SAFE_MODE_LIMITS: MotionLimits = MotionLimits {
+    max_velocity: 5.0,
+    max_accel: 20.0,
+    max_duty: 0.3,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// No record present (erased flash or foreign data).
+    BadMagic,
+    /// Record was written by a firmware with an unknown layout.
+    UnsupportedVersion(u16),
+    /// Record contents are corrupt.
+    BadCrc,
+    /// Flash erase/program failed.
+    Flash(flash::Error),
+}
+
+impl ConfigError {
+    /// True if a record was present but can't be used, as opposed to a board that was never
+    /// provisioned. The firmware boots into safe mode in that case.
+    pub fn is_corrupt(&self) -> bool {
+        matches!(
+            self,
+            ConfigError::BadCrc | ConfigError::UnsupportedVersion(_)
+        )
+    }
+}
+
+impl From<flash::Error> for ConfigError {
+    fn from(e: flash::Error) -> Self {
+        ConfigError::Flash(e)
+    }
+}
+
+/// Raw potentiometer readings at the two mechanical ends of an actuator.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AxisCalibration {
+    pub retracted_raw: u16,
+    pub extended_raw: u16,
+}
+
+impl AxisCalibration {
+    /// True once both endpoints have been measured and span a usable range.
+    pub fn is_valid(&self) -> bool {
+        self.extended_raw.abs_diff(self.retracted_raw) > 100
+    }
+}
+
+/// Wiring polarity flags, so units with swapped leads don't need code changes.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Polarity {
+    /// M1 extends when driven "retract" at the H-bridge.
+    pub m1_extend_inverted: bool,
+    /// M2 extends when driven "retract" at the H-bridge.
+    pub m2_extend_inverted: bool,
+    /// FIT0185 forward/reverse are swapped.
+    pub motor_inverted: bool,
+    /// Encoder counts down when the FIT0185 turns forward.
+    pub encoder_inverted: bool,
+}
+
+impl Polarity {
+    /// Pack into the bit layout used by the config record and the protocol.
+    pub fn to_bits(self) -> u8 {
+        (self.m1_extend_inverted as u8)
+            | (self.m2_extend_inverted as u8) << 1
+            | (self.motor_inverted as u8) << 2
+            | (self.encoder_inverted as u8) << 3
+    }
+
+    /// Unpack from [`to_bits`](Self::to_bits).
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            m1_extend_inverted: bits & (1 << 0) != 0,
+            m2_extend_inverted: bits & (1 << 1) != 0,
+            motor_inverted: bits & (1 << 2) != 0,
+            encoder_inverted: bits & (1 << 3) != 0,
+        }
+    }
+}
+
+/// Persistent per-tile configuration.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Config {
+    /// Node ID on the shared bus / fleet. 0 means unassigned.
+    pub node_id: u8,
+    /// Set once the factory provisioning flow has completed successfully.
+    pub provisioned: bool,
+    /// Endpoint calibration for M1 (fused pot reading).
+    pub m1_cal: AxisCalibration,
+    /// Endpoint calibration for M2 (fused pot reading).
+    pub m2_cal: AxisCalibration,
+    /// Effective encoder counts per output revolution, as measured by
+    /// [`Fit0185::measure_counts_per_rev_limits`](crate::drivers::Fit0185::measure_counts_per_rev_limits)
+    /// or the index-pulse variant. Defaults to [`NOMINAL_ENCODER_CPR`].
+    pub encoder_cpr: u32,
+    /// Wiring polarity flags (since version 2).
+    pub polarity: Polarity,
+    /// M1 motion limits in mm/s, mm/s² and duty (since version 3).
+    pub m1_limits: MotionLimits,
+    /// M2 motion limits in mm/s, mm/s² and duty (since version 3).
+    pub m2_limits: MotionLimits,
+    /// Unattended motion schedule (since version 4).
+    pub schedule: [ScheduleEntry; SCHEDULE_SLOTS],
+    /// M1 DRV8873 current regulation threshold, `None` = regulation off (since version 5).
+    pub m1_itrip: Option<ItripLevel>,
+    /// M2 DRV8873 current regulation threshold, `None` = regulation off (since version 5).
+    pub m2_itrip: Option<ItripLevel>,
+    /// M1 drive duty range (since version 6).
+    pub m1_output: OutputRange,
+    /// M2 drive duty range (since version 6).
+    pub m2_output: OutputRange,
+    /// Cross-axis motion interlocks (since version 7).
+    pub interlocks: [interlock::Rule; INTERLOCK_SLOTS],
+    /// Start demo mode at boot (since version 8).
+    pub demo_at_boot: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            node_id: 0,
+            provisioned: false,
+            m1_cal: AxisCalibration::default(),
+            m2_cal: AxisCalibration::default(),
+            encoder_cpr: NOMINAL_ENCODER_CPR,
+            polarity: Polarity::default(),
+            m1_limits: MotionLimits::UNLIMITED,
+            m2_limits: MotionLimits::UNLIMITED,
+            schedule: [ScheduleEntry::default(); SCHEDULE_SLOTS],
+            // The DRV8873 powers up regulating at its highest level.
+            m1_itrip: Some(ItripLevel::A7_0),
+            m2_itrip: Some(ItripLevel::A7_0),
+            m1_output: OutputRange::FULL,
+            m2_output: OutputRange::FULL,
+            interlocks: [interlock::Rule::NONE; INTERLOCK_SLOTS],
+            demo_at_boot: false,
+        }
+    }
+}
+
+/// Little-endian cursor used to serialize a record.
+struct Writer<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> Writer<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn bytes(&mut self, b: &[u8]) {
+        self.buf[self.pos..self.pos + b.len()].copy_from_slice(b);
+        self.pos += b.len();
+    }
+
+    fn u8(&mut self, v: u8) {
+        self.bytes(&[v]);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn f32(&mut self, v: f32) {
+        self.bytes(&v.to_le_bytes());
+    }
+
+    fn limits(&mut self, l: &MotionLimits) {
+        self.f32(l.max_velocity);
+        self.f32(l.max_accel);
+        self.f32(l.max_duty);
+    }
+
+    fn output(&mut self, o: &OutputRange) {
+        self.f32(o.min);
+        self.f32(o.max);
+    }
+}
+
+/// Little-endian cursor used to deserialize a record.
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let mut out = [0u8; N];
+        out.copy_from_slice(&self.buf[self.pos..self.pos + N]);
+        self.pos += N;
+        out
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.take::<1>()[0]
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.take())
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take())
+    }
+
+    fn f32(&mut self) -> f32 {
+        f32::from_le_bytes(self.take())
+    }
+
+    fn limits(&mut self) -> MotionLimits {
+        MotionLimits {
+            max_velocity: self.f32(),
+            max_accel: self.f32(),
+            max_duty: self.f32(),
+        }
+    }
+
+    fn output(&mut self) -> OutputRange {
+        OutputRange {
+            min: self.f32(),
+            max: self.f32(),
+        }
+    }
+}
+
+/// Offset of the CRC-32 that covers `[0, RECORD_LEN - 4)`.
+const CRC_OFFSET: usize = RECORD_LEN - 4;
+
+impl Config {
+    /// Serialize into a fixed-size record, including header and CRC.
+    pub fn to_bytes(&self) -> [u8; RECORD_LEN] {
+        let mut buf = [0u8; RECORD_LEN];
+        {
+            let mut w = Writer::new(&mut buf);
+            w.u32(MAGIC);
+            w.u16(VERSION);
+            w.u8(self.node_id);
+            w.u8(self.provisioned as u8);
+            w.u16(self.m1_cal.retracted_raw);
+            w.u16(self.m1_cal.extended_raw);
+            w.u16(self.m2_cal.retracted_raw);
+            w.u16(self.m2_cal.extended_raw);
+            w.u32(self.encoder_cpr);
+            w.u8(self.polarity.to_bits());
+            w.limits(&self.m1_limits);
+            w.limits(&self.m2_limits);
+            for entry in &self.schedule {
+                w.bytes(&entry.to_bytes());
+            }
+            w.u8(itrip_to_byte(self.m1_itrip));
+            w.u8(itrip_to_byte(self.m2_itrip));
+            w.output(&self.m1_output);
+            w.output(&self.m2_output);
+            for rule in &self.interlocks {
+                w.bytes(&rule.to_bytes());
+            }
+            w.u8(self.demo_at_boot as u8);
+        }
+        let crc = crc32(&buf[..CRC_OFFSET]);
+        buf[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+        buf
+    }
+
+    /// Parse and validate a record produced by [`to_bytes`](Self::to_bytes). Stored parameters
+    /// outside their registry range come back as defaults, see [`params::sanitize`].
+    pub fn from_bytes(buf: &[u8; RECORD_LEN]) -> Result<Self, ConfigError> {
+        let mut r = Reader::new(buf);
+        if r.u32() != MAGIC {
+            return Err(ConfigError::BadMagic);
+        }
+        let version = r.u16();
+        if version == 0 || version > VERSION {
+            return Err(ConfigError::UnsupportedVersion(version));
+        }
+        let stored_crc = u32::from_le_bytes(buf[CRC_OFFSET..].try_into().unwrap());
+        if crc32(&buf[..CRC_OFFSET]) != stored_crc {
+            return Err(ConfigError::BadCrc);
+        }
+
+        let mut cfg = Self {
+            node_id: r.u8(),
+            provisioned: r.u8() != 0,
+            m1_cal: AxisCalibration {
+                retracted_raw: r.u16(),
+                extended_raw: r.u16(),
+            },
+            m2_cal: AxisCalibration {
+                retracted_raw: r.u16(),
+                extended_raw: r.u16(),
+            },
+            encoder_cpr: r.u32(),
+            ..Self::default()
+        };
+        if version >= 2 {
+            cfg.polarity = Polarity::from_bits(r.u8());
+        }
+        if version >= 3 {
+            cfg.m1_limits = r.limits();
+            cfg.m2_limits = r.limits();
+        }
+        if version >= 4 {
+            for entry in cfg.schedule.iter_mut() {
+                *entry = ScheduleEntry::from_bytes(r.take::<SCHEDULE_ENTRY_LEN>());
+            }
+        }
+        if version >= 5 {
+            cfg.m1_itrip = itrip_from_byte(r.u8());
+            cfg.m2_itrip = itrip_from_byte(r.u8());
+        }
+        if version >= 6 {
+            cfg.m1_output = r.output();
+            cfg.m2_output = r.output();
+        }
+        if version >= 7 {
+            for rule in cfg.interlocks.iter_mut() {
+                *rule = interlock::Rule::from_bytes(r.take::<INTERLOCK_RULE_LEN>());
+            }
+        }
+        if version >= 8 {
+            cfg.demo_at_boot = r.u8() != 0;
+        }
+        params::sanitize(&mut cfg);
+        Ok(cfg)
+    }
+
+    /// Load the newest valid configuration record from flash.
+    pub fn load() -> Result<Self, ConfigError> {
+        bank::load()
+    }
+
+    /// Write this configuration as a pending change, keeping the last committed record for
+    /// [`bank::revert`].
+    pub fn store(&self, flash: &mut Flash) -> Result<(), ConfigError> {
+        bank::store(flash, self)
+    }
+}
+
+/// Stored and wire encoding of an ITRIP setting: the `ITRIP_LVL` bits, or 0xFF for off.
+pub fn itrip_to_byte(level: Option<ItripLevel>) -> u8 {
+    level.map_or(0xFF, |l| l as u8)
+}
+
+/// Inverse of [`itrip_to_byte`]. Any value above 3 means off.
+pub fn itrip_from_byte(b: u8) -> Option<ItripLevel> {
+    (b <= 3).then(|| ItripLevel::from_bits(b))
+}
+
+/// CRC-32 (IEEE 802.3, reflected, polynomial 0xEDB88320).
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
@@ -0,0 +1,398 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Registry of tunable parameters addressed by numeric ID.
+//!
+//! `MSG_GET_PARAM` and `MSG_SET_PARAM` read and write any entry as an `f32`, so a new tunable
+//! needs a row here instead of its own message type. IDs `0x0n` are tile-wide; `0x1n` are M1 and
+//! `0x2n` M2, with the same low nibble selecting the same [`AxisParam`] on both axes.
+//!
+//! [`PARAMS`] describes every entry: its value type, accepted range, default and whether it is
+//! stored in the [`Config`] record. Protocol handlers, logs and persistence all go through that
+//! table, so adding a tunable means one row plus its accessor in [`Params`]. Stored parameters
+//! should be written to flash after a successful set (see [`Param::is_persistent`]); the others,
+//! such as PID gains, last until the next reset. Calibration endpoints and output ranges only live
+//! in the configuration, so the caller applies them to the actuators after a set, as it does for
+//! the endpoints provisioning measures.
+
+use super::{Config, NOMINAL_ENCODER_CPR};
+use crate::control::LinearController;
+use crate::drivers::OutputRange;
+use crate::protocol::telemetry::FieldKind;
+use crate::protocol::wire::ParamRecord;
+
+/// Per-axis parameter, the low nibble of its ID.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AxisParam {
+    Kp = 0,
+    Ki = 1,
+    Kd = 2,
+    /// Profile velocity limit in mm/s, 0 = unlimited.
+    MaxVelocity = 3,
+    /// Profile acceleration limit in mm/s², 0 = unlimited.
+    MaxAccel = 4,
+    /// Output duty clamp, 0.0..=1.0.
+    MaxDuty = 5,
+    /// On-target tolerance in mm. Raises the off-target tolerance to match if needed.
+    OnTargetTolerance = 6,
+    /// Raw pot reading at the retracted end.
+    RetractedRaw = 7,
+    /// Raw pot reading at the extended end.
+    ExtendedRaw = 8,
+    /// Drive duty for the smallest non-zero output, see [`OutputRange`].
+    MinOutput = 9,
+    /// Drive duty at full output, see [`OutputRange`].
+    MaxOutput = 10,
+    /// Setpoint weight of the PID's P term, 0.0..=1.0 (see [`Pid`](crate::control::Pid)).
+    PWeight = 11,
+    /// Setpoint weight of the PID's D term, 0.0..=1.0.
+    DWeight = 12,
+    /// Output slew limit in duty per second, 0 = unlimited.
+    MaxOutputRate = 13,
+}
+
+/// A parameter in the registry.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Param {
+    /// FIT0185 encoder counts per output revolution (0x01).
+    EncoderCpr,
+    /// A per-axis parameter of M1 (`axis` 1) or M2 (`axis` 2).
+    Axis { axis: u8, param: AxisParam },
+}
+
+impl Param {
+    /// Decode a wire ID, `None` for IDs not in the registry.
+    pub fn from_id(id: u8) -> Option<Self> {
+        ParamDesc::find(id).map(|d| d.param)
+    }
+
+    pub const fn id(self) -> u8 {
+        match self {
+            Param::EncoderCpr => 0x01,
+            Param::Axis { axis, param } => axis << 4 | param as u8,
+        }
+    }
+
+    /// Table entry for this parameter.
+    pub fn desc(self) -> &'static ParamDesc {
+        // Every `Param` has a row; the table is checked at compile time.
+        ParamDesc::find(self.id()).unwrap()
+    }
+
+    /// True if the value lives in the [`Config`] record and should be stored after a set.
+    pub fn is_persistent(self) -> bool {
+        self.desc().storage == Storage::Config
+    }
+}
+
+/// How a value is held; integers are rounded on set.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParamKind {
+    F32,
+    U16,
+    U32,
+}
+
+/// Where a value lives.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Storage {
+    /// In the [`Config`] record, persisted to flash.
+    Config,
+    /// In RAM only; back to the default on reset.
+    Runtime,
+}
+
+/// Metadata for one parameter.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParamDesc {
+    pub param: Param,
+    /// Short name for logs, e.g. `"m1.kp"`.
+    pub name: &'static str,
+    pub kind: ParamKind,
+    /// Inclusive range accepted by [`Params::set`].
+    pub min: f32,
+    pub max: f32,
+    /// Value after a reset (runtime parameters) or in a fresh configuration (stored ones).
+    pub default: f32,
+    pub storage: Storage,
+}
+
+impl ParamDesc {
+    /// Look up a wire ID.
+    pub fn find(id: u8) -> Option<&'static ParamDesc> {
+        PARAMS.iter().find(|d| d.param.id() == id)
+    }
+
+    /// Row as described by `MSG_WIRE_DESCRIPTOR`.
+    pub fn record(&self) -> ParamRecord {
+        ParamRecord {
+            id: self.param.id(),
+            kind: match self.kind {
+                ParamKind::F32 => FieldKind::F32,
+                ParamKind::U16 => FieldKind::U16,
+                ParamKind::U32 => FieldKind::U32,
+            },
+            stored: self.storage == Storage::Config,
+            min: self.min,
+            max: self.max,
+            default: self.default,
+            name: self.name,
+        }
+    }
+}
+
+const fn row(
+    param: Param,
+    name: &'static str,
+    kind: ParamKind,
+    (min, max): (f32, f32),
+    default: f32,
+    storage: Storage,
+) -> ParamDesc {
+    ParamDesc {
+        param,
+        name,
+        kind,
+        min,
+        max,
+        default,
+        storage,
+    }
+}
+
+const fn axis(axis: u8, param: AxisParam) -> Param {
+    Param::Axis { axis, param }
+}
+
+const GAIN: (f32, f32) = (0.0, 1000.0);
+const VELOCITY: (f32, f32) = (0.0, 1000.0);
+const ACCEL: (f32, f32) = (0.0, 10_000.0);
+const DUTY: (f32, f32) = (0.0, 1.0);
+const WEIGHT: (f32, f32) = (0.0, 1.0);
+const SLEW: (f32, f32) = (0.0, 1000.0);
+const TOLERANCE: (f32, f32) = (0.01, 50.0);
+const RAW: (f32, f32) = (0.0, u16::MAX as f32);
+const CPR: (f32, f32) = (1.0, 1_000_000.0);
+
+/// Every parameter. Runtime defaults match the controller setup in `main.rs`; stored defaults
+/// match [`Config::default`].
+#[rustfmt::skip]
+pub const PARAMS: &[ParamDesc] = {
+    use AxisParam::*;
+    use ParamKind::*;
+    use Storage::{Config as Stored, Runtime};
+    &[
+        row(Param::EncoderCpr, "encoder_cpr", U32, CPR, NOMINAL_ENCODER_CPR as f32, Stored),
+        row(axis(1, Kp), "m1.kp", F32, GAIN, 0.0, Runtime),
+        row(axis(1, Ki), "m1.ki", F32, GAIN, 5.0, Runtime),
+        row(axis(1, Kd), "m1.kd", F32, GAIN, 0.0, Runtime),
+        row(axis(1, MaxVelocity), "m1.max_velocity", F32, VELOCITY, 0.0, Stored),
+        row(axis(1, MaxAccel), "m1.max_accel", F32, ACCEL, 0.0, Stored),
+        row(axis(1, MaxDuty), "m1.max_duty", F32, DUTY, 1.0, Stored),
+        row(axis(1, OnTargetTolerance), "m1.tolerance", F32, TOLERANCE, 2.0, Runtime),
+        row(axis(1, RetractedRaw), "m1.retracted_raw", U16, RAW, 0.0, Stored),
+        row(axis(1, ExtendedRaw), "m1.extended_raw", U16, RAW, 0.0, Stored),
+        row(axis(1, MinOutput), "m1.min_output", F32, DUTY, 0.0, Stored),
+        row(axis(1, MaxOutput), "m1.max_output", F32, DUTY, 1.0, Stored),
+        row(axis(1, PWeight), "m1.p_weight", F32, WEIGHT, 1.0, Runtime),
+        row(axis(1, DWeight), "m1.d_weight", F32, WEIGHT, 0.0, Runtime),
+        row(axis(1, MaxOutputRate), "m1.max_output_rate", F32, SLEW, 0.0, Runtime),
+        row(axis(2, Kp), "m2.kp", F32, GAIN, 0.0, Runtime),
+        row(axis(2, Ki), "m2.ki", F32, GAIN, 5.0, Runtime),
+        row(axis(2, Kd), "m2.kd", F32, GAIN, 0.0, Runtime),
+        row(axis(2, MaxVelocity), "m2.max_velocity", F32, VELOCITY, 0.0, Stored),
+        row(axis(2, MaxAccel), "m2.max_accel", F32, ACCEL, 0.0, Stored),
+        row(axis(2, MaxDuty), "m2.max_duty", F32, DUTY, 1.0, Stored),
+        row(axis(2, OnTargetTolerance), "m2.tolerance", F32, TOLERANCE, 0.45, Runtime),
+        row(axis(2, RetractedRaw), "m2.retracted_raw", U16, RAW, 0.0, Stored),
+        row(axis(2, ExtendedRaw), "m2.extended_raw", U16, RAW, 0.0, Stored),
+        row(axis(2, MinOutput), "m2.min_output", F32, DUTY, 0.0, Stored),
+        row(axis(2, MaxOutput), "m2.max_output", F32, DUTY, 1.0, Stored),
+        row(axis(2, PWeight), "m2.p_weight", F32, WEIGHT, 1.0, Runtime),
+        row(axis(2, DWeight), "m2.d_weight", F32, WEIGHT, 0.0, Runtime),
+        row(axis(2, MaxOutputRate), "m2.max_output_rate", F32, SLEW, 0.0, Runtime),
+    ]
+};
+
+/// True if `PARAMS` has exactly one row for every `Param` value.
+const fn table_is_complete() -> bool {
+    let mut id = 0u8;
+    loop {
+        let expected = match id >> 4 {
+            0 => id == 0x01,
+            1 | 2 => (id & 0x0F) <= AxisParam::MaxOutputRate as u8,
+            _ => false,
+        };
+        let mut rows = 0;
+        let mut i = 0;
+        while i < PARAMS.len() {
+            if PARAMS[i].param.id() == id {
+                rows += 1;
+            }
+            i += 1;
+        }
+        if rows != expected as usize {
+            return false;
+        }
+        if id == u8::MAX {
+            return true;
+        }
+        id += 1;
+    }
+}
+
+const _: () = assert!(
+    table_is_complete(),
+    "params: PARAMS must have one row per parameter"
+);
+
+/// Why a parameter could not be set.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ParamError {
+    /// The ID is not in the registry.
+    UnknownId = 1,
+    /// The value is not finite or outside the parameter's [`ParamDesc`] range.
+    OutOfRange = 2,
+}
+
+/// Value of a stored parameter in `config`, or `None` for a runtime one.
+pub fn stored(config: &Config, param: Param) -> Option<f32> {
+    let Param::Axis { axis, param } = param else {
+        return Some(config.encoder_cpr as f32);
+    };
+    let (cal, limits, output) = match axis {
+        1 => (&config.m1_cal, &config.m1_limits, &config.m1_output),
+        _ => (&config.m2_cal, &config.m2_limits, &config.m2_output),
+    };
+    Some(match param {
+        AxisParam::MaxVelocity => limits.max_velocity,
+        AxisParam::MaxAccel => limits.max_accel,
+        AxisParam::MaxDuty => limits.max_duty,
+        AxisParam::RetractedRaw => cal.retracted_raw as f32,
+        AxisParam::ExtendedRaw => cal.extended_raw as f32,
+        AxisParam::MinOutput => output.min,
+        AxisParam::MaxOutput => output.max,
+        _ => return None,
+    })
+}
+
+/// Write a stored parameter into `config`, rounding it for integer kinds. Returns `false`, and
+/// leaves `config` alone, for a runtime parameter.
+fn set_stored(config: &mut Config, param: Param, value: f32) -> bool {
+    // Integer ranges are non-negative, so adding 0.5 rounds to nearest.
+    let int = (value + 0.5) as u32;
+    let Param::Axis { axis, param } = param else {
+        config.encoder_cpr = int;
+        return true;
+    };
+    let (cal, limits, output) = match axis {
+        1 => (
+            &mut config.m1_cal,
+            &mut config.m1_limits,
+            &mut config.m1_output,
+        ),
+        _ => (
+            &mut config.m2_cal,
+            &mut config.m2_limits,
+            &mut config.m2_output,
+        ),
+    };
+    match param {
+        AxisParam::MaxVelocity => limits.max_velocity = value,
+        AxisParam::MaxAccel => limits.max_accel = value,
+        AxisParam::MaxDuty => limits.max_duty = value,
+        AxisParam::RetractedRaw => cal.retracted_raw = int as u16,
+        AxisParam::ExtendedRaw => cal.extended_raw = int as u16,
+        AxisParam::MinOutput => output.min = value,
+        AxisParam::MaxOutput => output.max = value,
+        _ => return false,
+    }
+    true
+}
+
+/// Reset every stored value that is outside its [`ParamDesc`] range, or not finite, to the
+/// table default. [`Config::from_bytes`] runs this on each record it loads, so flash holds the
+/// same bounds the protocol enforces. Returns how many values were reset.
+pub fn sanitize(config: &mut Config) -> usize {
+    let mut reset = 0;
+    for desc in PARAMS.iter().filter(|d| d.storage == Storage::Config) {
+        let value = stored(config, desc.param).unwrap_or(desc.default);
+        if !(desc.min..=desc.max).contains(&value) {
+            set_stored(config, desc.param, desc.default);
+            reset += 1;
+        }
+    }
+    reset
+}
+
+/// The state the registry reads and writes.
+pub struct Params<'a> {
+    pub config: &'a mut Config,
+    pub m1: &'a mut LinearController,
+    pub m2: &'a mut LinearController,
+}
+
+impl Params<'_> {
+    /// Current value of `param`. Stored parameters are read from the configuration.
+    pub fn get(&self, param: Param) -> f32 {
+        let Param::Axis { axis, param: p } = param else {
+            return self.config.encoder_cpr as f32;
+        };
+        let ctl = match axis {
+            1 => &*self.m1,
+            _ => &*self.m2,
+        };
+        let (kp, ki, kd) = ctl.pid.gains();
+        let (p_weight, d_weight) = ctl.pid.setpoint_weights();
+        match p {
+            AxisParam::Kp => kp,
+            AxisParam::Ki => ki,
+            AxisParam::Kd => kd,
+            AxisParam::OnTargetTolerance => ctl.on_target_tolerance_mm,
+            AxisParam::PWeight => p_weight,
+            AxisParam::DWeight => d_weight,
+            AxisParam::MaxOutputRate => ctl.max_output_rate,
+            _ => stored(self.config, param).unwrap_or(f32::NAN),
+        }
+    }
+
+    /// Check `value` against the parameter's [`ParamDesc`] range, round it for integer kinds
+    /// and apply it. A limit is applied to both the controller and the configuration; storing the
+    /// configuration, and passing output ranges on to the drivers, is left to the caller.
+    pub fn set(&mut self, param: Param, value: f32) -> Result<(), ParamError> {
+        let desc = param.desc();
+        if !(desc.min..=desc.max).contains(&value) {
+            return Err(ParamError::OutOfRange);
+        }
+        set_stored(self.config, param, value);
+        let Param::Axis { axis, param } = param else {
+            return Ok(());
+        };
+        let ctl = match axis {
+            1 => &mut *self.m1,
+            _ => &mut *self.m2,
+        };
+        let (kp, ki, kd) = ctl.pid.gains();
+        let (p_weight, d_weight) = ctl.pid.setpoint_weights();
+        match param {
+            AxisParam::Kp => ctl.pid.set_gains(value, ki, kd),
+            AxisParam::Ki => ctl.pid.set_gains(kp, value, kd),
+            AxisParam::Kd => ctl.pid.set_gains(kp, ki, value),
+            AxisParam::MaxVelocity => ctl.limits.max_velocity = value,
+            AxisParam::MaxAccel => ctl.limits.max_accel = value,
+            AxisParam::MaxDuty => ctl.limits.max_duty = value,
+            AxisParam::OnTargetTolerance => {
+                ctl.on_target_tolerance_mm = value;
+                ctl.off_target_tolerance_mm = ctl.off_target_tolerance_mm.max(value);
+            }
+            AxisParam::PWeight => ctl.pid.set_setpoint_weights(value, d_weight),
+            AxisParam::DWeight => ctl.pid.set_setpoint_weights(p_weight, value),
+            AxisParam::MaxOutputRate => ctl.max_output_rate = value,
+            // Calibration and output range only live in the configuration.
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,389 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Factory provisioning flow.
+//!
+//! Walks a freshly assembled tile through the steps needed before it can join a fleet:
+//!
+//! 1. Assign the node ID.
+//! 2. Run endpoint calibration on each actuator (drive to both mechanical stops and record the
+//!    fused pot reading at each end).
+//! 3. Measure the encoder CPR and sanity-check it against the nominal FIT0185 value. Tiles
+//!    without a FIT0185 skip this step.
+//! 4. Store the results to flash and mark the tile as provisioned.
+//!
+//! The flow is only entered on explicit request (a protocol command, or the service button held
+//! through boot) while the tile is otherwise idle. It blocks until done, feeding the watchdog
+//! between samples of the end stop seeks, and reports its progress as [`Operation::Calibration`]
+//! with the [`Step`] as the phase. Once it succeeds the caller hands the new endpoints to the
+//! actuators with [`ActuonixLinear::set_calibration`], as it does with the stored ones at boot.
+
+use core::fmt::Write;
+use core::task::Poll;
+
+use cortex_m::delay::Delay;
+use stm32f7xx_hal::prelude::*;
+
+use crate::config::{crc32, AxisCalibration, Config, ConfigError, NOMINAL_ENCODER_CPR};
+use crate::control::AlphaBeta;
+use crate::drivers::fit0185::CalibrationError;
+use crate::drivers::{ActuonixLinear, Fit0185};
+use crate::hw::{iwdg, spi::CsControl, Flash};
+use crate::protocol::progress::{self, Operation, Progress, Reporter};
+
+/// Drive speed used while seeking the mechanical end stops.
+const SEEK_SPEED: f32 = 0.5;
+/// Sampling period while seeking.
+const SEEK_SAMPLE_MS: u32 = 50;
+/// Give up on an end stop after this long.
+const SEEK_TIMEOUT_MS: u32 = 10_000;
+/// Give up on each end stop of the encoder CPR measurement after this long.
+const CPR_TIMEOUT_MS: u32 = 30_000;
+/// Filtered speeds at or below this many raw counts per second count as "not moving".
+const STALL_COUNTS_PER_S: f32 = 60.0;
+/// Consecutive stalled samples required to declare an end stop.
+const STALL_SAMPLES: u32 = 4;
+/// Stalls aren't counted this long after the drive starts, while the velocity estimate catches up
+/// with the axis.
+const STALL_GRACE_MS: u32 = 250;
+/// Observer gain for the seek velocity; see [`AlphaBeta::critically_damped`].
+const SEEK_ALPHA: f32 = 0.6;
+/// Allowed deviation of the encoder CPR from nominal, in percent.
+const CPR_TOLERANCE_PCT: u32 = 10;
+/// Base address of the 96-bit unique device ID (RM0410 §45.1).
+const UID_ADDR: u32 = 0x1FF0_F420;
+
+/// Provisioning steps, in order. The discriminant is the progress phase.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Step {
+    AssignNodeId = 0,
+    CalibrateM1 = 1,
+    CalibrateM2 = 2,
+    CheckEncoder = 3,
+    Store = 4,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ProvisionError {
+    /// Node IDs 0 and 0xFF are reserved.
+    InvalidNodeId(u8),
+    /// An actuator did not reach a stable end stop, or the measured span is too small.
+    Calibration(Step),
+    /// Encoder CPR is outside the plausible range around the nominal value.
+    EncoderCpr(u32),
+    /// The encoder CPR could not be measured.
+    Encoder(CalibrationError),
+    /// Writing the configuration failed.
+    Config(ConfigError),
+}
+
+/// Minimal axis interface needed for endpoint calibration.
+pub trait CalibrationAxis {
+    /// Drive open-loop, ignoring soft limits. `speed` is in -1.0..1.0.
+    fn drive(&mut self, speed: f32);
+    /// Stop the axis.
+    fn stop(&mut self);
+    /// Fused raw position reading, or `None` without feedback.
+    fn read_raw(&mut self) -> Option<u16>;
+}
+
+/// Encoder whose resolution is measured during provisioning.
+pub trait CprAxis {
+    /// Measure the effective encoder counts per output revolution, driving the motor as needed.
+    fn measure_cpr(&mut self, delay: &mut Delay) -> Result<u32, CalibrationError>;
+}
+
+impl<
+        CS: CsControl,
+        const IN1_P: char,
+        const IN1_N: u8,
+        const IN2_P: char,
+        const IN2_N: u8,
+        const SLP_P: char,
+        const SLP_N: u8,
+        const DIS_P: char,
+        const DIS_N: u8,
+    > CprAxis for Fit0185<CS, IN1_P, IN1_N, IN2_P, IN2_N, SLP_P, SLP_N, DIS_P, DIS_N>
+{
+    /// Limit-to-limit measurement over [`Fit0185::travel_revs`].
+    fn measure_cpr(&mut self, delay: &mut Delay) -> Result<u32, CalibrationError> {
+        let travel_revs = self.travel_revs();
+        self.measure_counts_per_rev_limits(travel_revs, delay, CPR_TIMEOUT_MS)
+    }
+}
+
+impl<
+        CS: CsControl,
+        const SLP_P: char,
+        const SLP_N: u8,
+        const DIS_P: char,
+        const DIS_N: u8,
+        Pwm1,
+        Pwm2,
+        ReadPos,
+        const N: usize,
+    > CalibrationAxis for ActuonixLinear<CS, SLP_P, SLP_N, DIS_P, DIS_N, Pwm1, Pwm2, ReadPos, N>
+where
+    Pwm1: _embedded_hal_PwmPin<Duty = u16>,
+    Pwm2: _embedded_hal_PwmPin<Duty = u16>,
+    ReadPos: FnMut() -> [u16; N],
+{
+    fn drive(&mut self, speed: f32) {
+        self.set_speed_unchecked(speed);
+    }
+
+    fn stop(&mut self) {
+        self.brake();
+    }
+
+    fn read_raw(&mut self) -> Option<u16> {
+        self.position_raw()
+    }
+}
+
+/// Drive in the direction of `speed` until the position stops changing, then brake and return
+/// the resting reading. Returns `None` on timeout or missing feedback.
+///
+/// Reports the seek as `lo..hi` percent of `phase`, by time against the timeout. Blocking,
+/// feeding the watchdog between samples; see [`EndpointSeek`] for the stepped form.
+pub fn find_endpoint<A: CalibrationAxis, F: FnMut(Progress)>(
+    axis: &mut A,
+    speed: f32,
+    delay: &mut Delay,
+    progress: &mut Reporter<F>,
+    span: (u8, u8, u8),
+) -> Option<u16> {
+    let mut seek = EndpointSeek::new(speed, span);
+    iwdg::block_on(delay, SEEK_SAMPLE_MS, |dt| {
+        seek.run_step(axis, progress, dt)
+    })
+}
+
+/// [`find_endpoint`] as a state machine.
+pub struct EndpointSeek {
+    speed: f32,
+    span: (u8, u8, u8),
+    /// Tracks the raw reading; initialized by the first call.
+    observer: AlphaBeta,
+    stalled: u32,
+    elapsed: u32,
+}
+
+impl EndpointSeek {
+    pub fn new(speed: f32, span: (u8, u8, u8)) -> Self {
+        Self {
+            speed,
+            span,
+            observer: AlphaBeta::critically_damped(SEEK_ALPHA),
+            stalled: 0,
+            elapsed: 0,
+        }
+    }
+
+    /// Start the axis on the first call, then take one sample per call, `elapsed_ms` after the
+    /// previous one. Returns the end stop reading once found, or `None` on failure; the axis is
+    /// braked either way.
+    pub fn run_step<A: CalibrationAxis, F: FnMut(Progress)>(
+        &mut self,
+        axis: &mut A,
+        progress: &mut Reporter<F>,
+        elapsed_ms: u32,
+    ) -> Poll<Option<u16>> {
+        if !self.observer.is_initialized() {
+            let Some(first) = axis.read_raw() else {
+                return Poll::Ready(None);
+            };
+            self.observer.update(first as f32, 0.0);
+            axis.drive(self.speed);
+            return Poll::Pending;
+        }
+
+        let (phase, lo, hi) = self.span;
+        self.elapsed += elapsed_ms;
+        let percent = progress::scale(lo, hi, self.elapsed, SEEK_TIMEOUT_MS);
+        progress.update(phase, percent, elapsed_ms);
+
+        let Some(now) = axis.read_raw() else {
+            axis.stop();
+            return Poll::Ready(None);
+        };
+        self.observer.update(now as f32, elapsed_ms as f32 / 1000.0);
+        let moving = self.observer.velocity().abs() > STALL_COUNTS_PER_S;
+        if !moving && self.elapsed >= STALL_GRACE_MS {
+            self.stalled += 1;
+            if self.stalled >= STALL_SAMPLES {
+                axis.stop();
+                return Poll::Ready(Some(now));
+            }
+        } else {
+            self.stalled = 0;
+        }
+
+        if self.elapsed >= SEEK_TIMEOUT_MS {
+            axis.stop();
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
+
+/// Measure both end stops of an axis. Leaves the axis braked at the extended end.
+///
+/// Reports the two seeks as `lo..hi` percent of `step`.
+pub fn calibrate_endpoints<A: CalibrationAxis, F: FnMut(Progress)>(
+    axis: &mut A,
+    delay: &mut Delay,
+    progress: &mut Reporter<F>,
+    (step, lo, hi): (Step, u8, u8),
+) -> Option<AxisCalibration> {
+    let mid = lo + (hi - lo) / 2;
+    let retracted_raw = find_endpoint(axis, -SEEK_SPEED, delay, progress, (step as u8, lo, mid))?;
+    let extended_raw = find_endpoint(axis, SEEK_SPEED, delay, progress, (step as u8, mid, hi))?;
+    let cal = AxisCalibration {
+        retracted_raw,
+        extended_raw,
+    };
+    cal.is_valid().then_some(cal)
+}
+
+/// True if `cpr` is within [`CPR_TOLERANCE_PCT`] of the nominal encoder resolution.
+pub fn encoder_cpr_plausible(cpr: u32) -> bool {
+    let tol = NOMINAL_ENCODER_CPR * CPR_TOLERANCE_PCT / 100;
+    cpr.abs_diff(NOMINAL_ENCODER_CPR) <= tol
+}
+
+/// Node ID for a provisioning run started on the tile itself, with no host to assign one: the
+/// stored ID if it is valid, else one derived from the MCU's unique ID. A clash with another
+/// tile shows up on the backbone, see [`IdGuard`](crate::net::IdGuard).
+pub fn local_node_id(cfg: &Config) -> u8 {
+    if cfg.node_id != 0 && cfg.node_id != 0xFF {
+        return cfg.node_id;
+    }
+    // SAFETY: the unique ID is three read-only words in system memory.
+    let uid = unsafe { core::ptr::read_volatile(UID_ADDR as *const [u32; 3]) };
+    let mut bytes = [0u8; 12];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(uid) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    // 1..=254, skipping the reserved IDs.
+    (crc32(&bytes) % 254) as u8 + 1
+}
+
+/// Run the full provisioning flow and persist the result.
+///
+/// `encoder` is measured for the CPR check; pass `None` on tiles without a FIT0185, which skips
+/// the check and keeps the stored CPR. Progress and results are logged to `log` one line per
+/// step, and reported to `report` as it goes (see [`Reporter`]). On failure `cfg` is left with
+/// `provisioned == false` and nothing is written to flash.
+#[allow(clippy::too_many_arguments)]
+pub fn run<M1, M2, W, F>(
+    cfg: &mut Config,
+    node_id: u8,
+    m1: &mut M1,
+    m2: &mut M2,
+    encoder: Option<&mut dyn CprAxis>,
+    flash: &mut Flash,
+    delay: &mut Delay,
+    log: &mut W,
+    report: F,
+) -> Result<(), ProvisionError>
+where
+    M1: CalibrationAxis,
+    M2: CalibrationAxis,
+    W: Write,
+    F: FnMut(Progress),
+{
+    let mut progress = Reporter::new(Operation::Calibration, 0, report);
+    let result = run_steps(
+        cfg,
+        node_id,
+        m1,
+        m2,
+        encoder,
+        flash,
+        delay,
+        log,
+        &mut progress,
+    );
+    progress.finish(result.is_ok());
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_steps<M1, M2, W, F>(
+    cfg: &mut Config,
+    node_id: u8,
+    m1: &mut M1,
+    m2: &mut M2,
+    encoder: Option<&mut dyn CprAxis>,
+    flash: &mut Flash,
+    delay: &mut Delay,
+    log: &mut W,
+    progress: &mut Reporter<F>,
+) -> Result<(), ProvisionError>
+where
+    M1: CalibrationAxis,
+    M2: CalibrationAxis,
+    W: Write,
+    F: FnMut(Progress),
+{
+    cfg.provisioned = false;
+
+    progress.update(Step::AssignNodeId as u8, 0, 0);
+
+    writeln!(
+        log,
+        "provision: {:?} node_id={}\r",
+        Step::AssignNodeId,
+        node_id
+    )
+    .ok();
+    if node_id == 0 || node_id == 0xFF {
+        return Err(ProvisionError::InvalidNodeId(node_id));
+    }
+    cfg.node_id = node_id;
+
+    writeln!(log, "provision: {:?}\r", Step::CalibrateM1).ok();
+    cfg.m1_cal = calibrate_endpoints(m1, delay, progress, (Step::CalibrateM1, 0, 45))
+        .ok_or(ProvisionError::Calibration(Step::CalibrateM1))?;
+    writeln!(log, "provision: M1 {:?}\r", cfg.m1_cal).ok();
+
+    writeln!(log, "provision: {:?}\r", Step::CalibrateM2).ok();
+    cfg.m2_cal = calibrate_endpoints(m2, delay, progress, (Step::CalibrateM2, 45, 90))
+        .ok_or(ProvisionError::Calibration(Step::CalibrateM2))?;
+    writeln!(log, "provision: M2 {:?}\r", cfg.m2_cal).ok();
+
+    progress.update(Step::CheckEncoder as u8, 90, 0);
+    match encoder {
+        Some(encoder) => {
+            let cpr = encoder
+                .measure_cpr(delay)
+                .map_err(ProvisionError::Encoder)?;
+            writeln!(log, "provision: {:?} cpr={}\r", Step::CheckEncoder, cpr).ok();
+            if !encoder_cpr_plausible(cpr) {
+                return Err(ProvisionError::EncoderCpr(cpr));
+            }
+            cfg.encoder_cpr = cpr;
+        }
+        None => {
+            writeln!(
+                log,
+                "provision: {:?} skipped, no encoder\r",
+                Step::CheckEncoder
+            )
+            .ok();
+        }
+    }
+
+    writeln!(log, "provision: {:?}\r", Step::Store).ok();
+    progress.update(Step::Store as u8, 95, 0);
+    cfg.provisioned = true;
+    if let Err(e) = cfg.store(flash) {
+        cfg.provisioned = false;
+        return Err(ProvisionError::Config(e));
+    }
+
+    writeln!(log, "provision: done\r").ok();
+    Ok(())
+}
@@ -0,0 +1,114 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Persistent motor usage counters.
+//!
+//! The counters change far more often than the configuration, so they live in their own sector
+//! ([`flash::STATS_SECTOR`]) as an append-only log of fixed-size records. Each store programs the
+//! next erased slot and the sector is only erased once every slot has been used, which keeps
+//! erase cycles (and the stall an erase causes) rare. On boot the newest valid record wins.
+//!
+//! Record layout (little-endian): magic, sequence number, M1 counters, M2 counters, time of the
+//! store (Unix seconds, 0 if the RTC was not set), zero padding and a CRC-32 over everything
+//! before it. Records written before the timestamp was added read back as 0.
+
+use super::{crc32, Reader, Writer};
+use crate::control::stats::{MotorStats, STATS_LEN};
+use crate::hw::flash::{self, Flash};
+
+/// Record magic ("OTST").
+pub const STATS_MAGIC: u32 = 0x4F54_5354;
+/// Size of one log slot.
+pub const STATS_RECORD_LEN: usize = 64;
+/// Number of slots in the sector.
+const SLOTS: usize = flash::STATS_SECTOR_LEN / STATS_RECORD_LEN;
+/// Offset of the CRC-32 that covers `[0, STATS_RECORD_LEN - 4)`.
+const CRC_OFFSET: usize = STATS_RECORD_LEN - 4;
+/// Value of a slot's first word while it is still erased.
+const ERASED: u32 = 0xFFFF_FFFF;
+
+fn slot_addr(slot: usize) -> u32 {
+    flash::STATS_ADDR + (slot * STATS_RECORD_LEN) as u32
+}
+
+/// Write position in the statistics log.
+pub struct StatsLog {
+    next_slot: usize,
+    seq: u32,
+    saved_unix_s: u32,
+}
+
+impl StatsLog {
+    /// Scan the log, restore the newest valid record into `m1` and `m2`, and position the log
+    /// after it. Leaves the counters untouched if no record is found.
+    pub fn load(m1: &mut MotorStats, m2: &mut MotorStats) -> Self {
+        let mut log = Self {
+            next_slot: SLOTS,
+            seq: 0,
+            saved_unix_s: 0,
+        };
+        let mut buf = [0u8; STATS_RECORD_LEN];
+
+        for slot in 0..SLOTS {
+            Flash::read(slot_addr(slot), &mut buf);
+            let mut r = Reader::new(&buf);
+            let magic = r.u32();
+            if magic == ERASED {
+                log.next_slot = slot;
+                break;
+            }
+            let stored_crc = u32::from_le_bytes(buf[CRC_OFFSET..].try_into().unwrap());
+            if magic != STATS_MAGIC || crc32(&buf[..CRC_OFFSET]) != stored_crc {
+                continue;
+            }
+            log.seq = r.u32().wrapping_add(1);
+            m1.restore(&r.take::<STATS_LEN>());
+            m2.restore(&r.take::<STATS_LEN>());
+            log.saved_unix_s = r.u32();
+        }
+        log
+    }
+
+    /// Wall-clock time of the newest record in Unix seconds, 0 if unknown.
+    pub fn saved_unix_s(&self) -> u32 {
+        self.saved_unix_s
+    }
+
+    /// Append the current counters stamped with `unix_s`, erasing the sector first if it is full.
+    /// Marks both trackers clean on success.
+    pub fn store(
+        &mut self,
+        flash: &mut Flash,
+        m1: &mut MotorStats,
+        m2: &mut MotorStats,
+        unix_s: u32,
+    ) -> Result<(), flash::Error> {
+        if self.next_slot >= SLOTS {
+            flash.erase_sector(flash::STATS_SECTOR)?;
+            self.next_slot = 0;
+        }
+
+        let mut buf = [0u8; STATS_RECORD_LEN];
+        {
+            let mut w = Writer::new(&mut buf);
+            w.u32(STATS_MAGIC);
+            w.u32(self.seq);
+            w.bytes(&m1.to_bytes());
+            w.bytes(&m2.to_bytes());
+            w.u32(unix_s);
+        }
+        let crc = crc32(&buf[..CRC_OFFSET]);
+        buf[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+
+        // Skip the slot even if programming fails; it may be partially written.
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        flash.program(slot_addr(slot), &buf)?;
+
+        self.seq = self.seq.wrapping_add(1);
+        self.saved_unix_s = unix_s;
+        m1.mark_clean();
+        m2.mark_clean();
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Warm-restart state kept in backup SRAM.
+//!
+//! Flash is too slow and wears too quickly for state that changes every few hundred milliseconds,
+//! so the last known lift height and a short fault log live in [`BackupSram`] instead. After a
+//! watchdog or software reset the record is still there, and if its CRC checks out the firmware
+//! can trust the saved height instead of homing again. After power loss without VBAT the CRC
+//! fails and the tile cold-starts.
+//!
+//! Record layout (little-endian): magic, boot count, lift height (f32 mm, NaN if unknown), fault
+//! count, [`FAULT_LOG_LEN`] fault entries, zero padding and a CRC-32 over everything before it.
+
+use super::{crc32, Reader, Writer};
+use crate::hw::backup_sram::BackupSram;
+use crate::hw::ResetReason;
+
+/// Record magic ("OTWM").
+pub const WARM_MAGIC: u32 = 0x4F54_574D;
+/// Size of the record in backup SRAM.
+pub const WARM_RECORD_LEN: usize = 128;
+/// Number of faults kept; older ones are dropped.
+pub const FAULT_LOG_LEN: usize = 8;
+/// Offset of the record in backup SRAM.
+const OFFSET: usize = 0;
+/// Offset of the CRC-32 that covers `[0, WARM_RECORD_LEN - 4)`.
+const CRC_OFFSET: usize = WARM_RECORD_LEN - 4;
+
+/// One logged fault.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FaultEntry {
+    pub axis: u8,
+    pub code: u8,
+    /// Boot during which the fault happened (see [`WarmState::boots`]).
+    pub boot: u16,
+    /// Milliseconds since that boot.
+    pub time_ms: u32,
+    /// Wall-clock time in Unix seconds, 0 if the RTC was not set.
+    pub unix_s: u32,
+}
+
+/// State that survives a reset.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WarmState {
+    /// Boots since the backup SRAM was last initialized, wrapping.
+    pub boots: u32,
+    /// Last known lift height in mm, NaN if never measured.
+    pub lift_mm: f32,
+    faults: [FaultEntry; FAULT_LOG_LEN],
+    fault_count: u8,
+}
+
+impl Default for WarmState {
+    fn default() -> Self {
+        Self {
+            boots: 0,
+            lift_mm: f32::NAN,
+            faults: [FaultEntry::default(); FAULT_LOG_LEN],
+            fault_count: 0,
+        }
+    }
+}
+
+impl WarmState {
+    /// Restore the record from backup SRAM, or `None` if it is missing or corrupt.
+    pub fn load(sram: &BackupSram) -> Option<Self> {
+        let mut buf = [0u8; WARM_RECORD_LEN];
+        sram.read(OFFSET, &mut buf);
+        Self::from_bytes(&buf)
+    }
+
+    /// Write the record to backup SRAM.
+    pub fn store(&self, sram: &mut BackupSram) {
+        sram.write(OFFSET, &self.to_bytes());
+    }
+
+    /// True if the saved lift height can stand in for homing after `reason`.
+    ///
+    /// Only resets that leave the mechanics untouched qualify: after a power cycle or a reset
+    /// button press the lift may have been moved by hand.
+    pub fn can_skip_homing(&self, reason: ResetReason) -> bool {
+        matches!(
+            reason,
+            ResetReason::IndependentWatchdog | ResetReason::WindowWatchdog | ResetReason::Software
+        ) && self.lift_mm.is_finite()
+    }
+
+    /// Logged faults, oldest first.
+    pub fn faults(&self) -> &[FaultEntry] {
+        &self.faults[..self.fault_count as usize]
+    }
+
+    /// Append a fault, dropping the oldest if the log is full.
+    pub fn log_fault(&mut self, entry: FaultEntry) {
+        let n = self.fault_count as usize;
+        if n == FAULT_LOG_LEN {
+            self.faults.copy_within(1.., 0);
+            self.faults[FAULT_LOG_LEN - 1] = entry;
+        } else {
+            self.faults[n] = entry;
+            self.fault_count += 1;
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; WARM_RECORD_LEN] {
+        let mut buf = [0u8; WARM_RECORD_LEN];
+        {
+            let mut w = Writer::new(&mut buf);
+            w.u32(WARM_MAGIC);
+            w.u32(self.boots);
+            w.f32(self.lift_mm);
+            w.u8(self.fault_count);
+            for f in &self.faults {
+                w.u8(f.axis);
+                w.u8(f.code);
+                w.u16(f.boot);
+                w.u32(f.time_ms);
+                w.u32(f.unix_s);
+            }
+        }
+        let crc = crc32(&buf[..CRC_OFFSET]);
+        buf[CRC_OFFSET..].copy_from_slice(&crc.to_le_bytes());
+        buf
+    }
+
+    pub fn from_bytes(buf: &[u8; WARM_RECORD_LEN]) -> Option<Self> {
+        let stored_crc = u32::from_le_bytes(buf[CRC_OFFSET..].try_into().unwrap());
+        let mut r = Reader::new(buf);
+        if r.u32() != WARM_MAGIC || crc32(&buf[..CRC_OFFSET]) != stored_crc {
+            return None;
+        }
+        let mut state = Self {
+            boots: r.u32(),
+            lift_mm: r.f32(),
+            fault_count: r.u8().min(FAULT_LOG_LEN as u8),
+            ..Self::default()
+        };
+        for f in state.faults.iter_mut() {
+            *f = FaultEntry {
+                axis: r.u8(),
+                code: r.u8(),
+                boot: r.u16(),
+                time_ms: r.u32(),
+                unix_s: r.u32(),
+            };
+        }
+        Some(state)
+    }
+}
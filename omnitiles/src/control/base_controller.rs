@@ -37,6 +37,7 @@ pub struct BaseController<
     pub fr: Tb6612<W2_IN1_P, W2_IN1_N, W2_IN2_P, W2_IN2_N, W2Pwm>,
     pub rl: Tb6612<W3_IN1_P, W3_IN1_N, W3_IN2_P, W3_IN2_N, W3Pwm>,
     pub rr: Tb6612<W4_IN1_P, W4_IN1_N, W4_IN2_P, W4_IN2_N, W4Pwm>,
+    moving: bool,
 }
 
 impl<
@@ -90,7 +91,13 @@ impl<
         rl: Tb6612<W3_IN1_P, W3_IN1_N, W3_IN2_P, W3_IN2_N, W3Pwm>,
         rr: Tb6612<W4_IN1_P, W4_IN1_N, W4_IN2_P, W4_IN2_N, W4Pwm>,
     ) -> Self {
-        Self { fl, fr, rl, rr }
+        Self {
+            fl,
+            fr,
+            rl,
+            rr,
+            moving: false,
+        }
     }
 
     /// Set body-frame velocity. Inputs are normalized: -1.0..1.0 for each axis.
@@ -100,6 +107,7 @@ impl<
         self.fr.set_speed(fr);
         self.rl.set_speed(-rl);
         self.rr.set_speed(rr);
+        self.moving = vx != 0.0 || vy != 0.0 || omega != 0.0;
     }
 
     /// Brake all four wheels.
@@ -108,5 +116,11 @@ impl<
         self.fr.brake();
         self.rl.brake();
         self.rr.brake();
+        self.moving = false;
+    }
+
+    /// True from a non-zero velocity command until the next brake or zero velocity.
+    pub fn is_moving(&self) -> bool {
+        self.moving
     }
 }
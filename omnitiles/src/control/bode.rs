@@ -0,0 +1,204 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Closed-loop frequency response (Bode) test.
+//!
+//! A gearbox swap, a new load or stiffer mounting changes the plant under the position loop, and
+//! gains that were stable before may now ring. [`BodeTest`] checks the margins on the tile
+//! itself: the axis stays in position control while its target follows a small sine about the
+//! starting position, stepping through [`FREQUENCIES_HZ`]. At each frequency the first
+//! [`SETTLE_CYCLES`] let the response settle, then the target and the measured position are
+//! correlated with the sine over [`MEASURE_CYCLES`] cycles. The ratio of the two phasors is the
+//! closed-loop gain and phase at that frequency.
+//!
+//! A resonance peak well above 0 dB means a small phase margin; the frequency where the gain
+//! falls through −3 dB is the loop bandwidth. [`Summary`] reports both.
+
+use core::f32::consts::PI;
+use micromath::F32Ext;
+
+/// Test frequencies, in order.
+pub const FREQUENCIES_HZ: [f32; 6] = [0.2, 0.5, 1.0, 2.0, 3.0, 5.0];
+/// Cycles run before measuring at each frequency.
+pub const SETTLE_CYCLES: u8 = 1;
+/// Cycles measured at each frequency.
+pub const MEASURE_CYCLES: u8 = 3;
+/// Largest sine amplitude.
+pub const MAX_AMPLITUDE_MM: f32 = 10.0;
+
+/// Gain and phase of the closed loop at one frequency.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BodePoint {
+    pub freq_hz: f32,
+    /// Position amplitude over target amplitude, in dB.
+    pub gain_db: f32,
+    /// Position phase relative to the target, in degrees, −180..=180. Negative is lag.
+    pub phase_deg: f32,
+}
+
+/// Figures of merit over a whole sweep.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Summary {
+    /// Highest gain measured, in dB.
+    pub peak_db: f32,
+    /// Frequency of the peak.
+    pub peak_hz: f32,
+    /// First frequency with the gain below −3 dB, `None` if every frequency stayed above.
+    pub bandwidth_hz: Option<f32>,
+}
+
+/// Outcome of one [`BodeTest::step`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BodeStep {
+    /// Set the target to `target_mm`. `point` is the result of a frequency that has just been
+    /// measured.
+    Track {
+        target_mm: f32,
+        point: Option<BodePoint>,
+    },
+    /// Every frequency has been measured; hold the starting position.
+    Done(Summary),
+    /// Position feedback was lost.
+    Abort,
+}
+
+/// Running phasor sums of the target and position deviations.
+#[derive(Copy, Clone, Debug, Default)]
+struct Phasors {
+    target_sin: f32,
+    target_cos: f32,
+    position_sin: f32,
+    position_cos: f32,
+}
+
+/// A frequency response sweep on one axis.
+#[derive(Copy, Clone, Debug)]
+pub struct BodeTest {
+    axis: u8,
+    center_mm: f32,
+    amplitude_mm: f32,
+    freq_index: usize,
+    /// Phase of the sine in the current cycle.
+    phase: f32,
+    cycle: u8,
+    /// Sine value the current target was computed from.
+    last_sin: f32,
+    last_cos: f32,
+    sums: Phasors,
+    summary: Option<Summary>,
+}
+
+impl BodeTest {
+    /// Sweep `axis` with a sine of `amplitude_mm` about `position_mm`. The amplitude is clamped
+    /// to [`MAX_AMPLITUDE_MM`] and the center moved so the sine stays within
+    /// `min_mm..=max_mm`. Returns `None` if the range is too short for the amplitude.
+    pub fn new(
+        axis: u8,
+        position_mm: f32,
+        amplitude_mm: f32,
+        (min_mm, max_mm): (f32, f32),
+    ) -> Option<Self> {
+        let amplitude_mm = amplitude_mm.clamp(0.0, MAX_AMPLITUDE_MM);
+        if amplitude_mm == 0.0 || max_mm - min_mm < 2.0 * amplitude_mm {
+            return None;
+        }
+        Some(Self {
+            axis,
+            center_mm: position_mm.clamp(min_mm + amplitude_mm, max_mm - amplitude_mm),
+            amplitude_mm,
+            freq_index: 0,
+            phase: 0.0,
+            cycle: 0,
+            last_sin: 0.0,
+            last_cos: 1.0,
+            sums: Phasors::default(),
+            summary: None,
+        })
+    }
+
+    pub fn axis(&self) -> u8 {
+        self.axis
+    }
+
+    /// Starting position the sine is centered on.
+    pub fn center_mm(&self) -> f32 {
+        self.center_mm
+    }
+
+    /// Advance by `dt` seconds with the axis at `position` (mm), measured under the target from
+    /// the previous step.
+    pub fn step(&mut self, position: Option<f32>, dt: f32) -> BodeStep {
+        let Some(position) = position else {
+            return BodeStep::Abort;
+        };
+        let Some(&freq_hz) = FREQUENCIES_HZ.get(self.freq_index) else {
+            return BodeStep::Done(self.summary.unwrap_or(Summary {
+                peak_db: 0.0,
+                peak_hz: 0.0,
+                bandwidth_hz: None,
+            }));
+        };
+
+        if self.cycle >= SETTLE_CYCLES {
+            let target = self.amplitude_mm * self.last_sin;
+            let deviation = position - self.center_mm;
+            self.sums.target_sin += target * self.last_sin * dt;
+            self.sums.target_cos += target * self.last_cos * dt;
+            self.sums.position_sin += deviation * self.last_sin * dt;
+            self.sums.position_cos += deviation * self.last_cos * dt;
+        }
+
+        let mut point = None;
+        self.phase += 2.0 * PI * freq_hz * dt;
+        if self.phase >= 2.0 * PI {
+            self.phase -= 2.0 * PI;
+            self.cycle += 1;
+            if self.cycle == SETTLE_CYCLES + MEASURE_CYCLES {
+                let p = self.point(freq_hz);
+                self.record(p);
+                point = Some(p);
+                self.freq_index += 1;
+                self.cycle = 0;
+                self.sums = Phasors::default();
+            }
+        }
+        (self.last_sin, self.last_cos) = self.phase.sin_cos();
+        BodeStep::Track {
+            target_mm: self.center_mm + self.amplitude_mm * self.last_sin,
+            point,
+        }
+    }
+
+    fn point(&self, freq_hz: f32) -> BodePoint {
+        let s = &self.sums;
+        let target = (s.target_sin * s.target_sin + s.target_cos * s.target_cos).sqrt();
+        let position = (s.position_sin * s.position_sin + s.position_cos * s.position_cos).sqrt();
+        let gain = position / target.max(f32::EPSILON);
+        let mut phase = s.position_cos.atan2(s.position_sin) - s.target_cos.atan2(s.target_sin);
+        if phase > PI {
+            phase -= 2.0 * PI;
+        } else if phase <= -PI {
+            phase += 2.0 * PI;
+        }
+        BodePoint {
+            freq_hz,
+            gain_db: 20.0 * gain.max(1e-6).log10(),
+            phase_deg: phase * 180.0 / PI,
+        }
+    }
+
+    fn record(&mut self, p: BodePoint) {
+        let summary = self.summary.get_or_insert(Summary {
+            peak_db: p.gain_db,
+            peak_hz: p.freq_hz,
+            bandwidth_hz: None,
+        });
+        if p.gain_db > summary.peak_db {
+            summary.peak_db = p.gain_db;
+            summary.peak_hz = p.freq_hz;
+        }
+        if summary.bandwidth_hz.is_none() && p.gain_db < -3.0 {
+            summary.bandwidth_hz = Some(p.freq_hz);
+        }
+    }
+}
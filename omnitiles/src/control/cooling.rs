@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Temperature-driven fan control.
+//!
+//! A tile running demos for hours in a closed enclosure heats up until its motors derate (see
+//! [`MotorTemperature`](crate::sensors::MotorTemperature)). A fan on the
+//! [`Fan`](crate::drivers::fan::Fan) output keeps it below that. [`Cooling`] turns the hottest
+//! temperature measured into a fan duty in one of two ways, chosen with [`FanMode`]:
+//!
+//! - [`FanMode::Hysteresis`]: full speed from `on_c` until the temperature falls back to
+//!   `off_c`. Suits any fan, including 2-wire ones that don't like partial duty.
+//! - [`FanMode::Pi`]: a PI loop holds `target_c` with the slowest fan that manages it. Quieter,
+//!   but needs a fan that runs at partial duty.
+//!
+//! Without a reading the fan runs at full speed: a broken sensor must not leave the tile to cook.
+
+use crate::control::Pid;
+
+/// How the fan duty follows the temperature.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FanMode {
+    /// Fan always off.
+    Off,
+    /// On at full speed at `on_c`, off again at `off_c`.
+    Hysteresis { on_c: f32, off_c: f32 },
+    /// Hold `target_c`. `kp` in duty per °C, `ki` in duty per °C·s.
+    Pi { target_c: f32, kp: f32, ki: f32 },
+}
+
+/// Fan duty from a temperature, see the module docs.
+pub struct Cooling {
+    mode: FanMode,
+    pid: Pid,
+    running: bool,
+}
+
+impl Cooling {
+    pub fn new(mode: FanMode) -> Self {
+        let (kp, ki) = match mode {
+            FanMode::Pi { kp, ki, .. } => (kp, ki),
+            _ => (0.0, 0.0),
+        };
+        Self {
+            mode,
+            pid: Pid::new(kp, ki, 0.0)
+                .with_output_limits(0.0, 1.0)
+                .with_integral_limits(0.0, 1.0),
+            running: false,
+        }
+    }
+
+    pub fn mode(&self) -> FanMode {
+        self.mode
+    }
+
+    /// Fan duty (0.0..=1.0) for `celsius` measured `dt` seconds after the previous update.
+    pub fn update(&mut self, celsius: Option<f32>, dt: f32) -> f32 {
+        let Some(c) = celsius else {
+            self.pid.reset();
+            self.running = self.mode != FanMode::Off;
+            return if self.running { 1.0 } else { 0.0 };
+        };
+        match self.mode {
+            FanMode::Off => {
+                self.running = false;
+                0.0
+            }
+            FanMode::Hysteresis { on_c, off_c } => {
+                if c >= on_c {
+                    self.running = true;
+                } else if c <= off_c {
+                    self.running = false;
+                }
+                if self.running {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            FanMode::Pi { target_c, .. } => {
+                // The fan pushes the temperature down, so the roles of setpoint and measurement
+                // swap: the duty rises while the temperature is above target.
+                let duty = self.pid.update(c, target_c, dt);
+                self.running = duty > 0.0;
+                duty
+            }
+        }
+    }
+
+    /// True while the fan is commanded on.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
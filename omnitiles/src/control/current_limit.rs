@@ -0,0 +1,62 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Motion derating while a driver is current-limited.
+//!
+//! When the DRV8873 regulates at its ITRIP level the bridge chops the output and the commanded
+//! duty no longer turns into speed, so a profile planned for full limits runs away from the axis.
+//! [`CurrentDerate`] turns the driver's regulation flags into a derate factor for
+//! [`LinearController::set_derate`](crate::control::LinearController::set_derate): each check
+//! that finds the driver regulating cuts the factor by [`CUT`], and once it has stayed out of
+//! regulation for [`RECOVER_MS`] the factor climbs back by [`RECOVER_STEP`] per check.
+
+/// Factor applied per check while the driver is regulating.
+pub const CUT: f32 = 0.8;
+/// Time out of regulation before the factor starts recovering.
+pub const RECOVER_MS: u32 = 2000;
+/// Factor added back per check once recovering.
+pub const RECOVER_STEP: f32 = 0.05;
+/// Lowest factor; matches the controller's own floor.
+const MIN_FACTOR: f32 = 0.1;
+
+pub struct CurrentDerate {
+    factor: f32,
+    /// Time since the driver last reported regulating.
+    calm_ms: u32,
+}
+
+impl CurrentDerate {
+    pub const fn new() -> Self {
+        Self {
+            factor: 1.0,
+            calm_ms: 0,
+        }
+    }
+
+    /// Feed one check taken `dt_ms` after the previous one. `limited` is `None` when the driver
+    /// can't be read, which counts as not regulating. Returns the derate factor.
+    pub fn update(&mut self, limited: Option<bool>, dt_ms: u32) -> f32 {
+        if limited == Some(true) {
+            self.calm_ms = 0;
+            self.factor = (self.factor * CUT).max(MIN_FACTOR);
+        } else {
+            self.calm_ms = self.calm_ms.saturating_add(dt_ms);
+            if self.calm_ms >= RECOVER_MS {
+                self.factor = (self.factor + RECOVER_STEP).min(1.0);
+            }
+        }
+        self.factor
+    }
+
+    /// Current derate factor, 1.0 when not derated.
+    #[inline]
+    pub fn factor(&self) -> f32 {
+        self.factor
+    }
+}
+
+impl Default for CurrentDerate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
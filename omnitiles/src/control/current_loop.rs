@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Inner motor-current loop, run from a timer interrupt.
+//!
+//! In PWM mode the DRV8873 sets the bridge voltage, so a position PID's output is really a
+//! voltage and the torque it gives depends on speed and supply. Closing a PI loop on the measured
+//! current (from the driver's IPROPI output) at several kHz turns the bridge into a torque source:
+//! the position loop in the main task then commands amps, and the current loop holds them against
+//! back-EMF and supply changes.
+//!
+//! The two loops run at different rates in different contexts and share only a
+//! [`CurrentCommand`]: the main task writes the target, the interrupt writes back the measurement.
+//! All fields are atomics, so it can be a `static` and neither side ever blocks.
+//!
+//! ```no_run
+//! static CURRENT: CurrentCommand = CurrentCommand::new();
+//! static DRIVE: Mutex<RefCell<Option<IsrState>>> = Mutex::new(RefCell::new(None));
+//!
+//! #[interrupt]
+//! fn TIM7() {
+//!     if tick_timer::on_tim7_interrupt() {
+//!         interrupt::free(|cs| {
+//!             if let Some(s) = DRIVE.borrow(cs).borrow_mut().as_mut() {
+//!                 s.drive.on_tick(&CURRENT, s.adc.latest(0));
+//!             }
+//!         });
+//!     }
+//! }
+//! ```
+//!
+//! with a [`TorqueDrive`](crate::drivers::TorqueDrive) and an [`AdcDma`](crate::hw::AdcDma) in
+//! `IsrState`, a [`TickTimer`](crate::hw::TickTimer) at [`CURRENT_LOOP_HZ`], and a
+//! [`TorqueAxis`](crate::drivers::TorqueAxis) under the usual
+//! [`LinearController`](crate::control::LinearController) in the main loop.
+//!
+//! The firmware's `current-loop` feature wires M1 this way, with
+//! [`TorqueInputs`](crate::drivers::TorqueInputs) under its `ActuonixLinear` in place of the
+//! `TorqueAxis`.
+//!
+//! IPROPI only reports the magnitude of the current; the loop takes the sign from the direction it
+//! is driving. Filter IPROPI with an RC well above the loop rate but below the PWM frequency.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use crate::control::Pid;
+
+/// Rate the loop is tuned for; fast enough to stay well inside the motor's electrical time
+/// constant, slow enough to leave the CPU to the main task.
+pub const CURRENT_LOOP_HZ: u32 = 5_000;
+
+/// DRV8873 IPROPI output current per amp of load current.
+const A_IPROPI: f32 = 1100e-6;
+
+/// Amps per ADC count with IPROPI into `r_ipropi` ohms, for a 12-bit ADC at `v_ref`.
+pub fn ipropi_amps_per_count(r_ipropi: f32, v_ref: f32) -> f32 {
+    v_ref / 4095.0 / (r_ipropi * A_IPROPI)
+}
+
+/// Current target and measurement shared between the main task and the interrupt.
+pub struct CurrentCommand {
+    /// `f32` bits.
+    target: AtomicU32,
+    measured: AtomicU32,
+    enabled: AtomicBool,
+}
+
+impl CurrentCommand {
+    /// Disabled, with a zero target.
+    pub const fn new() -> Self {
+        Self {
+            target: AtomicU32::new(0),
+            measured: AtomicU32::new(0),
+            enabled: AtomicBool::new(false),
+        }
+    }
+
+    /// Regulate to `amps`, signed in the drive direction.
+    pub fn set_target(&self, amps: f32) {
+        let amps = if amps.is_nan() { 0.0 } else { amps };
+        self.target.store(amps.to_bits(), Ordering::Relaxed);
+        self.enabled.store(true, Ordering::Release);
+    }
+
+    /// Stop regulating; the interrupt brakes the bridge.
+    pub fn disable(&self) {
+        self.enabled.store(false, Ordering::Release);
+        self.target.store(0, Ordering::Relaxed);
+    }
+
+    pub fn target(&self) -> f32 {
+        f32::from_bits(self.target.load(Ordering::Relaxed))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    /// Latest measured current in amps, signed like the target.
+    pub fn measured(&self) -> f32 {
+        f32::from_bits(self.measured.load(Ordering::Relaxed))
+    }
+
+    fn publish(&self, amps: f32) {
+        self.measured.store(amps.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl Default for CurrentCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// PI current controller. Outputs a signed bridge duty.
+pub struct CurrentLoop {
+    pid: Pid,
+    dt: f32,
+    amps_per_count: f32,
+    /// ADC reading at zero current.
+    offset: u16,
+    /// Duty applied last, for the sign of the measurement.
+    duty: f32,
+}
+
+impl CurrentLoop {
+    /// `kp` in duty per amp, `ki` in duty per amp-second, for a loop running at `rate_hz`.
+    pub fn new(kp: f32, ki: f32, rate_hz: u32, amps_per_count: f32) -> Self {
+        Self {
+            pid: Pid::new(kp, ki, 0.0)
+                .with_output_limits(-1.0, 1.0)
+                .with_integral_limits(-1.0, 1.0),
+            dt: 1.0 / rate_hz as f32,
+            amps_per_count,
+            offset: 0,
+            duty: 0.0,
+        }
+    }
+
+    /// ADC reading with no current flowing, measured with the bridge off.
+    pub fn with_offset(mut self, offset: u16) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Signed current from an IPROPI reading.
+    pub fn measure(&self, raw: u16, target: f32) -> f32 {
+        let amps = raw.saturating_sub(self.offset) as f32 * self.amps_per_count;
+        let driving = if self.duty != 0.0 { self.duty } else { target };
+        if driving < 0.0 {
+            -amps
+        } else {
+            amps
+        }
+    }
+
+    /// One loop step on the IPROPI reading `raw`. Returns the duty to apply, `None` while the
+    /// command is disabled.
+    pub fn step(&mut self, cmd: &CurrentCommand, raw: u16) -> Option<f32> {
+        let target = cmd.target();
+        let measured = self.measure(raw, target);
+        cmd.publish(measured);
+        if !cmd.is_enabled() {
+            self.pid.reset();
+            self.duty = 0.0;
+            return None;
+        }
+        self.duty = self.pid.update(target, measured, self.dt);
+        Some(self.duty)
+    }
+
+    pub fn set_gains(&mut self, kp: f32, ki: f32) {
+        self.pid.set_gains(kp, ki, 0.0);
+    }
+}
@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Lift height vs tilt angle collision envelope.
+//!
+//! Tilting the top lowers one of its edges by `half_span · sin|θ|`. Near the bottom of the stroke
+//! that edge reaches the frame, so how far the top may tilt depends on how high the lift is. The
+//! [`Envelope`] models the edge as a point `half_span_mm` from the tilt pivot that starts `gap_mm`
+//! above the frame with the lift retracted and the top level, and must stay `margin_mm` clear of
+//! it:
+//!
+//! ```text
+//! gap + lift − half_span · sin|θ| ≥ margin
+//! ```
+//!
+//! [`Envelope::DEFAULT`] is built from the frame constants in [`crate::config`]. Lift heights are
+//! measured from the retracted end, angles from level. The firmware clamps tilt moves to the lift
+//! (M2) height and raises M2 position targets to clear the tilt target; manual M2 drive is not
+//! limited.
+//!
+//! Only end points are checked. A combined move that lowers the lift while levelling the top can
+//! still pass through the frame on the way; order such moves with an interlock (see
+//! [`interlock`](super::interlock)) so the lift only descends once the top is level.
+
+use core::f32::consts::FRAC_PI_2;
+
+use micromath::F32Ext;
+
+use crate::config::{FRAME_GAP_MM, FRAME_MARGIN_MM, TOP_HALF_SPAN_MM};
+use crate::units::{Mm, Rad};
+
+/// A lift/tilt combination outside the envelope, with the nearest safe values for each axis.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Collision {
+    /// Lowest lift height at which the requested tilt clears the frame.
+    pub min_lift: Mm,
+    /// Largest tilt magnitude that clears the frame at the requested height.
+    pub max_tilt: Rad,
+}
+
+/// Frame geometry seen by the tilting top.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Envelope {
+    /// Horizontal distance from the tilt pivot to the top's edge.
+    pub half_span_mm: f32,
+    /// Height of the edge above the frame with the lift retracted and the top level.
+    pub gap_mm: f32,
+    /// Clearance kept between the edge and the frame.
+    pub margin_mm: f32,
+}
+
+impl Envelope {
+    pub const DEFAULT: Self = Self {
+        half_span_mm: TOP_HALF_SPAN_MM,
+        gap_mm: FRAME_GAP_MM,
+        margin_mm: FRAME_MARGIN_MM,
+    };
+
+    /// How far the edge may drop below its level position at lift height `lift`.
+    fn allowed_drop(&self, lift: Mm) -> f32 {
+        self.gap_mm + lift.0 - self.margin_mm
+    }
+
+    /// Largest tilt magnitude that clears the frame at lift height `lift`.
+    pub fn max_tilt(&self, lift: Mm) -> Rad {
+        let drop = self.allowed_drop(lift);
+        if drop <= 0.0 {
+            Rad(0.0)
+        } else if drop >= self.half_span_mm {
+            Rad(FRAC_PI_2)
+        } else {
+            Rad((drop / self.half_span_mm).asin())
+        }
+    }
+
+    /// Lowest lift height at which `tilt` clears the frame.
+    pub fn min_lift(&self, tilt: Rad) -> Mm {
+        let drop = self.half_span_mm * tilt.0.abs().min(FRAC_PI_2).sin();
+        Mm((drop - self.gap_mm + self.margin_mm).max(0.0))
+    }
+
+    /// Check a combined lift/tilt target.
+    pub fn check(&self, lift: Mm, tilt: Rad) -> Result<(), Collision> {
+        if self.allowed_drop(lift) >= self.half_span_mm * tilt.0.abs().min(FRAC_PI_2).sin() {
+            Ok(())
+        } else {
+            Err(Collision {
+                min_lift: self.min_lift(tilt),
+                max_tilt: self.max_tilt(lift),
+            })
+        }
+    }
+
+    /// Keep `lift` and reduce `tilt` to what clears the frame at that height.
+    pub fn clamp_tilt(&self, lift: Mm, tilt: Rad) -> Rad {
+        let max = self.max_tilt(lift);
+        tilt.clamp(-max, max)
+    }
+
+    /// Keep `tilt` and raise `lift` to the lowest height at which it clears the frame.
+    pub fn clamp_lift(&self, lift: Mm, tilt: Rad) -> Mm {
+        let min = self.min_lift(tilt);
+        if lift < min {
+            min
+        } else {
+            lift
+        }
+    }
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
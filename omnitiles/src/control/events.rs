@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Controller events and a fixed-capacity event queue.
+//!
+//! Controllers latch state transitions (target reached, homing finished, fault raised) as
+//! [`Event`]s. The dispatcher drains them into an [`EventQueue`] and forwards them to the host as
+//! unsolicited `MSG_EVENT` frames, so the host doesn't have to poll `on_target()`.
+//!
+//! Controllers don't own a clock, so the dispatcher stamps each event with the
+//! [`MonoClock`](crate::hw::MonoClock) time as it queues it; see [`Event::at`]. It likewise tags
+//! an event with the token of the command it completes ([`Event::with_token`]), which sends it as
+//! `MSG_TOKEN_EVENT` instead; see [`crate::protocol::seq`].
+
+/// Kind of controller event. The discriminant is the wire value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum EventKind {
+    /// The axis settled within its on-target tolerance.
+    TargetReached = 0x01,
+    /// A homing routine completed.
+    HomingDone = 0x02,
+    /// The controller stopped the axis because of a fault. `code` identifies the fault.
+    Fault = 0x03,
+    /// The surface load crossed a threshold (axis 0). `code` is 1 when pressed, 0 when released.
+    Load = 0x04,
+    /// The axis was stopped because an interlock stopped holding. `code` is the source axis.
+    Interlock = 0x05,
+}
+
+/// A single controller event.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// Axis number (1 = M1, 2 = M2, ...), or 0 for tile-level events.
+    pub axis: u8,
+    pub kind: EventKind,
+    /// Kind-specific detail (fault code for [`EventKind::Fault`], press state for
+    /// [`EventKind::Load`], source axis for [`EventKind::Interlock`], otherwise 0).
+    pub code: u8,
+    /// Microseconds since boot when the event was queued, wrapping after ~71 minutes.
+    pub timestamp_us: u32,
+    /// Token of the command that caused the event, if it was sent with one.
+    pub token: Option<u16>,
+}
+
+/// Serialized length of an event without a token.
+pub const EVENT_LEN: usize = 7;
+/// Serialized length of an event with a token.
+pub const TOKEN_EVENT_LEN: usize = 9;
+
+impl Event {
+    /// An unstamped event.
+    pub const fn new(axis: u8, kind: EventKind, code: u8) -> Self {
+        Self {
+            axis,
+            kind,
+            code,
+            timestamp_us: 0,
+            token: None,
+        }
+    }
+
+    /// Stamp the event with `now_us` (truncated to 32 bits).
+    pub fn at(self, now_us: u64) -> Self {
+        Self {
+            timestamp_us: now_us as u32,
+            ..self
+        }
+    }
+
+    /// Tag the event with the token of the command that caused it.
+    pub fn with_token(self, token: Option<u16>) -> Self {
+        Self { token, ..self }
+    }
+
+    /// Wire payload: `[kind, axis, code, timestamp_us: u32 LE]`.
+    pub fn to_bytes(&self) -> [u8; EVENT_LEN] {
+        let t = self.timestamp_us.to_le_bytes();
+        [
+            self.kind as u8,
+            self.axis,
+            self.code,
+            t[0],
+            t[1],
+            t[2],
+            t[3],
+        ]
+    }
+
+    /// Wire payload with the token appended: `[kind, axis, code, timestamp_us: u32 LE,
+    /// token: u16 LE]`. The token reads 0 if there is none.
+    pub fn to_token_bytes(&self) -> [u8; TOKEN_EVENT_LEN] {
+        let mut out = [0u8; TOKEN_EVENT_LEN];
+        out[..EVENT_LEN].copy_from_slice(&self.to_bytes());
+        out[EVENT_LEN..].copy_from_slice(&self.token.unwrap_or(0).to_le_bytes());
+        out
+    }
+}
+
+/// Fixed-capacity FIFO of events. When full, the oldest event is dropped.
+pub struct EventQueue<const N: usize> {
+    buf: [Option<Event>; N],
+    head: usize,
+    len: usize,
+    dropped: u32,
+}
+
+impl<const N: usize> EventQueue<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: [None; N],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Append an event, dropping the oldest one if the queue is full.
+    pub fn push(&mut self, event: Event) {
+        if N == 0 {
+            self.dropped = self.dropped.wrapping_add(1);
+            return;
+        }
+        if self.len == N {
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+            self.dropped = self.dropped.wrapping_add(1);
+        }
+        let tail = (self.head + self.len) % N;
+        self.buf[tail] = Some(event);
+        self.len += 1;
+    }
+
+    /// Remove and return the oldest event.
+    pub fn pop(&mut self) -> Option<Event> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.buf[self.head].take();
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        event
+    }
+
+    /// Look at the oldest event without removing it.
+    pub fn peek(&self) -> Option<&Event> {
+        if self.len == 0 {
+            None
+        } else {
+            self.buf[self.head].as_ref()
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of events lost to overflow since creation.
+    #[inline]
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+}
+
+impl<const N: usize> Default for EventQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Per-axis state captured when a fault stops a motor.
+//!
+//! Intermittent field faults are hard to reproduce, so when a controller raises
+//! [`EventKind::Fault`](crate::control::EventKind::Fault) the dispatcher freezes the axis's last
+//! known motion state, the driver fault registers and the recent command history into a
+//! [`FaultSnapshot`]. It stays in RAM until the next fault on that axis and is read back with
+//! `MSG_GET_FAULT_SNAPSHOT`.
+
+use crate::protocol::history::{CommandHistory, HISTORY_LEN};
+
+/// Serialized header length (see [`FaultSnapshot::header_bytes`]).
+pub const SNAPSHOT_HEADER_LEN: usize = 18;
+
+/// Command IDs per history frame.
+pub const HISTORY_CHUNK: usize = 16;
+
+/// Marker for a value that was not available.
+pub const NOT_AVAILABLE: i16 = i16::MAX;
+
+/// Last good motion state of an axis, updated every control step.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FaultRecorder {
+    position_mm: Option<f32>,
+    velocity_mm_s: f32,
+    duty: f32,
+}
+
+impl FaultRecorder {
+    pub const fn new() -> Self {
+        Self {
+            position_mm: None,
+            velocity_mm_s: 0.0,
+            duty: 0.0,
+        }
+    }
+
+    /// Record one control step. `velocity_mm_s` should come from an observer (see
+    /// [`LinearController::velocity`](crate::control::LinearController::velocity)). Samples
+    /// without a position are ignored, so a snapshot taken after feedback is lost still shows
+    /// the state leading up to the loss.
+    pub fn observe(&mut self, position_mm: Option<f32>, velocity_mm_s: f32, duty: f32) {
+        let Some(pos) = position_mm else {
+            return;
+        };
+        self.velocity_mm_s = velocity_mm_s;
+        self.position_mm = Some(pos);
+        self.duty = duty;
+    }
+
+    /// Freeze the recorded state into a snapshot.
+    pub fn capture(
+        &self,
+        axis: u8,
+        code: u8,
+        timestamp_us: u32,
+        driver_fault: u8,
+        driver_diag: u8,
+        history: &CommandHistory,
+    ) -> FaultSnapshot {
+        let mut commands = [0u8; HISTORY_LEN];
+        let command_count = history.snapshot(&mut commands);
+        let centi = |v: f32| (v * 100.0).clamp(i16::MIN as f32, (i16::MAX - 1) as f32) as i16;
+        FaultSnapshot {
+            axis,
+            code,
+            timestamp_us,
+            position_centi: self.position_mm.map_or(NOT_AVAILABLE, centi),
+            velocity_centi: centi(self.velocity_mm_s),
+            duty_milli: (self.duty.clamp(-1.0, 1.0) * 1000.0) as i16,
+            current_ma: u16::MAX,
+            driver_fault,
+            driver_diag,
+            commands,
+            command_count: command_count as u8,
+        }
+    }
+}
+
+/// Frozen state of one axis at the moment it faulted.
+#[derive(Copy, Clone, Debug)]
+pub struct FaultSnapshot {
+    pub axis: u8,
+    /// Fault code from the [`Event`](crate::control::Event).
+    pub code: u8,
+    pub timestamp_us: u32,
+    /// Last measured position in 0.01 mm, or [`NOT_AVAILABLE`].
+    pub position_centi: i16,
+    /// Measured velocity in 0.01 mm/s.
+    pub velocity_centi: i16,
+    /// Commanded duty in 1/1000 of full scale, signed.
+    pub duty_milli: i16,
+    /// Motor current, or `u16::MAX` where the board has no current sense.
+    pub current_ma: u16,
+    /// DRV8873 FAULT and DIAG registers, 0 where the driver can't be read.
+    pub driver_fault: u8,
+    pub driver_diag: u8,
+    /// Executed command IDs, oldest first.
+    pub commands: [u8; HISTORY_LEN],
+    pub command_count: u8,
+}
+
+impl FaultSnapshot {
+    /// `[axis, code, timestamp_us: u32, position: i16, velocity: i16, duty: i16, current: u16,
+    /// fault, diag, command_count, reserved]`, little-endian.
+    pub fn header_bytes(&self) -> [u8; SNAPSHOT_HEADER_LEN] {
+        let mut out = [0u8; SNAPSHOT_HEADER_LEN];
+        out[0] = self.axis;
+        out[1] = self.code;
+        out[2..6].copy_from_slice(&self.timestamp_us.to_le_bytes());
+        out[6..8].copy_from_slice(&self.position_centi.to_le_bytes());
+        out[8..10].copy_from_slice(&self.velocity_centi.to_le_bytes());
+        out[10..12].copy_from_slice(&self.duty_milli.to_le_bytes());
+        out[12..14].copy_from_slice(&self.current_ma.to_le_bytes());
+        out[14] = self.driver_fault;
+        out[15] = self.driver_diag;
+        out[16] = self.command_count;
+        out
+    }
+
+    /// History frame `index` (0 or 1): `[axis, index, 16 command IDs]`.
+    pub fn history_bytes(&self, index: usize) -> [u8; 2 + HISTORY_CHUNK] {
+        let mut out = [0u8; 2 + HISTORY_CHUNK];
+        out[0] = self.axis;
+        out[1] = index as u8;
+        let start = index * HISTORY_CHUNK;
+        if let Some(chunk) = self.commands.get(start..start + HISTORY_CHUNK) {
+            out[2..].copy_from_slice(chunk);
+        }
+        out
+    }
+}
@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Complementary fusion of lift actuator feedback and ToF height.
+//!
+//! The actuator feedback is fast and smooth but only relative: after a slip it is offset from the
+//! real platform height for good. The ToF sensor is absolute but slow and noisy. [`HeightFusion`]
+//! propagates the estimate with feedback deltas every control step and pulls it toward each ToF
+//! reading by a fixed gain, so the feedback supplies the high-frequency motion and the ToF the
+//! long-term level.
+//!
+//! ToF readings far from the estimate are treated as outliers (a hand over the sensor). If they
+//! persist, the estimate is re-seeded from the ToF, since a consistent jump means the feedback
+//! slipped rather than the ToF being wrong.
+//!
+//! The estimate is in ToF millimeters (distance from the sensor to the platform reflector).
+
+/// Consecutive outliers after which the estimate is re-seeded from the ToF.
+const RESEED_AFTER: u8 = 5;
+
+pub struct HeightFusion {
+    /// ToF millimeters per millimeter of actuator feedback.
+    mm_per_mm: f32,
+    /// Fraction of each ToF innovation applied (0..1].
+    tof_gain: f32,
+    /// Innovations larger than this are outliers.
+    max_innovation_mm: f32,
+    estimate: Option<f32>,
+    last_feedback: Option<f32>,
+    outliers: u8,
+}
+
+impl HeightFusion {
+    /// `mm_per_mm` is the ToF change per millimeter of actuator travel. `tof_gain` sets how fast
+    /// the estimate converges to the ToF (0.1 at 10 Hz ≈ 1 s time constant).
+    pub fn new(mm_per_mm: f32, tof_gain: f32, max_innovation_mm: f32) -> Self {
+        Self {
+            mm_per_mm,
+            tof_gain: tof_gain.clamp(0.001, 1.0),
+            max_innovation_mm,
+            estimate: None,
+            last_feedback: None,
+            outliers: 0,
+        }
+    }
+
+    /// Propagate the estimate with the actuator feedback. Call every control step. Lost feedback
+    /// holds the estimate; the next valid reading resumes from there.
+    pub fn predict(&mut self, feedback_mm: Option<f32>) {
+        if let (Some(est), Some(prev), Some(fb)) = (self.estimate, self.last_feedback, feedback_mm)
+        {
+            self.estimate = Some(est + self.mm_per_mm * (fb - prev));
+        }
+        self.last_feedback = feedback_mm;
+    }
+
+    /// Correct the estimate with a ToF reading (`None` for no valid reading).
+    pub fn correct(&mut self, tof_mm: Option<u16>) {
+        let Some(tof) = tof_mm.map(|t| t as f32) else {
+            return;
+        };
+        let Some(est) = self.estimate else {
+            self.estimate = Some(tof);
+            return;
+        };
+
+        let innovation = tof - est;
+        if innovation.abs() > self.max_innovation_mm {
+            self.outliers = self.outliers.saturating_add(1);
+            if self.outliers >= RESEED_AFTER {
+                self.estimate = Some(tof);
+                self.outliers = 0;
+            }
+            return;
+        }
+        self.outliers = 0;
+        self.estimate = Some(est + self.tof_gain * innovation);
+    }
+
+    /// Fused height in ToF millimeters, or `None` before the first ToF reading.
+    pub fn height_mm(&self) -> Option<f32> {
+        self.estimate
+    }
+
+    /// Discard the estimate; the next ToF reading seeds it again.
+    pub fn reset(&mut self) {
+        self.estimate = None;
+        self.last_feedback = None;
+        self.outliers = 0;
+    }
+}
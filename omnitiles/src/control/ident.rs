@@ -0,0 +1,211 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Open-loop system identification runs.
+//!
+//! Tuning by trial and error on the tile is slow and hard to repeat. Instead the host can start
+//! an identification run on one axis: the position loop lets go, the axis is driven with a small
+//! zero-mean test signal, and every control step streams the applied duty and the measured
+//! position as a `MSG_IDENT_SAMPLE` frame. The recording is fitted to a plant model offline
+//! (e.g. a first-order-plus-integrator for the Actuonix actuators) and gains are computed from
+//! the model analytically.
+//!
+//! Two signals are available:
+//!
+//! - [`Signal::Prbs`]: a pseudo-random binary sequence of ±amplitude from a 9-bit maximal-length
+//!   LFSR (511 bits before it repeats), each bit held for `param` control steps. Broadband and
+//!   cheap; longer holds move more energy to low frequencies.
+//! - [`Signal::Chirp`]: a sine of the given amplitude whose frequency sweeps linearly from
+//!   [`CHIRP_START_HZ`] to `param` × 0.1 Hz over the run.
+//!
+//! Runs stay within safe limits: the amplitude is capped at [`MAX_AMPLITUDE`] (and by the
+//! caller at the axis's duty limit), a run lasts at most [`MAX_DURATION_S`], the actuator's soft
+//! limits still apply, and the run is aborted if the axis comes within [`LIMIT_MARGIN_MM`] of
+//! either end of its range or loses position feedback.
+
+use core::f32::consts::PI;
+use micromath::F32Ext;
+
+/// Serialized `MSG_IDENT_SAMPLE` payload length.
+pub const IDENT_SAMPLE_LEN: usize = 9;
+/// Largest test signal amplitude, as a duty.
+pub const MAX_AMPLITUDE: f32 = 0.5;
+/// Longest run.
+pub const MAX_DURATION_S: u8 = 60;
+/// Distance from either end of the range at which a run is aborted.
+pub const LIMIT_MARGIN_MM: f32 = 2.0;
+/// Frequency a chirp starts at.
+pub const CHIRP_START_HZ: f32 = 0.1;
+
+/// Test signal of a run, the `signal` byte of `MSG_IDENTIFY`.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Signal {
+    /// Stop a run in progress.
+    Off = 0,
+    Prbs = 1,
+    Chirp = 2,
+}
+
+impl Signal {
+    pub fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(Signal::Off),
+            1 => Some(Signal::Prbs),
+            2 => Some(Signal::Chirp),
+            _ => None,
+        }
+    }
+}
+
+/// Why a run ended.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum End {
+    /// The run lasted its full duration.
+    Done,
+    /// The axis came too close to the end of its range.
+    Limit,
+    /// Position feedback was lost.
+    NoFeedback,
+}
+
+/// Outcome of one [`IdentRun::step`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum IdentStep {
+    /// Drive the axis at `duty` until the next step and send `sample` to the host.
+    Drive {
+        duty: f32,
+        sample: [u8; IDENT_SAMPLE_LEN],
+    },
+    /// Brake the axis; the run is over.
+    End(End),
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Excitation {
+    Prbs { lfsr: u16, hold: u8, left: u8 },
+    Chirp { end_hz: f32, phase: f32 },
+}
+
+/// One identification run on one axis.
+#[derive(Copy, Clone, Debug)]
+pub struct IdentRun {
+    axis: u8,
+    excitation: Excitation,
+    amplitude: f32,
+    duration_s: f32,
+    elapsed_s: f32,
+    index: u16,
+    min_mm: f32,
+    max_mm: f32,
+}
+
+impl IdentRun {
+    /// Start a run on `axis` whose range is `min_mm..=max_mm`. `amplitude` is clamped to
+    /// [`MAX_AMPLITUDE`] and `duration_s` to [`MAX_DURATION_S`]. `param` is the PRBS bit length
+    /// in steps or the chirp end frequency in 0.1 Hz. Returns `None` for [`Signal::Off`] or a
+    /// zero `param`.
+    pub fn new(
+        axis: u8,
+        signal: Signal,
+        amplitude: f32,
+        param: u8,
+        duration_s: u8,
+        (min_mm, max_mm): (f32, f32),
+    ) -> Option<Self> {
+        if param == 0 {
+            return None;
+        }
+        let excitation = match signal {
+            Signal::Off => return None,
+            Signal::Prbs => Excitation::Prbs {
+                lfsr: 0x1FF,
+                hold: param,
+                left: 0,
+            },
+            Signal::Chirp => Excitation::Chirp {
+                end_hz: param as f32 * 0.1,
+                phase: 0.0,
+            },
+        };
+        Some(Self {
+            axis,
+            excitation,
+            amplitude: amplitude.clamp(0.0, MAX_AMPLITUDE),
+            duration_s: duration_s.min(MAX_DURATION_S) as f32,
+            elapsed_s: 0.0,
+            index: 0,
+            min_mm: min_mm + LIMIT_MARGIN_MM,
+            max_mm: max_mm - LIMIT_MARGIN_MM,
+        })
+    }
+
+    pub fn axis(&self) -> u8 {
+        self.axis
+    }
+
+    /// Advance by `dt` seconds with the axis at `position` (mm).
+    pub fn step(&mut self, position: Option<f32>, dt: f32) -> IdentStep {
+        let Some(position) = position else {
+            return IdentStep::End(End::NoFeedback);
+        };
+        if !(self.min_mm..=self.max_mm).contains(&position) {
+            return IdentStep::End(End::Limit);
+        }
+        if self.elapsed_s >= self.duration_s {
+            return IdentStep::End(End::Done);
+        }
+        let duty = self.next_input(dt);
+        let sample = self.sample(duty, position);
+        self.elapsed_s += dt;
+        self.index = self.index.wrapping_add(1);
+        IdentStep::Drive { duty, sample }
+    }
+
+    fn next_input(&mut self, dt: f32) -> f32 {
+        match &mut self.excitation {
+            Excitation::Prbs { lfsr, hold, left } => {
+                if *left == 0 {
+                    // x⁹ + x⁵ + 1
+                    let bit = (*lfsr ^ (*lfsr >> 4)) & 1;
+                    *lfsr = (*lfsr >> 1) | (bit << 8);
+                    *left = *hold;
+                }
+                *left -= 1;
+                if *lfsr & 1 != 0 {
+                    self.amplitude
+                } else {
+                    -self.amplitude
+                }
+            }
+            Excitation::Chirp { end_hz, phase } => {
+                let progress = self.elapsed_s / self.duration_s;
+                let hz = CHIRP_START_HZ + (*end_hz - CHIRP_START_HZ) * progress;
+                let out = self.amplitude * phase.sin();
+                *phase = (*phase + 2.0 * PI * hz * dt) % (2.0 * PI);
+                out
+            }
+        }
+    }
+
+    /// `[axis, index: u16, t_ms: u16, duty: i16 (1/1000), position: i16 (0.01 mm)]`: the
+    /// position measured at the start of the step and the duty applied from then on. `t_ms` is
+    /// the time since the run started; gaps in `index` are lost frames.
+    fn sample(&self, duty: f32, position: f32) -> [u8; IDENT_SAMPLE_LEN] {
+        let index = self.index.to_le_bytes();
+        let t = ((self.elapsed_s * 1000.0) as u32 as u16).to_le_bytes();
+        let duty = ((duty * 1000.0) as i16).to_le_bytes();
+        let position = ((position * 100.0) as i16).to_le_bytes();
+        [
+            self.axis,
+            index[0],
+            index[1],
+            t[0],
+            t[1],
+            duty[0],
+            duty[1],
+            position[0],
+            position[1],
+        ]
+    }
+}
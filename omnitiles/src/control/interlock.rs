@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Cross-axis motion interlocks.
+//!
+//! Some axis combinations collide mechanically, e.g. the top can't tilt until the lift has raised
+//! it clear of the frame. A [`Rule`] gates motion on one axis on the position of another: the
+//! gated axis may only move while the source axis is inside `[min, max]`. Several rules may gate
+//! the same axis; all of them must hold. A collision that can happen from either side needs a
+//! rule per direction, e.g. "tilt only above 40 mm" and "lift only while level".
+//!
+//! The dispatcher rejects motion commands whose axis is blocked, and the main loop stops an axis
+//! that is still moving when a rule stops holding (the source drifted or was driven out of its
+//! window). An axis whose position is unknown never satisfies a rule, so a feedback fault blocks
+//! what it gates rather than letting it through.
+//!
+//! Axes are numbered as in events: 1 = M1, 2 = M2, 3 = tilt. Windows are in 0.01 mm for the lifts
+//! and 0.01° for tilt, the units of the relative move commands.
+
+/// Number of rules kept in the configuration.
+pub const INTERLOCK_SLOTS: usize = 4;
+/// Serialized [`Rule`] length.
+pub const INTERLOCK_RULE_LEN: usize = 6;
+/// Highest axis number a rule can name.
+pub const MAX_AXIS: u8 = 3;
+
+/// Motion on `axis` is allowed only while `source` is within `[min, max]`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rule {
+    /// Gated axis, 0 = slot unused.
+    pub axis: u8,
+    /// Axis whose position is checked.
+    pub source: u8,
+    /// Lower bound in 0.01 source units, inclusive.
+    pub min: i16,
+    /// Upper bound in 0.01 source units, inclusive.
+    pub max: i16,
+}
+
+impl Rule {
+    /// An unused slot.
+    pub const NONE: Self = Self {
+        axis: 0,
+        source: 0,
+        min: 0,
+        max: 0,
+    };
+
+    pub fn is_active(&self) -> bool {
+        self.axis != 0
+    }
+
+    /// True for an unused slot or a rule between two distinct known axes with an ordered window.
+    pub fn is_valid(&self) -> bool {
+        !self.is_active()
+            || ((1..=MAX_AXIS).contains(&self.axis)
+                && (1..=MAX_AXIS).contains(&self.source)
+                && self.source != self.axis
+                && self.min <= self.max)
+    }
+
+    /// True if a source at `position` (mm or degrees) lets the gated axis move.
+    pub fn permits(&self, position: Option<f32>) -> bool {
+        match position {
+            Some(p) => {
+                let p = p * 100.0;
+                p >= self.min as f32 && p <= self.max as f32
+            }
+            None => false,
+        }
+    }
+
+    /// `[axis, source, min: i16 LE, max: i16 LE]`.
+    pub fn to_bytes(&self) -> [u8; INTERLOCK_RULE_LEN] {
+        let min = self.min.to_le_bytes();
+        let max = self.max.to_le_bytes();
+        [self.axis, self.source, min[0], min[1], max[0], max[1]]
+    }
+
+    /// Inverse of [`to_bytes`](Self::to_bytes). Invalid rules decode as unused.
+    pub fn from_bytes(b: [u8; INTERLOCK_RULE_LEN]) -> Self {
+        let rule = Self {
+            axis: b[0],
+            source: b[1],
+            min: i16::from_le_bytes([b[2], b[3]]),
+            max: i16::from_le_bytes([b[4], b[5]]),
+        };
+        if rule.is_valid() {
+            rule
+        } else {
+            Self::NONE
+        }
+    }
+}
+
+/// The active rule set.
+pub struct Interlocks {
+    rules: [Rule; INTERLOCK_SLOTS],
+}
+
+impl Interlocks {
+    pub fn new(rules: [Rule; INTERLOCK_SLOTS]) -> Self {
+        Self { rules }
+    }
+
+    /// Replace one slot. Out-of-range slots are ignored.
+    pub fn set(&mut self, slot: usize, rule: Rule) {
+        if let Some(r) = self.rules.get_mut(slot) {
+            *r = rule;
+        }
+    }
+
+    pub fn rules(&self) -> &[Rule; INTERLOCK_SLOTS] {
+        &self.rules
+    }
+
+    /// Check whether `axis` may move, given each axis's position (`None` if unknown). Returns the
+    /// first rule that doesn't hold.
+    pub fn check(&self, axis: u8, position: impl Fn(u8) -> Option<f32>) -> Result<(), Rule> {
+        match self
+            .rules
+            .iter()
+            .find(|r| r.is_active() && r.axis == axis && !r.permits(position(r.source)))
+        {
+            Some(&rule) => Err(rule),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,12 +1,34 @@
 // SPDX-License-Identifier: MIT
 // © 2025-2026 Christopher Liu
 
-//! PID position control for Actuonix linear actuators.
+//! PID position control for any [`ClosedLoopAxis`], written for the Actuonix linear actuators.
+//!
+//! Position fields carry an `_mm` suffix; for rotary axes they hold the axis's native unit
+//! (output revolutions) instead.
+//!
+//! The controller doesn't own its axis. Methods that touch the motor borrow it for the call, so
+//! fault polling, telemetry and manual drive can use the same axis between control steps.
 
-use crate::control::Pid;
-use crate::drivers::ActuonixLinear;
-use crate::hw::spi::CsControl;
-use stm32f7xx_hal::prelude::*;
+use crate::control::events::{Event, EventKind};
+use crate::control::{AlphaBeta, MotionLimits, Pid, Profile};
+use crate::drivers::ClosedLoopAxis;
+use crate::hw::iwdg;
+use crate::units::Mm;
+use core::task::Poll;
+use cortex_m::delay::Delay;
+
+/// Control period used by the blocking helpers.
+const BLOCKING_STEP_MS: u32 = 20;
+/// Default observer gain, see [`LinearController::with_observer`].
+const DEFAULT_OBSERVER_ALPHA: f32 = 0.5;
+/// Setpoint lead over the measured position beyond which a saturated axis re-plans its profile.
+const MAX_TRACKING_LAG_MM: f32 = 3.0;
+/// Lowest accepted derate factor. Zero would disable the profile limits altogether; stop the
+/// axis with [`LinearMode::Disabled`] instead.
+const MIN_DERATE: f32 = 0.1;
+/// Smallest derate change that is applied. A thermal derate moves a little with every reading,
+/// and re-planning the move on each would keep restarting its profile.
+const DERATE_STEP: f32 = 0.05;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum LinearMode {
@@ -15,102 +37,455 @@ pub enum LinearMode {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
 pub enum ControlError {
     /// PID was requested but no pot channels are enabled on the actuator.
-    NoPositionFeedback,
+    NoPositionFeedback = 0x01,
+    /// Two redundant feedback signals disagree (see
+    /// [`Plausibility`](crate::control::Plausibility)).
+    FeedbackMismatch = 0x02,
+    /// The axis reported a fault (see [`ClosedLoopAxis::is_faulted`]).
+    AxisFault = 0x03,
+}
+
+/// Failure of a blocking move.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// The axis did not settle on target within the timeout.
+    Timeout,
+    /// The controller faulted while moving.
+    Fault(ControlError),
+}
+
+impl From<ControlError> for MoveError {
+    fn from(e: ControlError) -> Self {
+        MoveError::Fault(e)
+    }
 }
 
-/// PID position controller for an Actuonix linear actuator. Call [`step`](Self::step) periodically.
-pub struct LinearController<
-    CS: CsControl,
-    const SLP_P: char,
-    const SLP_N: u8,
-    const DIS_P: char,
-    const DIS_N: u8,
-    Pwm1,
-    Pwm2,
-    ReadPos,
-    const N: usize,
-> {
-    pub actuator: ActuonixLinear<CS, SLP_P, SLP_N, DIS_P, DIS_N, Pwm1, Pwm2, ReadPos, N>,
+/// PID position controller for a [`ClosedLoopAxis`]. Call [`step`](Self::step) periodically
+/// with the axis it controls.
+pub struct LinearController {
     pub pid: Pid,
     pub mode: LinearMode,
 
     pub target_position_mm: f32,
     pub min_position_mm: f32,
     pub max_position_mm: f32,
+    /// Error within which a settled axis is declared on target and braked.
     pub on_target_tolerance_mm: f32,
+    /// Error beyond which an on-target axis resumes control. At least `on_target_tolerance_mm`;
+    /// the gap keeps slow drift under load from toggling the brake every step.
+    pub off_target_tolerance_mm: f32,
+
+    /// Velocity/acceleration limits for the profile generator and output duty clamp.
+    pub limits: MotionLimits,
+    /// Scale applied to `limits` while derated, see [`set_derate`](Self::set_derate).
+    derate: f32,
+    profile: Profile,
+    profile_pending: bool,
+    replan_pending: bool,
+    /// Velocity estimate for the D term and [`velocity`](Self::velocity).
+    observer: AlphaBeta,
+
+    /// Axis number reported in emitted events.
+    axis: u8,
+    on_target: bool,
+    /// Following a moving target, see [`track_target_position_mm`](Self::track_target_position_mm).
+    tracking: bool,
+    faulted: bool,
+    event: Option<Event>,
+    output: f32,
+    /// True if the last step drove the axis, false after a brake or outside position control.
+    driving: bool,
+    /// Duty held towards extend as the axis comes off the brake, see
+    /// [`with_brake_release`](Self::with_brake_release).
+    release_hold: f32,
+    release_s: f32,
+    release_left_s: f32,
+    /// Largest change of the output per second, 0 = unlimited. See
+    /// [`with_output_rate_limit`](Self::with_output_rate_limit).
+    pub max_output_rate: f32,
 }
 
-impl<
-        CS: CsControl,
-        const SLP_P: char,
-        const SLP_N: u8,
-        const DIS_P: char,
-        const DIS_N: u8,
-        Pwm1,
-        Pwm2,
-        ReadPos,
-        const N: usize,
-    > LinearController<CS, SLP_P, SLP_N, DIS_P, DIS_N, Pwm1, Pwm2, ReadPos, N>
-where
-    Pwm1: _embedded_hal_PwmPin<Duty = u16>,
-    Pwm2: _embedded_hal_PwmPin<Duty = u16>,
-    ReadPos: FnMut() -> [u16; N],
-{
+impl LinearController {
     /// Create a new linear controller with PID gains and limits.
     pub fn new(
-        actuator: ActuonixLinear<CS, SLP_P, SLP_N, DIS_P, DIS_N, Pwm1, Pwm2, ReadPos, N>,
         pid: Pid,
         min_position_mm: f32,
         max_position_mm: f32,
         on_target_tolerance_mm: f32,
     ) -> Self {
         Self {
-            actuator,
             pid,
             mode: LinearMode::PositionControl,
             target_position_mm: 0.0,
             min_position_mm,
             max_position_mm,
             on_target_tolerance_mm,
+            off_target_tolerance_mm: on_target_tolerance_mm,
+            limits: MotionLimits::UNLIMITED,
+            profile: Profile::new(),
+            profile_pending: true,
+            replan_pending: false,
+            derate: 1.0,
+            observer: AlphaBeta::critically_damped(DEFAULT_OBSERVER_ALPHA),
+            axis: 0,
+            on_target: false,
+            tracking: false,
+            faulted: false,
+            event: None,
+            output: 0.0,
+            driving: false,
+            release_hold: 0.0,
+            release_s: 0.0,
+            release_left_s: 0.0,
+            max_output_rate: 0.0,
         }
     }
 
+    /// Set the axis number reported in [`Event`]s (1 = M1, 2 = M2).
+    pub fn with_axis(mut self, axis: u8) -> Self {
+        self.axis = axis;
+        self
+    }
+
+    /// Set the deadband exit threshold (see
+    /// [`off_target_tolerance_mm`](Self::off_target_tolerance_mm)). Clamped to at least the
+    /// on-target tolerance.
+    pub fn with_hysteresis(mut self, off_target_tolerance_mm: f32) -> Self {
+        self.off_target_tolerance_mm = off_target_tolerance_mm.max(self.on_target_tolerance_mm);
+        self
+    }
+
+    /// Replace the position/velocity observer. Lower gains smooth noisy feedback more at the
+    /// cost of lag in the D term.
+    pub fn with_observer(mut self, observer: AlphaBeta) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Ease a loaded axis off the brake.
+    ///
+    /// Between the brake letting go and the PID building up enough output, a heavy load on a lift
+    /// sags by a few millimeters. For `duration_ms` after the controller starts driving from a
+    /// standstill, the output is kept at least `hold` (towards extend, against gravity), with the
+    /// floor fading linearly to zero. Moves up are unaffected; moves down start once the floor
+    /// has faded below the PID output. `hold` is clamped to the duty limit. 0 disables it.
+    pub fn with_brake_release(mut self, hold: f32, duration_ms: u32) -> Self {
+        self.release_hold = hold.clamp(0.0, 1.0);
+        self.release_s = duration_ms as f32 / 1000.0;
+        self
+    }
+
+    /// Limit how fast the output may change, in duty per second (0 = unlimited).
+    ///
+    /// The profile limits shape the setpoint, but aggressive gains can still swing the output
+    /// from full one way to full the other in a single step, e.g. on overshoot or when a raw
+    /// target step arrives with the profile off. The slew limit applies after the PID, duty clamp
+    /// and brake-release floor, so the gearbox never sees a faster change than this; braking is
+    /// not limited. Coming off the brake or manual drive the output ramps up from zero.
+    pub fn with_output_rate_limit(mut self, duty_per_s: f32) -> Self {
+        self.max_output_rate = duty_per_s.max(0.0);
+        self
+    }
+
+    /// Set the motion limits (mm/s, mm/s², duty).
+    pub fn with_limits(mut self, limits: MotionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     /// Set a new target position (mm), automatically clamped to limits.
+    ///
+    /// A target given mid-move is blended into the move in progress: the profile carries its
+    /// current setpoint and velocity over to the new target and the PID keeps its state, so the
+    /// axis bends onto the new path instead of jerking to a stop and starting again. From rest the
+    /// profile restarts at the measured position.
     pub fn set_target_position_mm(&mut self, mm: f32) {
         self.target_position_mm = mm.clamp(self.min_position_mm, self.max_position_mm);
-        self.pid.reset();
+        self.tracking = false;
+        if !self.is_moving() {
+            self.pid.reset();
+            self.profile_pending = true;
+        }
+        self.on_target = false;
+    }
+
+    /// Follow a target that moves every step, such as the sine of a
+    /// [`BodeTest`](crate::control::bode::BodeTest).
+    ///
+    /// Unlike [`set_target_position_mm`](Self::set_target_position_mm) this never restarts the
+    /// move, so the PID and profile keep their state from step to step. The on-target deadband is
+    /// suspended while tracking, since a moving target is never reached and braking at every
+    /// crossing would distort the response; the next `set_target_position_mm` ends tracking.
+    pub fn track_target_position_mm(&mut self, mm: f32) {
+        self.target_position_mm = mm.clamp(self.min_position_mm, self.max_position_mm);
+        self.tracking = true;
+        self.on_target = false;
+    }
+
+    /// True while a profiled move is under way.
+    fn is_moving(&self) -> bool {
+        self.mode == LinearMode::PositionControl
+            && !self.profile_pending
+            && self.profile.velocity() != 0.0
+    }
+
+    /// Typed variant of [`set_target_position_mm`](Self::set_target_position_mm).
+    #[inline]
+    pub fn set_target(&mut self, position: Mm) {
+        self.set_target_position_mm(position.get());
+    }
+
+    /// Shift the target by `delta`, clamped to limits.
+    ///
+    /// Outside position control the move is taken relative to the measured position (or the
+    /// stale target without feedback), so a nudge after manual jogging starts from where the
+    /// actuator actually is.
+    pub fn move_relative<A: ClosedLoopAxis>(&mut self, motor: &mut A, delta: Mm) {
+        let base = match self.mode {
+            LinearMode::PositionControl => self.target(),
+            _ => motor.position().map(Mm).unwrap_or(self.target()),
+        };
+        self.set_target(base + delta);
+    }
+
+    /// Scale the motion limits by `factor` (clamped to 0.1..=1.0), e.g. while the driver is hot or
+    /// current-limited. Velocity, acceleration and duty all shrink by the same factor, and a move
+    /// in progress is re-planned from the measured state on the next step, so the setpoint slows
+    /// down smoothly instead of running ahead of an axis that can no longer follow it.
+    ///
+    /// Changes smaller than 0.05 are ignored, except those that reach either end of the range.
+    pub fn set_derate(&mut self, factor: f32) {
+        let factor = factor.clamp(MIN_DERATE, 1.0);
+        let at_end = factor == 1.0 || factor == MIN_DERATE;
+        if (factor - self.derate).abs() >= DERATE_STEP || (at_end && factor != self.derate) {
+            self.derate = factor;
+            self.replan_pending = true;
+        }
+    }
+
+    /// Current derate factor, 1.0 when not derated.
+    #[inline]
+    pub fn derate(&self) -> f32 {
+        self.derate
+    }
+
+    /// `limits` with the derate factor applied.
+    pub fn effective_limits(&self) -> MotionLimits {
+        MotionLimits {
+            max_velocity: self.limits.max_velocity * self.derate,
+            max_accel: self.limits.max_accel * self.derate,
+            max_duty: self.limits.max_duty * self.derate,
+        }
+    }
+
+    /// Current target position, after clamping.
+    #[inline]
+    pub fn target(&self) -> Mm {
+        Mm(self.target_position_mm)
+    }
+
+    /// Estimated velocity in mm/s, 0 before the first position sample.
+    #[inline]
+    pub fn velocity(&self) -> f32 {
+        self.observer.velocity()
+    }
+
+    /// Duty applied by the last [`step`](Self::step), 0 if it braked or left the axis to manual
+    /// drive.
+    #[inline]
+    pub fn output(&self) -> f32 {
+        self.output
+    }
+
+    /// True while the axis is in position control and settled at its target.
+    #[inline]
+    pub fn on_target(&self) -> bool {
+        self.on_target
+    }
+
+    /// True while `step` is failing (e.g. lost position feedback or an axis fault).
+    #[inline]
+    pub fn is_faulted(&self) -> bool {
+        self.faulted
+    }
+
+    /// Take the most recent unreported event, if any.
+    ///
+    /// [`EventKind::TargetReached`] is latched once per target, on the step where the axis
+    /// settles. [`EventKind::Fault`] is latched once when `step` starts failing.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.event.take()
+    }
+
+    fn emit(&mut self, kind: EventKind, code: u8) {
+        self.event = Some(Event::new(self.axis, kind, code));
+    }
+
+    /// Brake and latch a fault event the first time `step` fails.
+    fn fail<A: ClosedLoopAxis>(
+        &mut self,
+        motor: &mut A,
+        error: ControlError,
+    ) -> Result<(), ControlError> {
+        motor.brake();
+        self.on_target = false;
+        self.profile_pending = true;
+        self.observer.reset();
+        if !self.faulted {
+            self.faulted = true;
+            self.emit(EventKind::Fault, error as u8);
+        }
+        Err(error)
     }
 
     /// Run one control step. Returns `Err(NoPositionFeedback)` if the mode is
-    /// `PositionControl` but the actuator has no enabled pot channels; in that
-    /// case the actuator is braked for safety.
-    pub fn step(&mut self, dt: f32) -> Result<(), ControlError> {
-        self.actuator.enforce_limits();
+    /// `PositionControl` but the actuator has no position feedback, or `Err(AxisFault)` if the
+    /// actuator reports a fault; in both cases the actuator is braked for safety.
+    pub fn step<A: ClosedLoopAxis>(&mut self, motor: &mut A, dt: f32) -> Result<(), ControlError> {
+        motor.pre_step();
+        let prev_output = core::mem::replace(&mut self.output, 0.0);
+        let was_driving = core::mem::replace(&mut self.driving, false);
 
         match self.mode {
-            LinearMode::Disabled => Ok(()),
+            LinearMode::Disabled => {
+                // Keep the velocity estimate current during manual drive.
+                match motor.position() {
+                    Some(position_mm) => self.observer.update(position_mm, dt),
+                    None => self.observer.reset(),
+                }
+                // Manual drive moves the axis out from under the profile.
+                self.profile_pending = true;
+                self.on_target = false;
+                self.faulted = false;
+                Ok(())
+            }
 
             LinearMode::PositionControl => {
-                let Some(position_mm) = self.actuator.position_mm() else {
-                    self.actuator.brake();
-                    return Err(ControlError::NoPositionFeedback);
+                if motor.is_faulted() {
+                    return self.fail(motor, ControlError::AxisFault);
+                }
+                let Some(position_mm) = motor.position() else {
+                    return self.fail(motor, ControlError::NoPositionFeedback);
                 };
+                self.faulted = false;
+                self.observer.update(position_mm, dt);
                 let target = self
                     .target_position_mm
                     .clamp(self.min_position_mm, self.max_position_mm);
                 let error = target - position_mm;
 
-                if error.abs() <= self.on_target_tolerance_mm {
-                    self.actuator.brake();
+                if self.profile_pending {
+                    self.profile.reset(position_mm);
+                    self.profile_pending = false;
+                    self.replan_pending = false;
+                } else if self.replan_pending {
+                    self.profile.resume(position_mm, self.observer.velocity());
+                    self.replan_pending = false;
+                }
+                let limits = self.effective_limits();
+                let setpoint = self.profile.update(target, &limits, dt);
+
+                let tolerance = if self.on_target {
+                    self.off_target_tolerance_mm
+                } else {
+                    self.on_target_tolerance_mm
+                };
+                if !self.tracking && error.abs() <= tolerance && self.profile.is_done(target) {
+                    motor.brake();
+                    if !self.on_target {
+                        self.on_target = true;
+                        self.emit(EventKind::TargetReached, 0);
+                    }
                     return Ok(());
                 }
+                if self.on_target {
+                    // Drifted out of the deadband: restart the correction from rest.
+                    self.on_target = false;
+                    self.pid.reset();
+                }
 
-                let output = self.pid.update(target, position_mm, dt);
-                self.actuator.set_speed(output);
+                let max_duty = limits.max_duty.clamp(0.0, 1.0);
+                let raw =
+                    self.pid
+                        .update_with_rate(setpoint, position_mm, self.observer.velocity(), dt);
+                let mut output = raw.clamp(-max_duty, max_duty);
+                if !was_driving {
+                    self.release_left_s = self.release_s;
+                }
+                if self.release_left_s > 0.0 {
+                    let floor =
+                        self.release_hold.min(max_duty) * self.release_left_s / self.release_s;
+                    output = output.max(floor);
+                    self.release_left_s -= dt;
+                }
+                if self.max_output_rate > 0.0 {
+                    let max_change = self.max_output_rate * dt;
+                    output = output.clamp(prev_output - max_change, prev_output + max_change);
+                }
+                // Saturated and falling behind: the trajectory is out of reach with the duty
+                // available. Re-plan from where the axis actually is, so the setpoint doesn't run
+                // away and wind up the integrator. The integrator itself is kept: it carries the
+                // load, and clearing it would let the axis sag at every re-plan.
+                if raw.abs() > max_duty && (setpoint - position_mm).abs() > MAX_TRACKING_LAG_MM {
+                    self.replan_pending = true;
+                }
+                motor.apply_output(output);
+                self.output = output;
+                self.driving = true;
                 Ok(())
             }
         }
     }
+
+    /// Move to `position` and block until on target, running the control loop at a fixed period.
+    ///
+    /// Intended for scripted demos and test binaries, not the main loop. On success the axis is
+    /// left holding position; on error it is braked and disabled.
+    pub fn move_to_blocking<A: ClosedLoopAxis>(
+        &mut self,
+        motor: &mut A,
+        position: Mm,
+        timeout_ms: u32,
+        delay: &mut Delay,
+    ) -> Result<(), MoveError> {
+        self.mode = LinearMode::PositionControl;
+        self.set_target(position);
+        self.wait_on_target_blocking(motor, timeout_ms, delay)
+    }
+
+    /// Keep stepping the controller until it reports on target, a fault or `timeout_ms` elapses,
+    /// feeding the watchdog between steps.
+    pub fn wait_on_target_blocking<A: ClosedLoopAxis>(
+        &mut self,
+        motor: &mut A,
+        timeout_ms: u32,
+        delay: &mut Delay,
+    ) -> Result<(), MoveError> {
+        let dt = BLOCKING_STEP_MS as f32 / 1000.0;
+        let mut elapsed = 0;
+        iwdg::block_on(delay, BLOCKING_STEP_MS, |step_ms| {
+            elapsed += step_ms;
+            let result = self.step(motor, dt).map_err(MoveError::from).and_then(|_| {
+                if self.on_target {
+                    Ok(true)
+                } else if elapsed >= timeout_ms {
+                    Err(MoveError::Timeout)
+                } else {
+                    Ok(false)
+                }
+            });
+            match result {
+                Ok(true) => Poll::Ready(Ok(())),
+                Ok(false) => Poll::Pending,
+                Err(e) => {
+                    self.mode = LinearMode::Disabled;
+                    motor.brake();
+                    Poll::Ready(Err(e))
+                }
+            }
+        })
+    }
 }
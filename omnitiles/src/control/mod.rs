@@ -8,13 +8,61 @@
 //! ## Modules
 //!
 //! - [`pid`] - General-purpose PID controller implementation.
-//! - [`linear_controller`] - Closed-loop position controller for Actuonix linear actuators.
+//! - [`observer`] - Alpha–beta observer for smoothed position and velocity.
+//! - [`linear_controller`] - Closed-loop position controller for any `ClosedLoopAxis`.
+//! - [`events`] - Controller events (target reached, homing done, faults) and an event queue.
+//! - [`tilt_controller`] - Position commands and move-complete detection for the GIM6010 tilt axis.
+//! - [`profile`] - Trapezoidal motion profile generator and per-axis motion limits.
+//! - [`stats`] - Cumulative per-motor usage counters (travel, reversals, load time, OCP trips).
+//! - [`height_fusion`] - Complementary fusion of lift actuator feedback with ToF height.
+//! - [`fault_snapshot`] - Post-mortem capture of axis state and command history on a fault.
+//! - [`plausibility`] - Time-filtered agreement check between two redundant feedback signals.
+//! - [`schedule`] - Interval and daily triggers that run built-in motion sequences unattended.
+//! - [`interlock`] - Rules that gate motion on one axis on the position of another.
+//! - [`envelope`] - Lift height vs tilt angle combinations that clear the frame.
+//! - [`current_loop`] - Inner PI current loop run from a timer interrupt, for torque control.
+//! - [`current_limit`] - Motion derate while the motor driver is current-limited.
+//! - [`ident`] - Open-loop PRBS and chirp runs that record input/output data for tuning.
+//! - [`bode`] - Closed-loop sine sweep measuring gain and phase to check stability margins.
+//! - [`cooling`] - Fan duty from temperature, by hysteresis or a PI loop.
 
 pub mod base_controller;
+pub mod bode;
+pub mod cooling;
+pub mod current_limit;
+pub mod current_loop;
+pub mod envelope;
+pub mod events;
+pub mod fault_snapshot;
+pub mod height_fusion;
+pub mod ident;
+pub mod interlock;
 pub mod linear_controller;
 pub mod mecanum;
+pub mod observer;
 pub mod pid;
+pub mod plausibility;
+pub mod profile;
+pub mod schedule;
+pub mod stats;
+pub mod tilt_controller;
 
 pub use base_controller::BaseController;
-pub use linear_controller::{LinearController, LinearMode};
+pub use bode::BodeTest;
+pub use cooling::{Cooling, FanMode};
+pub use current_limit::CurrentDerate;
+pub use current_loop::{CurrentCommand, CurrentLoop};
+pub use envelope::Envelope;
+pub use events::{Event, EventKind, EventQueue};
+pub use fault_snapshot::{FaultRecorder, FaultSnapshot};
+pub use height_fusion::HeightFusion;
+pub use ident::IdentRun;
+pub use interlock::Interlocks;
+pub use linear_controller::{LinearController, LinearMode, MoveError};
+pub use observer::AlphaBeta;
 pub use pid::Pid;
+pub use plausibility::{Plausibility, Verdict};
+pub use profile::{MotionLimits, Profile};
+pub use schedule::{ScheduleEntry, Scheduler, SequenceRunner};
+pub use stats::MotorStats;
+pub use tilt_controller::{HomingError, TiltController};
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Alpha–beta observer for position and velocity from noisy position samples.
+//!
+//! Differencing consecutive potentiometer or encoder readings amplifies quantization and ADC
+//! noise by `1/dt`, which makes a raw finite difference useless for a D term or a stall check at
+//! control rates. The observer instead predicts the next position from its velocity estimate and
+//! corrects both by a fraction of the prediction error:
+//!
+//! ```text
+//! x̂ ← x̂ + v̂·dt          (predict)
+//! r  = z − x̂             (residual)
+//! x̂ ← x̂ + α·r
+//! v̂ ← v̂ + (β/dt)·r
+//! ```
+//!
+//! Smaller gains smooth more but lag more. [`AlphaBeta::critically_damped`] picks `β` for a given
+//! `α` so the estimate settles without overshoot.
+
+use micromath::F32Ext;
+
+/// Alpha–beta tracking filter.
+#[derive(Copy, Clone, Debug)]
+pub struct AlphaBeta {
+    alpha: f32,
+    beta: f32,
+    position: f32,
+    velocity: f32,
+    initialized: bool,
+}
+
+impl AlphaBeta {
+    /// Observer with explicit gains. Stable for `0 < alpha <= 1` and `0 < beta < 4 - 2 * alpha`.
+    pub fn new(alpha: f32, beta: f32) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            beta: beta.max(0.0),
+            position: 0.0,
+            velocity: 0.0,
+            initialized: false,
+        }
+    }
+
+    /// Observer with `beta = 2 − α − 2√(1 − α)`, the critically damped choice for `alpha`: the
+    /// error dynamics then have a double real pole at `√(1 − α)`.
+    pub fn critically_damped(alpha: f32) -> Self {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let beta = 2.0 - alpha - 2.0 * (1.0 - alpha).sqrt();
+        Self::new(alpha, beta)
+    }
+
+    /// Forget the state; the next sample is taken as-is with zero velocity.
+    pub fn reset(&mut self) {
+        self.position = 0.0;
+        self.velocity = 0.0;
+        self.initialized = false;
+    }
+
+    /// Feed a position sample taken `dt` seconds after the previous one.
+    pub fn update(&mut self, measurement: f32, dt: f32) {
+        if !self.initialized {
+            self.position = measurement;
+            self.velocity = 0.0;
+            self.initialized = true;
+            return;
+        }
+        if dt <= 0.0 {
+            return;
+        }
+        let predicted = self.position + self.velocity * dt;
+        let residual = measurement - predicted;
+        self.position = predicted + self.alpha * residual;
+        self.velocity += self.beta / dt * residual;
+    }
+
+    /// Smoothed position.
+    #[inline]
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Estimated velocity in position units per second.
+    #[inline]
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    /// True once at least one sample has been fed since the last reset.
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
@@ -4,6 +4,28 @@
 //! Generic PID controller for closed-loop control.
 //!
 //! Works in `no_std` and does not allocate memory.
+//!
+//! The gains are continuous-time: `kp` per unit of error, `ki` per unit of error-second and `kd`
+//! per unit of error per second. Every step is discretized with the `dt` it is given (the
+//! integral accumulates `ki·e·dt`, the derivative divides by `dt`), so the same gains give the
+//! same closed-loop response whether the loop runs at 50 Hz or 1 kHz, and a late step is weighted
+//! by how late it was. Pass the measured time since the previous step, not the nominal period.
+//!
+//! A finite difference over a 1 ms step is twenty times noisier than over a 20 ms one. To keep
+//! the D term's bandwidth independent of the rate as well, give it a low-pass time constant in
+//! seconds with [`Pid::with_derivative_filter`] instead of relying on the sample period.
+//!
+//! The P and D terms act on a weighted setpoint (a two-degree-of-freedom PID):
+//!
+//! ```text
+//! u = kp·(b·r − y) + ki·∫(r − y) dt + kd·d(c·r − y)/dt
+//! ```
+//!
+//! With the defaults `b = 1`, `c = 0` this is the usual PID with the D term on the measurement,
+//! so a setpoint step doesn't kick the output through D. Lowering `b` also softens the
+//! proportional response to a step (the integral, on the full error, still removes any offset)
+//! without slowing disturbance rejection, which matters when the host sends raw steps with the
+//! motion profile off. See [`Pid::with_setpoint_weights`].
 
 /// PID controller with tunable gains and output clamping.
 pub struct Pid {
@@ -18,6 +40,16 @@ pub struct Pid {
     integral: f32,
     /// Last process variable (for derivative term)
     prev_measurement: f32,
+    /// Derivative low-pass time constant in seconds, 0 = unfiltered.
+    d_tau: f32,
+    /// Setpoint weight of the P term (`b`).
+    p_weight: f32,
+    /// Setpoint weight of the D term (`c`).
+    d_weight: f32,
+    /// Last setpoint, for the D term's setpoint rate.
+    prev_setpoint: Option<f32>,
+    /// Filtered rate of change of `c·setpoint − measurement`.
+    d_rate: f32,
 
     /// Output clamp
     out_min: f32,
@@ -42,6 +74,11 @@ impl Pid {
 
             integral: 0.0,
             prev_measurement: 0.0,
+            d_tau: 0.0,
+            p_weight: 1.0,
+            d_weight: 0.0,
+            prev_setpoint: None,
+            d_rate: 0.0,
 
             out_min: -1.0,
             out_max: 1.0,
@@ -67,10 +104,47 @@ impl Pid {
         self
     }
 
+    /// Low-pass the derivative term with time constant `tau_s` seconds (0 = off).
+    pub fn with_derivative_filter(mut self, tau_s: f32) -> Self {
+        self.d_tau = tau_s.max(0.0);
+        self
+    }
+
+    /// Weight the setpoint by `p` in the P term and `d` in the D term, each 0.0..=1.0.
+    pub fn with_setpoint_weights(mut self, p: f32, d: f32) -> Self {
+        self.set_setpoint_weights(p, d);
+        self
+    }
+
+    /// Current `(p, d)` setpoint weights.
+    pub fn setpoint_weights(&self) -> (f32, f32) {
+        (self.p_weight, self.d_weight)
+    }
+
+    /// Change the setpoint weights at runtime.
+    pub fn set_setpoint_weights(&mut self, p: f32, d: f32) {
+        self.p_weight = p.clamp(0.0, 1.0);
+        self.d_weight = d.clamp(0.0, 1.0);
+    }
+
+    /// Current `(kp, ki, kd)`.
+    pub fn gains(&self) -> (f32, f32, f32) {
+        (self.kp, self.ki, self.kd)
+    }
+
+    /// Change the gains at runtime. The integrator keeps its (clamped) state.
+    pub fn set_gains(&mut self, kp: f32, ki: f32, kd: f32) {
+        self.kp = kp;
+        self.ki = ki;
+        self.kd = kd;
+    }
+
     /// Reset integrator + derivative history.
     pub fn reset(&mut self) {
         self.integral = 0.0;
         self.prev_measurement = 0.0;
+        self.prev_setpoint = None;
+        self.d_rate = 0.0;
         self.first_update = true;
     }
 
@@ -78,14 +152,32 @@ impl Pid {
     ///
     /// `setpoint` — desired value  
     /// `measurement` — current value  
-    /// `dt` — timestep in seconds (e.g. 0.02 for 50 Hz control loop)
+    /// `dt` — time since the previous update in seconds (e.g. 0.02 for 50 Hz control loop)
     ///
     /// Returns a normalized command in [`out_min`, `out_max`] which can be mapped to motor drive.
     pub fn update(&mut self, setpoint: f32, measurement: f32, dt: f32) -> f32 {
+        // ----- D term (on measurement to reduce noise sensitivity) -----
+        let rate = if self.first_update || dt <= 0.0 || !dt.is_finite() {
+            self.first_update = false;
+            0.0
+        } else {
+            (measurement - self.prev_measurement) / dt
+        };
+        self.prev_measurement = measurement;
+
+        self.update_with_rate(setpoint, measurement, rate, dt)
+    }
+
+    /// Like [`update`](Self::update), but with the measurement's rate of change supplied by the
+    /// caller (e.g. from an [`AlphaBeta`](crate::control::AlphaBeta) observer) instead of a
+    /// finite difference of consecutive measurements.
+    pub fn update_with_rate(&mut self, setpoint: f32, measurement: f32, rate: f32, dt: f32) -> f32 {
+        // A step with no elapsed time (or a bogus one) adds nothing to the integral.
+        let dt = if dt.is_finite() { dt.max(0.0) } else { 0.0 };
         let error = setpoint - measurement;
 
-        // ----- P term -----
-        let p = self.kp * error;
+        // ----- P term (on the weighted setpoint) -----
+        let p = self.kp * (self.p_weight * setpoint - measurement);
 
         // ----- I term -----
         self.integral += error * dt * self.ki;
@@ -100,15 +192,19 @@ impl Pid {
 
         let i = self.integral;
 
-        // ----- D term (on measurement to reduce noise sensitivity) -----
-        let d = if self.first_update {
-            self.first_update = false;
-            0.0
+        // ----- D term (on the weighted setpoint, so with c = 0 setpoint steps don't kick) -----
+        let setpoint_rate = match self.prev_setpoint {
+            Some(prev) if dt > 0.0 => (setpoint - prev) / dt,
+            _ => 0.0,
+        };
+        self.prev_setpoint = Some(setpoint);
+        let d_rate = self.d_weight * setpoint_rate - rate;
+        self.d_rate = if self.d_tau > 0.0 {
+            self.d_rate + (d_rate - self.d_rate) * dt / (self.d_tau + dt)
         } else {
-            let dv = self.prev_measurement - measurement;
-            self.kd * (dv / dt)
+            d_rate
         };
-        self.prev_measurement = measurement;
+        let d = self.kd * self.d_rate;
 
         // ----- Output clamp -----
         let mut out = p + i + d;
@@ -0,0 +1,99 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Plausibility check between two redundant feedback signals.
+//!
+//! Many faults only show up as two signals that should agree no longer doing so: commanded duty
+//! with no resulting velocity (stalled or disconnected motor), an encoder that disagrees with a
+//! potentiometer (slipping coupling), or two ganged pots that drift apart (broken wiper).
+//! [`Plausibility`] compares a `reference` signal, scaled by a linear model, against a `measured`
+//! one and latches a fault once they disagree beyond the bounds for longer than a persistence
+//! window, so transients (start-up lag, noise) don't trip it.
+//!
+//! The allowed error is `abs_tolerance + rel_tolerance * |expected|`, so a check can be strict
+//! near zero and proportional at speed.
+
+/// Outcome of one [`Plausibility::update`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The signals agree, or disagree for less than the persistence window.
+    Ok,
+    /// The signals have disagreed for the full window. Reported once; see
+    /// [`Plausibility::is_faulted`]. Report it with the
+    /// [`FeedbackMismatch`](crate::control::linear_controller::ControlError::FeedbackMismatch)
+    /// fault code.
+    Fault,
+}
+
+pub struct Plausibility {
+    /// `expected = gain * reference + offset`.
+    gain: f32,
+    offset: f32,
+    abs_tolerance: f32,
+    rel_tolerance: f32,
+    persist_ms: u32,
+    /// How long the signals have disagreed without interruption.
+    disagree_ms: u32,
+    faulted: bool,
+}
+
+impl Plausibility {
+    /// Compare `measured` against `gain * reference`. A disagreement beyond `abs_tolerance`
+    /// lasting `persist_ms` is a fault.
+    pub fn new(gain: f32, abs_tolerance: f32, persist_ms: u32) -> Self {
+        Self {
+            gain,
+            offset: 0.0,
+            abs_tolerance,
+            rel_tolerance: 0.0,
+            persist_ms,
+            disagree_ms: 0,
+            faulted: false,
+        }
+    }
+
+    /// Add a constant to the expected value (e.g., a fixed mechanical sum for opposed sensors).
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Allow an additional error proportional to the expected magnitude.
+    pub fn with_rel_tolerance(mut self, rel_tolerance: f32) -> Self {
+        self.rel_tolerance = rel_tolerance.max(0.0);
+        self
+    }
+
+    /// Feed one pair of samples taken `dt_ms` after the previous pair. Missing samples reset the
+    /// persistence timer but keep a latched fault.
+    pub fn update(&mut self, reference: Option<f32>, measured: Option<f32>, dt_ms: u32) -> Verdict {
+        let (Some(r), Some(m)) = (reference, measured) else {
+            self.disagree_ms = 0;
+            return Verdict::Ok;
+        };
+        let expected = self.gain * r + self.offset;
+        let allowed = self.abs_tolerance + self.rel_tolerance * expected.abs();
+        if (m - expected).abs() <= allowed {
+            self.disagree_ms = 0;
+            return Verdict::Ok;
+        }
+
+        self.disagree_ms = self.disagree_ms.saturating_add(dt_ms);
+        if !self.faulted && self.disagree_ms >= self.persist_ms {
+            self.faulted = true;
+            return Verdict::Fault;
+        }
+        Verdict::Ok
+    }
+
+    /// True once a fault has been reported, until [`clear`](Self::clear).
+    pub fn is_faulted(&self) -> bool {
+        self.faulted
+    }
+
+    /// Clear a latched fault (after the operator has inspected the axis).
+    pub fn clear(&mut self) {
+        self.faulted = false;
+        self.disagree_ms = 0;
+    }
+}
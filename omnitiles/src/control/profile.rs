@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Online trapezoidal motion profile generator.
+//!
+//! Instead of handing the PID a step change in setpoint, the profile moves an intermediate
+//! setpoint toward the target while respecting per-axis velocity and acceleration limits. The
+//! profile is recomputed every step, so the target may change at any time.
+
+use micromath::F32Ext;
+
+/// Per-axis motion limits.
+///
+/// A value of `0.0` for `max_velocity` or `max_accel` disables that limit. `max_duty` clamps the
+/// controller output magnitude (0.0..=1.0).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MotionLimits {
+    /// Maximum setpoint velocity in units/s.
+    pub max_velocity: f32,
+    /// Maximum setpoint acceleration in units/s².
+    pub max_accel: f32,
+    /// Maximum output duty magnitude.
+    pub max_duty: f32,
+}
+
+impl MotionLimits {
+    /// No velocity or acceleration limit, full duty.
+    pub const UNLIMITED: Self = Self {
+        max_velocity: 0.0,
+        max_accel: 0.0,
+        max_duty: 1.0,
+    };
+
+    /// True if the profile generator should be bypassed.
+    #[inline]
+    pub fn is_unlimited(&self) -> bool {
+        self.max_velocity <= 0.0 || self.max_accel <= 0.0
+    }
+}
+
+impl Default for MotionLimits {
+    fn default() -> Self {
+        Self::UNLIMITED
+    }
+}
+
+/// Trapezoidal profile state: the current intermediate setpoint and its velocity.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Profile {
+    position: f32,
+    velocity: f32,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restart the profile from a measured position at rest.
+    pub fn reset(&mut self, position: f32) {
+        self.position = position;
+        self.velocity = 0.0;
+    }
+
+    /// Re-plan from a measured position and velocity, e.g. when the axis can't keep up with the
+    /// setpoint. A velocity above the limits is ramped down at the acceleration limit.
+    pub fn resume(&mut self, position: f32, velocity: f32) {
+        self.position = position;
+        self.velocity = velocity;
+    }
+
+    /// Current intermediate setpoint.
+    #[inline]
+    pub fn position(&self) -> f32 {
+        self.position
+    }
+
+    /// Current setpoint velocity.
+    #[inline]
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    /// True once the intermediate setpoint has reached `target` and stopped.
+    #[inline]
+    pub fn is_done(&self, target: f32) -> bool {
+        self.velocity == 0.0 && self.position == target
+    }
+
+    /// Advance the profile by `dt` seconds toward `target` and return the new setpoint.
+    pub fn update(&mut self, target: f32, limits: &MotionLimits, dt: f32) -> f32 {
+        if limits.is_unlimited() || dt <= 0.0 {
+            self.position = target;
+            self.velocity = 0.0;
+            return target;
+        }
+
+        let error = target - self.position;
+        let dir = if error >= 0.0 { 1.0 } else { -1.0 };
+
+        // Fastest speed from which we can still stop at the target.
+        let v_stop = (2.0 * limits.max_accel * error.abs()).sqrt();
+        let v_desired = dir * v_stop.min(limits.max_velocity);
+
+        let dv_max = limits.max_accel * dt;
+        let dv = (v_desired - self.velocity).clamp(-dv_max, dv_max);
+        self.velocity += dv;
+        self.position += self.velocity * dt;
+
+        // Snap to the target instead of oscillating around it.
+        let overshoot = (target - self.position) * dir < 0.0;
+        if overshoot || (error.abs() < dv_max * dt && self.velocity.abs() <= dv_max) {
+            self.position = target;
+            self.velocity = 0.0;
+        }
+
+        self.position
+    }
+}
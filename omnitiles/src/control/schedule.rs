@@ -0,0 +1,356 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! On-device scheduling of built-in motion sequences.
+//!
+//! A tile can exercise its actuators on its own, e.g. a nightly full-stroke self-check that keeps
+//! lubricant spread and surfaces feedback faults before the next session. The [`Scheduler`] holds
+//! up to [`SCHEDULE_SLOTS`] entries, each pairing a [`Trigger`] with a built-in [`sequence`]. The
+//! [`SequenceRunner`] then walks the sequence one position move at a time.
+//!
+//! Interval triggers run on uptime and work without a host. Daily triggers need the RTC to have
+//! been set; until then they never fire. A due sequence stays pending until the dispatcher finds
+//! the tile idle, so a trigger that falls during a session runs once the session ends.
+//!
+//! Sequence steps are given as a fraction of each axis's reachable range rather than in mm, so
+//! the same sequence works for any stroke and respects the configured end buffers.
+
+/// Number of schedule entries kept in the configuration.
+pub const SCHEDULE_SLOTS: usize = 4;
+/// Serialized [`ScheduleEntry`] length.
+pub const SCHEDULE_ENTRY_LEN: usize = 4;
+/// A move that hasn't settled within this time aborts the sequence.
+pub const MOVE_TIMEOUT_MS: u64 = 30_000;
+
+const DAY_S: u32 = 86_400;
+
+/// One position move within a sequence.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Step {
+    /// Axis number (1 = M1, 2 = M2).
+    pub axis: u8,
+    /// Target as a fraction of the axis's reachable range, 0.0 = retracted limit.
+    pub fraction: f32,
+    /// Time to hold after the axis settles, before the next step.
+    pub dwell_ms: u32,
+}
+
+impl Step {
+    /// Target in mm for an axis whose reachable range is `[min_mm, max_mm]`.
+    pub fn target_mm(&self, min_mm: f32, max_mm: f32) -> f32 {
+        min_mm + self.fraction.clamp(0.0, 1.0) * (max_mm - min_mm)
+    }
+}
+
+const fn step(axis: u8, fraction: f32, dwell_ms: u32) -> Step {
+    Step {
+        axis,
+        fraction,
+        dwell_ms,
+    }
+}
+
+/// Sequence 1: one full stroke per axis, ending at mid-stroke.
+pub const SELF_CHECK: &[Step] = &[
+    step(1, 0.0, 500),
+    step(1, 1.0, 500),
+    step(1, 0.5, 0),
+    step(2, 0.0, 500),
+    step(2, 1.0, 500),
+    step(2, 0.5, 0),
+];
+
+/// Sequence 2: three full strokes of both axes, ending retracted.
+pub const EXERCISE: &[Step] = &[
+    step(1, 1.0, 0),
+    step(2, 1.0, 1000),
+    step(1, 0.0, 0),
+    step(2, 0.0, 1000),
+    step(1, 1.0, 0),
+    step(2, 1.0, 1000),
+    step(1, 0.0, 0),
+    step(2, 0.0, 1000),
+    step(1, 1.0, 0),
+    step(2, 1.0, 1000),
+    step(1, 0.0, 0),
+    step(2, 0.0, 0),
+];
+
+/// Sequence 3: slow alternating waves for unattended demos, ending at mid-stroke. Demo mode runs
+/// it in a loop.
+pub const DEMO: &[Step] = &[
+    step(2, 1.0, 1000),
+    step(1, 1.0, 500),
+    step(1, 0.0, 500),
+    step(2, 0.25, 1000),
+    step(1, 0.75, 0),
+    step(2, 0.75, 0),
+    step(1, 0.25, 0),
+    step(2, 0.25, 0),
+    step(1, 0.5, 0),
+    step(2, 0.5, 2000),
+];
+
+/// Built-in sequence by ID, `None` for unknown IDs.
+pub fn sequence(id: u8) -> Option<&'static [Step]> {
+    match id {
+        1 => Some(SELF_CHECK),
+        2 => Some(EXERCISE),
+        3 => Some(DEMO),
+        _ => None,
+    }
+}
+
+/// When a schedule entry fires. Stored as a kind byte and a `u16` argument, see
+/// [`to_parts`](Self::to_parts).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Trigger {
+    #[default]
+    Off,
+    /// Every `minutes` of uptime, first after one period.
+    Every { minutes: u16 },
+    /// Once a day at `minute` past midnight UTC. Needs the RTC.
+    DailyAt { minute: u16 },
+}
+
+impl Trigger {
+    /// Decode from a kind byte and argument; `None` for unknown kinds or out-of-range arguments.
+    pub fn from_parts(kind: u8, arg: u16) -> Option<Self> {
+        match kind {
+            0 => Some(Trigger::Off),
+            1 if arg > 0 => Some(Trigger::Every { minutes: arg }),
+            2 if arg < 24 * 60 => Some(Trigger::DailyAt { minute: arg }),
+            _ => None,
+        }
+    }
+
+    pub fn to_parts(self) -> (u8, u16) {
+        match self {
+            Trigger::Off => (0, 0),
+            Trigger::Every { minutes } => (1, minutes),
+            Trigger::DailyAt { minute } => (2, minute),
+        }
+    }
+}
+
+/// A trigger paired with the sequence it starts.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ScheduleEntry {
+    pub trigger: Trigger,
+    /// Built-in sequence ID, see [`sequence`].
+    pub sequence: u8,
+}
+
+impl ScheduleEntry {
+    /// `[kind, sequence, arg: u16 LE]`.
+    pub fn to_bytes(&self) -> [u8; SCHEDULE_ENTRY_LEN] {
+        let (kind, arg) = self.trigger.to_parts();
+        let arg = arg.to_le_bytes();
+        [kind, self.sequence, arg[0], arg[1]]
+    }
+
+    /// Inverse of [`to_bytes`](Self::to_bytes). Corrupt entries decode as disabled.
+    pub fn from_bytes(b: [u8; SCHEDULE_ENTRY_LEN]) -> Self {
+        let trigger = Trigger::from_parts(b[0], u16::from_le_bytes([b[2], b[3]]));
+        match trigger {
+            Some(trigger) if sequence(b[1]).is_some() => Self {
+                trigger,
+                sequence: b[1],
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+/// Tracks when each schedule entry is next due.
+pub struct Scheduler {
+    entries: [ScheduleEntry; SCHEDULE_SLOTS],
+    /// Uptime at which each interval entry next fires.
+    next_due_ms: [u64; SCHEDULE_SLOTS],
+    /// Unix day on which each daily entry last fired.
+    last_day: [Option<u32>; SCHEDULE_SLOTS],
+    pending: Option<u8>,
+}
+
+impl Scheduler {
+    pub fn new(entries: [ScheduleEntry; SCHEDULE_SLOTS], now_ms: u64) -> Self {
+        let mut s = Self {
+            entries,
+            next_due_ms: [0; SCHEDULE_SLOTS],
+            last_day: [None; SCHEDULE_SLOTS],
+            pending: None,
+        };
+        for slot in 0..SCHEDULE_SLOTS {
+            s.rearm(slot, now_ms);
+        }
+        s
+    }
+
+    pub fn entries(&self) -> &[ScheduleEntry; SCHEDULE_SLOTS] {
+        &self.entries
+    }
+
+    /// Replace one entry. Its timer restarts from `now_ms`.
+    ///
+    /// Panics if `slot >= SCHEDULE_SLOTS`.
+    pub fn set(&mut self, slot: usize, entry: ScheduleEntry, now_ms: u64) {
+        self.entries[slot] = entry;
+        self.last_day[slot] = None;
+        self.rearm(slot, now_ms);
+    }
+
+    fn rearm(&mut self, slot: usize, now_ms: u64) {
+        if let Trigger::Every { minutes } = self.entries[slot].trigger {
+            self.next_due_ms[slot] = now_ms + minutes as u64 * 60_000;
+        }
+    }
+
+    /// Check the triggers. `unix_s` is the wall clock, `None` if the RTC isn't set. A due entry
+    /// becomes pending; if several fall due before one is taken, only the latest is kept.
+    pub fn poll(&mut self, now_ms: u64, unix_s: Option<u32>) {
+        for slot in 0..SCHEDULE_SLOTS {
+            let entry = self.entries[slot];
+            let due = match entry.trigger {
+                Trigger::Off => false,
+                Trigger::Every { minutes } => {
+                    let due = now_ms >= self.next_due_ms[slot];
+                    if due {
+                        self.next_due_ms[slot] = now_ms + minutes as u64 * 60_000;
+                    }
+                    due
+                }
+                Trigger::DailyAt { minute } => match unix_s {
+                    Some(t) => {
+                        let day = t / DAY_S;
+                        let due = t % DAY_S >= minute as u32 * 60;
+                        match self.last_day[slot] {
+                            // Past today's time on the first check: wait for tomorrow instead of
+                            // firing right after boot or after the clock is set.
+                            None => {
+                                self.last_day[slot] = if due {
+                                    Some(day)
+                                } else {
+                                    Some(day.wrapping_sub(1))
+                                };
+                                false
+                            }
+                            Some(last) if due && day != last => {
+                                self.last_day[slot] = Some(day);
+                                true
+                            }
+                            Some(_) => false,
+                        }
+                    }
+                    None => false,
+                },
+            };
+            if due {
+                self.pending = Some(entry.sequence);
+            }
+        }
+    }
+
+    /// Take the pending sequence ID, if any.
+    pub fn take_pending(&mut self) -> Option<u8> {
+        self.pending.take()
+    }
+}
+
+/// What the dispatcher should do after [`SequenceRunner::poll`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    /// Nothing to do (idle, moving or dwelling).
+    Wait,
+    /// Command this move.
+    Move(Step),
+    /// The last step completed.
+    Finished,
+    /// An axis didn't settle within [`MOVE_TIMEOUT_MS`]; the sequence was abandoned.
+    TimedOut(u8),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum RunState {
+    Idle,
+    Issue,
+    Moving { deadline_ms: u64 },
+    Dwell { until_ms: u64 },
+}
+
+/// Steps through a sequence, one move at a time.
+pub struct SequenceRunner {
+    steps: &'static [Step],
+    index: usize,
+    state: RunState,
+}
+
+impl SequenceRunner {
+    pub fn new() -> Self {
+        Self {
+            steps: &[],
+            index: 0,
+            state: RunState::Idle,
+        }
+    }
+
+    pub fn start(&mut self, steps: &'static [Step]) {
+        self.steps = steps;
+        self.index = 0;
+        self.state = RunState::Issue;
+    }
+
+    /// Stop without commanding anything further, e.g. when the host takes over.
+    pub fn abort(&mut self) {
+        self.state = RunState::Idle;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state != RunState::Idle
+    }
+
+    /// Advance the sequence. `on_target(axis)` reports whether an axis has settled.
+    pub fn poll(&mut self, now_ms: u64, on_target: impl Fn(u8) -> bool) -> Action {
+        match self.state {
+            RunState::Idle => Action::Wait,
+            RunState::Issue => match self.steps.get(self.index) {
+                Some(&step) => {
+                    self.state = RunState::Moving {
+                        deadline_ms: now_ms + MOVE_TIMEOUT_MS,
+                    };
+                    Action::Move(step)
+                }
+                None => {
+                    self.state = RunState::Idle;
+                    Action::Finished
+                }
+            },
+            RunState::Moving { deadline_ms } => {
+                let step = self.steps[self.index];
+                if on_target(step.axis) {
+                    self.state = RunState::Dwell {
+                        until_ms: now_ms + step.dwell_ms as u64,
+                    };
+                    Action::Wait
+                } else if now_ms >= deadline_ms {
+                    self.state = RunState::Idle;
+                    Action::TimedOut(step.axis)
+                } else {
+                    Action::Wait
+                }
+            }
+            RunState::Dwell { until_ms } => {
+                if now_ms >= until_ms {
+                    self.index += 1;
+                    self.state = RunState::Issue;
+                }
+                Action::Wait
+            }
+        }
+    }
+}
+
+impl Default for SequenceRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,146 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Cumulative per-motor usage counters for preventive maintenance.
+//!
+//! [`MotorStats`] is fed once per control step with the measured position and the commanded
+//! drive. It accumulates distance travelled, direction reversals, time spent driven and
+//! overcurrent trips. The counters are persisted by [`crate::config::stats`] and reported over
+//! the protocol with `MSG_STATS`.
+//!
+//! Distance is counted in the axis' native unit: millimeters for linear actuators, revolutions for
+//! rotary motors. Position noise smaller than the travel deadband is ignored so a motor sitting
+//! still does not slowly accumulate distance.
+
+/// Serialized length of the persisted counters.
+pub const STATS_LEN: usize = 20;
+
+/// Commanded drive magnitude above which the motor counts as under load.
+const LOAD_DUTY_THRESHOLD: f32 = 0.05;
+
+/// Position change (native units) that must accumulate before it counts as travel.
+const DEFAULT_TRAVEL_DEADBAND: f32 = 0.2;
+
+/// Usage counters for one motor.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MotorStats {
+    /// Total distance moved, in hundredths of the native unit.
+    pub travel_centi: u64,
+    /// Number of times the commanded direction flipped.
+    pub reversals: u32,
+    /// Total time driven above [`LOAD_DUTY_THRESHOLD`], in seconds.
+    pub load_s: u32,
+    /// Number of driver overcurrent trips.
+    pub ocp_trips: u32,
+
+    travel_deadband: f32,
+    anchor: Option<f32>,
+    last_dir: i8,
+    load_ms: u32,
+    dirty: bool,
+}
+
+impl MotorStats {
+    pub const fn new() -> Self {
+        Self {
+            travel_centi: 0,
+            reversals: 0,
+            load_s: 0,
+            ocp_trips: 0,
+            travel_deadband: DEFAULT_TRAVEL_DEADBAND,
+            anchor: None,
+            last_dir: 0,
+            load_ms: 0,
+            dirty: false,
+        }
+    }
+
+    /// Set the position change that must accumulate before it counts as travel.
+    pub fn with_travel_deadband(mut self, deadband: f32) -> Self {
+        self.travel_deadband = deadband;
+        self
+    }
+
+    /// Feed one control step: measured `position` (if any), commanded `drive` in -1.0..=1.0 and
+    /// the step length in milliseconds.
+    pub fn update(&mut self, position: Option<f32>, drive: f32, dt_ms: u32) {
+        if let Some(pos) = position {
+            match self.anchor {
+                None => self.anchor = Some(pos),
+                Some(anchor) => {
+                    let moved = (pos - anchor).abs();
+                    if moved >= self.travel_deadband {
+                        self.travel_centi += (moved * 100.0) as u64;
+                        self.anchor = Some(pos);
+                        self.dirty = true;
+                    }
+                }
+            }
+        }
+
+        if drive.abs() > LOAD_DUTY_THRESHOLD {
+            let dir = if drive > 0.0 { 1 } else { -1 };
+            if self.last_dir != 0 && dir != self.last_dir {
+                self.reversals = self.reversals.saturating_add(1);
+                self.dirty = true;
+            }
+            self.last_dir = dir;
+
+            self.load_ms += dt_ms;
+            if self.load_ms >= 1000 {
+                self.load_s = self.load_s.saturating_add(self.load_ms / 1000);
+                self.load_ms %= 1000;
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Count one driver overcurrent trip.
+    pub fn record_ocp(&mut self) {
+        self.ocp_trips = self.ocp_trips.saturating_add(1);
+        self.dirty = true;
+    }
+
+    /// Total distance moved, in native units.
+    #[inline]
+    pub fn travel(&self) -> f32 {
+        self.travel_centi as f32 / 100.0
+    }
+
+    /// True if a counter changed since the last [`mark_clean`](Self::mark_clean).
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Note that the current counters have been persisted.
+    #[inline]
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Little-endian `[travel_centi: u64, reversals: u32, load_s: u32, ocp_trips: u32]`.
+    pub fn to_bytes(&self) -> [u8; STATS_LEN] {
+        let mut out = [0u8; STATS_LEN];
+        out[0..8].copy_from_slice(&self.travel_centi.to_le_bytes());
+        out[8..12].copy_from_slice(&self.reversals.to_le_bytes());
+        out[12..16].copy_from_slice(&self.load_s.to_le_bytes());
+        out[16..20].copy_from_slice(&self.ocp_trips.to_le_bytes());
+        out
+    }
+
+    /// Restore counters saved with [`to_bytes`](Self::to_bytes), keeping this tracker's settings.
+    pub fn restore(&mut self, bytes: &[u8; STATS_LEN]) {
+        self.travel_centi = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        self.reversals = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        self.load_s = u32::from_le_bytes(bytes[12..16].try_into().unwrap());
+        self.ocp_trips = u32::from_le_bytes(bytes[16..20].try_into().unwrap());
+        self.dirty = false;
+    }
+}
+
+impl Default for MotorStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
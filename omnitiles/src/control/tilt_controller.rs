@@ -0,0 +1,423 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Position control for the GIM6010 tilt axis.
+//!
+//! The GIM6010 closes its own position loop, so this controller only issues position commands and
+//! tracks completion by polling the motor with a [`MoveMonitor`]. Polling never waits on the
+//! motor: [`TiltController::step`] sends the read requests and picks up their replies on a later
+//! call.
+//!
+//! The tilt mechanism has no limit switch, so [`TiltController::home`] finds a hard stop by
+//! driving at low torque until the shaft stalls, and uses that as the angle reference. Homing
+//! takes seconds, so it is a [`Homing`] state machine stepped like any other long operation (see
+//! [`iwdg`]).
+
+use core::task::Poll;
+
+use crate::control::envelope::Envelope;
+use crate::control::events::{Event, EventKind};
+use crate::drivers::gim6010::{Error, Gim6010, MoveMonitor, MultiTurn, Reply};
+use crate::hw::{iwdg, CanInterface};
+use crate::protocol::progress::{self, phase, Operation, Progress, Reporter};
+use crate::protocol::{AxisCaps, Unit};
+use crate::units::{Deg, Mm, Rad};
+use cortex_m::delay::Delay;
+
+/// Default completion window: ~0.15 rad at the shaft, 2 rpm, 3 consecutive samples.
+const DEFAULT_POS_TOLERANCE_RAW: u16 = 50;
+const DEFAULT_SPEED_TOLERANCE_RPM: f32 = 2.0;
+const DEFAULT_SETTLE_SAMPLES: u8 = 3;
+/// [`TiltController::step`] calls to wait for a poll's replies before asking again.
+const POLL_RETRY_STEPS: u8 = 5;
+
+/// Homing torque, low enough that the hard stop is not damaged.
+const HOME_CURRENT_A: f32 = 0.4;
+/// Shaft speed below which the motor counts as stalled.
+const HOME_STALL_RPM: f32 = 1.0;
+/// Fraction of the commanded current that must be flowing for a stall to count. Rules out a
+/// motor that is stopped because it is not being driven at all.
+const HOME_STALL_CURRENT_FRAC: f32 = 0.7;
+/// Consecutive stalled samples required to declare the hard stop.
+const HOME_STALL_SAMPLES: u32 = 5;
+/// Ignore stalls during spin-up.
+const HOME_SPINUP_MS: u32 = 200;
+const HOME_SAMPLE_MS: u32 = 20;
+const HOME_TIMEOUT_MS: u32 = 15_000;
+/// Distance to back off from the hard stop after homing (rad at the shaft).
+const HOME_BACKOFF: Rad = Rad(0.5);
+
+/// Failure of [`TiltController::home`].
+#[derive(Debug)]
+pub enum HomingError {
+    /// No hard stop detected within the timeout.
+    Timeout,
+    /// CAN communication with the motor failed.
+    Motor(Error),
+}
+
+impl From<Error> for HomingError {
+    fn from(e: Error) -> Self {
+        HomingError::Motor(e)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum HomingPhase {
+    Start,
+    Seek { stalled: u32 },
+    BackOff,
+}
+
+/// [`TiltController::home`] as a state machine, from [`TiltController::start_homing`].
+pub struct Homing<F: FnMut(Progress)> {
+    towards_min: bool,
+    phase: HomingPhase,
+    /// Time in the current phase.
+    elapsed: u32,
+    progress: Reporter<F>,
+}
+
+impl<F: FnMut(Progress)> Homing<F> {
+    /// Take one sample, `elapsed_ms` after the previous one. Each step is a few CAN requests.
+    /// Returns the result once homing has finished or failed; don't call it again after that.
+    pub fn run_step<B: CanInterface, const DEV_ADDR: u16>(
+        &mut self,
+        tilt: &mut TiltController<DEV_ADDR>,
+        bus: &mut B,
+        elapsed_ms: u32,
+    ) -> Poll<Result<(), HomingError>> {
+        let result = match self.advance(tilt, bus, elapsed_ms) {
+            Ok(false) => return Poll::Pending,
+            Ok(true) => Ok(()),
+            Err(e) => Err(e),
+        };
+        self.progress.finish(result.is_ok());
+        Poll::Ready(result)
+    }
+
+    /// One step of the seek or back-off. `Ok(true)` once homed.
+    fn advance<B: CanInterface, const DEV_ADDR: u16>(
+        &mut self,
+        tilt: &mut TiltController<DEV_ADDR>,
+        bus: &mut B,
+        elapsed_ms: u32,
+    ) -> Result<bool, HomingError> {
+        self.elapsed += elapsed_ms;
+        match self.phase {
+            HomingPhase::Start => {
+                tilt.homed = false;
+                tilt.monitor.cancel();
+                tilt.on_target = false;
+
+                let current = if self.towards_min {
+                    -HOME_CURRENT_A
+                } else {
+                    HOME_CURRENT_A
+                };
+                tilt.motor.set_current_a(bus, current)?;
+                self.phase = HomingPhase::Seek { stalled: 0 };
+                self.elapsed = 0;
+            }
+            HomingPhase::Seek { stalled } => {
+                let percent = progress::scale(0, 70, self.elapsed, HOME_TIMEOUT_MS);
+                self.progress
+                    .update(phase::HOMING_SEEK, percent, elapsed_ms);
+                if self.elapsed >= HOME_TIMEOUT_MS {
+                    tilt.motor.disable_output(bus)?;
+                    return Err(HomingError::Timeout);
+                }
+                if self.elapsed < HOME_SPINUP_MS {
+                    return Ok(false);
+                }
+
+                let speed = tilt.motor.read_speed_rpm(bus)?;
+                let amps = tilt.motor.read_current_a(bus)?;
+                if speed.abs() > HOME_STALL_RPM
+                    || amps.abs() < HOME_CURRENT_A * HOME_STALL_CURRENT_FRAC
+                {
+                    self.phase = HomingPhase::Seek { stalled: 0 };
+                } else if stalled + 1 < HOME_STALL_SAMPLES {
+                    self.phase = HomingPhase::Seek {
+                        stalled: stalled + 1,
+                    };
+                } else {
+                    let stop = Rad(tilt.motor.read_position_unwrapped(bus, &mut tilt.turns)?);
+                    tilt.motor.set_current_a(bus, 0.0)?;
+
+                    let (home, backoff) = if self.towards_min {
+                        (tilt.min_angle, HOME_BACKOFF)
+                    } else {
+                        (tilt.max_angle, -HOME_BACKOFF)
+                    };
+                    tilt.zero_offset = stop - home;
+                    tilt.homed = true;
+
+                    tilt.set_target(bus, home + backoff)?;
+                    self.phase = HomingPhase::BackOff;
+                    self.elapsed = 0;
+                }
+            }
+            HomingPhase::BackOff => {
+                if tilt.on_target {
+                    tilt.event = Some(Event::new(tilt.axis, EventKind::HomingDone, 0));
+                    return Ok(true);
+                }
+                if self.elapsed >= HOME_TIMEOUT_MS {
+                    tilt.disable(bus)?;
+                    return Err(HomingError::Timeout);
+                }
+                tilt.step(bus)?;
+                let percent = progress::scale(70, 100, self.elapsed, HOME_TIMEOUT_MS);
+                self.progress
+                    .update(phase::HOMING_BACKOFF, percent, elapsed_ms);
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Tilt axis controller. Call [`step`](Self::step) periodically while a move is in progress.
+pub struct TiltController<const DEV_ADDR: u16> {
+    pub motor: Gim6010<DEV_ADDR>,
+    pub monitor: MoveMonitor,
+    pub min_angle: Rad,
+    pub max_angle: Rad,
+
+    /// Raw-frame angle that corresponds to zero in the controller frame. Set by homing.
+    pub zero_offset: Rad,
+    /// Unwraps the raw encoder position across the ±Pos_Max boundary.
+    pub turns: MultiTurn,
+
+    target: Rad,
+    position: Rad,
+    homed: bool,
+    axis: u8,
+    on_target: bool,
+    event: Option<Event>,
+    /// Position and speed replies to the outstanding poll.
+    sample: (Option<u16>, Option<f32>),
+    /// Steps since the outstanding poll was sent, `None` if there is none.
+    polled: Option<u8>,
+}
+
+impl<const DEV_ADDR: u16> TiltController<DEV_ADDR> {
+    /// Create a new tilt controller with shaft angle limits.
+    pub fn new(
+        motor: Gim6010<DEV_ADDR>,
+        min_angle: impl Into<Rad>,
+        max_angle: impl Into<Rad>,
+    ) -> Self {
+        Self {
+            motor,
+            monitor: MoveMonitor::new(
+                DEFAULT_POS_TOLERANCE_RAW,
+                DEFAULT_SPEED_TOLERANCE_RPM,
+                DEFAULT_SETTLE_SAMPLES,
+            ),
+            min_angle: min_angle.into(),
+            max_angle: max_angle.into(),
+            zero_offset: Rad(0.0),
+            turns: MultiTurn::new(),
+            target: Rad(0.0),
+            position: Rad(0.0),
+            homed: false,
+            axis: 0,
+            on_target: false,
+            event: None,
+            sample: (None, None),
+            polled: None,
+        }
+    }
+
+    /// Set the axis number reported in [`Event`]s.
+    pub fn with_axis(mut self, axis: u8) -> Self {
+        self.axis = axis;
+        self
+    }
+
+    /// Replace the completion window.
+    pub fn with_monitor(mut self, monitor: MoveMonitor) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    /// Current target angle, after clamping.
+    #[inline]
+    pub fn target(&self) -> Rad {
+        self.target
+    }
+
+    /// Capabilities record for the handshake. The tilt axis has no scaled absolute command, so
+    /// `full_scale` is zero.
+    pub fn caps(&self, axis: u8) -> AxisCaps {
+        AxisCaps {
+            axis,
+            unit: Unit::Degree,
+            min: Deg::from(self.min_angle).get(),
+            max: Deg::from(self.max_angle).get(),
+            full_scale: 0.0,
+        }
+    }
+
+    /// Command a move of `delta` from the current target, clamped to the angle limits.
+    pub fn move_relative<B: CanInterface>(
+        &mut self,
+        bus: &mut B,
+        delta: impl Into<Rad>,
+    ) -> Result<(), Error> {
+        let target = self.target + delta.into();
+        self.set_target(bus, target)
+    }
+
+    /// Command a move to `angle`, clamped to the angle limits.
+    pub fn set_target<B: CanInterface>(
+        &mut self,
+        bus: &mut B,
+        angle: impl Into<Rad>,
+    ) -> Result<(), Error> {
+        let target = angle.into().clamp(self.min_angle, self.max_angle);
+        // The motor only accepts positions in [0..65535] of the encoder range it is in. Fold the
+        // unwrapped target into that range, and refuse one that lies in another range rather than
+        // send the motor to the same raw value in this one.
+        let counts = Gim6010::<DEV_ADDR>::rad_to_counts((target + self.zero_offset).get());
+        if counts.div_euclid(65536) != self.turns.wraps() as i64 {
+            return Err(Error::OutOfRange);
+        }
+        let raw = counts.rem_euclid(65536) as u16;
+        self.target = target;
+        self.on_target = false;
+        self.sample = (None, None);
+        self.polled = None;
+        self.motor.set_position_raw(bus, raw)?;
+        self.monitor.start(raw);
+        Ok(())
+    }
+
+    /// Command a move to `angle`, clamped to the angle limits and to what clears the frame at lift
+    /// height `lift`.
+    pub fn set_target_within<B: CanInterface>(
+        &mut self,
+        bus: &mut B,
+        angle: impl Into<Rad>,
+        lift: Mm,
+        envelope: &Envelope,
+    ) -> Result<(), Error> {
+        self.set_target(bus, envelope.clamp_tilt(lift, angle.into()))
+    }
+
+    /// Poll the motor and update completion state. Does nothing when no move is in progress.
+    ///
+    /// Takes the replies that arrived since the last call off `bus` and asks for the next
+    /// position and speed sample once both are in, or once the last request has gone unanswered
+    /// for a few calls. Never waits for a reply, so call it every control step.
+    pub fn step<B: CanInterface>(&mut self, bus: &mut B) -> Result<(), Error> {
+        if !self.monitor.is_active() {
+            return Ok(());
+        }
+        while let Some(frame) = bus.try_recv() {
+            match Gim6010::<DEV_ADDR>::decode_reply(&frame?) {
+                Some(Reply::Position(raw)) => self.sample.0 = Some(raw),
+                Some(Reply::Speed(rpm)) => self.sample.1 = Some(rpm),
+                _ => {}
+            }
+        }
+
+        if let (Some(position_raw), Some(speed_rpm)) = self.sample {
+            self.sample = (None, None);
+            self.polled = None;
+            self.position = Rad(Gim6010::<DEV_ADDR>::counts_to_rad(
+                self.turns.update(position_raw),
+            )) - self.zero_offset;
+            if self.monitor.update(position_raw, speed_rpm) {
+                self.on_target = true;
+                self.event = Some(Event::new(self.axis, EventKind::TargetReached, 0));
+                return Ok(());
+            }
+        }
+
+        match self.polled {
+            Some(steps) if steps < POLL_RETRY_STEPS => self.polled = Some(steps + 1),
+            _ => {
+                self.sample = (None, None);
+                self.polled = Some(0);
+                self.motor.request_read(bus, 0xA3)?;
+                self.motor.request_read(bus, 0xA2)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop a move where the shaft is now by retargeting the last measured angle.
+    pub fn hold<B: CanInterface>(&mut self, bus: &mut B) -> Result<(), Error> {
+        self.set_target(bus, self.position)
+    }
+
+    /// Last measured angle in the controller frame, unwrapped across encoder range wraps.
+    /// Updated by [`step`](Self::step) while a move is in progress.
+    #[inline]
+    pub fn position(&self) -> Rad {
+        self.position
+    }
+
+    /// Read the position now, regardless of whether a move is in progress.
+    pub fn read_position<B: CanInterface>(&mut self, bus: &mut B) -> Result<Rad, Error> {
+        let rad = self.motor.read_position_unwrapped(bus, &mut self.turns)?;
+        self.position = Rad(rad) - self.zero_offset;
+        Ok(self.position)
+    }
+
+    /// True once the last commanded move has completed.
+    #[inline]
+    pub fn on_target(&self) -> bool {
+        self.on_target
+    }
+
+    /// True once [`home`](Self::home) has completed since power-on.
+    #[inline]
+    pub fn is_homed(&self) -> bool {
+        self.homed
+    }
+
+    /// Home against a hard stop.
+    ///
+    /// Drives toward the stop (`towards_min` selects the direction) at low torque until the shaft
+    /// stalls with current flowing, sets [`zero_offset`](Self::zero_offset) so that the
+    /// stop reads as `min_angle` (or `max_angle`), then backs off and waits for the move to
+    /// complete. Blocking, feeding the watchdog between samples; reports its progress to `report`
+    /// as [`Operation::Homing`]. See [`start_homing`](Self::start_homing) for the same from the
+    /// main loop.
+    pub fn home<B: CanInterface, F: FnMut(Progress)>(
+        &mut self,
+        bus: &mut B,
+        delay: &mut Delay,
+        towards_min: bool,
+        report: F,
+    ) -> Result<(), HomingError> {
+        let mut homing = self.start_homing(towards_min, report);
+        iwdg::block_on(delay, HOME_SAMPLE_MS, |dt| homing.run_step(self, bus, dt))
+    }
+
+    /// Prepare [`home`](Self::home) as a resumable operation: call [`Homing::run_step`] every
+    /// `HOME_SAMPLE_MS` or so until it is ready.
+    pub fn start_homing<F: FnMut(Progress)>(&self, towards_min: bool, report: F) -> Homing<F> {
+        Homing {
+            towards_min,
+            phase: HomingPhase::Start,
+            elapsed: 0,
+            progress: Reporter::new(Operation::Homing, self.axis, report),
+        }
+    }
+
+    /// Take the most recent unreported event, if any.
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.event.take()
+    }
+
+    /// Stop tracking and turn off the motor output.
+    pub fn disable<B: CanInterface>(&mut self, bus: &mut B) -> Result<(), Error> {
+        self.monitor.cancel();
+        self.on_target = false;
+        self.motor.disable_output(bus)
+    }
+}
@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Binary data log on a FAT-formatted microSD card.
+//!
+//! Multi-hour installations produce more telemetry than any host link keeps, so [`SdLogger`]
+//! records it on the tile for offline analysis: every telemetry frame plus the fault snapshots
+//! captured by [`FaultRecorder`](crate::control::FaultRecorder). Each boot opens a new
+//! `LOGnnnnn.BIN` in the root directory, stamped with the RTC time.
+//!
+//! The file is a stream of little-endian records:
+//!
+//! ```text
+//! [kind: u8] [len: u8] [timestamp_us: u32] [payload: len bytes]
+//! ```
+//!
+//! `timestamp_us` is time since boot, wrapping like the telemetry sample time. Each file starts
+//! with a [`kind::SESSION`] record. Records are buffered in RAM and reach the card a block at a
+//! time or on [`SdLogger::flush`]; a power cut loses at most what was written since the last
+//! flush. Card writes block the main loop for a few milliseconds. Requires the `sd-log` feature.
+
+use embedded_sdmmc::{Mode, RawFile, TimeSource, Timestamp, VolumeIdx, VolumeManager};
+
+use crate::hw::{iwdg, rtc, sdmmc::Sdmmc};
+
+/// Record kinds.
+pub mod kind {
+    /// Start of a log file. Payload: `[format_version, node_id, unix_s: u32]`, `unix_s` 0 if the
+    /// RTC was never set.
+    pub const SESSION: u8 = 0x00;
+    /// Telemetry frame payload as sent over SPI (`MSG_TELEMETRY`, without start byte, ID and
+    /// checksum).
+    pub const TELEMETRY: u8 = 0x01;
+    /// `MSG_FAULT_SNAPSHOT` payload.
+    pub const FAULT_SNAPSHOT: u8 = 0x02;
+    /// `MSG_FAULT_HISTORY` payload.
+    pub const FAULT_HISTORY: u8 = 0x03;
+}
+
+/// Record layout version in [`kind::SESSION`].
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Bytes collected before a write, one card block.
+const BUFFER_LEN: usize = 512;
+/// Highest file number; a card that already holds it gets no new log.
+const MAX_FILE_NUMBER: u32 = 99_999;
+
+pub type Error = embedded_sdmmc::Error<crate::hw::sdmmc::Error>;
+
+/// Fixed file timestamp taken from the RTC when the log is opened.
+struct OpenTime(Timestamp);
+
+impl OpenTime {
+    fn new(unix_s: u32) -> Self {
+        let (year, month, day, hours, minutes, seconds) = rtc::civil_from_unix(unix_s);
+        // FAT dates start in 1980, so an unset clock gets the earliest one.
+        let ts = Timestamp::from_calendar(
+            year as u16,
+            month as u8,
+            day as u8,
+            hours as u8,
+            minutes as u8,
+            seconds as u8,
+        )
+        .or_else(|_| Timestamp::from_calendar(1980, 1, 1, 0, 0, 0))
+        .unwrap();
+        Self(ts)
+    }
+}
+
+impl TimeSource for OpenTime {
+    fn get_timestamp(&self) -> Timestamp {
+        self.0
+    }
+}
+
+/// Appends records to a new log file on the card.
+pub struct SdLogger {
+    volumes: VolumeManager<Sdmmc, OpenTime>,
+    file: RawFile,
+    name: [u8; 12],
+    buf: [u8; BUFFER_LEN],
+    len: usize,
+}
+
+impl SdLogger {
+    /// Open `LOGnnnnn.BIN`, one past the highest number in the root directory of the card's first
+    /// partition, and write the session record. `card` must already be initialized.
+    ///
+    /// Finding the number takes one pass over the directory, feeding the watchdog per entry, so
+    /// the time grows with the files on the card rather than with the names tried.
+    pub fn open(card: Sdmmc, node_id: u8, unix_s: Option<u32>) -> Result<Self, Error> {
+        let unix_s = unix_s.unwrap_or(0);
+        let mut volumes = VolumeManager::new(card, OpenTime::new(unix_s));
+        let volume = volumes.open_raw_volume(VolumeIdx(0))?;
+        let root = volumes.open_root_dir(volume)?;
+
+        let mut next = 0;
+        volumes.iterate_dir(root, |entry| {
+            iwdg::feed();
+            if let Some(n) = log_number(entry.name.base_name(), entry.name.extension()) {
+                next = next.max(n + 1);
+            }
+        })?;
+        let mut name = *b"LOG00000.BIN";
+        let result = if next > MAX_FILE_NUMBER {
+            Err(Error::NotEnoughSpace)
+        } else {
+            let mut digits = next;
+            for d in name[3..8].iter_mut().rev() {
+                *d = b'0' + (digits % 10) as u8;
+                digits /= 10;
+            }
+            // Only ASCII digits were written.
+            let s = core::str::from_utf8(&name).unwrap();
+            volumes.open_file_in_dir(root, s, Mode::ReadWriteCreate)
+        };
+        volumes.close_dir(root)?;
+        let file = result?;
+
+        let mut logger = Self {
+            volumes,
+            file,
+            name,
+            buf: [0; BUFFER_LEN],
+            len: 0,
+        };
+        let [a, b, c, d] = unix_s.to_le_bytes();
+        logger.record(kind::SESSION, 0, &[FORMAT_VERSION, node_id, a, b, c, d])?;
+        logger.flush()?;
+        Ok(logger)
+    }
+
+    /// Name of the file being written.
+    pub fn file_name(&self) -> &str {
+        // Only ASCII was ever written.
+        core::str::from_utf8(&self.name).unwrap()
+    }
+
+    /// Append a record. Payloads over 255 bytes are truncated.
+    pub fn record(&mut self, kind: u8, timestamp_us: u32, payload: &[u8]) -> Result<(), Error> {
+        let payload = &payload[..payload.len().min(u8::MAX as usize)];
+        let [t0, t1, t2, t3] = timestamp_us.to_le_bytes();
+        self.push(&[kind, payload.len() as u8, t0, t1, t2, t3])?;
+        self.push(payload)
+    }
+
+    fn push(&mut self, mut bytes: &[u8]) -> Result<(), Error> {
+        while !bytes.is_empty() {
+            let n = bytes.len().min(BUFFER_LEN - self.len);
+            self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+            self.len += n;
+            bytes = &bytes[n..];
+            if self.len == BUFFER_LEN {
+                self.write_buffer()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_buffer(&mut self) -> Result<(), Error> {
+        let len = core::mem::take(&mut self.len);
+        self.volumes.write(self.file, &self.buf[..len])
+    }
+
+    /// Write out buffered records and update the file's directory entry, so everything so far
+    /// survives a power cut.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        if self.len > 0 {
+            self.write_buffer()?;
+        }
+        self.volumes.flush_file(self.file)
+    }
+}
+
+/// File number of a `LOGnnnnn.BIN` short name, given as its base name and extension.
+fn log_number(base: &[u8], extension: &[u8]) -> Option<u32> {
+    let digits = base.strip_prefix(b"LOG")?;
+    if extension != b"BIN" || digits.len() != 5 || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    Some(digits.iter().fold(0, |n, d| n * 10 + (d - b'0') as u32))
+}
@@ -12,9 +12,11 @@
 //! - Pin 4 (Black):  Motor Terminal B (-)
 //! - Pin 5 (Yellow): Potentiometer Reference (3.3V)
 
-use crate::drivers::drv8873::{Drv8873, Fault};
+use crate::config::AxisCalibration;
+use crate::drivers::drv8873::{Drv8873, Fault, ItripLevel, SleepPin};
+use crate::drivers::OutputRange;
 use crate::hw::spi::CsControl;
-use crate::hw::SpiBus;
+use crate::hw::{BusError, SpiBus};
 
 use stm32f7xx_hal::{
     gpio::{self, Output, PushPull},
@@ -56,7 +58,7 @@ pub struct ActuonixLinear<
     drv: Drv8873<CS>,
     pwm1: Pwm1,
     pwm2: Pwm2,
-    nsleep: gpio::Pin<SLP_P, SLP_N, Output<PushPull>>,
+    nsleep: SleepPin<SLP_P, SLP_N>,
     disable: gpio::Pin<DIS_P, DIS_N, Output<PushPull>>,
     read_positions: ReadPos,
     adc_history: [[u16; N]; 5],
@@ -70,6 +72,14 @@ pub struct ActuonixLinear<
     buffer_top_mm: f32,
     current_speed: f32,
     limit_brake_active: bool,
+    extend_inverted: bool,
+    output_range: OutputRange,
+    /// Bus voltage compensation factor applied to every duty.
+    supply_scale: f32,
+    /// `(scale, offset_mm)` applied to the position in millimeters.
+    position_correction: (f32, f32),
+    /// Measured end stops; the nominal 0..4095 pot range without one.
+    calibration: Option<AxisCalibration>,
 }
 
 impl<
@@ -106,11 +116,10 @@ where
         buffer_bottom_mm: f32,
         buffer_top_mm: f32,
     ) -> Self {
-        let mut nsleep = nsleep.into_push_pull_output();
         let mut disable = disable.into_push_pull_output();
 
         // Default: Awake, Enabled
-        nsleep.set_high();
+        let nsleep = SleepPin::new(nsleep);
         disable.set_low();
 
         let initial = (read_positions)();
@@ -133,6 +142,11 @@ where
             buffer_top_mm,
             current_speed: 0.0,
             limit_brake_active: false,
+            extend_inverted: false,
+            output_range: OutputRange::FULL,
+            supply_scale: 1.0,
+            position_correction: (1.0, 0.0),
+            calibration: None,
         }
     }
 
@@ -225,20 +239,39 @@ where
             }
         }
 
+        self.drive(speed);
+    }
+
+    /// Drive the H-bridge without soft-limit checks.
+    ///
+    /// Intended for calibration routines that must reach the mechanical end stops. Limit
+    /// enforcement in [`enforce_limits`](Self::enforce_limits) still applies while moving, so
+    /// callers must not run it concurrently.
+    pub fn set_speed_unchecked(&mut self, speed: f32) {
+        self.limit_brake_active = false;
+        self.drive(speed.clamp(-1.0, 1.0));
+    }
+
+    fn drive(&mut self, speed: f32) {
         self.current_speed = speed;
 
         let max_duty = self.pwm1.get_max_duty(); // Assuming Pwm1/Pwm2 have same resolution
 
         // Calculate target duty cycle
-        let duty = (speed.abs() * max_duty as f32) as u16;
+        let speed = self.output_range.apply(speed);
+        let duty = ((speed.abs() * self.supply_scale).min(1.0) * max_duty as f32) as u16;
+
+        // Swapped motor leads: mirror the drive direction at the H-bridge only, so limits and
+        // position feedback keep working in logical (extend = positive) terms.
+        let speed = if self.extend_inverted { -speed } else { speed };
 
-        if speed > 0.001 {
+        if speed > 0.0 {
             // Extend: IN1 PWM, IN2 Low
             self.pwm1.set_duty(duty);
             self.pwm2.set_duty(0);
             self.pwm1.enable();
             self.pwm2.enable();
-        } else if speed < -0.001 {
+        } else if speed < 0.0 {
             // Retract: IN1 Low, IN2 PWM
             self.pwm1.set_duty(0);
             self.pwm2.set_duty(duty);
@@ -299,6 +332,7 @@ where
 
     #[inline]
     fn brake_raw(&mut self) {
+        self.current_speed = 0.0;
         let max = self.pwm1.get_max_duty();
         self.pwm1.set_duty(max);
         self.pwm2.set_duty(max);
@@ -306,6 +340,70 @@ where
         self.pwm2.enable();
     }
 
+    /// Swap which H-bridge input extends the actuator, for units with reversed motor leads.
+    #[inline]
+    pub fn set_extend_inverted(&mut self, inverted: bool) {
+        self.extend_inverted = inverted;
+    }
+
+    /// True if the extend polarity is inverted.
+    #[inline]
+    pub fn is_extend_inverted(&self) -> bool {
+        self.extend_inverted
+    }
+
+    /// Set the duty range speeds are scaled onto. Applies to every drive, including manual
+    /// extend/retract commands.
+    #[inline]
+    pub fn set_output_range(&mut self, range: OutputRange) {
+        self.output_range = range;
+    }
+
+    #[inline]
+    pub fn output_range(&self) -> OutputRange {
+        self.output_range
+    }
+
+    /// Scale every duty by `scale` (capped at full duty) to offset bus voltage sag; see
+    /// [`SupplyCompensation`](crate::sensors::SupplyCompensation). A running drive picks up the new
+    /// scale immediately.
+    pub fn set_supply_scale(&mut self, scale: f32) {
+        self.supply_scale = if scale.is_finite() && scale > 0.0 {
+            scale
+        } else {
+            1.0
+        };
+        if self.current_speed != 0.0 {
+            self.drive(self.current_speed);
+        }
+    }
+
+    /// Correct [`position_mm`](Self::position_mm) to `mm * scale + offset_mm`, e.g. for thermal
+    /// drift of the pots; see [`PotTempCompensation`](crate::sensors::PotTempCompensation). Soft
+    /// limits use the corrected position. Raw readings are left alone, so pot calibration still
+    /// sees the pots as they are.
+    pub fn set_position_correction(&mut self, scale: f32, offset_mm: f32) {
+        self.position_correction = if scale.is_finite() && scale > 0.0 && offset_mm.is_finite() {
+            (scale, offset_mm)
+        } else {
+            (1.0, 0.0)
+        };
+    }
+
+    /// Map the end stops measured by [`provision`](crate::config::provision) to the ends of the
+    /// stroke, so [`position_percent`](Self::position_percent), [`position_mm`](Self::position_mm)
+    /// and the soft limits follow this unit's pots. An invalid calibration (see
+    /// [`AxisCalibration::is_valid`]) goes back to the nominal pot range.
+    pub fn set_calibration(&mut self, cal: AxisCalibration) {
+        self.calibration = cal.is_valid().then_some(cal);
+    }
+
+    /// Last commanded drive, from -1.0 (full retract) to 1.0 (full extend). Zero while braked.
+    #[inline]
+    pub fn speed(&self) -> f32 {
+        self.current_speed
+    }
+
     /// True when we are currently braking due to software limit enforcement.
     #[inline]
     pub fn is_limit_braking(&self) -> bool {
@@ -320,14 +418,24 @@ where
         self.fused_raw_from_cache()
     }
 
-    /// Read position as a fraction (0.0 = Retracted, 1.0 = Extended).
+    /// Read position as a fraction (0.0 = Retracted, 1.0 = Extended). With a calibration set,
+    /// readings past a measured end stop fall outside 0.0..1.0.
     pub fn position_percent(&mut self) -> Option<f32> {
-        self.position_raw().map(|r| (r as f32) / 4095.0)
+        let raw = self.position_raw()? as f32;
+        Some(match self.calibration {
+            Some(cal) => {
+                let retracted = cal.retracted_raw as f32;
+                (raw - retracted) / (cal.extended_raw as f32 - retracted)
+            }
+            None => raw / 4095.0,
+        })
     }
 
     /// Read position in millimeters.
     pub fn position_mm(&mut self) -> Option<f32> {
-        self.position_percent().map(|p| p * self.stroke_len_mm)
+        let (scale, offset_mm) = self.position_correction;
+        self.position_percent()
+            .map(|p| p * self.stroke_len_mm * scale + offset_mm)
     }
 
     /// Get the max stroke length.
@@ -345,13 +453,13 @@ where
     /// This shuts down most of the internal circuitry to reduce power consumption.
     #[inline]
     pub fn sleep(&mut self) {
-        self.nsleep.set_low();
+        self.nsleep.sleep();
     }
 
-    /// Wake the driver from sleep mode.
+    /// Wake the driver from sleep mode. Blocks for `tWAKE` (1 ms) if it was asleep.
     #[inline]
     pub fn wake(&mut self) {
-        self.nsleep.set_high();
+        self.nsleep.wake();
     }
 
     /// Enable the motor and wake the driver if in sleep.
@@ -369,14 +477,38 @@ where
     }
 
     /// Read the FAULT status register from the DRV8873.
-    pub fn read_fault<I, PINS>(
+    pub fn read_fault<I, PINS>(&mut self, spi_bus: &mut SpiBus<I, PINS>) -> Result<Fault, BusError>
+    where
+        I: spi::Instance,
+        PINS: spi::Pins<I>,
+    {
+        self.drv.read_fault(spi_bus)
+    }
+
+    /// Cap the motor current with the DRV8873's ITRIP regulation, or remove the cap with `None`.
+    /// See [`Drv8873::set_itrip`].
+    pub fn set_current_limit<I, PINS>(
         &mut self,
         spi_bus: &mut SpiBus<I, PINS>,
-    ) -> Result<Fault, spi::Error>
+        level: Option<ItripLevel>,
+    ) -> Result<(), BusError>
     where
         I: spi::Instance,
         PINS: spi::Pins<I>,
     {
-        self.drv.read_fault(spi_bus)
+        self.drv.set_itrip(spi_bus, level)
+    }
+
+    /// Verify the DRV8873 configuration against its shadow copy, re-applying it if the driver
+    /// reset. See [`Drv8873::verify_config`].
+    pub fn verify_driver_config<I, PINS>(
+        &mut self,
+        spi_bus: &mut SpiBus<I, PINS>,
+    ) -> Result<u8, BusError>
+    where
+        I: spi::Instance,
+        PINS: spi::Pins<I>,
+    {
+        self.drv.verify_config(spi_bus)
     }
 }
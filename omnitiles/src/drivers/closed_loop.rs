@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Common interface for motors driven by an on-MCU position loop.
+//!
+//! [`LinearController`](crate::control::LinearController) drives any [`ClosedLoopAxis`], so the
+//! same PID, profile and on-target logic runs any axis that can report a position and take a
+//! signed drive output. Positions are in the axis's native unit: millimeters for linear
+//! actuators, output revolutions for rotary motors.
+//!
+//! Motors that close their own loop over CAN (the GIM6010) don't fit this shape, since every
+//! access needs the bus; they have dedicated controllers such as
+//! [`TiltController`](crate::control::TiltController).
+
+use crate::drivers::drv8313::HalfBridge;
+use crate::drivers::{ActuonixLinear, Drv8313, Fit0185, Stepper, TorqueAxis};
+use crate::hw::spi::CsControl;
+use stm32f7xx_hal::prelude::*;
+
+/// Outputs smaller than this count as zero.
+const ZERO_OUTPUT: f32 = 0.001;
+
+/// Drive range of one axis: where a controller output of magnitude 0..1 lands at the H-bridge.
+///
+/// Gearboxes differ between tile revisions, and a stiff one doesn't turn at all below some duty.
+/// Non-zero outputs are scaled linearly onto `min..=max`, so the smallest correction still moves
+/// the axis and full output stays within what the mechanics take. Direction is set separately by
+/// the driver's inversion flag (`config::Polarity`).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OutputRange {
+    /// Duty for the smallest non-zero output, 0.0..=1.0.
+    pub min: f32,
+    /// Duty at full output, 0.0..=1.0. Raised to `min` if below it.
+    pub max: f32,
+}
+
+impl OutputRange {
+    /// Outputs passed through unchanged.
+    pub const FULL: Self = Self { min: 0.0, max: 1.0 };
+
+    /// Map a signed output in -1.0..1.0 to a signed duty. Outputs near zero, and NaN, give 0.
+    pub fn apply(&self, output: f32) -> f32 {
+        let magnitude = output.abs().min(1.0);
+        if !(magnitude >= ZERO_OUTPUT) {
+            return 0.0;
+        }
+        let min = self.min.clamp(0.0, 1.0);
+        let max = self.max.clamp(0.0, 1.0).max(min);
+        let duty = min + magnitude * (max - min);
+        if output < 0.0 {
+            -duty
+        } else {
+            duty
+        }
+    }
+}
+
+impl Default for OutputRange {
+    fn default() -> Self {
+        Self::FULL
+    }
+}
+
+/// Axis interface needed for closed-loop position control.
+pub trait ClosedLoopAxis {
+    /// Measured position, or `None` without feedback.
+    fn position(&mut self) -> Option<f32>;
+    /// Drive with a signed output in -1.0..1.0. Positive moves towards increasing position.
+    fn apply_output(&mut self, output: f32);
+    /// Stop the axis.
+    fn brake(&mut self);
+    /// True if the axis has detected a fault that must stop control. Only state the axis owns
+    /// (pins, cached readings) can be used, since the controller holds no bus.
+    fn is_faulted(&mut self) -> bool {
+        false
+    }
+    /// Per-step safety checks such as soft end stops, run before the position is read.
+    fn pre_step(&mut self) {}
+}
+
+impl<
+        CS: CsControl,
+        const SLP_P: char,
+        const SLP_N: u8,
+        const DIS_P: char,
+        const DIS_N: u8,
+        Pwm1,
+        Pwm2,
+        ReadPos,
+        const N: usize,
+    > ClosedLoopAxis for ActuonixLinear<CS, SLP_P, SLP_N, DIS_P, DIS_N, Pwm1, Pwm2, ReadPos, N>
+where
+    Pwm1: _embedded_hal_PwmPin<Duty = u16>,
+    Pwm2: _embedded_hal_PwmPin<Duty = u16>,
+    ReadPos: FnMut() -> [u16; N],
+{
+    fn position(&mut self) -> Option<f32> {
+        self.position_mm()
+    }
+
+    fn apply_output(&mut self, output: f32) {
+        self.set_speed(output);
+    }
+
+    fn brake(&mut self) {
+        ActuonixLinear::brake(self);
+    }
+
+    fn pre_step(&mut self) {
+        self.enforce_limits();
+    }
+}
+
+/// Positions in output revolutions. The direction pins have no PWM, so any non-zero output
+/// drives at full speed.
+impl<
+        CS: CsControl,
+        const IN1_P: char,
+        const IN1_N: u8,
+        const IN2_P: char,
+        const IN2_N: u8,
+        const SLP_P: char,
+        const SLP_N: u8,
+        const DIS_P: char,
+        const DIS_N: u8,
+    > ClosedLoopAxis for Fit0185<CS, IN1_P, IN1_N, IN2_P, IN2_N, SLP_P, SLP_N, DIS_P, DIS_N>
+{
+    fn position(&mut self) -> Option<f32> {
+        Some(self.position_revs())
+    }
+
+    fn apply_output(&mut self, output: f32) {
+        self.apply_pid_output(output);
+    }
+
+    fn brake(&mut self) {
+        Fit0185::brake(self);
+    }
+}
+
+/// Open-loop: the position is the count of steps issued. Outputs set the speed.
+impl<STEP, const DIR_P: char, const DIR_N: u8> ClosedLoopAxis for Stepper<STEP, DIR_P, DIR_N> {
+    fn position(&mut self) -> Option<f32> {
+        Some(Stepper::position(self))
+    }
+
+    fn apply_output(&mut self, output: f32) {
+        Stepper::apply_output(self, output);
+    }
+
+    fn brake(&mut self) {
+        Stepper::brake(self);
+    }
+}
+
+/// Positions in output revolutions, counted from hall edges.
+impl<A, B, C, ReadHalls> ClosedLoopAxis for Drv8313<A, B, C, ReadHalls>
+where
+    A: HalfBridge,
+    B: HalfBridge,
+    C: HalfBridge,
+    ReadHalls: FnMut() -> u8,
+{
+    fn position(&mut self) -> Option<f32> {
+        Some(self.position_revs())
+    }
+
+    fn apply_output(&mut self, output: f32) {
+        self.set_speed(output);
+    }
+
+    fn brake(&mut self) {
+        Drv8313::brake(self);
+    }
+
+    fn is_faulted(&mut self) -> bool {
+        Drv8313::is_faulted(self)
+    }
+}
+
+/// Outputs are current targets for the interrupt-driven current loop.
+impl<ReadPos> ClosedLoopAxis for TorqueAxis<ReadPos>
+where
+    ReadPos: FnMut() -> Option<f32>,
+{
+    fn position(&mut self) -> Option<f32> {
+        TorqueAxis::position(self)
+    }
+
+    fn apply_output(&mut self, output: f32) {
+        self.set_torque(output);
+    }
+
+    fn brake(&mut self) {
+        TorqueAxis::brake(self);
+    }
+}
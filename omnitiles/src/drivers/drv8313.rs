@@ -0,0 +1,235 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Brushless (BLDC) motor on a TI DRV8313 triple half-bridge, commutated from hall sensors.
+//!
+//! Intended for a direct-drive tilt mechanism. Each DRV8313 phase has an INx input, driven with
+//! PWM (high = high side on, low = low side on), and an ENx input that floats the phase when low.
+//! Commutation is six-step: the three hall sensors give the rotor's 60° electrical sector, and the
+//! driver PWMs one phase, holds a second low and floats the third so the field leads the rotor.
+//!
+//! The same hall edges count position ([`HallTracker`]), six per electrical revolution, so the
+//! axis runs under the usual PID and motion profile through
+//! [`ClosedLoopAxis`](super::ClosedLoopAxis) with positions in output revolutions.
+//!
+//! Commutation only advances when [`Drv8313::commutate`] (or `apply_output`) sees a new sector. At
+//! the 100 Hz control rate that is enough for a slow, heavily geared axis only; for anything
+//! faster, route the hall pins to EXTI ([`Halls::listen`](crate::hw::Halls::listen)) and call
+//! [`Drv8313::commutate`] from the handler.
+//!
+//! nSLEEP, nRESET and nFAULT are left to the board. A hall reading of all-low or all-high (a
+//! disconnected or shorted sensor) floats the bridge and latches a fault.
+
+use crate::drivers::OutputRange;
+use crate::hw::HallTracker;
+use stm32f7xx_hal::{
+    gpio::{self, Output, PushPull},
+    prelude::*,
+};
+
+/// Drive vectors in forward order: (PWM phase, low phase); the third phase floats.
+const VECTOR: [(usize, usize); 6] = [(0, 1), (0, 2), (1, 2), (1, 0), (2, 0), (2, 1)];
+
+/// One DRV8313 phase: the INx PWM channel and the ENx pin.
+pub struct Phase<Pwm, const EN_P: char, const EN_N: u8> {
+    pwm: Pwm,
+    en: gpio::Pin<EN_P, EN_N, Output<PushPull>>,
+}
+
+impl<Pwm, const EN_P: char, const EN_N: u8> Phase<Pwm, EN_P, EN_N>
+where
+    Pwm: _embedded_hal_PwmPin<Duty = u16>,
+{
+    /// Starts floating.
+    pub fn new(mut pwm: Pwm, mut en: gpio::Pin<EN_P, EN_N, Output<PushPull>>) -> Self {
+        en.set_low();
+        pwm.set_duty(0);
+        pwm.enable();
+        Self { pwm, en }
+    }
+
+    pub fn free(self) -> (Pwm, gpio::Pin<EN_P, EN_N, Output<PushPull>>) {
+        (self.pwm, self.en)
+    }
+}
+
+/// A half-bridge the commutation can drive.
+pub trait HalfBridge {
+    /// Enable the phase and PWM it at `duty` (0.0..=1.0 of the high side).
+    fn drive(&mut self, duty: f32);
+    /// Float the phase.
+    fn float(&mut self);
+}
+
+impl<Pwm, const EN_P: char, const EN_N: u8> HalfBridge for Phase<Pwm, EN_P, EN_N>
+where
+    Pwm: _embedded_hal_PwmPin<Duty = u16>,
+{
+    fn drive(&mut self, duty: f32) {
+        let duty = (duty.clamp(0.0, 1.0) * self.pwm.get_max_duty() as f32) as u16;
+        self.pwm.set_duty(duty);
+        self.en.set_high();
+    }
+
+    fn float(&mut self) {
+        self.en.set_low();
+        self.pwm.set_duty(0);
+    }
+}
+
+pub struct Drv8313<A, B, C, ReadHalls> {
+    a: A,
+    b: B,
+    c: C,
+    /// Returns the hall code, H1 in bit 0.
+    read_halls: ReadHalls,
+    /// Drive vectors the field leads the rotor sector by, for forward output.
+    lead: u8,
+    /// Hall edges per output revolution.
+    edges_per_rev: f32,
+    output_range: OutputRange,
+    inverted: bool,
+    /// Signed duty currently applied.
+    duty: f32,
+    halls: HallTracker,
+}
+
+impl<A, B, C, ReadHalls> Drv8313<A, B, C, ReadHalls>
+where
+    A: HalfBridge,
+    B: HalfBridge,
+    C: HalfBridge,
+    ReadHalls: FnMut() -> u8,
+{
+    /// `pole_pairs` is the motor's, `gear_ratio` motor turns per output turn (1.0 for direct
+    /// drive). Starts with the bridge floating.
+    pub fn new(a: A, b: B, c: C, read_halls: ReadHalls, pole_pairs: u8, gear_ratio: f32) -> Self {
+        let mut drv = Self {
+            a,
+            b,
+            c,
+            read_halls,
+            lead: 1,
+            edges_per_rev: 6.0 * pole_pairs as f32 * gear_ratio,
+            output_range: OutputRange::FULL,
+            inverted: false,
+            duty: 0.0,
+            halls: HallTracker::new(),
+        };
+        drv.coast();
+        let code = (drv.read_halls)();
+        drv.halls.update(code);
+        drv
+    }
+
+    /// Lead of the field over the rotor, in 60° steps (1 or 2 for most motors). It depends on how
+    /// the halls sit relative to the windings: pick the one that runs forward smoothly at low duty
+    /// with the least current.
+    pub fn with_lead(mut self, lead: u8) -> Self {
+        self.lead = lead % 6;
+        self
+    }
+
+    /// Set the duty range outputs are scaled onto.
+    pub fn set_output_range(&mut self, range: OutputRange) {
+        self.output_range = range;
+    }
+
+    /// Swap the forward direction, for position and drive alike.
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.inverted = inverted;
+    }
+
+    /// Drive from -1.0 to 1.0. Near zero brakes.
+    pub fn set_speed(&mut self, speed: f32) {
+        let duty = self.output_range.apply(speed);
+        if duty == 0.0 || self.halls.is_faulted() {
+            self.brake();
+            return;
+        }
+        self.duty = if self.inverted { -duty } else { duty };
+        self.commutate();
+    }
+
+    /// Read the halls, count any edge and apply the drive vector for the rotor's sector.
+    pub fn commutate(&mut self) {
+        let code = (self.read_halls)();
+        let Some(sector) = self.halls.update(code) else {
+            self.coast();
+            return;
+        };
+        if self.duty == 0.0 {
+            return;
+        }
+        let lead = if self.duty > 0.0 {
+            self.lead
+        } else {
+            self.lead + 3
+        };
+        let (high, low) = VECTOR[((sector + lead) % 6) as usize];
+        let duty = self.duty.abs();
+        for phase in 0..3 {
+            let bridge: &mut dyn HalfBridge = match phase {
+                0 => &mut self.a,
+                1 => &mut self.b,
+                _ => &mut self.c,
+            };
+            if phase == high {
+                bridge.drive(duty);
+            } else if phase == low {
+                bridge.drive(0.0);
+            } else {
+                bridge.float();
+            }
+        }
+    }
+
+    /// Short brake: all low sides on.
+    pub fn brake(&mut self) {
+        self.duty = 0.0;
+        self.a.drive(0.0);
+        self.b.drive(0.0);
+        self.c.drive(0.0);
+    }
+
+    /// Float all phases.
+    pub fn coast(&mut self) {
+        self.duty = 0.0;
+        self.a.float();
+        self.b.float();
+        self.c.float();
+    }
+
+    /// Position in output revolutions since construction or [`set_position`](Self::set_position).
+    pub fn position_revs(&mut self) -> f32 {
+        if self.duty == 0.0 {
+            // Keep counting while coasting or braked, when nothing else commutates.
+            self.commutate();
+        }
+        let revs = self.halls.edges() as f32 / self.edges_per_rev;
+        if self.inverted {
+            -revs
+        } else {
+            revs
+        }
+    }
+
+    /// Redefine the current position, e.g. after homing.
+    pub fn set_position(&mut self, revs: f32) {
+        let revs = if self.inverted { -revs } else { revs };
+        self.halls.set_edges((revs * self.edges_per_rev) as i32);
+    }
+
+    /// True once an invalid hall code was seen. Cleared by [`clear_fault`](Self::clear_fault).
+    pub fn is_faulted(&self) -> bool {
+        self.halls.is_faulted()
+    }
+
+    pub fn clear_fault(&mut self) {
+        self.halls.clear_fault();
+    }
+
+    pub fn free(self) -> (A, B, C, ReadHalls) {
+        (self.a, self.b, self.c, self.read_halls)
+    }
+}
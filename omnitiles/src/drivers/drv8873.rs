@@ -6,8 +6,12 @@
 //! This module handles SPI framing and register access for DRV8873-Q1. Higher-level motor control
 //! can be layered on top of these primitives.
 
-use crate::hw::{spi::CsControl, SpiBus};
-use stm32f7xx_hal::spi;
+use crate::hw::{spi::CsControl, BusError, SpiBus};
+use cortex_m::asm;
+use stm32f7xx_hal::{
+    gpio::{self, Output, PushPull},
+    spi::{self, Phase},
+};
 
 // Register addresses
 pub mod reg {
@@ -19,6 +23,70 @@ pub mod reg {
     pub const IC4: u8 = 0x05;
 }
 
+/// IC3 fields.
+pub mod ic3 {
+    /// `CLR_FLT`: write 1 to clear latched faults. Reads back as 0.
+    pub const CLR_FLT: u8 = 1 << 7;
+}
+
+/// IC4 fields controlling ITRIP current regulation. IC3 only carries `CLR_FLT` and `LOCK`.
+pub mod ic4 {
+    /// `ITRIP_LVL`: regulation threshold, see [`ItripLevel`](super::ItripLevel).
+    pub const ITRIP_LVL_SHIFT: u8 = 2;
+    pub const ITRIP_LVL_MASK: u8 = 0b11 << ITRIP_LVL_SHIFT;
+    /// `DIS_ITRIP`: 0b00 regulates both outputs, 0b11 disables regulation on both.
+    pub const DIS_ITRIP_MASK: u8 = 0b11;
+}
+
+/// ITRIP current regulation threshold. When the load current reaches it, the driver chops the
+/// bridge for `TOFF`, which caps motor torque without faulting.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ItripLevel {
+    A4_0 = 0b00,
+    A5_4 = 0b01,
+    A6_5 = 0b10,
+    /// Power-on default.
+    A7_0 = 0b11,
+}
+
+impl ItripLevel {
+    /// Decode the two `ITRIP_LVL` bits.
+    pub fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0b00 => ItripLevel::A4_0,
+            0b01 => ItripLevel::A5_4,
+            0b10 => ItripLevel::A6_5,
+            _ => ItripLevel::A7_0,
+        }
+    }
+
+    /// Nominal threshold in amps.
+    pub fn amps(self) -> f32 {
+        match self {
+            ItripLevel::A4_0 => 4.0,
+            ItripLevel::A5_4 => 5.4,
+            ItripLevel::A6_5 => 6.5,
+            ItripLevel::A7_0 => 7.0,
+        }
+    }
+}
+
+/// IC4 value with current regulation set to `level` (`None` disables it), other fields kept.
+pub fn ic4_with_itrip(ic4: u8, level: Option<ItripLevel>) -> u8 {
+    let rest = ic4 & !(ic4::ITRIP_LVL_MASK | ic4::DIS_ITRIP_MASK);
+    match level {
+        Some(level) => rest | ((level as u8) << ic4::ITRIP_LVL_SHIFT),
+        None => rest | ic4::ITRIP_LVL_MASK | ic4::DIS_ITRIP_MASK,
+    }
+}
+
+/// Number of writable configuration registers (IC1..IC4) kept in the shadow copy.
+const CONFIG_REGS: usize = 4;
+
+/// How often the application should call [`Drv8873::verify_config`].
+pub const VERIFY_INTERVAL_MS: u64 = 500;
+
 /// Status byte returned in the upper 8 bits of SDO.
 #[derive(Copy, Clone, Debug)]
 pub struct Status {
@@ -26,6 +94,11 @@ pub struct Status {
 }
 
 impl Status {
+    #[inline]
+    pub fn from_raw(raw: u8) -> Self {
+        Self { raw }
+    }
+
     #[inline]
     pub fn raw(&self) -> u8 {
         self.raw
@@ -75,6 +148,11 @@ pub struct Fault {
 }
 
 impl Fault {
+    #[inline]
+    pub fn from_raw(raw: u8) -> Self {
+        Self { raw }
+    }
+
     #[inline]
     pub fn raw(&self) -> u8 {
         self.raw
@@ -130,6 +208,11 @@ pub struct Diag {
 }
 
 impl Diag {
+    #[inline]
+    pub fn from_raw(raw: u8) -> Self {
+        Self { raw }
+    }
+
     #[inline]
     pub fn raw(&self) -> u8 {
         self.raw
@@ -200,15 +283,40 @@ pub struct Response {
 /// DRV8873 driver bound to a chip-select control.
 ///
 /// The SPI bus is passed in as &mut to each method so that multiple DRV8873 instances can share the
-/// same bus. Use `NoChipSelect` if the DRV8873's SPI interface is not connected.
+/// same bus. Use `NoChipSelect` if the DRV8873's SPI interface is not connected. Drivers on
+/// [`ChipSelect`](crate::hw::ChipSelect) pins all have the type `Drv8873<ChipSelect>`, so a board
+/// with several of them can keep them in an array.
+///
+/// Every value written to IC1..IC4 is kept in a shadow copy. The device resets its configuration
+/// to defaults after a UVLO event, and a glitched SPI frame can corrupt a register, so the
+/// application should call [`Drv8873::verify_config`] every [`VERIFY_INTERVAL_MS`] to read the
+/// registers back and re-apply anything that no longer matches.
 pub struct Drv8873<CS: CsControl> {
     cs: CS,
+    shadow: [Option<u8>; CONFIG_REGS],
 }
 
 impl<CS: CsControl> Drv8873<CS> {
     /// Construct a driver from a chip-select control (real pin or `NoChipSelect`).
     pub fn new(cs: CS) -> Self {
-        Self { cs }
+        Self {
+            cs,
+            shadow: [None; CONFIG_REGS],
+        }
+    }
+
+    /// Shadow slot for a configuration register address, if it is one.
+    #[inline]
+    fn shadow_index(addr: u8) -> Option<usize> {
+        match addr {
+            reg::IC1..=reg::IC4 => Some((addr - reg::IC1) as usize),
+            _ => None,
+        }
+    }
+
+    /// Last value written to a configuration register, or `None` if it was never written.
+    pub fn shadow(&self, addr: u8) -> Option<u8> {
+        Self::shadow_index(addr).and_then(|i| self.shadow[i])
     }
 
     /// Release the chip-select control.
@@ -241,22 +349,27 @@ impl<CS: CsControl> Drv8873<CS> {
     }
 
     /// Send a 16-bit word and receive the status + data bytes.
-    /// - `spi` must be an enabled 8-bit SPI bus configured in the correct mode
-    ///   for the DRV8873 (CPOL=0, CPHA=1: data captured on falling edge, driven on rising edge).
+    /// - `spi` must be an enabled 8-bit SPI bus with CPOL=0. The word is clocked with CPHA=1
+    ///   (data captured on falling edge, driven on rising edge) through
+    ///   [`SpiBus::with_phase`], so a bus shared with mode 0 devices needs
+    ///   [`SpiBus::with_phase_switching`].
+    ///
+    /// The status byte always starts with two set bits and the FAULT register's top bit is
+    /// reserved, so a word of all zeros or all ones is a bus fault and is retried.
     pub fn transfer_word<I, PINS>(
         &mut self,
         spi: &mut SpiBus<I, PINS>,
         word: u16,
-    ) -> Result<Response, spi::Error>
+    ) -> Result<Response, BusError>
     where
         I: spi::Instance,
         PINS: spi::Pins<I>,
     {
         let mut buf = [(word >> 8) as u8, word as u8];
 
-        self.cs.select();
-        spi.transfer_in_place(&mut buf)?;
-        self.cs.deselect();
+        spi.with_phase(Phase::CaptureOnSecondTransition, |spi| {
+            spi.transfer_checked(&mut self.cs, &mut buf, 0)
+        })?;
 
         let status = Status { raw: buf[0] };
         let data = buf[1];
@@ -270,13 +383,17 @@ impl<CS: CsControl> Drv8873<CS> {
         spi: &mut SpiBus<I, PINS>,
         addr: u8,
         value: u8,
-    ) -> Result<Response, spi::Error>
+    ) -> Result<Response, BusError>
     where
         I: spi::Instance,
         PINS: spi::Pins<I>,
     {
         let word = Self::build_word(false, addr, value);
-        self.transfer_word(spi, word)
+        let resp = self.transfer_word(spi, word)?;
+        if let Some(i) = Self::shadow_index(addr) {
+            self.shadow[i] = Some(value);
+        }
+        Ok(resp)
     }
 
     /// Read a register and return the response (status + register value).
@@ -284,7 +401,7 @@ impl<CS: CsControl> Drv8873<CS> {
         &mut self,
         spi: &mut SpiBus<I, PINS>,
         addr: u8,
-    ) -> Result<Response, spi::Error>
+    ) -> Result<Response, BusError>
     where
         I: spi::Instance,
         PINS: spi::Pins<I>,
@@ -296,7 +413,7 @@ impl<CS: CsControl> Drv8873<CS> {
     /// Read the FAULT register and parse into a `Fault` struct.
     ///
     /// To get the status result as well, use `read_reg`.
-    pub fn read_fault<I, PINS>(&mut self, spi: &mut SpiBus<I, PINS>) -> Result<Fault, spi::Error>
+    pub fn read_fault<I, PINS>(&mut self, spi: &mut SpiBus<I, PINS>) -> Result<Fault, BusError>
     where
         I: spi::Instance,
         PINS: spi::Pins<I>,
@@ -309,7 +426,7 @@ impl<CS: CsControl> Drv8873<CS> {
     /// Read the DIAG register and parse into a `Diag` struct.
     ///
     /// To get the status result as well, use `read_reg`.
-    pub fn read_diag<I, PINS>(&mut self, spi: &mut SpiBus<I, PINS>) -> Result<Diag, spi::Error>
+    pub fn read_diag<I, PINS>(&mut self, spi: &mut SpiBus<I, PINS>) -> Result<Diag, BusError>
     where
         I: spi::Instance,
         PINS: spi::Pins<I>,
@@ -318,4 +435,178 @@ impl<CS: CsControl> Drv8873<CS> {
             raw: self.read_reg(spi, reg::DIAG)?.data,
         })
     }
+
+    /// Enable ITRIP current regulation at `level` on both outputs, or disable it with `None`.
+    ///
+    /// Starts from the shadowed IC4 value, or the device's if IC4 was never written, so other IC4
+    /// settings are kept. Use [`read_diag`](Self::read_diag) (`itrip1`/`itrip2`) to see whether
+    /// the driver is currently regulating.
+    pub fn set_itrip<I, PINS>(
+        &mut self,
+        spi: &mut SpiBus<I, PINS>,
+        level: Option<ItripLevel>,
+    ) -> Result<(), BusError>
+    where
+        I: spi::Instance,
+        PINS: spi::Pins<I>,
+    {
+        let current = match self.shadow(reg::IC4) {
+            Some(v) => v,
+            None => self.read_reg(spi, reg::IC4)?.data,
+        };
+        self.write_reg(spi, reg::IC4, ic4_with_itrip(current, level))?;
+        Ok(())
+    }
+
+    /// Read back every shadowed configuration register and rewrite those that differ.
+    ///
+    /// Returns a bitmask of the registers that were re-applied (bit 0 = IC1 .. bit 3 = IC4), so 0
+    /// means the device configuration matched. A non-zero result usually means the chip reset
+    /// (e.g., after UVLO) and should be logged.
+    pub fn verify_config<I, PINS>(&mut self, spi: &mut SpiBus<I, PINS>) -> Result<u8, BusError>
+    where
+        I: spi::Instance,
+        PINS: spi::Pins<I>,
+    {
+        let mut restored = 0u8;
+        for i in 0..CONFIG_REGS {
+            let Some(expected) = self.shadow[i] else {
+                continue;
+            };
+            let addr = reg::IC1 + i as u8;
+            if self.read_reg(spi, addr)?.data != expected {
+                self.write_reg(spi, addr, expected)?;
+                restored |= 1 << i;
+            }
+        }
+        Ok(restored)
+    }
+}
+
+/// Register access shared by [`Drv8873`] and the `mock-drv8873` stand-in, so fault handling is
+/// written once and can run without hardware. `B` is whatever reaches the device: the SPI bus for
+/// [`Drv8873`], `()` for the mock.
+pub trait Drv8873Regs<B> {
+    /// Read a register and return the response (status + register value).
+    fn read_reg(&mut self, bus: &mut B, addr: u8) -> Result<Response, BusError>;
+
+    /// Write a register and return the response (status + current register contents).
+    fn write_reg(&mut self, bus: &mut B, addr: u8, value: u8) -> Result<Response, BusError>;
+
+    /// Enable ITRIP current regulation at `level` on both outputs, or disable it with `None`.
+    fn set_itrip(&mut self, bus: &mut B, level: Option<ItripLevel>) -> Result<(), BusError>;
+
+    /// Re-apply configuration registers that no longer match; returns the restored bitmask.
+    fn verify_config(&mut self, bus: &mut B) -> Result<u8, BusError>;
+
+    /// Read the FAULT register.
+    fn read_fault(&mut self, bus: &mut B) -> Result<Fault, BusError> {
+        Ok(Fault::from_raw(self.read_reg(bus, reg::FAULT)?.data))
+    }
+
+    /// Read the DIAG register.
+    fn read_diag(&mut self, bus: &mut B) -> Result<Diag, BusError> {
+        Ok(Diag::from_raw(self.read_reg(bus, reg::DIAG)?.data))
+    }
+}
+
+impl<CS: CsControl, I, PINS> Drv8873Regs<SpiBus<I, PINS>> for Drv8873<CS>
+where
+    I: spi::Instance,
+    PINS: spi::Pins<I>,
+{
+    fn read_reg(&mut self, spi: &mut SpiBus<I, PINS>, addr: u8) -> Result<Response, BusError> {
+        Drv8873::read_reg(self, spi, addr)
+    }
+
+    fn write_reg(
+        &mut self,
+        spi: &mut SpiBus<I, PINS>,
+        addr: u8,
+        value: u8,
+    ) -> Result<Response, BusError> {
+        Drv8873::write_reg(self, spi, addr, value)
+    }
+
+    fn set_itrip(
+        &mut self,
+        spi: &mut SpiBus<I, PINS>,
+        level: Option<ItripLevel>,
+    ) -> Result<(), BusError> {
+        Drv8873::set_itrip(self, spi, level)
+    }
+
+    fn verify_config(&mut self, spi: &mut SpiBus<I, PINS>) -> Result<u8, BusError> {
+        Drv8873::verify_config(self, spi)
+    }
+}
+
+/// Check for a latched fault and clear it.
+///
+/// Returns the FAULT and DIAG readings if the global fault bit was set, after writing `CLR_FLT`
+/// so the bridge can drive again. `Ok(None)` means the driver is healthy.
+pub fn take_fault<B, D: Drv8873Regs<B>>(
+    drv: &mut D,
+    bus: &mut B,
+) -> Result<Option<(Fault, Diag)>, BusError> {
+    let fault = drv.read_fault(bus)?;
+    if !fault.fault() {
+        return Ok(None);
+    }
+    let diag = drv.read_diag(bus)?;
+    let value = drv.read_reg(bus, reg::IC3)?.data & !ic3::CLR_FLT;
+    drv.write_reg(bus, reg::IC3, value | ic3::CLR_FLT)?;
+    // CLR_FLT reads back as 0. Write the register again without it so the shadow copy matches
+    // the device and `verify_config` doesn't keep clearing faults.
+    drv.write_reg(bus, reg::IC3, value)?;
+    Ok(Some((fault, diag)))
+}
+
+/// CPU cycles covering `tWAKE` (nSLEEP high to outputs ready, 1 ms max) at the 216 MHz SYSCLK.
+/// Lower clocks only make the wait longer.
+const WAKE_CYCLES: u32 = 216_000;
+
+/// nSLEEP line of a DRV8873.
+///
+/// The driver ignores the inputs and SPI for `tWAKE` after nSLEEP rises, so [`wake`](Self::wake)
+/// blocks until the outputs are ready. It tracks the sleep state, so waking an awake driver is
+/// free and can be done before every enable. Sleeping also clears latched faults.
+pub struct SleepPin<const P: char, const N: u8> {
+    pin: gpio::Pin<P, N, Output<PushPull>>,
+    asleep: bool,
+}
+
+impl<const P: char, const N: u8> SleepPin<P, N> {
+    /// Take the pin as a push-pull output and wake the driver.
+    pub fn new<MODE>(pin: gpio::Pin<P, N, MODE>) -> Self {
+        let mut this = Self {
+            pin: pin.into_push_pull_output(),
+            asleep: true,
+        };
+        this.wake();
+        this
+    }
+
+    pub fn free(self) -> gpio::Pin<P, N, Output<PushPull>> {
+        self.pin
+    }
+
+    /// Put the driver to sleep. [`Drv8873::verify_config`] restores its registers after waking.
+    pub fn sleep(&mut self) {
+        self.pin.set_low();
+        self.asleep = true;
+    }
+
+    /// Wake the driver, waiting out `tWAKE` if it was asleep.
+    pub fn wake(&mut self) {
+        if self.asleep {
+            self.pin.set_high();
+            asm::delay(WAKE_CYCLES);
+            self.asleep = false;
+        }
+    }
+
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
 }
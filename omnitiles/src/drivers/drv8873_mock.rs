@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Scripted stand-in for [`Drv8873`](super::Drv8873), enabled with the `mock-drv8873` feature.
+//!
+//! The mock mirrors the register API of the SPI driver without a bus: writes are recorded so a
+//! test can assert what configuration was applied, and FAULT/DIAG reads return a scripted
+//! sequence so OCP, UVLO and open-load handling can be exercised without provoking real faults.
+//! It implements [`Drv8873Regs`] on the `()` bus, so code written against that trait runs on
+//! either driver.
+//!
+//! A scripted UVLO step also resets the configuration registers, like the real device does, so
+//! the shadow/verify path re-applies them.
+
+use crate::drivers::drv8873::{
+    ic3::CLR_FLT, ic4_with_itrip, reg, Diag, Drv8873Regs, Fault, ItripLevel, Response, Status,
+};
+use crate::hw::BusError;
+
+/// Writes kept before the oldest are dropped.
+pub const WRITE_LOG_LEN: usize = 32;
+
+/// Scripted steps kept before new ones are refused.
+pub const SCRIPT_LEN: usize = 16;
+
+/// One scripted FAULT/DIAG reading.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Step {
+    pub fault: u8,
+    pub diag: u8,
+}
+
+impl Step {
+    /// Overcurrent on the high-side FET of half bridge 1.
+    pub const OCP_H1: Step = Step {
+        fault: (1 << 6) | (1 << 2),
+        diag: 1 << 3,
+    };
+
+    /// Supply undervoltage; the device resets its configuration registers.
+    pub const UVLO: Step = Step {
+        fault: (1 << 6) | (1 << 4),
+        diag: 0,
+    };
+
+    /// No faults.
+    pub const CLEAR: Step = Step { fault: 0, diag: 0 };
+}
+
+/// Recorded register write.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Write {
+    pub addr: u8,
+    pub value: u8,
+}
+
+/// Fake DRV8873 with a register file, a write log and a fault script.
+pub struct MockDrv8873 {
+    regs: [u8; 6],
+    shadow: [Option<u8>; 4],
+    writes: [Write; WRITE_LOG_LEN],
+    write_count: usize,
+    script: [Step; SCRIPT_LEN],
+    script_len: usize,
+    script_pos: usize,
+}
+
+impl MockDrv8873 {
+    /// New mock with all registers zero and an empty script.
+    pub const fn new() -> Self {
+        Self {
+            regs: [0; 6],
+            shadow: [None; 4],
+            writes: [Write { addr: 0, value: 0 }; WRITE_LOG_LEN],
+            write_count: 0,
+            script: [Step::CLEAR; SCRIPT_LEN],
+            script_len: 0,
+            script_pos: 0,
+        }
+    }
+
+    /// Append steps to the fault script. Returns `false` if the script is full.
+    pub fn script(&mut self, steps: &[Step]) -> bool {
+        if self.script_len + steps.len() > SCRIPT_LEN {
+            return false;
+        }
+        self.script[self.script_len..self.script_len + steps.len()].copy_from_slice(steps);
+        self.script_len += steps.len();
+        true
+    }
+
+    /// Advance the script by one step, applying it to FAULT/DIAG. Once the script is exhausted
+    /// the last step repeats.
+    fn advance(&mut self) {
+        if self.script_pos >= self.script_len {
+            return;
+        }
+        let step = self.script[self.script_pos];
+        self.script_pos += 1;
+        self.regs[reg::FAULT as usize] = step.fault;
+        self.regs[reg::DIAG as usize] = step.diag;
+        if Fault::from_raw(step.fault).uvlo() {
+            self.regs[reg::IC1 as usize..=reg::IC4 as usize].fill(0);
+        }
+    }
+
+    fn status(&self) -> Status {
+        // The real status byte always starts with two set bits.
+        Status::from_raw(0xC0 | (self.regs[reg::FAULT as usize] & 0x3F))
+    }
+
+    /// Write a register. FAULT and DIAG are read-only; writing IC3's CLR_FLT bit clears them.
+    pub fn write_reg(&mut self, addr: u8, value: u8) -> Response {
+        let idx = self.write_count % WRITE_LOG_LEN;
+        self.writes[idx] = Write { addr, value };
+        self.write_count += 1;
+
+        match addr {
+            reg::IC1..=reg::IC4 => {
+                self.regs[addr as usize] = value;
+                self.shadow[(addr - reg::IC1) as usize] = Some(value);
+                if addr == reg::IC3 && value & CLR_FLT != 0 {
+                    self.regs[reg::FAULT as usize] = 0;
+                    self.regs[reg::DIAG as usize] = 0;
+                }
+            }
+            _ => {}
+        }
+        Response {
+            status: self.status(),
+            data: self.regs.get(addr as usize).copied().unwrap_or(0),
+        }
+    }
+
+    /// Read a register. Reading FAULT advances the script.
+    pub fn read_reg(&mut self, addr: u8) -> Response {
+        if addr == reg::FAULT {
+            self.advance();
+        }
+        Response {
+            status: self.status(),
+            data: self.regs.get(addr as usize).copied().unwrap_or(0),
+        }
+    }
+
+    /// Read FAULT, advancing the script.
+    pub fn read_fault(&mut self) -> Fault {
+        Fault::from_raw(self.read_reg(reg::FAULT).data)
+    }
+
+    /// Read DIAG for the current script step.
+    pub fn read_diag(&mut self) -> Diag {
+        Diag::from_raw(self.read_reg(reg::DIAG).data)
+    }
+
+    /// Same contract as [`Drv8873::set_itrip`](super::Drv8873::set_itrip).
+    pub fn set_itrip(&mut self, level: Option<ItripLevel>) {
+        let current = match self.shadow[(reg::IC4 - reg::IC1) as usize] {
+            Some(v) => v,
+            None => self.read_reg(reg::IC4).data,
+        };
+        self.write_reg(reg::IC4, ic4_with_itrip(current, level));
+    }
+
+    /// Same contract as [`Drv8873::verify_config`](super::Drv8873::verify_config).
+    pub fn verify_config(&mut self) -> u8 {
+        let mut restored = 0u8;
+        for i in 0..self.shadow.len() {
+            let Some(expected) = self.shadow[i] else {
+                continue;
+            };
+            let addr = reg::IC1 + i as u8;
+            if self.read_reg(addr).data != expected {
+                self.write_reg(addr, expected);
+                restored |= 1 << i;
+            }
+        }
+        restored
+    }
+
+    /// Recorded writes, oldest first. Only the last [`WRITE_LOG_LEN`] are kept.
+    pub fn writes(&self) -> impl Iterator<Item = &Write> {
+        let n = self.write_count.min(WRITE_LOG_LEN);
+        let start = self.write_count - n;
+        (start..self.write_count).map(move |i| &self.writes[i % WRITE_LOG_LEN])
+    }
+
+    /// Total writes since construction.
+    pub fn write_count(&self) -> usize {
+        self.write_count
+    }
+}
+
+impl Default for MockDrv8873 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drv8873Regs<()> for MockDrv8873 {
+    fn read_reg(&mut self, _: &mut (), addr: u8) -> Result<Response, BusError> {
+        Ok(MockDrv8873::read_reg(self, addr))
+    }
+
+    fn write_reg(&mut self, _: &mut (), addr: u8, value: u8) -> Result<Response, BusError> {
+        Ok(MockDrv8873::write_reg(self, addr, value))
+    }
+
+    fn set_itrip(&mut self, _: &mut (), level: Option<ItripLevel>) -> Result<(), BusError> {
+        MockDrv8873::set_itrip(self, level);
+        Ok(())
+    }
+
+    fn verify_config(&mut self, _: &mut ()) -> Result<u8, BusError> {
+        Ok(MockDrv8873::verify_config(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::drv8873::take_fault;
+
+    #[test]
+    fn ocp_is_reported_once_and_cleared() {
+        let mut drv = MockDrv8873::new();
+        assert!(drv.script(&[Step::OCP_H1]));
+
+        let (fault, diag) = take_fault(&mut drv, &mut ()).unwrap().unwrap();
+        assert!(fault.ocp());
+        assert!(diag.ocp_h1());
+        assert!(drv
+            .writes()
+            .any(|w| w.addr == reg::IC3 && w.value & CLR_FLT != 0));
+
+        // The script is exhausted and the clear stuck, so the next check is healthy.
+        assert!(take_fault(&mut drv, &mut ()).unwrap().is_none());
+    }
+
+    #[test]
+    fn clearing_a_fault_leaves_ic3_verified() {
+        let mut drv = MockDrv8873::new();
+        drv.script(&[Step::OCP_H1]);
+        take_fault(&mut drv, &mut ()).unwrap();
+        assert_eq!(Drv8873Regs::verify_config(&mut drv, &mut ()).unwrap(), 0);
+    }
+
+    #[test]
+    fn uvlo_resets_config_and_verify_restores_it() {
+        let mut drv = MockDrv8873::new();
+        Drv8873Regs::set_itrip(&mut drv, &mut (), Some(ItripLevel::A6_5)).unwrap();
+        let expected = ic4_with_itrip(0, Some(ItripLevel::A6_5));
+        drv.script(&[Step::UVLO]);
+
+        let fault = Drv8873Regs::read_fault(&mut drv, &mut ()).unwrap();
+        assert!(fault.uvlo());
+        assert_ne!(drv.read_reg(reg::IC4).data, expected);
+
+        let restored = Drv8873Regs::verify_config(&mut drv, &mut ()).unwrap();
+        assert_eq!(restored, 1 << (reg::IC4 - reg::IC1));
+        assert_eq!(drv.read_reg(reg::IC4).data, expected);
+    }
+
+    #[test]
+    fn script_refuses_overflow() {
+        let mut drv = MockDrv8873::new();
+        assert!(drv.script(&[Step::CLEAR; SCRIPT_LEN]));
+        assert!(!drv.script(&[Step::CLEAR]));
+    }
+
+    #[test]
+    fn write_log_keeps_the_newest() {
+        let mut drv = MockDrv8873::new();
+        for i in 0..WRITE_LOG_LEN + 2 {
+            drv.write_reg(reg::IC1, i as u8);
+        }
+        assert_eq!(drv.write_count(), WRITE_LOG_LEN + 2);
+        assert_eq!(drv.writes().next().unwrap().value, 2);
+        assert_eq!(drv.writes().last().unwrap().value, WRITE_LOG_LEN as u8 + 1);
+    }
+}
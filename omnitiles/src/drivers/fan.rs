@@ -0,0 +1,68 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Cooling fan on a PWM channel.
+//!
+//! Works with a 4-wire PC fan, whose PWM input wants about 25 kHz, or a 2-wire fan switched by a
+//! MOSFET. The caller sets the timer up, e.g.
+//! `dp.TIM10.pwm::<_, _, 1_000_000>(pin, 40.micros(), &clocks)`; [`Fan`] only writes duty.
+//!
+//! Below some duty a fan stalls instead of turning slowly, and a stalled fan moves no air while
+//! still looking commanded. Any non-zero duty is therefore raised to the fan's minimum, see
+//! [`Fan::with_min_duty`]. Duty 0 turns the output off.
+
+use stm32f7xx_hal::prelude::*;
+
+pub struct Fan<Pwm> {
+    pwm: Pwm,
+    min_duty: f32,
+    duty: f32,
+}
+
+impl<Pwm> Fan<Pwm>
+where
+    Pwm: _embedded_hal_PwmPin<Duty = u16>,
+{
+    /// Take the channel, with the fan off.
+    pub fn new(mut pwm: Pwm) -> Self {
+        pwm.set_duty(0);
+        pwm.disable();
+        Self {
+            pwm,
+            min_duty: 0.0,
+            duty: 0.0,
+        }
+    }
+
+    /// Set the lowest duty the fan reliably starts and keeps turning at (0.2–0.3 for most PC
+    /// fans).
+    pub fn with_min_duty(mut self, duty: f32) -> Self {
+        self.min_duty = duty.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Run the fan at `duty` (0.0..=1.0), raised to the minimum duty; 0 stops it.
+    pub fn set_duty(&mut self, duty: f32) {
+        let duty = if duty > 0.0 {
+            duty.clamp(self.min_duty, 1.0)
+        } else {
+            0.0
+        };
+        if duty == self.duty {
+            return;
+        }
+        let max = self.pwm.get_max_duty() as f32;
+        self.pwm.set_duty((duty * max) as u16);
+        if duty == 0.0 {
+            self.pwm.disable();
+        } else if self.duty == 0.0 {
+            self.pwm.enable();
+        }
+        self.duty = duty;
+    }
+
+    /// Duty applied, 0 while off.
+    pub fn duty(&self) -> f32 {
+        self.duty
+    }
+}
@@ -5,10 +5,12 @@
 //!
 //! This module includes functions to drive the motor and read encoder values.
 
-use crate::drivers::drv8873::{Diag, Drv8873, Fault};
+use crate::drivers::drv8873::{Diag, Drv8873, Fault, ItripLevel, SleepPin};
+use crate::drivers::OutputRange;
 use crate::hw::spi::CsControl;
-use crate::hw::{Encoder, SpiBus};
+use crate::hw::{iwdg, BusError, Encoder, SpiBus};
 
+use cortex_m::delay::Delay;
 use micromath::F32Ext;
 
 use stm32f7xx_hal::{
@@ -25,6 +27,24 @@ pub enum Direction {
     Coast,
 }
 
+/// Encoder ticks that count as "still moving" between CPR calibration samples.
+const CAL_STALL_TICKS: i32 = 5;
+/// Sampling period used while seeking an end stop during CPR calibration.
+const CAL_SAMPLE_MS: u32 = 20;
+/// Consecutive stalled samples that mark an end stop.
+const CAL_STALL_SAMPLES: u32 = 5;
+
+/// Error returned by the counts-per-rev calibration routines.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CalibrationError {
+    /// The motor did not reach an end stop or the requested index count in time.
+    Timeout,
+    /// The encoder did not register any movement.
+    NoMotion,
+    /// The travel between the stops is not a positive number of revolutions.
+    InvalidTravel,
+}
+
 /// Motor abstraction that combines a DRV8873 driver, four control pins, and a TIM2 encoder.
 pub struct Fit0185<
     CS: CsControl,
@@ -41,9 +61,12 @@ pub struct Fit0185<
     enc: Encoder<pac::TIM2>,
     in1: gpio::Pin<IN1_P, IN1_N, Output<PushPull>>,
     in2: gpio::Pin<IN2_P, IN2_N, Output<PushPull>>,
-    nsleep: gpio::Pin<SLP_P, SLP_N, Output<PushPull>>,
+    nsleep: SleepPin<SLP_P, SLP_N>,
     disable: gpio::Pin<DIS_P, DIS_N, Output<PushPull>>,
     counts_per_rev: u32,
+    travel_revs: f32,
+    inverted: bool,
+    output_range: OutputRange,
 }
 
 impl<
@@ -72,15 +95,14 @@ impl<
     ) -> Self {
         let mut in1 = in1.into_push_pull_output();
         let mut in2 = in2.into_push_pull_output();
-        let mut nsleep = nsleep.into_push_pull_output();
         let mut disable = disable.into_push_pull_output();
 
         in1.set_low();
         in2.set_low();
 
         // Initialize as awake + disabled
-        nsleep.set_high();
         disable.set_high();
+        let nsleep = SleepPin::new(nsleep);
 
         Self {
             drv,
@@ -90,6 +112,9 @@ impl<
             nsleep,
             disable,
             counts_per_rev,
+            travel_revs: 0.0,
+            inverted: false,
+            output_range: OutputRange::FULL,
         }
     }
 
@@ -109,13 +134,13 @@ impl<
             self.enc,
             self.in1,
             self.in2,
-            self.nsleep,
+            self.nsleep.free(),
             self.disable,
         )
     }
 
     /// Initialize and set base configuration for the DRV8873.
-    pub fn init<I, PINS>(&mut self, spi_bus: &mut SpiBus<I, PINS>) -> Result<(), spi::Error>
+    pub fn init<I, PINS>(&mut self, spi_bus: &mut SpiBus<I, PINS>) -> Result<(), BusError>
     where
         I: spi::Instance,
         PINS: spi::Pins<I>,
@@ -128,10 +153,7 @@ impl<
 
     /// Read the FAULT status register.
     #[inline]
-    pub fn read_fault<I, PINS>(
-        &mut self,
-        spi_bus: &mut SpiBus<I, PINS>,
-    ) -> Result<Fault, spi::Error>
+    pub fn read_fault<I, PINS>(&mut self, spi_bus: &mut SpiBus<I, PINS>) -> Result<Fault, BusError>
     where
         I: spi::Instance,
         PINS: spi::Pins<I>,
@@ -141,7 +163,7 @@ impl<
 
     /// Read the DIAG status register.
     #[inline]
-    pub fn read_diag<I, PINS>(&mut self, spi_bus: &mut SpiBus<I, PINS>) -> Result<Diag, spi::Error>
+    pub fn read_diag<I, PINS>(&mut self, spi_bus: &mut SpiBus<I, PINS>) -> Result<Diag, BusError>
     where
         I: spi::Instance,
         PINS: spi::Pins<I>,
@@ -149,6 +171,33 @@ impl<
         self.drv.read_diag(spi_bus)
     }
 
+    /// Cap the motor current with the DRV8873's ITRIP regulation, or remove the cap with `None`.
+    /// See [`Drv8873::set_itrip`].
+    pub fn set_current_limit<I, PINS>(
+        &mut self,
+        spi_bus: &mut SpiBus<I, PINS>,
+        level: Option<ItripLevel>,
+    ) -> Result<(), BusError>
+    where
+        I: spi::Instance,
+        PINS: spi::Pins<I>,
+    {
+        self.drv.set_itrip(spi_bus, level)
+    }
+
+    /// Verify the DRV8873 configuration against its shadow copy, re-applying it if the driver
+    /// reset. See [`Drv8873::verify_config`].
+    pub fn verify_driver_config<I, PINS>(
+        &mut self,
+        spi_bus: &mut SpiBus<I, PINS>,
+    ) -> Result<u8, BusError>
+    where
+        I: spi::Instance,
+        PINS: spi::Pins<I>,
+    {
+        self.drv.verify_config(spi_bus)
+    }
+
     /// Access the underlying DRV8873 driver for advanced SPI control.
     #[inline]
     pub fn drv(&mut self) -> &mut Drv8873<CS> {
@@ -160,13 +209,13 @@ impl<
     /// This shuts down most of the internal circuitry to reduce power consumption.
     #[inline]
     pub fn sleep(&mut self) {
-        self.nsleep.set_low();
+        self.nsleep.sleep();
     }
 
-    /// Wake the driver from sleep mode.
+    /// Wake the driver from sleep mode. Blocks for `tWAKE` (1 ms) if it was asleep.
     #[inline]
     pub fn wake(&mut self) {
-        self.nsleep.set_high();
+        self.nsleep.wake();
     }
 
     /// Enable the motor and wake the driver if in sleep.
@@ -183,8 +232,43 @@ impl<
         self.disable.set_high();
     }
 
+    /// Swap the meaning of forward and reverse, for units with swapped motor leads.
+    ///
+    /// Combine with [`Encoder::set_inverted`] on [`encoder_mut`](Self::encoder_mut) so that a
+    /// positive command still produces a positive encoder count.
+    #[inline]
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.inverted = inverted;
+    }
+
+    /// True if the motor direction is inverted.
+    #[inline]
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Apply the stored wiring polarity (`config::Polarity::motor_inverted` and
+    /// `encoder_inverted`) to the motor and its encoder.
+    pub fn set_polarity(&mut self, motor_inverted: bool, encoder_inverted: bool) {
+        self.set_inverted(motor_inverted);
+        self.enc.set_inverted(encoder_inverted);
+    }
+
+    /// Set the output range used by [`apply_pid_output`](Self::apply_pid_output). The direction
+    /// pins have no PWM, so the range can't scale the drive: outputs below `min` coast instead
+    /// of driving at full speed, and `max` is unused.
+    #[inline]
+    pub fn set_output_range(&mut self, range: OutputRange) {
+        self.output_range = range;
+    }
+
     /// Configure IN1/IN2 pins for a given direction/mode.
     fn set_direction_pins(&mut self, dir: Direction) {
+        let dir = match (dir, self.inverted) {
+            (Direction::Forward, true) => Direction::Reverse,
+            (Direction::Reverse, true) => Direction::Forward,
+            (d, _) => d,
+        };
         match dir {
             Direction::Forward => {
                 self.in1.set_high();
@@ -278,8 +362,153 @@ impl<
         &mut self.enc
     }
 
-    /// Apply PID output.
+    /// Encoder counts per output revolution currently used for conversions.
+    #[inline]
+    pub fn counts_per_rev(&self) -> u32 {
+        self.counts_per_rev
+    }
+
+    /// Override the counts per output revolution, e.g. with a calibrated value from config.
+    #[inline]
+    pub fn set_counts_per_rev(&mut self, counts_per_rev: u32) {
+        self.counts_per_rev = counts_per_rev;
+    }
+
+    /// Output revolutions between the two hard stops, used when the CPR is measured during
+    /// provisioning. Unset (zero) until the mechanism is known.
+    #[inline]
+    pub fn set_travel_revs(&mut self, travel_revs: f32) {
+        self.travel_revs = travel_revs;
+    }
+
+    /// Travel set by [`set_travel_revs`](Self::set_travel_revs).
+    #[inline]
+    pub fn travel_revs(&self) -> f32 {
+        self.travel_revs
+    }
+
+    /// Drive in the current direction until the encoder stops advancing, then brake.
+    fn seek_stall(&mut self, delay: &mut Delay, timeout_ms: u32) -> Result<i32, CalibrationError> {
+        let mut last = self.position_ticks();
+        let mut stalled = 0;
+        let mut elapsed = 0;
+        while elapsed < timeout_ms {
+            iwdg::feed();
+            delay.delay_ms(CAL_SAMPLE_MS);
+            elapsed += CAL_SAMPLE_MS;
+            let now = self.position_ticks();
+            if now.wrapping_sub(last).abs() <= CAL_STALL_TICKS {
+                stalled += 1;
+                if stalled >= CAL_STALL_SAMPLES {
+                    self.brake();
+                    return Ok(now);
+                }
+            } else {
+                stalled = 0;
+            }
+            last = now;
+        }
+        self.brake();
+        Err(CalibrationError::Timeout)
+    }
+
+    /// Measure the effective counts per output revolution from limit-to-limit travel.
+    ///
+    /// Drives in reverse to the lower hard stop, zeroes the encoder, then drives forward to the
+    /// upper hard stop. `travel_revs` is the known number of output revolutions between the two
+    /// stops (e.g. stroke length divided by lead). The measured value is applied and returned.
+    ///
+    /// The stall is detected from the encoder, so the motor presses into each stop for a few
+    /// samples. Set a low [`set_current_limit`](Self::set_current_limit) beforehand to bound the
+    /// torque at the stops, and restore it afterwards.
+    pub fn measure_counts_per_rev_limits(
+        &mut self,
+        travel_revs: f32,
+        delay: &mut Delay,
+        timeout_ms: u32,
+    ) -> Result<u32, CalibrationError> {
+        if !travel_revs.is_finite() || travel_revs <= 0.0 {
+            return Err(CalibrationError::InvalidTravel);
+        }
+
+        self.reverse();
+        self.seek_stall(delay, timeout_ms)?;
+        self.zero();
+
+        self.forward();
+        let ticks = self.seek_stall(delay, timeout_ms)?.abs();
+        if ticks <= CAL_STALL_TICKS {
+            return Err(CalibrationError::NoMotion);
+        }
+
+        let cpr = (ticks as f32 / travel_revs).round() as u32;
+        self.counts_per_rev = cpr;
+        Ok(cpr)
+    }
+
+    /// Measure the effective counts per output revolution using the output shaft index pulse.
+    ///
+    /// Drives forward and records the encoder position at the first rising edge of `index`, then
+    /// at the edge `revs` revolutions later. `index` must return the current level of the index
+    /// sensor. The measured value is applied and returned.
+    pub fn measure_counts_per_rev_index<F>(
+        &mut self,
+        revs: u32,
+        mut index: F,
+        delay: &mut Delay,
+        timeout_ms: u32,
+    ) -> Result<u32, CalibrationError>
+    where
+        F: FnMut() -> bool,
+    {
+        const POLL_US: u32 = 20;
+        if revs == 0 {
+            return Err(CalibrationError::NoMotion);
+        }
+        let max_polls = timeout_ms.saturating_mul(1000 / POLL_US);
+
+        let mut prev = index();
+        let mut start: Option<i32> = None;
+        let mut seen = 0;
+
+        self.forward();
+        for _ in 0..max_polls {
+            delay.delay_us(POLL_US);
+            let level = index();
+            if level && !prev {
+                let pos = self.position_ticks();
+                match start {
+                    None => start = Some(pos),
+                    Some(s) => {
+                        seen += 1;
+                        if seen >= revs {
+                            self.brake();
+                            let ticks = pos.wrapping_sub(s).abs();
+                            if ticks == 0 {
+                                return Err(CalibrationError::NoMotion);
+                            }
+                            let cpr = ticks as u32 / revs;
+                            self.counts_per_rev = cpr;
+                            return Ok(cpr);
+                        }
+                    }
+                }
+            }
+            prev = level;
+        }
+
+        self.brake();
+        Err(CalibrationError::Timeout)
+    }
+
+    /// Apply PID output. Positive drives forward, subject to the inversion set by
+    /// [`set_inverted`](Self::set_inverted); magnitudes below the output range's `min` coast.
     pub fn apply_pid_output(&mut self, u: f32) {
+        let u = if u.abs() < self.output_range.min {
+            0.0
+        } else {
+            u
+        };
         if u > 0.0 {
             self.forward();
         } else if u < 0.0 {
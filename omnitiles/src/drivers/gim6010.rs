@@ -3,15 +3,21 @@
 
 //! Motor control over CAN for SteadyWin GIM6010-48 with a built-in GDZ468 driver.
 //!
-//! This module wraps the driver's custom CAN protocol for a single motor.
+//! This module wraps the driver's custom CAN protocol for a single motor. It talks to the bus
+//! through [`CanInterface`], so the motor can sit on any CAN controller.
 
-use crate::hw::CanBus;
+use crate::hw::{CanError, CanFrame, CanId, CanInterface};
+use crate::units::Rad;
 
-use bxcan::{Frame, Id, OverrunError, StandardId};
 use core::convert::TryInto;
 use core::f32::consts::PI;
+use cortex_m::peripheral::DWT;
 use micromath::F32Ext;
 
+/// How long to wait for a response. The motor answers within a millisecond; without a bound an
+/// unpowered or unplugged motor would hang the caller until the watchdog fires.
+pub const REPLY_TIMEOUT_MS: u32 = 20;
+
 /// Error type for `CanMotor` operations.
 #[derive(Debug)]
 pub enum Error {
@@ -20,19 +26,39 @@ pub enum Error {
     /// TX mailbox or transmit failure.
     TxMailbox,
     /// Receive-side CAN error.
-    Rx(OverrunError),
+    Rx(CanError),
     /// Response frame contained no data bytes.
     NoData,
     /// Response had a different command code than expected.
     UnexpectedCommand(u8),
+    /// [`Gim6010::read_raw`] was given a command outside the read range.
+    NotARead(u8),
+    /// No matching response within [`REPLY_TIMEOUT_MS`].
+    Timeout,
+    /// Position target outside the encoder range the motor is in. A position command only
+    /// reaches raw values in that range.
+    OutOfRange,
 }
 
-impl From<OverrunError> for Error {
-    fn from(e: OverrunError) -> Self {
+impl From<CanError> for Error {
+    fn from(e: CanError) -> Self {
         Error::Rx(e)
     }
 }
 
+/// A reply to one of the read commands, see [`Gim6010::decode_reply`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Reply {
+    /// Phase current in amps (0xA1).
+    Current(f32),
+    /// Shaft speed in rpm (0xA2).
+    Speed(f32),
+    /// Raw single-turn encoder position [0..65535] (0xA3).
+    Position(u16),
+    /// Any other reply, by command code.
+    Other(u8),
+}
+
 /// High-level CAN motor for a single driver instance, parameterized by logical device address.
 ///
 /// `DEV_ADDR` is the protocol device address (`Dev_addr`), in the range 1 to 254 inclusive. The
@@ -41,27 +67,33 @@ impl From<OverrunError> for Error {
 /// The driver will:
 ///   - transmit commands with `StdID = 0x100 | DEV_ADDR`
 ///   - expect responses from `StdID = DEV_ADDR`
-pub struct Gim6010<const DEV_ADDR: u16>;
+pub struct Gim6010<const DEV_ADDR: u16> {
+    /// [`REPLY_TIMEOUT_MS`] in DWT cycles.
+    reply_timeout_cycles: u32,
+}
 
 impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
-    /// Create a new handle for this motor address.
+    /// Create a new handle for this motor address. `sysclk_hz` is the core clock, which the DWT
+    /// cycle counter timing the replies runs at.
     ///
-    /// This is a zero-sized type; all state lives on the driver itself.
+    /// All motor state lives on the driver itself.
     #[inline]
-    pub fn new() -> Self {
-        Self
+    pub fn new(sysclk_hz: u32) -> Self {
+        Self {
+            reply_timeout_cycles: sysclk_hz / 1000 * REPLY_TIMEOUT_MS,
+        }
     }
 
     /// Host -> motor StdID (11-bit) used for commands.
     #[inline]
-    fn host_id() -> StandardId {
-        StandardId::new((0x100 | (DEV_ADDR & 0x7FF)) as u16).unwrap()
+    fn host_id() -> u16 {
+        0x100 | (DEV_ADDR & 0x7FF)
     }
 
     /// Motor -> host StdID (11-bit) used for responses.
     #[inline]
-    fn dev_id() -> StandardId {
-        StandardId::new(DEV_ADDR & 0x7FF).unwrap()
+    fn dev_id() -> u16 {
+        DEV_ADDR & 0x7FF
     }
 
     /// Send a command an optionally wait for its response.
@@ -69,21 +101,19 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
     /// - `cmd` is the command code (e.g., 0xA2 for read speed).
     /// - `payload` is any extra bytes following the command code.
     /// - If `wait_reply` is true, this will block until a matching response frame (same device ID
-    ///   and command code) is received.
+    ///   and command code) is received, or fail with [`Error::Timeout`] after
+    ///   [`REPLY_TIMEOUT_MS`].
     ///
     /// Returns:
     ///   - `Ok(Some(data))` when `wait_reply` is true and a response was received.
     ///   - `Ok(None)` when `wait_reply` is false.
-    fn request_response<I>(
+    fn request_response<B: CanInterface>(
         &mut self,
-        bus: &mut CanBus<I>,
+        bus: &mut B,
         cmd: u8,
         payload: &[u8],
         wait_reply: bool,
-    ) -> Result<Option<[u8; 8]>, Error>
-    where
-        stm32f7xx_hal::can::Can<I>: bxcan::Instance,
-    {
+    ) -> Result<Option<[u8; 8]>, Error> {
         // Total payload including command must be <= 8 bytes
         if payload.len() > 7 {
             return Err(Error::PayloadTooLong);
@@ -95,40 +125,36 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
         let dlc = 1 + payload.len();
         buf[1..dlc].copy_from_slice(payload);
 
-        // Transmit
-        let tx_id = Self::host_id();
-        let tx_result = bus
-            .transmit_data(tx_id, &buf[..dlc])
-            .ok_or(Error::PayloadTooLong)?;
-
-        match tx_result {
-            Ok(_status) => {}
-            Err(_) => return Err(Error::TxMailbox),
-        }
+        // Transmit. A motor that is unplugged never acknowledges, so its frames stay in the
+        // mailboxes; fail rather than wait for one to free up.
+        let frame =
+            CanFrame::standard(Self::host_id(), &buf[..dlc]).ok_or(Error::PayloadTooLong)?;
+        bus.try_send(&frame).map_err(|_| Error::TxMailbox)?;
 
         if !wait_reply {
             return Ok(None);
         }
 
         // Wait for matching response
+        let start = DWT::cycle_count();
         loop {
-            let frame: Frame = bus.receive()?;
-
-            // Standard frame only
-            let id = match frame.id() {
-                Id::Standard(id) => id,
-                Id::Extended(_) => continue,
+            if DWT::cycle_count().wrapping_sub(start) > self.reply_timeout_cycles {
+                return Err(Error::Timeout);
+            }
+            let Some(result) = bus.try_recv() else {
+                continue;
             };
+            let frame = result?;
 
-            // Only accept DEV_ADDR responses
-            if id != Self::dev_id() {
+            // Only accept standard-ID responses from DEV_ADDR
+            if frame.id() != CanId::Standard(Self::dev_id()) {
                 continue;
             }
 
-            let data = match frame.data() {
-                Some(d) if d.len() > 0 => d,
-                _ => return Err(Error::NoData),
-            };
+            let data = frame.data();
+            if data.is_empty() {
+                return Err(Error::NoData);
+            }
 
             let resp_cmd = data[0];
             if resp_cmd != cmd {
@@ -143,20 +169,14 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
     }
 
     /// Clear any latched faults.
-    pub fn clear_faults<I>(&mut self, bus: &mut CanBus<I>) -> Result<(), Error>
-    where
-        stm32f7xx_hal::can::Can<I>: bxcan::Instance,
-    {
+    pub fn clear_faults<B: CanInterface>(&mut self, bus: &mut B) -> Result<(), Error> {
         let _ = self.request_response(bus, 0xAF, &[], true)?;
         Ok(())
     }
 
     /// Turn off motor output. The motor enters a free state and becomes uncontrollable (this is the
     /// state the motor enters after power-on).
-    pub fn disable_output<I>(&mut self, bus: &mut CanBus<I>) -> Result<(), Error>
-    where
-        stm32f7xx_hal::can::Can<I>: bxcan::Instance,
-    {
+    pub fn disable_output<B: CanInterface>(&mut self, bus: &mut B) -> Result<(), Error> {
         let _ = self.request_response(bus, 0xCF, &[], false)?;
         Ok(())
     }
@@ -165,10 +185,7 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
     ///
     /// - `rpm` is signed (negative values indicate reverse direction).
     /// - Resolution is 0.01 rpm
-    pub fn set_speed_rpm<I>(&mut self, bus: &mut CanBus<I>, rpm: f32) -> Result<(), Error>
-    where
-        stm32f7xx_hal::can::Can<I>: bxcan::Instance,
-    {
+    pub fn set_speed_rpm<B: CanInterface>(&mut self, bus: &mut B, rpm: f32) -> Result<(), Error> {
         // Convert to protocol units: little-endian signed 32-bit int, 0.01 rpm
         let scaled: i32 = (rpm * 100.0).round() as i32;
         let bytes = scaled.to_le_bytes();
@@ -178,10 +195,7 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
     }
 
     /// Read back the real-time motor speed in rpm.
-    pub fn read_speed_rpm<I>(&mut self, bus: &mut CanBus<I>) -> Result<f32, Error>
-    where
-        stm32f7xx_hal::can::Can<I>: bxcan::Instance,
-    {
+    pub fn read_speed_rpm<B: CanInterface>(&mut self, bus: &mut B) -> Result<f32, Error> {
         let resp = self
             .request_response(bus, 0xA2, &[], true)?
             .ok_or(Error::NoData)?;
@@ -196,11 +210,69 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
         Ok(rpm)
     }
 
+    /// Command the motor in current (torque) control mode with a setpoint in amps.
+    ///
+    /// - `amps` is signed (negative values indicate reverse torque).
+    /// - Resolution is 0.001 A (command 0xC0).
+    pub fn set_current_a<B: CanInterface>(&mut self, bus: &mut B, amps: f32) -> Result<(), Error> {
+        let scaled: i32 = (amps * 1000.0).round() as i32;
+        let _ = self.request_response(bus, 0xC0, &scaled.to_le_bytes(), false)?;
+        Ok(())
+    }
+
+    /// Read back the real-time phase current in amps (command 0xA1).
+    pub fn read_current_a<B: CanInterface>(&mut self, bus: &mut B) -> Result<f32, Error> {
+        let resp = self
+            .request_response(bus, 0xA1, &[], true)?
+            .ok_or(Error::NoData)?;
+
+        if resp[0] != 0xA1 {
+            return Err(Error::UnexpectedCommand(resp[0]));
+        }
+
+        let raw = i32::from_le_bytes(resp[1..5].try_into().expect("slice with exact length"));
+        Ok(raw as f32 / 1000.0)
+    }
+
+    /// Command an absolute position move to a raw encoder value [0..65535] (command 0xC2).
+    ///
+    /// The driver acknowledges the command but never reports when the move has finished; use a
+    /// [`MoveMonitor`] to detect completion.
+    pub fn set_position_raw<B: CanInterface>(
+        &mut self,
+        bus: &mut B,
+        raw: u16,
+    ) -> Result<(), Error> {
+        let _ = self.request_response(bus, 0xC2, &raw.to_le_bytes(), false)?;
+        Ok(())
+    }
+
+    /// Read back the raw single-turn encoder position [0..65535] (command 0xA3).
+    pub fn read_position_raw<B: CanInterface>(&mut self, bus: &mut B) -> Result<u16, Error> {
+        let resp = self
+            .request_response(bus, 0xA3, &[], true)?
+            .ok_or(Error::NoData)?;
+
+        if resp[0] != 0xA3 {
+            return Err(Error::UnexpectedCommand(resp[0]));
+        }
+
+        Ok(u16::from_le_bytes([resp[1], resp[2]]))
+    }
+
+    /// Read the encoder position and extend it across range wraps with `tracker`, returning the
+    /// unwrapped shaft angle in radians.
+    pub fn read_position_unwrapped<B: CanInterface>(
+        &mut self,
+        bus: &mut B,
+        tracker: &mut MultiTurn,
+    ) -> Result<f32, Error> {
+        let raw = self.read_position_raw(bus)?;
+        Ok(Self::counts_to_rad(tracker.update(raw)))
+    }
+
     // Read the raw status frame.
-    pub fn read_status_frame<I>(&mut self, bus: &mut CanBus<I>) -> Result<[u8; 8], Error>
-    where
-        stm32f7xx_hal::can::Can<I>: bxcan::Instance,
-    {
+    pub fn read_status_frame<B: CanInterface>(&mut self, bus: &mut B) -> Result<[u8; 8], Error> {
         let resp = self
             .request_response(bus, 0xAE, &[], true)?
             .ok_or(Error::NoData)?;
@@ -211,6 +283,42 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
 
         Ok(resp)
     }
+
+    /// Send read command `cmd` (0xA0–0xAE) with no payload and return the raw reply frame, for
+    /// diagnostics. Other codes drive the motor or change its state and are refused.
+    pub fn read_raw<B: CanInterface>(&mut self, bus: &mut B, cmd: u8) -> Result<[u8; 8], Error> {
+        if !(0xA0..=0xAE).contains(&cmd) {
+            return Err(Error::NotARead(cmd));
+        }
+        self.request_response(bus, cmd, &[], true)?
+            .ok_or(Error::NoData)
+    }
+
+    /// Send read command `cmd` (0xA0–0xAE) without waiting for the reply. Take the reply off the
+    /// bus later with [`decode_reply`](Self::decode_reply), so a caller in the main loop never
+    /// waits on the motor.
+    pub fn request_read<B: CanInterface>(&mut self, bus: &mut B, cmd: u8) -> Result<(), Error> {
+        if !(0xA0..=0xAE).contains(&cmd) {
+            return Err(Error::NotARead(cmd));
+        }
+        let _ = self.request_response(bus, cmd, &[], false)?;
+        Ok(())
+    }
+
+    /// Decode `frame` if it is a reply from this motor.
+    pub fn decode_reply(frame: &CanFrame) -> Option<Reply> {
+        if frame.id() != CanId::Standard(Self::dev_id()) {
+            return None;
+        }
+        let (&cmd, rest) = frame.data().split_first()?;
+        let word = || Some(i32::from_le_bytes(rest.get(..4)?.try_into().ok()?));
+        Some(match cmd {
+            0xA1 => Reply::Current(word()? as f32 / 1000.0),
+            0xA2 => Reply::Speed(word()? as f32 / 100.0),
+            0xA3 => Reply::Position(u16::from_le_bytes(rest.get(..2)?.try_into().ok()?)),
+            _ => Reply::Other(cmd),
+        })
+    }
 }
 
 impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
@@ -239,6 +347,24 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
         norm * max
     }
 
+    /// Convert an unwrapped count from [`MultiTurn`] to a motor shaft angle in radians.
+    ///
+    /// Counts in [0..65535] map exactly like [`raw_angle_to_rad`](Self::raw_angle_to_rad); every
+    /// further 65536 counts add one full encoder range (2 × Pos_Max).
+    #[inline]
+    pub fn counts_to_rad(counts: i64) -> f32 {
+        let max = Self::shaft_pos_max_rad();
+        let norm = (counts as f32) / 65535.0 * 2.0 - 1.0;
+        norm * max
+    }
+
+    /// Inverse of [`counts_to_rad`](Self::counts_to_rad), without clamping.
+    #[inline]
+    pub fn rad_to_counts(angle_rad: f32) -> i64 {
+        let max = Self::shaft_pos_max_rad();
+        ((angle_rad / max + 1.0) * 0.5 * 65535.0).round() as i64
+    }
+
     /// Convert a raw encoder value [0..65535] to a motor shaft angle in degrees.
     #[inline]
     pub fn raw_angle_to_deg(raw: u16) -> f32 {
@@ -272,6 +398,21 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
         raw_f.round() as u16
     }
 
+    /// Convert a typed shaft angle ([`Deg`](crate::units::Deg) or [`Rad`]) to a raw encoder value
+    /// [0..65535].
+    ///
+    /// Prefer this over the `_rad`/`_deg` variants so the unit is checked by the compiler.
+    #[inline]
+    pub fn angle_to_raw(angle: impl Into<Rad>) -> u16 {
+        Self::angle_rad_to_raw(angle.into().get())
+    }
+
+    /// Convert a raw encoder value [0..65535] to a typed shaft angle.
+    #[inline]
+    pub fn raw_to_angle(raw: u16) -> Rad {
+        Rad(Self::raw_angle_to_rad(raw))
+    }
+
     /// Convert a desired motor shaft angle in degrees to a raw encoder value [0..65535].
     #[inline]
     pub fn angle_deg_to_raw(angle_deg: f32) -> u16 {
@@ -279,3 +420,133 @@ impl<const DEV_ADDR: u16> Gim6010<DEV_ADDR> {
         Self::angle_rad_to_raw(rad)
     }
 }
+
+/// Detects completion of a commanded position move.
+///
+/// The GIM6010 protocol has no "move done" notification, so completion is inferred by polling:
+/// a move is complete once the position has been within `pos_tolerance_raw` of the target and the
+/// speed below `speed_tolerance_rpm` for `settle_samples` consecutive samples.
+#[derive(Copy, Clone, Debug)]
+pub struct MoveMonitor {
+    pub pos_tolerance_raw: u16,
+    pub speed_tolerance_rpm: f32,
+    pub settle_samples: u8,
+    target_raw: u16,
+    settled: u8,
+    active: bool,
+}
+
+impl MoveMonitor {
+    pub const fn new(pos_tolerance_raw: u16, speed_tolerance_rpm: f32, settle_samples: u8) -> Self {
+        Self {
+            pos_tolerance_raw,
+            speed_tolerance_rpm,
+            settle_samples,
+            target_raw: 0,
+            settled: 0,
+            active: false,
+        }
+    }
+
+    /// Begin monitoring a move toward `target_raw`.
+    pub fn start(&mut self, target_raw: u16) {
+        self.target_raw = target_raw;
+        self.settled = 0;
+        self.active = true;
+    }
+
+    /// Stop monitoring without declaring the move complete.
+    pub fn cancel(&mut self) {
+        self.active = false;
+        self.settled = 0;
+    }
+
+    /// True while a move is being monitored and has not completed yet.
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// True once the monitored move has settled.
+    #[inline]
+    pub fn is_done(&self) -> bool {
+        !self.active && self.settled >= self.settle_samples
+    }
+
+    /// Feed one position/speed sample. Returns `true` on the sample where the move completes.
+    pub fn update(&mut self, position_raw: u16, speed_rpm: f32) -> bool {
+        if !self.active {
+            return false;
+        }
+        // Compare modulo the encoder range so a target next to the wrap point still settles.
+        let error = position_raw.wrapping_sub(self.target_raw) as i16;
+        let in_window = error.unsigned_abs() <= self.pos_tolerance_raw
+            && speed_rpm.abs() <= self.speed_tolerance_rpm;
+        if in_window {
+            self.settled = self.settled.saturating_add(1);
+        } else {
+            self.settled = 0;
+        }
+        if self.settled >= self.settle_samples {
+            self.active = false;
+            return true;
+        }
+        false
+    }
+}
+
+/// Software multi-turn tracking for the raw 16-bit encoder position.
+///
+/// The raw position wraps from 65535 back to 0 (and vice versa) when the shaft crosses the ±Pos_Max
+/// boundary. Successive readings are unwrapped by assuming the shaft moved less than half the range
+/// between samples, which holds as long as the position is polled often enough.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct MultiTurn {
+    last_raw: u16,
+    wraps: i32,
+    initialized: bool,
+}
+
+impl MultiTurn {
+    pub const fn new() -> Self {
+        Self {
+            last_raw: 0,
+            wraps: 0,
+            initialized: false,
+        }
+    }
+
+    /// Forget the history; the next reading starts a new count at wrap 0.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Feed a raw reading and return the unwrapped count.
+    pub fn update(&mut self, raw: u16) -> i64 {
+        if self.initialized {
+            let delta = raw.wrapping_sub(self.last_raw) as i16 as i32;
+            let prev = self.last_raw as i32;
+            let next = prev + delta;
+            if next > u16::MAX as i32 {
+                self.wraps += 1;
+            } else if next < 0 {
+                self.wraps -= 1;
+            }
+        }
+        self.last_raw = raw;
+        self.initialized = true;
+        self.counts()
+    }
+
+    /// Unwrapped count: `wraps * 65536 + last_raw`.
+    #[inline]
+    pub fn counts(&self) -> i64 {
+        self.wraps as i64 * 65536 + self.last_raw as i64
+    }
+
+    /// Number of times the raw value has wrapped (positive = upward).
+    #[inline]
+    pub fn wraps(&self) -> i32 {
+        self.wraps
+    }
+}
@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Device address and CAN bitrate provisioning for GIM6010 motors.
+//!
+//! Replacement motors ship at address 1 and 1 Mbit/s. [`provision`] moves one to the address and
+//! bitrate the tile expects using the GDZ468 setting commands, then checks that it answers a
+//! status read on the new settings before returning. If it doesn't, the old settings are written
+//! back and checked the same way, so a failed attempt leaves the motor where it was found rather
+//! than somewhere unknown on the bus.
+//!
+//! The address is changed first, then the bitrate, each verified on its own. Only the motor being
+//! provisioned should be powered: every other node on the bus has to follow the bitrate change.
+//!
+//! The flow is blocking and [`Gim6010`](super::Gim6010) handles can't be used with it, since their
+//! address is a type parameter. Only run it while the motor is otherwise idle. It needs a
+//! [`CanBus`] rather than any [`CanInterface`] because it changes the bit timing. The firmware
+//! runs it for the `PROVISION_TILT_MOTOR` command, which moves a motor to the tilt axis settings.
+
+use cortex_m::delay::Delay;
+use stm32f7xx_hal::can as hal_can;
+
+use crate::hw::{iwdg, CanBus, CanFrame, CanId, CanInterface};
+use crate::net::slcan::btr_for_bitrate;
+
+/// Set the device address. Payload: `[addr]`.
+const CMD_SET_ADDR: u8 = 0xB1;
+/// Set the CAN bitrate. Payload: `[code]`, see [`Bitrate::code`].
+const CMD_SET_BITRATE: u8 = 0xB2;
+/// Read the status frame, used as a ping.
+const CMD_READ_STATUS: u8 = 0xAE;
+
+/// Time for the motor to store a setting and switch over.
+const APPLY_MS: u32 = 100;
+/// How long to wait for each status reply.
+const PROBE_TIMEOUT_MS: u32 = 20;
+/// Status reads tried before a motor counts as silent.
+const PROBE_ATTEMPTS: u32 = 3;
+/// Receive polling period while waiting for a reply.
+const POLL_US: u32 = 100;
+
+/// Bitrates the GDZ468 supports.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Bitrate {
+    K125,
+    K250,
+    K500,
+    M1,
+}
+
+impl Bitrate {
+    /// Bits per second.
+    pub fn bps(self) -> u32 {
+        match self {
+            Bitrate::K125 => 125_000,
+            Bitrate::K250 => 250_000,
+            Bitrate::K500 => 500_000,
+            Bitrate::M1 => 1_000_000,
+        }
+    }
+
+    /// Bitrate for a set-bitrate code, see [`code`](Self::code).
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Bitrate::M1),
+            1 => Some(Bitrate::K500),
+            2 => Some(Bitrate::K250),
+            3 => Some(Bitrate::K125),
+            _ => None,
+        }
+    }
+
+    /// Code sent with the set-bitrate command.
+    pub fn code(self) -> u8 {
+        match self {
+            Bitrate::M1 => 0,
+            Bitrate::K500 => 1,
+            Bitrate::K250 => 2,
+            Bitrate::K125 => 3,
+        }
+    }
+}
+
+/// Address and bitrate of one motor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// Device address, 1 to 254.
+    pub addr: u8,
+    pub bitrate: Bitrate,
+}
+
+impl Settings {
+    /// Factory settings of a new motor.
+    pub const FACTORY: Self = Self {
+        addr: 1,
+        bitrate: Bitrate::M1,
+    };
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Address outside 1 to 254.
+    InvalidAddress(u8),
+    /// No CAN bit timing for this bitrate at the bus clock.
+    Timing(Bitrate),
+    /// A setting command could not be queued.
+    Tx,
+    /// The motor didn't answer on its current settings.
+    NotFound,
+    /// Another node already answers on the target address.
+    AddressInUse(u8),
+    /// The motor didn't answer on the new settings and was restored to the old ones.
+    RolledBack,
+    /// The motor answers on neither the old nor the new settings. Find it with the slcan bridge.
+    Lost,
+}
+
+/// Move the motor at `from` to `to` and verify it. `bus` must be running at `from.bitrate`; on
+/// success it is left at `to.bitrate`, otherwise at `from.bitrate`.
+pub fn provision<I>(
+    bus: &mut CanBus<I>,
+    pclk1_hz: u32,
+    from: Settings,
+    to: Settings,
+    delay: &mut Delay,
+) -> Result<(), Error>
+where
+    hal_can::Can<I>: bxcan::Instance,
+{
+    for addr in [from.addr, to.addr] {
+        if !(1..=254).contains(&addr) {
+            return Err(Error::InvalidAddress(addr));
+        }
+    }
+    let btr =
+        |bitrate: Bitrate| btr_for_bitrate(pclk1_hz, bitrate.bps()).ok_or(Error::Timing(bitrate));
+    let from_btr = btr(from.bitrate)?;
+    let to_btr = btr(to.bitrate)?;
+
+    if !probe(bus, from.addr, delay) {
+        return Err(Error::NotFound);
+    }
+
+    if to.addr != from.addr {
+        if probe(bus, to.addr, delay) {
+            return Err(Error::AddressInUse(to.addr));
+        }
+        send(bus, from.addr, CMD_SET_ADDR, &[to.addr])?;
+        delay.delay_ms(APPLY_MS);
+        if !probe(bus, to.addr, delay) {
+            // Either the change never took or the motor went quiet; put it back either way.
+            send(bus, to.addr, CMD_SET_ADDR, &[from.addr])?;
+            delay.delay_ms(APPLY_MS);
+            return Err(restored(probe(bus, from.addr, delay)));
+        }
+    }
+
+    if to.bitrate != from.bitrate {
+        send(bus, to.addr, CMD_SET_BITRATE, &[to.bitrate.code()])?;
+        delay.delay_ms(APPLY_MS);
+        bus.set_bit_timing(to_btr);
+        if !probe(bus, to.addr, delay) {
+            // Usually the change never took and the motor is still on the old bitrate. If it
+            // isn't there either, it may have switched and just not answered, so send the
+            // rollback on the new bitrate too.
+            bus.set_bit_timing(from_btr);
+            if !probe(bus, to.addr, delay) {
+                bus.set_bit_timing(to_btr);
+                let sent = send(bus, to.addr, CMD_SET_BITRATE, &[from.bitrate.code()]);
+                delay.delay_ms(APPLY_MS);
+                bus.set_bit_timing(from_btr);
+                sent?;
+                if !probe(bus, to.addr, delay) {
+                    return Err(Error::Lost);
+                }
+            }
+            if to.addr != from.addr {
+                send(bus, to.addr, CMD_SET_ADDR, &[from.addr])?;
+                delay.delay_ms(APPLY_MS);
+            }
+            return Err(restored(probe(bus, from.addr, delay)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Outcome of a rollback, given whether the motor answers on its old settings.
+fn restored(found: bool) -> Error {
+    if found {
+        Error::RolledBack
+    } else {
+        Error::Lost
+    }
+}
+
+/// Send `cmd` to the motor at `addr` without waiting for a reply.
+fn send<B: CanInterface>(bus: &mut B, addr: u8, cmd: u8, payload: &[u8]) -> Result<(), Error> {
+    let mut buf = [0u8; 8];
+    buf[0] = cmd;
+    buf[1..=payload.len()].copy_from_slice(payload);
+    let frame = CanFrame::standard(0x100 | addr as u16, &buf[..=payload.len()]).ok_or(Error::Tx)?;
+    bus.send(&frame).map_err(|_| Error::Tx)
+}
+
+/// True if the motor at `addr` answers a status read.
+fn probe<B: CanInterface>(bus: &mut B, addr: u8, delay: &mut Delay) -> bool {
+    let dev_id = CanId::Standard(addr as u16);
+    for _ in 0..PROBE_ATTEMPTS {
+        iwdg::feed();
+        // Drop stale frames so an earlier reply isn't taken for this one.
+        while bus.try_recv().is_some() {}
+        if send(bus, addr, CMD_READ_STATUS, &[]).is_err() {
+            continue;
+        }
+        for _ in 0..PROBE_TIMEOUT_MS * 1000 / POLL_US {
+            match bus.try_recv() {
+                Some(Ok(frame)) if frame.id() == dev_id => {
+                    if frame.data().first() == Some(&CMD_READ_STATUS) {
+                        return true;
+                    }
+                }
+                Some(_) => {}
+                None => delay.delay_us(POLL_US),
+            }
+        }
+    }
+    false
+}
@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! 16-bit I2C GPIO expander (Microchip MCP23017 or NXP PCA9535).
+//!
+//! The production tile board runs out of MCU GPIOs for limit switches and status LEDs, so those
+//! move to an expander. Both parts have two 8-bit ports with the same direction convention
+//! (1 = input) and auto-incrementing register pairs, so one driver covers both.
+//!
+//! Pins are handed out as [`ExpanderLed`] and [`ExpanderInput`] handles with the methods of
+//! [`Led`](crate::hw::Led) and HAL input pins, except that they return I2C errors. Like
+//! [`Adc::make_reader`](crate::hw::Adc), handles borrow the expander through a `RefCell` so several
+//! can share one device, and the expander itself borrows the [`I2cBus`] through a `RefCell` so the
+//! bus stays free for other devices. Outputs are written through immediately and inputs are read
+//! live. Failed transfers are also counted in [`GpioExpander::errors`] for diagnostics.
+
+use crate::hw::{led::ActiveLevel, I2cBus};
+use core::cell::RefCell;
+use stm32f7xx_hal::i2c::{self, PinScl, PinSda};
+use stm32f7xx_hal::pac::I2C1;
+
+/// Expander error.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The I2C transfer failed.
+    I2c(i2c::Error),
+    /// The pin number is not below [`PIN_COUNT`].
+    InvalidPin(u8),
+}
+
+impl From<i2c::Error> for Error {
+    fn from(e: i2c::Error) -> Self {
+        Error::I2c(e)
+    }
+}
+
+/// Expander part number. Selects the register map.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Chip {
+    Mcp23017,
+    Pca9535,
+}
+
+impl Chip {
+    /// Direction register for port 0 (1 = input).
+    fn dir_reg(self) -> u8 {
+        match self {
+            Chip::Mcp23017 => 0x00, // IODIRA
+            Chip::Pca9535 => 0x06,  // Configuration port 0
+        }
+    }
+
+    /// Input register for port 0.
+    fn input_reg(self) -> u8 {
+        match self {
+            Chip::Mcp23017 => 0x12, // GPIOA
+            Chip::Pca9535 => 0x00,  // Input port 0
+        }
+    }
+
+    /// Output latch register for port 0.
+    fn output_reg(self) -> u8 {
+        match self {
+            Chip::Mcp23017 => 0x14, // OLATA
+            Chip::Pca9535 => 0x02,  // Output port 0
+        }
+    }
+}
+
+/// 7-bit address with A2..A0 tied low (both parts decode 0x20..0x27).
+pub const BASE_ADDR: u8 = 0x20;
+
+/// Number of pins (port 0 = bits 0..7, port 1 = bits 8..15).
+pub const PIN_COUNT: u8 = 16;
+
+/// Bit mask of `pin`, or `InvalidPin` if the expander has no such pin.
+fn pin_mask(pin: u8) -> Result<u16, Error> {
+    if pin < PIN_COUNT {
+        Ok(1 << pin)
+    } else {
+        Err(Error::InvalidPin(pin))
+    }
+}
+
+pub struct GpioExpander<'a, SCL, SDA> {
+    bus: &'a RefCell<I2cBus<SCL, SDA>>,
+    chip: Chip,
+    addr: u8,
+    /// Output latch shadow, so single-pin writes don't need a read-modify-write over I2C.
+    output: u16,
+    errors: u32,
+}
+
+impl<'a, SCL, SDA> GpioExpander<'a, SCL, SDA>
+where
+    SCL: PinScl<I2C1>,
+    SDA: PinSda<I2C1>,
+{
+    /// Configure the expander. Pins set in `outputs` become outputs driven low; the rest are
+    /// inputs. The latch is written before the direction so outputs never glitch high.
+    pub fn new(
+        bus: &'a RefCell<I2cBus<SCL, SDA>>,
+        chip: Chip,
+        addr: u8,
+        outputs: u16,
+    ) -> Result<Self, Error> {
+        let mut dev = Self {
+            bus,
+            chip,
+            addr,
+            output: 0,
+            errors: 0,
+        };
+        dev.write_pair(chip.output_reg(), 0)?;
+        dev.write_pair(chip.dir_reg(), !outputs)?;
+        Ok(dev)
+    }
+
+    /// I2C transfers that failed since construction.
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    /// Drive an output pin high or low.
+    pub fn set_pin(&mut self, pin: u8, high: bool) -> Result<(), Error> {
+        self.set_mask(pin_mask(pin)?, high)
+    }
+
+    fn set_mask(&mut self, mask: u16, high: bool) -> Result<(), Error> {
+        let next = if high {
+            self.output | mask
+        } else {
+            self.output & !mask
+        };
+        if next != self.output {
+            self.write_pair(self.chip.output_reg(), next)?;
+            self.output = next;
+        }
+        Ok(())
+    }
+
+    /// Last level written to an output pin.
+    pub fn is_set_high(&self, pin: u8) -> Result<bool, Error> {
+        Ok(self.output & pin_mask(pin)? != 0)
+    }
+
+    /// Read both input ports. Bit n is pin n.
+    pub fn read_inputs(&mut self) -> Result<u16, Error> {
+        let mut buf = [0u8; 2];
+        let res = self
+            .bus
+            .borrow_mut()
+            .write_read(self.addr, &[self.chip.input_reg()], &mut buf);
+        if res.is_err() {
+            self.errors = self.errors.wrapping_add(1);
+        }
+        res?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    /// Write a register pair (port 0 then port 1).
+    fn write_pair(&mut self, reg: u8, value: u16) -> Result<(), Error> {
+        let [lo, hi] = value.to_le_bytes();
+        let res = self.bus.borrow_mut().write(self.addr, &[reg, lo, hi]);
+        if res.is_err() {
+            self.errors = self.errors.wrapping_add(1);
+        }
+        Ok(res?)
+    }
+
+    /// Hand out an LED on an output pin, initialized to OFF.
+    pub fn led(
+        dev: &RefCell<Self>,
+        pin: u8,
+        active: ActiveLevel,
+    ) -> Result<ExpanderLed<'_, 'a, SCL, SDA>, Error> {
+        let mut led = ExpanderLed {
+            dev,
+            mask: pin_mask(pin)?,
+            active,
+        };
+        led.off()?;
+        Ok(led)
+    }
+
+    /// Hand out an input pin.
+    pub fn input(dev: &RefCell<Self>, pin: u8) -> Result<ExpanderInput<'_, 'a, SCL, SDA>, Error> {
+        Ok(ExpanderInput {
+            dev,
+            mask: pin_mask(pin)?,
+        })
+    }
+}
+
+/// LED on an expander output. Same methods as [`Led`](crate::hw::Led), returning I2C errors.
+pub struct ExpanderLed<'d, 'a, SCL, SDA> {
+    dev: &'d RefCell<GpioExpander<'a, SCL, SDA>>,
+    mask: u16,
+    active: ActiveLevel,
+}
+
+impl<SCL, SDA> ExpanderLed<'_, '_, SCL, SDA>
+where
+    SCL: PinScl<I2C1>,
+    SDA: PinSda<I2C1>,
+{
+    /// Drive the LED logically ON (true) or OFF (false).
+    pub fn set(&mut self, on: bool) -> Result<(), Error> {
+        let high = on == (self.active == ActiveLevel::High);
+        self.dev.borrow_mut().set_mask(self.mask, high)
+    }
+
+    #[inline]
+    pub fn on(&mut self) -> Result<(), Error> {
+        self.set(true)
+    }
+
+    #[inline]
+    pub fn off(&mut self) -> Result<(), Error> {
+        self.set(false)
+    }
+
+    /// Last state written. Doesn't touch the bus.
+    #[inline]
+    pub fn is_on(&self) -> bool {
+        let high = self.dev.borrow().output & self.mask != 0;
+        high == (self.active == ActiveLevel::High)
+    }
+
+    #[inline]
+    pub fn toggle(&mut self) -> Result<(), Error> {
+        let on = self.is_on();
+        self.set(!on)
+    }
+}
+
+/// Input on an expander pin (limit switch, button). Reads go over I2C each call.
+pub struct ExpanderInput<'d, 'a, SCL, SDA> {
+    dev: &'d RefCell<GpioExpander<'a, SCL, SDA>>,
+    mask: u16,
+}
+
+impl<SCL, SDA> ExpanderInput<'_, '_, SCL, SDA>
+where
+    SCL: PinScl<I2C1>,
+    SDA: PinSda<I2C1>,
+{
+    /// Read the pin level.
+    pub fn level(&self) -> Result<bool, Error> {
+        let inputs = self.dev.borrow_mut().read_inputs()?;
+        Ok(inputs & self.mask != 0)
+    }
+
+    #[inline]
+    pub fn is_high(&self) -> Result<bool, Error> {
+        self.level()
+    }
+
+    #[inline]
+    pub fn is_low(&self) -> Result<bool, Error> {
+        self.level().map(|high| !high)
+    }
+}
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Avia HX711 24-bit load-cell amplifier, bit-banged on two GPIOs.
+//!
+//! DOUT goes low when a conversion is ready. The host then clocks 24 data bits out on PD_SCK,
+//! MSB first, followed by 1–3 extra pulses that select the channel and gain for the *next*
+//! conversion (see [`Gain`]). Holding PD_SCK high for more than 60 µs powers the chip down, so
+//! the clock train runs with interrupts disabled.
+//!
+//! The driver implements [`LoadSource`], so tare, filtering and calibration to newtons come from
+//! wrapping it in a [`LoadSensor`](crate::sensors::LoadSensor).
+//! [`Gain::nominal_newtons_per_count`] gives a starting scale from the load cell's datasheet.
+
+use crate::sensors::LoadSource;
+use cortex_m::{asm, interrupt};
+use stm32f7xx_hal::gpio::{self, Input, Output, PushPull};
+
+/// Cycles to hold each PD_SCK level. ≥ 0.2 µs is required; this is ~0.5 µs at 216 MHz and
+/// ~6 µs at 16 MHz, well under the 60 µs power-down limit either way.
+const HALF_PERIOD_CYCLES: u32 = 100;
+
+/// Input channel and PGA gain.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Gain {
+    /// Channel A, gain 128 (±20 mV full scale at 5 V).
+    A128,
+    /// Channel B, gain 32 (±80 mV full scale at 5 V).
+    B32,
+    /// Channel A, gain 64 (±40 mV full scale at 5 V).
+    A64,
+}
+
+impl Gain {
+    /// PD_SCK pulses after the 24 data bits that select this setting.
+    fn extra_pulses(self) -> u8 {
+        match self {
+            Gain::A128 => 1,
+            Gain::B32 => 2,
+            Gain::A64 => 3,
+        }
+    }
+
+    fn factor(self) -> f32 {
+        match self {
+            Gain::A128 => 128.0,
+            Gain::B32 => 32.0,
+            Gain::A64 => 64.0,
+        }
+    }
+
+    /// Nominal newtons per count for a load cell rated `full_scale_n` with output
+    /// `mv_per_v` at full scale, excited from the HX711's own supply. The measurement is
+    /// ratiometric, so the supply voltage cancels out. Calibrate against a known load for
+    /// better than the cell's rated accuracy.
+    pub fn nominal_newtons_per_count(self, full_scale_n: f32, mv_per_v: f32) -> f32 {
+        // Full-scale input is ±0.5 AVDD / gain over ±2^23 counts.
+        full_scale_n / (mv_per_v / 1000.0 * self.factor() * (1u32 << 24) as f32)
+    }
+}
+
+/// HX711 on a push-pull PD_SCK output and a DOUT input.
+pub struct Hx711<const SP: char, const SN: u8, const DP: char, const DN: u8, DMODE> {
+    sck: gpio::Pin<SP, SN, Output<PushPull>>,
+    dout: gpio::Pin<DP, DN, Input<DMODE>>,
+    gain: Gain,
+    /// Gain programmed by the last read, i.e. the one the pending conversion uses.
+    active_gain: Option<Gain>,
+}
+
+impl<const SP: char, const SN: u8, const DP: char, const DN: u8, DMODE>
+    Hx711<SP, SN, DP, DN, DMODE>
+{
+    /// Create the driver with PD_SCK low (chip running). The first conversion after power-up
+    /// uses channel A / gain 128; `gain` takes effect from the second.
+    pub fn new(
+        mut sck: gpio::Pin<SP, SN, Output<PushPull>>,
+        dout: gpio::Pin<DP, DN, Input<DMODE>>,
+        gain: Gain,
+    ) -> Self {
+        sck.set_low();
+        Self {
+            sck,
+            dout,
+            gain,
+            active_gain: None,
+        }
+    }
+
+    /// Select the channel and gain. Takes effect after the next read, whose result is
+    /// discarded because it was converted with the old setting.
+    pub fn set_gain(&mut self, gain: Gain) {
+        self.gain = gain;
+    }
+
+    pub fn gain(&self) -> Gain {
+        self.gain
+    }
+
+    /// True when a conversion is ready to be read.
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        self.dout.is_low()
+    }
+
+    /// Read a conversion if one is ready. Non-blocking; at the default 10 Hz output rate most
+    /// calls return `None`. A sample converted with a different gain than the current one is
+    /// read (to program the new gain) and discarded.
+    pub fn read(&mut self) -> Option<i32> {
+        if !self.is_ready() {
+            return None;
+        }
+
+        let gain = self.gain;
+        let raw = interrupt::free(|_| {
+            let mut value: u32 = 0;
+            for _ in 0..24 {
+                self.pulse();
+                value = (value << 1) | self.dout.is_high() as u32;
+            }
+            for _ in 0..gain.extra_pulses() {
+                self.pulse();
+            }
+            value
+        });
+
+        let converted_with = self.active_gain.unwrap_or(Gain::A128);
+        self.active_gain = Some(gain);
+        if converted_with != gain {
+            return None;
+        }
+        // Sign-extend the 24-bit two's complement result.
+        Some(((raw << 8) as i32) >> 8)
+    }
+
+    /// Power the chip down (PD_SCK held high). The next conversion after [`power_up`] uses
+    /// channel A / gain 128.
+    ///
+    /// [`power_up`]: Self::power_up
+    pub fn power_down(&mut self) {
+        self.sck.set_high();
+        asm::delay(HALF_PERIOD_CYCLES * 200);
+        self.active_gain = None;
+    }
+
+    pub fn power_up(&mut self) {
+        self.sck.set_low();
+    }
+
+    #[inline(always)]
+    fn pulse(&mut self) {
+        self.sck.set_high();
+        asm::delay(HALF_PERIOD_CYCLES);
+        self.sck.set_low();
+        asm::delay(HALF_PERIOD_CYCLES);
+    }
+
+    /// Release the pins.
+    pub fn free(
+        self,
+    ) -> (
+        gpio::Pin<SP, SN, Output<PushPull>>,
+        gpio::Pin<DP, DN, Input<DMODE>>,
+    ) {
+        (self.sck, self.dout)
+    }
+}
+
+impl<const SP: char, const SN: u8, const DP: char, const DN: u8, DMODE> LoadSource
+    for Hx711<SP, SN, DP, DN, DMODE>
+{
+    fn read_raw(&mut self) -> Option<i32> {
+        self.read()
+    }
+}
@@ -8,7 +8,7 @@
 //! synchronized accelerometer + gyroscope reading converted to SI units (m/s^2 and
 //! rad/s) so the tilt control loop on the STM32 can consume it directly.
 
-use crate::hw::{spi::CsControl, SpiBus};
+use crate::hw::{spi::CsControl, BusError, SpiBus};
 use core::f32::consts::PI;
 use stm32f7xx_hal::spi;
 
@@ -18,7 +18,7 @@ const WHO_AM_I_VALUE: u8 = 0x70;
 /// Standard gravity (m/s^2) for converting raw accel LSB to SI units.
 const G: f32 = 9.80665;
 
-mod reg {
+pub mod reg {
     pub const WHO_AM_I: u8 = 0x0F;
     pub const CTRL1: u8 = 0x10;
     pub const CTRL2: u8 = 0x11;
@@ -35,6 +35,8 @@ const SPI_READ: u8 = 0x80;
 pub enum Error {
     Spi(spi::Error),
     InvalidDevice,
+    /// A sample read back as all zeros or all ones (sensor unpowered or disconnected).
+    Implausible,
 }
 
 impl From<spi::Error> for Error {
@@ -43,6 +45,15 @@ impl From<spi::Error> for Error {
     }
 }
 
+impl From<BusError> for Error {
+    fn from(e: BusError) -> Self {
+        match e {
+            BusError::Spi(e) => Error::Spi(e),
+            BusError::Implausible => Error::Implausible,
+        }
+    }
+}
+
 /// A single synchronized IMU sample in SI units.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ImuSample {
@@ -108,6 +119,9 @@ impl Lsm6dsv16x {
 
     /// Burst-read gyro XYZ and accel XYZ (12 bytes starting at OUTX_L_G) and convert
     /// to SI units. Gyro comes first in the register map, then accel.
+    ///
+    /// Gravity keeps a live sensor's output away from all zeros or all ones, so such a read is
+    /// treated as a bus fault and retried.
     pub fn read_sample<I, PINS, CS>(
         &mut self,
         spi: &mut SpiBus<I, PINS>,
@@ -121,10 +135,7 @@ impl Lsm6dsv16x {
         let mut buf = [0u8; 13];
         buf[0] = reg::OUTX_L_G | SPI_READ;
 
-        cs.select();
-        let res = spi.transfer_in_place(&mut buf);
-        cs.deselect();
-        res?;
+        spi.transfer_checked(cs, &mut buf, 1)?;
 
         let gx_raw = i16::from_le_bytes([buf[1], buf[2]]);
         let gy_raw = i16::from_le_bytes([buf[3], buf[4]]);
@@ -143,7 +154,8 @@ impl Lsm6dsv16x {
         })
     }
 
-    fn read_reg<I, PINS, CS>(
+    /// Read a single register.
+    pub fn read_reg<I, PINS, CS>(
         &mut self,
         spi: &mut SpiBus<I, PINS>,
         cs: &mut CS,
@@ -162,7 +174,8 @@ impl Lsm6dsv16x {
         Ok(buf[1])
     }
 
-    fn write_reg<I, PINS, CS>(
+    /// Write a single register.
+    pub fn write_reg<I, PINS, CS>(
         &mut self,
         spi: &mut SpiBus<I, PINS>,
         cs: &mut CS,
@@ -8,30 +8,75 @@
 //!
 //! ## Existing drivers
 //!
+//! - [`closed_loop`] – `ClosedLoopAxis` trait for motors driven by an on-MCU position loop, and
+//!   the per-axis `OutputRange`
+//! - [`motors`] – Fixed-capacity collection of `ClosedLoopAxis` motors, to loop over many axes
 //! - [`drv8873`] – TI DRV8873-Q1 4-wire SPI motor driver
+//! - `drv8873_mock` – Scripted DRV8873 for exercising fault handling (`mock-drv8873` feature)
 //! - [`actuonix_linear`] – Actuonix linear actuator with DRV8873 driver and potentiometer feedback
+//! - [`drv8313`] – TI DRV8313 triple half-bridge, six-step BLDC commutation from hall sensors
+//! - [`torque_drive`] – DRV8873 PWM bridge under the interrupt-driven current loop, as a torque
+//!   source for the position loop
 //! - [`tb6612`] – ST TB6612FNG 2-channel motor driver
+//! - [`servo`] – 50 Hz hobby RC servo on a spare timer channel
+//! - [`stepper`] – STEP/DIR stepper with hardware step bursts on TIM8 and acceleration limiting
+//! - [`tmc2209`] – Trinamic TMC2209 stepper driver over single-wire UART, with stallGuard homing
 //! - [`vl53l0x`] – VL53L0X Time-of-Flight sensor
 //! - [`lsm6dsv16x`] – ST LSM6DSV16X 6-axis IMU
+//! - [`gpio_expander`] – MCP23017 / PCA9535 16-bit I2C GPIO expander
+//! - [`hx711`] – Avia HX711 load-cell amplifier (bit-banged)
+//! - [`ntc`] – NTC thermistor in an ADC divider, Steinhart–Hart conversion
+//! - [`fan`] – Cooling fan on a PWM channel, with a minimum running duty
+//! - [`ws2812`] – WS2812 addressable LED strip on TIM5 + DMA, with edge-lighting patterns
 //!
 //! ## Legacy drivers
 //!
 //! - [`fit0185`] – DFRobot FIT0185 motor with DRV8873 driver and TIM2 encoder
 //! - [`gim6010`] – SteadyWin GIM6010-48 motor with built-in GDZ468 encoder
+//! - [`gim6010_setup`] – GIM6010 device address and CAN bitrate provisioning
 
+pub mod closed_loop;
+pub mod drv8313;
 pub mod drv8873;
+#[cfg(feature = "mock-drv8873")]
+pub mod drv8873_mock;
 
 pub mod actuonix_linear;
+pub mod fan;
 pub mod fit0185;
 pub mod gim6010;
+pub mod gim6010_setup;
+pub mod gpio_expander;
+pub mod hx711;
 pub mod lsm6dsv16x;
+pub mod motors;
+pub mod ntc;
+pub mod servo;
+pub mod stepper;
 pub mod tb6612;
+pub mod tmc2209;
+pub mod torque_drive;
 pub mod vl53l0x;
+pub mod ws2812;
 
 pub use actuonix_linear::ActuonixLinear;
-pub use drv8873::Drv8873;
+pub use closed_loop::{ClosedLoopAxis, OutputRange};
+pub use drv8313::Drv8313;
+pub use drv8873::{Drv8873, Drv8873Regs};
+#[cfg(feature = "mock-drv8873")]
+pub use drv8873_mock::MockDrv8873;
+pub use fan::Fan;
 pub use fit0185::Fit0185;
-pub use gim6010::Gim6010;
+pub use gim6010::{Gim6010, MoveMonitor, MultiTurn, Reply};
+pub use gpio_expander::GpioExpander;
+pub use hx711::Hx711;
 pub use lsm6dsv16x::{ImuSample, Lsm6dsv16x};
+pub use motors::{MotorHandle, Motors};
+pub use ntc::Ntc;
+pub use servo::Servo;
+pub use stepper::Stepper;
 pub use tb6612::Tb6612;
+pub use tmc2209::Tmc2209;
+pub use torque_drive::{TorqueAxis, TorqueDrive, TorqueInputs};
 pub use vl53l0x::Vl53l0x;
+pub use ws2812::Ws2812;
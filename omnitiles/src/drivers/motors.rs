@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Collections of closed-loop motors, for boards with more actuators than it's reasonable to
+//! name one by one.
+//!
+//! Every actuator type carries its pins in its type (`ActuonixLinear<CS, 'E', 2, ...>`), so two
+//! actuators never have the same type. [`Motors`] holds them as [`MotorHandle`]s, each a borrowed
+//! `&mut dyn ClosedLoopAxis` tagged with its protocol axis number, in a fixed-capacity array like
+//! the other no-heap containers in the firmware. Code that runs the same steps on every motor
+//! (braking, fault checks, the control step) loops over [`Motors::iter_mut`]:
+//!
+//! ```ignore
+//! let mut motors = Motors::<4>::new();
+//! motors.push(1, &mut m1_actuator);
+//! motors.push(2, &mut m2_actuator);
+//! for (handle, ctrl) in motors.iter_mut().zip(&mut controllers) {
+//!     let _ = ctrl.step(handle, dt);
+//! }
+//! ```
+//!
+//! The collection borrows the actuators for its lifetime. Driver-specific calls (e.g. reading
+//! back DRV8873 registers) still go through the concrete actuators outside that borrow.
+
+use super::ClosedLoopAxis;
+
+/// One motor in a [`Motors`] collection.
+pub struct MotorHandle<'a> {
+    /// Protocol axis number (1 = M1, 2 = M2, ...).
+    pub axis: u8,
+    motor: &'a mut dyn ClosedLoopAxis,
+}
+
+impl<'a> MotorHandle<'a> {
+    pub fn new(axis: u8, motor: &'a mut dyn ClosedLoopAxis) -> Self {
+        Self { axis, motor }
+    }
+
+    /// The motor behind the handle.
+    pub fn motor(&mut self) -> &mut dyn ClosedLoopAxis {
+        &mut *self.motor
+    }
+}
+
+/// A handle drives its motor, so a [`LinearController`](crate::control::LinearController) can
+/// step it directly.
+impl ClosedLoopAxis for MotorHandle<'_> {
+    fn position(&mut self) -> Option<f32> {
+        self.motor.position()
+    }
+
+    fn apply_output(&mut self, output: f32) {
+        self.motor.apply_output(output);
+    }
+
+    fn brake(&mut self) {
+        self.motor.brake();
+    }
+
+    fn is_faulted(&mut self) -> bool {
+        self.motor.is_faulted()
+    }
+
+    fn pre_step(&mut self) {
+        self.motor.pre_step();
+    }
+}
+
+/// Up to `N` motors, in the order they were added.
+pub struct Motors<'a, const N: usize> {
+    handles: [Option<MotorHandle<'a>>; N],
+    len: usize,
+}
+
+impl<'a, const N: usize> Motors<'a, N> {
+    pub fn new() -> Self {
+        Self {
+            handles: core::array::from_fn(|_| None),
+            len: 0,
+        }
+    }
+
+    /// Add `motor` as `axis`. Returns `false`, dropping the borrow, if the collection is full or
+    /// already has that axis.
+    pub fn push(&mut self, axis: u8, motor: &'a mut dyn ClosedLoopAxis) -> bool {
+        if self.len == N || self.get_mut(axis).is_some() {
+            return false;
+        }
+        self.handles[self.len] = Some(MotorHandle::new(axis, motor));
+        self.len += 1;
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The motor of `axis`, if it is in the collection.
+    pub fn get_mut(&mut self, axis: u8) -> Option<&mut MotorHandle<'a>> {
+        self.iter_mut().find(|h| h.axis == axis)
+    }
+
+    /// Motors in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &MotorHandle<'a>> {
+        self.handles[..self.len].iter().flatten()
+    }
+
+    /// Motors in the order they were added.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut MotorHandle<'a>> {
+        self.handles[..self.len].iter_mut().flatten()
+    }
+
+    /// Stop every motor.
+    pub fn brake_all(&mut self) {
+        for handle in self.iter_mut() {
+            handle.brake();
+        }
+    }
+
+    /// Axis number of the first motor that reports a fault, if any does.
+    pub fn first_faulted(&mut self) -> Option<u8> {
+        for handle in self.iter_mut() {
+            if handle.is_faulted() {
+                return Some(handle.axis);
+            }
+        }
+        None
+    }
+}
+
+impl<const N: usize> Default for Motors<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct FakeAxis {
+        faulted: bool,
+        braked: bool,
+    }
+
+    impl ClosedLoopAxis for FakeAxis {
+        fn position(&mut self) -> Option<f32> {
+            None
+        }
+
+        fn apply_output(&mut self, _output: f32) {}
+
+        fn brake(&mut self) {
+            self.braked = true;
+        }
+
+        fn is_faulted(&mut self) -> bool {
+            self.faulted
+        }
+    }
+
+    #[test]
+    fn push_rejects_a_duplicate_axis() {
+        let (mut a, mut b) = (FakeAxis::default(), FakeAxis::default());
+        let mut motors = Motors::<2>::new();
+        assert!(motors.push(1, &mut a));
+        assert!(!motors.push(1, &mut b));
+        assert_eq!(motors.len(), 1);
+    }
+
+    #[test]
+    fn push_rejects_once_full() {
+        let (mut a, mut b) = (FakeAxis::default(), FakeAxis::default());
+        let mut motors = Motors::<1>::new();
+        assert!(motors.push(1, &mut a));
+        assert!(!motors.push(2, &mut b));
+        assert_eq!(motors.len(), 1);
+        assert!(motors.get_mut(2).is_none());
+    }
+
+    #[test]
+    fn first_faulted_follows_push_order() {
+        let mut a = FakeAxis::default();
+        let mut b = FakeAxis {
+            faulted: true,
+            ..FakeAxis::default()
+        };
+        let mut c = FakeAxis {
+            faulted: true,
+            ..FakeAxis::default()
+        };
+        let mut motors = Motors::<3>::new();
+        motors.push(1, &mut a);
+        motors.push(3, &mut c);
+        motors.push(2, &mut b);
+        assert_eq!(motors.first_faulted(), Some(3));
+        motors.brake_all();
+        drop(motors);
+        assert!(a.braked && b.braked && c.braked);
+    }
+}
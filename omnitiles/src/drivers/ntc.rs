@@ -0,0 +1,128 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! NTC thermistor in a resistor divider on an ADC channel.
+//!
+//! The thermistor and a fixed resistor divide the ADC reference; the reading gives the
+//! thermistor's resistance, and the Steinhart–Hart equation turns that into temperature:
+//!
+//! ```text
+//! 1 / T = A + B·ln(R) + C·ln(R)³        (T in kelvin, R in ohms)
+//! ```
+//!
+//! [`SteinhartHart::from_beta`] builds the coefficients from the R25/β pair on a thermistor's
+//! datasheet, good to about ±1 °C over 0–100 °C. [`SteinhartHart::fit`] solves them from three
+//! points of the datasheet's R/T table, which holds over the whole range.
+//!
+//! The driver implements [`TemperatureSource`], so it can feed
+//! [`MotorTemperature`](crate::sensors::MotorTemperature) for thermal derating or
+//! [`PotTempCompensation`](crate::sensors::PotTempCompensation).
+
+use crate::sensors::TemperatureSource;
+use micromath::F32Ext;
+
+/// 0 °C in kelvin.
+const ZERO_C_IN_K: f32 = 273.15;
+/// Full scale of the 12-bit ADC.
+const FULL_SCALE: u16 = 4095;
+/// Readings this close to either rail mean an open or shorted thermistor.
+const RAIL_MARGIN: u16 = 8;
+
+/// Steinhart–Hart coefficients of a thermistor.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SteinhartHart {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+}
+
+impl SteinhartHart {
+    /// Coefficients of the β model: resistance `r0_ohms` at `t0_c` (usually 10 kΩ at 25 °C)
+    /// and the datasheet's `beta` (e.g. 3950 K). The β model is Steinhart–Hart with `c` = 0.
+    pub fn from_beta(r0_ohms: f32, t0_c: f32, beta: f32) -> Self {
+        Self {
+            a: 1.0 / (t0_c + ZERO_C_IN_K) - r0_ohms.ln() / beta,
+            b: 1.0 / beta,
+            c: 0.0,
+        }
+    }
+
+    /// Solve the coefficients from three `(°C, ohms)` points, e.g. the 0, 25 and 85 °C rows of
+    /// the datasheet table. Returns `None` unless the resistances are distinct.
+    pub fn fit(points: [(f32, f32); 3]) -> Option<Self> {
+        let [(t1, r1), (t2, r2), (t3, r3)] = points;
+        let (l1, l2, l3) = (r1.ln(), r2.ln(), r3.ln());
+        let (y1, y2, y3) = (
+            1.0 / (t1 + ZERO_C_IN_K),
+            1.0 / (t2 + ZERO_C_IN_K),
+            1.0 / (t3 + ZERO_C_IN_K),
+        );
+        if l1 == l2 || l1 == l3 || l2 == l3 {
+            return None;
+        }
+        let g2 = (y2 - y1) / (l2 - l1);
+        let g3 = (y3 - y1) / (l3 - l1);
+        let c = (g3 - g2) / (l3 - l2) / (l1 + l2 + l3);
+        let b = g2 - c * (l1 * l1 + l1 * l2 + l2 * l2);
+        let a = y1 - (b + c * l1 * l1) * l1;
+        Some(Self { a, b, c })
+    }
+
+    /// Temperature in °C at resistance `ohms`.
+    pub fn celsius(&self, ohms: f32) -> f32 {
+        let l = ohms.ln();
+        1.0 / (self.a + self.b * l + self.c * l * l * l) - ZERO_C_IN_K
+    }
+}
+
+/// Which leg of the divider the thermistor is on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Placement {
+    /// Fixed resistor to the reference, thermistor to ground. The reading falls as it warms.
+    LowSide,
+    /// Thermistor to the reference, fixed resistor to ground. The reading rises as it warms.
+    HighSide,
+}
+
+/// An NTC thermistor read with a reader closure (see
+/// [`Adc::make_reader`](crate::hw::Adc::make_reader)). The divider runs from the ADC reference,
+/// so the measurement is ratiometric and needs no reference voltage.
+pub struct Ntc<F: FnMut() -> u16> {
+    read: F,
+    fixed_ohms: f32,
+    placement: Placement,
+    coeffs: SteinhartHart,
+}
+
+impl<F: FnMut() -> u16> Ntc<F> {
+    /// Thermistor with coefficients `coeffs` at `placement` in a divider with a `fixed_ohms`
+    /// resistor.
+    pub fn new(read: F, fixed_ohms: f32, placement: Placement, coeffs: SteinhartHart) -> Self {
+        Self {
+            read,
+            fixed_ohms,
+            placement,
+            coeffs,
+        }
+    }
+
+    /// Thermistor resistance, or `None` if the reading is at a rail (open or shorted).
+    pub fn read_ohms(&mut self) -> Option<f32> {
+        let raw = (self.read)();
+        if raw <= RAIL_MARGIN || raw >= FULL_SCALE - RAIL_MARGIN {
+            return None;
+        }
+        let ratio = raw as f32 / (FULL_SCALE - raw) as f32;
+        Some(match self.placement {
+            Placement::LowSide => self.fixed_ohms * ratio,
+            Placement::HighSide => self.fixed_ohms / ratio,
+        })
+    }
+}
+
+impl<F: FnMut() -> u16> TemperatureSource for Ntc<F> {
+    fn read_celsius(&mut self) -> Option<f32> {
+        let ohms = self.read_ohms()?;
+        Some(self.coeffs.celsius(ohms))
+    }
+}
@@ -0,0 +1,110 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Hobby RC servo on a PWM channel.
+//!
+//! Small auxiliary actuators (latches, pointer flags) are driven straight from a spare timer
+//! channel, no driver chip needed. The servo reads the width of a pulse repeated every 20 ms:
+//! 1000 µs is one end of its travel and 2000 µs the other on most parts, some go to 500–2500 µs.
+//!
+//! The caller sets the timer up for a [`PERIOD_US`] period, e.g.
+//! `dp.TIM12.pwm::<_, _, 1_000_000>(pin, 20.millis(), &clocks)`; [`Servo`] only writes duty. The
+//! output starts without pulses, so the servo stays limp until the first command instead of
+//! jumping to some default position at boot.
+
+use crate::units::Deg;
+use stm32f7xx_hal::prelude::*;
+
+/// Servo frame period.
+pub const PERIOD_US: u32 = 20_000;
+
+/// Pulse widths at the two ends of travel and the angle between them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ServoRange {
+    pub min_us: u16,
+    pub max_us: u16,
+    /// Travel between `min_us` and `max_us`.
+    pub travel: Deg,
+}
+
+impl ServoRange {
+    /// 1000–2000 µs over 180°, the common default.
+    pub const STANDARD: Self = Self {
+        min_us: 1000,
+        max_us: 2000,
+        travel: Deg(180.0),
+    };
+}
+
+impl Default for ServoRange {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
+
+pub struct Servo<Pwm> {
+    pwm: Pwm,
+    range: ServoRange,
+    pulse_us: Option<u16>,
+}
+
+impl<Pwm> Servo<Pwm>
+where
+    Pwm: _embedded_hal_PwmPin<Duty = u16>,
+{
+    pub fn new(mut pwm: Pwm) -> Self {
+        pwm.set_duty(0);
+        pwm.disable();
+        Self {
+            pwm,
+            range: ServoRange::STANDARD,
+            pulse_us: None,
+        }
+    }
+
+    /// Use a different pulse range, e.g. for a 500–2500 µs or 270° servo.
+    pub fn with_range(mut self, range: ServoRange) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Output `us`-wide pulses, clamped to the range.
+    pub fn set_pulse_us(&mut self, us: u16) {
+        let us = us.max(self.range.min_us).min(self.range.max_us);
+        let duty = us as u32 * self.pwm.get_max_duty() as u32 / PERIOD_US;
+        self.pwm.set_duty(duty as u16);
+        if self.pulse_us.is_none() {
+            self.pwm.enable();
+        }
+        self.pulse_us = Some(us);
+    }
+
+    /// Move to `fraction` of the travel, 0.0 = `min_us` end.
+    pub fn set_position(&mut self, fraction: f32) {
+        let span = self.range.max_us.saturating_sub(self.range.min_us) as f32;
+        // NaN falls through the clamp and casts to 0, the `min_us` end.
+        let us = self.range.min_us as f32 + fraction.clamp(0.0, 1.0) * span;
+        self.set_pulse_us((us + 0.5) as u16);
+    }
+
+    /// Move to `angle` from the `min_us` end.
+    pub fn set_angle(&mut self, angle: Deg) {
+        self.set_position(angle.get() / self.range.travel.get());
+    }
+
+    /// Stop the pulses. Most servos stop holding position.
+    pub fn release(&mut self) {
+        self.pwm.set_duty(0);
+        self.pwm.disable();
+        self.pulse_us = None;
+    }
+
+    /// Commanded pulse width, `None` while released.
+    pub fn pulse_us(&self) -> Option<u16> {
+        self.pulse_us
+    }
+
+    pub fn free(self) -> Pwm {
+        self.pwm
+    }
+}
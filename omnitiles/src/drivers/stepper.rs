@@ -0,0 +1,179 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! STEP/DIR stepper driver (A4988, DRV8825, TMC2209 in standalone mode, ...) on TIM8 CH1.
+//!
+//! For tile variants whose lift is a stepper instead of a DC motor. The axis takes a velocity
+//! command from [`ClosedLoopAxis::apply_output`](super::ClosedLoopAxis::apply_output), once per
+//! control period, and ramps its step rate towards it within an acceleration limit so the motor
+//! doesn't stall on a step change in output.
+//!
+//! Steps are generated in hardware: each period, TIM8 runs in one-pulse mode with its repetition
+//! counter set to the number of steps due, and stops by itself after exactly that many pulses.
+//! Position is the running sum of the steps issued, so it is exact without an interrupt as long as
+//! the motor doesn't skip. There is no feedback: a stall goes unnoticed, so home against a switch
+//! and keep the acceleration limit well inside what the mechanics can follow.
+//!
+//! The step pulse is the last [`PULSE_US`] of each timer period, which gives the driver at least
+//! that much DIR setup time after a direction change at the start of a burst.
+
+use stm32f7xx_hal::{
+    gpio::{self, Output, PushPull},
+    pac,
+};
+
+/// Timer tick rate after the prescaler.
+const TICK_HZ: u32 = 1_000_000;
+/// STEP high time; well above the 1–2 µs that common drivers need.
+pub const PULSE_US: u32 = 3;
+/// Highest step rate the timer can produce with [`PULSE_US`] high and as long low.
+pub const MAX_STEP_HZ: f32 = (TICK_HZ / (2 * PULSE_US)) as f32;
+
+// CR1 fields.
+const CR1_CEN: u32 = 1 << 0;
+const CR1_URS: u32 = 1 << 2;
+const CR1_OPM: u32 = 1 << 3;
+// BDTR main output enable.
+const BDTR_MOE: u32 = 1 << 15;
+
+/// Stepper axis. Owns TIM8, the STEP pin (TIM8_CH1, AF3) and the DIR pin.
+pub struct Stepper<STEP, const DIR_P: char, const DIR_N: u8> {
+    tim: pac::TIM8,
+    step: STEP,
+    dir: gpio::Pin<DIR_P, DIR_N, Output<PushPull>>,
+    /// Control period, i.e. the time between [`apply_output`](Self::apply_output) calls.
+    period_s: f32,
+    steps_per_unit: f32,
+    /// Step rate at full output, steps/s.
+    max_rate: f32,
+    /// Step rate change per second, steps/s².
+    max_accel: f32,
+    /// Current signed step rate.
+    rate: f32,
+    /// Fraction of a step carried into the next period.
+    carry: f32,
+    steps: i32,
+}
+
+impl<STEP, const DIR_P: char, const DIR_N: u8> Stepper<STEP, DIR_P, DIR_N> {
+    /// Configure TIM8 CH1 for step bursts.
+    ///
+    /// `timer_hz` is the TIM8 kernel clock (APB2 timer clock), `period_ms` the control period (at
+    /// most 65 ms, the 16-bit timer's range at 1 µs per tick) and `steps_per_unit` the steps per
+    /// mm (or per output revolution) including microstepping.
+    /// Defaults to 1 unit/s and 10 units/s²; see [`with_limits`](Self::with_limits).
+    pub fn new(
+        tim: pac::TIM8,
+        step: STEP,
+        dir: gpio::Pin<DIR_P, DIR_N, Output<PushPull>>,
+        timer_hz: u32,
+        period_ms: u32,
+        steps_per_unit: f32,
+    ) -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.apb2enr.modify(|_, w| w.tim8en().set_bit());
+
+        tim.cr1.write(|w| unsafe { w.bits(CR1_URS | CR1_OPM) });
+        tim.psc.write(|w| unsafe { w.bits(timer_hz / TICK_HZ - 1) });
+        // CH1 PWM mode 2: low until CCR1, then high for the rest of the period.
+        tim.ccmr1_output()
+            .modify(|_, w| unsafe { w.oc1m().bits(0b111).oc1pe().set_bit() });
+        tim.ccer.modify(|_, w| w.cc1e().set_bit());
+        tim.bdtr
+            .modify(|r, w| unsafe { w.bits(r.bits() | BDTR_MOE) });
+
+        Self {
+            tim,
+            step,
+            dir,
+            period_s: period_ms as f32 / 1000.0,
+            steps_per_unit,
+            max_rate: steps_per_unit,
+            max_accel: 10.0 * steps_per_unit,
+            rate: 0.0,
+            carry: 0.0,
+            steps: 0,
+        }
+    }
+
+    /// Speed at full output in units/s and acceleration limit in units/s².
+    pub fn with_limits(mut self, max_speed: f32, max_accel: f32) -> Self {
+        self.max_rate = (max_speed * self.steps_per_unit).min(MAX_STEP_HZ);
+        self.max_accel = max_accel * self.steps_per_unit;
+        self
+    }
+
+    /// Position in units, counted from the last [`set_position`](Self::set_position). Includes
+    /// the steps of a burst still in progress.
+    pub fn position(&self) -> f32 {
+        self.steps as f32 / self.steps_per_unit
+    }
+
+    /// Redefine the current position, e.g. after homing.
+    pub fn set_position(&mut self, units: f32) {
+        self.steps = (units * self.steps_per_unit) as i32;
+    }
+
+    /// Current speed in units/s.
+    pub fn speed(&self) -> f32 {
+        self.rate / self.steps_per_unit
+    }
+
+    /// True while a burst is being output.
+    pub fn is_stepping(&self) -> bool {
+        self.tim.cr1.read().bits() & CR1_CEN != 0
+    }
+
+    /// Ramp towards `output` (-1.0..1.0 of full speed) and output this period's steps.
+    pub fn apply_output(&mut self, output: f32) {
+        // NaN falls through the clamp; treat it as a stop.
+        let output = if output.is_nan() {
+            0.0
+        } else {
+            output.clamp(-1.0, 1.0)
+        };
+        let target = output * self.max_rate;
+        let dv = self.max_accel * self.period_s;
+        self.rate = target.clamp(self.rate - dv, self.rate + dv);
+
+        // The previous burst is sized to fit the period; if it hasn't finished (late call), skip
+        // this one rather than lose count of the pulses already queued.
+        if self.is_stepping() {
+            return;
+        }
+        let due = self.rate.abs() * self.period_s + self.carry;
+        let count = (due as u32).min(u16::MAX as u32 + 1);
+        self.carry = due - count as f32;
+        if count == 0 {
+            return;
+        }
+        if self.rate >= 0.0 {
+            self.dir.set_high();
+            self.steps += count as i32;
+        } else {
+            self.dir.set_low();
+            self.steps -= count as i32;
+        }
+        let ticks = (self.period_s * TICK_HZ as f32) as u32 / count;
+        let ticks = ticks.max(2 * PULSE_US);
+        self.tim.arr.write(|w| unsafe { w.bits(ticks - 1) });
+        self.tim.ccr1.write(|w| unsafe { w.bits(ticks - PULSE_US) });
+        self.tim.rcr.write(|w| unsafe { w.bits(count - 1) });
+        // Load ARR, CCR1 and the repetition counter; URS keeps this from flagging an update.
+        self.tim.egr.write(|w| w.ug().set_bit());
+        self.tim
+            .cr1
+            .modify(|r, w| unsafe { w.bits(r.bits() | CR1_CEN) });
+    }
+
+    /// Stop issuing steps. A burst in progress runs to its end (at most one control period), so
+    /// the step count stays exact.
+    pub fn brake(&mut self) {
+        self.rate = 0.0;
+        self.carry = 0.0;
+    }
+
+    pub fn free(self) -> (pac::TIM8, STEP, gpio::Pin<DIR_P, DIR_N, Output<PushPull>>) {
+        (self.tim, self.step, self.dir)
+    }
+}
@@ -0,0 +1,460 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Trinamic TMC2209 stepper driver over its single-wire UART.
+//!
+//! The TMC2209 is the alternative lift drive for stepper tile variants: [`Stepper`](super::Stepper)
+//! generates STEP/DIR, and this driver configures the chip (run and hold current, microstepping)
+//! and reads stallGuard, which lets the lift home against its mechanical end instead of a limit
+//! switch ([`Tmc2209::home`]).
+//!
+//! PDN_UART is a single wire shared by TX and RX (TX through 1 kΩ), so every byte the MCU sends
+//! is also received; reads skip that echo before the reply. Up to four drivers share the wire,
+//! addressed 0–3 by their MS1/MS2 pins.
+//!
+//! stallGuard4 only works in stealthChop, which is the power-on mode, and only above the
+//! `TCOOLTHRS` velocity; [`Tmc2209::home`] sets both up for the duration of the move.
+
+use cortex_m::delay::Delay;
+use stm32f7xx_hal::serial::Instance;
+
+use crate::hw::{iwdg, Usart};
+
+const SYNC: u8 = 0x05;
+/// Address the driver answers reads with.
+const MASTER_ADDR: u8 = 0xFF;
+const WRITE: u8 = 0x80;
+const WRITE_LEN: usize = 8;
+const READ_REQUEST_LEN: usize = 4;
+const READ_REPLY_LEN: usize = 8;
+
+/// How long to wait for a reply byte.
+const REPLY_TIMEOUT_US: u32 = 5_000;
+const POLL_US: u32 = 10;
+
+pub mod reg {
+    pub const GCONF: u8 = 0x00;
+    pub const GSTAT: u8 = 0x01;
+    pub const IFCNT: u8 = 0x02;
+    pub const IOIN: u8 = 0x06;
+    pub const IHOLD_IRUN: u8 = 0x10;
+    pub const TPOWERDOWN: u8 = 0x11;
+    pub const TSTEP: u8 = 0x12;
+    pub const TPWMTHRS: u8 = 0x13;
+    pub const TCOOLTHRS: u8 = 0x14;
+    pub const VACTUAL: u8 = 0x22;
+    pub const SGTHRS: u8 = 0x40;
+    pub const SG_RESULT: u8 = 0x41;
+    pub const COOLCONF: u8 = 0x42;
+    pub const MSCNT: u8 = 0x6A;
+    pub const CHOPCONF: u8 = 0x6C;
+    pub const DRV_STATUS: u8 = 0x6F;
+    pub const PWMCONF: u8 = 0x70;
+}
+
+// GCONF fields.
+const GCONF_EN_SPREADCYCLE: u32 = 1 << 2;
+const GCONF_SHAFT: u32 = 1 << 3;
+const GCONF_PDN_DISABLE: u32 = 1 << 6;
+const GCONF_MSTEP_REG_SELECT: u32 = 1 << 7;
+const GCONF_MULTISTEP_FILT: u32 = 1 << 8;
+
+/// CHOPCONF power-on default (TOFF 3, HSTRT 5, interpolation on, 256 microsteps).
+const CHOPCONF_DEFAULT: u32 = 0x1000_0053;
+const CHOPCONF_MRES_SHIFT: u32 = 24;
+const CHOPCONF_MRES_MASK: u32 = 0xF << CHOPCONF_MRES_SHIFT;
+
+/// `TCOOLTHRS` that keeps stallGuard on at any velocity.
+const TCOOLTHRS_ALWAYS: u32 = 0xF_FFFF;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// No complete reply in time.
+    Timeout,
+    /// The reply failed its CRC or isn't for the register asked for.
+    BadReply,
+    /// A write didn't advance `IFCNT`, so the driver didn't take it.
+    WriteLost,
+    /// Microstep count other than a power of two from 1 to 256.
+    InvalidMicrosteps(u16),
+}
+
+/// `DRV_STATUS` bits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DrvStatus(pub u32);
+
+impl DrvStatus {
+    /// Overtemperature prewarning (120 °C).
+    pub fn otpw(self) -> bool {
+        self.0 & (1 << 0) != 0
+    }
+
+    /// Overtemperature shutdown.
+    pub fn ot(self) -> bool {
+        self.0 & (1 << 1) != 0
+    }
+
+    /// Short to ground or across a low-side MOSFET on either coil.
+    pub fn short(self) -> bool {
+        self.0 & (0b1111 << 2) != 0
+    }
+
+    /// Open load on either coil. Only meaningful while the motor turns slowly.
+    pub fn open_load(self) -> bool {
+        self.0 & (0b11 << 6) != 0
+    }
+
+    /// Actual motor current scale, 0–31.
+    pub fn cs_actual(self) -> u8 {
+        ((self.0 >> 16) & 0x1F) as u8
+    }
+
+    /// Running in stealthChop.
+    pub fn stealth(self) -> bool {
+        self.0 & (1 << 30) != 0
+    }
+
+    /// Standstill.
+    pub fn standstill(self) -> bool {
+        self.0 & (1 << 31) != 0
+    }
+}
+
+/// Settings applied by [`Tmc2209::init`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// Run current scale, 0–31 (of the full scale set by the sense resistors).
+    pub run_current: u8,
+    /// Standstill current scale, 0–31.
+    pub hold_current: u8,
+    /// Microsteps per full step: 1, 2, 4, ..., 256.
+    pub microsteps: u16,
+    /// Reverse the motor direction.
+    pub inverted: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            run_current: 16,
+            hold_current: 8,
+            microsteps: 16,
+            inverted: false,
+        }
+    }
+}
+
+/// Sensorless homing parameters for [`Tmc2209::home`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Homing {
+    /// Internal step generator velocity, signed: the sign picks the end to home against. Units
+    /// of 0.715 µsteps/s at the internal 12 MHz clock.
+    pub vactual: i32,
+    /// `SGTHRS`; a stall is flagged when `SG_RESULT` falls to twice this. Higher is more
+    /// sensitive. Tune per mechanism and velocity.
+    pub threshold: u8,
+    /// Ignore stalls while the motor spins up.
+    pub spinup_ms: u32,
+    /// Consecutive stalled samples that count as the end stop.
+    pub samples: u8,
+    pub sample_ms: u32,
+    pub timeout_ms: u32,
+}
+
+impl Default for Homing {
+    fn default() -> Self {
+        Self {
+            vactual: -20_000,
+            threshold: 60,
+            spinup_ms: 200,
+            samples: 3,
+            sample_ms: 10,
+            timeout_ms: 20_000,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HomingError {
+    /// No stall within the timeout.
+    Timeout,
+    /// UART communication failed.
+    Driver(Error),
+}
+
+impl From<Error> for HomingError {
+    fn from(e: Error) -> Self {
+        HomingError::Driver(e)
+    }
+}
+
+/// One TMC2209 on the UART wire.
+pub struct Tmc2209 {
+    addr: u8,
+    /// Last `GCONF` written.
+    gconf: u32,
+    /// Last `CHOPCONF` written.
+    chopconf: u32,
+}
+
+impl Tmc2209 {
+    /// Driver at node address `addr` (0–3, from MS1/MS2).
+    pub fn new(addr: u8) -> Self {
+        Self {
+            addr: addr & 0x03,
+            gconf: 0,
+            chopconf: CHOPCONF_DEFAULT,
+        }
+    }
+
+    /// Take the driver over from its pins: current and microsteps come from UART from now on.
+    pub fn init<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+        settings: Settings,
+    ) -> Result<(), Error> {
+        self.read_reg(usart, delay, reg::GSTAT)?;
+        // Clear reset and error flags.
+        self.write_checked(usart, delay, reg::GSTAT, 0b111)?;
+        let mut gconf = GCONF_PDN_DISABLE | GCONF_MSTEP_REG_SELECT | GCONF_MULTISTEP_FILT;
+        if settings.inverted {
+            gconf |= GCONF_SHAFT;
+        }
+        self.write_checked(usart, delay, reg::GCONF, gconf)?;
+        self.gconf = gconf;
+        self.set_current(usart, delay, settings.run_current, settings.hold_current)?;
+        self.set_microsteps(usart, delay, settings.microsteps)?;
+        // Drop to hold current about 1 s after the last step.
+        self.write_checked(usart, delay, reg::TPOWERDOWN, 20)
+    }
+
+    /// Run and hold current scales, 0–31.
+    pub fn set_current<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+        run: u8,
+        hold: u8,
+    ) -> Result<(), Error> {
+        // IHOLDDELAY 6: ramp down to hold current over ~0.5 s.
+        let value = (6 << 16) | ((run.min(31) as u32) << 8) | hold.min(31) as u32;
+        self.write_checked(usart, delay, reg::IHOLD_IRUN, value)
+    }
+
+    /// Microsteps per full step, a power of two from 1 to 256.
+    pub fn set_microsteps<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+        microsteps: u16,
+    ) -> Result<(), Error> {
+        if !microsteps.is_power_of_two() || microsteps > 256 {
+            return Err(Error::InvalidMicrosteps(microsteps));
+        }
+        let mres = 8 - microsteps.trailing_zeros();
+        let chopconf = (self.chopconf & !CHOPCONF_MRES_MASK) | (mres << CHOPCONF_MRES_SHIFT);
+        self.write_checked(usart, delay, reg::CHOPCONF, chopconf)?;
+        self.chopconf = chopconf;
+        Ok(())
+    }
+
+    /// Microsteps per full step as last configured.
+    pub fn microsteps(&self) -> u16 {
+        256 >> ((self.chopconf & CHOPCONF_MRES_MASK) >> CHOPCONF_MRES_SHIFT)
+    }
+
+    /// Latest stallGuard load measurement, 0–510. Lower means more load; only valid in
+    /// stealthChop above `TCOOLTHRS`.
+    pub fn stallguard<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+    ) -> Result<u16, Error> {
+        Ok((self.read_reg(usart, delay, reg::SG_RESULT)? & 0x3FF) as u16)
+    }
+
+    pub fn drv_status<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+    ) -> Result<DrvStatus, Error> {
+        Ok(DrvStatus(self.read_reg(usart, delay, reg::DRV_STATUS)?))
+    }
+
+    /// Run the internal step generator at `vactual` (signed, 24-bit); 0 returns control to the
+    /// STEP input.
+    pub fn set_velocity<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+        vactual: i32,
+    ) -> Result<(), Error> {
+        let value = (vactual.clamp(-0x7F_FFFF, 0x7F_FFFF) as u32) & 0xFF_FFFF;
+        self.write_checked(usart, delay, reg::VACTUAL, value)
+    }
+
+    /// Drive against a mechanical end until stallGuard reports a stall, then stop.
+    ///
+    /// Runs on the internal step generator, so STEP pulses must be idle. Afterwards the motor
+    /// stands at the end stop; redefine the axis position there (e.g.
+    /// [`Stepper::set_position`](super::Stepper::set_position)).
+    pub fn home<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+        homing: Homing,
+    ) -> Result<(), HomingError> {
+        let gconf = self.gconf & !GCONF_EN_SPREADCYCLE;
+        if gconf != self.gconf {
+            self.write_checked(usart, delay, reg::GCONF, gconf)?;
+            self.gconf = gconf;
+        }
+        self.write_checked(usart, delay, reg::TCOOLTHRS, TCOOLTHRS_ALWAYS)?;
+        self.write_checked(usart, delay, reg::SGTHRS, homing.threshold as u32)?;
+        self.set_velocity(usart, delay, homing.vactual)?;
+
+        let result = self.wait_for_stall(usart, delay, &homing);
+        // Always stop, and hand the motor back to STEP/DIR, even if the wait failed.
+        let stopped = self.set_velocity(usart, delay, 0);
+        let restored = self.write_checked(usart, delay, reg::TCOOLTHRS, 0);
+        result?;
+        stopped?;
+        restored?;
+        Ok(())
+    }
+
+    fn wait_for_stall<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+        homing: &Homing,
+    ) -> Result<(), HomingError> {
+        delay.delay_ms(homing.spinup_ms);
+        let stall_level = 2 * homing.threshold as u16;
+        let mut stalled = 0;
+        let mut elapsed_ms = homing.spinup_ms;
+        while elapsed_ms < homing.timeout_ms {
+            iwdg::feed();
+            if self.stallguard(usart, delay)? <= stall_level {
+                stalled += 1;
+                if stalled >= homing.samples {
+                    return Ok(());
+                }
+            } else {
+                stalled = 0;
+            }
+            delay.delay_ms(homing.sample_ms);
+            elapsed_ms += homing.sample_ms;
+        }
+        Err(HomingError::Timeout)
+    }
+
+    /// Write a register and confirm the driver took it via `IFCNT`.
+    pub fn write_checked<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+        reg: u8,
+        value: u32,
+    ) -> Result<(), Error> {
+        let before = self.read_reg(usart, delay, reg::IFCNT)? as u8;
+        self.write_reg(usart, delay, reg, value)?;
+        let after = self.read_reg(usart, delay, reg::IFCNT)? as u8;
+        if after == before.wrapping_add(1) {
+            Ok(())
+        } else {
+            Err(Error::WriteLost)
+        }
+    }
+
+    /// Write a register without confirmation.
+    pub fn write_reg<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+        reg: u8,
+        value: u32,
+    ) -> Result<(), Error> {
+        let v = value.to_be_bytes();
+        let mut frame = [SYNC, self.addr, reg | WRITE, v[0], v[1], v[2], v[3], 0];
+        frame[WRITE_LEN - 1] = crc8(&frame[..WRITE_LEN - 1]);
+        drain(usart);
+        send(usart, &frame);
+        // Skip the echo.
+        receive::<U, WRITE_LEN>(usart, delay).map(|_| ())
+    }
+
+    /// Read a register.
+    pub fn read_reg<U: Instance>(
+        &mut self,
+        usart: &mut Usart<U>,
+        delay: &mut Delay,
+        reg: u8,
+    ) -> Result<u32, Error> {
+        let mut request = [SYNC, self.addr, reg & !WRITE, 0];
+        request[READ_REQUEST_LEN - 1] = crc8(&request[..READ_REQUEST_LEN - 1]);
+        drain(usart);
+        send(usart, &request);
+        receive::<U, READ_REQUEST_LEN>(usart, delay)?;
+        let reply = receive::<U, READ_REPLY_LEN>(usart, delay)?;
+        if reply[0] != SYNC
+            || reply[1] != MASTER_ADDR
+            || reply[2] != reg & !WRITE
+            || reply[READ_REPLY_LEN - 1] != crc8(&reply[..READ_REPLY_LEN - 1])
+        {
+            return Err(Error::BadReply);
+        }
+        Ok(u32::from_be_bytes([reply[3], reply[4], reply[5], reply[6]]))
+    }
+}
+
+/// Datagram CRC: CRC-8 with polynomial 0x07, each byte fed LSB first.
+pub fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        let mut b = byte;
+        for _ in 0..8 {
+            crc = if (crc >> 7) ^ (b & 1) != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+            b >>= 1;
+        }
+    }
+    crc
+}
+
+/// Drop stale bytes so they aren't taken for a reply.
+fn drain<U: Instance>(usart: &mut Usart<U>) {
+    while usart.read_byte().is_some() {}
+}
+
+fn send<U: Instance>(usart: &mut Usart<U>, bytes: &[u8]) {
+    for &b in bytes {
+        usart.write_byte(b);
+    }
+    usart.flush();
+}
+
+fn receive<U: Instance, const N: usize>(
+    usart: &mut Usart<U>,
+    delay: &mut Delay,
+) -> Result<[u8; N], Error> {
+    let mut buf = [0u8; N];
+    for byte in buf.iter_mut() {
+        let mut waited_us = 0;
+        *byte = loop {
+            if let Some(b) = usart.read_byte() {
+                break b;
+            }
+            if waited_us >= REPLY_TIMEOUT_US {
+                return Err(Error::Timeout);
+            }
+            delay.delay_us(POLL_US);
+            waited_us += POLL_US;
+        };
+    }
+    Ok(buf)
+}
@@ -0,0 +1,232 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! DRV8873 in PWM mode run as a torque source by the current loop.
+//!
+//! The axis is split by context (see [`current_loop`](crate::control::current_loop)):
+//!
+//! - [`TorqueDrive`] lives in the timer interrupt. It owns the bridge's IN1/IN2 PWM channels and
+//!   applies the duty from a [`CurrentLoop`] step on every tick.
+//! - [`TorqueAxis`] lives in the main task. It reads position and turns the position loop's
+//!   output into a current target, so the usual PID, profiles and on-target logic run unchanged
+//!   through [`ClosedLoopAxis`](super::ClosedLoopAxis).
+//! - [`TorqueInputs`] also lives in the main task, in place of the bridge's PWM channels under a
+//!   driver written for duty, such as [`ActuonixLinear`](super::ActuonixLinear). Its duties become
+//!   a current target, so the driver's range, supply scaling and end-stop checks carry over.
+//!
+//! The DRV8873's SPI configuration and fault handling stay with the main task.
+
+use core::sync::atomic::{AtomicU16, Ordering};
+
+use crate::control::current_loop::{CurrentCommand, CurrentLoop};
+use stm32f7xx_hal::prelude::*;
+
+/// Interrupt side: the current loop and the bridge PWM.
+pub struct TorqueDrive<Pwm1, Pwm2> {
+    pwm1: Pwm1,
+    pwm2: Pwm2,
+    current: CurrentLoop,
+    inverted: bool,
+}
+
+impl<Pwm1, Pwm2> TorqueDrive<Pwm1, Pwm2>
+where
+    Pwm1: _embedded_hal_PwmPin<Duty = u16>,
+    Pwm2: _embedded_hal_PwmPin<Duty = u16>,
+{
+    /// Starts braked.
+    pub fn new(pwm1: Pwm1, pwm2: Pwm2, current: CurrentLoop) -> Self {
+        let mut drive = Self {
+            pwm1,
+            pwm2,
+            current,
+            inverted: false,
+        };
+        drive.brake();
+        drive
+    }
+
+    /// Swap which input drives forward, for reversed motor leads.
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.inverted = inverted;
+    }
+
+    /// Run one loop step on the IPROPI reading `raw`. Call at the loop's rate.
+    pub fn on_tick(&mut self, cmd: &CurrentCommand, raw: u16) {
+        match self.current.step(cmd, raw) {
+            Some(duty) => self.drive(duty),
+            None => self.brake(),
+        }
+    }
+
+    pub fn current_loop(&mut self) -> &mut CurrentLoop {
+        &mut self.current
+    }
+
+    fn drive(&mut self, duty: f32) {
+        let duty = if self.inverted { -duty } else { duty };
+        let counts = (duty.abs().min(1.0) * self.pwm1.get_max_duty() as f32) as u16;
+        if duty >= 0.0 {
+            self.pwm1.set_duty(counts);
+            self.pwm2.set_duty(0);
+        } else {
+            self.pwm1.set_duty(0);
+            self.pwm2.set_duty(counts);
+        }
+        self.pwm1.enable();
+        self.pwm2.enable();
+    }
+
+    /// Both inputs high: low-side brake.
+    fn brake(&mut self) {
+        self.pwm1.set_duty(self.pwm1.get_max_duty());
+        self.pwm2.set_duty(self.pwm2.get_max_duty());
+        self.pwm1.enable();
+        self.pwm2.enable();
+    }
+
+    pub fn free(self) -> (Pwm1, Pwm2) {
+        (self.pwm1, self.pwm2)
+    }
+}
+
+/// Main-task side: position feedback and the current target.
+pub struct TorqueAxis<ReadPos> {
+    cmd: &'static CurrentCommand,
+    read_pos: ReadPos,
+    /// Current at full position-loop output.
+    max_amps: f32,
+}
+
+impl<ReadPos> TorqueAxis<ReadPos>
+where
+    ReadPos: FnMut() -> Option<f32>,
+{
+    /// `read_pos` returns the position in the axis unit; a position-loop output of ±1.0 asks for
+    /// ±`max_amps`.
+    pub fn new(cmd: &'static CurrentCommand, read_pos: ReadPos, max_amps: f32) -> Self {
+        cmd.disable();
+        Self {
+            cmd,
+            read_pos,
+            max_amps,
+        }
+    }
+
+    pub fn position(&mut self) -> Option<f32> {
+        (self.read_pos)()
+    }
+
+    /// Command `output` (-1.0..1.0) of the maximum current.
+    pub fn set_torque(&mut self, output: f32) {
+        self.cmd.set_target(output.clamp(-1.0, 1.0) * self.max_amps);
+    }
+
+    /// Stop regulating and brake.
+    pub fn brake(&mut self) {
+        self.cmd.disable();
+    }
+
+    /// Latest measured current in amps.
+    pub fn current(&self) -> f32 {
+        self.cmd.measured()
+    }
+
+    pub fn set_max_amps(&mut self, max_amps: f32) {
+        self.max_amps = max_amps;
+    }
+}
+
+/// Full duty of a [`TorqueInput`].
+pub const INPUT_MAX_DUTY: u16 = 10_000;
+
+/// Stand-ins for a bridge's IN1/IN2 PWM channels that command current instead of duty.
+///
+/// IN1's duty minus IN2's, as a fraction of [`INPUT_MAX_DUTY`], asks for that fraction of
+/// `max_amps`. Both inputs at full duty is a brake and disables the current loop. The driver's
+/// `enable` applies the pair, so the half-written pair between its two `set_duty` calls is never
+/// commanded.
+pub struct TorqueInputs {
+    cmd: &'static CurrentCommand,
+    in1: AtomicU16,
+    in2: AtomicU16,
+    max_amps: f32,
+}
+
+impl TorqueInputs {
+    pub const fn new(cmd: &'static CurrentCommand, max_amps: f32) -> Self {
+        Self {
+            cmd,
+            in1: AtomicU16::new(INPUT_MAX_DUTY),
+            in2: AtomicU16::new(INPUT_MAX_DUTY),
+            max_amps,
+        }
+    }
+
+    /// The IN1 and IN2 channels.
+    pub fn split(&'static self) -> (TorqueInput, TorqueInput) {
+        (
+            TorqueInput {
+                inputs: self,
+                in2: false,
+            },
+            TorqueInput {
+                inputs: self,
+                in2: true,
+            },
+        )
+    }
+
+    fn apply(&self) {
+        let in1 = self.in1.load(Ordering::Relaxed);
+        let in2 = self.in2.load(Ordering::Relaxed);
+        if in1 >= INPUT_MAX_DUTY && in2 >= INPUT_MAX_DUTY {
+            self.cmd.disable();
+        } else {
+            let output = (in1 as f32 - in2 as f32) / INPUT_MAX_DUTY as f32;
+            self.cmd.set_target(output * self.max_amps);
+        }
+    }
+}
+
+/// One input of a [`TorqueInputs`] pair.
+pub struct TorqueInput {
+    inputs: &'static TorqueInputs,
+    in2: bool,
+}
+
+impl TorqueInput {
+    fn duty(&self) -> &AtomicU16 {
+        if self.in2 {
+            &self.inputs.in2
+        } else {
+            &self.inputs.in1
+        }
+    }
+}
+
+impl _embedded_hal_PwmPin for TorqueInput {
+    type Duty = u16;
+
+    /// Brakes.
+    fn disable(&mut self) {
+        self.inputs.cmd.disable();
+    }
+
+    fn enable(&mut self) {
+        self.inputs.apply();
+    }
+
+    fn get_duty(&self) -> u16 {
+        self.duty().load(Ordering::Relaxed)
+    }
+
+    fn get_max_duty(&self) -> u16 {
+        INPUT_MAX_DUTY
+    }
+
+    fn set_duty(&mut self, duty: u16) {
+        self.duty()
+            .store(duty.min(INPUT_MAX_DUTY), Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! WS2812 addressable LED strip on TIM5 CH1 (PA0, AF2) with DMA1 stream 6.
+//!
+//! Each bit of the GRB stream is one 800 kHz PWM period whose duty encodes the bit. The frame is
+//! pre-encoded into a buffer of CCR1 values and DMA writes one value per timer update, so a strip
+//! refresh costs no CPU time beyond encoding. The buffer ends with [`RESET_SLOTS`] zero-duty periods
+//! that hold the line low long enough for the strip to latch.
+//!
+//! [`Pattern`] and [`render`] are a small animation layer for the tile edge lighting (height bar,
+//! fault blink, interaction chase).
+//!
+//! The buffer must live in memory DMA1 can reach (not DTCM). [`Ws2812::show`] cleans it from the
+//! data cache, which a buffer in the `.dma` section (see [`memory`](crate::hw::memory)) doesn't
+//! need.
+
+use stm32f7xx_hal::{
+    gpio::{gpioa, Alternate},
+    pac,
+};
+
+use crate::hw::memory;
+
+/// Strip bit rate.
+const BIT_HZ: u32 = 800_000;
+
+/// Zero-duty periods appended after the data (≥ 50 µs low latches the strip).
+pub const RESET_SLOTS: usize = 48;
+
+/// DMA1 channel that carries TIM5_UP on stream 6.
+const DMA_CHANNEL: u32 = 6;
+const DMA_STREAM: usize = 6;
+
+/// Buffer length needed for `leds` LEDs.
+pub const fn buffer_len(leds: usize) -> usize {
+    leds * 24 + RESET_SLOTS
+}
+
+/// 8-bit RGB color.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const OFF: Rgb = Rgb::new(0, 0, 0);
+    pub const RED: Rgb = Rgb::new(255, 0, 0);
+    pub const GREEN: Rgb = Rgb::new(0, 255, 0);
+    pub const BLUE: Rgb = Rgb::new(0, 0, 255);
+    pub const AMBER: Rgb = Rgb::new(255, 120, 0);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Scale by `level` (0 = off, 255 = unchanged).
+    pub fn scaled(self, level: u8) -> Self {
+        let s = |c: u8| ((c as u16 * level as u16) / 255) as u8;
+        Self::new(s(self.r), s(self.g), s(self.b))
+    }
+}
+
+/// WS2812 strip driver. Owns TIM5, DMA1 and PA0.
+pub struct Ws2812 {
+    tim: pac::TIM5,
+    dma: pac::DMA1,
+    pin: gpioa::PA0<Alternate<2>>,
+    buf: &'static mut [u16],
+    duty0: u16,
+    duty1: u16,
+}
+
+impl Ws2812 {
+    /// Configure TIM5 CH1 for 800 kHz PWM and DMA1 stream 6 to feed CCR1 on each update.
+    ///
+    /// `timer_hz` is the TIM5 kernel clock (APB1 timer clock). `buf` sets the strip length; size it
+    /// with [`buffer_len`].
+    pub fn new(
+        tim: pac::TIM5,
+        dma: pac::DMA1,
+        pin: gpioa::PA0<Alternate<2>>,
+        timer_hz: u32,
+        buf: &'static mut [u16],
+    ) -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.apb1enr.modify(|_, w| w.tim5en().set_bit());
+        rcc.ahb1enr.modify(|_, w| w.dma1en().set_bit());
+
+        // 1.25 µs period; a 0 bit is high for 0.4 µs (32%), a 1 bit for 0.8 µs (64%).
+        let period = timer_hz / BIT_HZ;
+        let duty0 = (period * 8 / 25) as u16;
+        let duty1 = (period * 16 / 25) as u16;
+
+        tim.cr1.modify(|_, w| w.cen().clear_bit());
+        tim.psc.write(|w| unsafe { w.bits(0) });
+        tim.arr.write(|w| unsafe { w.bits(period - 1) });
+        tim.ccr1.write(|w| unsafe { w.bits(0) });
+        // CH1 PWM mode 1 with preload, so each DMA write takes effect on the next period.
+        tim.ccmr1_output()
+            .modify(|_, w| unsafe { w.oc1m().bits(0b110).oc1pe().set_bit() });
+        tim.ccer.modify(|_, w| w.cc1e().set_bit());
+        tim.cr1.modify(|_, w| w.arpe().set_bit());
+        tim.egr.write(|w| w.ug().set_bit());
+        tim.dier.modify(|_, w| w.ude().set_bit());
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+        let st = &dma.st[DMA_STREAM];
+        st.cr.write(|w| unsafe { w.bits(0) });
+        while st.cr.read().en().bit_is_set() {}
+        st.par
+            .write(|w| unsafe { w.bits(tim.ccr1.as_ptr() as u32) });
+        // CHSEL, 16-bit memory and peripheral, memory increment, memory-to-peripheral.
+        st.cr.write(|w| unsafe {
+            w.bits((DMA_CHANNEL << 25) | (0b01 << 13) | (0b01 << 11) | (1 << 10) | (0b01 << 6))
+        });
+
+        buf.fill(0);
+
+        Self {
+            tim,
+            dma,
+            pin,
+            buf,
+            duty0,
+            duty1,
+        }
+    }
+
+    /// Number of LEDs the buffer holds.
+    pub fn len(&self) -> usize {
+        (self.buf.len().saturating_sub(RESET_SLOTS)) / 24
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// True while a frame is still being clocked out.
+    pub fn is_busy(&self) -> bool {
+        self.dma.st[DMA_STREAM].cr.read().en().bit_is_set()
+    }
+
+    /// Encode `colors` and start the DMA transfer. Returns `false` without touching the strip if
+    /// the previous frame is still being sent. LEDs past the end of `colors` are turned off.
+    pub fn show(&mut self, colors: &[Rgb]) -> bool {
+        if self.is_busy() {
+            return false;
+        }
+
+        let leds = self.len();
+        for i in 0..leds {
+            let c = colors.get(i).copied().unwrap_or(Rgb::OFF);
+            let word = ((c.g as u32) << 16) | ((c.r as u32) << 8) | c.b as u32;
+            let slots = &mut self.buf[i * 24..(i + 1) * 24];
+            for (bit, slot) in slots.iter_mut().enumerate() {
+                *slot = if word & (1 << (23 - bit)) != 0 {
+                    self.duty1
+                } else {
+                    self.duty0
+                };
+            }
+        }
+        self.buf[leds * 24..].fill(0);
+        memory::before_dma_read(self.buf);
+
+        // Clear stream 6 flags (FEIF, DMEIF, TEIF, HTIF, TCIF) before re-enabling.
+        self.dma.hifcr.write(|w| unsafe { w.bits(0b11_1101 << 16) });
+        let st = &self.dma.st[DMA_STREAM];
+        st.m0ar
+            .write(|w| unsafe { w.bits(self.buf.as_ptr() as u32) });
+        st.ndtr.write(|w| unsafe { w.bits(self.buf.len() as u32) });
+        cortex_m::asm::dsb();
+        st.cr.modify(|_, w| w.en().set_bit());
+        true
+    }
+
+    /// Stop the timer and DMA and release the peripherals.
+    pub fn free(self) -> (pac::TIM5, pac::DMA1, gpioa::PA0<Alternate<2>>) {
+        self.dma.st[DMA_STREAM].cr.modify(|_, w| w.en().clear_bit());
+        self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+        (self.tim, self.dma, self.pin)
+    }
+}
+
+/// Edge lighting pattern.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pattern {
+    Off,
+    Solid(Rgb),
+    /// Fade in and out with a 2 s period.
+    Breathe(Rgb),
+    /// Light the first `fraction` of the strip, e.g. platform height over stroke.
+    Level {
+        color: Rgb,
+        fraction: f32,
+    },
+    /// Blink at 2 Hz, for faults.
+    Blink(Rgb),
+    /// A three-LED comet running around the strip, for interaction.
+    Chase(Rgb),
+}
+
+/// Render `pattern` at time `now_ms` into `out`.
+pub fn render(pattern: Pattern, now_ms: u64, out: &mut [Rgb]) {
+    let n = out.len();
+    match pattern {
+        Pattern::Off => out.fill(Rgb::OFF),
+        Pattern::Solid(c) => out.fill(c),
+        Pattern::Breathe(c) => {
+            // Triangle wave 0..255..0 over 2 s.
+            let phase = (now_ms % 2000) as u32;
+            let ramp = if phase < 1000 { phase } else { 2000 - phase };
+            let level = ramp * 255 / 1000;
+            out.fill(c.scaled(level as u8));
+        }
+        Pattern::Level { color, fraction } => {
+            let lit = (fraction.clamp(0.0, 1.0) * n as f32 + 0.5) as usize;
+            for (i, px) in out.iter_mut().enumerate() {
+                *px = if i < lit { color } else { Rgb::OFF };
+            }
+        }
+        Pattern::Blink(c) => out.fill(if now_ms % 500 < 250 { c } else { Rgb::OFF }),
+        Pattern::Chase(c) => {
+            if n == 0 {
+                return;
+            }
+            let head = (now_ms / 40) as usize % n;
+            for (i, px) in out.iter_mut().enumerate() {
+                let behind = (head + n - i) % n;
+                *px = match behind {
+                    0 => c,
+                    1 => c.scaled(96),
+                    2 => c.scaled(24),
+                    _ => Rgb::OFF,
+                };
+            }
+        }
+    }
+}
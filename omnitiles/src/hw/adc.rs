@@ -15,6 +15,10 @@ use core::cell::RefCell;
 
 use stm32f7xx_hal::pac;
 
+/// ADC1 channel of the internal temperature sensor, see
+/// [`McuTemperature`](crate::sensors::McuTemperature). Shared with VBAT, which stays off.
+pub const TEMP_SENSOR_CHANNEL: u8 = 18;
+
 /// Generic ADC wrapper over a PAC ADCx peripheral.
 pub struct Adc<ADC> {
     adc: ADC,
@@ -136,6 +140,9 @@ fn read_channel(adc: &pac::adc1::RegisterBlock, channel: u8) -> u16 {
             13 => w.smp13().bits(0b111),
             14 => w.smp14().bits(0b111),
             15 => w.smp15().bits(0b111),
+            16 => w.smp16().bits(0b111),
+            17 => w.smp17().bits(0b111),
+            18 => w.smp18().bits(0b111),
             _ => w,
         });
     }
@@ -172,6 +179,16 @@ impl Adc<pac::ADC1> {
     pub fn read(&self, channel: u8) -> u16 {
         read_channel(&self.adc, channel)
     }
+
+    /// Power the internal temperature sensor on [`TEMP_SENSOR_CHANNEL`]. It needs about 10 µs
+    /// to start. Creating ADC2 or ADC3 rewrites the common control register and turns it off
+    /// again, so call this once every ADC exists.
+    pub fn enable_temperature_sensor(&self) {
+        let common = unsafe { &*pac::ADC_COMMON::ptr() };
+        common
+            .ccr
+            .modify(|_, w| w.vbate().clear_bit().tsvrefe().set_bit());
+    }
 }
 
 impl Adc<pac::ADC2> {
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Free-running ADC3 scan into a DMA buffer.
+//!
+//! [`Adc`](super::Adc) reads block for 16 averaged conversions, far too slow for an interrupt that
+//! runs every few hundred microseconds. [`AdcDma`] instead converts a fixed list of ADC3 channels
+//! over and over, and DMA2 stream 0 copies each result into a circular buffer. The buffer always
+//! holds the latest sample of every channel, a few microseconds old at most, and reading it costs
+//! a memory load.
+//!
+//! The data cache is on and would hide the DMA's writes, and the buffer is read from interrupts
+//! too often for cache maintenance, so it must be in DTCM or the `.dma` section (see
+//! [`memory`](super::memory)).
+//!
+//! ADC1 and ADC2 stay with the blocking driver. Create [`AdcDma`] after
+//! [`Adc::adc1`](super::Adc::adc1): that one pulses the common ADC reset, which would stop the
+//! scan.
+
+use stm32f7xx_hal::pac;
+
+use super::memory;
+
+const DMA_STREAM: usize = 0;
+/// ADC3 request on DMA2 stream 0.
+const DMA_CHANNEL: u32 = 2;
+/// Sample time per conversion: 56 ADC clocks.
+const SAMPLE_TIME: u32 = 0b011;
+
+/// ADC3 scanning `N` channels continuously into a DMA buffer. Owns ADC3 and DMA2.
+pub struct AdcDma<const N: usize> {
+    adc: pac::ADC3,
+    dma: pac::DMA2,
+    buf: &'static mut [u16; N],
+}
+
+impl<const N: usize> AdcDma<N> {
+    /// Start scanning `channels` (at most 16), in order, into `buf`.
+    pub fn adc3(
+        adc: pac::ADC3,
+        dma: pac::DMA2,
+        channels: [u8; N],
+        buf: &'static mut [u16; N],
+    ) -> Self {
+        assert!(N > 0 && N <= 16);
+        assert!(memory::is_uncached(buf.as_ptr() as usize, N * 2));
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.apb2enr.modify(|_, w| w.adc3en().set_bit());
+        rcc.ahb1enr.modify(|_, w| w.dma2en().set_bit());
+
+        let common = unsafe { &*pac::ADC_COMMON::ptr() };
+        common.ccr.modify(|_, w| w.adcpre().div4());
+
+        adc.cr2.write(|w| unsafe { w.bits(0) });
+        // 12-bit, scan mode.
+        adc.cr1.write(|w| w.res().bits(0b00).scan().set_bit());
+        let mut smpr1 = 0u32;
+        let mut smpr2 = 0u32;
+        let mut sqr = [0u32; 3];
+        for (i, &ch) in channels.iter().enumerate() {
+            let ch = (ch & 0x1F) as u32;
+            if ch <= 9 {
+                smpr2 |= SAMPLE_TIME << (3 * ch);
+            } else if ch <= 18 {
+                smpr1 |= SAMPLE_TIME << (3 * (ch - 10));
+            }
+            // SQR3 holds ranks 1–6, SQR2 7–12, SQR1 13–16.
+            sqr[i / 6] |= ch << (5 * (i % 6));
+        }
+        adc.smpr1.write(|w| unsafe { w.bits(smpr1) });
+        adc.smpr2.write(|w| unsafe { w.bits(smpr2) });
+        adc.sqr3.write(|w| unsafe { w.bits(sqr[0]) });
+        adc.sqr2.write(|w| unsafe { w.bits(sqr[1]) });
+        adc.sqr1
+            .write(|w| unsafe { w.bits(sqr[2] | ((N as u32 - 1) << 20)) });
+        adc.sr.write(|w| unsafe { w.bits(0) });
+
+        buf.fill(0);
+        let st = &dma.st[DMA_STREAM];
+        st.cr.write(|w| unsafe { w.bits(0) });
+        while st.cr.read().en().bit_is_set() {}
+        // Clear stream 0 flags (FEIF, DMEIF, TEIF, HTIF, TCIF).
+        dma.lifcr.write(|w| unsafe { w.bits(0b11_1101) });
+        st.par.write(|w| unsafe { w.bits(adc.dr.as_ptr() as u32) });
+        st.m0ar.write(|w| unsafe { w.bits(buf.as_ptr() as u32) });
+        st.ndtr.write(|w| unsafe { w.bits(N as u32) });
+        // CHSEL, 16-bit memory and peripheral, memory increment, circular, peripheral-to-memory.
+        st.cr.write(|w| unsafe {
+            w.bits((DMA_CHANNEL << 25) | (0b01 << 13) | (0b01 << 11) | (1 << 10) | (1 << 8))
+        });
+        cortex_m::asm::dsb();
+        st.cr.modify(|_, w| w.en().set_bit());
+
+        // Continuous conversion, DMA requests for as long as the scan runs.
+        adc.cr2.write(|w| {
+            w.adon()
+                .set_bit()
+                .cont()
+                .set_bit()
+                .dma()
+                .set_bit()
+                .dds()
+                .set_bit()
+        });
+        adc.cr2.modify(|_, w| w.swstart().set_bit());
+
+        Self { adc, dma, buf }
+    }
+
+    /// Latest sample of the `index`-th scanned channel.
+    #[inline]
+    pub fn latest(&self, index: usize) -> u16 {
+        // The DMA writes behind the compiler's back.
+        unsafe { core::ptr::read_volatile(&self.buf[index]) }
+    }
+
+    /// Latest sample of every channel.
+    pub fn read_all(&self) -> [u16; N] {
+        let mut out = [0u16; N];
+        for (i, v) in out.iter_mut().enumerate() {
+            *v = self.latest(i);
+        }
+        out
+    }
+
+    /// True if the ADC overran the DMA and the scan stopped. Restart by building a new `AdcDma`.
+    pub fn is_stalled(&self) -> bool {
+        self.adc.sr.read().ovr().bit_is_set()
+    }
+
+    /// Stop the scan and release the peripherals.
+    pub fn free(self) -> (pac::ADC3, pac::DMA2, &'static mut [u16; N]) {
+        self.adc.cr2.write(|w| unsafe { w.bits(0) });
+        self.dma.st[DMA_STREAM].cr.modify(|_, w| w.en().clear_bit());
+        (self.adc, self.dma, self.buf)
+    }
+}
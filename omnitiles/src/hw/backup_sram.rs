@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! 4 KB battery-backed SRAM in the backup domain.
+//!
+//! The backup SRAM keeps its contents across every reset and, with the backup regulator on and
+//! VBAT present, across power loss. It is much faster than flash and has no erase cycle limit,
+//! so it suits state that changes often and is only useful for a warm restart. Contents are
+//! random after the first power-up, so callers must validate whatever they read.
+//!
+//! Enabling the region needs the RCC clock gates after the HAL has taken `RCC`, so this module
+//! accesses the RCC registers directly; it only sets the PWR and BKPSRAM enable bits.
+
+use core::ptr;
+
+use stm32f7xx_hal::pac;
+
+/// Base address of the backup SRAM.
+const BASE: usize = 0x4002_4000;
+/// Size of the backup SRAM in bytes.
+pub const LEN: usize = 4 * 1024;
+
+/// Handle to the enabled backup SRAM.
+pub struct BackupSram {
+    pwr: pac::PWR,
+}
+
+impl BackupSram {
+    /// Clock the backup SRAM, lift backup-domain write protection and turn on the backup
+    /// regulator so the contents survive on VBAT.
+    pub fn new(pwr: pac::PWR) -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.apb1enr.modify(|_, w| w.pwren().set_bit());
+        rcc.ahb1enr.modify(|_, w| w.bkpsramen().set_bit());
+
+        pwr.cr1.modify(|_, w| w.dbp().set_bit());
+        pwr.csr1.modify(|_, w| w.bre().set_bit());
+        // The regulator only reports ready when VBAT is present; without a battery the SRAM still
+        // survives resets, so don't wait forever.
+        for _ in 0..10_000 {
+            if pwr.csr1.read().brr().bit_is_set() {
+                break;
+            }
+        }
+
+        Self { pwr }
+    }
+
+    pub fn free(self) -> pac::PWR {
+        self.pwr
+    }
+
+    /// True if the backup regulator is running, i.e. contents will survive power loss.
+    pub fn is_battery_backed(&self) -> bool {
+        self.pwr.csr1.read().brr().bit_is_set()
+    }
+
+    /// Copy `buf.len()` bytes starting at `offset` out of the backup SRAM.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn read(&self, offset: usize, buf: &mut [u8]) {
+        assert!(offset + buf.len() <= LEN);
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = unsafe { ptr::read_volatile((BASE + offset + i) as *const u8) };
+        }
+    }
+
+    /// Copy `data` into the backup SRAM starting at `offset`.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn write(&mut self, offset: usize, data: &[u8]) {
+        assert!(offset + data.len() <= LEN);
+        for (i, &b) in data.iter().enumerate() {
+            unsafe { ptr::write_volatile((BASE + offset + i) as *mut u8, b) };
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Push-button input with debounce and press events.
+//!
+//! [`Button`] wraps a GPIO input (the B1 user button on the Nucleo, or a service button on the
+//! tile) and turns raw levels into [`ButtonEvent`]s when polled from the main loop. A level only
+//! counts once it has been stable for [`DEBOUNCE_MS`]. Holding the button for [`LONG_PRESS_MS`]
+//! reports [`ButtonEvent::Long`] right away, without waiting for the release, so the user can tell
+//! when to let go; a release before that reports [`ButtonEvent::Short`].
+//!
+//! Polling is enough to catch any press a person can make. [`Button::listen`] additionally routes
+//! the pin to its EXTI line on both edges, so a press wakes a core sleeping in `wfi` and shows up
+//! in [`Button::take_edge`]. Unmasking the EXTI interrupt in the NVIC is left to the caller.
+//!
+//! What a press does is up to the caller. On `pins_v2` the service button comes with the `button`
+//! feature, and the tile firmware hands presses to the dispatcher as local commands: a short press
+//! toggles demo mode, a long one brakes the lift. Held through boot, it provisions the tile.
+
+use stm32f7xx_hal::gpio::{self, Input};
+
+use super::exti;
+use super::led::ActiveLevel;
+
+/// A level must hold this long to count.
+pub const DEBOUNCE_MS: u64 = 20;
+/// Hold time that makes a press long.
+pub const LONG_PRESS_MS: u64 = 1500;
+
+/// A completed press.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ButtonEvent {
+    /// Pressed and released within [`LONG_PRESS_MS`].
+    Short,
+    /// Held for [`LONG_PRESS_MS`]; reported while still held.
+    Long,
+}
+
+/// Debounced press tracking, independent of the pin.
+#[derive(Copy, Clone, Debug)]
+pub struct Debounce {
+    /// Debounced state.
+    pressed: bool,
+    /// Raw state seen last and when it started.
+    raw: bool,
+    raw_since_ms: u64,
+    /// When the debounced press started.
+    pressed_at_ms: u64,
+    /// A long press was already reported for the current press.
+    long_sent: bool,
+}
+
+impl Debounce {
+    pub const fn new() -> Self {
+        Self {
+            pressed: false,
+            raw: false,
+            raw_since_ms: 0,
+            pressed_at_ms: 0,
+            long_sent: false,
+        }
+    }
+
+    /// Feed a raw sample (`true` = pressed) taken at `now_ms`.
+    pub fn update(&mut self, raw: bool, now_ms: u64) -> Option<ButtonEvent> {
+        if raw != self.raw {
+            self.raw = raw;
+            self.raw_since_ms = now_ms;
+        }
+        if self.raw != self.pressed && now_ms - self.raw_since_ms >= DEBOUNCE_MS {
+            self.pressed = self.raw;
+            if self.pressed {
+                self.pressed_at_ms = self.raw_since_ms;
+                self.long_sent = false;
+            } else if !self.long_sent {
+                return Some(ButtonEvent::Short);
+            }
+        }
+        if self.pressed && !self.long_sent && now_ms - self.pressed_at_ms >= LONG_PRESS_MS {
+            self.long_sent = true;
+            return Some(ButtonEvent::Long);
+        }
+        None
+    }
+
+    /// Debounced state.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+}
+
+impl Default for Debounce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A button on an input pin.
+pub struct Button<const P: char, const N: u8, MODE> {
+    pin: gpio::Pin<P, N, Input<MODE>>,
+    active: ActiveLevel,
+    debounce: Debounce,
+}
+
+impl<const P: char, const N: u8, MODE> Button<P, N, MODE> {
+    /// Wrap an input pin; `active` is the level while pressed.
+    pub fn new(pin: gpio::Pin<P, N, Input<MODE>>, active: ActiveLevel) -> Self {
+        Self {
+            pin,
+            active,
+            debounce: Debounce::new(),
+        }
+    }
+
+    /// Raw, undebounced state.
+    pub fn is_pressed_raw(&self) -> bool {
+        match self.active {
+            ActiveLevel::High => self.pin.is_high(),
+            ActiveLevel::Low => self.pin.is_low(),
+        }
+    }
+
+    /// Debounced state.
+    pub fn is_pressed(&self) -> bool {
+        self.debounce.is_pressed()
+    }
+
+    /// Sample the pin. Call at least every [`DEBOUNCE_MS`].
+    pub fn poll(&mut self, now_ms: u64) -> Option<ButtonEvent> {
+        let raw = self.is_pressed_raw();
+        self.debounce.update(raw, now_ms)
+    }
+
+    /// Route the pin to EXTI line `N` and unmask it on both edges.
+    pub fn listen(&mut self) {
+        exti::listen(P, N);
+    }
+
+    /// True if an edge arrived since the last call; clears the EXTI pending bit.
+    pub fn take_edge(&mut self) -> bool {
+        exti::take_pending(N)
+    }
+
+    pub fn free(self) -> gpio::Pin<P, N, Input<MODE>> {
+        self.pin
+    }
+}
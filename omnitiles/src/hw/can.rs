@@ -3,15 +3,139 @@
 
 //! Controller Area Network (CAN) abstraction layer.
 //!
-//! - `CanBus` wraps a HAL `can::Can` instance in `bxcan::Can`.
+//! - `CanFrame` and the `CanInterface` trait are what protocol code and CAN drivers use, so they
+//!   don't depend on `bxcan` and can run on other controllers (e.g. an MCP2515 on SPI for an
+//!   extra bus).
+//! - `CanBus` wraps a HAL `can::Can` instance in `bxcan::Can` and implements `CanInterface`.
 //! - Provides simple helpers for sending and receiving frames.
+//! - `RxQueue` buffers received frames in software so one consumer doesn't drop another's frames.
+//! - `DualCan` runs CAN1 and CAN2 together, with CAN1 acting as the shared filter owner.
 
 use core::convert::Infallible;
 use nb::block;
 
-use bxcan::{self, Data, Frame, OverrunError, StandardId, TransmitStatus};
+use bxcan::{self, Data, ExtendedId, Frame, Id, OverrunError, StandardId, TransmitStatus};
 use stm32f7xx_hal::can as hal_can;
 
+/// Identifier of a [`CanFrame`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CanId {
+    /// 11-bit standard identifier.
+    Standard(u16),
+    /// 29-bit extended identifier.
+    Extended(u32),
+}
+
+/// CAN data frame of up to 8 bytes, independent of the controller that sends or receives it.
+///
+/// Remote frames are not represented: none of the protocols use them, and the slcan bridge,
+/// which forwards them, works on `bxcan` frames directly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CanFrame {
+    id: CanId,
+    len: u8,
+    data: [u8; 8],
+}
+
+impl CanFrame {
+    /// Data frame, or `None` if `id` is out of range or `data` is longer than 8 bytes.
+    pub fn new(id: CanId, data: &[u8]) -> Option<Self> {
+        let id_ok = match id {
+            CanId::Standard(raw) => raw <= 0x7FF,
+            CanId::Extended(raw) => raw <= 0x1FFF_FFFF,
+        };
+        if !id_ok || data.len() > 8 {
+            return None;
+        }
+        let mut buf = [0; 8];
+        buf[..data.len()].copy_from_slice(data);
+        Some(Self {
+            id,
+            len: data.len() as u8,
+            data: buf,
+        })
+    }
+
+    /// Data frame with a standard ID, see [`new`](Self::new).
+    pub fn standard(id: u16, data: &[u8]) -> Option<Self> {
+        Self::new(CanId::Standard(id), data)
+    }
+
+    #[inline]
+    pub fn id(&self) -> CanId {
+        self.id
+    }
+
+    /// The standard ID, or `None` for an extended frame.
+    #[inline]
+    pub fn standard_id(&self) -> Option<u16> {
+        match self.id {
+            CanId::Standard(id) => Some(id),
+            CanId::Extended(_) => None,
+        }
+    }
+
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+}
+
+impl From<&CanFrame> for Frame {
+    fn from(frame: &CanFrame) -> Self {
+        // Both were range checked by `CanFrame::new`.
+        let id: Id = match frame.id {
+            CanId::Standard(raw) => StandardId::new(raw).unwrap().into(),
+            CanId::Extended(raw) => ExtendedId::new(raw).unwrap().into(),
+        };
+        Frame::new_data(id, Data::new(frame.data()).unwrap())
+    }
+}
+
+impl TryFrom<&Frame> for CanFrame {
+    /// The frame is a remote frame.
+    type Error = ();
+
+    fn try_from(frame: &Frame) -> Result<Self, ()> {
+        let id = match frame.id() {
+            Id::Standard(id) => CanId::Standard(id.as_raw()),
+            Id::Extended(id) => CanId::Extended(id.as_raw()),
+        };
+        CanFrame::new(id, frame.data().ok_or(())?).ok_or(())
+    }
+}
+
+/// Error from a [`CanInterface`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CanError {
+    /// No transmit slot is free.
+    TxFull,
+    /// The receive buffer overflowed and frames were lost.
+    Overrun,
+}
+
+/// A CAN controller as seen by protocols and drivers: send and receive data frames.
+///
+/// Bit timing, filters and operating modes are set up on the concrete controller.
+pub trait CanInterface {
+    /// Queue `frame` for transmission without waiting. Fails with [`CanError::TxFull`] if the
+    /// controller has no room for it.
+    fn try_send(&mut self, frame: &CanFrame) -> Result<(), CanError>;
+
+    /// Next received data frame, or `None` if nothing is pending.
+    fn try_recv(&mut self) -> Option<Result<CanFrame, CanError>>;
+
+    /// Queue `frame`, waiting for a free transmit slot.
+    fn send(&mut self, frame: &CanFrame) -> Result<(), CanError> {
+        loop {
+            match self.try_send(frame) {
+                Err(CanError::TxFull) => continue,
+                result => return result,
+            }
+        }
+    }
+}
+
 /// Wrapper around a bxcan CAN instance built from a HAL CAN peripheral.
 pub struct CanBus<I>
 where
@@ -46,6 +170,11 @@ where
         &mut self.can
     }
 
+    /// Change the bit timing (CAN_BTR value). Briefly takes the peripheral off the bus.
+    pub fn set_bit_timing(&mut self, btr: u32) {
+        self.can.modify_config().set_bit_timing(btr).enable();
+    }
+
     /// Consume the wrapper and get back the underlying HAL CAN instance.
     pub fn free(self) -> hal_can::Can<I> {
         self.can.free()
@@ -77,6 +206,145 @@ where
     pub fn receive(&mut self) -> Result<Frame, OverrunError> {
         block!(self.can.receive())
     }
+
+    /// Non-blocking receive. Returns `None` if no frame is pending.
+    pub fn try_receive(&mut self) -> Option<Result<Frame, OverrunError>> {
+        match self.can.receive() {
+            Ok(frame) => Some(Ok(frame)),
+            Err(nb::Error::WouldBlock) => None,
+            Err(nb::Error::Other(e)) => Some(Err(e)),
+        }
+    }
+
+    /// Move every pending hardware frame into `queue`. Returns the number of frames moved.
+    ///
+    /// Hardware FIFO overruns are counted in the queue's [`overruns`](RxQueue::overruns).
+    pub fn poll_into<const N: usize>(&mut self, queue: &mut RxQueue<N>) -> usize {
+        let mut moved = 0;
+        while let Some(result) = self.try_receive() {
+            match result {
+                Ok(frame) => {
+                    queue.push(frame);
+                    moved += 1;
+                }
+                Err(_) => queue.overruns = queue.overruns.wrapping_add(1),
+            }
+        }
+        moved
+    }
+}
+
+/// A frame with a higher priority ID may displace a lower priority one that is still pending in a
+/// mailbox; that frame is dropped, as with [`CanBus::transmit_frame`]. Remote frames are skipped
+/// on receive.
+impl<I> CanInterface for CanBus<I>
+where
+    hal_can::Can<I>: bxcan::Instance,
+{
+    fn try_send(&mut self, frame: &CanFrame) -> Result<(), CanError> {
+        match self.can.transmit(&Frame::from(frame)) {
+            Ok(_) => Ok(()),
+            Err(nb::Error::WouldBlock) => Err(CanError::TxFull),
+            Err(nb::Error::Other(never)) => match never {},
+        }
+    }
+
+    fn try_recv(&mut self) -> Option<Result<CanFrame, CanError>> {
+        loop {
+            match self.try_receive()? {
+                Ok(frame) => {
+                    if let Ok(frame) = CanFrame::try_from(&frame) {
+                        return Some(Ok(frame));
+                    }
+                }
+                Err(_) => return Some(Err(CanError::Overrun)),
+            }
+        }
+    }
+}
+
+/// Software receive FIFO for one CAN bus. When full, the oldest frame is dropped.
+pub struct RxQueue<const N: usize> {
+    buf: [Option<Frame>; N],
+    head: usize,
+    len: usize,
+    /// Frames dropped because the queue was full.
+    pub dropped: u32,
+    /// Hardware FIFO overruns seen while draining into this queue.
+    pub overruns: u32,
+}
+
+impl<const N: usize> RxQueue<N> {
+    const EMPTY: Option<Frame> = None;
+
+    pub const fn new() -> Self {
+        Self {
+            buf: [Self::EMPTY; N],
+            head: 0,
+            len: 0,
+            dropped: 0,
+            overruns: 0,
+        }
+    }
+
+    /// Append a frame, dropping the oldest one if the queue is full.
+    pub fn push(&mut self, frame: Frame) {
+        if N == 0 {
+            self.dropped = self.dropped.wrapping_add(1);
+            return;
+        }
+        if self.len == N {
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+            self.dropped = self.dropped.wrapping_add(1);
+        }
+        self.buf[(self.head + self.len) % N] = Some(frame);
+        self.len += 1;
+    }
+
+    /// Remove and return the oldest frame.
+    pub fn pop(&mut self) -> Option<Frame> {
+        if self.len == 0 {
+            return None;
+        }
+        let frame = self.buf[self.head].take();
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        frame
+    }
+
+    /// Remove and return the oldest frame matching `pred`, keeping the others in order.
+    pub fn pop_matching<F: FnMut(&Frame) -> bool>(&mut self, mut pred: F) -> Option<Frame> {
+        let pos = (0..self.len).find(|&i| {
+            self.buf[(self.head + i) % N]
+                .as_ref()
+                .map_or(false, &mut pred)
+        })?;
+        let frame = self.buf[(self.head + pos) % N].take();
+        // Close the gap by shifting the younger frames down by one.
+        for i in pos..self.len - 1 {
+            let next = self.buf[(self.head + i + 1) % N].take();
+            self.buf[(self.head + i) % N] = next;
+        }
+        self.len -= 1;
+        frame
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<const N: usize> Default for RxQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Extra helpers for CAN instances that own filters (e.g., CAN1 on STM32F7).
@@ -129,3 +397,66 @@ where
         regs.fmr.modify(|_, w| w.finit().clear_bit());
     }
 }
+
+/// CAN1 and CAN2 running concurrently.
+///
+/// On the STM32F7 the filter banks belong to CAN1 and CAN2 is a slave that can only run while CAN1
+/// is enabled. `DualCan` keeps both alive together: CAN1 is the inter-tile backbone, CAN2 the motor
+/// bus. Each bus has its own software [`RxQueue`]; call [`poll`](Self::poll) regularly (from the
+/// main loop or an RX interrupt) to move frames out of the hardware FIFOs.
+pub struct DualCan<I1, I2, const N: usize>
+where
+    hal_can::Can<I1>: bxcan::Instance + bxcan::FilterOwner,
+    hal_can::Can<I2>: bxcan::Instance,
+{
+    pub backbone: CanBus<I1>,
+    pub motor: CanBus<I2>,
+    pub backbone_rx: RxQueue<N>,
+    pub motor_rx: RxQueue<N>,
+}
+
+impl<I1, I2, const N: usize> DualCan<I1, I2, N>
+where
+    hal_can::Can<I1>: bxcan::Instance + bxcan::FilterOwner,
+    hal_can::Can<I2>: bxcan::Instance,
+{
+    /// Enable CAN1, split the filter banks between both buses, then enable CAN2.
+    ///
+    /// * `btr1` / `btr2` – CAN_BTR values for the backbone and motor bus.
+    pub fn new(can1: hal_can::Can<I1>, mut can2: hal_can::Can<I2>, btr1: u32, btr2: u32) -> Self {
+        let mut backbone = CanBus::new(can1, btr1, false, false);
+        backbone.configure_accept_all_filters_for_dual_can(&mut can2);
+        let motor = CanBus::new(can2, btr2, false, false);
+        Self {
+            backbone,
+            motor,
+            backbone_rx: RxQueue::new(),
+            motor_rx: RxQueue::new(),
+        }
+    }
+
+    /// Drain both hardware FIFOs into their software queues.
+    pub fn poll(&mut self) {
+        self.backbone.poll_into(&mut self.backbone_rx);
+        self.motor.poll_into(&mut self.motor_rx);
+    }
+
+    /// Next queued backbone frame.
+    pub fn recv_backbone(&mut self) -> Option<Frame> {
+        self.poll();
+        self.backbone_rx.pop()
+    }
+
+    /// Next queued motor bus frame.
+    pub fn recv_motor(&mut self) -> Option<Frame> {
+        self.poll();
+        self.motor_rx.pop()
+    }
+
+    /// Release both peripherals. CAN2 is released first since it depends on CAN1.
+    pub fn free(self) -> (hal_can::Can<I1>, hal_can::Can<I2>) {
+        let can2 = self.motor.free();
+        let can1 = self.backbone.free();
+        (can1, can2)
+    }
+}
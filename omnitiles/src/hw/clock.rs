@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Monotonic microsecond clock built on the DWT cycle counter.
+//!
+//! The 32-bit cycle counter wraps every ~20 s at 216 MHz. [`MonoClock`] extends it to 64 bits in
+//! software, so [`now_us`](MonoClock::now_us) must be called at least once per wrap period (the
+//! main loop does this every iteration). The cycle counter must already be enabled.
+
+use cortex_m::peripheral::DWT;
+
+/// 64-bit monotonic clock derived from `DWT::cycle_count()`.
+pub struct MonoClock {
+    cycles_per_us: u32,
+    last_cycles: u32,
+    high: u32,
+}
+
+impl MonoClock {
+    /// Create a clock for a core running at `sysclk_hz`.
+    pub fn new(sysclk_hz: u32) -> Self {
+        Self {
+            cycles_per_us: (sysclk_hz / 1_000_000).max(1),
+            last_cycles: DWT::cycle_count(),
+            high: 0,
+        }
+    }
+
+    /// Extended 64-bit cycle count since creation.
+    pub fn now_cycles(&mut self) -> u64 {
+        let now = DWT::cycle_count();
+        if now < self.last_cycles {
+            self.high = self.high.wrapping_add(1);
+        }
+        self.last_cycles = now;
+        ((self.high as u64) << 32) | now as u64
+    }
+
+    /// Microseconds since boot (since the cycle counter was started).
+    #[inline]
+    pub fn now_us(&mut self) -> u64 {
+        self.now_cycles() / self.cycles_per_us as u64
+    }
+
+    /// Milliseconds since boot.
+    #[inline]
+    pub fn now_ms(&mut self) -> u64 {
+        self.now_us() / 1000
+    }
+}
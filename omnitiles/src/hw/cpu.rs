@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Cortex-M7 core setup: FPU and caches.
+//!
+//! [`init`] runs first thing in `main`, before any peripheral setup. It turns on the FPU with
+//! automatic lazy stacking and the instruction and data caches, which together roughly double the
+//! speed of the control math.
+//!
+//! ## Floats in interrupt handlers
+//!
+//! The control loops move into timer interrupts (see
+//! [`current_loop`](crate::control::current_loop)), so handlers use the FPU too. With automatic
+//! lazy stacking the hardware takes care of the FP context:
+//!
+//! - Exception entry reserves room for S0–S15 and FPSCR but only stores them when the handler
+//!   executes its first FP instruction. Handlers without floats cost nothing extra; the first
+//!   float in a handler costs the 17-word store, once per entry. The compiler saves S16–S31
+//!   itself if the handler uses them.
+//! - Each nested frame with FP context takes 18 more words of stack. Size the stack for the
+//!   deepest nesting of float-using handlers, not just the main task.
+//! - Floats shared between a handler and the main task go through atomics holding `f32` bits
+//!   (as [`CurrentCommand`](crate::control::current_loop::CurrentCommand) does) or a critical
+//!   section. A single `f32` store is atomic, a struct of them is not.
+//! - Don't write FPSCR (rounding mode, flush-to-zero) from a handler; the change is undone on
+//!   return, and one from the main task leaks into handlers that interrupt it.
+//! - Stay in `f32`. The target is `thumbv7em-none-eabihf`, which uses only the single-precision
+//!   unit, so `f64` is software emulation and far too slow for a handler.
+//!
+//! ## Data cache and DMA
+//!
+//! The data cache sits between the core and SRAM; DMA does not go through it. Where DMA buffers
+//! go so that this doesn't matter is laid out in [`memory`]. The internal flash is cached as
+//! well: [`Flash`](super::Flash) invalidates what it erases or programs, see
+//! [`invalidate_dcache_range`].
+
+use cortex_m::asm;
+use cortex_m::peripheral::{CBP, CPUID, FPU, MPU, SCB};
+
+use super::memory;
+
+/// `FPCCR.ASPEN`: save the FP context automatically on exception entry.
+const FPCCR_ASPEN: u32 = 1 << 31;
+/// `FPCCR.LSPEN`: lazy stacking, only store the reserved FP context once it is used.
+const FPCCR_LSPEN: u32 = 1 << 30;
+
+/// Enable the FPU with automatic lazy stacking, the MPU regions of [`memory`] and the I/D caches.
+/// See the module docs.
+pub fn init(scb: &mut SCB, cpuid: &mut CPUID, fpu: &mut FPU, mpu: &mut MPU) {
+    // The reset handler already grants access on a hard-float target; doing it again keeps this
+    // correct on its own. Lazy stacking is the reset default, but the ISR rules above rely on it.
+    scb.enable_fpu();
+    // SAFETY: only changes how the FP context is stacked on exception entry; no exception with
+    // FP context is active this early.
+    unsafe { fpu.fpccr.modify(|r| r | FPCCR_ASPEN | FPCCR_LSPEN) };
+    asm::dsb();
+    asm::isb();
+
+    memory::configure_mpu(mpu);
+    scb.enable_icache();
+    scb.enable_dcache(cpuid);
+}
+
+/// Drop the data cache lines covering `len` bytes at `addr`, so the next read comes from memory.
+///
+/// Only for memory that the CPU does not write through the cache in the meantime (flash after an
+/// erase, a buffer DMA has just filled): dirty lines in the range are discarded, not written back.
+pub fn invalidate_dcache_range(addr: usize, len: usize) {
+    // SAFETY: DCIMVAC is a write-only maintenance register; invalidating lines nobody has
+    // written since they were filled loses nothing.
+    let cbp = unsafe { &*CBP::PTR };
+    asm::dsb();
+    let mut line = addr & !(memory::CACHE_LINE - 1);
+    while line < addr + len {
+        unsafe { cbp.dcimvac.write(line as u32) };
+        line += memory::CACHE_LINE;
+    }
+    asm::dsb();
+    asm::isb();
+}
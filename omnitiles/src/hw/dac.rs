@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! DAC outputs for watching internal signals on an oscilloscope.
+//!
+//! Both channels convert without a trigger, so a 12-bit write shows up on the pin within a
+//! microsecond, far ahead of anything a UART print could report. [`Signal`] names the values the
+//! main loop can route to a channel (`MSG_SET_DAC_PROBE`) and the range mapped onto 0–VDDA for
+//! each, so a scope trace reads the same from one tuning session to the next.
+//!
+//! OUT1 is PA4 and OUT2 is PA5; both pins must be in analog mode (`BoardPins::dac`). The output
+//! buffer is on, which keeps the swing about 0.2 V inside the rails.
+
+use stm32f7xx_hal::pac;
+
+/// Full-scale code of the 12-bit right-aligned data registers.
+pub const FULL_SCALE: u16 = 0x0FFF;
+
+// CR fields.
+const CR_EN1: u32 = 1 << 0;
+const CR_EN2: u32 = 1 << 16;
+
+/// Velocity mapped onto the full output swing, ±mm/s.
+const VELOCITY_RANGE_MM_S: f32 = 50.0;
+/// Position error mapped onto the full output swing, ±mm.
+const ERROR_RANGE_MM: f32 = 10.0;
+
+/// DAC output pin.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Channel {
+    /// PA4.
+    Out1,
+    /// PA5.
+    Out2,
+}
+
+impl Channel {
+    /// Decode the wire channel number (1 or 2).
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Channel::Out1),
+            2 => Some(Channel::Out2),
+            _ => None,
+        }
+    }
+}
+
+/// Internal signal that can be routed to a channel.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Signal {
+    /// Channel held at mid-scale.
+    Off = 0,
+    /// M1 controller output duty, -1..1.
+    M1Output = 1,
+    /// M2 controller output duty, -1..1.
+    M2Output = 2,
+    /// M1 estimated velocity.
+    M1Velocity = 3,
+    /// M2 estimated velocity.
+    M2Velocity = 4,
+    /// M1 target minus position.
+    M1Error = 5,
+    /// M2 target minus position.
+    M2Error = 6,
+}
+
+impl Signal {
+    /// Decode a wire ID, `None` for unknown signals.
+    pub fn from_id(id: u8) -> Option<Self> {
+        Some(match id {
+            0 => Signal::Off,
+            1 => Signal::M1Output,
+            2 => Signal::M2Output,
+            3 => Signal::M1Velocity,
+            4 => Signal::M2Velocity,
+            5 => Signal::M1Error,
+            6 => Signal::M2Error,
+            _ => return None,
+        })
+    }
+
+    /// Values mapped to 0 V and full scale. All ranges are symmetric, so 0 is mid-scale.
+    pub fn range(self) -> (f32, f32) {
+        let half = match self {
+            Signal::Off | Signal::M1Output | Signal::M2Output => 1.0,
+            Signal::M1Velocity | Signal::M2Velocity => VELOCITY_RANGE_MM_S,
+            Signal::M1Error | Signal::M2Error => ERROR_RANGE_MM,
+        };
+        (-half, half)
+    }
+}
+
+/// Both DAC channels, enabled at mid-scale.
+pub struct Dac {
+    dac: pac::DAC,
+}
+
+impl Dac {
+    pub fn new(dac: pac::DAC) -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.apb1enr.modify(|_, w| w.dacen().set_bit());
+        dac.cr.write(|w| unsafe { w.bits(CR_EN1 | CR_EN2) });
+        let mut this = Self { dac };
+        this.write_raw(Channel::Out1, FULL_SCALE / 2);
+        this.write_raw(Channel::Out2, FULL_SCALE / 2);
+        this
+    }
+
+    pub fn free(self) -> pac::DAC {
+        self.dac
+    }
+
+    /// Output a 12-bit code; larger values are clamped.
+    pub fn write_raw(&mut self, channel: Channel, code: u16) {
+        let code = code.min(FULL_SCALE) as u32;
+        match channel {
+            Channel::Out1 => self.dac.dhr12r1.write(|w| unsafe { w.bits(code) }),
+            Channel::Out2 => self.dac.dhr12r2.write(|w| unsafe { w.bits(code) }),
+        }
+    }
+
+    /// Output `value` scaled so `min` is 0 V and `max` full scale, clamped to that range.
+    pub fn write(&mut self, channel: Channel, value: f32, (min, max): (f32, f32)) {
+        let t = ((value - min) / (max - min)).clamp(0.0, 1.0);
+        // NaN passes through the clamp and casts to 0.
+        self.write_raw(channel, (t * FULL_SCALE as f32 + 0.5) as u16);
+    }
+}
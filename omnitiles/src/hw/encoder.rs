@@ -4,13 +4,28 @@
 //! Quadrature encoder support via STM32F7 timers in encoder mode.
 //!
 //! This module configures TIM2 (32-bit) and TIM3 (16-bit) reigsters for encoder mode and provides
-//! simple accessors.
+//! simple accessors. The counting direction can be inverted at runtime to match the wiring of a
+//! particular unit; [`raw`](Encoder::raw) always returns the unmodified hardware counter.
+//!
+//! TIM3's counter is extended to 32 bits in software: its update interrupt counts wraps, and
+//! `Encoder<TIM3>::position` combines them with the hardware count. The application must
+//! call [`on_tim3_interrupt`] from its `TIM3` handler.
+
+use core::sync::atomic::{AtomicI32, Ordering};
 
+use cortex_m::peripheral::NVIC;
 use stm32f7xx_hal::pac;
 
+/// TIM3 wraps counted by [`on_tim3_interrupt`]: +1 per overflow, -1 per underflow.
+static TIM3_WRAPS: AtomicI32 = AtomicI32::new(0);
+
+/// Half of the 16-bit range, used to tell an overflow from an underflow.
+const TIM3_HALF: u16 = 0x8000;
+
 /// Generic encoder wrapper over a PAC TIMx peripheral.
 pub struct Encoder<TIM> {
     tim: TIM,
+    inverted: bool,
 }
 
 impl<TIM> Encoder<TIM> {
@@ -19,11 +34,25 @@ impl<TIM> Encoder<TIM> {
     pub fn free(self) -> TIM {
         self.tim
     }
+
+    /// Invert the sign of [`position`](Self::position), for units whose A/B channels are swapped.
+    #[inline]
+    pub fn set_inverted(&mut self, inverted: bool) {
+        self.inverted = inverted;
+    }
+
+    /// True if the position is reported with inverted sign.
+    #[inline]
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
 }
 
 impl Encoder<pac::TIM2> {
     /// Configure TIM2 as a quadrature encoder with full 32-bit range.
     pub fn tim2(tim2: pac::TIM2) -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.apb1enr.modify(|_, w| w.tim2en().set_bit());
         let tim = tim2;
 
         // Disable counter while configuring
@@ -56,7 +85,10 @@ impl Encoder<pac::TIM2> {
         // Enable the counter
         tim.cr1.modify(|_, w| w.cen().set_bit());
 
-        Self { tim }
+        Self {
+            tim,
+            inverted: false,
+        }
     }
 
     /// Read the raw 32-bit counter value.
@@ -65,10 +97,15 @@ impl Encoder<pac::TIM2> {
         self.tim.cnt.read().cnt().bits()
     }
 
-    /// Interpret the counter as a signed 32-bit position.
+    /// Interpret the counter as a signed 32-bit position, honoring the inversion flag.
     #[inline]
     pub fn position(&self) -> i32 {
-        self.raw() as i32
+        let pos = self.raw() as i32;
+        if self.inverted {
+            pos.wrapping_neg()
+        } else {
+            pos
+        }
     }
 
     /// Reset the encoder position to zero.
@@ -79,8 +116,11 @@ impl Encoder<pac::TIM2> {
 }
 
 impl Encoder<pac::TIM3> {
-    /// Configure TIM3 as a quadrature encoder with full 16-bit range.
+    /// Configure TIM3 as a quadrature encoder with full 16-bit range, and enable its update
+    /// interrupt to extend the count to 32 bits.
     pub fn tim3(tim3: pac::TIM3) -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.apb1enr.modify(|_, w| w.tim3en().set_bit());
         let tim = tim3;
 
         // Disable counter while configuring
@@ -107,13 +147,22 @@ impl Encoder<pac::TIM3> {
                 .set_bit()
         });
 
-        // Reset counter
+        // Reset counter and wrap count
         tim.cnt.write(|w| unsafe { w.bits(0) });
+        TIM3_WRAPS.store(0, Ordering::Relaxed);
+
+        // Interrupt on overflow and underflow
+        tim.sr.modify(|_, w| w.uif().clear_bit());
+        tim.dier.modify(|_, w| w.uie().set_bit());
+        unsafe { NVIC::unmask(pac::Interrupt::TIM3) };
 
         // Enable counter
         tim.cr1.modify(|_, w| w.cen().set_bit());
 
-        Self { tim }
+        Self {
+            tim,
+            inverted: false,
+        }
     }
 
     /// Read the raw 16-bit counter value.
@@ -122,15 +171,65 @@ impl Encoder<pac::TIM3> {
         self.tim.cnt.read().cnt().bits()
     }
 
-    /// Interpret the counter as a signed 16-bit position.
-    #[inline]
-    pub fn position(&self) -> i16 {
-        self.raw() as i16
+    /// Extended 32-bit position, honoring the inversion flag.
+    ///
+    /// A wrap whose interrupt hasn't run yet (e.g. when called with interrupts masked) is still
+    /// counted, so the result never jumps by 65536.
+    pub fn position(&self) -> i32 {
+        let pos = loop {
+            let wraps = TIM3_WRAPS.load(Ordering::Acquire);
+            let pending = self.tim.sr.read().uif().bit_is_set();
+            let cnt = self.raw();
+            // Retry if the interrupt ran or a wrap happened in between.
+            if self.tim.sr.read().uif().bit_is_set() != pending
+                || TIM3_WRAPS.load(Ordering::Acquire) != wraps
+            {
+                continue;
+            }
+            let wraps = if pending {
+                wraps.wrapping_add(wrap_step(cnt))
+            } else {
+                wraps
+            };
+            break wraps.wrapping_mul(0x1_0000).wrapping_add(cnt as i32);
+        };
+        if self.inverted {
+            pos.wrapping_neg()
+        } else {
+            pos
+        }
     }
 
     /// Reset the encoder position to zero.
     #[inline]
     pub fn reset(&mut self) {
-        self.tim.cnt.write(|w| unsafe { w.bits(0) });
+        cortex_m::interrupt::free(|_| {
+            self.tim.cnt.write(|w| unsafe { w.bits(0) });
+            self.tim.sr.modify(|_, w| w.uif().clear_bit());
+            TIM3_WRAPS.store(0, Ordering::Release);
+        });
+    }
+}
+
+/// +1 for an overflow, -1 for an underflow, judged from the counter shortly after the wrap.
+#[inline]
+fn wrap_step(cnt: u16) -> i32 {
+    if cnt < TIM3_HALF {
+        1
+    } else {
+        -1
+    }
+}
+
+/// TIM3 update interrupt handler body: count the counter wrap that raised it.
+///
+/// The wrap direction is read from the counter rather than `CR1.DIR`, which may already have
+/// flipped if the shaft reversed right after wrapping.
+pub fn on_tim3_interrupt() {
+    let tim = unsafe { &*pac::TIM3::ptr() };
+    if tim.sr.read().uif().bit_is_set() {
+        tim.sr.modify(|_, w| w.uif().clear_bit());
+        let step = wrap_step(tim.cnt.read().cnt().bits());
+        TIM3_WRAPS.fetch_add(step, Ordering::AcqRel);
     }
 }
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Ethernet MAC with a smoltcp IPv4 stack and one UDP endpoint for telemetry and commands.
+//!
+//! Large installations can put tiles on a switched network instead of long UART/CAN runs. The
+//! endpoint speaks the same framed protocol as the host link: a datagram to [`UDP_PORT`] carries
+//! one or more command frames, and the tile answers with datagrams holding its telemetry, replies
+//! and events, exactly as they would go out over SPI. Replies go to the host that sent the most
+//! recent datagram, so a host subscribes by sending anything, e.g. `PING`.
+//!
+//! The MAC runs in RMII mode against an external PHY such as the LAN8742A. Driver setup
+//! (`stm32_eth::new` with the board's RMII pins and [`RING_LEN`]-entry descriptor rings) is left
+//! to the board code, which hands the resulting DMA to [`Eth::new`].
+//!
+//! RXD0/RXD1 only exist on PC4/PC5. PCB v1 uses them for current sense and can't run Ethernet;
+//! PCB v2 gives up the first pot of each axis for them (see
+//! [`ETH_PIN_MAP`](super::pins_v2::ETH_PIN_MAP)). [`RMII_PIN_MAP`] is the Nucleo-F767ZI routing,
+//! which also needs SPI1 MOSI moved off PA7. Requires the `ethernet` feature.
+
+use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet, SocketStorage};
+use smoltcp::socket::udp;
+use smoltcp::time::Instant;
+use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr, IpEndpoint};
+use stm32_eth::dma::EthernetDMA;
+
+use super::pin_map::{pin, Mode, PinUse};
+
+/// UDP port the tile listens on and sends from.
+pub const UDP_PORT: u16 = 5005;
+
+/// First three octets of the default tile subnet; node `n` is `10.88.0.n/24`.
+pub const DEFAULT_SUBNET: [u8; 3] = [10, 88, 0];
+
+/// Suggested DMA descriptors per direction.
+pub const RING_LEN: usize = 4;
+/// Datagrams buffered per direction.
+const UDP_PACKETS: usize = 4;
+/// Payload bytes buffered per direction, enough for [`UDP_PACKETS`] full link buffers.
+const UDP_BUFFER_LEN: usize = 512;
+
+/// RMII pins as routed on the Nucleo-F767ZI (LAN8742A PHY), all AF11.
+pub const RMII_PIN_MAP: &[PinUse] = &[
+    pin('A', 1, Mode::Alternate(11), "ETH_REF_CLK", "ETH REF_CLK"),
+    pin('A', 2, Mode::Alternate(11), "ETH_MDIO", "ETH MDIO"),
+    pin('A', 7, Mode::Alternate(11), "ETH_CRS_DV", "ETH CRS_DV"),
+    pin('C', 1, Mode::Alternate(11), "ETH_MDC", "ETH MDC"),
+    pin('C', 4, Mode::Alternate(11), "ETH_RXD0", "ETH RXD0"),
+    pin('C', 5, Mode::Alternate(11), "ETH_RXD1", "ETH RXD1"),
+    pin('G', 11, Mode::Alternate(11), "ETH_TX_EN", "ETH TX_EN"),
+    pin('G', 13, Mode::Alternate(11), "ETH_TXD0", "ETH TXD0"),
+    pin('B', 13, Mode::Alternate(11), "ETH_TXD1", "ETH TXD1"),
+];
+
+/// Locally administered MAC address for node `node_id` (`02:4F:54:00:00:<node_id>`).
+pub fn mac_address(node_id: u8) -> [u8; 6] {
+    [0x02, 0x4F, 0x54, 0x00, 0x00, node_id]
+}
+
+/// Default IPv4 address for node `node_id` in [`DEFAULT_SUBNET`].
+pub fn ip_address(node_id: u8) -> [u8; 4] {
+    let [a, b, c] = DEFAULT_SUBNET;
+    [a, b, c, node_id]
+}
+
+/// Socket buffers. Must outlive [`Eth`]; allocate once at startup, e.g. with
+/// `cortex_m::singleton!`.
+pub struct EthStorage<'a> {
+    sockets: [SocketStorage<'a>; 1],
+    rx_meta: [udp::PacketMetadata; UDP_PACKETS],
+    rx_payload: [u8; UDP_BUFFER_LEN],
+    tx_meta: [udp::PacketMetadata; UDP_PACKETS],
+    tx_payload: [u8; UDP_BUFFER_LEN],
+}
+
+impl EthStorage<'_> {
+    pub fn new() -> Self {
+        Self {
+            sockets: [SocketStorage::EMPTY],
+            rx_meta: [udp::PacketMetadata::EMPTY; UDP_PACKETS],
+            rx_payload: [0; UDP_BUFFER_LEN],
+            tx_meta: [udp::PacketMetadata::EMPTY; UDP_PACKETS],
+            tx_payload: [0; UDP_BUFFER_LEN],
+        }
+    }
+}
+
+impl Default for EthStorage<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// IPv4 interface with the tile's UDP endpoint.
+pub struct Eth<'a> {
+    dma: EthernetDMA<'a, 'a>,
+    iface: Interface,
+    sockets: SocketSet<'a>,
+    udp: SocketHandle,
+    /// Host that sent the most recent datagram; replies and telemetry go there.
+    peer: Option<IpEndpoint>,
+    /// Datagrams that could not be queued for sending.
+    pub dropped: u32,
+}
+
+impl<'a> Eth<'a> {
+    /// Bring up the interface on `dma` with `mac` and `ip`/`prefix_len`, listening on
+    /// [`UDP_PORT`].
+    pub fn new(
+        mut dma: EthernetDMA<'a, 'a>,
+        storage: &'a mut EthStorage<'a>,
+        mac: [u8; 6],
+        ip: [u8; 4],
+        prefix_len: u8,
+        now_ms: u64,
+    ) -> Self {
+        let config = Config::new(HardwareAddress::Ethernet(EthernetAddress(mac)));
+        let mut iface = Interface::new(config, &mut &mut dma, Instant::from_millis(now_ms as i64));
+        iface.update_ip_addrs(|addrs| {
+            let [a, b, c, d] = ip;
+            addrs
+                .push(IpCidr::new(IpAddress::v4(a, b, c, d), prefix_len))
+                .ok();
+        });
+
+        let mut sockets = SocketSet::new(&mut storage.sockets[..]);
+        let rx = udp::PacketBuffer::new(&mut storage.rx_meta[..], &mut storage.rx_payload[..]);
+        let tx = udp::PacketBuffer::new(&mut storage.tx_meta[..], &mut storage.tx_payload[..]);
+        let mut socket = udp::Socket::new(rx, tx);
+        // Binding a fresh socket to a non-zero port can't fail.
+        socket.bind(UDP_PORT).ok();
+        let udp = sockets.add(socket);
+
+        Self {
+            dma,
+            iface,
+            sockets,
+            udp,
+            peer: None,
+            dropped: 0,
+        }
+    }
+
+    /// Move frames between the MAC and the stack. Call every main-loop pass.
+    pub fn poll(&mut self, now_ms: u64) {
+        let now = Instant::from_millis(now_ms as i64);
+        self.iface.poll(now, &mut &mut self.dma, &mut self.sockets);
+    }
+
+    /// Copy the next received datagram into `buf`, truncating it if needed, and remember its
+    /// sender as the peer. Returns the number of bytes copied.
+    pub fn recv(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let socket = self.sockets.get_mut::<udp::Socket>(self.udp);
+        let (n, meta) = socket.recv_slice(buf).ok()?;
+        self.peer = Some(meta.endpoint);
+        Some(n)
+    }
+
+    /// Queue `data` as one datagram to the peer. Dropped if no host has sent anything yet or the
+    /// socket buffer is full; the next [`poll`](Self::poll) sends it.
+    pub fn send(&mut self, data: &[u8]) {
+        let Some(peer) = self.peer else {
+            return;
+        };
+        let socket = self.sockets.get_mut::<udp::Socket>(self.udp);
+        if socket.send_slice(data, peer).is_err() {
+            self.dropped = self.dropped.wrapping_add(1);
+        }
+    }
+
+    /// Host currently receiving telemetry, if any.
+    pub fn peer(&self) -> Option<IpEndpoint> {
+        self.peer
+    }
+}
@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! GPIO edge interrupts through EXTI lines 0–15.
+//!
+//! Line `n` serves pin `n` of one port at a time, selected in SYSCFG. Unmasking the EXTI interrupt
+//! in the NVIC and writing the handler are left to the caller.
+
+use stm32f7xx_hal::pac;
+
+/// Route pin `line` of `port` (`'A'`..) to its EXTI line and unmask it on both edges.
+pub fn listen(port: char, line: u8) {
+    let rcc = unsafe { &*pac::RCC::ptr() };
+    rcc.apb2enr.modify(|_, w| w.syscfgen().set_bit());
+    let syscfg = unsafe { &*pac::SYSCFG::ptr() };
+    let port = (port as u32) - ('A' as u32);
+    let shift = 4 * (line as u32 % 4);
+    let select = |bits: u32| (bits & !(0xF << shift)) | (port << shift);
+    match line / 4 {
+        0 => syscfg
+            .exticr1
+            .modify(|r, w| unsafe { w.bits(select(r.bits())) }),
+        1 => syscfg
+            .exticr2
+            .modify(|r, w| unsafe { w.bits(select(r.bits())) }),
+        2 => syscfg
+            .exticr3
+            .modify(|r, w| unsafe { w.bits(select(r.bits())) }),
+        _ => syscfg
+            .exticr4
+            .modify(|r, w| unsafe { w.bits(select(r.bits())) }),
+    }
+    let exti = unsafe { &*pac::EXTI::ptr() };
+    let mask = 1u32 << line;
+    exti.rtsr.modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+    exti.ftsr.modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+    exti.pr.write(|w| unsafe { w.bits(mask) });
+    exti.imr.modify(|r, w| unsafe { w.bits(r.bits() | mask) });
+}
+
+/// True if an edge arrived on `line` since the last call; clears its pending bit.
+pub fn take_pending(line: u8) -> bool {
+    let exti = unsafe { &*pac::EXTI::ptr() };
+    let mask = 1u32 << line;
+    let pending = exti.pr.read().bits() & mask != 0;
+    if pending {
+        exti.pr.write(|w| unsafe { w.bits(mask) });
+    }
+    pending
+}
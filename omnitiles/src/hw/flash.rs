@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Internal flash erase/program support for persistent storage.
+//!
+//! The STM32F777 is used in its default single-bank layout: sectors 0–3 are 32 KB, sector 4 is
+//! 128 KB and sectors 5–11 are 256 KB. The last three sectors are reserved in `memory.x`: 9 and
+//! 11 hold the two banks of the tile configuration (see `config::bank`) and 10 the motor
+//! statistics log. The linker never places code there.
+//!
+//! Programming uses x8 parallelism so any byte-aligned slice can be written, at the cost of speed.
+//! This is fine for the small records stored here.
+
+use stm32f7xx_hal::pac;
+
+use super::{cpu, iwdg};
+
+/// First key of the FLASH_KEYR unlock sequence.
+const KEY1: u32 = 0x4567_0123;
+/// Second key of the FLASH_KEYR unlock sequence.
+const KEY2: u32 = 0xCDEF_89AB;
+/// FLASH_CR.STRT.
+const CR_STRT: u32 = 1 << 16;
+/// FLASH_SR.BSY.
+const SR_BSY: u32 = 1 << 16;
+/// IWDG_KR reload key.
+const IWDG_RELOAD: u32 = 0xAAAA;
+
+/// Sector reserved for the tile configuration record (bank A).
+pub const CONFIG_SECTOR: u8 = 11;
+/// Base address of [`CONFIG_SECTOR`].
+pub const CONFIG_ADDR: u32 = 0x081C_0000;
+/// Size of [`CONFIG_SECTOR`] in bytes.
+pub const CONFIG_SECTOR_LEN: usize = 256 * 1024;
+
+/// Sector reserved for the second copy of the tile configuration record (bank B).
+pub const CONFIG_B_SECTOR: u8 = 9;
+/// Base address of [`CONFIG_B_SECTOR`].
+pub const CONFIG_B_ADDR: u32 = 0x0814_0000;
+
+/// Sector reserved for the motor statistics log.
+pub const STATS_SECTOR: u8 = 10;
+/// Base address of [`STATS_SECTOR`].
+pub const STATS_ADDR: u32 = 0x0818_0000;
+/// Size of [`STATS_SECTOR`] in bytes.
+pub const STATS_SECTOR_LEN: usize = 256 * 1024;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Write protection error (sector is write-protected).
+    WriteProtected,
+    /// Programming alignment error.
+    Alignment,
+    /// Programming parallelism error.
+    Parallelism,
+    /// Erase sequence error.
+    EraseSequence,
+    /// Operation error reported by the flash interface.
+    Operation,
+    /// Data read back after programming did not match.
+    Verify,
+}
+
+/// Wrapper around the FLASH peripheral for sector erase and byte programming.
+pub struct Flash {
+    flash: pac::FLASH,
+}
+
+impl Flash {
+    pub fn new(flash: pac::FLASH) -> Self {
+        Self { flash }
+    }
+
+    pub fn free(self) -> pac::FLASH {
+        self.flash
+    }
+
+    fn unlock(&mut self) {
+        if self.flash.cr.read().lock().bit_is_set() {
+            self.flash.keyr.write(|w| unsafe { w.bits(KEY1) });
+            self.flash.keyr.write(|w| unsafe { w.bits(KEY2) });
+        }
+    }
+
+    fn lock(&mut self) {
+        self.flash.cr.modify(|_, w| w.lock().set_bit());
+    }
+
+    /// Wait for the current operation to finish and translate any error flags.
+    ///
+    /// Sector erases wait in [`erase_from_ram`] instead, since this loop runs from flash; by the
+    /// time it runs after one, the erase is over. Programming a byte takes microseconds.
+    fn wait_ready(&mut self) -> Result<(), Error> {
+        while self.flash.sr.read().bsy().bit_is_set() {
+            iwdg::feed();
+        }
+
+        let sr = self.flash.sr.read();
+        let result = if sr.wrperr().bit_is_set() {
+            Err(Error::WriteProtected)
+        } else if sr.pgaerr().bit_is_set() {
+            Err(Error::Alignment)
+        } else if sr.pgperr().bit_is_set() {
+            Err(Error::Parallelism)
+        } else if sr.erserr().bit_is_set() {
+            Err(Error::EraseSequence)
+        } else if sr.operr().bit_is_set() {
+            Err(Error::Operation)
+        } else {
+            Ok(())
+        };
+
+        // Error and EOP flags are cleared by writing 1.
+        self.flash.sr.write(|w| unsafe { w.bits(0x0000_00F3) });
+        result
+    }
+
+    /// Base address and length of `sector` in the single-bank layout.
+    fn sector_range(sector: u8) -> (u32, usize) {
+        const BASE: u32 = 0x0800_0000;
+        const K: u32 = 1024;
+        match sector {
+            0..=3 => (BASE + sector as u32 * 32 * K, 32 * 1024),
+            4 => (BASE + 128 * K, 128 * 1024),
+            _ => (BASE + 256 * K + (sector as u32 - 5) * 256 * K, 256 * 1024),
+        }
+    }
+
+    /// Erase a single sector.
+    pub fn erase_sector(&mut self, sector: u8) -> Result<(), Error> {
+        self.unlock();
+        let result = self.wait_ready().and_then(|_| {
+            self.flash.cr.modify(|_, w| unsafe {
+                w.psize().bits(0b00);
+                w.snb().bits(sector & 0x0F);
+                w.ser().set_bit()
+            });
+            let cr = self.flash.cr.as_ptr();
+            let sr = self.flash.sr.as_ptr();
+            let kr = unsafe { (*pac::IWDG::ptr()).kr.as_ptr() };
+            // An interrupt's vector and handler come from flash and would stall the same way.
+            cortex_m::interrupt::free(|_| unsafe { erase_from_ram(cr, sr, kr) });
+            self.wait_ready()
+        });
+        self.flash.cr.modify(|_, w| w.ser().clear_bit());
+        self.lock();
+        // The data cache may still hold the old contents.
+        let (addr, len) = Self::sector_range(sector & 0x0F);
+        cpu::invalidate_dcache_range(addr as usize, len);
+        result
+    }
+
+    /// Program `data` starting at `addr`. The target range must already be erased.
+    pub fn program(&mut self, addr: u32, data: &[u8]) -> Result<(), Error> {
+        self.unlock();
+        let mut result = self.wait_ready();
+        if result.is_ok() {
+            self.flash
+                .cr
+                .modify(|_, w| unsafe { w.psize().bits(0b00).pg().set_bit() });
+            for (i, &b) in data.iter().enumerate() {
+                let ptr = (addr as usize + i) as *mut u8;
+                unsafe { core::ptr::write_volatile(ptr, b) };
+                cortex_m::asm::dsb();
+                result = self.wait_ready();
+                if result.is_err() {
+                    break;
+                }
+            }
+            self.flash.cr.modify(|_, w| w.pg().clear_bit());
+        }
+        self.lock();
+        cpu::invalidate_dcache_range(addr as usize, data.len());
+        result?;
+
+        // Verify
+        for (i, &b) in data.iter().enumerate() {
+            if Self::read_byte(addr + i as u32) != b {
+                return Err(Error::Verify);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a single byte from memory-mapped flash.
+    #[inline]
+    pub fn read_byte(addr: u32) -> u8 {
+        unsafe { core::ptr::read_volatile(addr as *const u8) }
+    }
+
+    /// Copy `buf.len()` bytes from memory-mapped flash starting at `addr`.
+    pub fn read(addr: u32, buf: &mut [u8]) {
+        for (i, b) in buf.iter_mut().enumerate() {
+            *b = Self::read_byte(addr + i as u32);
+        }
+    }
+}
+
+/// Start the erase set up in FLASH_CR and wait for it to finish, feeding the watchdog.
+///
+/// With a single bank, every read of flash stalls until an erase ends, up to a few seconds for a
+/// 256 KB sector at x8 parallelism. A wait loop fetched from flash would stop at its first
+/// instruction after the start and never feed the watchdog. This function is placed in `.data`,
+/// which the startup code copies to SRAM1, and reaches the registers through raw pointers only,
+/// so nothing it executes or reads lives in flash. `cr`, `sr` and `kr` must point at FLASH_CR,
+/// FLASH_SR and IWDG_KR, and interrupts must be masked.
+#[inline(never)]
+#[link_section = ".data.flash_erase_from_ram"]
+unsafe fn erase_from_ram(cr: *mut u32, sr: *const u32, kr: *mut u32) {
+    core::ptr::write_volatile(cr, core::ptr::read_volatile(cr) | CR_STRT);
+    while core::ptr::read_volatile(sr) & SR_BSY != 0 {
+        core::ptr::write_volatile(kr, IWDG_RELOAD);
+    }
+}
@@ -0,0 +1,263 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Hall-sensor feedback from three digital inputs.
+//!
+//! A BLDC motor's three hall sensors, 120° apart, split each electrical revolution into six
+//! sectors. Counting sector changes gives a coarse position (six edges per electrical revolution)
+//! and the time between them a velocity. That is the position feedback of the BLDC path
+//! ([`Drv8313`](crate::drivers::Drv8313)), and, on a motor that also has an encoder, an
+//! independent check of it: feed both positions to a
+//! [`Plausibility`](crate::control::plausibility::Plausibility) to catch a slipping or dead
+//! encoder, e.g. on the FIT0185.
+//!
+//! [`HallTracker`] does the decoding and counting on hall codes from any source; [`Halls`] reads
+//! them from three GPIO inputs. Codes must be sampled at least once per sector: skipping one
+//! sector is still counted in the right direction, skipping two is ambiguous and dropped. Poll
+//! fast enough for the top speed, or call [`Halls::listen`] and poll from the EXTI handler.
+
+use stm32f7xx_hal::gpio::{self, Input};
+
+use super::exti;
+
+/// Hall code (H3 H2 H1) to electrical sector 0–5, in forward order 1, 3, 2, 6, 4, 5. Codes 0 and
+/// 7 never occur on a working sensor.
+const SECTOR: [u8; 8] = [INVALID, 0, 2, 1, 4, 5, 3, INVALID];
+const INVALID: u8 = 0xFF;
+
+/// With no edge for this long, the velocity reads zero.
+const STOPPED_US: u64 = 500_000;
+
+/// Electrical sector 0–5 of a hall code (H1 in bit 0), `None` for an impossible code.
+pub fn sector(code: u8) -> Option<u8> {
+    match SECTOR[(code & 0b111) as usize] {
+        INVALID => None,
+        sector => Some(sector),
+    }
+}
+
+/// Sector decoding and edge counting, independent of the pins.
+#[derive(Copy, Clone, Debug)]
+pub struct HallTracker {
+    sector: Option<u8>,
+    /// Signed edge count, positive in the forward direction.
+    edges: i32,
+    last_edge_us: Option<u64>,
+    /// Signed time per edge over the last edge, 0 until two edges were seen.
+    edge_us: i64,
+    faulted: bool,
+}
+
+impl HallTracker {
+    pub const fn new() -> Self {
+        Self {
+            sector: None,
+            edges: 0,
+            last_edge_us: None,
+            edge_us: 0,
+            faulted: false,
+        }
+    }
+
+    /// Feed a hall code. Returns the sector, or `None` for an impossible code, which latches a
+    /// fault.
+    pub fn update(&mut self, code: u8) -> Option<u8> {
+        self.step(code).map(|(sector, _)| sector)
+    }
+
+    /// Feed a hall code sampled at `now_us`, also timing edges for [`velocity`](Self::velocity).
+    pub fn update_at(&mut self, code: u8, now_us: u64) -> Option<u8> {
+        let (sector, step) = self.step(code)?;
+        if step != 0 {
+            if let Some(last) = self.last_edge_us {
+                let per_edge = (now_us - last) as i64 / step.unsigned_abs() as i64;
+                self.edge_us = if step > 0 { per_edge } else { -per_edge };
+            }
+            self.last_edge_us = Some(now_us);
+        }
+        Some(sector)
+    }
+
+    /// Decode `code` and count the edges since the last sector.
+    fn step(&mut self, code: u8) -> Option<(u8, i32)> {
+        let Some(sector) = sector(code) else {
+            self.faulted = true;
+            return None;
+        };
+        let step = match self.sector {
+            // Two sectors is a missed edge, still unambiguous; three could be either way.
+            Some(last) => match (sector + 6 - last) % 6 {
+                1 => 1,
+                2 => 2,
+                4 => -2,
+                5 => -1,
+                _ => 0,
+            },
+            None => 0,
+        };
+        self.edges += step;
+        self.sector = Some(sector);
+        Some((sector, step))
+    }
+
+    /// Last valid sector.
+    pub fn sector(&self) -> Option<u8> {
+        self.sector
+    }
+
+    pub fn edges(&self) -> i32 {
+        self.edges
+    }
+
+    pub fn set_edges(&mut self, edges: i32) {
+        self.edges = edges;
+    }
+
+    /// Velocity in edges/s at `now_us`. Between edges it falls off as the time since the last one
+    /// grows, and reads zero after half a second without one.
+    pub fn velocity(&self, now_us: u64) -> f32 {
+        let Some(last) = self.last_edge_us else {
+            return 0.0;
+        };
+        let since = now_us.saturating_sub(last);
+        if self.edge_us == 0 || since >= STOPPED_US {
+            return 0.0;
+        }
+        // The next edge is at least `since` away, so the speed is at most 1 / since.
+        let per_edge = self.edge_us.unsigned_abs().max(since) as f32;
+        let speed = 1_000_000.0 / per_edge;
+        if self.edge_us > 0 {
+            speed
+        } else {
+            -speed
+        }
+    }
+
+    /// True once an impossible code was seen.
+    pub fn is_faulted(&self) -> bool {
+        self.faulted
+    }
+
+    /// Clear the fault and forget the sector, keeping the count.
+    pub fn clear_fault(&mut self) {
+        self.faulted = false;
+        self.sector = None;
+        self.last_edge_us = None;
+        self.edge_us = 0;
+    }
+}
+
+impl Default for HallTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Three hall sensors on GPIO inputs.
+pub struct Halls<
+    const H1_P: char,
+    const H1_N: u8,
+    const H2_P: char,
+    const H2_N: u8,
+    const H3_P: char,
+    const H3_N: u8,
+    MODE,
+> {
+    h1: gpio::Pin<H1_P, H1_N, Input<MODE>>,
+    h2: gpio::Pin<H2_P, H2_N, Input<MODE>>,
+    h3: gpio::Pin<H3_P, H3_N, Input<MODE>>,
+    tracker: HallTracker,
+    /// Hall edges per output revolution.
+    edges_per_rev: f32,
+}
+
+impl<
+        const H1_P: char,
+        const H1_N: u8,
+        const H2_P: char,
+        const H2_N: u8,
+        const H3_P: char,
+        const H3_N: u8,
+        MODE,
+    > Halls<H1_P, H1_N, H2_P, H2_N, H3_P, H3_N, MODE>
+{
+    /// `edges_per_rev` is 6 × pole pairs × gear ratio.
+    pub fn new(
+        h1: gpio::Pin<H1_P, H1_N, Input<MODE>>,
+        h2: gpio::Pin<H2_P, H2_N, Input<MODE>>,
+        h3: gpio::Pin<H3_P, H3_N, Input<MODE>>,
+        edges_per_rev: f32,
+    ) -> Self {
+        let mut halls = Self {
+            h1,
+            h2,
+            h3,
+            tracker: HallTracker::new(),
+            edges_per_rev,
+        };
+        let code = halls.code();
+        halls.tracker.update(code);
+        halls
+    }
+
+    /// Raw hall code, H1 in bit 0.
+    pub fn code(&self) -> u8 {
+        self.h1.is_high() as u8 | (self.h2.is_high() as u8) << 1 | (self.h3.is_high() as u8) << 2
+    }
+
+    /// Sample the sensors. Returns the sector, `None` for an impossible code.
+    pub fn poll(&mut self, now_us: u64) -> Option<u8> {
+        let code = self.code();
+        self.tracker.update_at(code, now_us)
+    }
+
+    /// Position in output revolutions as of the last poll.
+    pub fn position_revs(&self) -> f32 {
+        self.tracker.edges() as f32 / self.edges_per_rev
+    }
+
+    /// Redefine the current position, e.g. to match an encoder after homing.
+    pub fn set_position(&mut self, revs: f32) {
+        self.tracker.set_edges((revs * self.edges_per_rev) as i32);
+    }
+
+    /// Velocity in output revolutions per second.
+    pub fn velocity_rps(&self, now_us: u64) -> f32 {
+        self.tracker.velocity(now_us) / self.edges_per_rev
+    }
+
+    pub fn is_faulted(&self) -> bool {
+        self.tracker.is_faulted()
+    }
+
+    pub fn clear_fault(&mut self) {
+        self.tracker.clear_fault();
+    }
+
+    pub fn tracker(&self) -> &HallTracker {
+        &self.tracker
+    }
+
+    /// Route all three pins to their EXTI lines on both edges. The lines must be distinct.
+    pub fn listen(&mut self) {
+        exti::listen(H1_P, H1_N);
+        exti::listen(H2_P, H2_N);
+        exti::listen(H3_P, H3_N);
+    }
+
+    /// True if any sensor changed since the last call; clears the EXTI pending bits.
+    pub fn take_edge(&mut self) -> bool {
+        // No short-circuit: every pending bit must be cleared.
+        exti::take_pending(H1_N) | exti::take_pending(H2_N) | exti::take_pending(H3_N)
+    }
+
+    pub fn free(
+        self,
+    ) -> (
+        gpio::Pin<H1_P, H1_N, Input<MODE>>,
+        gpio::Pin<H2_P, H2_N, Input<MODE>>,
+        gpio::Pin<H3_P, H3_N, Input<MODE>>,
+    ) {
+        (self.h1, self.h2, self.h3)
+    }
+}
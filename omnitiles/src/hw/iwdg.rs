@@ -0,0 +1,111 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Independent watchdog and the step convention for long operations.
+//!
+//! Once [`Iwdg::start`]ed the watchdog resets the MCU unless it is fed within [`TIMEOUT_MS`].
+//! The main loop feeds it once per pass, so anything that blocks for longer must not be written
+//! as a plain loop. Long operations (homing, end stop seeks, waiting for a move) are state
+//! machines instead, with a method
+//!
+//! ```text
+//! fn run_step(&mut self, .., elapsed_ms: u32) -> Poll<T>
+//! ```
+//!
+//! that does one bounded piece of work and returns [`Poll::Pending`] until the result is
+//! ready. The main loop can then drive one step per pass alongside everything else, and a
+//! blocking caller uses [`block_on`], which feeds the watchdog between steps. One step must
+//! stay well inside [`STEP_BUDGET_MS`].
+//!
+//! [`feed`] is a free function so that drivers without access to the [`Iwdg`] (the flash busy
+//! wait, bounded CAN reply waits) can feed it too; feeding a watchdog that was never started has
+//! no effect.
+
+use core::task::Poll;
+
+use cortex_m::delay::Delay;
+use stm32f7xx_hal::pac;
+
+/// Watchdog timeout.
+pub const TIMEOUT_MS: u32 = 1000;
+/// Longest a single step of a long operation may block, leaving the rest of the window for the
+/// main loop around it.
+pub const STEP_BUDGET_MS: u32 = TIMEOUT_MS / 4;
+
+/// Frequency of the LSI clock the watchdog runs from (nominal; 17–47 kHz over process and
+/// temperature, so the actual timeout can be up to 2x shorter).
+const LSI_HZ: u32 = 32_000;
+/// Key register values (RM0410 §33.4).
+const KEY_RELOAD: u32 = 0xAAAA;
+const KEY_UNLOCK: u32 = 0x5555;
+const KEY_START: u32 = 0xCCCC;
+/// Largest reload value (12-bit down-counter).
+const RELOAD_MAX: u32 = 0x0FFF;
+/// `DBGMCU_APB1_FZ.DBG_IWDG_STOP`: freeze the counter while the core is halted by a debugger.
+const DBG_IWDG_STOP: u32 = 1 << 12;
+
+/// The running independent watchdog. Cannot be stopped again except by a reset.
+pub struct Iwdg {
+    iwdg: pac::IWDG,
+}
+
+impl Iwdg {
+    /// Start the watchdog with a timeout of `timeout_ms` (clamped to the longest the hardware
+    /// supports, about 32 s). The counter is frozen while a debugger halts the core.
+    pub fn start(iwdg: pac::IWDG, timeout_ms: u32) -> Self {
+        // SAFETY: read-modify-write of the debug freeze register, which nothing else touches.
+        unsafe {
+            (*pac::DBGMCU::ptr())
+                .apb1_fz
+                .modify(|r, w| w.bits(r.bits() | DBG_IWDG_STOP));
+        }
+
+        // Smallest prescaler (4 << pr) that fits the timeout into the 12-bit reload.
+        let ticks_at_div4 = timeout_ms.saturating_mul(LSI_HZ / 1000) / 4;
+        let mut pr = 0;
+        while pr < 6 && ticks_at_div4 >> pr > RELOAD_MAX {
+            pr += 1;
+        }
+        let reload = (ticks_at_div4 >> pr).clamp(1, RELOAD_MAX);
+
+        iwdg.kr.write(|w| unsafe { w.bits(KEY_START) });
+        iwdg.kr.write(|w| unsafe { w.bits(KEY_UNLOCK) });
+        iwdg.pr.write(|w| unsafe { w.bits(pr) });
+        iwdg.rlr.write(|w| unsafe { w.bits(reload) });
+        // PVU/RVU stay set until the values have crossed into the LSI domain.
+        while iwdg.sr.read().bits() & 0b11 != 0 {}
+        iwdg.kr.write(|w| unsafe { w.bits(KEY_RELOAD) });
+        Self { iwdg }
+    }
+
+    /// Reload the counter.
+    #[inline]
+    pub fn feed(&mut self) {
+        self.iwdg.kr.write(|w| unsafe { w.bits(KEY_RELOAD) });
+    }
+}
+
+/// Reload the watchdog counter from anywhere, see the module docs.
+#[inline]
+pub fn feed() {
+    // SAFETY: a single write of the reload key, which is idempotent and ignored by a stopped
+    // watchdog.
+    unsafe { (*pac::IWDG::ptr()).kr.write(|w| w.bits(KEY_RELOAD)) }
+}
+
+/// Drive a long operation to completion: call `step` with the time since the previous call
+/// every `period_ms`, feeding the watchdog between steps, until it is ready.
+///
+/// For scripted sequences, test binaries and flows like provisioning that run with the rest of
+/// the tile idle; the main loop calls `run_step` once per pass instead.
+pub fn block_on<T>(delay: &mut Delay, period_ms: u32, mut step: impl FnMut(u32) -> Poll<T>) -> T {
+    let mut elapsed = 0;
+    loop {
+        feed();
+        if let Poll::Ready(result) = step(elapsed) {
+            return result;
+        }
+        delay.delay_ms(period_ms);
+        elapsed = period_ms;
+    }
+}
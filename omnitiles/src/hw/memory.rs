@@ -0,0 +1,123 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! RAM layout and where DMA buffers go.
+//!
+//! The data cache (see [`cpu`](super::cpu)) sits between the core and memory but DMA bypasses
+//! it, so a DMA buffer in cached RAM goes stale in one direction or the other. `memory.x` splits
+//! the F777's RAM into regions with a fixed cache policy:
+//!
+//! | Region | Address | Size | Cached | Section | Use |
+//! | ------ | ------- | ---- | ------ | ------- | --- |
+//! | DTCM  | `0x2000_0000` | 128 KB | never  | `.dtcm` | ISR state, DMA2 buffers (ADC) |
+//! | SRAM1 | `0x2002_0000` | 368 KB | yes    | default | `.data`, `.bss`, stack |
+//! | SRAM2 | `0x2007_C000` | 16 KB  | no (MPU) | `.dma` | buffers for any DMA (SPI, USART, TIM) |
+//!
+//! [`configure_mpu`] makes SRAM2 non-cacheable; [`cpu::init`](super::cpu::init) runs it before
+//! enabling the cache. Declare a buffer there with
+//!
+//! ```no_run
+//! # use omnitiles::hw::CacheAligned;
+//! #[link_section = ".dma.spi1_rx"]
+//! static mut SPI1_RX: CacheAligned<[u8; 128]> = CacheAligned([0; 128]);
+//! ```
+//!
+//! Neither `.dtcm` nor `.dma` is initialised by the startup code; write a buffer before reading
+//! it. A buffer that stays in cached SRAM1 needs cache maintenance around each transfer:
+//! [`before_dma_read`] before DMA reads it, [`after_dma_write`] after DMA has written it. Both do
+//! nothing for a buffer in DTCM or SRAM2, so drivers call them unconditionally.
+
+use core::ops::{Deref, DerefMut, Range};
+
+use cortex_m::asm;
+use cortex_m::peripheral::{CBP, MPU};
+
+use super::cpu;
+
+/// Data TCM, never cached.
+pub const DTCM: Range<usize> = 0x2000_0000..0x2002_0000;
+/// SRAM1, cached write-back.
+pub const SRAM1: Range<usize> = 0x2002_0000..0x2007_C000;
+/// SRAM2, made non-cacheable by [`configure_mpu`].
+pub const SRAM2: Range<usize> = 0x2007_C000..0x2008_0000;
+
+/// Data cache line size of the Cortex-M7.
+pub const CACHE_LINE: usize = 32;
+
+/// `MPU_RASR` of SRAM2: execute never, full access, normal memory non-cacheable (TEX=001, C=0,
+/// B=0), shareable, 16 KB (size field 13), enabled.
+const SRAM2_RASR: u32 = (1 << 28) | (0b011 << 24) | (0b001 << 19) | (1 << 18) | (13 << 1) | 1;
+/// `MPU_CTRL`: enable, with the default memory map for everything outside the regions.
+const MPU_CTRL_ENABLE_PRIVDEFENA: u32 = 0b101;
+
+/// Value aligned to a cache line, so that invalidating it can't discard a neighbour's data.
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(C, align(32))]
+pub struct CacheAligned<T>(pub T);
+
+impl<T> Deref for CacheAligned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CacheAligned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// Make SRAM2 non-cacheable with MPU region 0. Must run before the data cache is enabled.
+pub fn configure_mpu(mpu: &mut MPU) {
+    asm::dmb();
+    // SAFETY: region 0 only changes the attributes of SRAM2, which holds nothing but `.dma`;
+    // every other address keeps the default map.
+    unsafe {
+        mpu.ctrl.write(0);
+        mpu.rnr.write(0);
+        mpu.rbar.write(SRAM2.start as u32);
+        mpu.rasr.write(SRAM2_RASR);
+        mpu.ctrl.write(MPU_CTRL_ENABLE_PRIVDEFENA);
+    }
+    asm::dsb();
+    asm::isb();
+}
+
+/// True if `len` bytes at `addr` lie in memory the data cache doesn't cover (DTCM or SRAM2),
+/// so DMA can use them without cache maintenance.
+pub fn is_uncached(addr: usize, len: usize) -> bool {
+    let end = addr + len;
+    [DTCM, SRAM2]
+        .iter()
+        .any(|r| r.start <= addr && end <= r.end)
+}
+
+/// Write `buf` back from the cache so that DMA reading it sees what the CPU wrote.
+pub fn before_dma_read<T>(buf: &[T]) {
+    let (addr, len) = (buf.as_ptr() as usize, core::mem::size_of_val(buf));
+    if is_uncached(addr, len) {
+        return;
+    }
+    // SAFETY: DCCMVAC is a write-only maintenance register; cleaning only writes back.
+    let cbp = unsafe { &*CBP::PTR };
+    asm::dsb();
+    let mut line = addr & !(CACHE_LINE - 1);
+    while line < addr + len {
+        unsafe { cbp.dccmvac.write(line as u32) };
+        line += CACHE_LINE;
+    }
+    asm::dsb();
+}
+
+/// Drop cached copies of `buf` so that the CPU reads what DMA wrote. `buf` must start and end
+/// on cache line boundaries (see [`CacheAligned`]), or data next to it may be lost.
+pub fn after_dma_write<T>(buf: &mut [T]) {
+    let (addr, len) = (buf.as_ptr() as usize, core::mem::size_of_val(buf));
+    if is_uncached(addr, len) {
+        return;
+    }
+    debug_assert!(addr % CACHE_LINE == 0 && len % CACHE_LINE == 0);
+    cpu::invalidate_dcache_range(addr, len);
+}
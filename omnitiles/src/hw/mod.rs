@@ -9,32 +9,89 @@
 //! ## Modules
 //!
 //! - [`pins_v1`] - OmniTiles STM32F777 pin assignments for PCB v1
+//! - [`pin_map`] – Pin tables and the compile-time pin conflict check
 //! - [`led`] – Active-high / active-low LED wrapper
+//! - [`button`] – Debounced push-button with short/long press events and optional EXTI
+//! - [`exti`] – Routing GPIO pins to EXTI lines for edge interrupts
+//! - [`halls`] – Three-sensor hall input: sector decoding, coarse position and velocity
 //! - [`usart`] – Blocking TX helpers with `core::fmt::Write` impl
 //! - [`spi`] – Blocking byte-level SPI and reusable CS abstraction
 //! - [`i2c`] – Blocking I2C bus wrapper
-//! - [`can`] – Safe wrapper around `bxcan` with blocking send/receive
+//! - [`can`] – `CanFrame`/`CanInterface`, a `bxcan` wrapper with RX queues and dual-bus setup
 //! - [`encoder`] – TIM2/TIM3 quadrature encoder mode
 //! - [`adc`] – ADC1/ADC2/ADC3 single-channel blocking reads
+//! - [`adc_dma`] – ADC3 free-running channel scan into a circular DMA buffer
+//! - [`dac`] – DAC outputs for watching control signals on an oscilloscope
+//! - [`tick_timer`] – Periodic TIM7 interrupt for fast inner loops
+//! - [`clock`] – Monotonic 64-bit microsecond clock on the DWT cycle counter
+//! - [`cpu`] – Core setup ([`init`]: FPU, lazy stacking, caches) and the float rules for ISRs
+//! - [`flash`] – Internal flash sector erase and programming
+//! - [`memory`] – RAM regions, the non-cacheable DMA region and cache maintenance for DMA buffers
+//! - [`backup_sram`] – Battery-backed SRAM that survives resets
+//! - [`iwdg`] – Independent watchdog and the `run_step` convention for long operations
+//! - [`reset_reason`] – Cause of the last reset from the RCC flags
+//! - [`stack`] – Stack painting, high-water-mark scan and the optional MPU stack guard
+//! - [`rtc`] – Calendar RTC keeping Unix wall-clock time across resets
+//! - `sdmmc` – microSD card on SDMMC2 as an `embedded-sdmmc` block device (feature `sd-log`)
+//! - `qspi` – External NOR flash on QUADSPI holding the fault black box (feature `qspi-flash`)
+//! - `eth` – Ethernet MAC, smoltcp stack and UDP telemetry/command endpoint (feature `ethernet`)
 
 pub mod adc;
+pub mod adc_dma;
+pub mod backup_sram;
+pub mod button;
 pub mod can;
+pub mod clock;
+pub mod cpu;
+pub mod dac;
 pub mod encoder;
+#[cfg(feature = "ethernet")]
+pub mod eth;
+pub mod exti;
+pub mod flash;
+pub mod halls;
 pub mod i2c;
+pub mod iwdg;
 pub mod led;
+pub mod memory;
+pub mod pin_map;
 pub mod pins_f767zi;
 pub mod pins_v1;
 pub mod pins_v2;
+#[cfg(feature = "qspi-flash")]
+pub mod qspi;
+pub mod reset_reason;
+pub mod rtc;
+#[cfg(feature = "sd-log")]
+pub mod sdmmc;
 pub mod spi;
+pub mod stack;
+pub mod tick_timer;
 pub mod usart;
 
 pub use adc::Adc;
-pub use can::CanBus;
+pub use adc_dma::AdcDma;
+pub use button::{Button, ButtonEvent};
+pub use can::{CanBus, CanError, CanFrame, CanId, CanInterface, DualCan, RxQueue};
+pub use clock::MonoClock;
+pub use cpu::init;
+pub use dac::Dac;
 pub use encoder::Encoder;
+#[cfg(feature = "ethernet")]
+pub use eth::Eth;
+pub use flash::Flash;
+pub use halls::{HallTracker, Halls};
 pub use i2c::I2cBus;
+pub use iwdg::Iwdg;
 pub use led::Led;
+pub use memory::CacheAligned;
 pub use pins_v2::BoardPins;
+pub use reset_reason::ResetReason;
+pub use rtc::Rtc;
+pub use spi::BusError;
 pub use spi::ChipSelect;
 pub use spi::NoChipSelect;
 pub use spi::SpiBus;
+pub use stack::StackMonitor;
+pub use tick_timer::TickTimer;
 pub use usart::Usart;
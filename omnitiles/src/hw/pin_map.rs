@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Static pin tables for conflict checking across board revisions.
+//!
+//! The `BoardPins` typestate already stops one pin from being taken twice inside a single
+//! `BoardPins::new`. It can't see pins claimed elsewhere (drivers that take a raw pin,
+//! feature-gated groups) or two pins routed to the same peripheral signal, such as TIM3 CH1
+//! driving M1 PWM while an encoder also expects it as an input. Each `pins_*` module therefore
+//! lists what it uses in a [`PinUse`] table and asserts at compile time that [`find_conflict`]
+//! comes back empty, so a board revision that reuses a pin or a timer channel fails to build
+//! instead of misbehaving on the bench.
+//!
+//! Tables must be kept in step with the `BoardPins` structs they describe; when adding a pin, add
+//! its row in the same change.
+
+/// How a pin is configured.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mode {
+    /// Push-pull or open-drain GPIO output.
+    Output,
+    /// GPIO input.
+    Input,
+    /// Analog (ADC) input.
+    Analog,
+    /// Alternate function `AFn`.
+    Alternate(u8),
+}
+
+/// One pin claimed by a board function.
+#[derive(Copy, Clone, Debug)]
+pub struct PinUse {
+    /// GPIO port letter, `'A'`..`'K'`.
+    pub port: char,
+    pub pin: u8,
+    pub mode: Mode,
+    /// Peripheral signal the pin is routed to, e.g. `"TIM3_CH1"`, or `""` for plain GPIO.
+    /// Two rows may not share a signal.
+    pub signal: &'static str,
+    /// Board function using the pin, for error messages.
+    pub owner: &'static str,
+}
+
+/// A pin-table row.
+pub const fn pin(
+    port: char,
+    pin: u8,
+    mode: Mode,
+    signal: &'static str,
+    owner: &'static str,
+) -> PinUse {
+    PinUse {
+        port,
+        pin,
+        mode,
+        signal,
+        owner,
+    }
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn conflicts(a: &PinUse, b: &PinUse) -> bool {
+    (a.port == b.port && a.pin == b.pin) || (!a.signal.is_empty() && str_eq(a.signal, b.signal))
+}
+
+/// First pair of rows across `groups` that claim the same pin or the same peripheral signal.
+pub const fn find_conflict(groups: &[&[PinUse]]) -> Option<(PinUse, PinUse)> {
+    let mut g = 0;
+    while g < groups.len() {
+        let mut i = 0;
+        while i < groups[g].len() {
+            let a = groups[g][i];
+            // Compare against every later row, in this group and the ones after it.
+            let mut h = g;
+            let mut j = i + 1;
+            while h < groups.len() {
+                while j < groups[h].len() {
+                    if conflicts(&a, &groups[h][j]) {
+                        return Some((a, groups[h][j]));
+                    }
+                    j += 1;
+                }
+                h += 1;
+                j = 0;
+            }
+            i += 1;
+        }
+        g += 1;
+    }
+    None
+}
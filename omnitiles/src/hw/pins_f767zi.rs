@@ -2,6 +2,15 @@
 // © 2025–2026 Christopher Liu
 
 //! Pin definitions for STM32F767ZI devboard.
+//!
+//! ## Alternates
+//!
+//! - SPI1 sits on PA5–PA7, so the TIM3 encoder inputs PA6/PA7 used on PCB v1 aren't available;
+//!   TIM3 CH1–CH4 drive the M1/M2 PWM inputs anyway.
+//! - `mobile-base` adds the TB6612 wheel pins, with TIM4 CH1–CH4 for PWM.
+//!
+//! [`PIN_MAP`] and [`WHEEL_PIN_MAP`] are checked for conflicts at compile time, see
+//! [`pin_map`](super::pin_map).
 
 use stm32f7xx_hal::{
     gpio::{
@@ -13,8 +22,12 @@ use stm32f7xx_hal::{
 #[cfg(feature = "mobile-base")]
 use stm32f7xx_hal::gpio::gpioe;
 
+use super::pin_map::{find_conflict, pin, Mode, PinUse};
+
 pub struct BoardPins {
     pub leds: Leds,
+    /// B1 user button, active high.
+    pub button: gpioc::PC13<Input<Floating>>,
     pub usart3: Usart3Pins,
     pub spi1: Spi1Pins,
     pub m1: Motor1Pins,
@@ -82,6 +95,56 @@ pub struct WheelPins {
     pub br_in2: gpioa::PA8<Output<PushPull>>,
 }
 
+/// Pins claimed by [`BoardPins`] without optional features.
+pub const PIN_MAP: &[PinUse] = &[
+    pin('B', 0, Mode::Output, "", "LED green"),
+    pin('B', 7, Mode::Output, "", "LED blue"),
+    pin('B', 14, Mode::Output, "", "LED red"),
+    pin('C', 13, Mode::Input, "", "User button B1"),
+    pin('D', 8, Mode::Alternate(7), "USART3_TX", "USART3 TX"),
+    pin('D', 9, Mode::Alternate(7), "USART3_RX", "USART3 RX"),
+    pin('A', 5, Mode::Alternate(5), "SPI1_SCK", "SPI1 SCK"),
+    pin('A', 6, Mode::Alternate(5), "SPI1_MISO", "SPI1 MISO"),
+    pin('A', 7, Mode::Alternate(5), "SPI1_MOSI", "SPI1 MOSI"),
+    pin('C', 3, Mode::Output, "", "SPI1 CS"),
+    pin('A', 3, Mode::Input, "", "SPI1 DRDY"),
+    pin('C', 6, Mode::Alternate(2), "TIM3_CH1", "M1 IN1 (PWM)"),
+    pin('C', 7, Mode::Alternate(2), "TIM3_CH2", "M1 IN2 (PWM)"),
+    pin('D', 0, Mode::Output, "", "M1 CS"),
+    pin('D', 1, Mode::Output, "", "M1 nSLEEP"),
+    pin('D', 2, Mode::Output, "", "M1 DISABLE"),
+    pin('B', 1, Mode::Analog, "ADC1_IN9", "M1 pot"),
+    pin('C', 8, Mode::Alternate(2), "TIM3_CH3", "M2 IN1 (PWM)"),
+    pin('C', 9, Mode::Alternate(2), "TIM3_CH4", "M2 IN2 (PWM)"),
+    pin('D', 3, Mode::Output, "", "M2 CS"),
+    pin('D', 4, Mode::Output, "", "M2 nSLEEP"),
+    pin('D', 5, Mode::Output, "", "M2 DISABLE"),
+    pin('C', 2, Mode::Analog, "ADC1_IN12", "M2 pot"),
+    pin('B', 8, Mode::Alternate(4), "I2C1_SCL", "I2C1 SCL"),
+    pin('B', 9, Mode::Alternate(4), "I2C1_SDA", "I2C1 SDA"),
+];
+
+/// Pins added by the `mobile-base` feature.
+pub const WHEEL_PIN_MAP: &[PinUse] = &[
+    pin('D', 12, Mode::Alternate(2), "TIM4_CH1", "FL wheel PWM"),
+    pin('D', 6, Mode::Output, "", "FL wheel IN1"),
+    pin('D', 7, Mode::Output, "", "FL wheel IN2"),
+    pin('D', 13, Mode::Alternate(2), "TIM4_CH2", "FR wheel PWM"),
+    pin('D', 11, Mode::Output, "", "FR wheel IN1"),
+    pin('E', 2, Mode::Output, "", "FR wheel IN2"),
+    pin('D', 14, Mode::Alternate(2), "TIM4_CH3", "BL wheel PWM"),
+    pin('A', 1, Mode::Output, "", "BL wheel IN1"),
+    pin('A', 2, Mode::Output, "", "BL wheel IN2"),
+    pin('D', 15, Mode::Alternate(2), "TIM4_CH4", "BR wheel PWM"),
+    pin('A', 4, Mode::Output, "", "BR wheel IN1"),
+    pin('A', 8, Mode::Output, "", "BR wheel IN2"),
+];
+
+const _: () = assert!(
+    find_conflict(&[PIN_MAP, WHEEL_PIN_MAP]).is_none(),
+    "pins_f767zi: two functions claim the same pin or peripheral signal"
+);
+
 impl BoardPins {
     pub fn new(
         gpioa: pac::GPIOA,
@@ -104,6 +167,8 @@ impl BoardPins {
                 red: gpiob.pb14.into_push_pull_output(),
             },
 
+            button: gpioc.pc13.into_floating_input(),
+
             usart3: Usart3Pins {
                 tx: gpiod.pd8.into_alternate::<7>(),
                 rx: gpiod.pd9.into_alternate::<7>(),
@@ -2,6 +2,15 @@
 // © 2025–2026 Christopher Liu
 
 //! Pin definitions for STM32F777 MCU for OmniTiles PCB v1.
+//!
+//! ## Alternates
+//!
+//! - TIM2 and TIM3 both run quadrature encoders (PA0/PA1 and PA6/PA7), so the motor inputs are
+//!   plain GPIO and there's no hardware PWM on this board.
+//! - The WS2812 edge strip driver needs PA0 (TIM5 CH1), which is the TIM2 encoder's CH1 here.
+//!
+//! [`PIN_MAP`] lists every pin and is checked for conflicts at compile time, see
+//! [`pin_map`](super::pin_map).
 
 use stm32f7xx_hal::{
     gpio::{
@@ -12,6 +21,8 @@ use stm32f7xx_hal::{
     prelude::*,
 };
 
+use super::pin_map::{find_conflict, pin, Mode, PinUse};
+
 /// All board pins. Construct this once at startup.
 pub struct BoardPins {
     pub leds: LedPins,
@@ -87,6 +98,47 @@ pub struct Can2Pins {
     pub rx: gpiob::PB12<Alternate<9>>,
 }
 
+/// Pins claimed by [`BoardPins`].
+pub const PIN_MAP: &[PinUse] = &[
+    pin('D', 8, Mode::Output, "", "LED red"),
+    pin('D', 9, Mode::Output, "", "LED yellow"),
+    pin('D', 10, Mode::Output, "", "LED green"),
+    pin('A', 9, Mode::Alternate(7), "USART1_TX", "USART1 TX"),
+    pin('A', 10, Mode::Alternate(7), "USART1_RX", "USART1 RX"),
+    pin('E', 12, Mode::Alternate(5), "SPI4_SCK", "SPI4 SCK"),
+    pin('E', 13, Mode::Alternate(5), "SPI4_MISO", "SPI4 MISO"),
+    pin('E', 14, Mode::Alternate(5), "SPI4_MOSI", "SPI4 MOSI"),
+    pin('E', 4, Mode::Output, "", "SPI4 CS1"),
+    pin('E', 11, Mode::Output, "", "SPI4 CS2"),
+    pin('A', 0, Mode::Alternate(1), "TIM2_CH1", "M1 encoder A"),
+    pin('A', 1, Mode::Alternate(1), "TIM2_CH2", "M1 encoder B"),
+    pin('A', 6, Mode::Alternate(2), "TIM3_CH1", "M2 encoder A"),
+    pin('A', 7, Mode::Alternate(2), "TIM3_CH2", "M2 encoder B"),
+    pin('H', 1, Mode::Output, "", "M1 IN1"),
+    pin('C', 0, Mode::Output, "", "M1 IN2"),
+    pin('A', 4, Mode::Output, "", "M1 nSLEEP"),
+    pin('A', 3, Mode::Output, "", "M1 DISABLE"),
+    pin('A', 2, Mode::Input, "", "M1 nFAULT"),
+    pin('C', 4, Mode::Analog, "ADC1_IN14", "M1 IPROPI1"),
+    pin('C', 5, Mode::Analog, "ADC1_IN15", "M1 IPROPI2"),
+    pin('D', 3, Mode::Output, "", "M2 IN1"),
+    pin('D', 4, Mode::Output, "", "M2 IN2"),
+    pin('D', 2, Mode::Output, "", "M2 nSLEEP"),
+    pin('D', 1, Mode::Output, "", "M2 DISABLE"),
+    pin('D', 0, Mode::Input, "", "M2 nFAULT"),
+    pin('C', 2, Mode::Analog, "ADC1_IN12", "M2 IPROPI1"),
+    pin('C', 3, Mode::Analog, "ADC1_IN13", "M2 IPROPI2"),
+    pin('A', 12, Mode::Alternate(9), "CAN1_TX", "CAN1 TX"),
+    pin('A', 11, Mode::Alternate(9), "CAN1_RX", "CAN1 RX"),
+    pin('B', 13, Mode::Alternate(9), "CAN2_TX", "CAN2 TX"),
+    pin('B', 12, Mode::Alternate(9), "CAN2_RX", "CAN2 RX"),
+];
+
+const _: () = assert!(
+    find_conflict(&[PIN_MAP]).is_none(),
+    "pins_v1: two functions claim the same pin or peripheral signal"
+);
+
 impl BoardPins {
     /// Create all named pins from raw GPIO peripherals.
     pub fn new(
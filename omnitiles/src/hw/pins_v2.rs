@@ -2,7 +2,46 @@
 // © 2025–2026 Christopher Liu
 
 //! Pin definitions for STM32F777 MCU Devboard for OmniTiles.
+//!
+//! ## Alternates
+//!
+//! - TIM3 CH1–CH3 drive the M1/M2 PWM inputs, so TIM3 can't run an encoder on this board.
+//! - `mobile-base` adds the TB6612 wheel pins on PD3/PD6/PD7/PD11-PD15 and PE0-PE2/PE6, with
+//!   TIM4 CH1–CH4 for PWM.
+//! - `load-cell` adds an HX711 load-cell amplifier on PE7 (PD_SCK) and PE8 (DOUT).
+//! - `ethernet` adds RMII to an external PHY: REF_CLK on PA1, CRS_DV on PA7, RXD0/RXD1 on
+//!   PC4/PC5, TX_EN on PB11 and TXD0/TXD1 on PB12/PB13. RXD0/RXD1 have no other pins, so the
+//!   first pot of each axis is dropped; TXD0/TXD1 rule out `can` and REF_CLK rules out
+//!   `qspi-flash`. The PHY runs on its strap settings, so MDIO/MDC are not used.
+//! - `drv-spi` adds DRV8873 chip selects for M1 on PE9 and M2 on PE10, sharing SPI4 with the IMU,
+//!   for boards with the driver SPI lines routed.
+//! - `current-loop` adds M1's DRV8873 IPROPI on PA3 (ADC3_IN3), sampled by the ADC3 scan for the
+//!   current loop. TIM7 runs the loop.
+//! - `supply-sense` adds the bus voltage divider (100 kΩ over 10 kΩ) on PC2 (ADC123_IN12).
+//! - `motor-ntc` adds the motor can thermistors, M1 on PA6 (ADC12_IN6) and M2 on PB0 (ADC12_IN8).
+//! - `encoder` adds the M1 motor encoder on TIM2, A on PA15 (CH1) and B on PB3 (CH2). PB3 is also
+//!   an SD data line, so it can't be combined with `sd-log`, and it takes over SWO.
+//! - `edge-leds` adds the WS2812 edge strip on PA0 (TIM5 CH1, AF2), which rules out TIM2 CH1 on
+//!   the same pin.
+//! - `sd-log` adds a microSD socket on SDMMC2 (PD6/PD7, PB14/PB15/PB3/PB4). PD6/PD7 are also wheel
+//!   pins, so it can't be combined with `mobile-base`, and PB3 takes over SWO.
+//! - `qspi-flash` adds an external NOR flash on QUADSPI bank 1 (PB2/PB10, PC9/PC10, PE2, PA1).
+//!   PE2 is also a wheel pin, so it can't be combined with `mobile-base`.
+//! - `fan` adds a cooling fan PWM output on PB8 (TIM10 CH1).
+//! - `button` adds the service button on PC13, active low with the internal pull-up.
+//! - `can` adds CAN1, the inter-tile backbone, on PA11/PA12 and CAN2, the motor bus, on
+//!   PB12/PB13.
+//!
+//! [`PIN_MAP`], [`WHEEL_PIN_MAP`], [`OPTIONAL_PIN_MAP`], [`SD_PIN_MAP`], [`QSPI_PIN_MAP`],
+//! [`FAN_PIN_MAP`], [`BUTTON_PIN_MAP`], [`CAN_PIN_MAP`], [`LOAD_CELL_PIN_MAP`],
+//! [`DRV_SPI_PIN_MAP`], [`IPROPI_PIN_MAP`], [`SUPPLY_PIN_MAP`], [`NTC_PIN_MAP`],
+//! [`ENCODER_PIN_MAP`], [`POT1_PIN_MAP`] and [`ETH_PIN_MAP`] list every pin and are checked for
+//! conflicts at compile time, see [`pin_map`](super::pin_map).
 
+#[cfg(feature = "button")]
+use stm32f7xx_hal::gpio::PullUp;
+#[cfg(any(feature = "sd-log", feature = "qspi-flash", feature = "ethernet"))]
+use stm32f7xx_hal::gpio::Speed;
 use stm32f7xx_hal::{
     gpio::{
         gpioa, gpiob, gpioc, gpiod, gpioe, Alternate, Analog, Floating, Input, OpenDrain, Output,
@@ -12,6 +51,8 @@ use stm32f7xx_hal::{
     prelude::*,
 };
 
+use super::pin_map::{find_conflict, pin, Mode, PinUse};
+
 /// All board pins. Construct this once at startup.
 pub struct BoardPins {
     pub leds: LedPins,
@@ -20,8 +61,35 @@ pub struct BoardPins {
     pub m1: Motor1Pins,
     pub m2: Motor2Pins,
     pub i2c1: I2c1Pins,
+    pub dac: DacPins,
     #[cfg(feature = "mobile-base")]
     pub wheels: WheelPins,
+    #[cfg(feature = "sd-log")]
+    pub sd: SdPins,
+    #[cfg(feature = "qspi-flash")]
+    pub qspi: QspiPins,
+    #[cfg(feature = "fan")]
+    pub fan: FanPins,
+    #[cfg(feature = "button")]
+    pub button: ButtonPins,
+    #[cfg(feature = "can")]
+    pub can: CanPins,
+    #[cfg(feature = "edge-leds")]
+    pub edge_leds: EdgeLedPins,
+    #[cfg(feature = "load-cell")]
+    pub load_cell: LoadCellPins,
+    #[cfg(feature = "drv-spi")]
+    pub drv_spi: DrvSpiPins,
+    #[cfg(feature = "current-loop")]
+    pub ipropi: IpropiPins,
+    #[cfg(feature = "supply-sense")]
+    pub supply: SupplyPins,
+    #[cfg(feature = "motor-ntc")]
+    pub ntc: NtcPins,
+    #[cfg(feature = "encoder")]
+    pub encoder: EncoderPins,
+    #[cfg(feature = "ethernet")]
+    pub rmii: RmiiPins,
 }
 
 pub struct LedPins {
@@ -51,6 +119,7 @@ pub struct Motor1Pins {
     pub in2: gpioc::PC7<Alternate<2>>, // TIM3_CH2 (PWM)
     pub nsleep: gpiod::PD2<Output<PushPull>>,
     pub disable: gpiod::PD1<Output<PushPull>>,
+    #[cfg(not(feature = "ethernet"))]
     pub adc1: gpioc::PC4<Analog>, // ADC1_IN14
     pub adc2: gpiob::PB1<Analog>, // ADC1_IN9
     pub adc3: gpioc::PC0<Analog>, // ADC1_IN10
@@ -62,6 +131,7 @@ pub struct Motor2Pins {
     pub in2: gpioa::PA8<Alternate<1>>, // TIM1_CH1 (PWM)
     pub nsleep: gpiod::PD4<Output<PushPull>>,
     pub disable: gpiod::PD5<Output<PushPull>>,
+    #[cfg(not(feature = "ethernet"))]
     pub adc1: gpioc::PC5<Analog>, // ADC1_IN15
     pub adc2: gpioc::PC3<Analog>, // ADC1_IN13
 }
@@ -71,6 +141,113 @@ pub struct I2c1Pins {
     pub sda: gpiob::PB9<Alternate<4, OpenDrain>>,
 }
 
+/// DAC outputs for scope probing, see [`dac`](super::dac).
+pub struct DacPins {
+    pub out1: gpioa::PA4<Analog>,
+    pub out2: gpioa::PA5<Analog>,
+}
+
+/// SDMMC2 in 4-bit mode. CMD and data lines are pulled up.
+#[cfg(feature = "sd-log")]
+pub struct SdPins {
+    pub ck: gpiod::PD6<Alternate<11>>,
+    pub cmd: gpiod::PD7<Alternate<11>>,
+    pub d0: gpiob::PB14<Alternate<10>>,
+    pub d1: gpiob::PB15<Alternate<10>>,
+    pub d2: gpiob::PB3<Alternate<10>>,
+    pub d3: gpiob::PB4<Alternate<10>>,
+}
+
+/// QUADSPI bank 1 to the external NOR flash.
+#[cfg(feature = "qspi-flash")]
+pub struct QspiPins {
+    pub clk: gpiob::PB2<Alternate<9>>,
+    pub ncs: gpiob::PB10<Alternate<9>>,
+    pub io0: gpioc::PC9<Alternate<9>>,
+    pub io1: gpioc::PC10<Alternate<9>>,
+    pub io2: gpioe::PE2<Alternate<9>>,
+    pub io3: gpioa::PA1<Alternate<9>>,
+}
+
+/// Cooling fan PWM, see [`Fan`](crate::drivers::fan::Fan).
+#[cfg(feature = "fan")]
+pub struct FanPins {
+    pub pwm: gpiob::PB8<Alternate<3>>, // TIM10_CH1
+}
+
+/// Service button, see [`Button`](crate::hw::button::Button).
+#[cfg(feature = "button")]
+pub struct ButtonPins {
+    pub service: gpioc::PC13<Input<PullUp>>, // active low
+}
+
+/// CAN1 (backbone) and CAN2 (motor bus) transceivers, see [`DualCan`](crate::hw::DualCan).
+#[cfg(feature = "can")]
+pub struct CanPins {
+    pub can1_tx: gpioa::PA12<Alternate<9>>,
+    pub can1_rx: gpioa::PA11<Alternate<9>>,
+    pub can2_tx: gpiob::PB13<Alternate<9>>,
+    pub can2_rx: gpiob::PB12<Alternate<9>>,
+}
+
+/// WS2812 edge strip data line, see [`Ws2812`](crate::drivers::Ws2812).
+#[cfg(feature = "edge-leds")]
+pub struct EdgeLedPins {
+    pub data: gpioa::PA0<Alternate<2>>, // TIM5_CH1
+}
+
+/// HX711 under the tile surface, see [`Hx711`](crate::drivers::Hx711).
+#[cfg(feature = "load-cell")]
+pub struct LoadCellPins {
+    pub sck: gpioe::PE7<Output<PushPull>>,
+    pub dout: gpioe::PE8<Input<Floating>>,
+}
+
+/// RMII to the Ethernet PHY, see [`eth`](crate::hw::eth).
+#[cfg(feature = "ethernet")]
+pub struct RmiiPins {
+    pub ref_clk: gpioa::PA1<Alternate<11>>,
+    pub crs_dv: gpioa::PA7<Alternate<11>>,
+    pub rxd0: gpioc::PC4<Alternate<11>>,
+    pub rxd1: gpioc::PC5<Alternate<11>>,
+    pub tx_en: gpiob::PB11<Alternate<11>>,
+    pub txd0: gpiob::PB12<Alternate<11>>,
+    pub txd1: gpiob::PB13<Alternate<11>>,
+}
+
+/// DRV8873 chip selects on SPI4, see [`Drv8873`](crate::drivers::Drv8873).
+#[cfg(feature = "drv-spi")]
+pub struct DrvSpiPins {
+    pub m1_cs: gpioe::PE9<Output<PushPull>>,
+    pub m2_cs: gpioe::PE10<Output<PushPull>>,
+}
+
+/// DRV8873 current sense, see [`current_loop`](crate::control::current_loop).
+#[cfg(feature = "current-loop")]
+pub struct IpropiPins {
+    pub m1: gpioa::PA3<Analog>, // ADC3_IN3
+}
+
+/// Bus voltage divider, see [`supply`](crate::sensors::supply).
+#[cfg(feature = "supply-sense")]
+pub struct SupplyPins {
+    pub bus: gpioc::PC2<Analog>, // ADC123_IN12
+}
+
+/// Motor can thermistors, see [`motor_temp`](crate::sensors::motor_temp).
+#[cfg(feature = "motor-ntc")]
+pub struct NtcPins {
+    pub m1: gpioa::PA6<Analog>, // ADC12_IN6
+    pub m2: gpiob::PB0<Analog>, // ADC12_IN8
+}
+
+/// M1 motor encoder, see [`Encoder`](crate::hw::Encoder).
+#[cfg(feature = "encoder")]
+pub struct EncoderPins {
+    pub a: gpioa::PA15<Alternate<1>>, // TIM2_CH1
+    pub b: gpiob::PB3<Alternate<1>>,  // TIM2_CH2
+}
+
 #[cfg(feature = "mobile-base")]
 pub struct WheelPins {
     pub fl_pwm: gpiod::PD12<Alternate<2>>, // TIM4_CH1
@@ -87,6 +264,218 @@ pub struct WheelPins {
     pub br_in2: gpioe::PE6<Output<PushPull>>,
 }
 
+/// Pins claimed by [`BoardPins`] without optional features.
+pub const PIN_MAP: &[PinUse] = &[
+    pin('D', 8, Mode::Output, "", "LED red"),
+    pin('D', 9, Mode::Output, "", "LED yellow"),
+    pin('D', 10, Mode::Output, "", "LED green"),
+    pin('A', 9, Mode::Alternate(7), "USART1_TX", "USART1 TX"),
+    pin('A', 10, Mode::Alternate(7), "USART1_RX", "USART1 RX"),
+    pin('E', 12, Mode::Alternate(5), "SPI4_SCK", "SPI4 SCK"),
+    pin('E', 5, Mode::Alternate(5), "SPI4_MISO", "SPI4 MISO"),
+    pin('E', 14, Mode::Alternate(5), "SPI4_MOSI", "SPI4 MOSI"),
+    pin('E', 4, Mode::Output, "", "SPI4 CS1 (UWB tag)"),
+    pin('E', 11, Mode::Input, "", "SPI4 DRDY"),
+    pin('E', 3, Mode::Output, "", "SPI4 CS2 (IMU)"),
+    pin('C', 6, Mode::Alternate(2), "TIM3_CH1", "M1 IN1 (PWM)"),
+    pin('C', 7, Mode::Alternate(2), "TIM3_CH2", "M1 IN2 (PWM)"),
+    pin('D', 2, Mode::Output, "", "M1 nSLEEP"),
+    pin('D', 1, Mode::Output, "", "M1 DISABLE"),
+    pin('B', 1, Mode::Analog, "ADC1_IN9", "M1 pot 2"),
+    pin('C', 0, Mode::Analog, "ADC1_IN10", "M1 pot 3"),
+    pin('C', 1, Mode::Analog, "ADC1_IN11", "M1 pot 4"),
+    pin('C', 8, Mode::Alternate(2), "TIM3_CH3", "M2 IN1 (PWM)"),
+    pin('A', 8, Mode::Alternate(1), "TIM1_CH1", "M2 IN2 (PWM)"),
+    pin('D', 4, Mode::Output, "", "M2 nSLEEP"),
+    pin('D', 5, Mode::Output, "", "M2 DISABLE"),
+    pin('C', 3, Mode::Analog, "ADC1_IN13", "M2 pot 2"),
+    pin('B', 6, Mode::Alternate(4), "I2C1_SCL", "I2C1 SCL"),
+    pin('B', 9, Mode::Alternate(4), "I2C1_SDA", "I2C1 SDA"),
+    pin('A', 4, Mode::Analog, "DAC_OUT1", "DAC probe 1"),
+    pin('A', 5, Mode::Analog, "DAC_OUT2", "DAC probe 2"),
+];
+
+/// First pot of each axis, on the only RMII RXD0/RXD1 pins. Dropped by the `ethernet` feature.
+pub const POT1_PIN_MAP: &[PinUse] = &[
+    pin('C', 4, Mode::Analog, "ADC1_IN14", "M1 pot 1"),
+    pin('C', 5, Mode::Analog, "ADC1_IN15", "M2 pot 1"),
+];
+
+/// Pins added by the `mobile-base` feature.
+pub const WHEEL_PIN_MAP: &[PinUse] = &[
+    pin('D', 12, Mode::Alternate(2), "TIM4_CH1", "FL wheel PWM"),
+    pin('D', 3, Mode::Output, "", "FL wheel IN1"),
+    pin('D', 6, Mode::Output, "", "FL wheel IN2"),
+    pin('D', 13, Mode::Alternate(2), "TIM4_CH2", "FR wheel PWM"),
+    pin('D', 7, Mode::Output, "", "FR wheel IN1"),
+    pin('D', 11, Mode::Output, "", "FR wheel IN2"),
+    pin('D', 14, Mode::Alternate(2), "TIM4_CH3", "BL wheel PWM"),
+    pin('E', 0, Mode::Output, "", "BL wheel IN1"),
+    pin('E', 1, Mode::Output, "", "BL wheel IN2"),
+    pin('D', 15, Mode::Alternate(2), "TIM4_CH4", "BR wheel PWM"),
+    pin('E', 2, Mode::Output, "", "BR wheel IN1"),
+    pin('E', 6, Mode::Output, "", "BR wheel IN2"),
+];
+
+/// Pins added by the `edge-leds` feature.
+pub const OPTIONAL_PIN_MAP: &[PinUse] = &[pin('A', 0, Mode::Alternate(2), "TIM5_CH1", "WS2812")];
+
+/// Pins added by the `sd-log` feature.
+pub const SD_PIN_MAP: &[PinUse] = &[
+    pin('D', 6, Mode::Alternate(11), "SDMMC2_CK", "microSD CK"),
+    pin('D', 7, Mode::Alternate(11), "SDMMC2_CMD", "microSD CMD"),
+    pin('B', 14, Mode::Alternate(10), "SDMMC2_D0", "microSD D0"),
+    pin('B', 15, Mode::Alternate(10), "SDMMC2_D1", "microSD D1"),
+    pin('B', 3, Mode::Alternate(10), "SDMMC2_D2", "microSD D2"),
+    pin('B', 4, Mode::Alternate(10), "SDMMC2_D3", "microSD D3"),
+];
+
+/// Pins added by the `encoder` feature.
+pub const ENCODER_PIN_MAP: &[PinUse] = &[
+    pin('A', 15, Mode::Alternate(1), "TIM2_CH1", "M1 encoder A"),
+    pin('B', 3, Mode::Alternate(1), "TIM2_CH2", "M1 encoder B"),
+];
+
+const _: () = assert!(
+    find_conflict(&[
+        PIN_MAP,
+        POT1_PIN_MAP,
+        WHEEL_PIN_MAP,
+        OPTIONAL_PIN_MAP,
+        ENCODER_PIN_MAP
+    ])
+    .is_none(),
+    "pins_v2: two functions claim the same pin or peripheral signal"
+);
+
+/// Pins added by the `qspi-flash` feature.
+pub const QSPI_PIN_MAP: &[PinUse] = &[
+    pin('B', 2, Mode::Alternate(9), "QUADSPI_CLK", "QSPI CLK"),
+    pin('B', 10, Mode::Alternate(9), "QUADSPI_BK1_NCS", "QSPI nCS"),
+    pin('C', 9, Mode::Alternate(9), "QUADSPI_BK1_IO0", "QSPI IO0"),
+    pin('C', 10, Mode::Alternate(9), "QUADSPI_BK1_IO1", "QSPI IO1"),
+    pin('E', 2, Mode::Alternate(9), "QUADSPI_BK1_IO2", "QSPI IO2"),
+    pin('A', 1, Mode::Alternate(9), "QUADSPI_BK1_IO3", "QSPI IO3"),
+];
+
+/// Pins added by the `fan` feature.
+pub const FAN_PIN_MAP: &[PinUse] = &[pin('B', 8, Mode::Alternate(3), "TIM10_CH1", "Fan PWM")];
+
+/// Pins added by the `button` feature.
+pub const BUTTON_PIN_MAP: &[PinUse] = &[pin('C', 13, Mode::Input, "", "Service button")];
+
+/// Pins added by the `can` feature.
+pub const CAN_PIN_MAP: &[PinUse] = &[
+    pin('A', 12, Mode::Alternate(9), "CAN1_TX", "Backbone CAN TX"),
+    pin('A', 11, Mode::Alternate(9), "CAN1_RX", "Backbone CAN RX"),
+    pin('B', 13, Mode::Alternate(9), "CAN2_TX", "Motor CAN TX"),
+    pin('B', 12, Mode::Alternate(9), "CAN2_RX", "Motor CAN RX"),
+];
+
+/// Pins added by the `load-cell` feature.
+pub const LOAD_CELL_PIN_MAP: &[PinUse] = &[
+    pin('E', 7, Mode::Output, "", "HX711 PD_SCK"),
+    pin('E', 8, Mode::Input, "", "HX711 DOUT"),
+];
+
+/// Pins added by the `drv-spi` feature.
+pub const DRV_SPI_PIN_MAP: &[PinUse] = &[
+    pin('E', 9, Mode::Output, "", "SPI4 CS (M1 DRV8873)"),
+    pin('E', 10, Mode::Output, "", "SPI4 CS (M2 DRV8873)"),
+];
+
+/// Pins added by the `current-loop` feature.
+pub const IPROPI_PIN_MAP: &[PinUse] = &[pin('A', 3, Mode::Analog, "ADC3_IN3", "M1 IPROPI")];
+
+/// Pins added by the `supply-sense` feature.
+pub const SUPPLY_PIN_MAP: &[PinUse] = &[pin('C', 2, Mode::Analog, "ADC123_IN12", "Bus voltage")];
+
+/// Pins added by the `motor-ntc` feature.
+pub const NTC_PIN_MAP: &[PinUse] = &[
+    pin('A', 6, Mode::Analog, "ADC12_IN6", "M1 NTC"),
+    pin('B', 0, Mode::Analog, "ADC12_IN8", "M2 NTC"),
+];
+
+const _: () = assert!(
+    find_conflict(&[
+        PIN_MAP,
+        POT1_PIN_MAP,
+        OPTIONAL_PIN_MAP,
+        SD_PIN_MAP,
+        QSPI_PIN_MAP,
+        FAN_PIN_MAP,
+        BUTTON_PIN_MAP,
+        CAN_PIN_MAP,
+        LOAD_CELL_PIN_MAP,
+        DRV_SPI_PIN_MAP,
+        IPROPI_PIN_MAP,
+        SUPPLY_PIN_MAP,
+        NTC_PIN_MAP
+    ])
+    .is_none(),
+    "pins_v2: two functions claim the same pin or peripheral signal"
+);
+
+#[cfg(all(feature = "sd-log", feature = "mobile-base"))]
+const _: () = assert!(
+    find_conflict(&[WHEEL_PIN_MAP, SD_PIN_MAP]).is_none(),
+    "pins_v2: `sd-log` and `mobile-base` both need PD6/PD7"
+);
+
+#[cfg(all(feature = "sd-log", feature = "encoder"))]
+const _: () = assert!(
+    find_conflict(&[SD_PIN_MAP, ENCODER_PIN_MAP]).is_none(),
+    "pins_v2: `sd-log` and `encoder` both need PB3"
+);
+
+#[cfg(all(feature = "qspi-flash", feature = "mobile-base"))]
+const _: () = assert!(
+    find_conflict(&[WHEEL_PIN_MAP, QSPI_PIN_MAP]).is_none(),
+    "pins_v2: `qspi-flash` and `mobile-base` both need PE2"
+);
+
+/// Pins added by the `ethernet` feature.
+pub const ETH_PIN_MAP: &[PinUse] = &[
+    pin('A', 1, Mode::Alternate(11), "ETH_REF_CLK", "ETH REF_CLK"),
+    pin('A', 7, Mode::Alternate(11), "ETH_CRS_DV", "ETH CRS_DV"),
+    pin('C', 4, Mode::Alternate(11), "ETH_RXD0", "ETH RXD0"),
+    pin('C', 5, Mode::Alternate(11), "ETH_RXD1", "ETH RXD1"),
+    pin('B', 11, Mode::Alternate(11), "ETH_TX_EN", "ETH TX_EN"),
+    pin('B', 12, Mode::Alternate(11), "ETH_TXD0", "ETH TXD0"),
+    pin('B', 13, Mode::Alternate(11), "ETH_TXD1", "ETH TXD1"),
+];
+
+const _: () = assert!(
+    find_conflict(&[
+        PIN_MAP,
+        WHEEL_PIN_MAP,
+        OPTIONAL_PIN_MAP,
+        SD_PIN_MAP,
+        FAN_PIN_MAP,
+        BUTTON_PIN_MAP,
+        LOAD_CELL_PIN_MAP,
+        DRV_SPI_PIN_MAP,
+        IPROPI_PIN_MAP,
+        SUPPLY_PIN_MAP,
+        NTC_PIN_MAP,
+        ETH_PIN_MAP
+    ])
+    .is_none(),
+    "pins_v2: `ethernet` claims a pin or peripheral signal of another function"
+);
+
+#[cfg(all(feature = "ethernet", feature = "can"))]
+const _: () = assert!(
+    find_conflict(&[CAN_PIN_MAP, ETH_PIN_MAP]).is_none(),
+    "pins_v2: `ethernet` and `can` both need PB12/PB13"
+);
+
+#[cfg(all(feature = "ethernet", feature = "qspi-flash"))]
+const _: () = assert!(
+    find_conflict(&[QSPI_PIN_MAP, ETH_PIN_MAP]).is_none(),
+    "pins_v2: `ethernet` and `qspi-flash` both need PA1"
+);
+
 impl BoardPins {
     /// Create all named pins from raw GPIO peripherals.
     pub fn new(
@@ -128,6 +517,7 @@ impl BoardPins {
                 in2: gpioc.pc7.into_alternate::<2>(),
                 nsleep: gpiod.pd2.into_push_pull_output(),
                 disable: gpiod.pd1.into_push_pull_output(),
+                #[cfg(not(feature = "ethernet"))]
                 adc1: gpioc.pc4.into_analog(),
                 adc2: gpiob.pb1.into_analog(),
                 adc3: gpioc.pc0.into_analog(), // Inverted
@@ -139,6 +529,7 @@ impl BoardPins {
                 in2: gpioa.pa8.into_alternate::<1>(),
                 nsleep: gpiod.pd4.into_push_pull_output(),
                 disable: gpiod.pd5.into_push_pull_output(),
+                #[cfg(not(feature = "ethernet"))]
                 adc1: gpioc.pc5.into_analog(),
                 adc2: gpioc.pc3.into_analog(),
             },
@@ -156,6 +547,11 @@ impl BoardPins {
                     .set_open_drain(),
             },
 
+            dac: DacPins {
+                out1: gpioa.pa4.into_analog(),
+                out2: gpioa.pa5.into_analog(),
+            },
+
             #[cfg(feature = "mobile-base")]
             wheels: WheelPins {
                 fl_pwm: gpiod.pd12.into_alternate::<2>(),
@@ -171,6 +567,118 @@ impl BoardPins {
                 br_in1: gpioe.pe2.into_push_pull_output(),
                 br_in2: gpioe.pe6.into_push_pull_output(),
             },
+
+            #[cfg(feature = "sd-log")]
+            sd: SdPins {
+                ck: gpiod.pd6.into_alternate::<11>().set_speed(Speed::VeryHigh),
+                cmd: gpiod
+                    .pd7
+                    .into_alternate::<11>()
+                    .internal_pull_up(true)
+                    .set_speed(Speed::VeryHigh),
+                d0: gpiob
+                    .pb14
+                    .into_alternate::<10>()
+                    .internal_pull_up(true)
+                    .set_speed(Speed::VeryHigh),
+                d1: gpiob
+                    .pb15
+                    .into_alternate::<10>()
+                    .internal_pull_up(true)
+                    .set_speed(Speed::VeryHigh),
+                d2: gpiob
+                    .pb3
+                    .into_alternate::<10>()
+                    .internal_pull_up(true)
+                    .set_speed(Speed::VeryHigh),
+                d3: gpiob
+                    .pb4
+                    .into_alternate::<10>()
+                    .internal_pull_up(true)
+                    .set_speed(Speed::VeryHigh),
+            },
+
+            #[cfg(feature = "qspi-flash")]
+            qspi: QspiPins {
+                clk: gpiob.pb2.into_alternate::<9>().set_speed(Speed::VeryHigh),
+                ncs: gpiob
+                    .pb10
+                    .into_alternate::<9>()
+                    .internal_pull_up(true)
+                    .set_speed(Speed::VeryHigh),
+                io0: gpioc.pc9.into_alternate::<9>().set_speed(Speed::VeryHigh),
+                io1: gpioc.pc10.into_alternate::<9>().set_speed(Speed::VeryHigh),
+                io2: gpioe.pe2.into_alternate::<9>().set_speed(Speed::VeryHigh),
+                io3: gpioa.pa1.into_alternate::<9>().set_speed(Speed::VeryHigh),
+            },
+
+            #[cfg(feature = "fan")]
+            fan: FanPins {
+                pwm: gpiob.pb8.into_alternate::<3>(),
+            },
+
+            #[cfg(feature = "button")]
+            button: ButtonPins {
+                service: gpioc.pc13.into_pull_up_input(),
+            },
+
+            #[cfg(feature = "can")]
+            can: CanPins {
+                can1_tx: gpioa.pa12.into_alternate::<9>(),
+                can1_rx: gpioa.pa11.into_alternate::<9>(),
+                can2_tx: gpiob.pb13.into_alternate::<9>(),
+                can2_rx: gpiob.pb12.into_alternate::<9>(),
+            },
+
+            #[cfg(feature = "edge-leds")]
+            edge_leds: EdgeLedPins {
+                data: gpioa.pa0.into_alternate::<2>(),
+            },
+
+            #[cfg(feature = "load-cell")]
+            load_cell: LoadCellPins {
+                sck: gpioe.pe7.into_push_pull_output(),
+                dout: gpioe.pe8.into_floating_input(),
+            },
+
+            #[cfg(feature = "ethernet")]
+            rmii: RmiiPins {
+                ref_clk: gpioa.pa1.into_alternate::<11>().set_speed(Speed::VeryHigh),
+                crs_dv: gpioa.pa7.into_alternate::<11>().set_speed(Speed::VeryHigh),
+                rxd0: gpioc.pc4.into_alternate::<11>().set_speed(Speed::VeryHigh),
+                rxd1: gpioc.pc5.into_alternate::<11>().set_speed(Speed::VeryHigh),
+                tx_en: gpiob.pb11.into_alternate::<11>().set_speed(Speed::VeryHigh),
+                txd0: gpiob.pb12.into_alternate::<11>().set_speed(Speed::VeryHigh),
+                txd1: gpiob.pb13.into_alternate::<11>().set_speed(Speed::VeryHigh),
+            },
+
+            #[cfg(feature = "drv-spi")]
+            drv_spi: DrvSpiPins {
+                m1_cs: gpioe.pe9.into_push_pull_output(),
+                m2_cs: gpioe.pe10.into_push_pull_output(),
+            },
+
+            #[cfg(feature = "current-loop")]
+            ipropi: IpropiPins {
+                m1: gpioa.pa3.into_analog(),
+            },
+
+            #[cfg(feature = "supply-sense")]
+            supply: SupplyPins {
+                bus: gpioc.pc2.into_analog(),
+            },
+
+            #[cfg(feature = "motor-ntc")]
+            ntc: NtcPins {
+                m1: gpioa.pa6.into_analog(),
+                m2: gpiob.pb0.into_analog(),
+            },
+
+            #[cfg(feature = "encoder")]
+            encoder: EncoderPins {
+                a: gpioa.pa15.into_alternate::<1>(),
+                b: gpiob.pb3.into_alternate::<1>(),
+            },
         }
     }
 }
@@ -0,0 +1,468 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Blocking driver for an external serial NOR flash on QUADSPI bank 1.
+//!
+//! Written against the Winbond W25Q128JV (16 MiB, 4 KiB sectors, 256-byte pages); other 25-series
+//! parts with the same commands and a Quad Enable bit in status register 2 work too. Reads use
+//! Fast Read Quad Output and programming Quad Page Program, with instruction and address on one
+//! line. The kernel clock is HCLK.
+//!
+//! The chip holds one [`Region`], [`BLACK_BOX`], where [`BlackBox`] keeps fault snapshots across
+//! power cycles. Further users get their own regions, so erasing one never touches another.
+//!
+//! NOR programming can only clear bits: erase a range before writing it again. Erases take
+//! tens of milliseconds per sector and block the caller. Requires the `qspi-flash` feature.
+
+use cortex_m::peripheral::DWT;
+use stm32f7xx_hal::pac;
+
+use super::iwdg;
+
+/// Highest bus clock used; the W25Q128JV takes 133 MHz for quad reads.
+const MAX_CLOCK_HZ: u32 = 104_000_000;
+
+pub const PAGE_LEN: u32 = 256;
+/// Smallest erasable unit.
+pub const SECTOR_LEN: u32 = 4 * 1024;
+/// Unit of the faster block erase.
+const BLOCK_LEN: u32 = 64 * 1024;
+
+/// Bytes the region layout needs; smaller chips are rejected by [`Qspi::init`].
+pub const LAYOUT_LEN: u32 = 16 * 1024 * 1024;
+
+/// A fixed range of the external flash.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub start: u32,
+    pub len: u32,
+}
+
+impl Region {
+    pub const fn end(&self) -> u32 {
+        self.start + self.len
+    }
+
+    /// Chip address of `len` bytes at `offset` into the region.
+    pub fn addr(&self, offset: u32, len: usize) -> Result<u32, Error> {
+        let len = u32::try_from(len).map_err(|_| Error::OutOfRange)?;
+        let end = offset.checked_add(len).ok_or(Error::OutOfRange)?;
+        if end > self.len {
+            return Err(Error::OutOfRange);
+        }
+        Ok(self.start + offset)
+    }
+}
+
+/// Black-box recorder, the whole chip.
+pub const BLACK_BOX: Region = Region {
+    start: 0,
+    len: LAYOUT_LEN,
+};
+
+const _: () = assert!(
+    BLACK_BOX.start % BLOCK_LEN == 0 && BLACK_BOX.end() == LAYOUT_LEN,
+    "qspi: regions must be block-aligned and fill the layout"
+);
+
+/// Bytes per [`BlackBox`] record slot: a length byte, then up to 63 payload bytes.
+pub const RECORD_LEN: u32 = 64;
+
+// Flash commands.
+const CMD_WRITE_ENABLE: u8 = 0x06;
+const CMD_READ_STATUS1: u8 = 0x05;
+const CMD_READ_STATUS2: u8 = 0x35;
+const CMD_WRITE_STATUS2: u8 = 0x31;
+const CMD_JEDEC_ID: u8 = 0x9F;
+const CMD_FAST_READ_QUAD: u8 = 0x6B;
+const CMD_QUAD_PAGE_PROGRAM: u8 = 0x32;
+const CMD_SECTOR_ERASE: u8 = 0x20;
+const CMD_BLOCK_ERASE: u8 = 0xD8;
+
+/// Status register 1 BUSY bit.
+const STATUS1_BUSY: u8 = 1 << 0;
+/// Status register 2 Quad Enable bit.
+const STATUS2_QE: u8 = 1 << 1;
+/// Dummy cycles after the address for Fast Read Quad Output.
+const FAST_READ_DUMMY: u32 = 8;
+
+// CR fields.
+const CR_EN: u32 = 1 << 0;
+const CR_ABORT: u32 = 1 << 1;
+const CR_SSHIFT: u32 = 1 << 4;
+
+// CCR fields.
+const CCR_IMODE_1: u32 = 0b01 << 8;
+const CCR_ADMODE_1: u32 = 0b01 << 10;
+const CCR_ADSIZE_24: u32 = 0b10 << 12;
+const CCR_DMODE_1: u32 = 0b01 << 24;
+const CCR_DMODE_4: u32 = 0b11 << 24;
+const CCR_FMODE_READ: u32 = 0b01 << 26;
+
+// SR flags.
+const SR_TEF: u32 = 1 << 0;
+const SR_TCF: u32 = 1 << 1;
+const SR_BUSY: u32 = 1 << 5;
+const SR_FLEVEL_SHIFT: u32 = 8;
+
+/// Longest a program or erase may take before it is given up on: a 64 KB block erase, with
+/// margin.
+const BUSY_TIMEOUT_MS: u32 = 3000;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The JEDEC ID reads as all zeros or all ones: no chip, or not powered.
+    NoChip,
+    /// The chip is smaller than [`LAYOUT_LEN`].
+    TooSmall,
+    /// The address range runs past the region or chip.
+    OutOfRange,
+    /// An erase address or length isn't sector-aligned.
+    Alignment,
+    /// QUADSPI flagged a transfer error.
+    Transfer,
+    /// The chip stayed busy after a program or erase.
+    Busy,
+    /// [`Qspi::init`] hasn't succeeded yet.
+    NotReady,
+}
+
+/// QUADSPI with a NOR flash on bank 1. The pins are set up by `BoardPins` (see
+/// `QSPI_PIN_MAP`).
+pub struct Qspi {
+    regs: pac::QUADSPI,
+    hclk_hz: u32,
+    jedec_id: [u8; 3],
+    size: u32,
+    ready: bool,
+}
+
+impl Qspi {
+    /// Clock QUADSPI and configure it for indirect transfers. The chip is left alone until
+    /// [`init`](Self::init).
+    pub fn new(regs: pac::QUADSPI, hclk_hz: u32) -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.ahb3enr.modify(|_, w| w.qspien().set_bit());
+
+        let prescaler = hclk_hz.div_ceil(MAX_CLOCK_HZ).saturating_sub(1).min(0xFF);
+        regs.cr
+            .write(|w| unsafe { w.bits(prescaler << 24 | CR_SSHIFT | CR_EN) });
+        // CS high for at least 2 cycles between commands; FSIZE is set once the size is known.
+        regs.dcr.write(|w| unsafe { w.bits(1 << 8 | 23 << 16) });
+        Self {
+            regs,
+            hclk_hz,
+            jedec_id: [0; 3],
+            size: 0,
+            ready: false,
+        }
+    }
+
+    /// Identify the chip, check it holds the region layout and enable quad I/O.
+    pub fn init(&mut self) -> Result<(), Error> {
+        self.ready = false;
+        let mut id = [0u8; 3];
+        self.read_command(CMD_JEDEC_ID, &mut id)?;
+        if id == [0; 3] || id == [0xFF; 3] {
+            return Err(Error::NoChip);
+        }
+        self.jedec_id = id;
+        // The capacity byte is log2 of the size in bytes.
+        if (id[2] as u32) < LAYOUT_LEN.trailing_zeros() {
+            return Err(Error::TooSmall);
+        }
+        // Addresses are 24 bits, so only the first 16 MiB of a larger chip is used.
+        let log2_size = id[2].min(24) as u32;
+        self.size = 1 << log2_size;
+        self.regs
+            .dcr
+            .modify(|r, w| unsafe { w.bits(r.bits() & !(0x1F << 16) | (log2_size - 1) << 16) });
+
+        let mut status2 = [0u8];
+        self.read_command(CMD_READ_STATUS2, &mut status2)?;
+        if status2[0] & STATUS2_QE == 0 {
+            self.command(CMD_WRITE_ENABLE)?;
+            self.write_command(CMD_WRITE_STATUS2, &[status2[0] | STATUS2_QE])?;
+            self.wait_idle()?;
+        }
+
+        self.ready = true;
+        Ok(())
+    }
+
+    /// Manufacturer, memory type and capacity bytes, zero before [`init`](Self::init).
+    pub fn jedec_id(&self) -> [u8; 3] {
+        self.jedec_id
+    }
+
+    /// Chip size in bytes, 0 before [`init`](Self::init).
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Read `buf.len()` bytes starting at chip address `addr`.
+    pub fn read(&self, addr: u32, buf: &mut [u8]) -> Result<(), Error> {
+        self.check(addr, buf.len())?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let ccr = CMD_FAST_READ_QUAD as u32
+            | CCR_IMODE_1
+            | CCR_ADMODE_1
+            | CCR_ADSIZE_24
+            | FAST_READ_DUMMY << 18
+            | CCR_DMODE_4
+            | CCR_FMODE_READ;
+        self.start(ccr, Some(addr), buf.len());
+        self.read_fifo(buf)
+    }
+
+    /// Program `data` at chip address `addr`. The range must have been erased; page boundaries
+    /// are handled here.
+    pub fn write(&self, addr: u32, data: &[u8]) -> Result<(), Error> {
+        self.check(addr, data.len())?;
+        let mut addr = addr;
+        let mut data = data;
+        while !data.is_empty() {
+            let room = (PAGE_LEN - addr % PAGE_LEN) as usize;
+            let (page, rest) = data.split_at(room.min(data.len()));
+            self.command(CMD_WRITE_ENABLE)?;
+            let ccr = CMD_QUAD_PAGE_PROGRAM as u32
+                | CCR_IMODE_1
+                | CCR_ADMODE_1
+                | CCR_ADSIZE_24
+                | CCR_DMODE_4;
+            self.start(ccr, Some(addr), page.len());
+            self.write_fifo(page)?;
+            self.wait_idle()?;
+            addr += page.len() as u32;
+            data = rest;
+        }
+        Ok(())
+    }
+
+    /// Erase `len` bytes from `addr` to 0xFF. Both must be multiples of [`SECTOR_LEN`]; aligned
+    /// 64 KiB stretches use the faster block erase.
+    pub fn erase(&self, addr: u32, len: u32) -> Result<(), Error> {
+        self.check(addr, len as usize)?;
+        if addr % SECTOR_LEN != 0 || len % SECTOR_LEN != 0 {
+            return Err(Error::Alignment);
+        }
+        let end = addr + len;
+        let mut addr = addr;
+        while addr < end {
+            let (cmd, step) = if addr % BLOCK_LEN == 0 && end - addr >= BLOCK_LEN {
+                (CMD_BLOCK_ERASE, BLOCK_LEN)
+            } else {
+                (CMD_SECTOR_ERASE, SECTOR_LEN)
+            };
+            self.command(CMD_WRITE_ENABLE)?;
+            self.start(
+                cmd as u32 | CCR_IMODE_1 | CCR_ADMODE_1 | CCR_ADSIZE_24,
+                Some(addr),
+                0,
+            );
+            self.wait_transfer()?;
+            self.wait_idle()?;
+            addr += step;
+        }
+        Ok(())
+    }
+
+    /// Erase a whole region.
+    pub fn erase_region(&self, region: Region) -> Result<(), Error> {
+        self.erase(region.start, region.len)
+    }
+
+    fn check(&self, addr: u32, len: usize) -> Result<(), Error> {
+        if !self.ready {
+            return Err(Error::NotReady);
+        }
+        match u32::try_from(len)
+            .ok()
+            .and_then(|len| addr.checked_add(len))
+        {
+            Some(end) if end <= self.size => Ok(()),
+            _ => Err(Error::OutOfRange),
+        }
+    }
+
+    /// Set up an indirect transfer of `len` data bytes. Commands with an address start when it
+    /// is written, the others when CCR is.
+    fn start(&self, ccr: u32, addr: Option<u32>, len: usize) {
+        while self.regs.sr.read().bits() & SR_BUSY != 0 {}
+        self.regs.fcr.write(|w| unsafe { w.bits(SR_TEF | SR_TCF) });
+        if len > 0 {
+            self.regs.dlr.write(|w| unsafe { w.bits(len as u32 - 1) });
+        }
+        self.regs.ccr.write(|w| unsafe { w.bits(ccr) });
+        if let Some(addr) = addr {
+            self.regs.ar.write(|w| unsafe { w.bits(addr) });
+        }
+    }
+
+    /// Instruction-only command such as write enable.
+    fn command(&self, cmd: u8) -> Result<(), Error> {
+        self.start(cmd as u32 | CCR_IMODE_1, None, 0);
+        self.wait_transfer()
+    }
+
+    /// Instruction followed by `buf.len()` bytes read on one line.
+    fn read_command(&self, cmd: u8, buf: &mut [u8]) -> Result<(), Error> {
+        let ccr = cmd as u32 | CCR_IMODE_1 | CCR_DMODE_1 | CCR_FMODE_READ;
+        self.start(ccr, None, buf.len());
+        self.read_fifo(buf)
+    }
+
+    /// Instruction followed by `data` written on one line.
+    fn write_command(&self, cmd: u8, data: &[u8]) -> Result<(), Error> {
+        self.start(cmd as u32 | CCR_IMODE_1 | CCR_DMODE_1, None, data.len());
+        self.write_fifo(data)
+    }
+
+    /// Byte-wide access to DR, so each access moves one byte through the FIFO.
+    fn dr(&self) -> *mut u8 {
+        self.regs.dr.as_ptr() as *mut u8
+    }
+
+    fn read_fifo(&self, buf: &mut [u8]) -> Result<(), Error> {
+        for b in buf.iter_mut() {
+            loop {
+                let sr = self.regs.sr.read().bits();
+                if sr & SR_TEF != 0 {
+                    return self.fail();
+                }
+                if (sr >> SR_FLEVEL_SHIFT) & 0x3F != 0 {
+                    break;
+                }
+            }
+            *b = unsafe { core::ptr::read_volatile(self.dr()) };
+        }
+        self.wait_transfer()
+    }
+
+    fn write_fifo(&self, data: &[u8]) -> Result<(), Error> {
+        for &b in data {
+            // The FIFO holds 32 bytes.
+            loop {
+                let sr = self.regs.sr.read().bits();
+                if sr & SR_TEF != 0 {
+                    return self.fail();
+                }
+                if (sr >> SR_FLEVEL_SHIFT) & 0x3F < 32 {
+                    break;
+                }
+            }
+            unsafe { core::ptr::write_volatile(self.dr(), b) };
+        }
+        self.wait_transfer()
+    }
+
+    /// Wait for the transfer complete flag and clear it.
+    fn wait_transfer(&self) -> Result<(), Error> {
+        loop {
+            let sr = self.regs.sr.read().bits();
+            if sr & SR_TEF != 0 {
+                return self.fail();
+            }
+            if sr & SR_TCF != 0 {
+                break;
+            }
+        }
+        self.regs.fcr.write(|w| unsafe { w.bits(SR_TCF) });
+        Ok(())
+    }
+
+    /// Abort the transfer in progress after an error.
+    fn fail(&self) -> Result<(), Error> {
+        self.regs
+            .cr
+            .modify(|r, w| unsafe { w.bits(r.bits() | CR_ABORT) });
+        while self.regs.cr.read().bits() & CR_ABORT != 0 {}
+        self.regs.fcr.write(|w| unsafe { w.bits(SR_TEF | SR_TCF) });
+        Err(Error::Transfer)
+    }
+
+    /// Poll status register 1 until a program, erase or status write finishes, for up to
+    /// [`BUSY_TIMEOUT_MS`]. A block erase outlasts the watchdog, so it is fed while waiting.
+    fn wait_idle(&self) -> Result<(), Error> {
+        let timeout_cycles = self.hclk_hz / 1000 * BUSY_TIMEOUT_MS;
+        let start = DWT::cycle_count();
+        let mut status = [0u8];
+        loop {
+            iwdg::feed();
+            self.read_command(CMD_READ_STATUS1, &mut status)?;
+            if status[0] & STATUS1_BUSY == 0 {
+                return Ok(());
+            }
+            if DWT::cycle_count().wrapping_sub(start) > timeout_cycles {
+                return Err(Error::Busy);
+            }
+            cortex_m::asm::delay(self.hclk_hz / 1_000_000);
+        }
+    }
+}
+
+/// Append-only ring of fixed-size records in [`BLACK_BOX`].
+///
+/// Each [`RECORD_LEN`] slot holds a length byte and the payload; an erased slot reads 0xFF. The
+/// sector after the write position is always erased before the position enters the one before
+/// it, so after a reset [`open`](Self::open) finds the position as the first free slot of the last
+/// used sector ahead of an erased one. Once full, the oldest sector is erased and overwritten.
+pub struct BlackBox {
+    /// Offset of the next slot in the region.
+    next: u32,
+}
+
+impl BlackBox {
+    /// Find the write position left by earlier boots.
+    pub fn open(qspi: &Qspi) -> Result<Self, Error> {
+        let used = |offset: u32| -> Result<bool, Error> {
+            let mut len = [0u8];
+            qspi.read(BLACK_BOX.start + offset, &mut len)?;
+            Ok(len[0] != 0xFF)
+        };
+        let sectors = BLACK_BOX.len / SECTOR_LEN;
+        let mut head = 0;
+        for sector in 0..sectors {
+            let ahead = (sector + 1) % sectors;
+            if used(sector * SECTOR_LEN)? && !used(ahead * SECTOR_LEN)? {
+                head = sector;
+                break;
+            }
+        }
+        let mut next = head * SECTOR_LEN;
+        while next < (head + 1) * SECTOR_LEN && used(next)? {
+            next += RECORD_LEN;
+        }
+        Ok(Self {
+            next: next % BLACK_BOX.len,
+        })
+    }
+
+    /// Slot index the next record goes to.
+    pub fn position(&self) -> u32 {
+        self.next / RECORD_LEN
+    }
+
+    /// Append one record made of `parts`, truncated to the slot. Entering a new sector erases it
+    /// and the one after it, which blocks for up to a few hundred milliseconds.
+    pub fn append(&mut self, qspi: &Qspi, parts: &[&[u8]]) -> Result<(), Error> {
+        if self.next % SECTOR_LEN == 0 {
+            let ahead = (self.next + SECTOR_LEN) % BLACK_BOX.len;
+            qspi.erase(BLACK_BOX.start + self.next, SECTOR_LEN)?;
+            qspi.erase(BLACK_BOX.start + ahead, SECTOR_LEN)?;
+        }
+        let mut slot = [0u8; RECORD_LEN as usize];
+        let mut len = 0;
+        for part in parts {
+            let n = part.len().min(slot.len() - 1 - len);
+            slot[1 + len..1 + len + n].copy_from_slice(&part[..n]);
+            len += n;
+        }
+        slot[0] = len as u8;
+        qspi.write(BLACK_BOX.start + self.next, &slot[..1 + len])?;
+        self.next = (self.next + RECORD_LEN) % BLACK_BOX.len;
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Cause of the last reset, decoded from the RCC `CSR` flags.
+//!
+//! The flags are sticky across resets until cleared with `RMVF`, so several can be set at once
+//! (an IWDG reset also pulls NRST, so `PINRSTF` is set alongside `IWDGRSTF`). [`ResetReason`]
+//! reports the most specific one. [`take`] must run before `RCC` is constrained by the HAL.
+
+use stm32f7xx_hal::pac;
+
+/// Why the MCU last came out of reset. The discriminant is the value reported to the host.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResetReason {
+    /// No flag set (flags already cleared, or a debugger reset).
+    Unknown = 0,
+    /// Power-on or brownout reset.
+    PowerOn = 1,
+    /// External NRST pin.
+    Pin = 2,
+    /// `SYSRESETREQ`, e.g. after a firmware update.
+    Software = 3,
+    /// Independent watchdog expired.
+    IndependentWatchdog = 4,
+    /// Window watchdog expired.
+    WindowWatchdog = 5,
+    /// Illegal entry into Stop or Standby.
+    LowPower = 6,
+}
+
+impl ResetReason {
+    /// Decode a raw `RCC_CSR` value.
+    pub fn from_csr(csr: u32) -> Self {
+        const BORRSTF: u32 = 1 << 25;
+        const PINRSTF: u32 = 1 << 26;
+        const PORRSTF: u32 = 1 << 27;
+        const SFTRSTF: u32 = 1 << 28;
+        const IWDGRSTF: u32 = 1 << 29;
+        const WWDGRSTF: u32 = 1 << 30;
+        const LPWRRSTF: u32 = 1 << 31;
+
+        // Most specific first: every internal reset also drives NRST low.
+        if csr & IWDGRSTF != 0 {
+            ResetReason::IndependentWatchdog
+        } else if csr & WWDGRSTF != 0 {
+            ResetReason::WindowWatchdog
+        } else if csr & LPWRRSTF != 0 {
+            ResetReason::LowPower
+        } else if csr & SFTRSTF != 0 {
+            ResetReason::Software
+        } else if csr & (PORRSTF | BORRSTF) != 0 {
+            ResetReason::PowerOn
+        } else if csr & PINRSTF != 0 {
+            ResetReason::Pin
+        } else {
+            ResetReason::Unknown
+        }
+    }
+
+    /// Short label for the boot banner.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ResetReason::Unknown => "unknown",
+            ResetReason::PowerOn => "power-on",
+            ResetReason::Pin => "pin",
+            ResetReason::Software => "software",
+            ResetReason::IndependentWatchdog => "IWDG",
+            ResetReason::WindowWatchdog => "WWDG",
+            ResetReason::LowPower => "low-power",
+        }
+    }
+}
+
+/// Read the reset cause and clear the flags so the next boot reports only its own reset.
+pub fn take(rcc: &pac::RCC) -> ResetReason {
+    let reason = ResetReason::from_csr(rcc.csr.read().bits());
+    rcc.csr.modify(|_, w| w.rmvf().set_bit());
+    reason
+}
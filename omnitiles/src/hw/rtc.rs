@@ -0,0 +1,270 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Real-time clock holding wall-clock time as Unix seconds.
+//!
+//! The RTC lives in the backup domain, so once set it keeps counting through resets (and power
+//! loss, with VBAT). The host sets it with `MSG_SET_TIME`; until then [`Rtc::now`] returns `None`
+//! and timestamps fall back to time since boot.
+//!
+//! The clock runs from the 32.768 kHz LSE crystal if one oscillates, otherwise from the ~32 kHz
+//! LSI, which drifts by up to a few percent and needs periodic resync. The calendar covers the
+//! years 2000–2099.
+//!
+//! Writes to the backup domain need `DBP`, which [`BackupSram::new`] sets, so [`Rtc::new`] takes
+//! the backup SRAM handle as proof.
+
+use cortex_m::delay::Delay;
+use stm32f7xx_hal::pac;
+
+use super::{backup_sram::BackupSram, iwdg};
+
+/// `RCC_BDCR.RTCSEL` value for LSE.
+const RTCSEL_LSE: u8 = 0b01;
+/// `RCC_BDCR.RTCSEL` value for LSI.
+const RTCSEL_LSI: u8 = 0b10;
+/// Polls to wait for LSI or an RTC flag before giving up.
+const READY_TIMEOUT: u32 = 1_000_000;
+/// Milliseconds to wait for the LSE crystal, its worst-case startup time (`tSU(LSE)`, 2 s). Only
+/// paid on the first boot after the backup domain lost power, since a running RTC is left alone,
+/// and the watchdog is fed while waiting.
+const LSE_STARTUP_MS: u32 = 2000;
+/// Seconds per day.
+const DAY_S: u32 = 86_400;
+/// Days from 1970-01-01 to 2000-01-01.
+const EPOCH_2000_DAYS: u32 = 10_957;
+
+/// Oscillator clocking the RTC.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    Lse,
+    Lsi,
+}
+
+/// The RTC could not be brought up or written.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Neither LSE nor LSI became ready.
+    NoClock,
+    /// The RTC did not enter or leave initialization mode.
+    Timeout,
+    /// Time outside the 2000–2099 calendar range.
+    OutOfRange,
+}
+
+/// Calendar RTC wrapper.
+pub struct Rtc {
+    rtc: pac::RTC,
+    source: Source,
+}
+
+impl Rtc {
+    /// Start the RTC clock if it isn't already running. A running RTC keeps its time and clock
+    /// source, so a reset doesn't lose the wall clock. `delay` times the LSE startup wait.
+    pub fn new(rtc: pac::RTC, _backup: &BackupSram, delay: &mut Delay) -> Result<Self, Error> {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+
+        let bdcr = rcc.bdcr.read();
+        let source = if bdcr.rtcen().bit_is_set() {
+            if bdcr.rtcsel().bits() == RTCSEL_LSE {
+                Source::Lse
+            } else {
+                Source::Lsi
+            }
+        } else {
+            let source = if start_lse(rcc, delay) {
+                Source::Lse
+            } else if start_lsi(rcc) {
+                Source::Lsi
+            } else {
+                return Err(Error::NoClock);
+            };
+            let sel = match source {
+                Source::Lse => RTCSEL_LSE,
+                Source::Lsi => RTCSEL_LSI,
+            };
+            rcc.bdcr
+                .modify(|_, w| unsafe { w.rtcsel().bits(sel).rtcen().set_bit() });
+            source
+        };
+
+        let mut this = Self { rtc, source };
+        if !this.is_set() {
+            // Program the prescalers for a 1 Hz calendar clock. LSI is nominally 32 kHz.
+            let prediv_s = match source {
+                Source::Lse => 255,
+                Source::Lsi => 249,
+            };
+            this.init_mode(|rtc| {
+                rtc.prer
+                    .write(|w| unsafe { w.prediv_a().bits(127).prediv_s().bits(prediv_s) });
+            })?;
+        }
+        this.wait_sync()?;
+        Ok(this)
+    }
+
+    pub fn free(self) -> pac::RTC {
+        self.rtc
+    }
+
+    pub fn source(&self) -> Source {
+        self.source
+    }
+
+    /// True once the calendar has been set, either in this boot or an earlier one.
+    pub fn is_set(&self) -> bool {
+        self.rtc.isr.read().inits().bit_is_set()
+    }
+
+    /// Current Unix time in seconds, or `None` if the clock was never set.
+    pub fn now(&self) -> Option<u32> {
+        if !self.is_set() {
+            return None;
+        }
+        // Reading TR freezes DR until DR is read, so the pair is consistent.
+        let tr = self.rtc.tr.read().bits();
+        let dr = self.rtc.dr.read().bits();
+
+        let year = 2000 + bcd(dr >> 16, 0xFF);
+        let month = bcd(dr >> 8, 0x1F);
+        let day = bcd(dr, 0x3F);
+        let hours = bcd(tr >> 16, 0x3F);
+        let minutes = bcd(tr >> 8, 0x7F);
+        let seconds = bcd(tr, 0x7F);
+
+        let days = days_from_civil(year, month, day);
+        Some(days * DAY_S + hours * 3600 + minutes * 60 + seconds)
+    }
+
+    /// Set the calendar to `unix_s`.
+    pub fn set(&mut self, unix_s: u32) -> Result<(), Error> {
+        let days = unix_s / DAY_S;
+        if days < EPOCH_2000_DAYS {
+            return Err(Error::OutOfRange);
+        }
+        let (year, month, day) = civil_from_days(days);
+        if year > 2099 {
+            return Err(Error::OutOfRange);
+        }
+        let secs = unix_s % DAY_S;
+        // 1970-01-01 was a Thursday; the RTC numbers Monday as 1.
+        let weekday = (days + 3) % 7 + 1;
+
+        let tr = (to_bcd(secs / 3600) << 16) | (to_bcd(secs / 60 % 60) << 8) | to_bcd(secs % 60);
+        let dr = (to_bcd(year - 2000) << 16) | (weekday << 13) | (to_bcd(month) << 8) | to_bcd(day);
+
+        self.init_mode(|rtc| {
+            rtc.tr.write(|w| unsafe { w.bits(tr) });
+            rtc.dr.write(|w| unsafe { w.bits(dr) });
+        })?;
+        self.wait_sync()
+    }
+
+    /// Run `f` with the RTC stopped in initialization mode and write protection lifted.
+    fn init_mode(&mut self, f: impl FnOnce(&pac::RTC)) -> Result<(), Error> {
+        self.rtc.wpr.write(|w| unsafe { w.key().bits(0xCA) });
+        self.rtc.wpr.write(|w| unsafe { w.key().bits(0x53) });
+
+        self.rtc.isr.modify(|_, w| w.init().set_bit());
+        let mut ready = false;
+        for _ in 0..READY_TIMEOUT {
+            if self.rtc.isr.read().initf().bit_is_set() {
+                ready = true;
+                break;
+            }
+        }
+        if ready {
+            f(&self.rtc);
+        }
+
+        self.rtc.isr.modify(|_, w| w.init().clear_bit());
+        self.rtc.wpr.write(|w| unsafe { w.key().bits(0xFF) });
+        if ready {
+            Ok(())
+        } else {
+            Err(Error::Timeout)
+        }
+    }
+
+    /// Wait until the shadow registers reflect the calendar, as required after init or reset.
+    fn wait_sync(&mut self) -> Result<(), Error> {
+        self.rtc.wpr.write(|w| unsafe { w.key().bits(0xCA) });
+        self.rtc.wpr.write(|w| unsafe { w.key().bits(0x53) });
+        self.rtc.isr.modify(|_, w| w.rsf().clear_bit());
+        self.rtc.wpr.write(|w| unsafe { w.key().bits(0xFF) });
+
+        for _ in 0..READY_TIMEOUT {
+            if self.rtc.isr.read().rsf().bit_is_set() {
+                return Ok(());
+            }
+        }
+        Err(Error::Timeout)
+    }
+}
+
+fn start_lse(rcc: &pac::rcc::RegisterBlock, delay: &mut Delay) -> bool {
+    rcc.bdcr.modify(|_, w| w.lseon().set_bit());
+    for _ in 0..LSE_STARTUP_MS {
+        if rcc.bdcr.read().lserdy().bit_is_set() {
+            return true;
+        }
+        iwdg::feed();
+        delay.delay_ms(1);
+    }
+    rcc.bdcr.modify(|_, w| w.lseon().clear_bit());
+    false
+}
+
+fn start_lsi(rcc: &pac::rcc::RegisterBlock) -> bool {
+    rcc.csr.modify(|_, w| w.lsion().set_bit());
+    for _ in 0..READY_TIMEOUT {
+        if rcc.csr.read().lsirdy().bit_is_set() {
+            return true;
+        }
+    }
+    false
+}
+
+/// Decode the two-digit BCD field in the low bits of `v`, masked to its register width.
+/// Calendar date and time of `unix_s` as `(year, month, day, hours, minutes, seconds)`, with
+/// months and days counted from 1.
+pub fn civil_from_unix(unix_s: u32) -> (u32, u32, u32, u32, u32, u32) {
+    let (year, month, day) = civil_from_days(unix_s / DAY_S);
+    let secs = unix_s % DAY_S;
+    (year, month, day, secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn bcd(v: u32, mask: u32) -> u32 {
+    let v = v & mask;
+    (v >> 4) * 10 + (v & 0xF)
+}
+
+fn to_bcd(v: u32) -> u32 {
+    ((v / 10) << 4) | (v % 10)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: u32, month: u32, day: u32) -> u32 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of [`days_from_civil`]: `(year, month, day)`.
+fn civil_from_days(days: u32) -> (u32, u32, u32) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
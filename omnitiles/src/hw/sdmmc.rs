@@ -0,0 +1,383 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Blocking microSD driver on SDMMC2, exposed as an `embedded-sdmmc` block device.
+//!
+//! The card runs in 4-bit mode with hardware flow control, so polled FIFO access can't overrun
+//! however slowly the CPU drains it. The kernel clock is SYSCLK; identification runs at 400 kHz
+//! or just above, which [`Sdmmc::new`] can only reach with SYSCLK at 102 MHz or less, and data
+//! transfers at up to 25 MHz. SDSC (v1/v2) and SDHC/SDXC cards are supported; MMC is not.
+//!
+//! Transfers move one 512-byte block per command and wait for the card to finish programming
+//! after each write, which can take a few milliseconds. Requires the `sd-log` feature.
+
+use cortex_m::peripheral::DWT;
+use embedded_sdmmc::{Block, BlockCount, BlockDevice, BlockIdx};
+use stm32f7xx_hal::pac;
+
+use super::iwdg;
+
+const BLOCK_LEN: usize = 512;
+
+/// Identification-mode bus clock.
+const INIT_CLOCK_HZ: u32 = 400_000;
+/// Data-transfer bus clock ceiling (default speed mode).
+const DATA_CLOCK_HZ: u32 = 25_000_000;
+
+// CLKCR fields.
+const CLKCR_CLKEN: u32 = 1 << 8;
+const CLKCR_WIDBUS_4: u32 = 0b01 << 11;
+const CLKCR_HWFC_EN: u32 = 1 << 14;
+
+// CMD fields.
+const CMD_WAITRESP_SHORT: u32 = 0b01 << 6;
+const CMD_WAITRESP_LONG: u32 = 0b11 << 6;
+const CMD_CPSMEN: u32 = 1 << 10;
+
+// DCTRL fields.
+const DCTRL_DTEN: u32 = 1 << 0;
+const DCTRL_DTDIR_READ: u32 = 1 << 1;
+/// DBLOCKSIZE = log2(512).
+const DCTRL_BLOCK_512: u32 = 9 << 4;
+
+// STA flags.
+const STA_CCRCFAIL: u32 = 1 << 0;
+const STA_DCRCFAIL: u32 = 1 << 1;
+const STA_CTIMEOUT: u32 = 1 << 2;
+const STA_DTIMEOUT: u32 = 1 << 3;
+const STA_TXUNDERR: u32 = 1 << 4;
+const STA_RXOVERR: u32 = 1 << 5;
+const STA_CMDREND: u32 = 1 << 6;
+const STA_CMDSENT: u32 = 1 << 7;
+const STA_DATAEND: u32 = 1 << 8;
+const STA_TXFIFOHE: u32 = 1 << 14;
+const STA_RXFIFOHF: u32 = 1 << 15;
+const STA_RXDAVL: u32 = 1 << 21;
+/// Every static flag, for ICR.
+const STA_CLEAR_ALL: u32 = 0x0040_05FF;
+const STA_DATA_ERRORS: u32 = STA_DCRCFAIL | STA_DTIMEOUT | STA_TXUNDERR | STA_RXOVERR;
+
+/// Card status (R1) READY_FOR_DATA bit.
+const R1_READY_FOR_DATA: u32 = 1 << 8;
+/// Card status (R1) CURRENT_STATE field value for the transfer state.
+const R1_STATE_TRAN: u32 = 4;
+/// OCR bit set once the card has finished powering up.
+const OCR_POWERED_UP: u32 = 1 << 31;
+/// OCR bit set for SDHC/SDXC (block-addressed) cards.
+const OCR_CCS: u32 = 1 << 30;
+
+/// ACMD41 polls before giving up, 1 ms apart.
+const POWER_UP_TRIES: u32 = 1000;
+/// Longest a card may stay busy after a write: the SDXC write timeout.
+const BUSY_TIMEOUT_MS: u32 = 500;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The card didn't answer a command (no card, or not powered).
+    Timeout,
+    /// A command response failed its CRC.
+    CommandCrc,
+    /// The card never finished powering up or doesn't speak SD.
+    Unsupported,
+    /// A data block failed its CRC.
+    DataCrc,
+    /// The card stopped sending or accepting data.
+    DataTimeout,
+    /// The FIFO over- or underran.
+    Fifo,
+    /// The card stayed busy after a write.
+    Busy,
+    /// [`Sdmmc::init`] hasn't succeeded yet.
+    NotReady,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Response {
+    None,
+    Short,
+    /// R3 carries no valid CRC.
+    ShortNoCrc,
+    Long,
+}
+
+/// SDMMC2 with a microSD card. The pins are set up by `BoardPins` (see `SD_PIN_MAP`).
+pub struct Sdmmc {
+    regs: pac::SDMMC2,
+    sysclk_hz: u32,
+    /// Relative card address, shifted into the upper half of the argument.
+    rca: u32,
+    /// Block (SDHC/SDXC) rather than byte (SDSC) addressing.
+    high_capacity: bool,
+    blocks: u32,
+    ready: bool,
+}
+
+impl Sdmmc {
+    /// Clock SDMMC2 from SYSCLK. The card is left alone until [`init`](Self::init).
+    pub fn new(regs: pac::SDMMC2, sysclk_hz: u32) -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        // DCKCFGR2.SDMMC2SEL = SYSCLK.
+        rcc.dckcfgr2
+            .modify(|r, w| unsafe { w.bits(r.bits() | 1 << 29) });
+        rcc.apb2enr.modify(|_, w| w.sdmmc2en().set_bit());
+        Self {
+            regs,
+            sysclk_hz,
+            rca: 0,
+            high_capacity: false,
+            blocks: 0,
+            ready: false,
+        }
+    }
+
+    /// Card capacity in 512-byte blocks, 0 before [`init`](Self::init).
+    pub fn block_count(&self) -> u32 {
+        self.blocks
+    }
+
+    /// Power the bus, identify the card and switch it to 4-bit transfers.
+    pub fn init(&mut self) -> Result<(), Error> {
+        self.ready = false;
+        self.regs.power.write(|w| unsafe { w.bits(0b11) });
+        self.set_clock(INIT_CLOCK_HZ, 0);
+        // At least 74 clocks and 1 ms before the first command.
+        cortex_m::asm::delay(self.sysclk_hz / 500);
+
+        self.cmd(0, 0, Response::None)?;
+        // CMD8 is only answered by v2 cards; 0x1AA = 2.7–3.6 V, check pattern 0xAA.
+        let v2 = match self.cmd(8, 0x1AA, Response::Short) {
+            Ok(r7) if r7 & 0xFFF == 0x1AA => true,
+            Ok(_) => return Err(Error::Unsupported),
+            Err(Error::Timeout) => false,
+            Err(e) => return Err(e),
+        };
+
+        let hcs = if v2 { OCR_CCS } else { 0 };
+        let mut ocr = 0;
+        for _ in 0..POWER_UP_TRIES {
+            self.cmd(55, 0, Response::Short)?;
+            // 3.2–3.4 V window.
+            ocr = self.cmd(41, hcs | 0x0030_0000, Response::ShortNoCrc)?;
+            if ocr & OCR_POWERED_UP != 0 {
+                break;
+            }
+            cortex_m::asm::delay(self.sysclk_hz / 1000);
+        }
+        if ocr & OCR_POWERED_UP == 0 {
+            return Err(Error::Unsupported);
+        }
+        self.high_capacity = ocr & OCR_CCS != 0;
+
+        self.cmd(2, 0, Response::Long)?;
+        self.rca = self.cmd(3, 0, Response::Short)? & 0xFFFF_0000;
+        self.cmd(9, self.rca, Response::Long)?;
+        self.blocks = self.csd_blocks();
+
+        self.cmd(7, self.rca, Response::Short)?;
+        if !self.high_capacity {
+            self.cmd(16, BLOCK_LEN as u32, Response::Short)?;
+        }
+        self.cmd(55, self.rca, Response::Short)?;
+        self.cmd(6, 0b10, Response::Short)?;
+        self.set_clock(DATA_CLOCK_HZ, CLKCR_WIDBUS_4);
+
+        self.ready = true;
+        Ok(())
+    }
+
+    /// Capacity from the CSD in RESP1..RESP4 (bits 127:96 .. 31:0).
+    fn csd_blocks(&self) -> u32 {
+        let r2 = self.regs.resp2.read().bits();
+        let r3 = self.regs.resp3.read().bits();
+        if self.regs.resp1.read().bits() >> 30 == 1 {
+            // CSD v2: C_SIZE[69:48], capacity = (C_SIZE + 1) × 512 KiB.
+            let c_size = (r2 & 0x3F) << 16 | r3 >> 16;
+            (c_size + 1) * 1024
+        } else {
+            // CSD v1: C_SIZE[73:62], C_SIZE_MULT[49:47], READ_BL_LEN[83:80].
+            let c_size = (r2 & 0x3FF) << 2 | r3 >> 30;
+            let mult = (r3 >> 15) & 0x7;
+            let read_bl_len = (r2 >> 16) & 0xF;
+            ((c_size + 1) << (mult + 2 + read_bl_len)) / BLOCK_LEN as u32
+        }
+    }
+
+    /// Run the bus at `hz` or the next speed below it.
+    fn set_clock(&self, hz: u32, widbus: u32) {
+        let div = self.sysclk_hz.div_ceil(hz).saturating_sub(2).min(0xFF);
+        self.regs
+            .clkcr
+            .write(|w| unsafe { w.bits(div | CLKCR_CLKEN | CLKCR_HWFC_EN | widbus) });
+    }
+
+    fn cmd(&self, index: u8, arg: u32, response: Response) -> Result<u32, Error> {
+        self.regs.icr.write(|w| unsafe { w.bits(STA_CLEAR_ALL) });
+        self.regs.arg.write(|w| unsafe { w.bits(arg) });
+        let waitresp = match response {
+            Response::None => 0,
+            Response::Short | Response::ShortNoCrc => CMD_WAITRESP_SHORT,
+            Response::Long => CMD_WAITRESP_LONG,
+        };
+        self.regs
+            .cmd
+            .write(|w| unsafe { w.bits(index as u32 | waitresp | CMD_CPSMEN) });
+
+        let done = match response {
+            Response::None => STA_CMDSENT,
+            _ => STA_CMDREND | STA_CCRCFAIL | STA_CTIMEOUT,
+        };
+        let sta = loop {
+            let sta = self.regs.sta.read().bits();
+            if sta & done != 0 {
+                break sta;
+            }
+        };
+        self.regs.icr.write(|w| unsafe { w.bits(STA_CLEAR_ALL) });
+
+        if sta & STA_CTIMEOUT != 0 {
+            return Err(Error::Timeout);
+        }
+        if sta & STA_CCRCFAIL != 0 && response != Response::ShortNoCrc {
+            return Err(Error::CommandCrc);
+        }
+        Ok(self.regs.resp1.read().bits())
+    }
+
+    /// Card address argument for block `idx`.
+    fn address(&self, idx: u32) -> u32 {
+        if self.high_capacity {
+            idx
+        } else {
+            idx * BLOCK_LEN as u32
+        }
+    }
+
+    /// Arm the data path for one block in the given direction.
+    fn start_data(&self, read: bool) {
+        // Generous timeout in bus clocks; writes are bounded by the busy poll instead.
+        self.regs.dtimer.write(|w| unsafe { w.bits(0xFFFF_FFFF) });
+        self.regs
+            .dlen
+            .write(|w| unsafe { w.bits(BLOCK_LEN as u32) });
+        let dir = if read { DCTRL_DTDIR_READ } else { 0 };
+        self.regs
+            .dctrl
+            .write(|w| unsafe { w.bits(DCTRL_DTEN | DCTRL_BLOCK_512 | dir) });
+    }
+
+    /// Wait for the data path to finish and map its error flags.
+    fn finish_data(&self, sta: u32) -> Result<(), Error> {
+        self.regs.icr.write(|w| unsafe { w.bits(STA_CLEAR_ALL) });
+        self.regs.dctrl.write(|w| unsafe { w.bits(0) });
+        if sta & STA_DCRCFAIL != 0 {
+            Err(Error::DataCrc)
+        } else if sta & STA_DTIMEOUT != 0 {
+            Err(Error::DataTimeout)
+        } else if sta & (STA_TXUNDERR | STA_RXOVERR) != 0 {
+            Err(Error::Fifo)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read block `idx` into `out`.
+    pub fn read_block(&self, idx: u32, out: &mut [u8; BLOCK_LEN]) -> Result<(), Error> {
+        if !self.ready {
+            return Err(Error::NotReady);
+        }
+        self.start_data(true);
+        if let Err(e) = self.cmd(17, self.address(idx), Response::Short) {
+            self.regs.dctrl.write(|w| unsafe { w.bits(0) });
+            return Err(e);
+        }
+
+        let mut words = out.chunks_exact_mut(4);
+        let sta = loop {
+            let sta = self.regs.sta.read().bits();
+            if sta & STA_DATA_ERRORS != 0 {
+                break sta;
+            }
+            // Half full = 8 words.
+            let burst = if sta & STA_RXFIFOHF != 0 {
+                8
+            } else if sta & STA_RXDAVL != 0 {
+                1
+            } else if sta & STA_DATAEND != 0 {
+                break sta;
+            } else {
+                continue;
+            };
+            for _ in 0..burst {
+                let word = self.regs.fifo.read().bits().to_le_bytes();
+                if let Some(chunk) = words.next() {
+                    chunk.copy_from_slice(&word);
+                }
+            }
+        };
+        self.finish_data(sta)
+    }
+
+    /// Write `data` to block `idx` and wait for the card to finish programming it.
+    pub fn write_block(&self, idx: u32, data: &[u8; BLOCK_LEN]) -> Result<(), Error> {
+        if !self.ready {
+            return Err(Error::NotReady);
+        }
+        self.cmd(24, self.address(idx), Response::Short)?;
+        self.start_data(false);
+
+        let mut words = data.chunks_exact(4);
+        let sta = loop {
+            let sta = self.regs.sta.read().bits();
+            if sta & (STA_DATA_ERRORS | STA_DATAEND) != 0 {
+                break sta;
+            }
+            if sta & STA_TXFIFOHE != 0 {
+                for chunk in words.by_ref().take(8) {
+                    let word = u32::from_le_bytes(chunk.try_into().unwrap());
+                    self.regs.fifo.write(|w| unsafe { w.bits(word) });
+                }
+            }
+        };
+        self.finish_data(sta)?;
+        self.wait_ready()
+    }
+
+    /// Poll CMD13 until the card is back in the transfer state, for up to [`BUSY_TIMEOUT_MS`].
+    /// The watchdog is fed while waiting, since the timeout is close to its own.
+    fn wait_ready(&self) -> Result<(), Error> {
+        let timeout_cycles = self.sysclk_hz / 1000 * BUSY_TIMEOUT_MS;
+        let start = DWT::cycle_count();
+        loop {
+            iwdg::feed();
+            let status = self.cmd(13, self.rca, Response::Short)?;
+            if status & R1_READY_FOR_DATA != 0 && (status >> 9) & 0xF == R1_STATE_TRAN {
+                return Ok(());
+            }
+            if DWT::cycle_count().wrapping_sub(start) > timeout_cycles {
+                return Err(Error::Busy);
+            }
+        }
+    }
+}
+
+impl BlockDevice for Sdmmc {
+    type Error = Error;
+
+    fn read(&self, blocks: &mut [Block], start_block_idx: BlockIdx) -> Result<(), Error> {
+        for (i, block) in blocks.iter_mut().enumerate() {
+            self.read_block(start_block_idx.0 + i as u32, &mut block.contents)?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, blocks: &[Block], start_block_idx: BlockIdx) -> Result<(), Error> {
+        for (i, block) in blocks.iter().enumerate() {
+            self.write_block(start_block_idx.0 + i as u32, &block.contents)?;
+        }
+        Ok(())
+    }
+
+    fn num_blocks(&self) -> Result<BlockCount, Error> {
+        Ok(BlockCount(self.blocks))
+    }
+}
@@ -4,17 +4,98 @@
 //! Serial Peripheral Interface (SPI) abstraction layer.
 //!
 //! - `SpiBus` wraps a configured HAL SPI instance with 8-bit words.
-//! - `ChipSelect` is an active-low GPIO output wrappr for manual CS control.
+//! - `BusHealth` counts failed transfers so a dead bus is reported instead of read as data.
+//! - `SpiBus::with_phase` runs frames for a device that samples on the other clock edge, so
+//!   devices in different SPI modes can share one bus.
+//! - `ChipSelect` is an active-low GPIO output wrappr for manual CS control. The pin is erased, so
+//!   every chip select has the same type and drivers bound to different pins (e.g. several
+//!   `Drv8873<ChipSelect>`) fit in one array.
+//! - `CsControl` is object safe and implemented for `&mut` references, so a driver can also
+//!   borrow a `&mut dyn CsControl` when chip selects of different kinds have to be mixed.
 
 use stm32f7xx_hal::{
-    gpio::{self, Output, PinState, PushPull},
+    gpio::{self, ErasedPin, Output, PinState, PushPull},
+    pac::{self, spi1},
     prelude::*,
-    spi::{self, Enabled, Spi},
+    spi::{self, Enabled, Phase, Spi},
 };
 
+/// Attempts per checked transfer before the failure is returned to the caller.
+pub const MAX_ATTEMPTS: u8 = 3;
+
+/// Consecutive failed transfers after which the bus is considered failed.
+pub const FAILED_THRESHOLD: u8 = 4;
+
+/// Status polls allowed for the last frame to clock out before the clock phase is changed.
+const BSY_POLLS: u32 = 1000;
+
+/// Error from a checked transfer.
+#[derive(Copy, Clone, Debug)]
+pub enum BusError {
+    /// The peripheral reported an error (overrun, mode fault, ...).
+    Spi(spi::Error),
+    /// The transfer completed but every received byte was 0x00 or every byte was 0xFF, which is
+    /// what a disconnected or unpowered device reads as.
+    Implausible,
+}
+
+impl From<spi::Error> for BusError {
+    fn from(e: spi::Error) -> Self {
+        BusError::Spi(e)
+    }
+}
+
+/// Transfer error counters for one bus.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BusHealth {
+    /// Transfers that failed after all retries.
+    pub errors: u32,
+    /// Individual attempts that were retried.
+    pub retries: u32,
+    consecutive: u8,
+}
+
+impl BusHealth {
+    /// Record a successful transfer.
+    pub fn record_ok(&mut self) {
+        self.consecutive = 0;
+    }
+
+    /// Record a transfer that failed after all retries.
+    pub fn record_failure(&mut self) {
+        self.errors = self.errors.wrapping_add(1);
+        self.consecutive = self.consecutive.saturating_add(1);
+    }
+
+    /// True once [`FAILED_THRESHOLD`] transfers in a row have failed. Clears on the next success.
+    pub fn is_failed(&self) -> bool {
+        self.consecutive >= FAILED_THRESHOLD
+    }
+}
+
+/// True unless every byte in `rx` is 0x00 or every byte is 0xFF.
+fn plausible(rx: &[u8]) -> bool {
+    !(rx.iter().all(|&b| b == 0x00) || rx.iter().all(|&b| b == 0xFF))
+}
+
+/// SPI peripherals whose registers [`SpiBus`] can reach while the HAL owns them.
+pub trait SpiRegs {
+    fn regs() -> &'static spi1::RegisterBlock;
+}
+
+impl SpiRegs for pac::SPI4 {
+    fn regs() -> &'static spi1::RegisterBlock {
+        // SAFETY: only CR1 is touched, between frames, by the `SpiBus` that owns SPI4.
+        unsafe { &*pac::SPI4::ptr() }
+    }
+}
+
 /// Wrapper around an enabled HAL SPI instance (8-bit words).
 pub struct SpiBus<I, P> {
     spi: Spi<I, P, Enabled<u8>>,
+    health: BusHealth,
+    /// Set by [`SpiBus::with_phase_switching`].
+    regs: Option<&'static spi1::RegisterBlock>,
 }
 
 impl<I, P> SpiBus<I, P>
@@ -23,7 +104,61 @@ where
     P: spi::Pins<I>,
 {
     pub fn new(spi: Spi<I, P, Enabled<u8>>) -> Self {
-        Self { spi }
+        Self {
+            spi,
+            health: BusHealth::default(),
+            regs: None,
+        }
+    }
+
+    /// Let [`with_phase`](Self::with_phase) change the clock phase. Without this the bus keeps
+    /// the mode it was enabled in.
+    pub fn with_phase_switching(mut self) -> Self
+    where
+        I: SpiRegs,
+    {
+        self.regs = Some(I::regs());
+        self
+    }
+
+    /// Run `f` with the clock phase set to `phase`, then restore the phase the bus was in.
+    ///
+    /// The phase can only change with the peripheral disabled, so call this with every chip
+    /// select deasserted.
+    pub fn with_phase<R>(&mut self, phase: Phase, f: impl FnOnce(&mut Self) -> R) -> R {
+        let Some(regs) = self.regs else {
+            return f(self);
+        };
+        let second = matches!(phase, Phase::CaptureOnSecondTransition);
+        let previous = regs.cr1.read().cpha().bit_is_set();
+        if previous == second {
+            return f(self);
+        }
+        Self::set_cpha(regs, second);
+        let result = f(self);
+        Self::set_cpha(regs, previous);
+        result
+    }
+
+    fn set_cpha(regs: &spi1::RegisterBlock, second: bool) {
+        for _ in 0..BSY_POLLS {
+            if regs.sr.read().bsy().bit_is_clear() {
+                break;
+            }
+        }
+        regs.cr1.modify(|_, w| w.spe().clear_bit());
+        regs.cr1.modify(|_, w| w.cpha().bit(second));
+        regs.cr1.modify(|_, w| w.spe().set_bit());
+    }
+
+    /// Transfer error counters.
+    pub fn health(&self) -> &BusHealth {
+        &self.health
+    }
+
+    /// Mutable error counters, for callers that check their own transfers.
+    pub fn health_mut(&mut self) -> &mut BusHealth {
+        &mut self.health
     }
 
     /// Perform a blocking, full-duplex transfer of one byte.
@@ -54,6 +189,40 @@ where
         Ok(())
     }
 
+    /// Transfer `buf` in-place as one chip-select frame, retrying up to [`MAX_ATTEMPTS`] times.
+    ///
+    /// Only use this for devices that can never legitimately return all-zero or all-one data.
+    /// The plausibility check skips the first `header` received bytes, which are clocked in while
+    /// the device is still receiving the command. Every attempt resends the original `buf`.
+    pub fn transfer_checked<C: CsControl, const N: usize>(
+        &mut self,
+        cs: &mut C,
+        buf: &mut [u8; N],
+        header: usize,
+    ) -> Result<(), BusError> {
+        let mut err = BusError::Implausible;
+        for attempt in 0..MAX_ATTEMPTS {
+            if attempt > 0 {
+                self.health.retries = self.health.retries.wrapping_add(1);
+            }
+            let mut rx = *buf;
+            cs.select();
+            let res = self.transfer_in_place(&mut rx);
+            cs.deselect();
+            match res {
+                Ok(()) if plausible(&rx[header.min(N)..]) => {
+                    *buf = rx;
+                    self.health.record_ok();
+                    return Ok(());
+                }
+                Ok(()) => err = BusError::Implausible,
+                Err(e) => err = BusError::Spi(e),
+            }
+        }
+        self.health.record_failure();
+        Err(err)
+    }
+
     pub fn free(self) -> Spi<I, P, Enabled<u8>> {
         self.spi
     }
@@ -65,6 +234,17 @@ pub trait CsControl {
     fn deselect(&mut self);
 }
 
+impl<C: CsControl + ?Sized> CsControl for &mut C {
+    #[inline]
+    fn select(&mut self) {
+        (**self).select();
+    }
+    #[inline]
+    fn deselect(&mut self) {
+        (**self).deselect();
+    }
+}
+
 /// Stub chip-select for when no SPI connection is used.
 /// Panics if `select()` or `deselect()` is ever called.
 pub struct NoChipSelect;
@@ -78,17 +258,17 @@ impl CsControl for NoChipSelect {
     }
 }
 
-/// Manual chip-select line, active-low, generic over any GPIO pin.
-pub struct ChipSelect<const P: char, const N: u8> {
-    pin: gpio::Pin<P, N, Output<PushPull>>,
+/// Manual chip-select line, active-low, on any GPIO pin.
+pub struct ChipSelect {
+    pin: ErasedPin<Output<PushPull>>,
 }
 
-impl<const P: char, const N: u8> ChipSelect<P, N> {
+impl ChipSelect {
     /// Create an active-low chip select and set to the inactive state (i.e., high).
-    pub fn active_low<MODE>(pin: gpio::Pin<P, N, MODE>) -> Self {
+    pub fn active_low<const P: char, const N: u8, MODE>(pin: gpio::Pin<P, N, MODE>) -> Self {
         let mut pin = pin.into_push_pull_output();
         pin.set_state(PinState::High);
-        Self { pin }
+        Self { pin: pin.erase() }
     }
 
     /// Assert the chip select.
@@ -103,12 +283,12 @@ impl<const P: char, const N: u8> ChipSelect<P, N> {
         self.pin.set_high();
     }
 
-    pub fn free(self) -> gpio::Pin<P, N, Output<PushPull>> {
+    pub fn free(self) -> ErasedPin<Output<PushPull>> {
         self.pin
     }
 }
 
-impl<const P: char, const N: u8> CsControl for ChipSelect<P, N> {
+impl CsControl for ChipSelect {
     #[inline]
     fn select(&mut self) {
         self.pin.set_low();
@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Stack usage monitoring.
+//!
+//! The stack grows down from the top of SRAM1 toward `.bss`, and nothing stops it when the two
+//! meet: an overflow silently overwrites statics. With more of the control work moving into
+//! interrupts, whose frames stack on top of whatever the main task is doing, the margin has to be
+//! measured rather than guessed.
+//!
+//! [`paint`] fills the free stack with a known pattern at boot. [`StackMonitor`] later finds the
+//! lowest word that no longer holds it, which is the deepest the stack has ever reached. The scan
+//! covers the whole free stack, so it runs in chunks, one per [`run_step`](StackMonitor::run_step)
+//! call, like the other long operations (see [`iwdg`](super::iwdg)).
+//!
+//! With the `stack-guard` feature, [`install_guard`] also makes the lowest [`GUARD_LEN`] bytes of
+//! the stack inaccessible through the MPU. An overflow into them faults instead of corrupting
+//! `.bss`; the fault handler can't stack its frame either, so the core locks up until the
+//! watchdog resets it, and the boot banner reports an IWDG reset.
+
+use core::ptr;
+use core::task::Poll;
+
+#[cfg(feature = "stack-guard")]
+use cortex_m::{asm, peripheral::MPU};
+
+extern "C" {
+    /// Lowest stack address (end of `.uninit`), from the `cortex-m-rt` linker script.
+    static _stack_end: u32;
+    /// Initial stack pointer, the top of the stack.
+    static _stack_start: u32;
+}
+
+/// Pattern written over the free stack.
+const PAINT: u32 = 0xDEAD_BEEF;
+/// Space left unpainted below the stack pointer of [`paint`]'s own frame.
+const PAINT_MARGIN: usize = 256;
+/// Words checked per [`StackMonitor::run_step`] call; about 0.2 ms at the 16 MHz HSI SYSCLK.
+const SCAN_CHUNK_WORDS: usize = 512;
+
+/// Size of the MPU guard region at the bottom of the stack. An overflow is only caught if it
+/// touches the guard: a frame that reserves more than this and writes only its far end steps over
+/// it.
+pub const GUARD_LEN: usize = 256;
+/// `MPU_RASR` of the guard: execute never, no access, normal memory, 256 bytes (size field 7),
+/// enabled.
+#[cfg(feature = "stack-guard")]
+const GUARD_RASR: u32 = (1 << 28) | (0b001 << 19) | (7 << 1) | 1;
+/// MPU region of the guard; region 0 is the DMA region (see [`memory`](super::memory)).
+#[cfg(feature = "stack-guard")]
+const GUARD_REGION: u32 = 1;
+
+/// Lowest usable address above the guard region, and the top of the stack.
+fn bounds() -> (usize, usize) {
+    // SAFETY: only the addresses of the linker symbols are taken.
+    let (end, start) = unsafe {
+        (
+            ptr::addr_of!(_stack_end) as usize,
+            ptr::addr_of!(_stack_start) as usize,
+        )
+    };
+    ((end + GUARD_LEN).next_multiple_of(GUARD_LEN), start)
+}
+
+/// Base of the guard region.
+#[cfg(feature = "stack-guard")]
+fn guard_base() -> usize {
+    bounds().0 - GUARD_LEN
+}
+
+/// Usable stack size in bytes, excluding the guard region.
+pub fn size() -> usize {
+    let (bottom, top) = bounds();
+    top - bottom
+}
+
+/// Fill the free stack below the current frame with the paint pattern. Call once, first thing in
+/// `main`.
+pub fn paint() {
+    let (bottom, _) = bounds();
+    let sp = cortex_m::register::msp::read() as usize;
+    let mut addr = bottom;
+    while addr + PAINT_MARGIN < sp {
+        // SAFETY: the range lies between the end of `.uninit` and this frame, which nothing
+        // uses yet.
+        unsafe { ptr::write_volatile(addr as *mut u32, PAINT) };
+        addr += 4;
+    }
+}
+
+/// Protect the bottom of the stack with MPU region 1. Requires the MPU to be enabled already
+/// ([`cpu::init`](super::cpu::init) does that).
+#[cfg(feature = "stack-guard")]
+pub fn install_guard(mpu: &mut MPU) {
+    asm::dmb();
+    // SAFETY: the guard only covers stack the firmware never legitimately reaches.
+    unsafe {
+        mpu.rnr.write(GUARD_REGION);
+        mpu.rbar.write(guard_base() as u32);
+        mpu.rasr.write(GUARD_RASR);
+    }
+    asm::dsb();
+    asm::isb();
+}
+
+/// Incremental scan for the stack high-water mark.
+pub struct StackMonitor {
+    /// Next word to check in the current pass.
+    cursor: usize,
+    /// Deepest stack use found so far, in bytes.
+    peak: usize,
+}
+
+impl StackMonitor {
+    pub fn new() -> Self {
+        Self {
+            cursor: bounds().0,
+            peak: 0,
+        }
+    }
+
+    /// Check the next chunk of the stack. Returns the peak usage in bytes when a pass completes,
+    /// then starts the next pass.
+    pub fn run_step(&mut self) -> Poll<usize> {
+        let (bottom, top) = bounds();
+        // Everything above the previous mark is known to be used; the pass only has to look for
+        // a deeper one.
+        let end = top - self.peak;
+        for _ in 0..SCAN_CHUNK_WORDS {
+            if self.cursor >= end {
+                break;
+            }
+            // SAFETY: inside the stack region, above the guard.
+            if unsafe { ptr::read_volatile(self.cursor as *const u32) } != PAINT {
+                self.peak = top - self.cursor;
+                self.cursor = bottom;
+                return Poll::Ready(self.peak);
+            }
+            self.cursor += 4;
+        }
+        if self.cursor < end {
+            return Poll::Pending;
+        }
+        self.cursor = bottom;
+        Poll::Ready(self.peak)
+    }
+
+    /// Deepest stack use found by the last completed pass, in bytes.
+    pub fn peak(&self) -> usize {
+        self.peak
+    }
+}
+
+impl Default for StackMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
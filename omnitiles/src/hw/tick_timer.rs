@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Periodic interrupt from the basic timer TIM7.
+//!
+//! Runs loops that must be faster and steadier than the main loop, such as the current loop
+//! (see [`current_loop`](crate::control::current_loop)). [`TickTimer::tim7`] unmasks the TIM7
+//! interrupt; the application's `TIM7` handler calls [`on_tim7_interrupt`] first and does its work
+//! when that returns `true`.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use cortex_m::peripheral::NVIC;
+use stm32f7xx_hal::pac;
+
+/// Timer tick rate after the prescaler.
+const TICK_HZ: u32 = 1_000_000;
+
+/// Ticks handled so far, for checking the rate from the main task.
+static TIM7_TICKS: AtomicU32 = AtomicU32::new(0);
+
+pub struct TickTimer {
+    tim: pac::TIM7,
+    rate_hz: u32,
+}
+
+impl TickTimer {
+    /// Interrupt at `rate_hz` (at most 100 kHz). `timer_hz` is the TIM7 kernel clock (APB1 timer
+    /// clock).
+    pub fn tim7(tim: pac::TIM7, timer_hz: u32, rate_hz: u32) -> Self {
+        let rcc = unsafe { &*pac::RCC::ptr() };
+        rcc.apb1enr.modify(|_, w| w.tim7en().set_bit());
+
+        let rate_hz = rate_hz.clamp(1, TICK_HZ / 10);
+        tim.cr1.write(|w| unsafe { w.bits(0) });
+        tim.psc.write(|w| unsafe { w.bits(timer_hz / TICK_HZ - 1) });
+        tim.arr.write(|w| unsafe { w.bits(TICK_HZ / rate_hz - 1) });
+        // Load PSC without raising an interrupt for it.
+        tim.cr1.modify(|_, w| w.urs().set_bit());
+        tim.egr.write(|w| w.ug().set_bit());
+        tim.sr.modify(|_, w| w.uif().clear_bit());
+        tim.dier.modify(|_, w| w.uie().set_bit());
+        unsafe { NVIC::unmask(pac::Interrupt::TIM7) };
+        tim.cr1.modify(|_, w| w.cen().set_bit());
+
+        Self { tim, rate_hz }
+    }
+
+    /// Interrupt rate, after clamping.
+    pub fn rate_hz(&self) -> u32 {
+        self.rate_hz
+    }
+
+    /// Ticks handled since boot; wraps.
+    pub fn ticks(&self) -> u32 {
+        TIM7_TICKS.load(Ordering::Relaxed)
+    }
+
+    /// Stop the interrupt and release the timer.
+    pub fn free(self) -> pac::TIM7 {
+        self.tim.cr1.modify(|_, w| w.cen().clear_bit());
+        self.tim.dier.modify(|_, w| w.uie().clear_bit());
+        NVIC::mask(pac::Interrupt::TIM7);
+        self.tim
+    }
+}
+
+/// TIM7 interrupt handler body: acknowledge the update. Returns `true` for a tick.
+pub fn on_tim7_interrupt() -> bool {
+    let tim = unsafe { &*pac::TIM7::ptr() };
+    if tim.sr.read().uif().bit_is_clear() {
+        return false;
+    }
+    tim.sr.modify(|_, w| w.uif().clear_bit());
+    TIM7_TICKS.fetch_add(1, Ordering::Relaxed);
+    true
+}
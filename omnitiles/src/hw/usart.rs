@@ -6,6 +6,10 @@
 //! Provides several printing helpers for hex, decimal, and ASCII strings to print to an attached
 //! debug terminal.
 //!
+//! The decimal helpers, including [`Usart::print_f32`], use integer math only. Formatting a float
+//! with `write!` pulls `core::fmt`'s float code into the binary, which costs tens of kB of flash;
+//! code that prints readings straight to a `Usart` should prefer the helpers.
+//!
 //! Note: When using `writeln!`, be sure to include `\r` (CR) in the format string to ensure correct
 //! line endings on the terminal.
 //!
@@ -94,14 +98,46 @@ impl<U: Instance> Usart<U> {
         }
     }
 
-    pub fn print_u32(&mut self, mut n: u32) {
-        let mut buf = [0u8; 10];
-        let mut i = buf.len();
-        if n == 0 {
-            self.write_byte(b'0');
+    pub fn print_u32(&mut self, n: u32) {
+        self.print_digits(n as u64, 1);
+    }
+
+    pub fn print_i32(&mut self, n: i32) {
+        if n < 0 {
+            self.write_byte(b'-');
+        }
+        self.print_u32(n.unsigned_abs());
+    }
+
+    /// Print `value` rounded to `decimals` places (at most [`MAX_DECIMALS`]), e.g. `-12.50` for
+    /// `print_f32(-12.5, 2)`. NaN and infinities print as `NaN`, `inf` and `-inf`; magnitudes
+    /// past `u64::MAX / 10^decimals` print as that limit.
+    pub fn print_f32(&mut self, value: f32, decimals: u8) {
+        if value.is_nan() {
+            self.write_str("NaN");
+            return;
+        }
+        if value.is_sign_negative() && value != 0.0 {
+            self.write_byte(b'-');
+        }
+        if value.is_infinite() {
+            self.write_str("inf");
             return;
         }
-        while n > 0 {
+
+        let (int, frac, decimals) = fixed_point(value, decimals);
+        self.print_digits(int, 1);
+        if decimals > 0 {
+            self.write_byte(b'.');
+            self.print_digits(frac, decimals as usize);
+        }
+    }
+
+    /// Print `n` in decimal, zero-padded to at least `min_width` digits.
+    fn print_digits(&mut self, mut n: u64, min_width: usize) {
+        let mut buf = [0u8; 20];
+        let mut i = buf.len();
+        while n > 0 || buf.len() - i < min_width {
             i -= 1;
             buf[i] = b'0' + (n % 10) as u8;
             n /= 10;
@@ -112,6 +148,20 @@ impl<U: Instance> Usart<U> {
     }
 }
 
+/// Most decimal places [`Usart::print_f32`] prints.
+pub const MAX_DECIMALS: u8 = 6;
+
+/// Integer part, fraction digits and number of decimals of `|value|` rounded to `decimals`
+/// places (at most [`MAX_DECIMALS`]), without float formatting. `value` must be finite.
+pub fn fixed_point(value: f32, decimals: u8) -> (u64, u64, u8) {
+    let decimals = decimals.min(MAX_DECIMALS);
+    let scale = 10u64.pow(decimals as u32);
+    // Stays in f32 (f64 is software emulation on this core): digits past `f32`'s precision are
+    // noise either way.
+    let scaled = (value.abs() * scale as f32 + 0.5) as u64;
+    (scaled / scale, scaled % scale, decimals)
+}
+
 // Implement `core::fmt::Write` so we can use `write!` / `writeln!` on `Usart`.
 impl<U: Instance> fmt::Write for Usart<U> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
@@ -11,9 +11,15 @@
 //! | Module | Purpose |
 //! | ------ | -------- |
 //! | [`hw`] | MCU-level wrappers around USART, SPI, CAN, timers, etc. |
+//! | [`log`]       | Runtime-selectable log sinks (USART, CAN, RTT, USB) |
 //! | [`drivers`] | Device-level drivers (e.g., DRV8873, GDZ468) |
 //! | [`control`]   | Control algorithms (PID, high-level control) |
-//! | [`protocol`]  | Command message IDs and frame parser |
+//! | [`protocol`]  | Command handling; wire definitions from the `omnitiles-proto` crate |
+//! | [`config`]    | Persistent tile configuration and factory provisioning |
+//! | `datalog`     | Telemetry and fault log on microSD (feature `sd-log`) |
+//! | [`net`]       | Inter-tile protocols on the CAN1 backbone (time sync) |
+//! | [`sensors`]   | Tile sensing above the drivers (surface load, step-on events) |
+//! | [`units`]     | Typed units (`Deg`, `Rad`, `Mm`) used at API boundaries |
 //!
 //! ## Getting Started
 //!
@@ -36,9 +42,16 @@
 //!
 //! © 2025–2026 Christopher Liu
 
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
+pub mod config;
 pub mod control;
+#[cfg(feature = "sd-log")]
+pub mod datalog;
 pub mod drivers;
 pub mod hw;
+pub mod log;
+pub mod net;
 pub mod protocol;
+pub mod sensors;
+pub mod units;
@@ -0,0 +1,257 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Log and telemetry text sinks.
+//!
+//! Subsystems write through [`core::fmt::Write`] (`writeln!(log, ...)`) without knowing where the
+//! text goes. A [`LogMux`] fans each write out to up to `N` attached [`LogSink`]s, each of which
+//! can be switched on and off at runtime, e.g. to keep the debug USART quiet while a host streams
+//! over USB.
+//!
+//! Sinks:
+//!
+//! - [`Usart`] – blocking, every byte is sent.
+//! - [`CanLogSink`] – packs text into 8-byte frames on [`CAN_LOG_BASE_ID`] + node ID. Frames the
+//!   bus can't take are dropped.
+//! - `RttSink` (feature `rtt-log`) – SEGGER RTT up channel, readable with `probe-rs` while the
+//!   debugger is attached.
+//! - `UsbSerialSink` (feature `usb-log`) – USB CDC-ACM port; text is dropped while no host is
+//!   reading.
+//!
+//! As with [`Usart`], terminate lines with `\r\n`.
+//!
+//! Print floats as [`Fixed`] rather than with `{:.N}`: without the `float-fmt` feature it formats
+//! them with integer math, so `core::fmt`'s float code stays out of the binary.
+
+use core::fmt;
+
+use stm32f7xx_hal::serial::Instance;
+
+use crate::hw::{CanFrame, CanInterface, Usart};
+
+/// Standard CAN ID of the log stream of node 0; node `n` logs on `CAN_LOG_BASE_ID + n`. Sits
+/// above the CANopen heartbeat range (0x700..=0x77F).
+pub const CAN_LOG_BASE_ID: u16 = 0x780;
+
+/// Destination for log text.
+pub trait LogSink {
+    /// Write raw bytes. Sinks that can't keep up may drop them, but must not block indefinitely.
+    fn write_bytes(&mut self, bytes: &[u8]);
+
+    /// Push out anything buffered.
+    fn flush(&mut self) {}
+}
+
+impl<U: Instance> LogSink for Usart<U> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_byte(b);
+        }
+    }
+
+    fn flush(&mut self) {
+        Usart::flush(self);
+    }
+}
+
+/// Log text over CAN, up to 8 bytes per frame. A frame goes out when it's full or at the end of
+/// a line.
+pub struct CanLogSink<'a, B: CanInterface> {
+    bus: &'a mut B,
+    id: u16,
+    buf: [u8; 8],
+    len: usize,
+    /// Frames dropped because no transmit mailbox was free.
+    pub dropped: u32,
+}
+
+impl<'a, B: CanInterface> CanLogSink<'a, B> {
+    pub fn new(bus: &'a mut B, node_id: u8) -> Self {
+        Self {
+            bus,
+            id: CAN_LOG_BASE_ID + (node_id & 0x7F) as u16,
+            buf: [0; 8],
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    fn send(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+        let frame = CanFrame::standard(self.id, &self.buf[..self.len]).unwrap();
+        if self.bus.try_send(&frame).is_err() {
+            self.dropped = self.dropped.wrapping_add(1);
+        }
+        self.len = 0;
+    }
+}
+
+impl<B: CanInterface> LogSink for CanLogSink<'_, B> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.buf[self.len] = b;
+            self.len += 1;
+            if self.len == self.buf.len() || b == b'\n' {
+                self.send();
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        self.send();
+    }
+}
+
+/// Log text to a SEGGER RTT up channel.
+#[cfg(feature = "rtt-log")]
+pub struct RttSink(pub rtt_target::UpChannel);
+
+#[cfg(feature = "rtt-log")]
+impl LogSink for RttSink {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.0.write(bytes);
+    }
+}
+
+/// Log text to a USB CDC-ACM serial port. The caller keeps polling the `UsbDevice`.
+#[cfg(feature = "usb-log")]
+pub struct UsbSerialSink<'a, 'b, B: usb_device::bus::UsbBus> {
+    port: &'a mut usbd_serial::SerialPort<'b, B>,
+}
+
+#[cfg(feature = "usb-log")]
+impl<'a, 'b, B: usb_device::bus::UsbBus> UsbSerialSink<'a, 'b, B> {
+    pub fn new(port: &'a mut usbd_serial::SerialPort<'b, B>) -> Self {
+        Self { port }
+    }
+}
+
+#[cfg(feature = "usb-log")]
+impl<B: usb_device::bus::UsbBus> LogSink for UsbSerialSink<'_, '_, B> {
+    fn write_bytes(&mut self, mut bytes: &[u8]) {
+        // Non-blocking: whatever the endpoint buffer can't take right now is dropped.
+        while !bytes.is_empty() {
+            match self.port.write(bytes) {
+                Ok(n) if n > 0 => bytes = &bytes[n..],
+                _ => return,
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        let _ = self.port.flush();
+    }
+}
+
+/// Fans log text out to the enabled sinks among up to `N` attached ones.
+pub struct LogMux<'a, const N: usize> {
+    sinks: [Option<&'a mut dyn LogSink>; N],
+    enabled: [bool; N],
+}
+
+impl<'a, const N: usize> LogMux<'a, N> {
+    pub fn new() -> Self {
+        Self {
+            sinks: core::array::from_fn(|_| None),
+            enabled: [false; N],
+        }
+    }
+
+    /// Attach a sink, enabled, and return its slot; `None` if all slots are taken.
+    pub fn attach(&mut self, sink: &'a mut dyn LogSink) -> Option<usize> {
+        let slot = self.sinks.iter().position(Option::is_none)?;
+        self.sinks[slot] = Some(sink);
+        self.enabled[slot] = true;
+        Some(slot)
+    }
+
+    /// Detach the sink in `slot`, handing it back.
+    pub fn detach(&mut self, slot: usize) -> Option<&'a mut dyn LogSink> {
+        self.set_enabled(slot, false);
+        self.sinks.get_mut(slot)?.take()
+    }
+
+    /// Switch the sink in `slot` on or off. Out-of-range slots are ignored.
+    pub fn set_enabled(&mut self, slot: usize, enabled: bool) {
+        if let Some(e) = self.enabled.get_mut(slot) {
+            *e = enabled;
+        }
+    }
+
+    pub fn is_enabled(&self, slot: usize) -> bool {
+        self.enabled.get(slot).copied().unwrap_or(false)
+    }
+
+    /// Write string and CRLF terminator.
+    pub fn println(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes());
+        self.write_bytes(b"\r\n");
+    }
+
+    /// Write to every enabled sink.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for (sink, &enabled) in self.sinks.iter_mut().zip(&self.enabled) {
+            if let (Some(sink), true) = (sink, enabled) {
+                sink.write_bytes(bytes);
+            }
+        }
+    }
+
+    /// Flush every enabled sink.
+    pub fn flush(&mut self) {
+        for (sink, &enabled) in self.sinks.iter_mut().zip(&self.enabled) {
+            if let (Some(sink), true) = (sink, enabled) {
+                sink.flush();
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for LogMux<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Write for LogMux<'_, N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_bytes(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// `f32` in a log line, rounded to a fixed number of decimals: `Fixed(celsius, 1)` prints like
+/// `{:.1}`. With the `float-fmt` feature it is exactly that; without, it uses
+/// [`fixed_point`](crate::hw::usart::fixed_point) and prints NaN and infinities as `NaN`, `inf`
+/// and `-inf`.
+#[derive(Copy, Clone)]
+pub struct Fixed(pub f32, pub u8);
+
+impl fmt::Display for Fixed {
+    #[cfg(feature = "float-fmt")]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.*}", self.1 as usize, self.0)
+    }
+
+    #[cfg(not(feature = "float-fmt"))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Fixed(value, decimals) = *self;
+        if value.is_nan() {
+            return f.write_str("NaN");
+        }
+        if value.is_sign_negative() && value != 0.0 {
+            f.write_str("-")?;
+        }
+        if value.is_infinite() {
+            return f.write_str("inf");
+        }
+        let (int, frac, decimals) = crate::hw::usart::fixed_point(value, decimals);
+        if decimals == 0 {
+            write!(f, "{}", int)
+        } else {
+            write!(f, "{}.{:02$}", int, frac, decimals as usize)
+        }
+    }
+}
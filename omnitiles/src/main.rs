@@ -10,37 +10,301 @@ use cortex_m::peripheral::DWT;
 use cortex_m_rt::entry;
 use panic_halt as _;
 
-use core::cell::RefCell;
+use core::cell::{Cell, RefCell};
 use core::fmt::Write;
+use core::task::Poll;
 
 use hal::{
     i2c::{BlockingI2c, Mode as I2cMode},
-    pac,
+    pac::{self, interrupt},
     prelude::*,
-    serial::{Config, Serial},
     spi::{Mode, Phase, Polarity, Spi},
 };
 use stm32f7xx_hal as hal;
 
+#[cfg(feature = "console")]
+use hal::serial::{Config, Serial};
+#[cfg(feature = "console")]
+use omnitiles::hw::Usart;
+
+#[cfg(feature = "drv-spi")]
+use omnitiles::drivers::drv8873::{self, Diag};
+#[cfg(feature = "edge-leds")]
+use omnitiles::drivers::ws2812::{self, Pattern, Rgb, Ws2812};
+#[cfg(feature = "ethernet")]
+use omnitiles::hw::eth::{self, Eth, EthStorage};
+#[cfg(feature = "qspi-flash")]
+use omnitiles::hw::qspi::{BlackBox, Qspi};
+#[cfg(not(feature = "drv-spi"))]
+use omnitiles::hw::NoChipSelect;
+#[cfg(feature = "rtt-log")]
+use omnitiles::log::RttSink;
+#[cfg(feature = "canopen")]
+use omnitiles::net::{canopen::Request as CanOpenRequest, CanOpenNode};
+#[cfg(feature = "supply-sense")]
+use omnitiles::sensors::{AdcDivider, SupplyCompensation};
+#[cfg(feature = "fan")]
+use omnitiles::{config::FAN_MODE, control::Cooling, drivers::Fan};
 #[cfg(feature = "mobile-base")]
 use omnitiles::{control::BaseController, drivers::Tb6612};
 use omnitiles::{
-    control::{LinearController, LinearMode, Pid},
-    drivers::{ActuonixLinear, Drv8873, ImuSample, Lsm6dsv16x, Vl53l0x},
-    hw::{Adc, BoardPins, ChipSelect, I2cBus, Led, NoChipSelect, SpiBus, Usart},
-    protocol::{Command, Parser},
+    config::{
+        bank, itrip_from_byte,
+        params::{self, AxisParam, Param, ParamDesc, ParamError, Params},
+        provision,
+        stats::StatsLog,
+        warm::{FaultEntry, WarmState},
+        Config, ConfigError, Polarity, MOTOR_THERMAL_LIMITS, POT_DRIFT_GAIN_PER_C,
+        POT_DRIFT_MM_PER_C, SAFE_MODE_LIMITS,
+    },
+    control::{
+        events::EVENT_LEN,
+        fault_snapshot,
+        interlock::Rule,
+        linear_controller::ControlError,
+        schedule::{self, Trigger},
+        stats, CurrentDerate, Event, EventKind, EventQueue, FaultRecorder, FaultSnapshot,
+        HeightFusion, Interlocks, LinearController, LinearMode, MotionLimits, MotorStats, Pid,
+        Plausibility, ScheduleEntry, Scheduler, SequenceRunner, Verdict,
+    },
+    drivers::{
+        ntc::{Placement, SteinhartHart},
+        ActuonixLinear, Drv8873, ImuSample, Lsm6dsv16x, Ntc, Vl53l0x,
+    },
+    hw::{
+        self,
+        adc::TEMP_SENSOR_CHANNEL,
+        backup_sram::BackupSram,
+        dac::{Channel as DacChannel, Signal as DacSignal},
+        iwdg, reset_reason, stack, Adc, BoardPins, ChipSelect, Dac, Flash, I2cBus, Iwdg, Led,
+        MonoClock, Rtc, SpiBus, StackMonitor,
+    },
+    log::{Fixed, LogMux},
+    protocol::{
+        caps, heartbeat,
+        limiter::{self, AxisState},
+        messages, priority, register, telemetry, wire, AckStatus, AxisCaps, Batch, Capabilities,
+        Command, CommandHistory, CommandLimiter, Heartbeat, Outbox, Parser, Progress,
+        RegisterReply, RegisterStatus, Reject, SeqTracker, Unit,
+    },
+    sensors::{
+        HeightCheck, HeightCrossCheck, McuTemperature, MotorTemperature, PotTempCompensation,
+        TemperatureSource,
+    },
+    units::Mm,
+};
+#[cfg(feature = "current-loop")]
+use omnitiles::{
+    control::current_loop::{ipropi_amps_per_count, CurrentCommand, CurrentLoop, CURRENT_LOOP_HZ},
+    drivers::{TorqueDrive, TorqueInputs},
+    hw::{tick_timer, AdcDma, TickTimer},
+};
+#[cfg(feature = "tuning")]
+use omnitiles::{
+    control::{
+        bode::{BodeStep, BodeTest},
+        ident::{self, IdentRun, IdentStep},
+    },
+    drivers::ClosedLoopAxis,
+};
+#[cfg(feature = "tilt")]
+use omnitiles::{
+    control::{Envelope, TiltController},
+    drivers::{
+        gim6010_setup::{self, Bitrate, Settings},
+        Gim6010,
+    },
+    units::{Deg, Rad},
+};
+#[cfg(feature = "sd-log")]
+use omnitiles::{
+    datalog::{self, SdLogger},
+    hw::sdmmc::Sdmmc,
+};
+#[cfg(feature = "load-cell")]
+use omnitiles::{
+    drivers::{hx711::Gain, Hx711},
+    sensors::{LoadEdge, LoadSensor},
 };
+#[cfg(feature = "button")]
+use omnitiles::{
+    hw::{
+        button::{Button, ButtonEvent},
+        led::ActiveLevel,
+    },
+    protocol::Packet,
+};
+#[cfg(feature = "can")]
+use omnitiles::{
+    hw::{CanInterface, DualCan},
+    net::{id_guard::GuardState, slcan::btr_for_bitrate, IdGuard},
+};
+#[cfg(feature = "ethernet")]
+use stm32_eth::dma::{RxRingEntry, TxRingEntry};
+
+/// `MSG_PARAM` payload: `[id, status, value: f32]`.
+fn param_reply(id: u8, result: Result<(), ParamError>, value: f32) -> [u8; 6] {
+    let status = result.err().map_or(0, |e| e as u8);
+    let v = value.to_le_bytes();
+    [id, status, v[0], v[1], v[2], v[3]]
+}
+
+/// `MSG_CONFIG_STATUS` payload for a commit or revert that did something (`Ok(true)`), found
+/// nothing pending (`Ok(false)`) or failed.
+fn config_status(result: Result<bool, ConfigError>) -> [u8; 2] {
+    let status = match result {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(_) => 2,
+    };
+    [status, bank::scan().is_pending() as u8]
+}
+
+/// Run `f` on the SD log, if open. A card error is logged and closes the SD log, so a pulled or
+/// full card costs one message instead of a stall every pass.
+#[cfg(feature = "sd-log")]
+fn sd_write(
+    sd: &mut Option<SdLogger>,
+    log: &mut impl Write,
+    f: impl FnOnce(&mut SdLogger) -> Result<(), datalog::Error>,
+) {
+    let Some(logger) = sd else {
+        return;
+    };
+    if let Err(e) = f(logger) {
+        writeln!(log, "SD log: {:?}, logging stopped\r", e).ok();
+        *sd = None;
+    }
+}
+
+/// Note FIT0185 polarity flags that can't take effect: v2 has no FIT0185 or encoder, so only the
+/// extend flags are applied ([`Fit0185::set_polarity`](omnitiles::drivers::Fit0185::set_polarity)
+/// applies the rest on boards that have one).
+fn warn_fit0185_polarity(polarity: &Polarity, log: &mut impl Write) {
+    if polarity.motor_inverted || polarity.encoder_inverted {
+        writeln!(log, "polarity: motor/encoder flags ignored, no FIT0185\r").ok();
+    }
+}
 
 /// Map protocol speed byte (0–255) to motor set_speed magnitude in [0.0, 1.0].
 fn speed_to_float(speed: u8) -> f32 {
     (speed as f32) / 255.0
 }
 
+/// Advance an identification run by one control step, driving `motor` with its test signal or
+/// braking it once the run ends.
+#[cfg(feature = "tuning")]
+fn step_ident<A: ClosedLoopAxis>(
+    run: &mut Option<IdentRun>,
+    motor: &mut A,
+    dt: f32,
+) -> Option<IdentStep> {
+    let step = run.as_mut()?.step(motor.position(), dt);
+    match step {
+        IdentStep::Drive { duty, .. } => motor.apply_output(duty),
+        IdentStep::End(_) => {
+            motor.brake();
+            *run = None;
+        }
+    }
+    Some(step)
+}
+
+/// Take a motor temperature reading and derate `ctrl` to the lower of the thermal factor and
+/// `current_derate`, logging changes of the thermal state. Returns the temperature for telemetry.
+fn step_thermal<S: TemperatureSource>(
+    thermal: &mut Option<MotorTemperature<S>>,
+    current_derate: f32,
+    axis: u8,
+    ctrl: &mut LinearController,
+    log: &mut impl Write,
+) -> Option<f32> {
+    let Some(thermal) = thermal.as_mut() else {
+        ctrl.set_derate(current_derate);
+        return None;
+    };
+    if let Some(state) = thermal.update() {
+        let celsius = thermal.celsius().unwrap_or(f32::NAN);
+        let derate = thermal.derate();
+        writeln!(
+            log,
+            "thermal: M{} {:?} at {} C, derate {}\r",
+            axis,
+            state,
+            Fixed(celsius, 1),
+            Fixed(derate, 2)
+        )
+        .ok();
+    }
+    ctrl.set_derate(thermal.derate().min(current_derate));
+    thermal.celsius()
+}
+
+/// Advance a frequency response sweep by one control step, steering `ctrl`'s target and
+/// reporting each result on `log` as `BODE,<axis>,...` lines. The sweep ends if the controller
+/// leaves position control or faults.
+#[cfg(feature = "tuning")]
+fn step_bode(
+    sweep: &mut Option<BodeTest>,
+    ctrl: &mut LinearController,
+    position: Option<f32>,
+    dt: f32,
+    log: &mut impl Write,
+) {
+    let Some(test) = sweep.as_mut() else {
+        return;
+    };
+    let axis = test.axis();
+    let step = if ctrl.mode != LinearMode::PositionControl || ctrl.is_faulted() {
+        BodeStep::Abort
+    } else {
+        test.step(position, dt)
+    };
+    match step {
+        BodeStep::Track { target_mm, point } => {
+            ctrl.track_target_position_mm(target_mm);
+            if let Some(p) = point {
+                writeln!(
+                    log,
+                    "BODE,{},{},{},{}\r",
+                    axis,
+                    Fixed(p.freq_hz, 1),
+                    Fixed(p.gain_db, 2),
+                    Fixed(p.phase_deg, 1)
+                )
+                .ok();
+            }
+        }
+        BodeStep::Done(s) => {
+            ctrl.set_target_position_mm(test.center_mm());
+            writeln!(
+                log,
+                "BODE,{},SUMMARY,peak_db={},peak_hz={},bandwidth_hz={}\r",
+                axis,
+                Fixed(s.peak_db, 2),
+                Fixed(s.peak_hz, 1),
+                Fixed(s.bandwidth_hz.unwrap_or(f32::NAN), 1)
+            )
+            .ok();
+            *sweep = None;
+        }
+        BodeStep::Abort => {
+            writeln!(log, "BODE,{},ABORT\r", axis).ok();
+            *sweep = None;
+        }
+    }
+}
+
 #[entry]
 fn main() -> ! {
+    stack::paint();
     let dp = pac::Peripherals::take().unwrap();
-    let cp = cortex_m::Peripherals::take().unwrap();
+    let mut cp = cortex_m::Peripherals::take().unwrap();
+    hw::init(&mut cp.SCB, &mut cp.CPUID, &mut cp.FPU, &mut cp.MPU);
+    #[cfg(feature = "stack-guard")]
+    stack::install_guard(&mut cp.MPU);
 
+    let reset_reason = reset_reason::take(&dp.RCC);
     let rcc = dp.RCC.constrain();
     let clocks = rcc.cfgr.freeze();
     let mut apb1 = rcc.apb1;
@@ -56,6 +320,7 @@ fn main() -> ! {
         DWT::unlock();
         dwt.enable_cycle_counter();
     }
+    let mut clock = MonoClock::new(clocks.sysclk().raw());
 
     let pins = BoardPins::new(dp.GPIOA, dp.GPIOB, dp.GPIOC, dp.GPIOD, dp.GPIOE);
 
@@ -64,7 +329,10 @@ fn main() -> ! {
     let mut led_yellow = Led::active_low(pins.leds.yellow);
     let mut led_green = Led::active_low(pins.leds.green);
 
-    let serial = Serial::new(
+    // Debug console on USART1. Without the `console` feature the log only reaches the sinks of
+    // the other log features.
+    #[cfg(feature = "console")]
+    let mut usart = Usart::new(Serial::new(
         dp.USART1,
         (pins.usart1.tx, pins.usart1.rx),
         &clocks,
@@ -72,10 +340,114 @@ fn main() -> ! {
             baud_rate: 115_200.bps(),
             ..Default::default()
         },
-    );
-    let mut usart = Usart::new(serial);
+    ));
+    #[cfg(feature = "rtt-log")]
+    let mut rtt = {
+        let channels = rtt_target::rtt_init! { up: { 0: { size: 1024, name: "Log" } } };
+        RttSink(channels.up.0)
+    };
+    let mut log = LogMux::<2>::new();
+    #[cfg(feature = "console")]
+    log.attach(&mut usart);
+    #[cfg(feature = "rtt-log")]
+    log.attach(&mut rtt);
+
+    log.println("Booting OmniTiles firmware...");
+    writeln!(log, "Reset reason: {}\r", reset_reason.as_str()).ok();
+
+    let mut backup = BackupSram::new(dp.PWR);
+    let mut warm = WarmState::load(&backup).unwrap_or_default();
+    warm.boots = warm.boots.wrapping_add(1);
+    writeln!(
+        log,
+        "Warm state: boot {}, {} faults logged, battery-backed={}\r",
+        warm.boots,
+        warm.faults().len(),
+        backup.is_battery_backed()
+    )
+    .ok();
+    warm.store(&mut backup);
+
+    let mut rtc = match Rtc::new(dp.RTC, &backup, &mut delay) {
+        Ok(r) => {
+            match r.now() {
+                Some(t) => writeln!(log, "RTC: {:?} clock, unix time {}\r", r.source(), t).ok(),
+                None => writeln!(log, "RTC: {:?} clock, not set\r", r.source()).ok(),
+            };
+            Some(r)
+        }
+        Err(e) => {
+            writeln!(log, "RTC: init failed: {:?}\r", e).ok();
+            None
+        }
+    };
+
+    let mut flash = Flash::new(dp.FLASH);
+    let mut safe_mode = false;
+    let mut config = match Config::load() {
+        Ok(cfg) => {
+            writeln!(
+                log,
+                "Config: node_id={} provisioned={}\r",
+                cfg.node_id, cfg.provisioned
+            )
+            .ok();
+            cfg
+        }
+        Err(e) if e.is_corrupt() => {
+            writeln!(log, "Config: {:?}, SAFE MODE until provisioned\r", e).ok();
+            safe_mode = true;
+            Config::default()
+        }
+        Err(e) => {
+            writeln!(log, "Config: {:?}, using defaults (not provisioned)\r", e).ok();
+            Config::default()
+        }
+    };
+    if bank::scan().is_pending() {
+        log.println("Config: uncommitted change, previous record kept for revert");
+    }
 
-    usart.println("Booting OmniTiles firmware...");
+    #[cfg(feature = "qspi-flash")]
+    let mut black_box = {
+        let mut q = Qspi::new(dp.QUADSPI, clocks.hclk().raw());
+        match q.init().and_then(|()| BlackBox::open(&q)) {
+            Ok(b) => {
+                let id = q.jedec_id();
+                writeln!(log, "QSPI flash: id {:02X?}, {} KiB\r", id, q.size() / 1024).ok();
+                writeln!(log, "Black box: next record {}\r", b.position()).ok();
+                Some((q, b))
+            }
+            Err(e) => {
+                writeln!(log, "QSPI flash: {:?}, no black box\r", e).ok();
+                None
+            }
+        }
+    };
+
+    #[cfg(feature = "sd-log")]
+    let mut sd_log = {
+        let mut card = Sdmmc::new(dp.SDMMC2, clocks.sysclk().raw());
+        let unix_s = rtc.as_ref().and_then(Rtc::now);
+        match card
+            .init()
+            .map_err(embedded_sdmmc::Error::DeviceError)
+            .and_then(|()| SdLogger::open(card, config.node_id, unix_s))
+        {
+            Ok(l) => {
+                writeln!(log, "SD log: writing {}\r", l.file_name()).ok();
+                Some(l)
+            }
+            Err(e) => {
+                writeln!(log, "SD log: {:?}, not logging\r", e).ok();
+                None
+            }
+        }
+    };
+    #[cfg(feature = "sd-log")]
+    const SD_FLUSH_INTERVAL_MS: u64 = 1000;
+    #[cfg(feature = "sd-log")]
+    let mut next_sd_flush_ms: u64 = 0;
 
     let i2c_raw = BlockingI2c::i2c1(
         dp.I2C1,
@@ -95,9 +467,9 @@ fn main() -> ! {
         })
         .ok();
     if tof.is_some() {
-        usart.println("ToF: VL53L0X initialized");
+        log.println("ToF: VL53L0X initialized");
     } else {
-        usart.println("ToF: not detected, skipping");
+        log.println("ToF: not detected, skipping");
     }
 
     let mut spi_bus = {
@@ -107,7 +479,8 @@ fn main() -> ! {
         };
         let spi4_raw = Spi::new(dp.SPI4, (pins.spi4.sck, pins.spi4.miso, pins.spi4.mosi));
         let spi4_enabled = spi4_raw.enable::<u8>(spi_mode, 100.kHz(), &clocks, &mut apb2);
-        SpiBus::new(spi4_enabled)
+        // The bridge and IMU run in mode 0; the DRV8873s switch to CPHA=1 for their frames.
+        SpiBus::new(spi4_enabled).with_phase_switching()
     };
     let mut cs1 = ChipSelect::active_low(pins.spi4.cs1);
     let drdy = pins.spi4.drdy;
@@ -118,11 +491,11 @@ fn main() -> ! {
 
     let mut imu = match Lsm6dsv16x::new(&mut spi_bus, &mut cs2) {
         Ok(d) => {
-            writeln!(usart, "IMU: LSM6DSV16X initialized\r").ok();
+            writeln!(log, "IMU: LSM6DSV16X initialized\r").ok();
             Some(d)
         }
         Err(e) => {
-            writeln!(usart, "IMU: LSM6DSV16X init failed: {:?}\r", e).ok();
+            writeln!(log, "IMU: LSM6DSV16X init failed: {:?}\r", e).ok();
             None
         }
     };
@@ -141,62 +514,285 @@ fn main() -> ! {
     let (m1_in1, m1_in2, m2_in1) = pwm_tim3.split();
     let m2_in2 = pwm_tim1.split();
 
+    // With `current-loop`, TIM7 runs M1's bridge as a current source on the IPROPI reading and the
+    // actuator below commands amps through `M1_INPUTS` instead of duty on the TIM3 channels.
+    #[cfg(feature = "current-loop")]
+    let (m1_in1, m1_in2) = {
+        // After `adc1`, whose common ADC reset would stop the scan.
+        let buf = unsafe { &mut *core::ptr::addr_of_mut!(M1_IPROPI) };
+        let ipropi = AdcDma::adc3(dp.ADC3, dp.DMA2, [M1_IPROPI_CHANNEL], buf);
+        // The bridge isn't driven yet, so this is the zero-current reading.
+        delay.delay_ms(1_u32);
+        let offset = ipropi.latest(0);
+        let current = CurrentLoop::new(
+            M1_CURRENT_KP,
+            M1_CURRENT_KI,
+            CURRENT_LOOP_HZ,
+            ipropi_amps_per_count(M1_R_IPROPI, 3.3),
+        )
+        .with_offset(offset);
+        let drive = TorqueDrive::new(m1_in1, m1_in2, current);
+        cortex_m::interrupt::free(|cs| {
+            M1_LOOP
+                .borrow(cs)
+                .replace(Some(CurrentLoopIsr { drive, ipropi }));
+        });
+        let tick = TickTimer::tim7(dp.TIM7, clocks.timclk1().raw(), CURRENT_LOOP_HZ);
+        writeln!(
+            log,
+            "Current loop: M1 at {} Hz, offset {}\r",
+            tick.rate_hz(),
+            offset
+        )
+        .ok();
+        M1_INPUTS.split()
+    };
+
+    // The stock v2 board doesn't route the DRV8873 SPI lines, so neither driver can be configured
+    // or have its configuration verified (`Drv8873::verify_config`) and both run on hardware
+    // defaults. Boards with the lines routed enable `drv-spi` and get the stored ITRIP level.
+    #[cfg(feature = "drv-spi")]
+    let (m1_drv, m2_drv) = (
+        Drv8873::new(ChipSelect::active_low(pins.drv_spi.m1_cs)),
+        Drv8873::new(ChipSelect::active_low(pins.drv_spi.m2_cs)),
+    );
+    #[cfg(not(feature = "drv-spi"))]
+    let (m1_drv, m2_drv) = (Drv8873::new(NoChipSelect), Drv8873::new(NoChipSelect));
+
     // M1 gangs four P16 actuators on one driver. adc1/adc2 are wired normally;
-    // adc3/adc4 are mechanically opposed, so their pot wiring is reversed.
+    // adc3/adc4 are mechanically opposed, so their pot wiring is reversed. M2 gangs two T16s,
+    // both wired normally. With `ethernet`, RMII takes the first pot of each axis.
+    #[cfg(not(feature = "ethernet"))]
+    let (m1_pots, m1_reversed) = ([14, 9, 10, 11], [false, false, true, true]);
+    #[cfg(not(feature = "ethernet"))]
+    let (m2_pots, m2_reversed) = ([15, 13], [false, false]);
+    #[cfg(feature = "ethernet")]
+    let (m1_pots, m1_reversed) = ([9, 10, 11], [false, true, true]);
+    #[cfg(feature = "ethernet")]
+    let (m2_pots, m2_reversed) = ([13], [false]);
     let mut m1_actuator = ActuonixLinear::new(
-        Drv8873::new(NoChipSelect),
+        m1_drv,
         m1_in1,
         m1_in2,
         pins.m1.nsleep,
         pins.m1.disable,
-        Adc::make_multi_reader(&adc1, [14, 9, 10, 11]),
-        [false, false, true, true],
+        Adc::make_multi_reader(&adc1, m1_pots),
+        m1_reversed,
         150.0, // P16 has 150 mm stroke length
         123.0, // opposed-pair mechanical sum (normal + inverted extensions)
         20.0,  // 20 mm buffer at bottom (retracted)
         35.0,  // 35 mm buffer at top (extended)
     );
+    m1_actuator.set_extend_inverted(config.polarity.m1_extend_inverted);
+    m1_actuator.set_output_range(config.m1_output);
+    m1_actuator.set_calibration(config.m1_cal);
     m1_actuator.enable_outputs();
     let mut m1 = LinearController::new(
-        m1_actuator,
         Pid::new(0.0, 5.0, 0.0), // Under load we might need to bring back kp
         20.0,                    // min_position_mm (buffer at retracted end)
         115.0,                   // max_position_mm (stroke 150 mm - buffer 35 mm at extended end)
         2.0,                     // on_target_tolerance_mm
-    );
+    )
+    .with_hysteresis(3.0) // resume correcting once drifted 3 mm off target
+    .with_brake_release(0.25, 150) // hold the surface up while the PID winds up
+    .with_limits(config.m1_limits)
+    .with_axis(1);
 
-    // M2 gangs two T16 actuators on one driver. Both pots are wired normally.
     let mut m2_actuator = ActuonixLinear::new(
-        Drv8873::new(NoChipSelect),
+        m2_drv,
         m2_in1,
         m2_in2,
         pins.m2.nsleep,
         pins.m2.disable,
-        Adc::make_multi_reader(&adc1, [15, 13]),
-        [false, false],
+        Adc::make_multi_reader(&adc1, m2_pots),
+        m2_reversed,
         100.0, // T16 has 100 mm stroke length
         100.0, // no inverted channels; value unused
         25.0,  // 25 mm buffer at bottom (retracted)
         15.0,  // 15 mm buffer at top (extended)
     );
+    m2_actuator.set_extend_inverted(config.polarity.m2_extend_inverted);
+    m2_actuator.set_output_range(config.m2_output);
+    m2_actuator.set_calibration(config.m2_cal);
+    warn_fit0185_polarity(&config.polarity, &mut log);
     m2_actuator.enable_outputs();
+    #[cfg(feature = "drv-spi")]
+    for (axis, result) in [
+        (
+            1,
+            m1_actuator.set_current_limit(&mut spi_bus, config.m1_itrip),
+        ),
+        (
+            2,
+            m2_actuator.set_current_limit(&mut spi_bus, config.m2_itrip),
+        ),
+    ] {
+        if let Err(e) = result {
+            writeln!(log, "M{}: ITRIP not applied {:?}\r", axis, e).ok();
+        }
+    }
     let mut m2 = LinearController::new(
-        m2_actuator,
         Pid::new(0.0, 5.0, 0.0), // Under load we might need to bring back kp
         25.0,                    // min_position_mm (buffer at retracted end)
         85.0,                    // max_position_mm (stroke 100 mm - buffer 15 mm at extended end)
         0.45,                    // on_target_tolerance_mm
+    )
+    .with_hysteresis(1.0) // resume correcting once drifted 1 mm off target
+    .with_brake_release(0.25, 150)
+    .with_limits(config.m2_limits)
+    .with_axis(2);
+
+    // Thermistors on the motor cans derate their axis as it heats up: 10 kΩ β3950 NTCs to ground
+    // under 10 kΩ pull-ups, on PA6 and PB0 with `motor-ntc`. The stock v2 board has no inputs for
+    // them, so without the feature neither is fitted.
+    #[cfg(feature = "motor-ntc")]
+    const MOTOR_NTC_CHANNELS: [Option<u8>; 2] = [Some(6), Some(8)];
+    #[cfg(not(feature = "motor-ntc"))]
+    const MOTOR_NTC_CHANNELS: [Option<u8>; 2] = [None, None];
+    let motor_thermal = |channel: u8| {
+        let coeffs = SteinhartHart::from_beta(10_000.0, 25.0, 3950.0);
+        let ntc = Ntc::new(
+            Adc::make_reader(&adc1, channel),
+            10_000.0,
+            Placement::LowSide,
+            coeffs,
+        );
+        MotorTemperature::new(ntc, MOTOR_THERMAL_LIMITS)
+    };
+    let mut m1_thermal = MOTOR_NTC_CHANNELS[0].map(motor_thermal);
+    let mut m2_thermal = MOTOR_NTC_CHANNELS[1].map(motor_thermal);
+    let mut motor_temps_c: [Option<f32>; 2] = [None, None];
+
+    // Bus voltage on PC2 (ADC1_IN12) through a 100 kΩ / 10 kΩ divider, 36 V full scale. Scales
+    // the actuators' duty so a sagging bus doesn't slow their moves.
+    #[cfg(feature = "supply-sense")]
+    let mut supply = SupplyCompensation::new(AdcDivider::new(
+        Adc::make_reader(&adc1, 12),
+        3.3,
+        100_000.0,
+        10_000.0,
+    ));
+
+    // Pot drift correction from the MCU die, which sits on the board beside the actuators and
+    // warms with them. Temperature moves over minutes, so it is read once a second.
+    const POT_TEMP_INTERVAL_MS: u64 = 1000;
+    adc1.borrow().enable_temperature_sensor();
+    let mut pot_temp = PotTempCompensation::new(
+        McuTemperature::new(Adc::make_reader(&adc1, TEMP_SENSOR_CHANNEL)),
+        POT_DRIFT_MM_PER_C,
+        POT_DRIFT_GAIN_PER_C,
     );
+    let mut last_pot_temp_ms: u64 = 0;
+
+    // Derate while a driver sits in ITRIP regulation. Only `drv-spi` boards can read the DIAG
+    // flags; elsewhere the factors stay at 1.
+    #[cfg(feature = "drv-spi")]
+    let (mut m1_current, mut m2_current) = (CurrentDerate::new(), CurrentDerate::new());
+    #[cfg(not(feature = "drv-spi"))]
+    let (m1_current, m2_current) = (CurrentDerate::new(), CurrentDerate::new());
+    #[cfg(feature = "drv-spi")]
+    let mut last_diag_ms: u64 = 0;
+    #[cfg(feature = "drv-spi")]
+    const DIAG_INTERVAL_MS: u64 = 100;
+    // A UVLO reset or a glitched frame can undo the driver configuration; restore it from the
+    // shadow copies.
+    #[cfg(feature = "drv-spi")]
+    let mut last_verify_ms: u64 = 0;
+
+    // Enclosure fan, driven from the hottest of the motor cans and the MCU die. 25 kHz for 4-wire
+    // PC fans.
+    #[cfg(feature = "fan")]
+    let (mut fan, mut cooling) = {
+        let pwm = dp
+            .TIM10
+            .pwm::<_, _, 1_000_000>(pins.fan.pwm, 40.micros(), &clocks)
+            .split();
+        (Fan::new(pwm).with_min_duty(0.2), Cooling::new(FAN_MODE))
+    };
+
+    // Load cell under the tile surface, for step-on events. The scale is nominal for four 50 kg,
+    // 1 mV/V cells; the tile must be unloaded at boot for the tare.
+    #[cfg(feature = "load-cell")]
+    let mut load = {
+        let hx711 = Hx711::new(pins.load_cell.sck, pins.load_cell.dout, Gain::A128);
+        let newtons_per_count = Gain::A128.nominal_newtons_per_count(4.0 * 490.0, 1.0);
+        let mut load = LoadSensor::new(hx711, newtons_per_count);
+        // 10 conversions at the HX711's 10 Hz output rate.
+        let mut tare = load.start_tare(10, 2000);
+        if !iwdg::block_on(&mut delay, 10, |elapsed| tare.run_step(&mut load, elapsed)) {
+            writeln!(log, "load: tare timed out, no HX711?\r").ok();
+        }
+        load
+    };
 
     // Disable PID control and engage brakes at boot
     m1.mode = LinearMode::Disabled;
     m2.mode = LinearMode::Disabled;
-    m1.actuator.brake();
-    m2.actuator.brake();
+    m1_actuator.brake();
+    m2_actuator.brake();
     led_red.off();
     led_yellow.off();
     led_green.off();
 
+    // The lift pots are absolute, so v2 never homes. After a watchdog reset the saved height is
+    // still compared against the pots so that motion across the reset shows up in the log.
+    if warm.can_skip_homing(reset_reason) {
+        let now_mm = m2_actuator.position_mm().unwrap_or(f32::NAN);
+        writeln!(
+            log,
+            "Warm restart: lift saved at {} mm, now {} mm\r",
+            Fixed(warm.lift_mm, 1),
+            Fixed(now_mm, 1)
+        )
+        .ok();
+    }
+
+    // Holding the service button through boot provisions the tile without a host, for the factory
+    // bench. It must be held for a few seconds, so a bump at power-up doesn't start a calibration.
+    #[cfg(feature = "button")]
+    let mut button = Button::new(pins.button.service, ActiveLevel::Low);
+    #[cfg(feature = "button")]
+    if button.is_pressed_raw() {
+        const BOOT_HOLD_MS: u32 = 3000;
+        let mut held_ms = 0;
+        while button.is_pressed_raw() && held_ms < BOOT_HOLD_MS {
+            delay.delay_ms(10_u32);
+            held_ms += 10;
+        }
+        if held_ms >= BOOT_HOLD_MS {
+            let node_id = provision::local_node_id(&config);
+            writeln!(log, "provision: button held at boot, node_id={}\r", node_id).ok();
+            led_yellow.on();
+            // v2 has no FIT0185, so there's no encoder to measure. No host is listening for
+            // progress yet.
+            let result = provision::run(
+                &mut config,
+                node_id,
+                &mut m1_actuator,
+                &mut m2_actuator,
+                None,
+                &mut flash,
+                &mut delay,
+                &mut log,
+                |_| {},
+            );
+            led_yellow.off();
+            match result {
+                Err(e) => {
+                    writeln!(log, "provision: FAILED {:?}\r", e).ok();
+                    led_red.on();
+                }
+                Ok(()) => {
+                    m1_actuator.set_calibration(config.m1_cal);
+                    m2_actuator.set_calibration(config.m2_cal);
+                    safe_mode = false;
+                }
+            }
+        }
+    }
+    #[cfg(feature = "button")]
+    let mut button_commands: [Option<Command>; 2] = [None; 2];
+
     #[cfg(feature = "mobile-base")]
     let mut base = {
         let wheel_pwm = dp.TIM4.pwm::<_, _, 1_000_000>(
@@ -221,43 +817,776 @@ fn main() -> ! {
         base
     };
 
+    // CAN1 is the inter-tile backbone, CAN2 the motor bus. Both accept every frame; each consumer
+    // picks out its own IDs.
+    #[cfg(feature = "can")]
+    let mut can = {
+        const BACKBONE_BITRATE: u32 = 500_000;
+        // GIM6010 factory setting, see `drivers::gim6010_setup`.
+        const MOTOR_BITRATE: u32 = 1_000_000;
+        let pclk1_hz = clocks.pclk1().raw();
+        let can1 = hal::can::Can::new(dp.CAN1, &mut apb1, (pins.can.can1_tx, pins.can.can1_rx));
+        let can2 = hal::can::Can::new(dp.CAN2, &mut apb1, (pins.can.can2_tx, pins.can.can2_rx));
+        DualCan::<_, _, 8>::new(
+            can1,
+            can2,
+            btr_for_bitrate(pclk1_hz, BACKBONE_BITRATE).unwrap(),
+            btr_for_bitrate(pclk1_hz, MOTOR_BITRATE).unwrap(),
+        )
+    };
+
+    // Edge lighting: green bar for platform height, blue while moving, blinking red on a fault.
+    #[cfg(feature = "edge-leds")]
+    const EDGE_LEDS: usize = 24;
+    #[cfg(feature = "edge-leds")]
+    let mut edge_leds = {
+        // Lives in cached SRAM1; `show` cleans it before each transfer.
+        let buf = cortex_m::singleton!(: [u16; ws2812::buffer_len(EDGE_LEDS)] =
+            [0; ws2812::buffer_len(EDGE_LEDS)])
+        .unwrap();
+        Ws2812::new(
+            dp.TIM5,
+            dp.DMA1,
+            pins.edge_leds.data,
+            clocks.timclk1().raw(),
+            buf,
+        )
+    };
+    #[cfg(feature = "edge-leds")]
+    let mut edge_colors = [Rgb::OFF; EDGE_LEDS];
+    #[cfg(feature = "edge-leds")]
+    let mut next_edge_ms: u64 = 0;
+
+    // A CANopen master on the backbone drives axis 1, in 0.01 mm, under the tile's node ID. An
+    // unassigned ID (0) is the NMT broadcast address, so such a tile answers as node 1.
+    #[cfg(feature = "canopen")]
+    let mut canopen = CanOpenNode::new(config.node_id.clamp(1, 127));
+
+    // Nothing goes out on the backbone until the guard has listened for another tile on the same
+    // IDs; the CANopen boot-up is sent once it clears.
+    #[cfg(feature = "can")]
+    let mut id_guard = IdGuard::new(config.node_id.clamp(1, 127), clock.now_us());
+    #[cfg(feature = "can")]
+    let mut guard_state = GuardState::Listening;
+
+    // The tilt GIM6010 sits alone on the motor bus at its factory address. It homes against its
+    // lower stop once the loop is running; until then tilt moves are refused as not homed.
+    #[cfg(feature = "tilt")]
+    const TILT_ADDR: u16 = 1;
+    // Where `PROVISION_TILT_MOTOR` moves a replacement motor to: the address above on the motor
+    // bus bitrate.
+    #[cfg(feature = "tilt")]
+    const TILT_SETTINGS: Settings = Settings {
+        addr: TILT_ADDR as u8,
+        bitrate: Bitrate::M1,
+    };
+    #[cfg(feature = "tilt")]
+    let mut tilt = TiltController::<TILT_ADDR>::new(
+        Gim6010::new(clocks.sysclk().raw()),
+        Deg(-30.0), // min shaft angle
+        Deg(30.0),  // max shaft angle
+    )
+    .with_axis(3);
+    // Homing runs a step per control period; its latest progress report waits here for the
+    // outbox, which the closure can't borrow.
+    #[cfg(feature = "tilt")]
+    let tilt_report: Cell<Option<Progress>> = Cell::new(None);
+    #[cfg(feature = "tilt")]
+    let tilt_progress = |p: Progress| tilt_report.set(Some(p));
+    #[cfg(feature = "tilt")]
+    let mut tilt_homing = Some(tilt.start_homing(true, tilt_progress));
+
+    // UDP endpoint for tiles on a switched network, at 10.88.0.<node_id>. It carries the same
+    // frames as the SPI link; see `hw::eth`.
+    #[cfg(feature = "ethernet")]
+    let mut eth = {
+        const RX_ENTRY: RxRingEntry = RxRingEntry::new();
+        const TX_ENTRY: TxRingEntry = TxRingEntry::new();
+        let rx_ring =
+            cortex_m::singleton!(: [RxRingEntry; eth::RING_LEN] = [RX_ENTRY; eth::RING_LEN])
+                .unwrap();
+        let tx_ring =
+            cortex_m::singleton!(: [TxRingEntry; eth::RING_LEN] = [TX_ENTRY; eth::RING_LEN])
+                .unwrap();
+        let storage = cortex_m::singleton!(: EthStorage<'static> = EthStorage::new()).unwrap();
+        let rmii = pins.rmii;
+        let parts = stm32_eth::new(
+            stm32_eth::PartsIn {
+                mac: dp.ETHERNET_MAC,
+                mmc: dp.ETHERNET_MMC,
+                dma: dp.ETHERNET_DMA,
+                ptp: dp.ETHERNET_PTP,
+            },
+            &mut rx_ring[..],
+            &mut tx_ring[..],
+            clocks,
+            stm32_eth::EthPins {
+                ref_clk: rmii.ref_clk,
+                crs: rmii.crs_dv,
+                tx_en: rmii.tx_en,
+                tx_d0: rmii.txd0,
+                tx_d1: rmii.txd1,
+                rx_d0: rmii.rxd0,
+                rx_d1: rmii.rxd1,
+            },
+        );
+        match parts {
+            Ok(parts) => {
+                let ip = eth::ip_address(config.node_id);
+                writeln!(log, "eth: {:?}:{}\r", ip, eth::UDP_PORT).ok();
+                let mac = eth::mac_address(config.node_id);
+                Some(Eth::new(parts.dma, storage, mac, ip, 24, clock.now_ms()))
+            }
+            Err(e) => {
+                writeln!(log, "eth: not started {:?}\r", e).ok();
+                None
+            }
+        }
+    };
+
     let mut parser = Parser::new();
+    // Controller events waiting to be sent after the telemetry frame.
+    let mut events: EventQueue<8> = EventQueue::new();
+    // Replies (ACKs) waiting to be sent after the telemetry frame.
+    let mut outbox: Outbox<8> = Outbox::new();
+    // Latest MSG_ECHO token and when the transfer carrying it completed. Answered in the next
+    // transfer, so the reported hold time covers everything the tile adds to the round trip.
+    let mut pending_echo: Option<(u32, u64)> = None;
+    let mut seq_tracker = SeqTracker::new();
+    let mut token_tracker: SeqTracker<u16> = SeqTracker::new();
+    // Token of the command each lift axis is carrying out; its next event completes it.
+    let mut axis_tokens: [Option<u16>; 2] = [None; 2];
+    // Commands of the current transfer, arbitrated before any of them runs.
+    let mut batch = Batch::new();
+    let mut limiter = CommandLimiter::new();
+    // Tunables that differ from their registry defaults, so the console shows what this tile
+    // runs with.
+    let params = Params {
+        config: &mut config,
+        m1: &mut m1,
+        m2: &mut m2,
+    };
+    for desc in params::PARAMS {
+        let value = params.get(desc.param);
+        if value != desc.default {
+            writeln!(log, "param: {} = {}\r", desc.name, Fixed(value, 4)).ok();
+        }
+    }
+    if safe_mode {
+        limiter.set_safe_mode(true);
+        m1.limits = SAFE_MODE_LIMITS;
+        m2.limits = SAFE_MODE_LIMITS;
+    }
     let mut drdy_prev = false;
 
+    // Post-mortem state: executed commands and the last good motion state of each axis, frozen
+    // into a snapshot when the axis faults.
+    let mut history = CommandHistory::new();
+    let mut m1_recorder = FaultRecorder::new();
+    let mut m2_recorder = FaultRecorder::new();
+    let mut m1_snapshot: Option<FaultSnapshot> = None;
+    let mut m2_snapshot: Option<FaultSnapshot> = None;
+
+    // Usage counters, restored from the stats log and written back periodically while idle. OCP
+    // trips are counted from the driver's FAULT register when an axis faults.
+    let mut m1_stats = MotorStats::new();
+    let mut m2_stats = MotorStats::new();
+    let mut stats_log = StatsLog::load(&mut m1_stats, &mut m2_stats);
+
+    // Driven duty against measured velocity, so a stalled, disconnected or reversed motor faults
+    // the axis instead of pushing on. The P16/T16 gearings run 11-46 mm/s at full duty; the
+    // expected 25 mm/s with a 90% band covers all of them, and only duty above ~0.4 is judged.
+    let duty_check = || Plausibility::new(25.0, 1.0, 1500).with_rel_tolerance(0.9);
+    let mut m1_check = duty_check();
+    let mut m2_check = duty_check();
+    writeln!(
+        log,
+        "stats: last saved at unix time {}\r",
+        stats_log.saved_unix_s()
+    )
+    .ok();
+    const STATS_PERSIST_INTERVAL_MS: u64 = 10 * 60 * 1000;
+    let mut next_stats_persist_ms: u64 = STATS_PERSIST_INTERVAL_MS;
+
+    // Backup SRAM writes are cheap, so the lift height is saved often enough that a watchdog
+    // reset loses at most a fraction of a second of motion.
+    const WARM_SAVE_INTERVAL_MS: u64 = 200;
+    let mut next_warm_save_ms: u64 = 0;
+
     // Communication watchdog: brake motors if no SPI command in this window.
     let mut last_spi_cycle: u32 = DWT::cycle_count();
     const SPI_WATCHDOG_MS: f32 = 1500.0;
+    // Longest a progress report waits for the bridge to arm a transfer before it is skipped.
+    const PROGRESS_DRDY_WAIT_MS: u32 = 30;
     let mut watchdog_braked = false;
     let mut last_tof_cycle: u32 = DWT::cycle_count();
     const TOF_INTERVAL_MS: f32 = 100.0;
     let mut tof_range_mm: u16 = 0xFFFF; // 0xFFFF = no reading
 
+    // M2 is the lift and the ToF looks up at the platform. The T16 is reversed and the linkage
+    // turns 60 mm of stroke into 600 mm of height (see gui/mapping.py), so the ToF distance
+    // drops 10 mm per mm of extension.
+    let mut height_check = HeightCrossCheck::new(-10.0, 40.0, 1000);
+    // Platform height in ToF millimeters, carried through a slip or a hand over the sensor. The
+    // ToF pulls it in with a ~1 s time constant; jumps over 40 mm are outliers.
+    let mut height = HeightFusion::new(-10.0, 0.1, 40.0);
+
+    // Scope probes on PA4/PA5, routed with MSG_SET_DAC_PROBE and updated every control step.
+    let mut dac = Dac::new(dp.DAC);
+    let mut dac_probes = [DacSignal::Off; 2];
+
+    // Unattended motion. A scheduled sequence only starts once the host has left the actuators
+    // alone for a while, and any host motion command cancels it.
+    let mut scheduler = Scheduler::new(config.schedule, clock.now_ms());
+    let mut runner = SequenceRunner::new();
+    const AUTOMATION_QUIET_MS: u64 = 5 * 60 * 1000;
+    let mut last_host_motion_ms: u64 = 0;
+
+    // Cross-axis interlocks, checked on every motion command and every control step.
+    let mut interlocks = Interlocks::new(config.interlocks);
+
+    // Identification runs drive their axis open loop in place of the controller. Any other
+    // command for the axis, an interlock trip or the SPI watchdog ends one.
+    #[cfg(feature = "tuning")]
+    let mut m1_ident: Option<IdentRun> = None;
+    #[cfg(feature = "tuning")]
+    let mut m2_ident: Option<IdentRun> = None;
+    // Frequency response sweeps steer their axis's target; they end the same way.
+    #[cfg(feature = "tuning")]
+    let mut m1_bode: Option<BodeTest> = None;
+    #[cfg(feature = "tuning")]
+    let mut m2_bode: Option<BodeTest> = None;
+
+    // Demo mode loops the demo sequence for as long as the tile is healthy and no host takes over.
+    let mut demo = config.demo_at_boot;
+    if demo {
+        writeln!(log, "demo: starting at boot\r").ok();
+    }
+
     let mut last_pid_cycle: u32 = DWT::cycle_count();
     const PID_INTERVAL_MS: f32 = 20.0;
 
+    let mut next_heartbeat_ms: u64 = 0;
+    let mut heartbeat_due = false;
+
+    // Deepest stack use since boot, scanned a chunk per pass. Warn once it passes three quarters
+    // of the stack, long before an overflow.
+    let mut stack_monitor = StackMonitor::new();
+    let stack_warn_bytes = stack::size() / 4 * 3;
+    let mut stack_warned = false;
+
+    // Everything from here on must return to the loop within the watchdog timeout; long
+    // operations are stepped or feed it themselves (see hw::iwdg).
+    let mut watchdog = Iwdg::start(dp.IWDG, iwdg::TIMEOUT_MS);
+
     loop {
+        watchdog.feed();
         let now = DWT::cycle_count();
+        let now_ms = clock.now_ms();
+        #[cfg(feature = "ethernet")]
+        if let Some(eth) = eth.as_mut() {
+            eth.poll(now_ms);
+        }
+
+        if let Poll::Ready(peak) = stack_monitor.run_step() {
+            if peak >= stack_warn_bytes && !stack_warned {
+                writeln!(log, "stack: peak {} of {} bytes\r", peak, stack::size()).ok();
+                stack_warned = true;
+            }
+        }
+
+        if now_ms >= next_heartbeat_ms {
+            next_heartbeat_ms = now_ms + heartbeat::HEARTBEAT_INTERVAL_MS;
+            heartbeat_due = true;
+        }
+
+        // Service button presses become local commands for the dispatcher: a short press starts
+        // or stops the demo, a long one brakes both lift axes. They join the batch of the next
+        // transfer.
+        #[cfg(feature = "button")]
+        match button.poll(now_ms) {
+            Some(ButtonEvent::Short) => {
+                button_commands = [Some(Command::SetDemo(if demo { 0 } else { 1 })), None];
+            }
+            Some(ButtonEvent::Long) => {
+                button_commands = [Some(Command::M1Brake), Some(Command::M2Brake)];
+            }
+            None => {}
+        }
+
+        #[cfg(feature = "sd-log")]
+        if now_ms >= next_sd_flush_ms {
+            next_sd_flush_ms = now_ms + SD_FLUSH_INTERVAL_MS;
+            sd_write(&mut sd_log, &mut log, SdLogger::flush);
+        }
+
+        #[cfg(feature = "can")]
+        {
+            while let Some(rx) = can.backbone.try_recv() {
+                let Ok(frame) = rx else { continue };
+                id_guard.observe(&frame);
+                #[cfg(feature = "canopen")]
+                if let Some(reply) = canopen.on_frame(&frame) {
+                    if id_guard.may_transmit() {
+                        can.backbone.try_send(&reply).ok();
+                    }
+                }
+            }
+            let state = id_guard.poll(clock.now_us());
+            if state != guard_state {
+                guard_state = state;
+                match state {
+                    GuardState::Clear => {
+                        #[cfg(feature = "canopen")]
+                        can.backbone.try_send(&canopen.boot_up()).ok();
+                    }
+                    GuardState::Collision(id) => {
+                        writeln!(log, "can: node ID in use, seen on 0x{:03X}\r", id).ok();
+                    }
+                    GuardState::Listening => {}
+                }
+            }
+        }
+
+        #[cfg(feature = "canopen")]
+        {
+            let position = m1_actuator
+                .position_mm()
+                .map_or(0, |mm| (mm * 100.0) as i32);
+            canopen.set_feedback(position, m1.on_target(), m1.is_faulted());
+            if let Some(target) = canopen.take_target() {
+                writeln!(log, "canopen: target={}\r", target).ok();
+                m1.mode = LinearMode::PositionControl;
+                m1.set_target(Mm(target as f32 / 100.0));
+            }
+            match canopen.take_request() {
+                Some(CanOpenRequest::ResetNode) => cortex_m::peripheral::SCB::sys_reset(),
+                Some(CanOpenRequest::Disable) => {
+                    m1.mode = LinearMode::Disabled;
+                    m1_actuator.brake();
+                }
+                // Faults clear on their own once `step` succeeds again.
+                Some(CanOpenRequest::FaultReset) => {
+                    writeln!(log, "canopen: fault reset\r").ok();
+                }
+                None => {}
+            }
+            if let Some(heartbeat) = canopen.poll(clock.now_us()) {
+                if id_guard.may_transmit() {
+                    can.backbone.try_send(&heartbeat).ok();
+                }
+            }
+        }
 
         let pid_elapsed_ms = now.wrapping_sub(last_pid_cycle) as f32 / (sysclk_hz / 1000.0);
         if pid_elapsed_ms >= PID_INTERVAL_MS {
             let dt = pid_elapsed_ms / 1000.0;
-            let _ = m1.step(dt);
-            let _ = m2.step(dt);
+            #[cfg(feature = "tuning")]
+            {
+                step_bode(
+                    &mut m1_bode,
+                    &mut m1,
+                    m1_actuator.position_mm(),
+                    dt,
+                    &mut log,
+                );
+                step_bode(
+                    &mut m2_bode,
+                    &mut m2,
+                    m2_actuator.position_mm(),
+                    dt,
+                    &mut log,
+                );
+            }
+            #[cfg(feature = "drv-spi")]
+            {
+                let since_ms = now_ms - last_diag_ms;
+                if since_ms >= DIAG_INTERVAL_MS {
+                    last_diag_ms = now_ms;
+                    let since_ms = since_ms as u32;
+                    let regulating = |diag: Diag| diag.itrip1() || diag.itrip2();
+                    let m1_diag = m1_actuator.drv().read_diag(&mut spi_bus);
+                    m1_current.update(m1_diag.ok().map(regulating), since_ms);
+                    let m2_diag = m2_actuator.drv().read_diag(&mut spi_bus);
+                    m2_current.update(m2_diag.ok().map(regulating), since_ms);
+                }
+                if now_ms - last_verify_ms >= drv8873::VERIFY_INTERVAL_MS {
+                    last_verify_ms = now_ms;
+                    for (axis, drv) in [(1, m1_actuator.drv()), (2, m2_actuator.drv())] {
+                        match drv.verify_config(&mut spi_bus) {
+                            Ok(0) => {}
+                            Ok(restored) => {
+                                writeln!(log, "M{}: DRV8873 restored 0b{:04b}\r", axis, restored)
+                                    .ok();
+                            }
+                            Err(e) => {
+                                writeln!(log, "M{}: DRV8873 verify {:?}\r", axis, e).ok();
+                            }
+                        }
+                    }
+                }
+            }
+            if now_ms - last_pot_temp_ms >= POT_TEMP_INTERVAL_MS {
+                last_pot_temp_ms = now_ms;
+                let correction = pot_temp.update();
+                m1_actuator.set_position_correction(correction.scale, correction.offset_mm);
+                m2_actuator.set_position_correction(correction.scale, correction.offset_mm);
+            }
+            #[cfg(feature = "supply-sense")]
+            {
+                let scale = supply.update();
+                // M1's current loop already holds its current against the supply.
+                #[cfg(not(feature = "current-loop"))]
+                m1_actuator.set_supply_scale(scale);
+                m2_actuator.set_supply_scale(scale);
+            }
+            motor_temps_c = [
+                step_thermal(&mut m1_thermal, m1_current.factor(), 1, &mut m1, &mut log),
+                step_thermal(&mut m2_thermal, m2_current.factor(), 2, &mut m2, &mut log),
+            ];
+            #[cfg(feature = "fan")]
+            {
+                let hottest = motor_temps_c
+                    .into_iter()
+                    .chain([pot_temp.source().read_celsius()])
+                    .flatten()
+                    .reduce(f32::max);
+                let was_running = cooling.is_running();
+                fan.set_duty(cooling.update(hottest, dt));
+                if cooling.is_running() != was_running {
+                    let celsius = hottest.unwrap_or(f32::NAN);
+                    writeln!(
+                        log,
+                        "fan: {} at {} C\r",
+                        if was_running { "off" } else { "on" },
+                        Fixed(celsius, 1)
+                    )
+                    .ok();
+                }
+            }
+            let _ = m1.step(&mut m1_actuator, dt);
+            let _ = m2.step(&mut m2_actuator, dt);
+            #[cfg(feature = "tilt")]
+            match tilt_homing.as_mut() {
+                Some(homing) => {
+                    let step_ms = pid_elapsed_ms as u32;
+                    if let Poll::Ready(result) = homing.run_step(&mut tilt, &mut can.motor, step_ms)
+                    {
+                        writeln!(log, "tilt: homing {:?}\r", result).ok();
+                        tilt_homing = None;
+                    }
+                }
+                None => {
+                    if let Err(e) = tilt.step(&mut can.motor) {
+                        writeln!(log, "tilt: {:?}\r", e).ok();
+                    }
+                }
+            }
+            #[cfg(feature = "tilt")]
+            if let Some(p) = tilt_report.take() {
+                outbox.push(messages::MSG_PROGRESS, &p.to_bytes());
+            }
+            #[cfg(feature = "tuning")]
+            {
+                let runs = [
+                    (1, step_ident(&mut m1_ident, &mut m1_actuator, dt)),
+                    (2, step_ident(&mut m2_ident, &mut m2_actuator, dt)),
+                ];
+                for (axis, step) in runs {
+                    match step {
+                        Some(IdentStep::Drive { sample, .. }) => {
+                            outbox.push(messages::MSG_IDENT_SAMPLE, &sample);
+                        }
+                        Some(IdentStep::End(end)) => {
+                            writeln!(log, "ident: M{} run ended ({:?})\r", axis, end).ok();
+                        }
+                        None => {}
+                    }
+                }
+            }
+            // Stop an axis that is still moving when one of its interlocks stops holding. Without
+            // the `tilt` feature there's no tilt axis, so rules sourced from it never hold.
+            let position = |axis: u8| match axis {
+                1 => m1_actuator.position_mm(),
+                2 => m2_actuator.position_mm(),
+                #[cfg(feature = "tilt")]
+                3 => tilt.is_homed().then(|| Deg::from(tilt.position()).get()),
+                _ => None,
+            };
+            let m1_blocked = interlocks.check(1, position).err();
+            let m2_blocked = interlocks.check(2, position).err();
+            #[cfg(feature = "tilt")]
+            let tilt_blocked = interlocks.check(3, position).err();
+            let mut m1_interlock = None;
+            let mut m2_interlock = None;
+            if let Some(rule) = m1_blocked.filter(|_| m1_actuator.speed() != 0.0) {
+                m1.mode = LinearMode::Disabled;
+                #[cfg(feature = "tuning")]
+                {
+                    m1_ident = None;
+                    m1_bode = None;
+                }
+                m1_actuator.brake();
+                led_green.off();
+                m1_interlock = Some(Event::new(1, EventKind::Interlock, rule.source));
+            }
+            if let Some(rule) = m2_blocked.filter(|_| m2_actuator.speed() != 0.0) {
+                m2.mode = LinearMode::Disabled;
+                #[cfg(feature = "tuning")]
+                {
+                    m2_ident = None;
+                    m2_bode = None;
+                }
+                m2_actuator.brake();
+                led_yellow.off();
+                m2_interlock = Some(Event::new(2, EventKind::Interlock, rule.source));
+            }
+            #[cfg(feature = "tilt")]
+            let mut tilt_interlock = None;
+            #[cfg(feature = "tilt")]
+            if let Some(rule) =
+                tilt_blocked.filter(|_| tilt.monitor.is_active() && tilt_homing.is_none())
+            {
+                if let Err(e) = tilt.hold(&mut can.motor) {
+                    writeln!(log, "tilt: hold failed {:?}\r", e).ok();
+                }
+                tilt_interlock = Some(Event::new(3, EventKind::Interlock, rule.source));
+            }
+            let step_ms = pid_elapsed_ms as u32;
+            let m1_pos = m1_actuator.position_mm();
+            let m2_pos = m2_actuator.position_mm();
+            // Only judged while driven; a braked axis can still be back-driven.
+            let m1_duty = Some(m1_actuator.speed()).filter(|d| *d != 0.0);
+            let m2_duty = Some(m2_actuator.speed()).filter(|d| *d != 0.0);
+            let mismatch =
+                |axis| Event::new(axis, EventKind::Fault, ControlError::FeedbackMismatch as u8);
+            let mut m1_mismatch = None;
+            let mut m2_mismatch = None;
+            if m1_check.update(m1_duty, m1_pos.map(|_| m1.velocity()), step_ms) == Verdict::Fault {
+                m1.mode = LinearMode::Disabled;
+                #[cfg(feature = "tuning")]
+                {
+                    m1_ident = None;
+                    m1_bode = None;
+                }
+                m1_actuator.brake();
+                led_green.off();
+                m1_mismatch = Some(mismatch(1));
+            }
+            if m2_check.update(m2_duty, m2_pos.map(|_| m2.velocity()), step_ms) == Verdict::Fault {
+                m2.mode = LinearMode::Disabled;
+                #[cfg(feature = "tuning")]
+                {
+                    m2_ident = None;
+                    m2_bode = None;
+                }
+                m2_actuator.brake();
+                led_yellow.off();
+                m2_mismatch = Some(mismatch(2));
+            }
+            m1_stats.update(m1_pos, m1_actuator.speed(), step_ms);
+            m2_stats.update(m2_pos, m2_actuator.speed(), step_ms);
+            height.predict(m2_pos);
+            m1_recorder.observe(m1_pos, m1.velocity(), m1_actuator.speed());
+            m2_recorder.observe(m2_pos, m2.velocity(), m2_actuator.speed());
+            for (channel, signal) in [DacChannel::Out1, DacChannel::Out2]
+                .into_iter()
+                .zip(dac_probes)
+            {
+                let value = match signal {
+                    DacSignal::Off => 0.0,
+                    DacSignal::M1Output => m1.output(),
+                    DacSignal::M2Output => m2.output(),
+                    DacSignal::M1Velocity => m1.velocity(),
+                    DacSignal::M2Velocity => m2.velocity(),
+                    DacSignal::M1Error => m1_pos.map_or(0.0, |p| m1.target().0 - p),
+                    DacSignal::M2Error => m2_pos.map_or(0.0, |p| m2.target().0 - p),
+                };
+                dac.write(channel, value, signal.range());
+            }
+            let now_us = clock.now_us();
+            let raised = [
+                m1.poll_event(),
+                m2.poll_event(),
+                m1_interlock,
+                m2_interlock,
+                m1_mismatch,
+                m2_mismatch,
+            ];
+            #[cfg(feature = "tilt")]
+            let tilt_raised = [tilt.poll_event(), tilt_interlock];
+            #[cfg(not(feature = "tilt"))]
+            let tilt_raised: [Option<Event>; 0] = [];
+            #[cfg(feature = "load-cell")]
+            let load_raised = [load.update().map(LoadEdge::event)];
+            #[cfg(not(feature = "load-cell"))]
+            let load_raised: [Option<Event>; 0] = [];
+            let raised = raised.into_iter().chain(tilt_raised).chain(load_raised);
+            for event in raised.flatten() {
+                let token = (event.axis as usize)
+                    .checked_sub(1)
+                    .and_then(|i| axis_tokens.get_mut(i))
+                    .and_then(Option::take);
+                let event = event.at(now_us).with_token(token);
+                writeln!(log, "event: {:?}\r", event).ok();
+                if event.kind == EventKind::Fault {
+                    // Snapshots cover the linear axes; the tilt axis only goes to the fault log.
+                    let snapshot = match event.axis {
+                        1 => Some((&m1_recorder, &mut m1_snapshot, &mut m1_stats)),
+                        2 => Some((&m2_recorder, &mut m2_snapshot, &mut m2_stats)),
+                        _ => None,
+                    };
+                    if let Some((recorder, slot, stats)) = snapshot {
+                        // Read and clear the driver's latched fault, counting overcurrent trips.
+                        #[cfg(feature = "drv-spi")]
+                        let (driver_fault, driver_diag) = {
+                            let drv = match event.axis {
+                                1 => m1_actuator.drv(),
+                                _ => m2_actuator.drv(),
+                            };
+                            match drv8873::take_fault(drv, &mut spi_bus) {
+                                Ok(Some((fault, diag))) => {
+                                    if fault.ocp() {
+                                        stats.record_ocp();
+                                    }
+                                    (fault.raw(), diag.raw())
+                                }
+                                Ok(None) => (0, 0),
+                                Err(e) => {
+                                    writeln!(log, "M{}: DRV8873 fault {:?}\r", event.axis, e).ok();
+                                    (0, 0)
+                                }
+                            }
+                        };
+                        // Without `drv-spi` the DRV8873 can't be read, so its registers are
+                        // recorded as 0 and an overcurrent trip looks like any other axis fault.
+                        #[cfg(not(feature = "drv-spi"))]
+                        let (driver_fault, driver_diag) = (0, 0);
+                        *slot = Some(recorder.capture(
+                            event.axis,
+                            event.code,
+                            event.timestamp_us,
+                            driver_fault,
+                            driver_diag,
+                            &history,
+                        ));
+                        #[cfg(feature = "sd-log")]
+                        if let Some(s) = slot.as_ref() {
+                            sd_write(&mut sd_log, &mut log, |l| {
+                                let ts = s.timestamp_us;
+                                l.record(datalog::kind::FAULT_SNAPSHOT, ts, &s.header_bytes())?;
+                                l.record(datalog::kind::FAULT_HISTORY, ts, &s.history_bytes(0))?;
+                                l.record(datalog::kind::FAULT_HISTORY, ts, &s.history_bytes(1))
+                            });
+                        }
+                        #[cfg(feature = "qspi-flash")]
+                        if let (Some(s), Some((q, b))) = (slot.as_ref(), black_box.as_mut()) {
+                            let commands = &s.commands[..s.command_count as usize];
+                            if let Err(e) = b.append(q, &[&s.header_bytes(), commands]) {
+                                writeln!(log, "Black box: {:?}\r", e).ok();
+                            }
+                        }
+                    }
+                    warm.log_fault(FaultEntry {
+                        axis: event.axis,
+                        code: event.code,
+                        boot: warm.boots as u16,
+                        time_ms: now_ms as u32,
+                        unix_s: rtc.as_ref().and_then(Rtc::now).unwrap_or(0),
+                    });
+                    warm.store(&mut backup);
+                }
+                events.push(event);
+            }
+
+            scheduler.poll(now_ms, rtc.as_ref().and_then(Rtc::now));
+            let quiet = now_ms.saturating_sub(last_host_motion_ms) >= AUTOMATION_QUIET_MS;
+            let healthy = !m1.is_faulted() && !m2.is_faulted() && !watchdog_braked;
+            // Sequences wait for identification runs and sweeps to finish rather than fight them.
+            #[cfg(feature = "tuning")]
+            let free =
+                m1_ident.is_none() && m2_ident.is_none() && m1_bode.is_none() && m2_bode.is_none();
+            #[cfg(not(feature = "tuning"))]
+            let free = true;
+            if !runner.is_running() && demo && healthy && free {
+                runner.start(schedule::DEMO);
+            }
+            if !runner.is_running() && quiet && healthy && free {
+                if let Some(id) = scheduler.take_pending() {
+                    if let Some(steps) = schedule::sequence(id) {
+                        writeln!(log, "schedule: starting sequence {}\r", id).ok();
+                        runner.start(steps);
+                    }
+                }
+            }
+            let on_target = |axis: u8| match axis {
+                1 => m1.on_target(),
+                _ => m2.on_target(),
+            };
+            match runner.poll(now_ms, on_target) {
+                schedule::Action::Wait => {}
+                schedule::Action::Move(step) if step.axis == 1 => {
+                    m1.mode = LinearMode::PositionControl;
+                    m1.set_target(Mm(step.target_mm(m1.min_position_mm, m1.max_position_mm)));
+                }
+                schedule::Action::Move(step) => {
+                    m2.mode = LinearMode::PositionControl;
+                    m2.set_target(Mm(step.target_mm(m2.min_position_mm, m2.max_position_mm)));
+                }
+                schedule::Action::Finished => {
+                    writeln!(log, "schedule: sequence finished\r").ok();
+                }
+                schedule::Action::TimedOut(axis) => {
+                    writeln!(log, "schedule: M{} did not settle, aborting\r", axis).ok();
+                    demo = false;
+                    m1.mode = LinearMode::Disabled;
+                    m2.mode = LinearMode::Disabled;
+                    m1_actuator.brake();
+                    m2_actuator.brake();
+                }
+            }
             last_pid_cycle = now;
         }
 
+        if now_ms >= next_warm_save_ms {
+            next_warm_save_ms = now_ms + WARM_SAVE_INTERVAL_MS;
+            if let Some(lift_mm) = m2_actuator.position_mm() {
+                warm.lift_mm = lift_mm;
+                warm.store(&mut backup);
+            }
+        }
+
+        // Flash programming stalls the CPU, so only persist while both actuators are stopped.
+        let idle = m1_actuator.speed() == 0.0 && m2_actuator.speed() == 0.0;
+        if now_ms >= next_stats_persist_ms && idle {
+            next_stats_persist_ms = now_ms + STATS_PERSIST_INTERVAL_MS;
+            if m1_stats.is_dirty() || m2_stats.is_dirty() {
+                let unix_s = rtc.as_ref().and_then(Rtc::now).unwrap_or(0);
+                if let Err(e) = stats_log.store(&mut flash, &mut m1_stats, &mut m2_stats, unix_s) {
+                    writeln!(log, "stats: store failed: {:?}\r", e).ok();
+                }
+            }
+        }
+
         let ms_since_spi = now.wrapping_sub(last_spi_cycle) as f32 / (sysclk_hz / 1000.0);
         if ms_since_spi >= SPI_WATCHDOG_MS && !watchdog_braked {
             writeln!(
-                usart,
+                log,
                 "WATCHDOG: no SPI in {}ms, braking motors\r",
                 SPI_WATCHDOG_MS
             )
             .ok();
             m1.mode = LinearMode::Disabled;
             m2.mode = LinearMode::Disabled;
-            m1.actuator.brake();
-            m2.actuator.brake();
+            #[cfg(feature = "tuning")]
+            {
+                m1_ident = None;
+                m2_ident = None;
+                m1_bode = None;
+                m2_bode = None;
+            }
+            m1_actuator.brake();
+            m2_actuator.brake();
             led_green.off();
             led_yellow.off();
             watchdog_braked = true;
@@ -272,14 +1601,55 @@ fn main() -> ! {
                     Err(_) => tof_range_mm = 0xFFFF,
                 }
             }
+            let tof_mm = (tof_range_mm != 0xFFFF).then_some(tof_range_mm);
+            height.correct(tof_mm);
+            match height_check.update(now_ms, m2_actuator.position_mm(), tof_mm) {
+                HeightCheck::Diverged => {
+                    writeln!(
+                        log,
+                        "height: M2 feedback and ToF disagree by {} mm, fused {:?} mm\r",
+                        height_check.error_mm() as i32,
+                        height.height_mm().map(|mm| mm as i32)
+                    )
+                    .ok();
+                }
+                HeightCheck::Recovered => {
+                    writeln!(log, "height: M2 feedback and ToF agree again\r").ok();
+                }
+                HeightCheck::Unchanged => {}
+            }
         }
 
-        if m1.actuator.is_limit_braking() || m2.actuator.is_limit_braking() {
+        if limiter.is_safe_mode() {
+            // Red and yellow alternate at 2 Hz until the tile is provisioned.
+            let phase = (now_ms / 250) % 2 == 0;
+            led_red.set(phase);
+            led_yellow.set(!phase);
+        } else if m1_actuator.is_limit_braking() || m2_actuator.is_limit_braking() {
             led_red.on();
         } else {
             led_red.off();
         }
 
+        // About 30 frames per second; a 24-LED frame takes under 1 ms to clock out.
+        #[cfg(feature = "edge-leds")]
+        if now_ms >= next_edge_ms {
+            next_edge_ms = now_ms + 33;
+            let pattern = if m1.is_faulted() || m2.is_faulted() {
+                Pattern::Blink(Rgb::RED)
+            } else if limiter.is_safe_mode() {
+                Pattern::Breathe(Rgb::AMBER)
+            } else {
+                let moving = m1_actuator.speed() != 0.0 || m2_actuator.speed() != 0.0;
+                Pattern::Level {
+                    color: if moving { Rgb::BLUE } else { Rgb::GREEN },
+                    fraction: m2_actuator.position_percent().unwrap_or(0.0),
+                }
+            };
+            ws2812::render(pattern, now_ms, &mut edge_colors);
+            edge_leds.show(&edge_colors);
+        }
+
         let drdy_now = drdy.is_high();
         if drdy_now && !drdy_prev {
             delay.delay_ms(2_u32);
@@ -293,8 +1663,8 @@ fn main() -> ! {
             let mut buf = [0u8; 128];
 
             // Fused position (what the PID sees). 0xFFFF = no feedback.
-            let p16_raw = m1.actuator.position_raw().unwrap_or(0xFFFF);
-            let t16_raw = m2.actuator.position_raw().unwrap_or(0xFFFF);
+            let p16_raw = m1_actuator.position_raw().unwrap_or(0xFFFF);
+            let t16_raw = m2_actuator.position_raw().unwrap_or(0xFFFF);
 
             let p16_lo = p16_raw as u8;
             let p16_hi = (p16_raw >> 8) as u8;
@@ -305,11 +1675,15 @@ fn main() -> ! {
             let tof_hi = (tof_range_mm >> 8) as u8;
 
             // Per-channel raw ADC values (after median filter)
-            let m1_adc_raw = *m1.actuator.channel_medians();
-            let m2_adc_raw = *m2.actuator.channel_medians();
+            let m1_adc_raw = *m1_actuator.channel_medians();
+            let m2_adc_raw = *m2_actuator.channel_medians();
+
+            // Sample time, so the host can line telemetry up with commands and events no matter
+            // how long frames sit in link buffers.
+            let sample_us = clock.now_us() as u32;
 
-            buf[0] = omnitiles::protocol::messages::START_BYTE;
-            buf[1] = omnitiles::protocol::messages::MSG_TELEMETRY;
+            buf[0] = messages::START_BYTE;
+            buf[1] = messages::MSG_TELEMETRY;
             buf[2] = p16_lo;
             buf[3] = p16_hi;
             buf[4] = t16_lo;
@@ -342,11 +1716,115 @@ fn main() -> ! {
                 buf[41 + i * 2] = (*v >> 8) as u8;
             }
 
+            buf[44..48].copy_from_slice(&sample_us.to_le_bytes());
+            buf[48..50].copy_from_slice(&telemetry::encode_temperature(motor_temps_c[0]));
+            buf[50..52].copy_from_slice(&telemetry::encode_temperature(motor_temps_c[1]));
+            // In KB: the stack is a few hundred KB, past what a u16 of bytes holds.
+            let stack_peak = stack_monitor.peak().div_ceil(1024).min(u16::MAX as usize) as u16;
+            buf[52..54].copy_from_slice(&stack_peak.to_le_bytes());
+
             let mut csum: u8 = 0;
-            for b in &buf[1..44] {
+            for b in &buf[1..54] {
                 csum = csum.wrapping_add(*b);
             }
-            buf[44] = csum;
+            buf[54] = csum;
+
+            #[cfg(feature = "sd-log")]
+            sd_write(&mut sd_log, &mut log, |l| {
+                l.record(datalog::kind::TELEMETRY, sample_us, &buf[2..54])
+            });
+
+            if let Some((token, rx_us)) = pending_echo.take() {
+                let held_us = clock.now_us().saturating_sub(rx_us) as u32;
+                let mut reply = [0u8; 8];
+                reply[..4].copy_from_slice(&token.to_le_bytes());
+                reply[4..].copy_from_slice(&held_us.to_le_bytes());
+                outbox.push(messages::MSG_ECHO, &reply);
+            }
+
+            // Unsolicited event frames follow the telemetry frame. Events that don't fit stay
+            // queued for the next transfer.
+            let mut tx_len = 55;
+            tx_len += outbox.drain_into(&mut buf[tx_len..]);
+            while let Some(event) = events.peek() {
+                let payload = event.to_token_bytes();
+                let (id, payload) = match event.token {
+                    Some(_) => (messages::MSG_TOKEN_EVENT, &payload[..]),
+                    None => (messages::MSG_EVENT, &payload[..EVENT_LEN]),
+                };
+                match messages::encode_frame(id, payload, &mut buf[tx_len..]) {
+                    Some(n) => {
+                        tx_len += n;
+                        events.pop();
+                    }
+                    None => break,
+                }
+            }
+
+            if heartbeat_due {
+                let mut mode = 0;
+                if m1.mode == LinearMode::PositionControl {
+                    mode |= heartbeat::mode::M1_POSITION;
+                }
+                if m2.mode == LinearMode::PositionControl {
+                    mode |= heartbeat::mode::M2_POSITION;
+                }
+                if watchdog_braked {
+                    mode |= heartbeat::mode::WATCHDOG_BRAKED;
+                }
+                if config.provisioned {
+                    mode |= heartbeat::mode::PROVISIONED;
+                }
+                if limiter.is_safe_mode() {
+                    mode |= heartbeat::mode::SAFE_MODE;
+                }
+                let mut faults = 0;
+                if m1.is_faulted() {
+                    faults |= heartbeat::fault::M1_FEEDBACK;
+                }
+                if m2.is_faulted() {
+                    faults |= heartbeat::fault::M2_FEEDBACK;
+                }
+                if m1_actuator.is_limit_braking() || m2_actuator.is_limit_braking() {
+                    faults |= heartbeat::fault::LIMIT_BRAKING;
+                }
+                if imu.is_none() {
+                    faults |= heartbeat::fault::IMU_MISSING;
+                }
+                if tof.is_none() {
+                    faults |= heartbeat::fault::TOF_MISSING;
+                }
+                if spi_bus.health().is_failed() {
+                    faults |= heartbeat::fault::SPI_BUS;
+                }
+                if height_check.is_diverged() {
+                    faults |= heartbeat::fault::HEIGHT_MISMATCH;
+                }
+                #[cfg(feature = "can")]
+                if id_guard.collision().is_some() {
+                    faults |= heartbeat::fault::NODE_ID_CONFLICT;
+                }
+                let hb = Heartbeat {
+                    node_id: config.node_id,
+                    uptime_ms: now_ms as u32,
+                    mode,
+                    faults,
+                };
+                if let Some(n) = messages::encode_frame(
+                    messages::MSG_HEARTBEAT,
+                    &hb.to_bytes(),
+                    &mut buf[tx_len..],
+                ) {
+                    tx_len += n;
+                    heartbeat_due = false;
+                }
+            }
+
+            // Ethernet hosts get the same frames; the transfer below overwrites them.
+            #[cfg(feature = "ethernet")]
+            if let Some(eth) = eth.as_mut() {
+                eth.send(&buf[..tx_len]);
+            }
 
             cs1.select();
             delay.delay_us(50_u32);
@@ -356,93 +1834,906 @@ fn main() -> ! {
             last_spi_cycle = DWT::cycle_count();
             watchdog_braked = false;
 
-            for &byte in &buf {
-                if let Some(cmd) = parser.push(byte) {
-                    match cmd {
-                        Command::Ping => {
-                            writeln!(usart, "cmd: PING — System is alive.\r").ok();
+            let rx_us = clock.now_us();
+            let rx_ms = rx_us / 1000;
+            // Commands from Ethernet hosts run after the SPI ones, through the same parser.
+            #[cfg(feature = "ethernet")]
+            let (eth_rx, eth_len) = {
+                let mut rx = [0u8; 128];
+                let mut len = 0;
+                if let Some(eth) = eth.as_mut() {
+                    while len < rx.len() {
+                        let Some(n) = eth.recv(&mut rx[len..]) else {
+                            break;
+                        };
+                        len += n;
+                    }
+                }
+                (rx, len)
+            };
+            #[cfg(not(feature = "ethernet"))]
+            let (eth_rx, eth_len) = ([0u8; 0], 0);
+            batch.clear();
+            for &byte in buf.iter().chain(&eth_rx[..eth_len]) {
+                let Some(packet) = parser.push_packet_at(byte, rx_ms) else {
+                    continue;
+                };
+                let mut verdict = Ok(match (packet.seq, packet.token) {
+                    (Some(seq), _) => seq_tracker.check(seq),
+                    (None, Some(token)) => token_tracker.check(token),
+                    (None, None) => AckStatus::Accepted,
+                });
+                if verdict == Ok(AckStatus::Accepted) {
+                    verdict = limiter
+                        .check(&packet.command, clock.now_ms())
+                        .map(|_| AckStatus::Accepted);
+                }
+                if let (Ok(AckStatus::Accepted), Some(axis)) =
+                    (verdict, priority::lane(&packet.command))
+                {
+                    let state = match axis {
+                        1 => AxisState::absolute(m1.is_faulted()),
+                        2 => AxisState::absolute(m2.is_faulted()),
+                        #[cfg(feature = "tilt")]
+                        3 => AxisState {
+                            present: true,
+                            faulted: false,
+                            homed: tilt.is_homed(),
+                        },
+                        4 if cfg!(feature = "mobile-base") => AxisState::absolute(false),
+                        _ => AxisState::ABSENT,
+                    };
+                    if let Err(reason) = limiter::check_axis(&packet.command, state) {
+                        verdict = Err(reason);
+                    }
+                }
+                if let (Ok(AckStatus::Accepted), Some(axis)) =
+                    (verdict, packet.command.motion_axis())
+                {
+                    let position = |axis: u8| match axis {
+                        1 => m1_actuator.position_mm(),
+                        2 => m2_actuator.position_mm(),
+                        #[cfg(feature = "tilt")]
+                        3 => tilt.is_homed().then(|| Deg::from(tilt.position()).get()),
+                        _ => None,
+                    };
+                    if let Err(rule) = interlocks.check(axis, position) {
+                        writeln!(log, "cmd: {:?} blocked by {:?}\r", packet.command, rule).ok();
+                        verdict = Err(Reject::Interlock);
+                    }
+                }
+                if !batch.push(packet, verdict) {
+                    writeln!(log, "cmd: {:?} dropped, batch full\r", packet.command).ok();
+                }
+            }
+            // Local commands skip the rate limit and get no ACK.
+            #[cfg(feature = "button")]
+            for command in button_commands.iter_mut().filter_map(Option::take) {
+                writeln!(log, "button: {:?}\r", command).ok();
+                let packet = Packet {
+                    seq: None,
+                    token: None,
+                    command,
+                };
+                if !batch.push(packet, Ok(AckStatus::Accepted)) {
+                    writeln!(log, "cmd: {:?} dropped, batch full\r", packet.command).ok();
+                }
+            }
+            #[cfg(feature = "mobile-base")]
+            let base_moving = base.is_moving();
+            #[cfg(not(feature = "mobile-base"))]
+            let base_moving = false;
+            #[cfg(feature = "tilt")]
+            let tilt_moving = tilt.monitor.is_active();
+            #[cfg(not(feature = "tilt"))]
+            let tilt_moving = false;
+            let moving = m1_actuator.speed() != 0.0
+                || m2_actuator.speed() != 0.0
+                || runner.is_running()
+                || base_moving
+                || tilt_moving;
+            batch.arbitrate(moving);
+
+            for entry in batch.iter() {
+                let packet = entry.packet;
+                if let Some(ack) = entry.ack() {
+                    outbox.push(messages::MSG_ACK, &ack);
+                }
+                if let Some(ack) = entry.token_ack() {
+                    outbox.push(messages::MSG_TOKEN_ACK, &ack);
+                }
+                match (entry.status, entry.reason) {
+                    (AckStatus::Accepted, _) => {}
+                    (AckStatus::Duplicate, _) => {
+                        writeln!(
+                            log,
+                            "cmd: duplicate seq={:?} token={:?}, not executed\r",
+                            packet.seq, packet.token
+                        )
+                        .ok();
+                        continue;
+                    }
+                    (_, reason) => {
+                        writeln!(log, "cmd: {:?} rejected ({:?})\r", packet.command, reason).ok();
+                        continue;
+                    }
+                }
+                history.record(packet.command.msg_id());
+                // A newer command for the axis, tokened or not, takes over its completion. Only
+                // position moves end in an event; brakes and manual moves are done at the ACK.
+                if let Some(slot) = priority::lane(&packet.command)
+                    .and_then(|axis| axis_tokens.get_mut(axis as usize - 1))
+                {
+                    let completes_later = matches!(
+                        packet.command,
+                        Command::M1SetPosition(_)
+                            | Command::M1MoveRelative(_)
+                            | Command::M2SetPosition(_)
+                            | Command::M2MoveRelative(_)
+                    );
+                    *slot = packet.token.filter(|_| completes_later);
+                }
+                if packet.command.is_motion() {
+                    // A new move is the operator's acknowledgement of a mismatch on that axis.
+                    match packet.command.motion_axis() {
+                        Some(1) => m1_check.clear(),
+                        Some(2) => m2_check.clear(),
+                        _ => {}
+                    }
+                    last_host_motion_ms = clock.now_ms();
+                    if demo {
+                        writeln!(log, "demo: host took over, stopped\r").ok();
+                        demo = false;
+                    }
+                    if runner.is_running() {
+                        writeln!(log, "schedule: host took over, sequence cancelled\r").ok();
+                        runner.abort();
+                    }
+                }
+                #[cfg(feature = "tuning")]
+                match priority::lane(&packet.command) {
+                    Some(1) => {
+                        m1_ident = None;
+                        m1_bode = None;
+                    }
+                    Some(2) => {
+                        m2_ident = None;
+                        m2_bode = None;
+                    }
+                    _ => {}
+                }
+                match packet.command {
+                    Command::Ping => {
+                        writeln!(log, "cmd: PING — System is alive.\r").ok();
+                    }
+                    Command::GetCapabilities => {
+                        writeln!(log, "cmd: GetCapabilities\r").ok();
+                        let summary = Capabilities {
+                            node_id: config.node_id,
+                            axis_count: if cfg!(feature = "tilt") { 3 } else { 2 },
+                            reset_reason,
+                            flags: if limiter.is_safe_mode() {
+                                caps::flags::SAFE_MODE
+                            } else {
+                                0
+                            },
+                        };
+                        outbox.push(messages::MSG_CAPABILITIES, &summary.to_bytes());
+                        let m1_caps = AxisCaps {
+                            axis: 1,
+                            unit: Unit::Millimeter,
+                            min: m1.min_position_mm,
+                            max: m1.max_position_mm,
+                            full_scale: m1_actuator.stroke_len_mm(),
+                        };
+                        outbox.push(messages::MSG_AXIS_CAPS, &m1_caps.to_bytes());
+                        let m2_caps = AxisCaps {
+                            axis: 2,
+                            unit: Unit::Millimeter,
+                            min: m2.min_position_mm,
+                            max: m2.max_position_mm,
+                            full_scale: m2_actuator.stroke_len_mm(),
+                        };
+                        outbox.push(messages::MSG_AXIS_CAPS, &m2_caps.to_bytes());
+                        #[cfg(feature = "tilt")]
+                        outbox.push(messages::MSG_AXIS_CAPS, &tilt.caps(3).to_bytes());
+                    }
+                    Command::SetTelemetryFields(mask) => {
+                        // Field selection is applied by the BLE bridge, which builds the
+                        // telemetry it streams; nothing changes on the SPI side.
+                        writeln!(log, "cmd: SetTelemetryFields mask=0x{:02x}\r", mask).ok();
+                    }
+                    Command::GetTelemetryDescriptor => {
+                        writeln!(log, "cmd: GetTelemetryDescriptor\r").ok();
+                        let mut desc = [0u8; telemetry::DESCRIPTOR_LEN];
+                        if let Some(n) = telemetry::write_descriptor(&mut desc) {
+                            outbox.push(messages::MSG_TELEMETRY_DESCRIPTOR, &desc[..n]);
                         }
-                        Command::M1Extend(speed) => {
-                            writeln!(usart, "cmd: M1Extend speed={}\r", speed).ok();
-                            let s = speed_to_float(speed);
-                            m1.mode = LinearMode::Disabled;
-                            m1.actuator.set_speed(s);
-                            led_green.on();
+                    }
+                    Command::GetStats(axis) => {
+                        writeln!(log, "cmd: GetStats axis={}\r", axis).ok();
+                        let counters = match axis {
+                            1 => Some(&m1_stats),
+                            2 => Some(&m2_stats),
+                            _ => None,
+                        };
+                        if let Some(counters) = counters {
+                            let mut payload = [0u8; 1 + stats::STATS_LEN];
+                            payload[0] = axis;
+                            payload[1..].copy_from_slice(&counters.to_bytes());
+                            outbox.push(messages::MSG_STATS, &payload);
                         }
-                        Command::M1Retract(speed) => {
-                            writeln!(usart, "cmd: M1Retract speed={}\r", speed).ok();
-                            let s = speed_to_float(speed);
-                            m1.mode = LinearMode::Disabled;
-                            m1.actuator.set_speed(-s);
-                            led_green.on();
+                    }
+                    Command::Echo(token) => {
+                        writeln!(log, "cmd: Echo token={}\r", token).ok();
+                        pending_echo = Some((token, rx_us));
+                    }
+                    Command::GetLinkStats => {
+                        let link = parser.stats();
+                        writeln!(log, "cmd: GetLinkStats {:?}\r", link).ok();
+                        outbox.push(messages::MSG_LINK_STATS, &link.to_bytes());
+                    }
+                    Command::GetParam(id) => {
+                        let name = ParamDesc::find(id).map_or("?", |d| d.name);
+                        writeln!(log, "cmd: GetParam 0x{:02x} ({})\r", id, name).ok();
+                        let params = Params {
+                            config: &mut config,
+                            m1: &mut m1,
+                            m2: &mut m2,
+                        };
+                        let reply = match Param::from_id(id) {
+                            Some(p) => param_reply(id, Ok(()), params.get(p)),
+                            None => param_reply(id, Err(ParamError::UnknownId), 0.0),
+                        };
+                        outbox.push(messages::MSG_PARAM, &reply);
+                    }
+                    Command::GetWireDescriptor(index) => {
+                        writeln!(log, "cmd: GetWireDescriptor {}\r", index).ok();
+                        let i = index as usize;
+                        let record = match wire::MESSAGES.get(i) {
+                            Some(m) => wire::Record::Message(m),
+                            None => params::PARAMS
+                                .get(i - wire::MESSAGES.len())
+                                .map_or(wire::Record::None, |d| wire::Record::Param(d.record())),
+                        };
+                        let total = wire::record_count(params::PARAMS.len()) as u8;
+                        let reply = wire::write_descriptor(index, total, record);
+                        outbox.push(messages::MSG_WIRE_DESCRIPTOR, &reply);
+                    }
+                    Command::GetFaultSnapshot(axis) => {
+                        writeln!(log, "cmd: GetFaultSnapshot axis={}\r", axis).ok();
+                        let snapshot = match axis {
+                            1 => m1_snapshot.as_ref(),
+                            _ => m2_snapshot.as_ref(),
+                        };
+                        match snapshot {
+                            Some(s) => {
+                                outbox.push(messages::MSG_FAULT_SNAPSHOT, &s.header_bytes());
+                                outbox.push(messages::MSG_FAULT_HISTORY, &s.history_bytes(0));
+                                outbox.push(messages::MSG_FAULT_HISTORY, &s.history_bytes(1));
+                            }
+                            None => {
+                                // Code 0: the axis hasn't faulted since boot.
+                                let mut empty = [0u8; fault_snapshot::SNAPSHOT_HEADER_LEN];
+                                empty[0] = axis;
+                                outbox.push(messages::MSG_FAULT_SNAPSHOT, &empty);
+                            }
                         }
-                        Command::M1Brake => {
-                            writeln!(usart, "cmd: M1Brake\r").ok();
-                            m1.mode = LinearMode::Disabled;
-                            m1.actuator.brake();
-                            led_green.off();
+                    }
+                    Command::SetTime(unix_s) => {
+                        writeln!(log, "cmd: SetTime {}\r", unix_s).ok();
+                        match rtc.as_mut().map(|r| r.set(unix_s)) {
+                            Some(Ok(())) => {}
+                            Some(Err(e)) => {
+                                writeln!(log, "RTC: set failed: {:?}\r", e).ok();
+                            }
+                            None => {
+                                writeln!(log, "RTC: not available\r").ok();
+                            }
                         }
-                        Command::M1SetPosition(scaled) => {
-                            let mm = m1.actuator.stroke_len_mm() * (scaled as f32) / 255.0;
-                            writeln!(usart, "cmd: M1SetPosition scaled={} mm={}\r", scaled, mm)
-                                .ok();
-                            m1.mode = LinearMode::PositionControl;
-                            m1.set_target_position_mm(mm);
-                            led_green.on();
+                    }
+                    Command::SetSchedule {
+                        slot,
+                        kind,
+                        sequence,
+                        arg,
+                    } => {
+                        let trigger = Trigger::from_parts(kind, arg).unwrap_or_default();
+                        let entry = match trigger {
+                            Trigger::Off => ScheduleEntry::default(),
+                            _ => ScheduleEntry { trigger, sequence },
+                        };
+                        writeln!(log, "cmd: SetSchedule slot={} {:?}\r", slot, entry).ok();
+                        config.schedule[slot as usize] = entry;
+                        scheduler.set(slot as usize, entry, clock.now_ms());
+                        if let Err(e) = config.store(&mut flash) {
+                            writeln!(log, "config: store failed {:?}\r", e).ok();
                         }
-                        Command::M2Extend(speed) => {
-                            writeln!(usart, "cmd: M2Extend speed={}\r", speed).ok();
-                            let s = speed_to_float(speed);
-                            m2.mode = LinearMode::Disabled;
-                            m2.actuator.set_speed(s);
-                            led_yellow.on();
+                    }
+                    Command::SetInterlock {
+                        slot,
+                        axis,
+                        source,
+                        min,
+                        max,
+                    } => {
+                        let rule = Rule {
+                            axis,
+                            source,
+                            min,
+                            max,
+                        };
+                        writeln!(log, "cmd: SetInterlock slot={} {:?}\r", slot, rule).ok();
+                        config.interlocks[slot as usize] = rule;
+                        interlocks.set(slot as usize, rule);
+                        if let Err(e) = config.store(&mut flash) {
+                            writeln!(log, "config: store failed {:?}\r", e).ok();
                         }
-                        Command::M2Retract(speed) => {
-                            writeln!(usart, "cmd: M2Retract speed={}\r", speed).ok();
-                            let s = speed_to_float(speed);
+                    }
+                    Command::SetDemo(mode) => {
+                        writeln!(log, "cmd: SetDemo mode={}\r", mode).ok();
+                        demo = mode != 0;
+                        if !demo && runner.is_running() {
+                            runner.abort();
+                            m1.mode = LinearMode::Disabled;
                             m2.mode = LinearMode::Disabled;
-                            m2.actuator.set_speed(-s);
-                            led_yellow.on();
+                            m1_actuator.brake();
+                            m2_actuator.brake();
                         }
-                        Command::M2Brake => {
-                            writeln!(usart, "cmd: M2Brake\r").ok();
+                        let at_boot = mode == 2;
+                        if config.demo_at_boot != at_boot {
+                            config.demo_at_boot = at_boot;
+                            if let Err(e) = config.store(&mut flash) {
+                                writeln!(log, "config: store failed {:?}\r", e).ok();
+                            }
+                        }
+                    }
+                    #[cfg(feature = "tilt")]
+                    Command::ProvisionTiltMotor { addr, bitrate } => {
+                        writeln!(
+                            log,
+                            "cmd: ProvisionTiltMotor addr={} bitrate={}\r",
+                            addr, bitrate
+                        )
+                        .ok();
+                        // Address and bitrate were checked by the limiter.
+                        let from = Settings {
+                            addr,
+                            bitrate: Bitrate::from_code(bitrate).unwrap_or(Bitrate::M1),
+                        };
+                        tilt.disable(&mut can.motor).ok();
+                        let result = gim6010_setup::provision(
+                            &mut can.motor,
+                            clocks.pclk1().raw(),
+                            from,
+                            TILT_SETTINGS,
+                            &mut delay,
+                        );
+                        writeln!(log, "tilt: provision {:?}\r", result).ok();
+                        // The axis lost its reference with the old motor; home it again.
+                        if result.is_ok() {
+                            tilt_homing = Some(tilt.start_homing(true, tilt_progress));
+                        }
+                    }
+                    // Refused as unsupported before dispatch, like tilt moves.
+                    #[cfg(not(feature = "tilt"))]
+                    Command::ProvisionTiltMotor { .. } => {}
+                    Command::SetTelemetryDelta(interval) => {
+                        // Applied by the BLE bridge, like field selection.
+                        writeln!(log, "cmd: SetTelemetryDelta interval={}\r", interval).ok();
+                    }
+                    Command::SetDacProbe { channel, signal } => {
+                        // The limiter only lets through channel 1 or 2 and known signals.
+                        let signal = DacSignal::from_id(signal).unwrap_or(DacSignal::Off);
+                        writeln!(log, "cmd: SetDacProbe ch={} {:?}\r", channel, signal).ok();
+                        dac_probes[channel as usize - 1] = signal;
+                    }
+                    Command::ReadRegister { device, addr } => {
+                        writeln!(
+                            log,
+                            "cmd: ReadRegister dev={} addr=0x{:02x}\r",
+                            device, addr
+                        )
+                        .ok();
+                        // The DRV8873s are only reachable with `drv-spi` and the GIM6010 with
+                        // `tilt`; anything else isn't built into this board.
+                        let reply = match device {
+                            #[cfg(feature = "drv-spi")]
+                            register::device::M1_DRIVER => register::read_drv8873(
+                                m1_actuator.drv(),
+                                &mut spi_bus,
+                                device,
+                                addr,
+                            ),
+                            #[cfg(feature = "drv-spi")]
+                            register::device::M2_DRIVER => register::read_drv8873(
+                                m2_actuator.drv(),
+                                &mut spi_bus,
+                                device,
+                                addr,
+                            ),
+                            #[cfg(feature = "tilt")]
+                            register::device::TILT_MOTOR => {
+                                register::read_gim6010(&mut tilt.motor, &mut can.motor, addr)
+                            }
+                            _ => RegisterReply::failed(device, addr, RegisterStatus::Unavailable),
+                        };
+                        outbox.push(messages::MSG_REGISTER, &reply.to_bytes());
+                    }
+                    Command::M1Extend(speed) => {
+                        writeln!(log, "cmd: M1Extend speed={}\r", speed).ok();
+                        let s = speed_to_float(speed);
+                        m1.mode = LinearMode::Disabled;
+                        m1_actuator.set_speed(s);
+                        led_green.on();
+                    }
+                    Command::M1Retract(speed) => {
+                        writeln!(log, "cmd: M1Retract speed={}\r", speed).ok();
+                        let s = speed_to_float(speed);
+                        m1.mode = LinearMode::Disabled;
+                        m1_actuator.set_speed(-s);
+                        led_green.on();
+                    }
+                    Command::M1Brake => {
+                        writeln!(log, "cmd: M1Brake\r").ok();
+                        m1.mode = LinearMode::Disabled;
+                        m1_actuator.brake();
+                        led_green.off();
+                    }
+                    Command::M1SetPosition(scaled) => {
+                        let mm = m1_actuator.stroke_len_mm() * (scaled as f32) / 255.0;
+                        writeln!(
+                            log,
+                            "cmd: M1SetPosition scaled={} mm={}\r",
+                            scaled,
+                            Fixed(mm, 2)
+                        )
+                        .ok();
+                        m1.mode = LinearMode::PositionControl;
+                        m1.set_target(Mm(mm));
+                        led_green.on();
+                    }
+                    Command::M1MoveRelative(delta) => {
+                        let delta_mm = delta as f32 / 100.0;
+                        m1.move_relative(&mut m1_actuator, Mm(delta_mm));
+                        m1.mode = LinearMode::PositionControl;
+                        writeln!(
+                            log,
+                            "cmd: M1MoveRelative delta_mm={} target_mm={}\r",
+                            Fixed(delta_mm, 2),
+                            Fixed(m1.target().get(), 2)
+                        )
+                        .ok();
+                        led_green.on();
+                    }
+                    Command::M2Extend(speed) => {
+                        writeln!(log, "cmd: M2Extend speed={}\r", speed).ok();
+                        let s = speed_to_float(speed);
+                        m2.mode = LinearMode::Disabled;
+                        m2_actuator.set_speed(s);
+                        led_yellow.on();
+                    }
+                    Command::M2Retract(speed) => {
+                        writeln!(log, "cmd: M2Retract speed={}\r", speed).ok();
+                        let s = speed_to_float(speed);
+                        m2.mode = LinearMode::Disabled;
+                        m2_actuator.set_speed(-s);
+                        led_yellow.on();
+                    }
+                    Command::M2Brake => {
+                        writeln!(log, "cmd: M2Brake\r").ok();
+                        m2.mode = LinearMode::Disabled;
+                        m2_actuator.brake();
+                        led_yellow.off();
+                    }
+                    Command::M2SetPosition(scaled) => {
+                        let mm = m2_actuator.stroke_len_mm() * (scaled as f32) / 255.0;
+                        writeln!(
+                            log,
+                            "cmd: M2SetPosition scaled={} mm={}\r",
+                            scaled,
+                            Fixed(mm, 2)
+                        )
+                        .ok();
+                        m2.mode = LinearMode::PositionControl;
+                        m2.set_target(Mm(mm));
+                        // Stay high enough for the tilt the top is headed to.
+                        #[cfg(feature = "tilt")]
+                        m2.set_target(Envelope::DEFAULT.clamp_lift(m2.target(), tilt.target()));
+                        led_yellow.on();
+                    }
+                    Command::M2MoveRelative(delta) => {
+                        let delta_mm = delta as f32 / 100.0;
+                        m2.move_relative(&mut m2_actuator, Mm(delta_mm));
+                        m2.mode = LinearMode::PositionControl;
+                        #[cfg(feature = "tilt")]
+                        m2.set_target(Envelope::DEFAULT.clamp_lift(m2.target(), tilt.target()));
+                        writeln!(
+                            log,
+                            "cmd: M2MoveRelative delta_mm={} target_mm={}\r",
+                            Fixed(delta_mm, 2),
+                            Fixed(m2.target().get(), 2)
+                        )
+                        .ok();
+                        led_yellow.on();
+                    }
+                    #[cfg(feature = "tilt")]
+                    Command::TiltMoveRelative(delta) => {
+                        let delta_deg = delta as f32 / 100.0;
+                        // Clear the frame at the lower of where the lift is and where it is
+                        // headed; without feedback, assume it is retracted.
+                        let lift = match (m2_actuator.position_mm(), m2.mode) {
+                            (Some(mm), LinearMode::PositionControl) => {
+                                Mm(mm.min(m2.target().get()))
+                            }
+                            (Some(mm), _) => Mm(mm),
+                            (None, _) => Mm(0.0),
+                        };
+                        let target = tilt.target() + Rad::from(Deg(delta_deg));
+                        let result = tilt.set_target_within(
+                            &mut can.motor,
+                            target,
+                            lift,
+                            &Envelope::DEFAULT,
+                        );
+                        writeln!(
+                            log,
+                            "cmd: TiltMoveRelative delta_deg={} target_deg={} {:?}\r",
+                            Fixed(delta_deg, 2),
+                            Fixed(Deg::from(tilt.target()).get(), 2),
+                            result
+                        )
+                        .ok();
+                    }
+                    // No tilt axis without the `tilt` feature; refused as unsupported before
+                    // dispatch.
+                    #[cfg(not(feature = "tilt"))]
+                    Command::TiltMoveRelative(_) => {}
+                    #[cfg(feature = "tuning")]
+                    Command::Identify {
+                        axis,
+                        signal,
+                        amplitude,
+                        param,
+                        duration_s,
+                    } => {
+                        writeln!(
+                            log,
+                            "cmd: Identify axis={} signal={} amplitude={} param={} {}s\r",
+                            axis, signal, amplitude, param, duration_s
+                        )
+                        .ok();
+                        // Axis and signal were checked by the limiter; a previous run on the
+                        // axis was ended above.
+                        let signal = ident::Signal::from_u8(signal).unwrap_or(ident::Signal::Off);
+                        let start = |c: &LinearController| {
+                            let amplitude =
+                                speed_to_float(amplitude).min(c.effective_limits().max_duty);
+                            let range = (c.min_position_mm, c.max_position_mm);
+                            IdentRun::new(axis, signal, amplitude, param, duration_s, range)
+                        };
+                        if axis == 1 {
+                            m1.mode = LinearMode::Disabled;
+                            m1_actuator.brake();
+                            m1_ident = start(&m1);
+                        } else {
                             m2.mode = LinearMode::Disabled;
-                            m2.actuator.brake();
-                            led_yellow.off();
+                            m2_actuator.brake();
+                            m2_ident = start(&m2);
                         }
-                        Command::M2SetPosition(scaled) => {
-                            let mm = m2.actuator.stroke_len_mm() * (scaled as f32) / 255.0;
-                            writeln!(usart, "cmd: M2SetPosition scaled={} mm={}\r", scaled, mm)
-                                .ok();
-                            m2.mode = LinearMode::PositionControl;
-                            m2.set_target_position_mm(mm);
-                            led_yellow.on();
+                    }
+                    #[cfg(feature = "tuning")]
+                    Command::FrequencyResponse { axis, amplitude } => {
+                        writeln!(
+                            log,
+                            "cmd: FrequencyResponse axis={} amplitude={}\r",
+                            axis, amplitude
+                        )
+                        .ok();
+                        // A sweep in progress on the axis was ended above.
+                        let amplitude_mm = amplitude as f32 / 10.0;
+                        let start = |c: &LinearController, position: Option<f32>| {
+                            let range = (c.min_position_mm, c.max_position_mm);
+                            position.and_then(|p| BodeTest::new(axis, p, amplitude_mm, range))
+                        };
+                        if axis == 1 {
+                            m1_bode = start(&m1, m1_actuator.position_mm());
+                            if let Some(test) = &m1_bode {
+                                m1.mode = LinearMode::PositionControl;
+                                m1.set_target_position_mm(test.center_mm());
+                                led_green.on();
+                            }
+                        } else {
+                            m2_bode = start(&m2, m2_actuator.position_mm());
+                            if let Some(test) = &m2_bode {
+                                m2.mode = LinearMode::PositionControl;
+                                m2.set_target_position_mm(test.center_mm());
+                                led_yellow.on();
+                            }
                         }
-                        #[cfg(feature = "mobile-base")]
-                        Command::BaseVelocity { vx, vy, omega } => {
-                            writeln!(
-                                usart,
-                                "cmd: BaseVelocity vx={} vy={} omega={}\r",
-                                vx, vy, omega
-                            )
-                            .ok();
-                            base.set_velocity(
-                                vx as f32 / 127.0,
-                                vy as f32 / 127.0,
-                                omega as f32 / 127.0,
+                    }
+                    // Built without `tuning`; refused as unsupported before dispatch.
+                    #[cfg(not(feature = "tuning"))]
+                    Command::Identify { .. } | Command::FrequencyResponse { .. } => {}
+                    Command::Provision { node_id } => {
+                        writeln!(log, "cmd: Provision node_id={}\r", node_id).ok();
+                        m1.mode = LinearMode::Disabled;
+                        m2.mode = LinearMode::Disabled;
+                        led_yellow.on();
+                        // Provisioning holds the loop for up to a minute. Each progress report
+                        // goes out in a transfer of its own, once the bridge raises DRDY; frames
+                        // the host sends meanwhile are dropped, and sequenced ones are retried
+                        // once the ACKs resume.
+                        let settle_cycles = (sysclk_hz / 20_000.0) as u32;
+                        let drdy_wait_cycles = (sysclk_hz / 1000.0) as u32 * PROGRESS_DRDY_WAIT_MS;
+                        let send_progress = |p: Progress| {
+                            let start = DWT::cycle_count();
+                            while !drdy.is_high() {
+                                if DWT::cycle_count().wrapping_sub(start) > drdy_wait_cycles {
+                                    return;
+                                }
+                            }
+                            let mut frame = [0u8; 128];
+                            messages::encode_frame(
+                                messages::MSG_PROGRESS,
+                                &p.to_bytes(),
+                                &mut frame,
                             );
+                            cs1.select();
+                            cortex_m::asm::delay(settle_cycles);
+                            spi_bus.transfer_in_place(&mut frame).unwrap_or_default();
+                            cortex_m::asm::delay(settle_cycles);
+                            cs1.deselect();
+                            last_spi_cycle = DWT::cycle_count();
+                        };
+                        // v2 has no FIT0185, so there's no encoder to measure.
+                        let result = provision::run(
+                            &mut config,
+                            node_id,
+                            &mut m1_actuator,
+                            &mut m2_actuator,
+                            None,
+                            &mut flash,
+                            &mut delay,
+                            &mut log,
+                            send_progress,
+                        );
+                        led_yellow.off();
+                        if result.is_ok() {
+                            m1_actuator.set_calibration(config.m1_cal);
+                            m2_actuator.set_calibration(config.m2_cal);
                         }
-                        #[cfg(feature = "mobile-base")]
-                        Command::BaseBrake => {
-                            writeln!(usart, "cmd: BaseBrake\r").ok();
-                            base.brake();
+                        match result {
+                            Err(e) => {
+                                writeln!(log, "provision: FAILED {:?}\r", e).ok();
+                                led_red.on();
+                            }
+                            Ok(()) if limiter.is_safe_mode() => {
+                                // A valid record is in flash again.
+                                writeln!(log, "provision: leaving safe mode\r").ok();
+                                limiter.set_safe_mode(false);
+                                m1.limits = config.m1_limits;
+                                m2.limits = config.m2_limits;
+                                led_red.off();
+                            }
+                            Ok(()) => {}
                         }
-                        #[cfg(not(feature = "mobile-base"))]
-                        _ => {}
                     }
+                    Command::SetPolarity(bits) => {
+                        config.polarity = Polarity::from_bits(bits);
+                        writeln!(log, "cmd: SetPolarity {:?}\r", config.polarity).ok();
+                        m1_actuator.brake();
+                        m2_actuator.brake();
+                        m1_actuator.set_extend_inverted(config.polarity.m1_extend_inverted);
+                        m2_actuator.set_extend_inverted(config.polarity.m2_extend_inverted);
+                        warn_fit0185_polarity(&config.polarity, &mut log);
+                        // The lift feedback may have flipped; start the height estimate over.
+                        height.reset();
+                        if let Err(e) = config.store(&mut flash) {
+                            writeln!(log, "config: store failed {:?}\r", e).ok();
+                        }
+                    }
+                    Command::SetMotionLimits {
+                        axis,
+                        max_velocity,
+                        max_accel,
+                        max_duty,
+                    } => {
+                        let limits = MotionLimits {
+                            max_velocity: max_velocity as f32 / 10.0,
+                            max_accel: max_accel as f32,
+                            max_duty: max_duty as f32 / 255.0,
+                        };
+                        writeln!(log, "cmd: SetMotionLimits axis={} {:?}\r", axis, limits).ok();
+                        if !(1..=2).contains(&axis) {
+                            continue;
+                        }
+                        // Through the registry, so the limits get the same range checks as
+                        // MSG_SET_PARAM.
+                        let mut params = Params {
+                            config: &mut config,
+                            m1: &mut m1,
+                            m2: &mut m2,
+                        };
+                        for (param, value) in [
+                            (AxisParam::MaxVelocity, limits.max_velocity),
+                            (AxisParam::MaxAccel, limits.max_accel),
+                            (AxisParam::MaxDuty, limits.max_duty),
+                        ] {
+                            let param = Param::Axis { axis, param };
+                            if let Err(e) = params.set(param, value) {
+                                writeln!(log, "cmd: {} {:?}\r", param.desc().name, e).ok();
+                            }
+                        }
+                        if let Err(e) = config.store(&mut flash) {
+                            writeln!(log, "config: store failed {:?}\r", e).ok();
+                        }
+                    }
+                    Command::SetCurrentLimit { axis, level } => {
+                        let level = itrip_from_byte(level);
+                        writeln!(log, "cmd: SetCurrentLimit axis={} {:?}\r", axis, level).ok();
+                        match axis {
+                            1 => config.m1_itrip = level,
+                            2 => config.m2_itrip = level,
+                            _ => continue,
+                        }
+                        // Without `drv-spi` it's only stored (see the driver setup above) and
+                        // the drivers stay at 7 A.
+                        #[cfg(feature = "drv-spi")]
+                        let applied = match axis {
+                            1 => m1_actuator.set_current_limit(&mut spi_bus, level),
+                            _ => m2_actuator.set_current_limit(&mut spi_bus, level),
+                        };
+                        #[cfg(feature = "drv-spi")]
+                        if let Err(e) = applied {
+                            writeln!(log, "M{}: ITRIP not applied {:?}\r", axis, e).ok();
+                        }
+                        if let Err(e) = config.store(&mut flash) {
+                            writeln!(log, "config: store failed {:?}\r", e).ok();
+                        }
+                    }
+                    Command::SetParam { id, value } => {
+                        let name = ParamDesc::find(id).map_or("?", |d| d.name);
+                        writeln!(
+                            log,
+                            "cmd: SetParam 0x{:02x} ({}) = {}\r",
+                            id,
+                            name,
+                            Fixed(value, 4)
+                        )
+                        .ok();
+                        let mut params = Params {
+                            config: &mut config,
+                            m1: &mut m1,
+                            m2: &mut m2,
+                        };
+                        let reply = match Param::from_id(id) {
+                            Some(p) => {
+                                let result = params.set(p, value);
+                                let reply = param_reply(id, result, params.get(p));
+                                m1_actuator.set_output_range(config.m1_output);
+                                m2_actuator.set_output_range(config.m2_output);
+                                m1_actuator.set_calibration(config.m1_cal);
+                                m2_actuator.set_calibration(config.m2_cal);
+                                if result.is_ok() && p.is_persistent() {
+                                    if let Err(e) = config.store(&mut flash) {
+                                        writeln!(log, "config: store failed {:?}\r", e).ok();
+                                    }
+                                }
+                                reply
+                            }
+                            None => param_reply(id, Err(ParamError::UnknownId), 0.0),
+                        };
+                        outbox.push(messages::MSG_PARAM, &reply);
+                    }
+                    Command::CommitConfig => {
+                        let result = bank::commit(&mut flash);
+                        writeln!(log, "cmd: CommitConfig {:?}\r", result).ok();
+                        outbox.push(messages::MSG_CONFIG_STATUS, &config_status(result));
+                    }
+                    Command::RevertConfig => {
+                        let result = bank::revert(&mut flash);
+                        if let Ok(Some(restored)) = result {
+                            config = restored;
+                            m1_actuator.brake();
+                            m2_actuator.brake();
+                            m1_actuator.set_extend_inverted(config.polarity.m1_extend_inverted);
+                            m2_actuator.set_extend_inverted(config.polarity.m2_extend_inverted);
+                            m1_actuator.set_output_range(config.m1_output);
+                            m2_actuator.set_output_range(config.m2_output);
+                            m1_actuator.set_calibration(config.m1_cal);
+                            m2_actuator.set_calibration(config.m2_cal);
+                            m1.limits = config.m1_limits;
+                            m2.limits = config.m2_limits;
+                            for (slot, &entry) in config.schedule.iter().enumerate() {
+                                scheduler.set(slot, entry, clock.now_ms());
+                            }
+                            interlocks = Interlocks::new(config.interlocks);
+                        }
+                        let result = result.map(|restored| restored.is_some());
+                        writeln!(log, "cmd: RevertConfig {:?}\r", result).ok();
+                        outbox.push(messages::MSG_CONFIG_STATUS, &config_status(result));
+                    }
+                    #[cfg(feature = "mobile-base")]
+                    Command::BaseVelocity { vx, vy, omega } => {
+                        writeln!(
+                            log,
+                            "cmd: BaseVelocity vx={} vy={} omega={}\r",
+                            vx, vy, omega
+                        )
+                        .ok();
+                        base.set_velocity(
+                            vx as f32 / 127.0,
+                            vy as f32 / 127.0,
+                            omega as f32 / 127.0,
+                        );
+                    }
+                    #[cfg(feature = "mobile-base")]
+                    Command::BaseBrake => {
+                        writeln!(log, "cmd: BaseBrake\r").ok();
+                        base.brake();
+                    }
+                    #[cfg(not(feature = "mobile-base"))]
+                    _ => {}
                 }
             }
         }
         drdy_prev = drdy_now;
     }
 }
+
+/// ADC3 channel of M1's IPROPI (PA3).
+#[cfg(feature = "current-loop")]
+const M1_IPROPI_CHANNEL: u8 = 3;
+/// IPROPI resistor on M1's DRV8873, ohms. 7.7 A full scale at 3.3 V.
+#[cfg(feature = "current-loop")]
+const M1_R_IPROPI: f32 = 390.0;
+/// Current at full actuator output: four P16s near stall.
+#[cfg(feature = "current-loop")]
+const M1_MAX_AMPS: f32 = 2.5;
+/// Current loop gains, in duty per amp and duty per amp-second.
+#[cfg(feature = "current-loop")]
+const M1_CURRENT_KP: f32 = 0.1;
+#[cfg(feature = "current-loop")]
+const M1_CURRENT_KI: f32 = 200.0;
+
+/// M1's current target: the main task writes it through [`M1_INPUTS`], `TIM7` regulates to it.
+#[cfg(feature = "current-loop")]
+static M1_CURRENT: CurrentCommand = CurrentCommand::new();
+#[cfg(feature = "current-loop")]
+static M1_INPUTS: TorqueInputs = TorqueInputs::new(&M1_CURRENT, M1_MAX_AMPS);
+/// IPROPI scan buffer, written by DMA2.
+#[cfg(feature = "current-loop")]
+#[link_section = ".dtcm.m1_ipropi"]
+static mut M1_IPROPI: [u16; 1] = [0; 1];
+#[cfg(feature = "current-loop")]
+static M1_LOOP: cortex_m::interrupt::Mutex<RefCell<Option<CurrentLoopIsr>>> =
+    cortex_m::interrupt::Mutex::new(RefCell::new(None));
+
+/// What the `TIM7` handler owns: M1's TIM3 channels and the IPROPI scan.
+#[cfg(feature = "current-loop")]
+struct CurrentLoopIsr {
+    drive: TorqueDrive<hal::timer::PwmChannel<pac::TIM3, 0>, hal::timer::PwmChannel<pac::TIM3, 1>>,
+    ipropi: AdcDma<1>,
+}
+
+/// Runs M1's current loop, see [`current_loop`](omnitiles::control::current_loop).
+#[cfg(feature = "current-loop")]
+#[interrupt]
+fn TIM7() {
+    if tick_timer::on_tim7_interrupt() {
+        cortex_m::interrupt::free(|cs| {
+            if let Some(s) = M1_LOOP.borrow(cs).borrow_mut().as_mut() {
+                s.drive.on_tick(&M1_CURRENT, s.ipropi.latest(0));
+            }
+        });
+    }
+}
+
+/// Counts TIM3 counter wraps for the 32-bit encoder count, see
+/// [`on_tim3_interrupt`](omnitiles::hw::encoder::on_tim3_interrupt). Only unmasked by
+/// `Encoder::tim3`; while TIM3 drives the PWM outputs it never runs.
+#[interrupt]
+fn TIM3() {
+    omnitiles::hw::encoder::on_tim3_interrupt();
+}
@@ -0,0 +1,381 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Minimal CANopen slave (CiA 301 subset with a CiA 402-lite drive profile).
+//!
+//! Lets an off-the-shelf CANopen master drive a tile without the custom protocol. Supported:
+//!
+//! - NMT state machine (boot-up, pre-operational, operational, stopped) and node reset commands.
+//! - Heartbeat producer (object 0x1017).
+//! - Expedited SDO upload/download on a small object dictionary.
+//! - RPDO1: `controlword: u16`, `target_position: i32`.
+//! - TPDO1: `statusword: u16`, `position_actual: i32`, sent on every CANopen SYNC.
+//!
+//! Positions are in 0.01 mm. This module only handles the protocol; the application feeds it
+//! feedback with [`CanOpenNode::set_feedback`] and applies targets from
+//! [`CanOpenNode::take_target`].
+//!
+//! CANopen SYNC uses COB-ID 0x080 with at most one data byte, which is distinguishable from the
+//! 8-byte SYNC frames of [`crate::net::sync`]; still, run only one of the two on a given backbone.
+
+use crate::hw::CanFrame;
+
+const COB_NMT: u16 = 0x000;
+const COB_SYNC: u16 = 0x080;
+const COB_TPDO1: u16 = 0x180;
+const COB_RPDO1: u16 = 0x200;
+const COB_SDO_TX: u16 = 0x580;
+const COB_SDO_RX: u16 = 0x600;
+const COB_HEARTBEAT: u16 = 0x700;
+
+/// Device type reported in 0x1000: drive profile 402.
+const DEVICE_TYPE: u32 = 0x0000_0192;
+
+// Object dictionary indices.
+const OD_DEVICE_TYPE: u16 = 0x1000;
+const OD_ERROR_REGISTER: u16 = 0x1001;
+const OD_HEARTBEAT_TIME: u16 = 0x1017;
+const OD_CONTROLWORD: u16 = 0x6040;
+const OD_STATUSWORD: u16 = 0x6041;
+const OD_POSITION_ACTUAL: u16 = 0x6064;
+const OD_TARGET_POSITION: u16 = 0x607A;
+
+// SDO abort codes.
+const ABORT_BAD_COMMAND: u32 = 0x0504_0001;
+const ABORT_READ_ONLY: u32 = 0x0601_0002;
+const ABORT_NO_OBJECT: u32 = 0x0602_0000;
+const ABORT_NO_SUBINDEX: u32 = 0x0609_0011;
+const ABORT_BAD_STATE: u32 = 0x0800_0022;
+
+// CiA 402 statusword bits (subset).
+const SW_READY_TO_SWITCH_ON: u16 = 1 << 0;
+const SW_SWITCHED_ON: u16 = 1 << 1;
+const SW_OPERATION_ENABLED: u16 = 1 << 2;
+const SW_FAULT: u16 = 1 << 3;
+const SW_TARGET_REACHED: u16 = 1 << 10;
+
+/// Controlword value that requests "operation enabled" (switch on, enable voltage, quick stop
+/// inactive, enable operation).
+const CW_ENABLE_OPERATION: u16 = 0x000F;
+/// Controlword fault-reset bit.
+const CW_FAULT_RESET: u16 = 1 << 7;
+
+/// NMT state. The discriminant is the value reported in heartbeat frames.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum NmtState {
+    BootUp = 0x00,
+    Stopped = 0x04,
+    Operational = 0x05,
+    PreOperational = 0x7F,
+}
+
+/// Side effects of a received frame that the application must handle.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Request {
+    /// NMT "reset node": the application should reset the MCU.
+    ResetNode,
+    /// Controlword cleared the enable bits: stop the axis.
+    Disable,
+    /// Controlword requested a fault reset.
+    FaultReset,
+}
+
+fn frame(cob: u16, bytes: &[u8]) -> CanFrame {
+    CanFrame::standard(cob, bytes).unwrap()
+}
+
+/// CANopen slave state for one node.
+pub struct CanOpenNode {
+    node_id: u8,
+    state: NmtState,
+    heartbeat_ms: u16,
+    next_heartbeat_us: u64,
+
+    controlword: u16,
+    target_position: i32,
+    target_pending: bool,
+
+    position_actual: i32,
+    on_target: bool,
+    fault: bool,
+    /// Error register (0x1001). Bit 0 is the generic error bit.
+    pub error_register: u8,
+
+    request: Option<Request>,
+}
+
+impl CanOpenNode {
+    /// Create a node. `node_id` must be in 1..=127.
+    pub fn new(node_id: u8) -> Self {
+        Self {
+            node_id: node_id & 0x7F,
+            state: NmtState::BootUp,
+            heartbeat_ms: 1000,
+            next_heartbeat_us: 0,
+            controlword: 0,
+            target_position: 0,
+            target_pending: false,
+            position_actual: 0,
+            on_target: false,
+            fault: false,
+            error_register: 0,
+            request: None,
+        }
+    }
+
+    #[inline]
+    pub fn node_id(&self) -> u8 {
+        self.node_id
+    }
+
+    #[inline]
+    pub fn state(&self) -> NmtState {
+        self.state
+    }
+
+    /// Boot-up message. Transmit once after power-on; the node then enters pre-operational.
+    pub fn boot_up(&mut self) -> CanFrame {
+        self.state = NmtState::PreOperational;
+        frame(
+            COB_HEARTBEAT + self.node_id as u16,
+            &[NmtState::BootUp as u8],
+        )
+    }
+
+    /// True when the master has enabled operation and the node is operational.
+    pub fn is_enabled(&self) -> bool {
+        self.state == NmtState::Operational
+            && self.controlword & CW_ENABLE_OPERATION == CW_ENABLE_OPERATION
+            && !self.fault
+    }
+
+    /// Update the feedback reported in TPDO1 and the statusword.
+    pub fn set_feedback(&mut self, position_actual: i32, on_target: bool, fault: bool) {
+        self.position_actual = position_actual;
+        self.on_target = on_target;
+        self.fault = fault;
+        if fault {
+            self.error_register |= 0x01;
+        }
+    }
+
+    /// Take a newly received target position (0.01 mm), if any.
+    pub fn take_target(&mut self) -> Option<i32> {
+        if self.target_pending && self.is_enabled() {
+            self.target_pending = false;
+            Some(self.target_position)
+        } else {
+            None
+        }
+    }
+
+    /// Take a pending request for the application.
+    pub fn take_request(&mut self) -> Option<Request> {
+        self.request.take()
+    }
+
+    /// CiA 402 statusword derived from the current state.
+    pub fn statusword(&self) -> u16 {
+        if self.fault {
+            return SW_FAULT;
+        }
+        let mut sw = 0;
+        if self.state == NmtState::Operational {
+            sw |= SW_READY_TO_SWITCH_ON | SW_SWITCHED_ON;
+            if self.is_enabled() {
+                sw |= SW_OPERATION_ENABLED;
+            }
+        }
+        if self.on_target {
+            sw |= SW_TARGET_REACHED;
+        }
+        sw
+    }
+
+    /// TPDO1 frame.
+    pub fn tpdo1(&self) -> CanFrame {
+        let sw = self.statusword().to_le_bytes();
+        let pos = self.position_actual.to_le_bytes();
+        frame(
+            COB_TPDO1 + self.node_id as u16,
+            &[sw[0], sw[1], pos[0], pos[1], pos[2], pos[3]],
+        )
+    }
+
+    /// Returns a heartbeat frame if one is due at `now_us`.
+    pub fn poll(&mut self, now_us: u64) -> Option<CanFrame> {
+        if self.heartbeat_ms == 0 || now_us < self.next_heartbeat_us {
+            return None;
+        }
+        self.next_heartbeat_us = now_us + self.heartbeat_ms as u64 * 1000;
+        Some(frame(
+            COB_HEARTBEAT + self.node_id as u16,
+            &[self.state as u8],
+        ))
+    }
+
+    /// Process a received frame. Returns a frame to transmit in reply, if any.
+    pub fn on_frame(&mut self, frame: &CanFrame) -> Option<CanFrame> {
+        let cob = frame.standard_id()?;
+        let data = frame.data();
+        let own = self.node_id as u16;
+
+        if cob == COB_NMT {
+            self.on_nmt(data)
+        } else if cob == COB_SYNC && data.len() <= 1 {
+            (self.state == NmtState::Operational).then(|| self.tpdo1())
+        } else if cob == COB_RPDO1 + own {
+            self.on_rpdo1(data);
+            None
+        } else if cob == COB_SDO_RX + own {
+            if self.state == NmtState::Stopped {
+                return None;
+            }
+            Some(self.on_sdo(data))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the boot-up frame to re-send after a communication reset.
+    fn on_nmt(&mut self, data: &[u8]) -> Option<CanFrame> {
+        if data.len() < 2 || (data[1] != 0 && data[1] != self.node_id) {
+            return None;
+        }
+        match data[0] {
+            0x01 => self.state = NmtState::Operational,
+            0x02 => {
+                self.state = NmtState::Stopped;
+                self.request = Some(Request::Disable);
+            }
+            0x80 => {
+                self.state = NmtState::PreOperational;
+                self.request = Some(Request::Disable);
+            }
+            0x81 => self.request = Some(Request::ResetNode),
+            // Reset communication: communication objects go back to their defaults and the node
+            // announces itself again, as after power-on.
+            0x82 => {
+                self.heartbeat_ms = 1000;
+                self.next_heartbeat_us = 0;
+                self.controlword = 0;
+                self.target_pending = false;
+                self.request = Some(Request::Disable);
+                return Some(self.boot_up());
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn on_rpdo1(&mut self, data: &[u8]) {
+        if self.state != NmtState::Operational || data.len() < 6 {
+            return;
+        }
+        self.write_controlword(u16::from_le_bytes([data[0], data[1]]));
+        self.target_position = i32::from_le_bytes([data[2], data[3], data[4], data[5]]);
+        self.target_pending = true;
+    }
+
+    fn write_controlword(&mut self, cw: u16) {
+        let was_enabled = self.controlword & CW_ENABLE_OPERATION == CW_ENABLE_OPERATION;
+        if cw & CW_FAULT_RESET != 0 && self.controlword & CW_FAULT_RESET == 0 {
+            self.error_register = 0;
+            self.request = Some(Request::FaultReset);
+        }
+        self.controlword = cw;
+        if was_enabled && cw & CW_ENABLE_OPERATION != CW_ENABLE_OPERATION {
+            self.request = Some(Request::Disable);
+        }
+    }
+
+    /// Handle an expedited SDO request and build the response.
+    fn on_sdo(&mut self, data: &[u8]) -> CanFrame {
+        let cob = COB_SDO_TX + self.node_id as u16;
+        if data.len() < 8 {
+            return frame(cob, &Self::abort(0, 0, ABORT_BAD_COMMAND));
+        }
+        let ccs = data[0];
+        let index = u16::from_le_bytes([data[1], data[2]]);
+        let sub = data[3];
+        let value = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+
+        let resp = match ccs & 0xE0 {
+            // Upload (read)
+            0x40 => match self.read_object(index, sub) {
+                Ok((v, len)) => {
+                    let mut r = [0u8; 8];
+                    r[0] = 0x43 | ((4 - len) << 2);
+                    r[1..4].copy_from_slice(&data[1..4]);
+                    r[4..8].copy_from_slice(&v.to_le_bytes());
+                    r
+                }
+                Err(code) => Self::abort(index, sub, code),
+            },
+            // Expedited download (write)
+            0x20 => match self.write_object(index, sub, value) {
+                Ok(()) => {
+                    let mut r = [0u8; 8];
+                    r[0] = 0x60;
+                    r[1..4].copy_from_slice(&data[1..4]);
+                    r
+                }
+                Err(code) => Self::abort(index, sub, code),
+            },
+            _ => Self::abort(index, sub, ABORT_BAD_COMMAND),
+        };
+        frame(cob, &resp)
+    }
+
+    fn abort(index: u16, sub: u8, code: u32) -> [u8; 8] {
+        let i = index.to_le_bytes();
+        let c = code.to_le_bytes();
+        [0x80, i[0], i[1], sub, c[0], c[1], c[2], c[3]]
+    }
+
+    /// Read an object. Returns the value and its size in bytes.
+    fn read_object(&self, index: u16, sub: u8) -> Result<(u32, u8), u32> {
+        if sub != 0 {
+            return Err(ABORT_NO_SUBINDEX);
+        }
+        match index {
+            OD_DEVICE_TYPE => Ok((DEVICE_TYPE, 4)),
+            OD_ERROR_REGISTER => Ok((self.error_register as u32, 1)),
+            OD_HEARTBEAT_TIME => Ok((self.heartbeat_ms as u32, 2)),
+            OD_CONTROLWORD => Ok((self.controlword as u32, 2)),
+            OD_STATUSWORD => Ok((self.statusword() as u32, 2)),
+            OD_POSITION_ACTUAL => Ok((self.position_actual as u32, 4)),
+            OD_TARGET_POSITION => Ok((self.target_position as u32, 4)),
+            _ => Err(ABORT_NO_OBJECT),
+        }
+    }
+
+    fn write_object(&mut self, index: u16, sub: u8, value: u32) -> Result<(), u32> {
+        if sub != 0 {
+            return Err(ABORT_NO_SUBINDEX);
+        }
+        match index {
+            OD_HEARTBEAT_TIME => {
+                self.heartbeat_ms = value as u16;
+                Ok(())
+            }
+            OD_CONTROLWORD => {
+                self.write_controlword(value as u16);
+                Ok(())
+            }
+            OD_TARGET_POSITION => {
+                if self.state != NmtState::Operational {
+                    return Err(ABORT_BAD_STATE);
+                }
+                self.target_position = value as i32;
+                self.target_pending = true;
+                Ok(())
+            }
+            OD_DEVICE_TYPE | OD_ERROR_REGISTER | OD_STATUSWORD | OD_POSITION_ACTUAL => {
+                Err(ABORT_READ_ONLY)
+            }
+            _ => Err(ABORT_NO_OBJECT),
+        }
+    }
+}
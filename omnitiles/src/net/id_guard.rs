@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Duplicate node ID detection on the CAN1 backbone.
+//!
+//! Two tiles provisioned with the same node ID would transmit on the same identifiers, and CAN
+//! arbitration can't tell their frames apart: both corrupt each other's log stream, heartbeat and
+//! PDOs without any error showing up. At boot a tile first listens for
+//! [`LISTEN_WINDOW_US`] and only starts transmitting on its own IDs if nobody else used them in
+//! that time. A longer window than the slowest periodic frame (the 1 s CANopen heartbeat) is
+//! enough to catch a running tile.
+//!
+//! Listening continues afterwards, so a duplicate that boots later is caught too. A collision
+//! latches until reset: the tile stops transmitting on the backbone and reports
+//! `heartbeat::fault::NODE_ID_CONFLICT` to the host until it is re-provisioned and rebooted.
+//!
+//! Frames are never received back by their sender unless the peripheral is in loopback mode, so
+//! the tile's own traffic can't trip the check.
+
+use crate::hw::CanFrame;
+use crate::log::CAN_LOG_BASE_ID;
+
+/// Listen this long before transmitting.
+pub const LISTEN_WINDOW_US: u64 = 1_500_000;
+
+/// Per-node base IDs: CANopen TPDO1, SDO response, heartbeat, and the CAN log stream.
+const NODE_BASE_IDS: [u16; 4] = [0x180, 0x580, 0x700, CAN_LOG_BASE_ID];
+
+/// Standard IDs a node transmits on.
+pub fn owned_ids(node_id: u8) -> [u16; 4] {
+    NODE_BASE_IDS.map(|base| base + (node_id & 0x7F) as u16)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GuardState {
+    /// Still inside the listen window; don't transmit yet.
+    Listening,
+    /// Nobody else uses this node's IDs.
+    Clear,
+    /// Another node transmitted on this ID.
+    Collision(u16),
+}
+
+/// Watches backbone traffic for frames on this node's IDs.
+pub struct IdGuard {
+    ids: [u16; 4],
+    listen_until_us: u64,
+    state: GuardState,
+}
+
+impl IdGuard {
+    /// Start listening at `now_us`.
+    pub fn new(node_id: u8, now_us: u64) -> Self {
+        Self {
+            ids: owned_ids(node_id),
+            listen_until_us: now_us + LISTEN_WINDOW_US,
+            state: GuardState::Listening,
+        }
+    }
+
+    /// Feed a received backbone frame.
+    pub fn observe(&mut self, frame: &CanFrame) {
+        if let GuardState::Collision(_) = self.state {
+            return;
+        }
+        if let Some(id) = frame.standard_id() {
+            if self.ids.contains(&id) {
+                self.state = GuardState::Collision(id);
+            }
+        }
+    }
+
+    /// Advance to [`GuardState::Clear`] once the listen window has passed quietly.
+    pub fn poll(&mut self, now_us: u64) -> GuardState {
+        if self.state == GuardState::Listening && now_us >= self.listen_until_us {
+            self.state = GuardState::Clear;
+        }
+        self.state
+    }
+
+    /// True if the node may transmit on its own IDs.
+    pub fn may_transmit(&self) -> bool {
+        self.state == GuardState::Clear
+    }
+
+    /// The ID another node was seen on, if any.
+    pub fn collision(&self) -> Option<u16> {
+        match self.state {
+            GuardState::Collision(id) => Some(id),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! # Inter-Tile Networking
+//!
+//! Protocols that run on the CAN1 backbone shared by all tiles on a floor.
+//!
+//! ## Modules
+//!
+//! - [`sync`] - Time synchronization and shared "go" ticks for coordinated motion.
+//! - [`slcan`] - slcan (LAWICEL) bridge that turns the tile into a USB-CAN adapter.
+//! - [`id_guard`] - Holds off backbone transmission while another node uses this node's IDs.
+//! - `canopen` - Minimal CANopen slave (NMT, heartbeat, SDO, PDOs). Requires the `canopen`
+//!   feature.
+
+#[cfg(feature = "canopen")]
+pub mod canopen;
+pub mod id_guard;
+pub mod slcan;
+pub mod sync;
+
+#[cfg(feature = "canopen")]
+pub use canopen::CanOpenNode;
+pub use id_guard::IdGuard;
+pub use slcan::Slcan;
+pub use sync::{SyncMaster, SyncSlave};
@@ -0,0 +1,253 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! SocketCAN-compatible serial bridge (slcan / LAWICEL ASCII protocol).
+//!
+//! In bridge mode the tile acts as a USB-CAN adapter: a laptop runs `slcand` against the debug
+//! UART and gets a regular `can0` interface for bus debugging during installation. Supported
+//! commands:
+//!
+//! | Command | Meaning |
+//! | ------- | ------- |
+//! | `Sn`    | Select bitrate (`S0` = 10 kbit/s … `S8` = 1 Mbit/s) |
+//! | `O` / `L` | Open the channel (normal / listen-only) |
+//! | `C`     | Close the channel |
+//! | `tIIILDD…` / `TIIIIIIIILDD…` | Transmit a standard / extended data frame |
+//! | `rIIIL` / `RIIIIIIIIL` | Transmit a standard / extended remote frame |
+//! | `V` / `N` / `F` | Version / serial number / status flags |
+//!
+//! Each command is terminated by CR and answered with CR (ok) or BEL (error). Received frames are
+//! forwarded in the same `t`/`T`/`r`/`R` format while the channel is open.
+
+use bxcan::{Data, ExtendedId, Frame, Id, StandardId};
+use stm32f7xx_hal::{can as hal_can, serial::Instance};
+
+use crate::hw::{CanBus, Usart};
+
+/// Positive acknowledgement.
+const ACK: u8 = b'\r';
+/// Negative acknowledgement.
+const NACK: u8 = 0x07;
+
+/// Longest command line: `T` + 8 id + 1 len + 16 data.
+const LINE_LEN: usize = 26;
+
+/// Bitrates selected by `S0`..`S8`.
+const BITRATES: [u32; 9] = [
+    10_000, 20_000, 50_000, 100_000, 125_000, 250_000, 500_000, 800_000, 1_000_000,
+];
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Compute a CAN_BTR value for `bitrate` with 16 time quanta per bit (sample point 87.5 %).
+///
+/// Returns `None` if `pclk1_hz` is not an integer multiple of `16 * bitrate`.
+pub fn btr_for_bitrate(pclk1_hz: u32, bitrate: u32) -> Option<u32> {
+    let tq_hz = bitrate.checked_mul(16)?;
+    if tq_hz == 0 || pclk1_hz % tq_hz != 0 {
+        return None;
+    }
+    let brp = pclk1_hz / tq_hz;
+    if brp == 0 || brp > 1024 {
+        return None;
+    }
+    // SJW = 1 tq, TS1 = 13 tq, TS2 = 2 tq.
+    Some((1 << 20) | (12 << 16) | (brp - 1))
+}
+
+fn hex_val(c: u8) -> Option<u32> {
+    match c {
+        b'0'..=b'9' => Some((c - b'0') as u32),
+        b'a'..=b'f' => Some((c - b'a' + 10) as u32),
+        b'A'..=b'F' => Some((c - b'A' + 10) as u32),
+        _ => None,
+    }
+}
+
+fn parse_hex(s: &[u8]) -> Option<u32> {
+    s.iter()
+        .try_fold(0u32, |acc, &c| Some(acc << 4 | hex_val(c)?))
+}
+
+/// Parse a `t`/`T`/`r`/`R` line into a frame.
+fn parse_frame(line: &[u8]) -> Option<Frame> {
+    let (extended, remote) = match line.first()? {
+        b't' => (false, false),
+        b'T' => (true, false),
+        b'r' => (false, true),
+        b'R' => (true, true),
+        _ => return None,
+    };
+    let id_len = if extended { 8 } else { 3 };
+    let raw_id = parse_hex(line.get(1..1 + id_len)?)?;
+    let dlc = parse_hex(line.get(1 + id_len..2 + id_len)?)? as usize;
+    if dlc > 8 {
+        return None;
+    }
+    let id: Id = if extended {
+        ExtendedId::new(raw_id)?.into()
+    } else {
+        StandardId::new(raw_id as u16)?.into()
+    };
+
+    if remote {
+        return (line.len() == 2 + id_len).then(|| Frame::new_remote(id, dlc as u8));
+    }
+
+    let hex = &line[2 + id_len..];
+    if hex.len() != dlc * 2 {
+        return None;
+    }
+    let mut data = [0u8; 8];
+    for (i, pair) in hex.chunks(2).enumerate() {
+        data[i] = parse_hex(pair)? as u8;
+    }
+    Some(Frame::new_data(id, Data::new(&data[..dlc])?))
+}
+
+/// slcan bridge state.
+pub struct Slcan {
+    pclk1_hz: u32,
+    line: [u8; LINE_LEN],
+    len: usize,
+    overflow: bool,
+    bitrate: Option<u32>,
+    open: bool,
+    /// Frames dropped because the CAN transmit failed.
+    pub tx_errors: u32,
+}
+
+impl Slcan {
+    /// Create a closed bridge. `pclk1_hz` is the APB1 clock feeding the CAN peripheral.
+    pub fn new(pclk1_hz: u32) -> Self {
+        Self {
+            pclk1_hz,
+            line: [0; LINE_LEN],
+            len: 0,
+            overflow: false,
+            bitrate: None,
+            open: false,
+            tx_errors: 0,
+        }
+    }
+
+    #[inline]
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Pump both directions once: handle any pending UART input and, while open, forward received
+    /// CAN frames to the UART. Call from the main loop in bridge mode.
+    pub fn service<U, I>(&mut self, usart: &mut Usart<U>, bus: &mut CanBus<I>)
+    where
+        U: Instance,
+        hal_can::Can<I>: bxcan::Instance,
+    {
+        while let Some(byte) = usart.read_byte() {
+            if byte != b'\r' {
+                if self.len < LINE_LEN {
+                    self.line[self.len] = byte;
+                    self.len += 1;
+                } else {
+                    self.overflow = true;
+                }
+                continue;
+            }
+            let ok = !self.overflow && self.execute(usart, bus);
+            usart.write_byte(if ok { ACK } else { NACK });
+            self.len = 0;
+            self.overflow = false;
+        }
+
+        if self.open {
+            while let Some(Ok(frame)) = bus.try_receive() {
+                Self::write_frame(usart, &frame);
+            }
+        }
+    }
+
+    /// Execute the buffered command line. Replies with payload (if any) are written here; the
+    /// caller writes the final ACK/NACK.
+    fn execute<U, I>(&mut self, usart: &mut Usart<U>, bus: &mut CanBus<I>) -> bool
+    where
+        U: Instance,
+        hal_can::Can<I>: bxcan::Instance,
+    {
+        let line = &self.line[..self.len];
+        let Some(&cmd) = line.first() else {
+            return true;
+        };
+        match cmd {
+            b'S' if line.len() == 2 && !self.open => {
+                let Some(&bitrate) = hex_val(line[1]).and_then(|i| BITRATES.get(i as usize)) else {
+                    return false;
+                };
+                self.bitrate = Some(bitrate);
+                true
+            }
+            b'O' | b'L' if line.len() == 1 && !self.open => {
+                let mut config = bus.inner().modify_config();
+                if let Some(bitrate) = self.bitrate {
+                    let Some(btr) = btr_for_bitrate(self.pclk1_hz, bitrate) else {
+                        return false;
+                    };
+                    config = config.set_bit_timing(btr);
+                }
+                config.set_silent(cmd == b'L').enable();
+                self.open = true;
+                true
+            }
+            // Sleep mode takes the controller off the bus, so it stops acknowledging frames.
+            // `O`/`L` wake it again through the configuration path.
+            b'C' if line.len() == 1 => {
+                bus.inner().sleep();
+                self.open = false;
+                true
+            }
+            b't' | b'T' | b'r' | b'R' if self.open => match parse_frame(line) {
+                Some(frame) => match bus.transmit_frame(&frame) {
+                    Ok(_) => true,
+                    Err(_) => {
+                        self.tx_errors = self.tx_errors.wrapping_add(1);
+                        false
+                    }
+                },
+                None => false,
+            },
+            b'V' => {
+                usart.write_str("V0101");
+                true
+            }
+            b'N' => {
+                usart.write_str("N0001");
+                true
+            }
+            b'F' => {
+                usart.write_str("F00");
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Write `frame` in slcan format, terminated by CR.
+    pub fn write_frame<U: Instance>(usart: &mut Usart<U>, frame: &Frame) {
+        let remote = frame.is_remote_frame();
+        let (tag, id, digits) = match frame.id() {
+            Id::Standard(id) => (if remote { b'r' } else { b't' }, id.as_raw() as u32, 3),
+            Id::Extended(id) => (if remote { b'R' } else { b'T' }, id.as_raw(), 8),
+        };
+        usart.write_byte(tag);
+        for i in (0..digits).rev() {
+            usart.write_byte(HEX[((id >> (i * 4)) & 0xF) as usize]);
+        }
+        usart.write_byte(HEX[(frame.dlc() & 0xF) as usize]);
+        if let Some(data) = frame.data() {
+            for &b in data.iter() {
+                usart.write_byte(HEX[(b >> 4) as usize]);
+                usart.write_byte(HEX[(b & 0xF) as usize]);
+            }
+        }
+        usart.write_byte(b'\r');
+    }
+}
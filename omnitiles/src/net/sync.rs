@@ -0,0 +1,176 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Time synchronization and coordinated start over the CAN1 backbone.
+//!
+//! One tile acts as the master and periodically broadcasts its monotonic time in a SYNC frame.
+//! Every other tile timestamps the frame on reception and keeps a filtered offset between the
+//! master clock and its own [`MonoClock`](crate::hw::MonoClock). To start a coordinated motion the
+//! master broadcasts a GO frame naming a future master time; each slave converts it to local time
+//! and fires when its own clock reaches that point, so all tiles start together regardless of how
+//! long their individual motion commands took to arrive.
+//!
+//! CanFrame layout (standard IDs, little-endian):
+//!
+//! | ID | Payload |
+//! | -- | ------- |
+//! | [`SYNC_ID`] | `master_us: u64` |
+//! | [`GO_ID`]   | `tag: u8`, `at_master_us: u56` |
+
+use crate::hw::CanFrame;
+
+/// Backbone ID of the SYNC broadcast.
+pub const SYNC_ID: u16 = 0x080;
+/// Backbone ID of the GO broadcast.
+pub const GO_ID: u16 = 0x081;
+
+/// Default SYNC broadcast period.
+pub const DEFAULT_SYNC_PERIOD_US: u64 = 100_000;
+/// A slave counts as synced if it has heard a SYNC within this many periods.
+const SYNC_TIMEOUT_PERIODS: u64 = 5;
+/// Offset low-pass filter weight as a power of two (new = old + (meas - old) / 2^N).
+const OFFSET_FILTER_SHIFT: u32 = 3;
+
+/// Largest master time that fits in the 56-bit GO field.
+const GO_TIME_MASK: u64 = (1 << 56) - 1;
+
+fn frame(id: u16, bytes: &[u8]) -> CanFrame {
+    CanFrame::standard(id, bytes).unwrap()
+}
+
+/// Backbone time master.
+pub struct SyncMaster {
+    pub period_us: u64,
+    next_sync_us: u64,
+    go: Option<(u8, u64)>,
+}
+
+impl SyncMaster {
+    pub fn new(period_us: u64) -> Self {
+        Self {
+            period_us,
+            next_sync_us: 0,
+            go: None,
+        }
+    }
+
+    /// Returns a SYNC frame to transmit if one is due at `now_us`.
+    pub fn poll(&mut self, now_us: u64) -> Option<CanFrame> {
+        if now_us < self.next_sync_us {
+            return None;
+        }
+        self.next_sync_us = now_us + self.period_us;
+        Some(frame(SYNC_ID, &now_us.to_le_bytes()))
+    }
+
+    /// Build a GO frame that fires `tag` on every slave at `at_master_us`, and arm the same tag
+    /// locally so the master moves with them.
+    pub fn go_frame(&mut self, tag: u8, at_master_us: u64) -> CanFrame {
+        let at_master_us = at_master_us & GO_TIME_MASK;
+        self.go = Some((tag, at_master_us));
+        let t = at_master_us.to_le_bytes();
+        frame(GO_ID, &[tag, t[0], t[1], t[2], t[3], t[4], t[5], t[6]])
+    }
+
+    /// Returns the locally armed GO tag once `now_us` reaches its fire time, consuming it.
+    pub fn go_due(&mut self, now_us: u64) -> Option<u8> {
+        match self.go {
+            Some((tag, at)) if now_us >= at => {
+                self.go = None;
+                Some(tag)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Backbone time slave.
+pub struct SyncSlave {
+    period_us: u64,
+    /// `master_us - local_us`, filtered.
+    offset_us: i64,
+    last_sync_local_us: Option<u64>,
+    /// Pending GO: (tag, local fire time).
+    go: Option<(u8, u64)>,
+}
+
+impl SyncSlave {
+    pub fn new(period_us: u64) -> Self {
+        Self {
+            period_us,
+            offset_us: 0,
+            last_sync_local_us: None,
+            go: None,
+        }
+    }
+
+    /// Feed a backbone frame received at local time `now_us`. Returns `true` if the frame was a
+    /// sync-layer frame.
+    pub fn on_frame(&mut self, frame: &CanFrame, now_us: u64) -> bool {
+        let Some(id) = frame.standard_id() else {
+            return false;
+        };
+        let data = frame.data();
+
+        match id {
+            SYNC_ID if data.len() == 8 => {
+                let master_us = u64::from_le_bytes(data[..8].try_into().unwrap());
+                let measured = master_us as i64 - now_us as i64;
+                if self.last_sync_local_us.is_none() {
+                    self.offset_us = measured;
+                } else {
+                    self.offset_us += (measured - self.offset_us) >> OFFSET_FILTER_SHIFT;
+                }
+                self.last_sync_local_us = Some(now_us);
+                true
+            }
+            GO_ID if data.len() == 8 => {
+                let mut t = [0u8; 8];
+                t[..7].copy_from_slice(&data[1..8]);
+                let at_master_us = u64::from_le_bytes(t);
+                self.go = Some((data[0], self.master_to_local(at_master_us)));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Convert a master timestamp to local time using the current offset estimate.
+    pub fn master_to_local(&self, master_us: u64) -> u64 {
+        (master_us as i64 - self.offset_us).max(0) as u64
+    }
+
+    /// Current master time estimate.
+    pub fn master_now(&self, now_us: u64) -> u64 {
+        (now_us as i64 + self.offset_us).max(0) as u64
+    }
+
+    /// Filtered `master - local` offset in microseconds.
+    #[inline]
+    pub fn offset_us(&self) -> i64 {
+        self.offset_us
+    }
+
+    /// True if a SYNC has been received recently.
+    pub fn is_synced(&self, now_us: u64) -> bool {
+        self.last_sync_local_us.map_or(false, |t| {
+            now_us.saturating_sub(t) <= self.period_us * SYNC_TIMEOUT_PERIODS
+        })
+    }
+
+    /// Returns the GO tag once its fire time has been reached, consuming it.
+    pub fn go_due(&mut self, now_us: u64) -> Option<u8> {
+        match self.go {
+            Some((tag, at)) if now_us >= at => {
+                self.go = None;
+                Some(tag)
+            }
+            _ => None,
+        }
+    }
+
+    /// Drop any pending GO.
+    pub fn cancel_go(&mut self) {
+        self.go = None;
+    }
+}
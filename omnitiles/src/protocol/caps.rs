@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Capabilities handshake.
+//!
+//! On `MSG_GET_CAPABILITIES` the tile replies with one `MSG_CAPABILITIES` summary frame followed
+//! by one `MSG_AXIS_CAPS` frame per axis, so the host learns ranges and units at connect time
+//! instead of hardcoding them per firmware build.
+//!
+//! `MSG_CAPABILITIES` payload: `[protocol_version, node_id, axis_count, reset_reason, flags]`,
+//! where `reset_reason` is a [`ResetReason`](crate::hw::ResetReason) discriminant and `flags`
+//! holds [`flags`] bits.
+//!
+//! `MSG_AXIS_CAPS` payload (little-endian, lengths in hundredths of [`Unit`]):
+//!
+//! | Offset | Field |
+//! | ------ | ----- |
+//! | 0 | `axis: u8` |
+//! | 1 | `unit: u8` (see [`Unit`]) |
+//! | 2 | `min: i16` – lowest reachable target |
+//! | 4 | `max: i16` – highest reachable target |
+//! | 6 | `full_scale: u16` – value that `SET_POSITION` 255 maps to, 0 if the axis has none |
+
+use crate::hw::ResetReason;
+
+/// Bumped whenever a message layout changes incompatibly.
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Serialized `MSG_CAPABILITIES` payload length.
+pub const CAPABILITIES_LEN: usize = 5;
+/// Serialized `MSG_AXIS_CAPS` payload length.
+pub const AXIS_CAPS_LEN: usize = 8;
+
+/// Tile state bits in `MSG_CAPABILITIES`.
+pub mod flags {
+    /// The stored configuration was corrupt at boot; motion is disabled until the tile is
+    /// provisioned again.
+    pub const SAFE_MODE: u8 = 1 << 0;
+}
+
+/// Physical unit of an axis.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Millimeter = 1,
+    Degree = 2,
+}
+
+/// Summary sent ahead of the per-axis records.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    pub node_id: u8,
+    pub axis_count: u8,
+    pub reset_reason: ResetReason,
+    /// [`flags`] bits.
+    pub flags: u8,
+}
+
+impl Capabilities {
+    pub fn to_bytes(&self) -> [u8; CAPABILITIES_LEN] {
+        [
+            PROTOCOL_VERSION,
+            self.node_id,
+            self.axis_count,
+            self.reset_reason as u8,
+            self.flags,
+        ]
+    }
+}
+
+/// Range and scaling of one axis, in [`Unit`]s.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AxisCaps {
+    pub axis: u8,
+    pub unit: Unit,
+    pub min: f32,
+    pub max: f32,
+    pub full_scale: f32,
+}
+
+impl AxisCaps {
+    pub fn to_bytes(&self) -> [u8; AXIS_CAPS_LEN] {
+        let min = centi_i16(self.min).to_le_bytes();
+        let max = centi_i16(self.max).to_le_bytes();
+        let fs = (self.full_scale * 100.0).clamp(0.0, u16::MAX as f32) as u16;
+        let fs = fs.to_le_bytes();
+        [
+            self.axis,
+            self.unit as u8,
+            min[0],
+            min[1],
+            max[0],
+            max[1],
+            fs[0],
+            fs[1],
+        ]
+    }
+}
+
+fn centi_i16(v: f32) -> i16 {
+    (v * 100.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+}
@@ -0,0 +1,47 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Ring buffer of recently executed command IDs, kept for fault post-mortems.
+
+/// Commands kept in the history.
+pub const HISTORY_LEN: usize = 32;
+
+pub struct CommandHistory {
+    ids: [u8; HISTORY_LEN],
+    next: usize,
+    len: usize,
+}
+
+impl CommandHistory {
+    pub const fn new() -> Self {
+        Self {
+            ids: [0; HISTORY_LEN],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Record an executed command's message ID.
+    pub fn record(&mut self, id: u8) {
+        self.ids[self.next] = id;
+        self.next = (self.next + 1) % HISTORY_LEN;
+        self.len = (self.len + 1).min(HISTORY_LEN);
+    }
+
+    /// Copy the history into `out`, oldest first, and return how many entries are valid. Unused
+    /// trailing entries are zero.
+    pub fn snapshot(&self, out: &mut [u8; HISTORY_LEN]) -> usize {
+        out.fill(0);
+        let start = (self.next + HISTORY_LEN - self.len) % HISTORY_LEN;
+        for (i, slot) in out.iter_mut().take(self.len).enumerate() {
+            *slot = self.ids[(start + i) % HISTORY_LEN];
+        }
+        self.len
+    }
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
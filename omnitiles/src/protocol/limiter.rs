@@ -0,0 +1,339 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Per-class command rate limiting, payload sanity checks and the reasons a command is refused.
+//!
+//! A misbehaving host script can send commands far faster than the 50 Hz control loop can use
+//! them, and every command costs parsing, logging and (for persistent settings) a flash write.
+//! [`CommandLimiter`] sorts commands into classes with their own token bucket and rejects those
+//! that exceed the class rate or carry nonsensical payloads. Stop commands (brakes) and pings are
+//! never limited.
+//!
+//! Commands with `f32` payloads must reject NaN and infinities in [`validate`].
+//!
+//! In safe mode (see [`CommandLimiter::set_safe_mode`]) only the commands needed to identify and
+//! re-provision the tile, plus brakes, are accepted.
+//!
+//! Motion commands are also checked against the state of their axis ([`check_axis`]): whether
+//! the build has it at all, whether it is faulted and whether it has been homed.
+//!
+//! Every refusal is reported to the host as a [`Reject`] code. The codes are part of the
+//! protocol: a code is never renumbered or reused, new reasons get new codes.
+
+use crate::control::ident;
+use crate::control::interlock::{Rule, INTERLOCK_SLOTS};
+use crate::control::schedule::{self, Trigger, SCHEDULE_SLOTS};
+use crate::drivers::gim6010_setup::Bitrate;
+use crate::hw::dac::{Channel, Signal};
+use crate::protocol::{register, Command, Reject};
+
+/// Rate class of a command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CommandClass {
+    /// Brakes, pings and cheap bridge settings.
+    Unlimited,
+    /// Closed-loop targets and relative moves.
+    Position,
+    /// Open-loop drive (extend/retract, base velocity).
+    Drive,
+    /// Settings persisted to flash.
+    Persist,
+    /// Requests that queue reply frames.
+    Query,
+}
+
+impl CommandClass {
+    pub fn of(cmd: &Command) -> Self {
+        match cmd {
+            Command::Ping
+            | Command::M1Brake
+            | Command::M2Brake
+            | Command::BaseBrake
+            | Command::SetTelemetryFields(_)
+            | Command::SetTelemetryDelta(_)
+            | Command::SetDacProbe { .. } => CommandClass::Unlimited,
+            Command::M1SetPosition(_)
+            | Command::M2SetPosition(_)
+            | Command::M1MoveRelative(_)
+            | Command::M2MoveRelative(_)
+            | Command::TiltMoveRelative(_)
+            | Command::FrequencyResponse { .. } => CommandClass::Position,
+            Command::M1Extend(_)
+            | Command::M1Retract(_)
+            | Command::M2Extend(_)
+            | Command::M2Retract(_)
+            | Command::BaseVelocity { .. }
+            | Command::Identify { .. } => CommandClass::Drive,
+            Command::Provision { .. }
+            | Command::SetPolarity(_)
+            | Command::SetMotionLimits { .. }
+            | Command::SetTime(_)
+            | Command::SetSchedule { .. }
+            | Command::SetCurrentLimit { .. }
+            | Command::SetParam { .. }
+            | Command::CommitConfig
+            | Command::RevertConfig
+            | Command::SetInterlock { .. }
+            | Command::SetDemo(_)
+            | Command::ProvisionTiltMotor { .. } => CommandClass::Persist,
+            Command::GetCapabilities
+            | Command::GetTelemetryDescriptor
+            | Command::GetStats(_)
+            | Command::GetFaultSnapshot(_)
+            | Command::GetLinkStats
+            | Command::GetParam(_)
+            | Command::GetWireDescriptor(_)
+            | Command::ReadRegister { .. }
+            | Command::Echo(_) => CommandClass::Query,
+        }
+    }
+}
+
+/// Check a command's payload independent of rate.
+pub fn validate(cmd: &Command) -> Result<(), Reject> {
+    let axis_ok = |axis: u8| axis == 1 || axis == 2;
+    match *cmd {
+        Command::SetMotionLimits { axis, .. }
+        | Command::GetStats(axis)
+        | Command::GetFaultSnapshot(axis)
+        | Command::SetCurrentLimit { axis, .. }
+        | Command::FrequencyResponse { axis, .. }
+            if !axis_ok(axis) =>
+        {
+            Err(Reject::OutOfRange)
+        }
+        Command::Provision { node_id: 0 } => Err(Reject::OutOfRange),
+        Command::SetDemo(mode) if mode > 2 => Err(Reject::OutOfRange),
+        Command::ProvisionTiltMotor { addr, bitrate }
+            if !(1..=254).contains(&addr) || Bitrate::from_code(bitrate).is_none() =>
+        {
+            Err(Reject::OutOfRange)
+        }
+        Command::Identify {
+            axis,
+            signal,
+            param,
+            duration_s,
+            ..
+        } if !axis_ok(axis)
+            || ident::Signal::from_u8(signal).is_none()
+            || (signal != 0
+                && (param == 0 || !(1..=ident::MAX_DURATION_S).contains(&duration_s))) =>
+        {
+            Err(Reject::OutOfRange)
+        }
+        Command::SetCurrentLimit { level, .. } if level > 3 && level != 0xFF => {
+            Err(Reject::OutOfRange)
+        }
+        Command::SetSchedule {
+            slot,
+            kind,
+            sequence,
+            arg,
+        } if slot as usize >= SCHEDULE_SLOTS
+            || Trigger::from_parts(kind, arg).is_none()
+            || (kind != 0 && schedule::sequence(sequence).is_none()) =>
+        {
+            Err(Reject::OutOfRange)
+        }
+        Command::SetInterlock {
+            slot,
+            axis,
+            source,
+            min,
+            max,
+        } if slot as usize >= INTERLOCK_SLOTS
+            || !(Rule {
+                axis,
+                source,
+                min,
+                max,
+            })
+            .is_valid() =>
+        {
+            Err(Reject::OutOfRange)
+        }
+        Command::SetDacProbe { channel, signal }
+            if Channel::from_id(channel).is_none() || Signal::from_id(signal).is_none() =>
+        {
+            Err(Reject::OutOfRange)
+        }
+        Command::ReadRegister { device, addr } if !register::is_readable(device, addr) => {
+            Err(Reject::OutOfRange)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// State of an axis as far as accepting commands goes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AxisState {
+    /// The build drives this axis.
+    pub present: bool,
+    pub faulted: bool,
+    pub homed: bool,
+}
+
+impl AxisState {
+    /// An axis this build doesn't have.
+    pub const ABSENT: Self = Self {
+        present: false,
+        faulted: false,
+        homed: false,
+    };
+
+    /// A present axis with absolute feedback, which never needs homing.
+    pub const fn absolute(faulted: bool) -> Self {
+        Self {
+            present: true,
+            faulted,
+            homed: true,
+        }
+    }
+}
+
+/// Check a command for an axis against the axis's state. Brakes and manual drive only need the
+/// axis to exist; position targets and identification runs, which rely on the soft limits, also
+/// need it healthy and homed. Identification runs and frequency response sweeps are
+/// unsupported in builds without the `tuning` feature.
+pub fn check_axis(cmd: &Command, state: AxisState) -> Result<(), Reject> {
+    let closed_loop = CommandClass::of(cmd) == CommandClass::Position
+        || matches!(cmd, Command::Identify { signal, .. } if *signal != 0);
+    let tuning = matches!(
+        cmd,
+        Command::Identify { .. } | Command::FrequencyResponse { .. }
+    );
+    if !state.present || (tuning && !cfg!(feature = "tuning")) {
+        Err(Reject::Unsupported)
+    } else if !closed_loop {
+        Ok(())
+    } else if state.faulted {
+        Err(Reject::Faulted)
+    } else if !state.homed {
+        Err(Reject::NotHomed)
+    } else {
+        Ok(())
+    }
+}
+
+/// Token bucket: allows bursts of `burst` commands and `per_s` commands per second sustained.
+pub struct TokenBucket {
+    burst: u32,
+    per_s: u32,
+    /// Available tokens, in thousandths.
+    tokens_milli: u32,
+    last_ms: u64,
+}
+
+impl TokenBucket {
+    pub const fn new(burst: u32, per_s: u32) -> Self {
+        Self {
+            burst,
+            per_s,
+            tokens_milli: burst * 1000,
+            last_ms: 0,
+        }
+    }
+
+    /// Take one token at `now_ms`. Returns `false` if the bucket is empty.
+    pub fn allow(&mut self, now_ms: u64) -> bool {
+        let elapsed = now_ms.saturating_sub(self.last_ms);
+        self.last_ms = now_ms;
+        let refill = elapsed.saturating_mul(self.per_s as u64);
+        self.tokens_milli =
+            (self.tokens_milli as u64 + refill).min(self.burst as u64 * 1000) as u32;
+
+        if self.tokens_milli >= 1000 {
+            self.tokens_milli -= 1000;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Position targets per second before commands are rejected.
+pub const POSITION_RATE: u32 = 50;
+/// Open-loop drive commands per second.
+pub const DRIVE_RATE: u32 = 50;
+/// Persistent settings per second. Most erase and rewrite the config sector.
+pub const PERSIST_RATE: u32 = 1;
+/// Queries per second.
+pub const QUERY_RATE: u32 = 10;
+
+/// Rate limiter and validator for the command dispatcher.
+pub struct CommandLimiter {
+    position: TokenBucket,
+    drive: TokenBucket,
+    persist: TokenBucket,
+    query: TokenBucket,
+    safe_mode: bool,
+    /// Commands rejected so far.
+    pub rejected: u32,
+}
+
+impl CommandLimiter {
+    pub const fn new() -> Self {
+        Self {
+            position: TokenBucket::new(10, POSITION_RATE),
+            drive: TokenBucket::new(10, DRIVE_RATE),
+            persist: TokenBucket::new(2, PERSIST_RATE),
+            query: TokenBucket::new(5, QUERY_RATE),
+            safe_mode: false,
+            rejected: 0,
+        }
+    }
+
+    /// Enter or leave safe mode. While in it, everything but pings, brakes, `GetCapabilities`
+    /// and `Provision` is rejected with [`Reject::SafeMode`].
+    pub fn set_safe_mode(&mut self, on: bool) {
+        self.safe_mode = on;
+    }
+
+    pub fn is_safe_mode(&self) -> bool {
+        self.safe_mode
+    }
+
+    /// Decide whether `cmd`, received at `now_ms`, may be executed.
+    pub fn check(&mut self, cmd: &Command, now_ms: u64) -> Result<(), Reject> {
+        let allowed = !self.safe_mode
+            || matches!(
+                cmd,
+                Command::Ping
+                    | Command::GetCapabilities
+                    | Command::Provision { .. }
+                    | Command::M1Brake
+                    | Command::M2Brake
+                    | Command::BaseBrake
+            );
+        let result = if allowed {
+            validate(cmd)
+        } else {
+            Err(Reject::SafeMode)
+        };
+        let result = result.and_then(|_| {
+            let bucket = match CommandClass::of(cmd) {
+                CommandClass::Unlimited => return Ok(()),
+                CommandClass::Position => &mut self.position,
+                CommandClass::Drive => &mut self.drive,
+                CommandClass::Persist => &mut self.persist,
+                CommandClass::Query => &mut self.query,
+            };
+            if bucket.allow(now_ms) {
+                Ok(())
+            } else {
+                Err(Reject::RateLimited)
+            }
+        });
+        if result.is_err() {
+            self.rejected = self.rejected.wrapping_add(1);
+        }
+        result
+    }
+}
+
+impl Default for CommandLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
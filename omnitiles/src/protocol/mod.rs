@@ -1,8 +1,25 @@
 // SPDX-License-Identifier: MIT
 // © 2025–2026 Christopher Liu
 
-pub mod messages;
-pub mod parser;
+//! Command protocol. The wire definitions live in the `omnitiles-proto` crate so host tools share
+//! them; they are re-exported here next to the firmware-side handling.
 
-pub use messages::Command;
-pub use parser::Parser;
+pub mod caps;
+pub mod history;
+pub mod limiter;
+pub mod outbox;
+pub mod priority;
+pub mod register;
+
+pub use omnitiles_proto::{heartbeat, messages, parser, progress, seq, telemetry, wire};
+
+pub use caps::{AxisCaps, Capabilities, Unit};
+pub use history::CommandHistory;
+pub use limiter::CommandLimiter;
+pub use omnitiles_proto::{
+    AckStatus, Command, Heartbeat, Packet, Parser, ParserStats, Progress, Reject, Reporter,
+    SeqTracker,
+};
+pub use outbox::Outbox;
+pub use priority::{Batch, Priority};
+pub use register::{RegisterReply, RegisterStatus};
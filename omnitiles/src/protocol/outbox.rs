@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Queue of encoded outbound frames.
+//!
+//! Replies that are produced while parsing (ACKs, NACKs, query responses) are encoded here and
+//! copied into the next SPI transfer after the telemetry frame, as many as fit.
+
+use crate::protocol::messages::encode_frame;
+
+/// Largest encoded frame stored in a slot (start byte, id, payload, checksum).
+pub const MAX_FRAME_LEN: usize = 48;
+
+/// Fixed-capacity FIFO of encoded frames. When full, new frames are dropped.
+pub struct Outbox<const N: usize> {
+    slots: [[u8; MAX_FRAME_LEN]; N],
+    lens: [u8; N],
+    head: usize,
+    len: usize,
+    /// Frames dropped because the outbox was full.
+    pub dropped: u32,
+}
+
+impl<const N: usize> Outbox<N> {
+    pub const fn new() -> Self {
+        Self {
+            slots: [[0; MAX_FRAME_LEN]; N],
+            lens: [0; N],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Encode and enqueue a frame. Returns `false` if it was dropped.
+    pub fn push(&mut self, id: u8, payload: &[u8]) -> bool {
+        if self.len == N {
+            self.dropped = self.dropped.wrapping_add(1);
+            return false;
+        }
+        let tail = (self.head + self.len) % N;
+        match encode_frame(id, payload, &mut self.slots[tail]) {
+            Some(n) => {
+                self.lens[tail] = n as u8;
+                self.len += 1;
+                true
+            }
+            None => {
+                self.dropped = self.dropped.wrapping_add(1);
+                false
+            }
+        }
+    }
+
+    /// Copy as many whole frames as fit into `out`, removing them from the queue. Returns the
+    /// number of bytes written.
+    pub fn drain_into(&mut self, out: &mut [u8]) -> usize {
+        let mut written = 0;
+        while self.len > 0 {
+            let n = self.lens[self.head] as usize;
+            if written + n > out.len() {
+                break;
+            }
+            out[written..written + n].copy_from_slice(&self.slots[self.head][..n]);
+            written += n;
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+        }
+        written
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<const N: usize> Default for Outbox<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Command priorities and preemption within one SPI transfer.
+//!
+//! Every transfer can carry several commands, and a host that falls behind tends to flush a
+//! backlog of motion followed by the stop the user pressed last. The dispatcher therefore
+//! collects a transfer's commands into a [`Batch`] and arbitrates them before executing any:
+//!
+//! - **Safety** commands (the brakes) always run. Motion queued ahead of a brake for the same
+//!   axis in the batch is dropped with [`Reject::Preempted`], so the axis never lurches towards a
+//!   stale target on its way to stopping.
+//! - **Motion** commands replace queued motion for the same axis: only the last one in the batch
+//!   runs, earlier ones are dropped with [`Reject::Superseded`]. Motion after a brake still runs;
+//!   that is the host starting a new move.
+//! - **Configuration** commands are refused with [`Reject::Busy`] while any axis is moving, since
+//!   a new calibration, polarity or limit taking effect mid-move can make the axis jump. Brake,
+//!   then configure in a later transfer.
+//! - Everything else (queries, telemetry settings, the clock, demo mode) runs in order.
+//!
+//! Each rejection is reported in the `MSG_ACK` reason byte.
+
+use crate::protocol::limiter::CommandClass;
+use crate::protocol::{AckStatus, Command, Packet, Reject};
+
+/// Commands one batch holds; more than a 128-byte transfer of 3-byte frames can carry.
+pub const BATCH_LEN: usize = 48;
+
+/// Dispatch priority of a command.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Priority {
+    /// Stops an axis.
+    Safety,
+    /// Sets an axis moving.
+    Motion,
+    /// Changes settings that shape how an axis moves.
+    Config,
+    Normal,
+}
+
+impl Priority {
+    pub fn of(cmd: &Command) -> Self {
+        match cmd {
+            Command::M1Brake | Command::M2Brake | Command::BaseBrake => Priority::Safety,
+            // The clock and demo mode are persisted but don't touch an axis, and `SetDemo(0)` is
+            // how a host stops a running demo.
+            Command::SetTime(_) | Command::SetDemo(_) => Priority::Normal,
+            _ if cmd.is_motion() => Priority::Motion,
+            _ if CommandClass::of(cmd) == CommandClass::Persist => Priority::Config,
+            _ => Priority::Normal,
+        }
+    }
+}
+
+/// Axis a motion or safety command acts on: 1 = M1, 2 = M2, 3 = tilt, 4 = base. Provisioning
+/// the tilt motor takes over the tilt axis too.
+pub fn lane(cmd: &Command) -> Option<u8> {
+    match cmd {
+        Command::M1Brake => Some(1),
+        Command::M2Brake => Some(2),
+        Command::ProvisionTiltMotor { .. } => Some(3),
+        Command::BaseVelocity { .. } | Command::BaseBrake => Some(4),
+        _ => cmd.motion_axis(),
+    }
+}
+
+/// A command with its verdict so far.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub packet: Packet,
+    pub status: AckStatus,
+    /// Why the command was rejected, with [`AckStatus::Rejected`].
+    pub reason: Option<Reject>,
+}
+
+impl Entry {
+    /// `MSG_ACK` payload for a sequenced command.
+    pub fn ack(&self) -> Option<[u8; 3]> {
+        let seq = self.packet.seq?;
+        Some([seq, self.status as u8, self.reason_code()])
+    }
+
+    /// `MSG_TOKEN_ACK` payload for a command sent with a token.
+    pub fn token_ack(&self) -> Option<[u8; 4]> {
+        let [lo, hi] = self.packet.token?.to_le_bytes();
+        Some([lo, hi, self.status as u8, self.reason_code()])
+    }
+
+    fn reason_code(&self) -> u8 {
+        self.reason.map_or(0, |r| r as u8)
+    }
+
+    pub fn is_accepted(&self) -> bool {
+        self.status == AckStatus::Accepted
+    }
+}
+
+/// The commands of one transfer, in arrival order.
+pub struct Batch {
+    entries: [Option<Entry>; BATCH_LEN],
+    len: usize,
+}
+
+impl Batch {
+    pub const fn new() -> Self {
+        Self {
+            entries: [None; BATCH_LEN],
+            len: 0,
+        }
+    }
+
+    /// Add a command with the verdict of the per-command checks (sequence, rate, interlocks).
+    /// Returns `false`, dropping it, if the batch is full.
+    pub fn push(&mut self, packet: Packet, verdict: Result<AckStatus, Reject>) -> bool {
+        if self.len == BATCH_LEN {
+            return false;
+        }
+        let (status, reason) = match verdict {
+            Ok(status) => (status, None),
+            Err(reason) => (AckStatus::Rejected, Some(reason)),
+        };
+        self.entries[self.len] = Some(Entry {
+            packet,
+            status,
+            reason,
+        });
+        self.len += 1;
+        true
+    }
+
+    /// Apply the priority rules to the accepted commands. `moving` is true while any axis is
+    /// under way.
+    pub fn arbitrate(&mut self, moving: bool) {
+        for i in 0..self.len {
+            let Some(entry) = self.entries[i] else {
+                continue;
+            };
+            if !entry.is_accepted() {
+                continue;
+            }
+            let cmd = &entry.packet.command;
+            let reason = match Priority::of(cmd) {
+                Priority::Motion => lane(cmd).and_then(|axis| self.overridden(i, axis)),
+                Priority::Config if moving => Some(Reject::Busy),
+                _ => None,
+            };
+            if let (Some(reason), Some(entry)) = (reason, self.entries[i].as_mut()) {
+                entry.status = AckStatus::Rejected;
+                entry.reason = Some(reason);
+            }
+        }
+    }
+
+    /// How later accepted commands in the batch override motion at `index` on `axis`, if they
+    /// do. A brake anywhere after it wins over newer motion.
+    fn overridden(&self, index: usize, axis: u8) -> Option<Reject> {
+        let mut reason = None;
+        let later = self.entries[index + 1..self.len].iter().flatten();
+        for e in later.filter(|e| e.is_accepted() && lane(&e.packet.command) == Some(axis)) {
+            match Priority::of(&e.packet.command) {
+                Priority::Safety => return Some(Reject::Preempted),
+                Priority::Motion => reason = Some(Reject::Superseded),
+                _ => {}
+            }
+        }
+        reason
+    }
+
+    /// Entries in arrival order.
+    pub fn iter(&self) -> impl Iterator<Item = &Entry> {
+        self.entries[..self.len].iter().flatten()
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl Default for Batch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,131 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Raw register reads for field diagnostics.
+//!
+//! `MSG_READ_REGISTER` (`[device, addr]`) reads one register of a motor driver and the tile
+//! answers with `MSG_REGISTER`, so a misbehaving part can be inspected without a debug build.
+//! Nothing is decoded: the host gets exactly what the device returned.
+//!
+//! | Device | `addr` | Data |
+//! | ------ | ------ | ---- |
+//! | 1, 2: M1/M2 DRV8873 | register, 0x00–0x1F | `[status, value]` |
+//! | 3: GIM6010 tilt motor | read command, 0xA0–0xAE | the 8-byte reply frame |
+//!
+//! `MSG_REGISTER` payload: `[device, addr, status, len, data: 8 bytes]`, with `status` a
+//! [`RegisterStatus`] and `len` the number of valid data bytes; the rest are zero. Reads only: the
+//! GIM6010 range stops short of 0xAF, which clears faults.
+
+use crate::drivers::{Drv8873, Gim6010};
+use crate::hw::{spi::CsControl, CanInterface, SpiBus};
+use stm32f7xx_hal::spi;
+
+/// Serialized `MSG_REGISTER` payload length.
+pub const REGISTER_REPLY_LEN: usize = 12;
+
+/// Device numbers for `MSG_READ_REGISTER`.
+pub mod device {
+    /// DRV8873 driving M1.
+    pub const M1_DRIVER: u8 = 1;
+    /// DRV8873 driving M2.
+    pub const M2_DRIVER: u8 = 2;
+    /// GIM6010 tilt motor.
+    pub const TILT_MOTOR: u8 = 3;
+}
+
+/// True if `addr` is a valid read on `device`.
+pub fn is_readable(device: u8, addr: u8) -> bool {
+    match device {
+        device::M1_DRIVER | device::M2_DRIVER => addr <= 0x1F,
+        device::TILT_MOTOR => (0xA0..=0xAE).contains(&addr),
+        _ => false,
+    }
+}
+
+/// Outcome of a register read.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RegisterStatus {
+    Ok = 0,
+    /// The device isn't wired to the MCU on this board.
+    Unavailable = 1,
+    /// The bus transfer failed or the device didn't answer.
+    BusError = 2,
+}
+
+/// A `MSG_REGISTER` reply.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RegisterReply {
+    pub device: u8,
+    pub addr: u8,
+    pub status: RegisterStatus,
+    len: u8,
+    data: [u8; 8],
+}
+
+impl RegisterReply {
+    /// A reply carrying no data.
+    pub fn failed(device: u8, addr: u8, status: RegisterStatus) -> Self {
+        Self {
+            device,
+            addr,
+            status,
+            len: 0,
+            data: [0; 8],
+        }
+    }
+
+    /// A successful reply with up to 8 data bytes.
+    pub fn ok(device: u8, addr: u8, data: &[u8]) -> Self {
+        let mut reply = Self::failed(device, addr, RegisterStatus::Ok);
+        let len = data.len().min(8);
+        reply.data[..len].copy_from_slice(&data[..len]);
+        reply.len = len as u8;
+        reply
+    }
+
+    /// Valid data bytes.
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+
+    pub fn to_bytes(&self) -> [u8; REGISTER_REPLY_LEN] {
+        let mut out = [0u8; REGISTER_REPLY_LEN];
+        out[0] = self.device;
+        out[1] = self.addr;
+        out[2] = self.status as u8;
+        out[3] = self.len;
+        out[4..].copy_from_slice(&self.data);
+        out
+    }
+}
+
+/// Read DRV8873 register `addr` on `device` ([`device::M1_DRIVER`] or [`device::M2_DRIVER`]).
+pub fn read_drv8873<CS, I, PINS>(
+    drv: &mut Drv8873<CS>,
+    spi: &mut SpiBus<I, PINS>,
+    device: u8,
+    addr: u8,
+) -> RegisterReply
+where
+    CS: CsControl,
+    I: spi::Instance,
+    PINS: spi::Pins<I>,
+{
+    match drv.read_reg(spi, addr) {
+        Ok(resp) => RegisterReply::ok(device, addr, &[resp.status.raw(), resp.data]),
+        Err(_) => RegisterReply::failed(device, addr, RegisterStatus::BusError),
+    }
+}
+
+/// Send GIM6010 read command `cmd` and return its reply frame.
+pub fn read_gim6010<const DEV_ADDR: u16, B: CanInterface>(
+    motor: &mut Gim6010<DEV_ADDR>,
+    bus: &mut B,
+    cmd: u8,
+) -> RegisterReply {
+    match motor.read_raw(bus, cmd) {
+        Ok(frame) => RegisterReply::ok(device::TILT_MOTOR, cmd, &frame),
+        Err(_) => RegisterReply::failed(device::TILT_MOTOR, cmd, RegisterStatus::BusError),
+    }
+}
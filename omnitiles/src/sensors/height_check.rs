@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Cross-check of the lift axis feedback against the ToF height sensor.
+//!
+//! The lift position comes from the actuator feedback, which only knows where the actuator is,
+//! not where the platform is. A slipping linkage or a miscalibrated pot shows up as the two
+//! disagreeing. The ToF sensor measures the platform directly but is slow and noisy, so
+//! [`HeightCrossCheck`] only flags a mismatch once it exceeds a tolerance continuously for a
+//! while.
+//!
+//! The ToF reading is predicted from the feedback with a linear model,
+//! `tof = offset + mm_per_mm * feedback`. The slope comes from the linkage geometry; the offset is
+//! learned from the first valid pair of readings (or after [`HeightCrossCheck::rebase`]), so
+//! sensor mounting tolerances don't need calibrating.
+
+/// Result of one cross-check update.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HeightCheck {
+    /// Nothing changed.
+    Unchanged,
+    /// The readings have diverged for longer than the persistence window.
+    Diverged,
+    /// The readings agree again.
+    Recovered,
+}
+
+pub struct HeightCrossCheck {
+    /// ToF millimeters per millimeter of actuator feedback.
+    mm_per_mm: f32,
+    tolerance_mm: f32,
+    persist_ms: u64,
+    offset: Option<f32>,
+    divergent_since: Option<u64>,
+    diverged: bool,
+    last_error_mm: f32,
+}
+
+impl HeightCrossCheck {
+    /// `mm_per_mm` is the ToF change per millimeter of actuator travel (negative if extending the
+    /// actuator lowers the platform). A mismatch beyond `tolerance_mm` lasting `persist_ms` is
+    /// flagged.
+    pub fn new(mm_per_mm: f32, tolerance_mm: f32, persist_ms: u64) -> Self {
+        Self {
+            mm_per_mm,
+            tolerance_mm,
+            persist_ms,
+            offset: None,
+            divergent_since: None,
+            diverged: false,
+            last_error_mm: 0.0,
+        }
+    }
+
+    /// Forget the learned offset; the next valid pair of readings sets it again. Call after a
+    /// recalibration or after the mismatch has been fixed.
+    pub fn rebase(&mut self) {
+        self.offset = None;
+        self.divergent_since = None;
+        self.diverged = false;
+        self.last_error_mm = 0.0;
+    }
+
+    /// Compare `feedback_mm` (actuator position) with `tof_mm` at `now_ms`. Missing readings
+    /// pause the check without clearing a flagged mismatch.
+    pub fn update(
+        &mut self,
+        now_ms: u64,
+        feedback_mm: Option<f32>,
+        tof_mm: Option<u16>,
+    ) -> HeightCheck {
+        let (Some(fb), Some(tof)) = (feedback_mm, tof_mm) else {
+            self.divergent_since = None;
+            return HeightCheck::Unchanged;
+        };
+        let tof = tof as f32;
+        let predicted = self.mm_per_mm * fb;
+        let offset = *self.offset.get_or_insert(tof - predicted);
+        let error = tof - (offset + predicted);
+        self.last_error_mm = error;
+
+        if error.abs() > self.tolerance_mm {
+            let since = *self.divergent_since.get_or_insert(now_ms);
+            if !self.diverged && now_ms.saturating_sub(since) >= self.persist_ms {
+                self.diverged = true;
+                return HeightCheck::Diverged;
+            }
+        } else {
+            self.divergent_since = None;
+            if self.diverged {
+                self.diverged = false;
+                return HeightCheck::Recovered;
+            }
+        }
+        HeightCheck::Unchanged
+    }
+
+    /// True while a mismatch is flagged.
+    pub fn is_diverged(&self) -> bool {
+        self.diverged
+    }
+
+    /// ToF reading minus the predicted reading at the last update, in millimeters.
+    pub fn error_mm(&self) -> f32 {
+        self.last_error_mm
+    }
+}
@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Surface load sensing.
+//!
+//! A [`LoadSensor`] wraps any [`LoadSource`] (an FSR divider on an ADC channel, a load-cell
+//! amplifier, ...) and turns raw counts into a filtered force in newtons:
+//!
+//! ```text
+//! force = (filtered_raw - tare_offset) * newtons_per_count
+//! ```
+//!
+//! Crossing the press threshold upward reports [`LoadEdge::Pressed`]; falling below the (lower)
+//! release threshold reports [`LoadEdge::Released`]. The gap between the two keeps a person
+//! shifting their weight from producing a burst of events.
+
+use core::task::Poll;
+
+use crate::control::{Event, EventKind};
+
+/// A source of raw load readings. Implemented by [`Fsr`] and
+/// [`Hx711`](crate::drivers::Hx711).
+pub trait LoadSource {
+    /// Latest raw reading, or `None` if no new sample is available or the read failed. A source
+    /// must not return the same conversion twice.
+    fn read_raw(&mut self) -> Option<i32>;
+}
+
+/// Force-sensitive resistor read through an ADC reader closure (see
+/// [`Adc::make_reader`](crate::hw::Adc::make_reader)). Several FSRs under one surface can be
+/// summed by a reader that adds their channels.
+pub struct Fsr<F: FnMut() -> u16> {
+    read: F,
+}
+
+impl<F: FnMut() -> u16> Fsr<F> {
+    pub fn new(read: F) -> Self {
+        Self { read }
+    }
+}
+
+impl<F: FnMut() -> u16> LoadSource for Fsr<F> {
+    fn read_raw(&mut self) -> Option<i32> {
+        Some((self.read)() as i32)
+    }
+}
+
+/// Threshold crossing reported by [`LoadSensor::update`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoadEdge {
+    Pressed,
+    Released,
+}
+
+impl LoadEdge {
+    /// Tile-level (axis 0) [`EventKind::Load`] event for this edge. Code 1 = pressed, 0 = released.
+    pub fn event(self) -> Event {
+        let code = match self {
+            LoadEdge::Pressed => 1,
+            LoadEdge::Released => 0,
+        };
+        Event::new(0, EventKind::Load, code)
+    }
+}
+
+/// Filtered, tared and scaled load reading with press/release detection.
+pub struct LoadSensor<S: LoadSource> {
+    source: S,
+    /// Raw reading at zero load.
+    offset: i32,
+    /// Newtons per raw count. Negative if the reading falls with load.
+    newtons_per_count: f32,
+    /// Exponential filter weight of each new sample (0..1].
+    alpha: f32,
+    filtered: Option<f32>,
+    press_n: f32,
+    release_n: f32,
+    pressed: bool,
+}
+
+impl<S: LoadSource> LoadSensor<S> {
+    /// Create a sensor with the given scale. Thresholds default to 100 N press / 50 N release,
+    /// roughly a child stepping on and off.
+    pub fn new(source: S, newtons_per_count: f32) -> Self {
+        Self {
+            source,
+            offset: 0,
+            newtons_per_count,
+            alpha: 0.3,
+            filtered: None,
+            press_n: 100.0,
+            release_n: 50.0,
+            pressed: false,
+        }
+    }
+
+    /// Set the press and release thresholds in newtons. `release_n` is clamped to `press_n`.
+    pub fn with_thresholds(mut self, press_n: f32, release_n: f32) -> Self {
+        self.press_n = press_n;
+        self.release_n = release_n.min(press_n);
+        self
+    }
+
+    /// Set the filter weight of each new sample (1.0 = unfiltered).
+    pub fn with_filter(mut self, alpha: f32) -> Self {
+        self.alpha = alpha.clamp(0.01, 1.0);
+        self
+    }
+
+    /// Start zeroing the reading at the current load, averaging `samples` fresh readings. Drive
+    /// the returned [`Tare`] until it is ready; an HX711 at its default 10 Hz takes a second for
+    /// 10 samples.
+    pub fn start_tare(&self, samples: u16, timeout_ms: u32) -> Tare {
+        Tare {
+            samples: samples.max(1),
+            timeout_ms,
+            elapsed_ms: 0,
+            sum: 0,
+            n: 0,
+        }
+    }
+
+    /// Set the scale so the current filtered reading equals `known_n` newtons. Tare first.
+    /// Returns `false` if there is no reading or it equals the tare offset.
+    pub fn calibrate(&mut self, known_n: f32) -> bool {
+        let Some(f) = self.filtered else {
+            return false;
+        };
+        let counts = f - self.offset as f32;
+        if counts == 0.0 {
+            return false;
+        }
+        self.newtons_per_count = known_n / counts;
+        true
+    }
+
+    /// Current scale, for persisting a calibration.
+    pub fn newtons_per_count(&self) -> f32 {
+        self.newtons_per_count
+    }
+
+    /// Read a new sample and report a threshold crossing, if any.
+    pub fn update(&mut self) -> Option<LoadEdge> {
+        let raw = self.source.read_raw()? as f32;
+        let f = match self.filtered {
+            Some(prev) => prev + self.alpha * (raw - prev),
+            None => raw,
+        };
+        self.filtered = Some(f);
+
+        let force = self.force()?;
+        if !self.pressed && force >= self.press_n {
+            self.pressed = true;
+            Some(LoadEdge::Pressed)
+        } else if self.pressed && force < self.release_n {
+            self.pressed = false;
+            Some(LoadEdge::Released)
+        } else {
+            None
+        }
+    }
+
+    /// Filtered force in newtons, or `None` before the first sample.
+    pub fn force(&self) -> Option<f32> {
+        self.filtered
+            .map(|f| (f - self.offset as f32) * self.newtons_per_count)
+    }
+
+    /// True while the load is above the press threshold (with release hysteresis).
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Access the underlying source (e.g., to change an amplifier's gain).
+    pub fn source(&mut self) -> &mut S {
+        &mut self.source
+    }
+}
+
+/// Zeroing in progress, from [`LoadSensor::start_tare`].
+///
+/// Each step takes at most one reading and sources only return new conversions, so a slow
+/// amplifier is waited for rather than averaged over stale or repeated values.
+pub struct Tare {
+    samples: u16,
+    timeout_ms: u32,
+    elapsed_ms: u32,
+    sum: i64,
+    n: u16,
+}
+
+impl Tare {
+    /// Collect a new reading, if one is ready. Ready with `true` once all samples are averaged
+    /// into the offset, or `false` (leaving the offset unchanged) if the timeout ran out first.
+    pub fn run_step<S: LoadSource>(
+        &mut self,
+        sensor: &mut LoadSensor<S>,
+        elapsed_ms: u32,
+    ) -> Poll<bool> {
+        if let Some(raw) = sensor.source.read_raw() {
+            self.sum += raw as i64;
+            self.n += 1;
+        }
+        if self.n >= self.samples {
+            sensor.offset = (self.sum / self.n as i64) as i32;
+            sensor.filtered = Some(sensor.offset as f32);
+            sensor.pressed = false;
+            return Poll::Ready(true);
+        }
+        self.elapsed_ms = self.elapsed_ms.saturating_add(elapsed_ms);
+        if self.elapsed_ms >= self.timeout_ms {
+            return Poll::Ready(false);
+        }
+        Poll::Pending
+    }
+}
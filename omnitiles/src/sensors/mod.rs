@@ -0,0 +1,29 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! # Tile Sensing
+//!
+//! Sensor processing that sits above the raw drivers: filtering, calibration and turning readings
+//! into events the tile can react to.
+//!
+//! ## Modules
+//!
+//! - [`height_check`] - Cross-check of lift actuator feedback against the ToF height sensor.
+//! - [`load`] - Surface load sensing (FSRs on ADC channels or an HX711 load cell) with tare,
+//!   scaling to newtons and step-on / step-off events.
+//! - [`motor_temp`] - Motor temperature monitoring and the thermal derating of the axes.
+//! - [`supply`] - Bus voltage measurement and the duty scale that keeps motor speeds independent of
+//!   supply sag.
+//! - [`temperature`] - Temperature sources and the correction for thermal drift of the lift pots.
+
+pub mod height_check;
+pub mod load;
+pub mod motor_temp;
+pub mod supply;
+pub mod temperature;
+
+pub use height_check::{HeightCheck, HeightCrossCheck};
+pub use load::{Fsr, LoadEdge, LoadSensor, LoadSource, Tare};
+pub use motor_temp::{MotorTemperature, ThermalLimits, ThermalState};
+pub use supply::{AdcDivider, SupplyCompensation, VoltageSource};
+pub use temperature::{McuTemperature, PotCorrection, PotTempCompensation, TemperatureSource};
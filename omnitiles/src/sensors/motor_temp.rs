@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Motor temperature monitoring and thermal derating.
+//!
+//! Long holds under load and back-to-back lifts heat the actuator motors well beyond what the
+//! duty cycle rating allows. [`MotorTemperature`] reads a [`TemperatureSource`] on the motor
+//! (usually an [`Ntc`](crate::drivers::ntc::Ntc) on its can) and gives the factor to derate the
+//! axis by, see [`set_derate`](crate::control::LinearController::set_derate):
+//!
+//! ```text
+//! below warn_c          1.0
+//! warn_c..limit_c       linear from 1.0 down to MIN_FACTOR
+//! limit_c and above     MIN_FACTOR, state Overheated
+//! ```
+//!
+//! The axis keeps working while hot, only slower and weaker, so a lift in progress still
+//! finishes. Without a plausible reading the factor is 1.0: a tile without a thermistor fitted
+//! runs as it always has.
+
+use crate::sensors::TemperatureSource;
+
+/// Derate factor at and above the limit temperature.
+pub const MIN_FACTOR: f32 = 0.25;
+/// Drop below the limit before an overheated motor is reported as merely derating again.
+const HYSTERESIS_C: f32 = 5.0;
+/// Readings outside this range are treated as a missing or broken sensor.
+const PLAUSIBLE_C: core::ops::RangeInclusive<f32> = -40.0..=150.0;
+
+/// Temperatures at which derating starts and at which it bottoms out.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ThermalLimits {
+    pub warn_c: f32,
+    pub limit_c: f32,
+}
+
+/// Thermal state of a motor.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ThermalState {
+    /// Below the warning temperature, or no reading.
+    Normal,
+    /// Between the warning and limit temperatures.
+    Derating,
+    /// At or above the limit temperature.
+    Overheated,
+}
+
+/// Filtered temperature of one motor and the derating derived from it.
+pub struct MotorTemperature<S: TemperatureSource> {
+    source: S,
+    limits: ThermalLimits,
+    /// Exponential filter weight of each new sample (0..1].
+    alpha: f32,
+    filtered: Option<f32>,
+    state: ThermalState,
+}
+
+impl<S: TemperatureSource> MotorTemperature<S> {
+    pub fn new(source: S, limits: ThermalLimits) -> Self {
+        Self {
+            source,
+            limits,
+            alpha: 0.05,
+            filtered: None,
+            state: ThermalState::Normal,
+        }
+    }
+
+    /// Set the filter weight of each new sample (1.0 = unfiltered). The default smooths ADC noise
+    /// over about a second at the 50 Hz control rate; the motor heats over tens of seconds.
+    pub fn with_filter(mut self, alpha: f32) -> Self {
+        self.alpha = alpha.clamp(0.001, 1.0);
+        self
+    }
+
+    /// Take a reading. Returns the new state if it changed.
+    pub fn update(&mut self) -> Option<ThermalState> {
+        match self.source.read_celsius() {
+            Some(c) if PLAUSIBLE_C.contains(&c) => {
+                self.filtered = Some(match self.filtered {
+                    Some(prev) => prev + self.alpha * (c - prev),
+                    None => c,
+                });
+            }
+            _ => self.filtered = None,
+        }
+
+        let state = match self.filtered {
+            None => ThermalState::Normal,
+            Some(c) if c >= self.limits.limit_c => ThermalState::Overheated,
+            Some(c)
+                if self.state == ThermalState::Overheated
+                    && c > self.limits.limit_c - HYSTERESIS_C =>
+            {
+                ThermalState::Overheated
+            }
+            Some(c) if c >= self.limits.warn_c => ThermalState::Derating,
+            Some(_) => ThermalState::Normal,
+        };
+        if state == self.state {
+            return None;
+        }
+        self.state = state;
+        Some(state)
+    }
+
+    /// Filtered temperature, or `None` without a plausible reading.
+    pub fn celsius(&self) -> Option<f32> {
+        self.filtered
+    }
+
+    pub fn state(&self) -> ThermalState {
+        self.state
+    }
+
+    /// Factor to scale the axis's motion limits by, 1.0 when cool or without a reading.
+    pub fn derate(&self) -> f32 {
+        let Some(c) = self.filtered else {
+            return 1.0;
+        };
+        let span = self.limits.limit_c - self.limits.warn_c;
+        if span <= 0.0 {
+            return if c >= self.limits.limit_c {
+                MIN_FACTOR
+            } else {
+                1.0
+            };
+        }
+        let heat = ((c - self.limits.warn_c) / span).clamp(0.0, 1.0);
+        1.0 - heat * (1.0 - MIN_FACTOR)
+    }
+
+    pub fn source(&mut self) -> &mut S {
+        &mut self.source
+    }
+}
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Bus voltage compensation of open-loop drive duty.
+//!
+//! A PWM duty sets a fraction of the bus voltage, so the same commanded speed runs slower when the
+//! supply sags. With several tiles lifting at once the shared 24.5 V bus drops to around 21 V, and
+//! manual moves, homing speeds and profile feed-forward all slow by that much.
+//! [`SupplyCompensation`] measures the bus and gives the factor to scale duties by, so the motor
+//! sees the voltage it would at [`NOMINAL_SUPPLY_V`]:
+//!
+//! ```text
+//! scale = NOMINAL_SUPPLY_V / filtered_bus_v      (clamped to MIN_SCALE..=MAX_SCALE)
+//! ```
+//!
+//! Apply it with [`set_supply_scale`](crate::drivers::ActuonixLinear::set_supply_scale) on each
+//! lift actuator; the firmware does so every control period with the `supply-sense` feature.
+//! A duty already at 100% can't be raised further, so full-speed moves still slow with the supply.
+//! Without a plausible reading the scale falls back to 1.0, i.e. no compensation.
+
+use crate::config::NOMINAL_SUPPLY_V;
+
+/// Largest boost, reached at about 19.6 V; below that the supply is failing, not sagging.
+pub const MAX_SCALE: f32 = 1.25;
+/// Smallest scale, for a bus above nominal (e.g. a fresh bench supply set high).
+pub const MIN_SCALE: f32 = 0.8;
+/// Readings below this are treated as a missing or broken measurement.
+const MIN_PLAUSIBLE_V: f32 = 5.0;
+
+/// A source of bus voltage readings. Implemented by [`AdcDivider`]; a current/power monitor on
+/// the bus can implement it the same way.
+pub trait VoltageSource {
+    /// Bus voltage in volts, or `None` if the read failed.
+    fn read_volts(&mut self) -> Option<f32>;
+}
+
+/// Bus voltage through a resistor divider on an ADC channel, read with a reader closure (see
+/// [`Adc::make_reader`](crate::hw::Adc::make_reader)).
+pub struct AdcDivider<F: FnMut() -> u16> {
+    read: F,
+    volts_per_count: f32,
+}
+
+impl<F: FnMut() -> u16> AdcDivider<F> {
+    /// `r_top` from the bus to the ADC pin, `r_bottom` from the pin to ground, 12-bit ADC at
+    /// `v_ref`.
+    pub fn new(read: F, v_ref: f32, r_top: f32, r_bottom: f32) -> Self {
+        Self {
+            read,
+            volts_per_count: v_ref / 4095.0 * (r_top + r_bottom) / r_bottom,
+        }
+    }
+}
+
+impl<F: FnMut() -> u16> VoltageSource for AdcDivider<F> {
+    fn read_volts(&mut self) -> Option<f32> {
+        Some((self.read)() as f32 * self.volts_per_count)
+    }
+}
+
+/// Filtered bus voltage and the duty scale derived from it.
+pub struct SupplyCompensation<S: VoltageSource> {
+    source: S,
+    nominal_v: f32,
+    /// Exponential filter weight of each new sample (0..1].
+    alpha: f32,
+    filtered: Option<f32>,
+}
+
+impl<S: VoltageSource> SupplyCompensation<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            nominal_v: NOMINAL_SUPPLY_V,
+            alpha: 0.2,
+            filtered: None,
+        }
+    }
+
+    /// Compensate towards a different nominal voltage, e.g. for a 12 V bench setup.
+    pub fn with_nominal(mut self, nominal_v: f32) -> Self {
+        self.nominal_v = nominal_v;
+        self
+    }
+
+    /// Set the filter weight of each new sample (1.0 = unfiltered). The default smooths PWM
+    /// ripple while following a sag within a few control periods.
+    pub fn with_filter(mut self, alpha: f32) -> Self {
+        self.alpha = alpha.clamp(0.01, 1.0);
+        self
+    }
+
+    /// Take a reading and return the duty scale.
+    pub fn update(&mut self) -> f32 {
+        match self.source.read_volts() {
+            Some(v) if v >= MIN_PLAUSIBLE_V => {
+                self.filtered = Some(match self.filtered {
+                    Some(prev) => prev + self.alpha * (v - prev),
+                    None => v,
+                });
+            }
+            // Drop the history so one bad read isn't followed by a stale compensation.
+            _ => self.filtered = None,
+        }
+        self.scale()
+    }
+
+    /// Filtered bus voltage, or `None` without a plausible reading.
+    pub fn volts(&self) -> Option<f32> {
+        self.filtered
+    }
+
+    /// Duty scale for the current reading; 1.0 without one.
+    pub fn scale(&self) -> f32 {
+        match self.filtered {
+            Some(v) => (self.nominal_v / v).clamp(MIN_SCALE, MAX_SCALE),
+            None => 1.0,
+        }
+    }
+
+    pub fn source(&mut self) -> &mut S {
+        &mut self.source
+    }
+}
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Temperature sources and temperature compensation of the Actuonix potentiometers.
+//!
+//! In an enclosed tile the position a lift reports drifts by up to a millimeter between a cold
+//! start and warm operation: the pot track, the plastic housing and the wiper all move with
+//! temperature. The drift is repeatable, so it can be measured once per actuator type and taken
+//! out again. [`PotTempCompensation`] reads a [`TemperatureSource`] and gives a correction
+//! linear in the rise above the calibration temperature:
+//!
+//! ```text
+//! dT        = filtered_temp_c - reference_c
+//! corrected = raw_mm - (offset_mm_per_c + gain_per_c * raw_mm) * dT
+//! ```
+//!
+//! Apply it with [`set_position_correction`](crate::drivers::ActuonixLinear::set_position_correction)
+//! on each lift actuator; the firmware does so once a second from the MCU's sensor with the
+//! coefficients in [`config`](crate::config). With both coefficients zero, or without a plausible
+//! reading, the correction is the identity.
+//!
+//! Any source will do: the MCU's own sensor ([`McuTemperature`]) sits on the same board as the
+//! actuators and is free, an external probe taped to the actuator body tracks it more closely.
+
+use crate::config::POT_REFERENCE_TEMP_C;
+
+/// Readings outside this range are treated as a missing or broken sensor.
+const PLAUSIBLE_C: core::ops::RangeInclusive<f32> = -40.0..=125.0;
+
+/// A source of temperature readings, such as [`McuTemperature`] or a thermistor.
+pub trait TemperatureSource {
+    /// Temperature in °C, or `None` if the read failed.
+    fn read_celsius(&mut self) -> Option<f32>;
+}
+
+/// Factory reading of the internal sensor at 30 °C with VDDA = 3.3 V (RM0410 §15.10).
+const TS_CAL1: *const u16 = 0x1FF0_F44C as *const u16;
+/// Factory reading of the internal sensor at 110 °C with VDDA = 3.3 V.
+const TS_CAL2: *const u16 = 0x1FF0_F44E as *const u16;
+
+/// The STM32's internal temperature sensor, read with a reader closure on
+/// [`TEMP_SENSOR_CHANNEL`](crate::hw::adc::TEMP_SENSOR_CHANNEL) after
+/// [`enable_temperature_sensor`](crate::hw::Adc::enable_temperature_sensor). Converted with the
+/// factory calibration points, good to about ±2 °C.
+pub struct McuTemperature<F: FnMut() -> u16> {
+    read: F,
+    cal1: f32,
+    cal2: f32,
+    /// Raw reading scale from the actual VDDA to the 3.3 V of the calibration.
+    vdda_scale: f32,
+}
+
+impl<F: FnMut() -> u16> McuTemperature<F> {
+    pub fn new(read: F) -> Self {
+        // SAFETY: the calibration values are in the read-only system memory of every F7.
+        let (cal1, cal2) = unsafe { (TS_CAL1.read_volatile(), TS_CAL2.read_volatile()) };
+        Self {
+            read,
+            cal1: cal1 as f32,
+            cal2: cal2 as f32,
+            vdda_scale: 1.0,
+        }
+    }
+
+    /// Set the analog supply the ADC runs from, if it isn't 3.3 V.
+    pub fn with_vdda(mut self, vdda: f32) -> Self {
+        self.vdda_scale = vdda / 3.3;
+        self
+    }
+}
+
+impl<F: FnMut() -> u16> TemperatureSource for McuTemperature<F> {
+    fn read_celsius(&mut self) -> Option<f32> {
+        if self.cal2 <= self.cal1 {
+            return None;
+        }
+        let raw = (self.read)() as f32 * self.vdda_scale;
+        Some(30.0 + (raw - self.cal1) * 80.0 / (self.cal2 - self.cal1))
+    }
+}
+
+/// Correction of a pot position, see [`PotTempCompensation`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PotCorrection {
+    pub scale: f32,
+    pub offset_mm: f32,
+}
+
+impl PotCorrection {
+    pub const IDENTITY: Self = Self {
+        scale: 1.0,
+        offset_mm: 0.0,
+    };
+
+    pub fn apply(&self, mm: f32) -> f32 {
+        mm * self.scale + self.offset_mm
+    }
+}
+
+/// Filtered temperature and the pot correction derived from it.
+pub struct PotTempCompensation<S: TemperatureSource> {
+    source: S,
+    reference_c: f32,
+    offset_mm_per_c: f32,
+    gain_per_c: f32,
+    /// Exponential filter weight of each new sample (0..1].
+    alpha: f32,
+    filtered: Option<f32>,
+}
+
+impl<S: TemperatureSource> PotTempCompensation<S> {
+    /// Compensate a drift of `offset_mm_per_c` plus `gain_per_c` of the reading per °C above
+    /// [`POT_REFERENCE_TEMP_C`]. Both come from logging a fixed position while the tile warms
+    /// up; positive values for a reading that rises with temperature.
+    pub fn new(source: S, offset_mm_per_c: f32, gain_per_c: f32) -> Self {
+        Self {
+            source,
+            reference_c: POT_REFERENCE_TEMP_C,
+            offset_mm_per_c,
+            gain_per_c,
+            alpha: 0.05,
+            filtered: None,
+        }
+    }
+
+    /// Set the temperature the pots were calibrated at.
+    pub fn with_reference(mut self, reference_c: f32) -> Self {
+        self.reference_c = reference_c;
+        self
+    }
+
+    /// Set the filter weight of each new sample (1.0 = unfiltered). Temperature changes over
+    /// minutes, so the default smooths sensor noise heavily.
+    pub fn with_filter(mut self, alpha: f32) -> Self {
+        self.alpha = alpha.clamp(0.001, 1.0);
+        self
+    }
+
+    /// Take a reading and return the correction.
+    pub fn update(&mut self) -> PotCorrection {
+        match self.source.read_celsius() {
+            Some(c) if PLAUSIBLE_C.contains(&c) => {
+                self.filtered = Some(match self.filtered {
+                    Some(prev) => prev + self.alpha * (c - prev),
+                    None => c,
+                });
+            }
+            // Drop the history so a recovered sensor isn't averaged with a stale temperature.
+            _ => self.filtered = None,
+        }
+        self.correction()
+    }
+
+    /// Filtered temperature, or `None` without a plausible reading.
+    pub fn celsius(&self) -> Option<f32> {
+        self.filtered
+    }
+
+    /// Correction for the current reading; the identity without one.
+    pub fn correction(&self) -> PotCorrection {
+        let Some(c) = self.filtered else {
+            return PotCorrection::IDENTITY;
+        };
+        let rise = c - self.reference_c;
+        PotCorrection {
+            scale: 1.0 - self.gain_per_c * rise,
+            offset_mm: -self.offset_mm_per_c * rise,
+        }
+    }
+
+    pub fn source(&mut self) -> &mut S {
+        &mut self.source
+    }
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MIT
+// © 2025–2026 Christopher Liu
+
+//! Typed physical units.
+//!
+//! Thin `f32` newtypes so angles in degrees, angles in radians and linear positions in millimeters
+//! can't be mixed up at API boundaries. Conversions between [`Deg`] and [`Rad`] are explicit via
+//! `From`; arithmetic is only defined within a unit.
+
+use core::f32::consts::PI;
+use core::ops::{Add, Neg, Sub};
+
+/// Angle in degrees.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct Deg(pub f32);
+
+/// Angle in radians.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct Rad(pub f32);
+
+/// Linear position or distance in millimeters.
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct Mm(pub f32);
+
+impl From<Deg> for Rad {
+    #[inline]
+    fn from(d: Deg) -> Self {
+        Rad(d.0 * PI / 180.0)
+    }
+}
+
+impl From<Rad> for Deg {
+    #[inline]
+    fn from(r: Rad) -> Self {
+        Deg(r.0 * 180.0 / PI)
+    }
+}
+
+macro_rules! unit_ops {
+    ($($t:ident),*) => {$(
+        impl $t {
+            /// The raw value in this unit.
+            #[inline]
+            pub const fn get(self) -> f32 {
+                self.0
+            }
+
+            #[inline]
+            pub fn clamp(self, min: Self, max: Self) -> Self {
+                $t(self.0.clamp(min.0, max.0))
+            }
+        }
+
+        impl Add for $t {
+            type Output = Self;
+            #[inline]
+            fn add(self, rhs: Self) -> Self {
+                $t(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $t {
+            type Output = Self;
+            #[inline]
+            fn sub(self, rhs: Self) -> Self {
+                $t(self.0 - rhs.0)
+            }
+        }
+
+        impl Neg for $t {
+            type Output = Self;
+            #[inline]
+            fn neg(self) -> Self {
+                $t(-self.0)
+            }
+        }
+    )*};
+}
+
+unit_ops!(Deg, Rad, Mm);
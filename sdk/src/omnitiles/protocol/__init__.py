@@ -1,5 +1,52 @@
-from omnitiles.protocol.messages import START_BYTE, MessageId
-from omnitiles.protocol.packet import checksum, encode
+from omnitiles.protocol.descriptor import (
+    DescriptorFrame,
+    MessageRecord,
+    ParamRecord,
+    decode_wire_descriptor,
+)
+from omnitiles.protocol.ident import IdentSample, decode_ident_sample
+from omnitiles.protocol.messages import (
+    START_BYTE,
+    AckStatus,
+    DacSignal,
+    DemoMode,
+    IdentSignal,
+    MessageId,
+    Operation,
+    ProgressStatus,
+    RejectReason,
+    Sequence,
+    TelemetryField,
+)
+from omnitiles.protocol.packet import (
+    checksum,
+    encode,
+    encode_sequenced,
+    encode_tokened,
+)
 from omnitiles.protocol.parser import StreamParser
 
-__all__ = ["START_BYTE", "MessageId", "checksum", "encode", "StreamParser"]
+__all__ = [
+    "START_BYTE",
+    "AckStatus",
+    "DacSignal",
+    "DemoMode",
+    "DescriptorFrame",
+    "IdentSample",
+    "IdentSignal",
+    "MessageId",
+    "MessageRecord",
+    "Operation",
+    "ParamRecord",
+    "ProgressStatus",
+    "RejectReason",
+    "Sequence",
+    "TelemetryField",
+    "checksum",
+    "decode_ident_sample",
+    "decode_wire_descriptor",
+    "encode",
+    "encode_sequenced",
+    "encode_tokened",
+    "StreamParser",
+]
@@ -0,0 +1,113 @@
+"""Decoder for ``WIRE_DESCRIPTOR`` frames.
+
+The tile describes its own message table and parameter registry, one record
+per ``GET_WIRE_DESCRIPTOR`` request, so tools can generate bindings without
+hardcoding them. Mirrors ``omnitiles/proto/src/wire.rs``.
+"""
+
+import struct
+from dataclasses import dataclass
+from enum import IntEnum
+
+#: Payload length of a ``WIRE_DESCRIPTOR`` frame.
+WIRE_DESCRIPTOR_LEN = 42
+
+_LAYOUT_LEN = 12
+_NAME_LEN = 24
+
+
+class RecordKind(IntEnum):
+    """``kind`` byte of a ``WIRE_DESCRIPTOR`` frame."""
+
+    NONE = 0
+    MESSAGE = 1
+    PARAM = 2
+
+
+class Direction(IntEnum):
+    """Who sends a described message."""
+
+    COMMAND = 0
+    REPLY = 1
+
+
+@dataclass(frozen=True, slots=True)
+class MessageRecord:
+    """One message of the tile's protocol."""
+
+    id: int
+    direction: Direction
+    layout: str
+    """Payload as a :mod:`struct` format without byte order, ``*`` if the
+    length varies."""
+    name: str
+
+    @property
+    def struct_format(self) -> str | None:
+        """Format for :func:`struct.unpack`, or ``None`` for variable payloads."""
+        return None if self.layout == "*" else "<" + self.layout
+
+
+@dataclass(frozen=True, slots=True)
+class ParamRecord:
+    """One tunable parameter, as read and written with ``GET_PARAM`` /
+    ``SET_PARAM``."""
+
+    id: int
+    kind: int
+    """Value type, a telemetry descriptor kind code (1 u16, 2 f32, 3 u32)."""
+    stored: bool
+    """Persisted to flash on set."""
+    min: float
+    max: float
+    default: float
+    name: str
+
+
+@dataclass(frozen=True, slots=True)
+class DescriptorFrame:
+    """A decoded ``WIRE_DESCRIPTOR`` frame."""
+
+    index: int
+    total: int
+    """Number of records; request indices ``0..total``."""
+    record: MessageRecord | ParamRecord | None
+    """``None`` if ``index`` was past the end."""
+
+
+def _text(raw: bytes) -> str:
+    return raw.split(b"\0", 1)[0].decode("ascii")
+
+
+def decode_wire_descriptor(payload: bytes) -> DescriptorFrame:
+    """Decode the payload of a ``WIRE_DESCRIPTOR`` frame.
+
+    Raises:
+        ValueError: If ``payload`` is not :data:`WIRE_DESCRIPTOR_LEN` bytes.
+    """
+    if len(payload) != WIRE_DESCRIPTOR_LEN:
+        raise ValueError(f"expected {WIRE_DESCRIPTOR_LEN} bytes, got {len(payload)}")
+    index, total, kind = payload[0], payload[1], payload[2]
+    body = payload[3:]
+
+    record: MessageRecord | ParamRecord | None = None
+    if kind == RecordKind.MESSAGE:
+        layout = body[2 : 2 + _LAYOUT_LEN]
+        record = MessageRecord(
+            id=body[0],
+            direction=Direction(body[1]),
+            layout=_text(layout),
+            name=_text(body[2 + _LAYOUT_LEN : 2 + _LAYOUT_LEN + _NAME_LEN]),
+        )
+    elif kind == RecordKind.PARAM:
+        lo, hi, default = struct.unpack_from("<3f", body, 3)
+        record = ParamRecord(
+            id=body[0],
+            kind=body[1],
+            stored=bool(body[2]),
+            min=lo,
+            max=hi,
+            default=default,
+            name=_text(body[15 : 15 + _NAME_LEN]),
+        )
+    return DescriptorFrame(index=index, total=total, record=record)
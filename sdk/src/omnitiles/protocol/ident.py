@@ -0,0 +1,44 @@
+"""Decoder for ``IDENT_SAMPLE`` frames.
+
+An ``IDENTIFY`` run streams one sample per control step: the duty applied
+from then on and the position measured at the start of the step. Mirrors
+``omnitiles/src/control/ident.rs``.
+"""
+
+import struct
+from dataclasses import dataclass
+
+#: Payload length of an ``IDENT_SAMPLE`` frame.
+IDENT_SAMPLE_LEN = 9
+
+
+@dataclass(frozen=True, slots=True)
+class IdentSample:
+    """One input/output pair of an identification run."""
+
+    axis: int
+    index: int
+    """Step counter, wraps at 65536; a gap means lost frames."""
+    t: float
+    """Seconds since the run started."""
+    duty: float
+    """Applied duty, -1.0 to 1.0."""
+    position_mm: float
+
+
+def decode_ident_sample(payload: bytes) -> IdentSample:
+    """Decode the payload of an ``IDENT_SAMPLE`` frame.
+
+    Raises:
+        ValueError: If ``payload`` is not :data:`IDENT_SAMPLE_LEN` bytes.
+    """
+    if len(payload) != IDENT_SAMPLE_LEN:
+        raise ValueError(f"expected {IDENT_SAMPLE_LEN} bytes, got {len(payload)}")
+    axis, index, t_ms, duty, position = struct.unpack("<BHHhh", payload)
+    return IdentSample(
+        axis=axis,
+        index=index,
+        t=t_ms / 1000.0,
+        duty=duty / 1000.0,
+        position_mm=position / 100.0,
+    )
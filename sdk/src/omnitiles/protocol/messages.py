@@ -1,6 +1,6 @@
 """Binary protocol message IDs.
 
-Mirrors ``omnitiles/src/protocol/messages.rs`` — keep these in sync with the
+Mirrors ``omnitiles/proto/src/messages.rs`` — keep these in sync with the
 firmware. Packet format:
 
     [START_BYTE] [msg_id] [payload...] [checksum]
@@ -8,7 +8,7 @@ firmware. Packet format:
 ``checksum`` is the 8-bit sum of ``msg_id`` and all payload bytes.
 """
 
-from enum import IntEnum
+from enum import IntEnum, IntFlag
 
 START_BYTE: int = 0xA5
 
@@ -20,15 +20,181 @@ class MessageId(IntEnum):
     M1_RETRACT = 0x31
     M1_BRAKE = 0x32
     M1_SET_POSITION = 0x33
+    M1_MOVE_RELATIVE = 0x34
 
     M2_EXTEND = 0x40
     M2_RETRACT = 0x41
     M2_BRAKE = 0x42
     M2_SET_POSITION = 0x43
+    M2_MOVE_RELATIVE = 0x44
 
     PING = 0x50
+    GET_CAPABILITIES = 0x51
+    SET_TELEMETRY_FIELDS = 0x52
+    GET_TELEMETRY_DESCRIPTOR = 0x53
+    SET_TELEMETRY_DELTA = 0x54
+    GET_STATS = 0x55
+    GET_FAULT_SNAPSHOT = 0x56
+    SET_TIME = 0x57
+    SET_SCHEDULE = 0x58
+    GET_LINK_STATS = 0x59
+    GET_PARAM = 0x5A
+    SET_DAC_PROBE = 0x5B
+    READ_REGISTER = 0x5C
+    ECHO = 0x5D
+    GET_WIRE_DESCRIPTOR = 0x5E
 
     TELEMETRY = 0x60
+    EVENT = 0x61
+    HEARTBEAT = 0x62
+    ACK = 0x63
+    CAPABILITIES = 0x64
+    AXIS_CAPS = 0x65
+    TELEMETRY_SELECTED = 0x66
+    TELEMETRY_DESCRIPTOR = 0x67
+    TELEMETRY_DELTA = 0x68
+    STATS = 0x69
+    FAULT_SNAPSHOT = 0x6A
+    FAULT_HISTORY = 0x6B
+    LINK_STATS = 0x6C
+    PARAM = 0x6D
+    CONFIG_STATUS = 0x6E
+    REGISTER = 0x6F
 
     BASE_VELOCITY = 0x70
     BASE_BRAKE = 0x71
+    TILT_MOVE_RELATIVE = 0x72
+    IDENTIFY = 0x73
+    FREQUENCY_RESPONSE = 0x74
+
+    PROVISION = 0x80
+    SET_POLARITY = 0x81
+    SET_MOTION_LIMITS = 0x82
+    SET_CURRENT_LIMIT = 0x83
+    SET_PARAM = 0x84
+    COMMIT_CONFIG = 0x85
+    REVERT_CONFIG = 0x86
+    SET_INTERLOCK = 0x87
+    SET_DEMO = 0x88
+    PROVISION_TILT_MOTOR = 0x89
+
+    SEQ = 0x90
+    TOKEN = 0x91
+
+    PROGRESS = 0xA0
+    TOKEN_ACK = 0xA1
+    TOKEN_EVENT = 0xA2
+    WIRE_DESCRIPTOR = 0xA3
+    IDENT_SAMPLE = 0xA4
+
+
+class TelemetryField(IntFlag):
+    """Optional telemetry fields for ``SET_TELEMETRY_FIELDS``.
+
+    Mirrors ``omnitiles/proto/src/telemetry.rs``. Positions are always sent.
+    """
+
+    UWB = 1 << 0
+    TOF = 1 << 1
+    IMU = 1 << 2
+    MOTOR_ADCS = 1 << 3
+    TIMESTAMP = 1 << 4
+    MOTOR_TEMPS = 1 << 5
+    STACK = 1 << 6
+
+    ALL = UWB | TOF | IMU | MOTOR_ADCS | TIMESTAMP | MOTOR_TEMPS | STACK
+
+
+class Sequence(IntEnum):
+    """Built-in motion sequences for ``SET_SCHEDULE``.
+
+    Mirrors ``omnitiles/src/control/schedule.rs``.
+    """
+
+    SELF_CHECK = 1
+    EXERCISE = 2
+    DEMO = 3
+
+
+class DemoMode(IntEnum):
+    """Modes for ``SET_DEMO``."""
+
+    OFF = 0
+    RUN = 1
+    AT_BOOT = 2
+
+
+class IdentSignal(IntEnum):
+    """Test signals for ``IDENTIFY``.
+
+    Mirrors ``omnitiles/src/control/ident.rs``.
+    """
+
+    OFF = 0
+    PRBS = 1
+    CHIRP = 2
+
+
+class AckStatus(IntEnum):
+    """Status byte of an ``ACK`` frame.
+
+    Mirrors ``omnitiles/proto/src/seq.rs``.
+    """
+
+    ACCEPTED = 0
+    DUPLICATE = 1
+    REJECTED = 2
+
+
+class RejectReason(IntEnum):
+    """Reason byte of an ``ACK`` or ``TOKEN_ACK`` frame with status
+    ``REJECTED``; 0 otherwise.
+
+    Mirrors ``Reject`` in ``omnitiles/proto/src/seq.rs``. Codes are stable: the
+    firmware never renumbers or reuses one.
+    """
+
+    RATE_LIMITED = 1
+    OUT_OF_RANGE = 2
+    SAFE_MODE = 3
+    INTERLOCK = 4
+    SUPERSEDED = 5
+    PREEMPTED = 6
+    BUSY = 7
+    FAULTED = 8
+    NOT_HOMED = 9
+    UNSUPPORTED = 10
+
+
+class Operation(IntEnum):
+    """Operation byte of a ``PROGRESS`` frame.
+
+    Mirrors ``omnitiles/proto/src/progress.rs``.
+    """
+
+    HOMING = 1
+    CALIBRATION = 2
+    FIRMWARE_UPDATE = 3
+
+
+class ProgressStatus(IntEnum):
+    """Status byte of a ``PROGRESS`` frame."""
+
+    RUNNING = 0
+    DONE = 1
+    FAILED = 2
+
+
+class DacSignal(IntEnum):
+    """Signals ``SET_DAC_PROBE`` can route to a DAC pin.
+
+    Mirrors ``omnitiles/src/hw/dac.rs``.
+    """
+
+    OFF = 0
+    M1_OUTPUT = 1
+    M2_OUTPUT = 2
+    M1_VELOCITY = 3
+    M2_VELOCITY = 4
+    M1_ERROR = 5
+    M2_ERROR = 6
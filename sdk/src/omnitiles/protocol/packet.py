@@ -31,3 +31,42 @@ def encode(msg_id: int | MessageId, payload: bytes | Iterable[int] = b"") -> byt
     payload_bytes = bytes(payload)
     csum = checksum(msg_id_int, payload_bytes)
     return bytes([START_BYTE, msg_id_int]) + payload_bytes + bytes([csum])
+
+
+def encode_sequenced(
+    seq: int, msg_id: int | MessageId, payload: bytes | Iterable[int] = b""
+) -> bytes:
+    """Encode a command wrapped in a ``SEQ`` envelope.
+
+    The tile acknowledges every sequenced command with an ``ACK`` frame
+    ``[seq, status, reason]`` (see :class:`AckStatus` and
+    :class:`RejectReason`) and ignores retransmissions of a ``seq`` it has
+    already accepted, so the same packet can be resent safely until
+    acknowledged.
+
+    Args:
+        seq: 8-bit sequence number. Must not be reused within 16 commands.
+        msg_id: Inner message identifier.
+        payload: Inner payload bytes.
+    """
+    inner = bytes([int(msg_id) & 0xFF]) + bytes(payload)
+    return encode(MessageId.SEQ, bytes([seq & 0xFF]) + inner)
+
+
+def encode_tokened(
+    token: int, msg_id: int | MessageId, payload: bytes | Iterable[int] = b""
+) -> bytes:
+    """Encode a command wrapped in a ``TOKEN`` envelope.
+
+    Like :func:`encode_sequenced`, with a 16-bit token the tile echoes in a
+    ``TOKEN_ACK`` frame ``[token, status, reason]`` and, for position moves,
+    in the ``TOKEN_EVENT`` frame that reports the move ending (target reached,
+    fault or interlock stop).
+
+    Args:
+        token: 16-bit token. Must not be reused within 16 tokened commands.
+        msg_id: Inner message identifier.
+        payload: Inner payload bytes.
+    """
+    inner = bytes([int(msg_id) & 0xFF]) + bytes(payload)
+    return encode(MessageId.TOKEN, (token & 0xFFFF).to_bytes(2, "little") + inner)
@@ -5,15 +5,35 @@ import time
 from collections.abc import Iterator
 
 from omnitiles.hardware import ADC_MAX, M1_CONFIG, M2_CONFIG
-from omnitiles.protocol.messages import START_BYTE, MessageId
+from omnitiles.protocol.messages import START_BYTE, MessageId, TelemetryField
+from omnitiles.protocol.packet import checksum
 from omnitiles.telemetry import ImuSample, Telemetry
 
 # Known telemetry packet lengths (bytes on the wire, including start byte and
 # checksum). Each variant is distinguished only by length; add new entries
 # here when the firmware grows the packet.
-_TELEMETRY_LENGTHS = (7, 15, 17, 53)
+_TELEMETRY_LENGTHS = (7, 15, 17, 53, 57, 61, 63)
 _MAX_TELEMETRY_LEN = max(_TELEMETRY_LENGTHS)
 
+# Byte length of each optional field in a TELEMETRY_SELECTED frame, in wire
+# order. Mirrors ``FIELDS`` in ``omnitiles/proto/src/telemetry.rs``.
+_SELECTED_FIELDS = (
+    (TelemetryField.UWB, 8),
+    (TelemetryField.TOF, 2),
+    (TelemetryField.IMU, 24),
+    (TelemetryField.MOTOR_ADCS, 12),
+    (TelemetryField.TIMESTAMP, 4),
+    (TelemetryField.MOTOR_TEMPS, 4),
+    (TelemetryField.STACK, 2),
+)
+
+# Slot widths of the full telemetry body, in wire order, for TELEMETRY_DELTA.
+# Mirrors ``DELTA_SLOTS`` in ``omnitiles/proto/src/telemetry.rs``.
+_DELTA_SLOTS = (2,) * 7 + (4,) * 6 + (2,) * 6 + (4,) + (2,) * 3
+
+# Motor temperature sent for a motor without a thermistor.
+_NO_TEMPERATURE = 0x7FFF
+
 
 class StreamParser:
     """Feed BLE notification bytes in, get :class:`Telemetry` frames out.
@@ -26,6 +46,8 @@ class StreamParser:
 
     def __init__(self) -> None:
         self._buf = bytearray()
+        # Last full telemetry packet, the base for TELEMETRY_DELTA frames.
+        self._delta_base: bytes | None = None
 
     def feed(self, data: bytes | bytearray) -> list[Telemetry]:
         """Append ``data`` to the internal buffer and return any frames
@@ -38,6 +60,8 @@ class StreamParser:
             frame = self._try_consume_frame()
             if frame is None:
                 return
+            if len(frame.raw) == _MAX_TELEMETRY_LEN:
+                self._delta_base = frame.raw
             yield frame
 
     def _try_consume_frame(self) -> Telemetry | None:
@@ -49,6 +73,12 @@ class StreamParser:
         if len(buf) < 2:
             return None
 
+        if buf[1] == MessageId.TELEMETRY_SELECTED:
+            return self._try_consume_selected()
+
+        if buf[1] == MessageId.TELEMETRY_DELTA:
+            return self._try_consume_delta()
+
         if buf[1] != MessageId.TELEMETRY:
             del buf[0]
             return None
@@ -77,6 +107,81 @@ class StreamParser:
         del buf[0]
         return None
 
+    def _try_consume_selected(self) -> Telemetry | None:
+        buf = self._buf
+        if len(buf) < 3:
+            return None
+
+        length = _selected_length(buf[2])
+        if len(buf) < length:
+            return None
+
+        frame = _try_parse_selected(bytes(buf[:length]))
+        if frame is None:
+            del buf[0]
+            return None
+        del buf[:length]
+        return frame
+
+    def _try_consume_delta(self) -> Telemetry | None:
+        buf = self._buf
+        if len(buf) < 5:
+            return None
+
+        changed = int.from_bytes(buf[2:5], "little")
+        length = 6 + sum(
+            1 if size == 2 else size
+            for i, size in enumerate(_DELTA_SLOTS)
+            if changed & (1 << i)
+        )
+        if len(buf) < length:
+            return None
+
+        packet = bytes(buf[:length])
+        if not _checksum_ok(packet):
+            del buf[0]
+            return None
+        del buf[:length]
+
+        # Without a base (e.g. joined mid-stream) wait for the next keyframe.
+        if self._delta_base is None:
+            return None
+        full = _apply_delta(self._delta_base, changed, packet[5:-1])
+        return _try_parse(full)
+
+
+def _apply_delta(base: bytes, changed: int, values: bytes) -> bytes:
+    """Rebuild a full telemetry packet from ``base`` and a delta payload."""
+    body = bytearray(base[2:-1])
+    offset = 0
+    pos = 0
+    for i, size in enumerate(_DELTA_SLOTS):
+        if changed & (1 << i):
+            if size == 2:
+                (prev,) = struct.unpack_from("<H", body, offset)
+                (diff,) = struct.unpack_from("<b", values, pos)
+                struct.pack_into("<H", body, offset, (prev + diff) & 0xFFFF)
+                pos += 1
+            else:
+                body[offset : offset + size] = values[pos : pos + size]
+                pos += size
+        offset += size
+    csum = checksum(MessageId.TELEMETRY, bytes(body))
+    return bytes([START_BYTE, MessageId.TELEMETRY]) + bytes(body) + bytes([csum])
+
+
+def _motor_temps(packet: bytes, offset: int) -> tuple[float | None, float | None]:
+    m1, m2 = struct.unpack_from("<hh", packet, offset)
+    return (
+        None if m1 == _NO_TEMPERATURE else m1 / 10.0,
+        None if m2 == _NO_TEMPERATURE else m2 / 10.0,
+    )
+
+
+def _selected_length(mask: int) -> int:
+    # start + id + mask + two positions + fields + checksum
+    return 8 + sum(size for bit, size in _SELECTED_FIELDS if mask & bit)
+
 
 def _checksum_ok(packet: bytes) -> bool:
     total = 0
@@ -85,6 +190,76 @@ def _checksum_ok(packet: bytes) -> bool:
     return packet[-1] == total
 
 
+def _try_parse_selected(packet: bytes) -> Telemetry | None:
+    if not _checksum_ok(packet):
+        return None
+
+    mask = packet[2]
+    m1_pos_adc, m2_pos_adc = struct.unpack_from("<HH", packet, 3)
+    offset = 7
+
+    uwb_mm: tuple[int | None, int | None, int | None, int | None] | None = None
+    tof_mm: int | None = None
+    imu: ImuSample | None = None
+    m1_adcs: tuple[int, ...] = ()
+    m2_adcs: tuple[int, ...] = ()
+    device_time_us: int | None = None
+    motor_temps_c: tuple[float | None, float | None] | None = None
+    stack_peak_kb: int | None = None
+
+    if mask & TelemetryField.UWB:
+        d0, d1, d2, d3 = struct.unpack_from("<HHHH", packet, offset)
+        uwb_mm = (
+            None if d0 == 0xFFFF else d0,
+            None if d1 == 0xFFFF else d1,
+            None if d2 == 0xFFFF else d2,
+            None if d3 == 0xFFFF else d3,
+        )
+        offset += 8
+
+    if mask & TelemetryField.TOF:
+        (tof_raw,) = struct.unpack_from("<H", packet, offset)
+        tof_mm = None if tof_raw == 0xFFFF else tof_raw
+        offset += 2
+
+    if mask & TelemetryField.IMU:
+        imu = ImuSample(*struct.unpack_from("<6f", packet, offset))
+        offset += 24
+
+    if mask & TelemetryField.MOTOR_ADCS:
+        m1_adcs = tuple(struct.unpack_from("<4H", packet, offset))
+        m2_adcs = tuple(struct.unpack_from("<2H", packet, offset + 8))
+        offset += 12
+
+    if mask & TelemetryField.TIMESTAMP:
+        (device_time_us,) = struct.unpack_from("<I", packet, offset)
+        offset += 4
+
+    if mask & TelemetryField.MOTOR_TEMPS:
+        motor_temps_c = _motor_temps(packet, offset)
+        offset += 4
+
+    if mask & TelemetryField.STACK:
+        (stack_peak_kb,) = struct.unpack_from("<H", packet, offset)
+
+    return Telemetry(
+        timestamp=time.monotonic(),
+        m1_pos_adc=m1_pos_adc,
+        m2_pos_adc=m2_pos_adc,
+        m1_pos_mm=(m1_pos_adc / ADC_MAX) * M1_CONFIG.stroke_mm,
+        m2_pos_mm=(m2_pos_adc / ADC_MAX) * M2_CONFIG.stroke_mm,
+        m1_adcs=m1_adcs,
+        m2_adcs=m2_adcs,
+        uwb_mm=uwb_mm,
+        tof_mm=tof_mm,
+        imu=imu,
+        device_time_us=device_time_us,
+        motor_temps_c=motor_temps_c,
+        stack_peak_kb=stack_peak_kb,
+        raw=packet,
+    )
+
+
 def _try_parse(packet: bytes) -> Telemetry | None:
     if not _checksum_ok(packet):
         return None
@@ -99,6 +274,9 @@ def _try_parse(packet: bytes) -> Telemetry | None:
     imu: ImuSample | None = None
     m1_adcs: tuple[int, ...] = ()
     m2_adcs: tuple[int, ...] = ()
+    device_time_us: int | None = None
+    motor_temps_c: tuple[float | None, float | None] | None = None
+    stack_peak_kb: int | None = None
 
     if length >= 15:
         d0, d1, d2, d3 = struct.unpack_from("<HHHH", packet, 6)
@@ -113,12 +291,21 @@ def _try_parse(packet: bytes) -> Telemetry | None:
         (tof_raw,) = struct.unpack_from("<H", packet, 14)
         tof_mm = None if tof_raw == 0xFFFF else tof_raw
 
-    if length == 53:
+    if length >= 53:
         imu_vals = struct.unpack_from("<6f", packet, 16)
         imu = ImuSample(*imu_vals)
         m1_adcs = tuple(struct.unpack_from("<4H", packet, 40))
         m2_adcs = tuple(struct.unpack_from("<2H", packet, 48))
 
+    if length >= 57:
+        (device_time_us,) = struct.unpack_from("<I", packet, 52)
+
+    if length >= 61:
+        motor_temps_c = _motor_temps(packet, 56)
+
+    if length >= 63:
+        (stack_peak_kb,) = struct.unpack_from("<H", packet, 60)
+
     return Telemetry(
         timestamp=time.monotonic(),
         m1_pos_adc=m1_pos_adc,
@@ -130,5 +317,8 @@ def _try_parse(packet: bytes) -> Telemetry | None:
         uwb_mm=uwb_mm,
         tof_mm=tof_mm,
         imu=imu,
+        device_time_us=device_time_us,
+        motor_temps_c=motor_temps_c,
+        stack_peak_kb=stack_peak_kb,
         raw=packet,
     )
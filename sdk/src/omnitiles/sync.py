@@ -123,6 +123,9 @@ class SyncTile:
     def ping(self) -> None:
         _run(self._tile.ping())
 
+    def set_time(self, unix_s: float | None = None) -> None:
+        _run(self._tile.set_time(unix_s))
+
     def m1_extend(self, speed: int = 255) -> None:
         _run(self._tile.m1_extend(speed))
 
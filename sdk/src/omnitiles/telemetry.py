@@ -55,5 +55,19 @@ class Telemetry:
     imu: ImuSample | None = None
     """IMU sample, or ``None`` if the packet didn't include IMU data."""
 
+    device_time_us: int | None = None
+    """Tile clock at sampling time, microseconds since boot (wraps every ~71
+    minutes), or ``None`` if not reported. Unlike ``timestamp`` it is not
+    skewed by link buffering."""
+
+    motor_temps_c: tuple[float | None, float | None] | None = None
+    """M1 and M2 motor temperatures in degrees Celsius, or ``None`` if not
+    reported. Individual entries are ``None`` when the motor has no
+    thermistor or its reading is implausible."""
+
+    stack_peak_kb: int | None = None
+    """Deepest firmware stack use since boot in KB (1024 bytes, rounded up),
+    or ``None`` if not reported."""
+
     raw: bytes = field(default=b"", repr=False)
     """The underlying packet bytes (for debugging)."""
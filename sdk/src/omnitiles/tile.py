@@ -4,11 +4,12 @@ from __future__ import annotations
 
 import asyncio
 import struct
+import time
 from collections.abc import Callable
 from typing import TYPE_CHECKING
 
 from omnitiles.hardware import M1_CONFIG, M2_CONFIG, ActuatorConfig
-from omnitiles.protocol import MessageId, StreamParser, encode
+from omnitiles.protocol import MessageId, StreamParser, TelemetryField, encode
 from omnitiles.telemetry import Telemetry
 from omnitiles.transport import BleakTransport, Transport
 
@@ -117,6 +118,140 @@ class Tile:
     async def ping(self) -> None:
         await self._send(MessageId.PING)
 
+    async def request_stats(self, axis: int) -> None:
+        """Ask the tile for the usage counters of ``axis`` (1 = M1, 2 = M2)."""
+        await self._send(MessageId.GET_STATS, _u8(axis))
+
+    async def request_fault_snapshot(self, axis: int) -> None:
+        """Ask the tile for the state captured at the last fault of ``axis``."""
+        await self._send(MessageId.GET_FAULT_SNAPSHOT, _u8(axis))
+
+    async def echo(self, token: int | None = None) -> int:
+        """Send a latency probe and return its token.
+
+        The tile answers with ``ECHO``: the same token and how long it held
+        the request, in microseconds. The default token is the host's
+        monotonic clock in microseconds, so the round trip is the clock at
+        reception minus the token.
+        """
+        if token is None:
+            token = time.monotonic_ns() // 1000
+        token &= 0xFFFFFFFF
+        await self._send(MessageId.ECHO, struct.pack("<I", token))
+        return token
+
+    async def request_link_stats(self) -> None:
+        """Ask the tile for its command link frame counters (frames, checksum errors, ...)."""
+        await self._send(MessageId.GET_LINK_STATS)
+
+    async def set_time(self, unix_s: float | None = None) -> None:
+        """Set the tile's wall clock to ``unix_s`` (default: the host's current time)."""
+        if unix_s is None:
+            unix_s = time.time()
+        await self._send(MessageId.SET_TIME, struct.pack("<I", int(unix_s)))
+
+    async def set_current_limit(self, axis: int, level: int | None) -> None:
+        """Set the driver current-regulation level of ``axis`` (0-3, ``None`` = off)."""
+        payload = _u8(axis) + _u8(0xFF if level is None else level)
+        await self._send(MessageId.SET_CURRENT_LIMIT, payload)
+
+    async def request_param(self, param_id: int) -> None:
+        """Ask the tile for the value of parameter ``param_id``; it replies with ``PARAM``."""
+        await self._send(MessageId.GET_PARAM, _u8(param_id))
+
+    async def set_param(self, param_id: int, value: float) -> None:
+        """Set parameter ``param_id`` (see the protocol docs for the registry)."""
+        await self._send(MessageId.SET_PARAM, _u8(param_id) + struct.pack("<f", value))
+
+    async def commit_config(self) -> None:
+        """Keep the pending configuration change; the tile replies with ``CONFIG_STATUS``."""
+        await self._send(MessageId.COMMIT_CONFIG)
+
+    async def revert_config(self) -> None:
+        """Roll back to the last committed configuration; replies with ``CONFIG_STATUS``."""
+        await self._send(MessageId.REVERT_CONFIG)
+
+    async def schedule_every(self, slot: int, sequence: int, minutes: int) -> None:
+        """Run ``sequence`` every ``minutes`` of tile uptime, stored in ``slot`` (0-3)."""
+        await self._set_schedule(slot, 1, sequence, minutes)
+
+    async def schedule_daily(
+        self, slot: int, sequence: int, hour: int, minute: int = 0
+    ) -> None:
+        """Run ``sequence`` once a day at ``hour:minute`` UTC. Needs :meth:`set_time`."""
+        await self._set_schedule(slot, 2, sequence, hour * 60 + minute)
+
+    async def clear_schedule(self, slot: int) -> None:
+        await self._set_schedule(slot, 0, 0, 0)
+
+    async def _set_schedule(self, slot: int, kind: int, sequence: int, arg: int) -> None:
+        payload = struct.pack("<BBBH", slot, kind, sequence, arg)
+        await self._send(MessageId.SET_SCHEDULE, payload)
+
+    async def set_interlock(
+        self, slot: int, axis: int, source: int, low: float, high: float
+    ) -> None:
+        """Let ``axis`` move only while ``source`` is within ``[low, high]``.
+
+        Axes are 1 = M1, 2 = M2, 3 = tilt; bounds are in mm or degrees of ``source``.
+        The rule is stored in ``slot`` (0-3).
+        """
+        payload = struct.pack(
+            "<BBBhh", slot, axis, source, round(low * 100), round(high * 100)
+        )
+        await self._send(MessageId.SET_INTERLOCK, payload)
+
+    async def clear_interlock(self, slot: int) -> None:
+        await self._send(MessageId.SET_INTERLOCK, struct.pack("<BBBhh", slot, 0, 0, 0, 0))
+
+    async def set_demo(self, mode: int) -> None:
+        """Start or stop demo mode, see :class:`~omnitiles.protocol.DemoMode`."""
+        await self._send(MessageId.SET_DEMO, _u8(mode))
+
+    async def provision_tilt_motor(
+        self, addr: int = 1, bitrate: int = 1_000_000
+    ) -> None:
+        """Move the tilt motor answering at ``addr`` and ``bitrate`` (bit/s) to the tilt axis.
+
+        Only that motor should be powered on the motor bus; the axis homes afterwards.
+        """
+        code = {1_000_000: 0, 500_000: 1, 250_000: 2, 125_000: 3}[bitrate]
+        await self._send(MessageId.PROVISION_TILT_MOTOR, _u8(addr) + _u8(code))
+
+    async def identify(
+        self,
+        axis: int,
+        signal: int,
+        amplitude: float,
+        param: int,
+        duration_s: int,
+    ) -> None:
+        """Drive ``axis`` (1 or 2) open loop with a test signal and stream
+        ``IDENT_SAMPLE`` frames for offline system identification.
+
+        ``signal`` is an :class:`~omnitiles.protocol.IdentSignal`;
+        ``amplitude`` a duty in 0-1, capped at 0.5 on the tile. ``param`` is the
+        PRBS bit length in control steps or the chirp end frequency in 0.1 Hz.
+        Any other command for the axis ends the run.
+        """
+        duty = round(min(max(amplitude, 0.0), 1.0) * 255)
+        payload = struct.pack("<BBBBB", axis, signal, duty, param, duration_s)
+        await self._send(MessageId.IDENTIFY, payload)
+
+    async def frequency_response(self, axis: int, amplitude_mm: float) -> None:
+        """Sweep ``axis`` (1 or 2) in position control with a sine of
+        ``amplitude_mm`` (0.1 mm resolution, at most 10 mm) about its position.
+
+        The tile logs the closed-loop gain and phase at each test frequency on
+        its USART. ``0`` stops a sweep, as does any other command for the axis.
+        """
+        amplitude = round(min(max(amplitude_mm, 0.0), 25.5) * 10)
+        await self._send(MessageId.FREQUENCY_RESPONSE, _u8(axis) + _u8(amplitude))
+
+    async def request_capabilities(self) -> None:
+        """Ask the tile for its ``CAPABILITIES`` and per-axis ``AXIS_CAPS`` frames."""
+        await self._send(MessageId.GET_CAPABILITIES)
+
     async def m1_extend(self, speed: int = 255) -> None:
         """Drive M1 in the extend direction at the given PWM speed (0-255)."""
         await self._send(MessageId.M1_EXTEND, _u8(speed))
@@ -135,6 +270,10 @@ class Tile:
         """Convenience: set M1 target in millimeters along the stroke."""
         await self.m1_set_position(_mm_to_scaled(mm, M1_CONFIG))
 
+    async def m1_move_relative_mm(self, delta_mm: float) -> None:
+        """Nudge the M1 target by ``delta_mm`` (0.01 mm resolution, clamped on the tile)."""
+        await self._send(MessageId.M1_MOVE_RELATIVE, _i16_centi(delta_mm))
+
     async def m2_extend(self, speed: int = 255) -> None:
         await self._send(MessageId.M2_EXTEND, _u8(speed))
 
@@ -150,6 +289,13 @@ class Tile:
     async def m2_set_position_mm(self, mm: float) -> None:
         await self.m2_set_position(_mm_to_scaled(mm, M2_CONFIG))
 
+    async def m2_move_relative_mm(self, delta_mm: float) -> None:
+        await self._send(MessageId.M2_MOVE_RELATIVE, _i16_centi(delta_mm))
+
+    async def tilt_move_relative_deg(self, delta_deg: float) -> None:
+        """Nudge the tilt target by ``delta_deg`` (0.01° resolution, clamped on the tile)."""
+        await self._send(MessageId.TILT_MOVE_RELATIVE, _i16_centi(delta_deg))
+
     async def base_velocity(self, vx: int, vy: int, omega: int) -> None:
         """Command open-loop mobile-base velocity. Each component is int8."""
         payload = struct.pack("<bbb", _i8(vx), _i8(vy), _i8(omega))
@@ -160,6 +306,36 @@ class Tile:
 
     # ---- telemetry ----
 
+    async def set_telemetry_fields(self, fields: TelemetryField) -> None:
+        """Stream only ``fields`` (plus positions). ``TelemetryField.ALL`` restores
+        the full frame."""
+        await self._send(MessageId.SET_TELEMETRY_FIELDS, bytes([int(fields) & 0xFF]))
+
+    async def set_telemetry_delta(self, keyframe_interval: int) -> None:
+        """Send a full frame every ``keyframe_interval`` frames and deltas in
+        between. ``0`` disables delta encoding."""
+        await self._send(MessageId.SET_TELEMETRY_DELTA, _u8(keyframe_interval))
+
+    async def set_dac_probe(self, channel: int, signal: int) -> None:
+        """Route ``signal`` (a :class:`~omnitiles.protocol.DacSignal`) to DAC
+        ``channel`` 1 (PA4) or 2 (PA5) for viewing on an oscilloscope."""
+        await self._send(MessageId.SET_DAC_PROBE, _u8(channel) + _u8(signal))
+
+    async def request_register(self, device: int, addr: int) -> None:
+        """Read register ``addr`` of motor driver ``device`` raw; the tile
+        replies with ``REGISTER``."""
+        await self._send(MessageId.READ_REGISTER, _u8(device) + _u8(addr))
+
+    async def request_telemetry_descriptor(self) -> None:
+        """Ask the tile for its ``TELEMETRY_DESCRIPTOR`` field map."""
+        await self._send(MessageId.GET_TELEMETRY_DESCRIPTOR)
+
+    async def request_wire_descriptor(self, index: int) -> None:
+        """Ask for record ``index`` of the tile's wire format descriptor; the
+        tile replies with ``WIRE_DESCRIPTOR`` (see
+        :func:`~omnitiles.protocol.decode_wire_descriptor`)."""
+        await self._send(MessageId.GET_WIRE_DESCRIPTOR, _u8(index))
+
     @property
     def telemetry(self) -> Telemetry | None:
         """Most recently received telemetry frame, or ``None``."""
@@ -213,6 +389,12 @@ def _i8(value: int) -> int:
     return max(-128, min(127, int(value)))
 
 
+def _i16_centi(value: float) -> bytes:
+    """Encode ``value`` in hundredths as a saturating little-endian int16."""
+    centi = max(-32768, min(32767, int(round(value * 100))))
+    return struct.pack("<h", centi)
+
+
 def _mm_to_scaled(mm: float, config: ActuatorConfig) -> int:
     """Map a millimeter target to the firmware's 0-255 set-position scale."""
     frac = mm / config.stroke_mm
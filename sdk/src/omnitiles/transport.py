@@ -1,4 +1,4 @@
-"""BLE transport layer wrapping :mod:`bleak`.
+"""BLE transport layer wrapping :mod:`bleak`, plus UDP for wired tiles.
 
 The SDK exposes a small :class:`Transport` protocol so alternative transports
 (mocks, loopbacks, future UART) can slot in without touching :class:`Tile`.
@@ -6,6 +6,7 @@ The SDK exposes a small :class:`Transport` protocol so alternative transports
 
 from __future__ import annotations
 
+import asyncio
 from collections.abc import Callable
 from typing import Protocol
 
@@ -13,6 +14,8 @@ from bleak import BleakClient, BleakScanner
 from bleak.backends.device import BLEDevice
 from bleak.backends.scanner import AdvertisementData
 
+from omnitiles.protocol import MessageId, encode
+
 NUS_SERVICE_UUID = "6e400001-b5a3-f393-e0a9-e50e24dcca9e"
 NUS_RX_UUID = "6e400002-b5a3-f393-e0a9-e50e24dcca9e"
 """Write characteristic (host → tile)."""
@@ -21,6 +24,9 @@ NUS_TX_UUID = "6e400003-b5a3-f393-e0a9-e50e24dcca9e"
 
 DEFAULT_TILE_NAME_PREFIX = "OmniTile_"
 
+DEFAULT_UDP_PORT = 5005
+"""Port tiles with the ``ethernet`` firmware feature listen on."""
+
 NotifyHandler = Callable[[bytes], None]
 DisconnectHandler = Callable[[], None]
 
@@ -94,6 +100,76 @@ class BleakTransport:
             handler(bytes(data))
 
 
+class UdpTransport:
+    """:class:`Transport` to a tile on Ethernet.
+
+    Each :meth:`send` is one datagram. The tile streams telemetry to whichever
+    host sent it the latest datagram, so :meth:`connect` sends a ``PING`` to
+    subscribe.
+    """
+
+    def __init__(self, host: str, port: int = DEFAULT_UDP_PORT) -> None:
+        self._host = host
+        self._port = port
+        self._udp: asyncio.DatagramTransport | None = None
+        self._notify_handler: NotifyHandler | None = None
+        self._disconnect_handler: DisconnectHandler | None = None
+
+    @property
+    def address(self) -> str:
+        return f"{self._host}:{self._port}"
+
+    @property
+    def connected(self) -> bool:
+        return self._udp is not None and not self._udp.is_closing()
+
+    def set_notify_handler(self, handler: NotifyHandler | None) -> None:
+        self._notify_handler = handler
+
+    def set_disconnect_handler(self, handler: DisconnectHandler | None) -> None:
+        self._disconnect_handler = handler
+
+    async def connect(self) -> None:
+        loop = asyncio.get_running_loop()
+        self._udp, _ = await loop.create_datagram_endpoint(
+            lambda: _UdpProtocol(self), remote_addr=(self._host, self._port)
+        )
+        self._udp.sendto(encode(MessageId.PING))
+
+    async def disconnect(self) -> None:
+        if self._udp is not None:
+            self._udp.close()
+            self._udp = None
+
+    async def send(self, data: bytes) -> None:
+        if not self.connected:
+            raise ConnectionError("UdpTransport is not connected")
+        self._udp.sendto(data)
+
+    def _on_datagram(self, data: bytes) -> None:
+        handler = self._notify_handler
+        if handler is not None:
+            handler(data)
+
+    def _on_lost(self) -> None:
+        self._udp = None
+        handler = self._disconnect_handler
+        if handler is not None:
+            handler()
+
+
+class _UdpProtocol(asyncio.DatagramProtocol):
+    def __init__(self, owner: UdpTransport) -> None:
+        self._owner = owner
+
+    def datagram_received(self, data: bytes, addr) -> None:
+        self._owner._on_datagram(data)
+
+    def connection_lost(self, exc: Exception | None) -> None:
+        if exc is not None:
+            self._owner._on_lost()
+
+
 async def discover_tiles(
     *,
     timeout: float = 5.0,
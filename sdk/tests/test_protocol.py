@@ -3,7 +3,19 @@
 import struct
 
 from omnitiles.hardware import ADC_MAX, M1_CONFIG, M2_CONFIG
-from omnitiles.protocol import MessageId, StreamParser, encode
+from omnitiles.protocol import (
+    IdentSample,
+    MessageId,
+    MessageRecord,
+    ParamRecord,
+    StreamParser,
+    TelemetryField,
+    decode_ident_sample,
+    decode_wire_descriptor,
+    encode,
+    encode_sequenced,
+    encode_tokened,
+)
 from omnitiles.protocol.packet import checksum
 
 
@@ -27,6 +39,28 @@ def test_encode_multi_byte_payload():
     assert packet[5] == expected_csum
 
 
+def test_encode_sequenced():
+    packet = encode_sequenced(7, MessageId.M1_EXTEND, bytes([200]))
+    assert packet[:4] == bytes([0xA5, 0x90, 7, 0x30])
+    assert packet[4] == 200
+    assert packet[5] == checksum(0x90, bytes([7, 0x30, 200]))
+
+
+def test_encode_tokened():
+    packet = encode_tokened(0x1234, MessageId.M1_SET_POSITION, bytes([128]))
+    assert packet[:5] == bytes([0xA5, 0x91, 0x34, 0x12, 0x33])
+    assert packet[5] == 128
+    assert packet[6] == checksum(0x91, bytes([0x34, 0x12, 0x33, 128]))
+
+
+def test_encode_set_schedule():
+    payload = struct.pack("<BBBH", 1, 2, 1, 3 * 60)
+    packet = encode(MessageId.SET_SCHEDULE, payload)
+    assert packet[:2] == bytes([0xA5, 0x58])
+    assert packet[2:7] == bytes([1, 2, 1, 0xB4, 0x00])
+    assert packet[7] == checksum(0x58, payload)
+
+
 def _telemetry_packet(body: bytes) -> bytes:
     """Wrap [msg_id, body] with start byte + checksum."""
     csum = checksum(MessageId.TELEMETRY, body)
@@ -156,3 +190,136 @@ def test_parser_invalid_sentinels_become_none():
     parser = StreamParser()
     [frame] = parser.feed(packet)
     assert frame.uwb_mm == (None, None, None, None)
+
+
+def test_parse_selected_telemetry():
+    body = struct.pack("<BHHH", TelemetryField.TOF, 1000, 2000, 0xFFFF)
+    csum = checksum(MessageId.TELEMETRY_SELECTED, body)
+    packet = bytes([0xA5, MessageId.TELEMETRY_SELECTED]) + body + bytes([csum])
+
+    parser = StreamParser()
+    assert parser.feed(packet[:5]) == []
+    [frame] = parser.feed(packet[5:])
+    assert frame.m1_pos_adc == 1000
+    assert frame.m2_pos_adc == 2000
+    assert frame.tof_mm is None
+    assert frame.uwb_mm is None
+    assert frame.imu is None
+
+
+def test_parse_delta_telemetry():
+    body = struct.pack(
+        "<HH4HH6f4H2HIhhH",
+        1000,
+        2000,
+        1,
+        2,
+        3,
+        4,
+        500,
+        *([0.0] * 6),
+        *range(6),
+        10_000,
+        251,
+        0x7FFF,
+        12,
+    )
+    keyframe = _telemetry_packet(body)
+
+    # M1 +5, ToF -3, new sample time.
+    changed = (1 << 0) | (1 << 6) | (1 << 19)
+    payload = changed.to_bytes(3, "little") + struct.pack("<bbI", 5, -3, 10_500)
+    csum = checksum(MessageId.TELEMETRY_DELTA, payload)
+    delta = bytes([0xA5, MessageId.TELEMETRY_DELTA]) + payload + bytes([csum])
+
+    parser = StreamParser()
+    assert parser.feed(delta) == []  # no base yet
+    key, frame = parser.feed(keyframe + delta)
+    assert key.m1_pos_adc == 1000
+    assert frame.m1_pos_adc == 1005
+    assert frame.m2_pos_adc == 2000
+    assert frame.tof_mm == 497
+    assert frame.m2_adcs == (4, 5)
+    assert key.device_time_us == 10_000
+    assert frame.device_time_us == 10_500
+    assert frame.motor_temps_c == (25.1, None)
+    assert frame.stack_peak_kb == 12
+
+
+def test_parse_full_packet_with_timestamp():
+    body = struct.pack(
+        "<HH4HH6f4H2HI", 1, 2, 0, 0, 0, 0, 0, *([0.0] * 6), *([0] * 6), 123_456
+    )
+    packet = _telemetry_packet(body)
+    assert len(packet) == 57
+
+    parser = StreamParser()
+    [frame] = parser.feed(packet)
+    assert frame.device_time_us == 123_456
+    assert frame.imu is not None
+    assert frame.motor_temps_c is None
+
+
+def test_parse_motor_temperatures():
+    body = struct.pack(
+        "<HH4HH6f4H2HIhh", 1, 2, 0, 0, 0, 0, 0, *([0.0] * 6), *([0] * 6), 0, 423, -52
+    )
+    packet = _telemetry_packet(body)
+    assert len(packet) == 61
+
+    parser = StreamParser()
+    [frame] = parser.feed(packet)
+    assert frame.motor_temps_c == (42.3, -5.2)
+
+    body = struct.pack("<BHHhh", TelemetryField.MOTOR_TEMPS, 1, 2, 0x7FFF, 650)
+    csum = checksum(MessageId.TELEMETRY_SELECTED, body)
+    packet = bytes([0xA5, MessageId.TELEMETRY_SELECTED]) + body + bytes([csum])
+    [frame] = parser.feed(packet)
+    assert frame.motor_temps_c == (None, 65.0)
+    assert frame.device_time_us is None
+
+
+def test_parse_stack_peak():
+    body = struct.pack(
+        "<HH4HH6f4H2HIhhH", 1, 2, 0, 0, 0, 0, 0, *([0.0] * 6), *([0] * 6), 0, 0, 0, 300
+    )
+    packet = _telemetry_packet(body)
+    assert len(packet) == 63
+
+    parser = StreamParser()
+    [frame] = parser.feed(packet)
+    assert frame.stack_peak_kb == 300
+    assert frame.motor_temps_c == (0.0, 0.0)
+
+    mask = TelemetryField.MOTOR_TEMPS | TelemetryField.STACK
+    body = struct.pack("<BHHhhH", mask, 1, 2, 100, 200, 6)
+    csum = checksum(MessageId.TELEMETRY_SELECTED, body)
+    packet = bytes([0xA5, MessageId.TELEMETRY_SELECTED]) + body + bytes([csum])
+    [frame] = parser.feed(packet)
+    assert frame.motor_temps_c == (10.0, 20.0)
+    assert frame.stack_peak_kb == 6
+
+
+def test_decode_wire_descriptor():
+    layout = b"BBBH".ljust(12, b"\0")
+    name = b"SET_SCHEDULE".ljust(24, b"\0")
+    payload = bytes([18, 83, 1, MessageId.SET_SCHEDULE, 0]) + layout + name + b"\0"
+    frame = decode_wire_descriptor(payload)
+    assert (frame.index, frame.total) == (18, 83)
+    assert isinstance(frame.record, MessageRecord)
+    assert frame.record.name == "SET_SCHEDULE"
+    assert frame.record.struct_format == "<BBBH"
+
+    body = bytes([0x10, 2, 0]) + struct.pack("<3f", 0.0, 1000.0, 0.5)
+    payload = bytes([61, 83, 2]) + body + b"m1.kp".ljust(24, b"\0")
+    frame = decode_wire_descriptor(payload)
+    assert frame.record == ParamRecord(0x10, 2, False, 0.0, 1000.0, 0.5, "m1.kp")
+
+    frame = decode_wire_descriptor(bytes([90, 83, 0]) + bytes(39))
+    assert frame.record is None
+
+
+def test_decode_ident_sample():
+    payload = struct.pack("<BHHhh", 2, 7, 140, -250, 5012)
+    sample = decode_ident_sample(payload)
+    assert sample == IdentSample(2, 7, 0.14, -0.25, 50.12)